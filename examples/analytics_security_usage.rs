@@ -1,4 +1,5 @@
 use wechat_mp_sdk::{
+    api::security::{RiskScene, Scene},
     api::AnalyticsDateRangeRequest,
     types::{AppId, AppSecret},
     WechatMp,
@@ -19,7 +20,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     match wechat
-        .msg_sec_check("o_user_openid_123456789012345678", 1, "safe content")
+        .msg_sec_check(
+            "o_user_openid_123456789012345678",
+            Scene::Profile,
+            "safe content",
+        )
         .await
     {
         Ok(response) => println!("Message security suggestion: {}", response.result.suggest),
@@ -27,7 +32,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     match wechat
-        .get_user_risk_rank("o_user_openid_123456789012345678", 0, None)
+        .get_user_risk_rank(
+            "o_user_openid_123456789012345678",
+            RiskScene::Registration,
+            None,
+        )
         .await
     {
         Ok(response) => println!("User risk rank: {}", response.risk_rank),
@@ -23,6 +23,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Err(error) => eprintln!("scan_qr_code failed: {error}"),
     }
 
+    // Long-tail categories are grouped behind one accessor each.
+    let _ = wechat.hardware();
+    let _ = wechat.live();
+
     let _ = WechatMp::database_query;
     let _ = WechatMp::create_room;
     let _ = WechatMp::add_local_order;
@@ -0,0 +1,50 @@
+//! Login and decrypt user data against a mock WeChat server.
+//!
+//! Run with: cargo run --example flow_login_decrypt --features flows
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::json;
+use wechat_mp_sdk::crypto::UserKeyCipher;
+use wechat_mp_sdk::flows::login_and_decrypt;
+use wechat_mp_sdk::types::{AppId, AppSecret};
+use wechat_mp_sdk::WechatMp;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const APPID: &str = "wx1234567890abcdef";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let session_key = BASE64.encode(b"0123456789abcdef");
+    let iv = BASE64.encode(b"fedcba9876543210");
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/sns/jscode2session"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "openid": "o_demo_user",
+            "session_key": session_key,
+        })))
+        .mount(&server)
+        .await;
+
+    // What the mini program would send alongside the login code.
+    let plaintext = json!({
+        "nickName": "Demo",
+        "watermark": { "appid": APPID, "timestamp": 1_700_000_000 },
+    });
+    let encrypted_data =
+        UserKeyCipher::new(&session_key, &iv, 0)?.encrypt(plaintext.to_string().as_bytes())?;
+
+    let wechat = WechatMp::builder()
+        .appid(AppId::new(APPID)?)
+        .secret(AppSecret::new("your_app_secret_here")?)
+        .base_url(server.uri())
+        .build()?;
+
+    let login = login_and_decrypt(&wechat, "code_from_wx_login", &encrypted_data, &iv).await?;
+    println!("OpenID: {}", login.session.openid);
+    println!("Nickname: {}", login.user.nick_name().unwrap_or("-"));
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! Ship an express order, safely retrying after a timeout, against a mock
+//! WeChat server.
+//!
+//! Run with: cargo run --example flow_logistics_shipment --features flows
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use wechat_mp_sdk::api::{LogisticsRequest, MemoryOrderOutbox};
+use wechat_mp_sdk::flows::ship_order;
+use wechat_mp_sdk::types::{AppId, AppSecret};
+use wechat_mp_sdk::WechatMp;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "demo_token",
+            "expires_in": 7200,
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/business/order/add"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 0,
+            "order_id": "SO-1001",
+            "waybill_id": "SF123456789",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/business/order/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 0,
+            "order_id": "SO-1001",
+            "waybill_id": "SF123456789",
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/business/path/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 0,
+            "path_item_num": 1,
+            "path_item_list": [{ "action_time": 1_700_000_000, "action_type": 100001 }],
+        })))
+        .mount(&server)
+        .await;
+
+    let wechat = WechatMp::builder()
+        .appid(AppId::new("wx1234567890abcdef")?)
+        .secret(AppSecret::new("your_app_secret_here")?)
+        .base_url(server.uri())
+        .build()?;
+
+    let payload: HashMap<String, Value> = [
+        ("order_id", json!("SO-1001")),
+        ("openid", json!("o_demo_user")),
+        ("delivery_id", json!("SF")),
+        ("biz_id", json!("SF_CASH")),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect();
+    let request = LogisticsRequest::new(payload);
+    let outbox = MemoryOrderOutbox::new();
+
+    let shipment = ship_order(&wechat, &outbox, &request).await?;
+    println!("waybill: {}", shipment.waybill_id().unwrap_or("-"));
+
+    // Running the flow again re-uses the recorded order instead of
+    // creating a second waybill.
+    let again = ship_order(&wechat, &outbox, &request).await?;
+    println!("created again: {}", again.order.is_created());
+
+    Ok(())
+}
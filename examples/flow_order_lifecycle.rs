@@ -0,0 +1,150 @@
+//! Ship a paid order and tell the buyer, with audit hooks, against a mock
+//! WeChat server.
+//!
+//! Run with: cargo run --example flow_order_lifecycle --features flows
+
+use std::sync::Arc;
+
+use serde_json::json;
+use wechat_mp_sdk::api::customer_service::{Message, TextMessage};
+use wechat_mp_sdk::api::subscribe::{
+    ExpectedTemplate, SubscribeMessageData, SubscribeMessageOptions, SubscribeMessageValue,
+    TemplateDriftReport,
+};
+use wechat_mp_sdk::api::{
+    AddOrderRequest, AddOrderResponse, MemoryOrderOutbox, OrderCreation, ShippingOrderKey,
+    UploadShippingInfoRequest,
+};
+use wechat_mp_sdk::flows::{
+    NotificationChannel, OrderHooks, OrderNotification, OrderNotifications, OrderShipment,
+};
+use wechat_mp_sdk::types::{AppId, AppSecret, OpenId};
+use wechat_mp_sdk::WechatMp;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Prints every stage, standing in for an order audit log
+struct AuditLog;
+
+impl OrderHooks for AuditLog {
+    fn templates_checked(&self, report: &TemplateDriftReport) {
+        println!("templates clean: {}", report.is_clean());
+    }
+
+    fn waybill_created(&self, order: &OrderCreation<AddOrderResponse>) {
+        println!("waybill ready, newly created: {}", order.is_created());
+    }
+
+    fn shipping_uploaded(&self, request: &UploadShippingInfoRequest) {
+        println!("shipping info uploaded for {:?}", request.order_key());
+    }
+
+    fn notified(&self, touser: &OpenId, channel: NotificationChannel) {
+        println!("{} notified via {channel:?}", touser.as_str());
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "demo_token",
+            "expires_in": 7200,
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/wxaapi/newtmpl/gettemplate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 0,
+            "data": [{
+                "priTmplId": "order_shipped_template",
+                "title": "Order shipped",
+                "content": "Order:{{character_string1.DATA}}\nWaybill:{{character_string2.DATA}}",
+                "type": 2,
+            }],
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/business/order/add"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 0,
+            "order_id": "SO-1001",
+            "waybill_id": "SF123456789",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    for endpoint in [
+        "/wxa/sec/order/upload_shipping_info",
+        "/cgi-bin/message/subscribe/send",
+    ] {
+        Mock::given(method("POST"))
+            .and(path(endpoint))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "errcode": 0,
+                "errmsg": "ok",
+            })))
+            .mount(&server)
+            .await;
+    }
+
+    let wechat = WechatMp::builder()
+        .appid(AppId::new("wx1234567890abcdef")?)
+        .secret(AppSecret::new("your_app_secret_here")?)
+        .base_url(server.uri())
+        .build()?;
+    let orders = OrderNotifications::new(wechat)
+        .expect_templates(vec![ExpectedTemplate::new("order_shipped_template")])
+        .hooks(Arc::new(AuditLog));
+    orders.check_templates().await?;
+
+    let buyer = "o_demo_buyer_0000000000";
+    let shipment = OrderShipment {
+        waybill: AddOrderRequest {
+            order_id: "SO-1001".to_string(),
+            openid: Some(buyer.to_string()),
+            delivery_id: "SF".to_string(),
+            biz_id: "SF_CASH".to_string(),
+            ..Default::default()
+        },
+        order_key: ShippingOrderKey::out_trade_no("1230000109", "SO-1001"),
+        item_desc: "T-shirt x2".to_string(),
+        receiver_contact: Some("189****1234".to_string()),
+    };
+
+    let outbox = MemoryOrderOutbox::new();
+    let shipped = orders
+        .ship(&outbox, shipment, |tracking_no| {
+            let mut data = SubscribeMessageData::new();
+            data.insert(
+                "character_string1".to_string(),
+                SubscribeMessageValue::new("SO-1001"),
+            );
+            data.insert(
+                "character_string2".to_string(),
+                SubscribeMessageValue::new(tracking_no),
+            );
+            OrderNotification {
+                subscribe: SubscribeMessageOptions {
+                    touser: OpenId::new(buyer).expect("valid openid"),
+                    template_id: "order_shipped_template".to_string(),
+                    data,
+                    page: Some("pages/order/detail?id=SO-1001".to_string()),
+                    miniprogram_state: None,
+                    lang: None,
+                },
+                fallback: Message::Text {
+                    text: TextMessage::new(format!("Order SO-1001 shipped: {tracking_no}")),
+                },
+                last_interaction: None,
+            }
+        })
+        .await?;
+    println!("shipped with tracking number {}", shipped.tracking_no);
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Notify users of an order status change against a mock WeChat server.
+//!
+//! Run with: cargo run --example flow_order_status --features flows
+
+use std::time::{Duration, SystemTime};
+
+use serde_json::json;
+use wechat_mp_sdk::api::customer_service::{Message, TextMessage};
+use wechat_mp_sdk::api::subscribe::{
+    SubscribeMessageData, SubscribeMessageOptions, SubscribeMessageValue,
+};
+use wechat_mp_sdk::flows::NotificationRouter;
+use wechat_mp_sdk::types::{AppId, AppSecret, OpenId};
+use wechat_mp_sdk::WechatMp;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "demo_token",
+            "expires_in": 7200,
+        })))
+        .mount(&server)
+        .await;
+    // The second user has used up their subscribe authorizations.
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/message/subscribe/send"))
+        .and(body_partial_json(
+            json!({ "touser": "o_user_out_of_quota_00000" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 43101,
+            "errmsg": "user refuse to accept the msg",
+        })))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    for endpoint in [
+        "/cgi-bin/message/subscribe/send",
+        "/cgi-bin/message/custom/send",
+    ] {
+        Mock::given(method("POST"))
+            .and(path(endpoint))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "errcode": 0,
+                "errmsg": "ok",
+            })))
+            .mount(&server)
+            .await;
+    }
+
+    let wechat = WechatMp::builder()
+        .appid(AppId::new("wx1234567890abcdef")?)
+        .secret(AppSecret::new("your_app_secret_here")?)
+        .base_url(server.uri())
+        .build()?;
+    let router = NotificationRouter::new(wechat);
+
+    // Last message from each user, e.g. recorded from message server pushes
+    let last_seen = SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+    for openid in ["o_user_subscribed_0000000", "o_user_out_of_quota_00000"] {
+        let mut data = SubscribeMessageData::new();
+        data.insert("phrase1".to_string(), SubscribeMessageValue::new("Shipped"));
+        let update = SubscribeMessageOptions {
+            touser: OpenId::new(openid)?,
+            template_id: "order_status_template".to_string(),
+            data,
+            page: Some("pages/order/detail?id=SO-1".to_string()),
+            miniprogram_state: None,
+            lang: None,
+        };
+        let fallback = Message::Text {
+            text: TextMessage::new("Your order SO-1 has shipped"),
+        };
+
+        let channel = router.send(update, fallback, Some(last_seen)).await?;
+        println!("{openid}: notified via {channel:?}");
+    }
+
+    Ok(())
+}
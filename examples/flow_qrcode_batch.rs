@@ -0,0 +1,59 @@
+//! Generate a batch of mini program codes from a mock WeChat server.
+//!
+//! Run with: cargo run --example flow_qrcode_batch --features flows
+
+use std::sync::Arc;
+
+use serde_json::json;
+use wechat_mp_sdk::api::qrcode::FsQrcodeSink;
+use wechat_mp_sdk::flows::generate_qrcode_batch;
+use wechat_mp_sdk::types::{AppId, AppSecret};
+use wechat_mp_sdk::WechatMp;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "demo_token",
+            "expires_in": 7200,
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/wxa/getwxacodeunlimit"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Content-Type", "image/png")
+                .set_body_bytes(b"\x89PNG demo".to_vec()),
+        )
+        .mount(&server)
+        .await;
+
+    let wechat = WechatMp::builder()
+        .appid(AppId::new("wx1234567890abcdef")?)
+        .secret(AppSecret::new("your_app_secret_here")?)
+        .base_url(server.uri())
+        .build()?;
+
+    let dir = std::env::temp_dir().join("wechat-mp-qrcodes");
+    std::fs::create_dir_all(&dir)?;
+    let scenes = (1..=5).map(|table| format!("table={table}"));
+    let manifest = generate_qrcode_batch(
+        &wechat,
+        "pages/order/index",
+        scenes,
+        Arc::new(FsQrcodeSink::new(&dir)),
+    )
+    .await;
+
+    for (scene, key) in manifest.keys() {
+        println!("{scene} -> {key}");
+    }
+    println!("complete: {}", manifest.is_complete());
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+//! Send a subscribe message campaign against a mock WeChat server.
+//!
+//! Run with: cargo run --example flow_subscribe_campaign --features flows
+
+use serde_json::json;
+use wechat_mp_sdk::api::subscribe::{SubscribeMessageData, SubscribeMessageValue};
+use wechat_mp_sdk::flows::send_subscribe_campaign;
+use wechat_mp_sdk::types::{AppId, AppSecret, OpenId};
+use wechat_mp_sdk::WechatMp;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "demo_token",
+            "expires_in": 7200,
+        })))
+        .mount(&server)
+        .await;
+    // The last recipient has not accepted the subscription.
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/message/subscribe/send"))
+        .and(body_partial_json(
+            json!({ "touser": "o_user_rejected_00000000" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 43101,
+            "errmsg": "user refuse to accept the msg",
+        })))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/message/subscribe/send"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 0,
+            "errmsg": "ok",
+        })))
+        .mount(&server)
+        .await;
+
+    let wechat = WechatMp::builder()
+        .appid(AppId::new("wx1234567890abcdef")?)
+        .secret(AppSecret::new("your_app_secret_here")?)
+        .base_url(server.uri())
+        .build()?;
+
+    let recipients = [
+        OpenId::new("o_user_first_000000000000")?,
+        OpenId::new("o_user_second_00000000000")?,
+        OpenId::new("o_user_rejected_00000000")?,
+    ];
+    let report = send_subscribe_campaign(&wechat, "template_id", &recipients, |openid| {
+        let mut data = SubscribeMessageData::new();
+        data.insert(
+            "thing1".to_string(),
+            SubscribeMessageValue::new(format!("Hello {}", &openid.as_str()[..8])),
+        );
+        data
+    })
+    .await?;
+
+    println!("sent: {}", report.sent.len());
+    for (openid, error) in &report.failed {
+        println!("failed: {openid}: {error}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+//! Print an OpenAPI 3 document for every endpoint in the inventory.
+//!
+//! ```sh
+//! cargo run --example openapi_spec > wechat-mp.openapi.json
+//! ```
+
+use wechat_mp_sdk::api::endpoint_inventory::openapi_spec;
+
+fn main() -> Result<(), serde_json::Error> {
+    println!("{}", serde_json::to_string_pretty(&openapi_spec())?);
+    Ok(())
+}
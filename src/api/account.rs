@@ -0,0 +1,607 @@
+//! Account Basic Info API
+//!
+//! Reads the mini program's registered profile and updates the parts of it
+//! that mini programs may change themselves: nickname, avatar, and signature.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::{WechatApi, WechatContext};
+use crate::error::WechatError;
+
+/// Verification state of the account, as reported in `wx_verify_info`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct WxVerifyInfo {
+    /// Whether WeChat verification has been completed
+    #[serde(default)]
+    pub qualification_verify: bool,
+    /// Whether the nickname is covered by the verification
+    #[serde(default)]
+    pub naming_verify: bool,
+    /// Whether annual review is required
+    #[serde(default)]
+    pub annual_review: bool,
+    /// Start of the annual review window (Unix seconds)
+    #[serde(default)]
+    pub annual_review_begin_time: i64,
+    /// End of the annual review window (Unix seconds)
+    #[serde(default)]
+    pub annual_review_end_time: i64,
+}
+
+/// Signature (introduction) and how often it may still be changed.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SignatureInfo {
+    #[serde(default)]
+    pub signature: String,
+    #[serde(default)]
+    pub modify_used_count: u32,
+    #[serde(default)]
+    pub modify_quota: u32,
+}
+
+/// Avatar and how often it may still be changed.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HeadImageInfo {
+    #[serde(default)]
+    pub head_image_url: String,
+    #[serde(default)]
+    pub modify_used_count: u32,
+    #[serde(default)]
+    pub modify_quota: u32,
+}
+
+/// Nickname and how often it may still be changed.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NicknameInfo {
+    #[serde(default)]
+    pub nickname: String,
+    #[serde(default)]
+    pub modify_used_count: u32,
+    #[serde(default)]
+    pub modify_quota: u32,
+}
+
+/// Response of `getaccountbasicinfo`
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AccountBasicInfo {
+    #[serde(default)]
+    pub appid: String,
+    /// Account type; `2` for mini programs
+    #[serde(default)]
+    pub account_type: i32,
+    /// Principal type (`0` individual, `1` enterprise, ...)
+    #[serde(default)]
+    pub principal_type: i32,
+    #[serde(default)]
+    pub principal_name: String,
+    /// Unified social credit code or equivalent principal identifier
+    #[serde(default)]
+    pub credential: String,
+    /// Real-name verification status (`1` verified)
+    #[serde(default)]
+    pub realname_status: i32,
+    #[serde(default)]
+    pub registered_country: i32,
+    #[serde(default)]
+    pub wx_verify_info: WxVerifyInfo,
+    #[serde(default)]
+    pub signature_info: SignatureInfo,
+    #[serde(default)]
+    pub head_image_info: HeadImageInfo,
+    #[serde(default)]
+    pub nickname_info: NicknameInfo,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+/// Request for `setnickname`
+///
+/// Nicknames that need proof of naming rights take the media ids of the
+/// supporting documents; upload them first with
+/// [`MediaApi::upload_temp_media`](super::MediaApi::upload_temp_media).
+#[non_exhaustive]
+#[derive(Debug, Clone, Serialize)]
+pub struct SetNicknameRequest {
+    pub nick_name: String,
+    /// Media id of the ID card photo (individual accounts)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_card: Option<String>,
+    /// Media id of the business license (organization accounts)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Media ids of other supporting documents, at most five
+    #[serde(skip)]
+    pub naming_other_stuff: Vec<String>,
+}
+
+impl SetNicknameRequest {
+    /// Most documents WeChat accepts per request
+    pub const MAX_OTHER_STUFF: usize = 5;
+
+    pub fn new(nick_name: impl Into<String>) -> Self {
+        Self {
+            nick_name: nick_name.into(),
+            id_card: None,
+            license: None,
+            naming_other_stuff: Vec::new(),
+        }
+    }
+
+    pub fn id_card(mut self, media_id: impl Into<String>) -> Self {
+        self.id_card = Some(media_id.into());
+        self
+    }
+
+    pub fn license(mut self, media_id: impl Into<String>) -> Self {
+        self.license = Some(media_id.into());
+        self
+    }
+
+    pub fn other_stuff(mut self, media_id: impl Into<String>) -> Self {
+        self.naming_other_stuff.push(media_id.into());
+        self
+    }
+
+    fn to_body(&self) -> Result<serde_json::Value, WechatError> {
+        if self.naming_other_stuff.len() > Self::MAX_OTHER_STUFF {
+            return Err(WechatError::Config(format!(
+                "setnickname accepts at most {} supporting documents, got {}",
+                Self::MAX_OTHER_STUFF,
+                self.naming_other_stuff.len()
+            )));
+        }
+        let mut body = serde_json::to_value(self)?;
+        if let Some(map) = body.as_object_mut() {
+            for (index, media_id) in self.naming_other_stuff.iter().enumerate() {
+                map.insert(
+                    format!("naming_other_stuff_{}", index + 1),
+                    serde_json::Value::String(media_id.clone()),
+                );
+            }
+        }
+        Ok(body)
+    }
+}
+
+/// Response of `setnickname`
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SetNicknameResponse {
+    /// Explanation shown when the nickname needs review or documents
+    #[serde(default)]
+    pub wording: String,
+    /// Present when the nickname went to manual review; pass it to
+    /// [`AccountApi::query_nickname_audit`]
+    #[serde(default)]
+    pub audit_id: Option<i64>,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+/// Review state of a nickname change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NicknameAuditState {
+    Reviewing,
+    Rejected,
+    Approved,
+    Unknown(i32),
+}
+
+impl From<i32> for NicknameAuditState {
+    fn from(value: i32) -> Self {
+        match value {
+            1 => Self::Reviewing,
+            2 => Self::Rejected,
+            3 => Self::Approved,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// Response of `api_wxa_querynickname`
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NicknameAuditResponse {
+    #[serde(default)]
+    pub nickname: String,
+    /// Raw review state; see [`state`](Self::state)
+    #[serde(default)]
+    pub audit_stat: i32,
+    #[serde(default)]
+    pub fail_reason: String,
+    #[serde(default)]
+    pub create_time: i64,
+    #[serde(default)]
+    pub audit_time: i64,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+impl NicknameAuditResponse {
+    pub fn state(&self) -> NicknameAuditState {
+        NicknameAuditState::from(self.audit_stat)
+    }
+}
+
+/// Response of `checkwxverifynickname`
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CheckNicknameResponse {
+    /// Whether the nickname collides with a protected name
+    #[serde(default)]
+    pub hit_condition: bool,
+    #[serde(default)]
+    pub wording: String,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+/// Crop box for [`AccountApi::modify_head_image`], as fractions of the
+/// uploaded image in `0.0..=1.0`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeadImageCrop {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+}
+
+impl HeadImageCrop {
+    /// The whole image
+    pub const FULL: Self = Self {
+        x1: 0.0,
+        y1: 0.0,
+        x2: 1.0,
+        y2: 1.0,
+    };
+}
+
+impl Default for HeadImageCrop {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct ModifyHeadImageBody<'a> {
+    head_img_media_id: &'a str,
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct AccountAck {
+    #[serde(default)]
+    errcode: i32,
+    #[serde(default)]
+    errmsg: String,
+}
+
+pub struct AccountApi {
+    context: Arc<WechatContext>,
+}
+
+impl AccountApi {
+    pub fn new(context: Arc<WechatContext>) -> Self {
+        Self { context }
+    }
+
+    /// Get the account's registered profile and modification quotas
+    ///
+    /// GET /cgi-bin/account/getaccountbasicinfo
+    pub async fn get_account_basic_info(&self) -> Result<AccountBasicInfo, WechatError> {
+        let response: AccountBasicInfo = self
+            .context
+            .authed_get("/cgi-bin/account/getaccountbasicinfo", &[])
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    /// Change the nickname
+    ///
+    /// POST /wxa/setnickname
+    pub async fn set_nickname(
+        &self,
+        request: &SetNicknameRequest,
+    ) -> Result<SetNicknameResponse, WechatError> {
+        let body = request.to_body()?;
+        let response: SetNicknameResponse =
+            self.context.authed_post("/wxa/setnickname", &body).await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    /// Query the review state of a nickname change
+    ///
+    /// POST /wxa/api_wxa_querynickname
+    pub async fn query_nickname_audit(
+        &self,
+        audit_id: i64,
+    ) -> Result<NicknameAuditResponse, WechatError> {
+        let body = serde_json::json!({ "audit_id": audit_id });
+        let response: NicknameAuditResponse = self
+            .context
+            .authed_post("/wxa/api_wxa_querynickname", &body)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    /// Check whether a nickname is available before submitting it
+    ///
+    /// POST /cgi-bin/wxverify/checkwxverifynickname
+    pub async fn check_nickname(
+        &self,
+        nick_name: &str,
+    ) -> Result<CheckNicknameResponse, WechatError> {
+        let body = serde_json::json!({ "nick_name": nick_name });
+        let response: CheckNicknameResponse = self
+            .context
+            .authed_post("/cgi-bin/wxverify/checkwxverifynickname", &body)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    /// Change the avatar to an uploaded temporary image
+    ///
+    /// POST /cgi-bin/account/modifyheadimage
+    pub async fn modify_head_image(
+        &self,
+        media_id: &str,
+        crop: HeadImageCrop,
+    ) -> Result<(), WechatError> {
+        let body = ModifyHeadImageBody {
+            head_img_media_id: media_id,
+            x1: crop.x1,
+            y1: crop.y1,
+            x2: crop.x2,
+            y2: crop.y2,
+        };
+        let response: AccountAck = self
+            .context
+            .authed_post("/cgi-bin/account/modifyheadimage", &body)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)
+    }
+
+    /// Change the signature (introduction)
+    ///
+    /// POST /cgi-bin/account/modifysignature
+    pub async fn modify_signature(&self, signature: &str) -> Result<(), WechatError> {
+        let body = serde_json::json!({ "signature": signature });
+        let response: AccountAck = self
+            .context
+            .authed_post("/cgi-bin/account/modifysignature", &body)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)
+    }
+}
+
+impl WechatApi for AccountApi {
+    fn context(&self) -> &WechatContext {
+        &self.context
+    }
+
+    fn api_name(&self) -> &'static str {
+        "account"
+    }
+}
+
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.response::<AccountBasicInfo>("account.getAccountBasicInfo");
+    schemas.response::<SetNicknameResponse>("account.setNickname");
+    schemas.response::<NicknameAuditResponse>("account.queryNickname");
+    schemas.response::<CheckNicknameResponse>("account.checkNickname");
+    schemas.add::<ModifyHeadImageBody<'static>, AccountAck>("account.modifyHeadImage");
+    schemas.response::<AccountAck>("account.modifySignature");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::WechatClient;
+    use crate::token::TokenManager;
+    use crate::types::{AppId, AppSecret};
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn create_test_context(base_url: &str) -> Arc<WechatContext> {
+        let appid = AppId::new("wx1234567890abcdef").unwrap();
+        let secret = AppSecret::new("secret1234567890ab").unwrap();
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(appid)
+                .secret(secret)
+                .base_url(base_url)
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(TokenManager::new((*client).clone()));
+        Arc::new(WechatContext::new(client, token_manager))
+    }
+
+    async fn setup_token_mock(mock_server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200,
+                "errcode": 0,
+                "errmsg": ""
+            })))
+            .mount(mock_server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn get_account_basic_info_parses_nested_quotas() {
+        let mock_server = MockServer::start().await;
+        setup_token_mock(&mock_server).await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/account/getaccountbasicinfo"))
+            .and(query_param("access_token", "test_token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "appid": "wx1234567890abcdef",
+                "account_type": 2,
+                "principal_type": 1,
+                "principal_name": "Example Ltd.",
+                "realname_status": 1,
+                "wx_verify_info": {
+                    "qualification_verify": true,
+                    "naming_verify": true
+                },
+                "signature_info": {
+                    "signature": "hello",
+                    "modify_used_count": 1,
+                    "modify_quota": 5
+                },
+                "head_image_info": {
+                    "head_image_url": "https://example.com/a.png",
+                    "modify_used_count": 0,
+                    "modify_quota": 5
+                },
+                "nickname_info": {
+                    "nickname": "Example",
+                    "modify_used_count": 2,
+                    "modify_quota": 2
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let api = AccountApi::new(create_test_context(&mock_server.uri()));
+        let info = api.get_account_basic_info().await.unwrap();
+        assert_eq!(info.account_type, 2);
+        assert_eq!(info.principal_name, "Example Ltd.");
+        assert!(info.wx_verify_info.qualification_verify);
+        assert_eq!(info.signature_info.signature, "hello");
+        assert_eq!(info.signature_info.modify_quota, 5);
+        assert_eq!(info.head_image_info.modify_used_count, 0);
+        assert_eq!(info.nickname_info.modify_used_count, 2);
+    }
+
+    #[tokio::test]
+    async fn set_nickname_numbers_supporting_documents() {
+        let mock_server = MockServer::start().await;
+        setup_token_mock(&mock_server).await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/setnickname"))
+            .and(body_json(serde_json::json!({
+                "nick_name": "Example",
+                "license": "license_media",
+                "naming_other_stuff_1": "doc_a",
+                "naming_other_stuff_2": "doc_b"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "wording": "under review",
+                "audit_id": 42
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let api = AccountApi::new(create_test_context(&mock_server.uri()));
+        let request = SetNicknameRequest::new("Example")
+            .license("license_media")
+            .other_stuff("doc_a")
+            .other_stuff("doc_b");
+        let response = api.set_nickname(&request).await.unwrap();
+        assert_eq!(response.audit_id, Some(42));
+        assert_eq!(response.wording, "under review");
+    }
+
+    #[test]
+    fn set_nickname_rejects_too_many_documents() {
+        let mut request = SetNicknameRequest::new("Example");
+        for i in 0..=SetNicknameRequest::MAX_OTHER_STUFF {
+            request = request.other_stuff(format!("doc_{i}"));
+        }
+        assert!(matches!(request.to_body(), Err(WechatError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn modify_head_image_sends_crop_box() {
+        let mock_server = MockServer::start().await;
+        setup_token_mock(&mock_server).await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/account/modifyheadimage"))
+            .and(body_json(serde_json::json!({
+                "head_img_media_id": "media_1",
+                "x1": 0.0,
+                "y1": 0.0,
+                "x2": 1.0,
+                "y2": 1.0
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"errcode": 0, "errmsg": "ok"})),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let api = AccountApi::new(create_test_context(&mock_server.uri()));
+        api.modify_head_image("media_1", HeadImageCrop::default())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn modify_signature_surfaces_api_errors() {
+        let mock_server = MockServer::start().await;
+        setup_token_mock(&mock_server).await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/account/modifysignature"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 53200,
+                "errmsg": "signature modification quota exhausted"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let api = AccountApi::new(create_test_context(&mock_server.uri()));
+        let err = api.modify_signature("new").await.unwrap_err();
+        assert!(matches!(err, WechatError::Api { code: 53200, .. }));
+    }
+
+    #[test]
+    fn nickname_audit_state_maps_codes() {
+        let json = r#"{"errcode":0,"errmsg":"ok","nickname":"Example","audit_stat":2,"fail_reason":"trademark"}"#;
+        let response: NicknameAuditResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.state(), NicknameAuditState::Rejected);
+        assert_eq!(NicknameAuditState::from(9), NicknameAuditState::Unknown(9));
+    }
+}
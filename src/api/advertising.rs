@@ -11,6 +11,7 @@ use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AdvertisingRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -18,6 +19,7 @@ pub struct AdvertisingRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AdvertisingResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -36,6 +38,7 @@ impl AdvertisingApi {
         Self { context }
     }
 
+    #[doc(alias = "回传数据")]
     pub async fn add_user_action(
         &self,
         request: &AdvertisingRequest,
@@ -43,6 +46,7 @@ impl AdvertisingApi {
         self.post_json("/marketing/add_user_action", request).await
     }
 
+    #[doc(alias = "创建数据源")]
     pub async fn add_user_action_set(
         &self,
         request: &AdvertisingRequest,
@@ -51,6 +55,7 @@ impl AdvertisingApi {
             .await
     }
 
+    #[doc(alias = "查询数据源报表")]
     pub async fn get_user_action_set_reports(
         &self,
         request: &AdvertisingRequest,
@@ -59,6 +64,7 @@ impl AdvertisingApi {
             .await
     }
 
+    #[doc(alias = "获取数据源信息")]
     pub async fn get_user_action_sets(
         &self,
         request: &AdvertisingRequest,
@@ -88,6 +94,14 @@ impl WechatApi for AdvertisingApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<AdvertisingRequest, AdvertisingResponse>("ad.addUserAction");
+    schemas.add::<AdvertisingRequest, AdvertisingResponse>("ad.addUserActionSet");
+    schemas.add::<AdvertisingRequest, AdvertisingResponse>("ad.getUserActionSetReports");
+    schemas.add::<AdvertisingRequest, AdvertisingResponse>("ad.getUserActionSets");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3,14 +3,17 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::common::{scalar, FieldCache};
 use super::{WechatApi, WechatContext};
 use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AnalyticsDateRangeRequest {
     pub begin_date: String,
     pub end_date: String,
@@ -23,10 +26,56 @@ impl AnalyticsDateRangeRequest {
             end_date: end_date.into(),
         }
     }
+
+    /// Check that both dates are real `yyyymmdd` dates and in order
+    ///
+    /// [`AnalyticsApi`] runs this before sending.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] naming `begin_date` or `end_date`.
+    pub fn validate(&self) -> Result<(), WechatError> {
+        validate_date("begin_date", &self.begin_date)?;
+        validate_date("end_date", &self.end_date)?;
+        // Equal-length digit strings order like the dates they encode
+        if self.begin_date > self.end_date {
+            return Err(WechatError::validation(
+                "end_date",
+                format!("{} is before begin_date {}", self.end_date, self.begin_date),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn validate_date(field: &str, date: &str) -> Result<(), WechatError> {
+    let invalid = || {
+        WechatError::validation(
+            field,
+            format!("{date:?} is not a yyyymmdd date, e.g. 20240131"),
+        )
+    };
+    if date.len() != 8 || !date.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let number = |range: std::ops::Range<usize>| date[range].parse::<u32>().unwrap_or(0);
+    let (year, month, day) = (number(0..4), number(4..6), number(6..8));
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return Err(invalid()),
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return Err(invalid());
+    }
+    Ok(())
 }
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PerformanceDataRequest {
     pub cost_time_type: i32,
     pub default_start_time: i64,
@@ -41,6 +90,7 @@ pub struct PerformanceDataRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AnalyticsResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -48,6 +98,77 @@ pub struct AnalyticsResponse {
     pub(crate) errmsg: String,
     #[serde(flatten)]
     pub extra: HashMap<String, Value>,
+    #[serde(skip)]
+    cache: FieldCache,
+}
+
+impl AnalyticsResponse {
+    /// Decode the `list` array into typed rows, memoized per row type.
+    pub fn list_as<T>(&self) -> Result<Arc<Vec<T>>, WechatError>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        self.cache.field_as(&self.extra, "list")
+    }
+
+    /// Decode any field into `T`, memoized per field and type.
+    pub fn field_as<T>(&self, key: &str) -> Result<Arc<T>, WechatError>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        self.cache.field_as(&self.extra, key)
+    }
+
+    /// Integer field, read from the top level or the single `list` row.
+    pub fn i64(&self, key: &str) -> Option<i64> {
+        scalar(&self.extra, key).and_then(Value::as_i64)
+    }
+
+    /// Numeric field, read from the top level or the single `list` row.
+    pub fn f64(&self, key: &str) -> Option<f64> {
+        scalar(&self.extra, key).and_then(Value::as_f64)
+    }
+
+    /// String field, read from the top level or the single `list` row.
+    pub fn str(&self, key: &str) -> Option<&str> {
+        scalar(&self.extra, key).and_then(Value::as_str)
+    }
+}
+
+/// Row of `get_daily_summary` (`getweanalysisappiddailysummarytrend`)
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DailySummaryItem {
+    #[serde(default)]
+    pub ref_date: String,
+    #[serde(default)]
+    pub visit_total: i64,
+    #[serde(default)]
+    pub share_pv: i64,
+    #[serde(default)]
+    pub share_uv: i64,
+}
+
+/// Row of the daily/weekly/monthly visit trend endpoints
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct VisitTrendItem {
+    #[serde(default)]
+    pub ref_date: String,
+    #[serde(default)]
+    pub session_cnt: i64,
+    #[serde(default)]
+    pub visit_pv: i64,
+    #[serde(default)]
+    pub visit_uv: i64,
+    #[serde(default)]
+    pub visit_uv_new: i64,
+    #[serde(default)]
+    pub stay_time_uv: f64,
+    #[serde(default)]
+    pub stay_time_session: f64,
+    #[serde(default)]
+    pub visit_depth: f64,
 }
 
 pub struct AnalyticsApi {
@@ -63,7 +184,7 @@ impl AnalyticsApi {
         &self,
         request: &AnalyticsDateRangeRequest,
     ) -> Result<AnalyticsResponse, WechatError> {
-        self.post_datacube("/datacube/getweanalysisappiddailysummarytrend", request)
+        self.post_date_range("/datacube/getweanalysisappiddailysummarytrend", request)
             .await
     }
 
@@ -71,7 +192,7 @@ impl AnalyticsApi {
         &self,
         request: &AnalyticsDateRangeRequest,
     ) -> Result<AnalyticsResponse, WechatError> {
-        self.post_datacube("/datacube/getweanalysisappiddailyvisittrend", request)
+        self.post_date_range("/datacube/getweanalysisappiddailyvisittrend", request)
             .await
     }
 
@@ -79,7 +200,7 @@ impl AnalyticsApi {
         &self,
         request: &AnalyticsDateRangeRequest,
     ) -> Result<AnalyticsResponse, WechatError> {
-        self.post_datacube("/datacube/getweanalysisappidweeklyvisittrend", request)
+        self.post_date_range("/datacube/getweanalysisappidweeklyvisittrend", request)
             .await
     }
 
@@ -87,7 +208,7 @@ impl AnalyticsApi {
         &self,
         request: &AnalyticsDateRangeRequest,
     ) -> Result<AnalyticsResponse, WechatError> {
-        self.post_datacube("/datacube/getweanalysisappidmonthlyvisittrend", request)
+        self.post_date_range("/datacube/getweanalysisappidmonthlyvisittrend", request)
             .await
     }
 
@@ -95,7 +216,7 @@ impl AnalyticsApi {
         &self,
         request: &AnalyticsDateRangeRequest,
     ) -> Result<AnalyticsResponse, WechatError> {
-        self.post_datacube("/datacube/getweanalysisappiddailyretaininfo", request)
+        self.post_date_range("/datacube/getweanalysisappiddailyretaininfo", request)
             .await
     }
 
@@ -103,7 +224,7 @@ impl AnalyticsApi {
         &self,
         request: &AnalyticsDateRangeRequest,
     ) -> Result<AnalyticsResponse, WechatError> {
-        self.post_datacube("/datacube/getweanalysisappidweeklyretaininfo", request)
+        self.post_date_range("/datacube/getweanalysisappidweeklyretaininfo", request)
             .await
     }
 
@@ -111,7 +232,7 @@ impl AnalyticsApi {
         &self,
         request: &AnalyticsDateRangeRequest,
     ) -> Result<AnalyticsResponse, WechatError> {
-        self.post_datacube("/datacube/getweanalysisappidmonthlyretaininfo", request)
+        self.post_date_range("/datacube/getweanalysisappidmonthlyretaininfo", request)
             .await
     }
 
@@ -119,7 +240,7 @@ impl AnalyticsApi {
         &self,
         request: &AnalyticsDateRangeRequest,
     ) -> Result<AnalyticsResponse, WechatError> {
-        self.post_datacube("/datacube/getweanalysisappidvisitpage", request)
+        self.post_date_range("/datacube/getweanalysisappidvisitpage", request)
             .await
     }
 
@@ -127,7 +248,7 @@ impl AnalyticsApi {
         &self,
         request: &AnalyticsDateRangeRequest,
     ) -> Result<AnalyticsResponse, WechatError> {
-        self.post_datacube("/datacube/getweanalysisappidvisitdistribution", request)
+        self.post_date_range("/datacube/getweanalysisappidvisitdistribution", request)
             .await
     }
 
@@ -135,7 +256,7 @@ impl AnalyticsApi {
         &self,
         request: &AnalyticsDateRangeRequest,
     ) -> Result<AnalyticsResponse, WechatError> {
-        self.post_datacube("/datacube/getweanalysisappiduserportrait", request)
+        self.post_date_range("/datacube/getweanalysisappiduserportrait", request)
             .await
     }
 
@@ -147,6 +268,15 @@ impl AnalyticsApi {
             .await
     }
 
+    async fn post_date_range(
+        &self,
+        endpoint: &str,
+        request: &AnalyticsDateRangeRequest,
+    ) -> Result<AnalyticsResponse, WechatError> {
+        request.validate()?;
+        self.post_datacube(endpoint, request).await
+    }
+
     async fn post_datacube<B: Serialize>(
         &self,
         endpoint: &str,
@@ -168,6 +298,21 @@ impl WechatApi for AnalyticsApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<AnalyticsDateRangeRequest, AnalyticsResponse>("analytics.getDailySummary");
+    schemas.add::<AnalyticsDateRangeRequest, AnalyticsResponse>("analytics.getDailyVisitTrend");
+    schemas.add::<AnalyticsDateRangeRequest, AnalyticsResponse>("analytics.getWeeklyVisitTrend");
+    schemas.add::<AnalyticsDateRangeRequest, AnalyticsResponse>("analytics.getMonthlyVisitTrend");
+    schemas.add::<AnalyticsDateRangeRequest, AnalyticsResponse>("analytics.getDailyRetain");
+    schemas.add::<AnalyticsDateRangeRequest, AnalyticsResponse>("analytics.getWeeklyRetain");
+    schemas.add::<AnalyticsDateRangeRequest, AnalyticsResponse>("analytics.getMonthlyRetain");
+    schemas.add::<AnalyticsDateRangeRequest, AnalyticsResponse>("analytics.getVisitPage");
+    schemas.add::<AnalyticsDateRangeRequest, AnalyticsResponse>("analytics.getVisitDistribution");
+    schemas.add::<AnalyticsDateRangeRequest, AnalyticsResponse>("analytics.getUserPortrait");
+    schemas.add::<PerformanceDataRequest, AnalyticsResponse>("analytics.getPerformanceData");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,6 +325,39 @@ mod tests {
         assert!(response.extra.contains_key("list"));
     }
 
+    #[test]
+    fn analytics_response_typed_accessors_are_memoized() {
+        let json = r#"{"errcode":0,"errmsg":"ok","list":[{"ref_date":"20240101","session_cnt":142549,"visit_pv":748940,"visit_uv":93305,"visit_uv_new":5450,"stay_time_session":2.5,"visit_depth":1.9}]}"#;
+        let response: AnalyticsResponse = serde_json::from_str(json).unwrap();
+
+        let rows = response.list_as::<VisitTrendItem>().unwrap();
+        assert_eq!(rows[0].ref_date, "20240101");
+        assert_eq!(rows[0].visit_pv, 748940);
+        assert_eq!(rows[0].visit_depth, 1.9);
+        let again = response.list_as::<VisitTrendItem>().unwrap();
+        assert!(Arc::ptr_eq(&rows, &again));
+
+        assert_eq!(response.i64("visit_pv"), Some(748940));
+        assert_eq!(response.f64("stay_time_session"), Some(2.5));
+        assert_eq!(response.str("ref_date"), Some("20240101"));
+        assert_eq!(response.i64("missing"), None);
+    }
+
+    #[test]
+    fn analytics_response_typed_accessor_reports_decode_path() {
+        let json =
+            r#"{"errcode":0,"errmsg":"ok","list":[{"ref_date":"20240101","visit_pv":"many"}]}"#;
+        let response: AnalyticsResponse = serde_json::from_str(json).unwrap();
+        match response.list_as::<VisitTrendItem>().unwrap_err() {
+            WechatError::Decode { path, .. } => assert_eq!(path, "list[0].visit_pv"),
+            other => panic!("Expected Decode error, got {:?}", other),
+        }
+        assert!(matches!(
+            response.field_as::<Vec<String>>("items"),
+            Err(WechatError::Decode { .. })
+        ));
+    }
+
     #[test]
     fn analytics_date_range_request_serializes() {
         let request = AnalyticsDateRangeRequest::new("20240101", "20240102");
@@ -187,4 +365,25 @@ mod tests {
         assert_eq!(value["begin_date"], "20240101");
         assert_eq!(value["end_date"], "20240102");
     }
+
+    #[test]
+    fn analytics_date_range_request_validates_dates() {
+        assert!(AnalyticsDateRangeRequest::new("20240229", "20240301")
+            .validate()
+            .is_ok());
+        for (begin, end, field) in [
+            ("2024-01-01", "20240102", "begin_date"),
+            ("20230229", "20230301", "begin_date"),
+            ("20240101", "20241301", "end_date"),
+            ("20240102", "20240101", "end_date"),
+        ] {
+            assert!(
+                matches!(
+                    AnalyticsDateRangeRequest::new(begin, end).validate(),
+                    Err(WechatError::Validation { field: f, .. }) if f == field
+                ),
+                "{begin}..{end}"
+            );
+        }
+    }
 }
@@ -3,16 +3,24 @@
 //! Provides login and authentication related APIs.
 
 use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
 use crate::api::r#trait::{WechatApi, WechatContext};
 use crate::error::WechatError;
+use crate::types::SessionKey;
+
+/// errcode returned by checksession when the signature does not match the
+/// current session key.
+const INVALID_SESSION_SIGNATURE: i32 = 87009;
 
 /// Login response from code2Session API
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LoginResponse {
     /// User's unique ID under the Mini Program
     #[serde(default)]
@@ -20,7 +28,8 @@ pub struct LoginResponse {
     /// Session key for decrypting user data
     #[serde(default)]
     pub session_key: String,
-    /// User's unique ID across WeChat platform (optional)
+    /// User's unique ID across WeChat platform (optional); see
+    /// [`unionid_status`](Self::unionid_status) for why it may be missing
     #[serde(default)]
     pub unionid: Option<String>,
     /// Error code (0 means success)
@@ -61,17 +70,75 @@ impl LoginResponse {
     pub fn errmsg(&self) -> &str {
         &self.errmsg
     }
+
+    /// Whether code2Session returned a `unionid`
+    ///
+    /// code2Session omits `unionid` without giving a reason, so an absent
+    /// (or empty) one reads as [`UnionIdStatus::Unknown`]. Use
+    /// [`unionid_status_for`](Self::unionid_status_for) when the Open
+    /// Platform binding of the mini program is known.
+    pub fn unionid_status(&self) -> UnionIdStatus {
+        match self.unionid.as_deref() {
+            Some(unionid) if !unionid.is_empty() => UnionIdStatus::Present(unionid.to_string()),
+            _ => UnionIdStatus::Unknown,
+        }
+    }
+
+    /// [`unionid_status`](Self::unionid_status) with the absence explained by
+    /// whether the mini program is bound to an Open Platform account
+    pub fn unionid_status_for(&self, bound_to_open_platform: bool) -> UnionIdStatus {
+        match self.unionid_status() {
+            UnionIdStatus::Unknown if bound_to_open_platform => UnionIdStatus::ScopeNotSatisfied,
+            UnionIdStatus::Unknown => UnionIdStatus::NotBoundToOpenPlatform,
+            status => status,
+        }
+    }
+}
+
+/// Availability of the `unionid` in a [`LoginResponse`]
+///
+/// Account merging should only key on [`Present`](Self::Present); in every
+/// other state fall back to the `openid` and try again on a later login or
+/// through `get_paid_unionid` / decrypted user info.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum UnionIdStatus {
+    /// WeChat returned the user's `unionid`
+    Present(String),
+    /// The mini program is not bound to an Open Platform account, so no
+    /// `unionid` exists for it
+    NotBoundToOpenPlatform,
+    /// The mini program is bound, but WeChat withheld the `unionid` because
+    /// the user has not met the authorization scope under that account
+    ScopeNotSatisfied,
+    /// No `unionid` was returned and the reason is not known
+    Unknown,
+}
+
+impl UnionIdStatus {
+    /// The `unionid`, if present
+    pub fn unionid(&self) -> Option<&str> {
+        match self {
+            Self::Present(unionid) => Some(unionid),
+            _ => None,
+        }
+    }
+
+    pub fn is_present(&self) -> bool {
+        matches!(self, Self::Present(_))
+    }
 }
 
 #[derive(Clone, Serialize)]
-struct StableAccessTokenRequest {
-    grant_type: String,
-    appid: String,
-    secret: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct StableAccessTokenRequest<'a> {
+    grant_type: &'a str,
+    appid: &'a str,
+    secret: &'a str,
     force_refresh: bool,
 }
 
-impl fmt::Debug for StableAccessTokenRequest {
+impl fmt::Debug for StableAccessTokenRequest<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("StableAccessTokenRequest")
             .field("grant_type", &self.grant_type)
@@ -85,6 +152,7 @@ impl fmt::Debug for StableAccessTokenRequest {
 /// Response from getStableAccessToken
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct StableAccessTokenResponse {
     /// The stable access token
     #[serde(default)]
@@ -101,13 +169,14 @@ pub struct StableAccessTokenResponse {
 }
 
 #[derive(Clone, Serialize)]
-struct CheckSessionKeyRequest {
-    openid: String,
-    signature: String,
-    sig_method: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct CheckSessionKeyRequest<'a> {
+    openid: &'a str,
+    signature: &'a str,
+    sig_method: &'a str,
 }
 
-impl fmt::Debug for CheckSessionKeyRequest {
+impl fmt::Debug for CheckSessionKeyRequest<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("CheckSessionKeyRequest")
             .field("openid", &self.openid)
@@ -118,13 +187,14 @@ impl fmt::Debug for CheckSessionKeyRequest {
 }
 
 #[derive(Clone, Serialize)]
-struct ResetUserSessionKeyRequest {
-    openid: String,
-    signature: String,
-    sig_method: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct ResetUserSessionKeyRequest<'a> {
+    openid: &'a str,
+    signature: &'a str,
+    sig_method: &'a str,
 }
 
-impl fmt::Debug for ResetUserSessionKeyRequest {
+impl fmt::Debug for ResetUserSessionKeyRequest<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ResetUserSessionKeyRequest")
             .field("openid", &self.openid)
@@ -137,6 +207,7 @@ impl fmt::Debug for ResetUserSessionKeyRequest {
 /// Response from resetUserSessionKey
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ResetSessionKeyResponse {
     #[serde(default)]
     pub openid: String,
@@ -156,6 +227,34 @@ struct BaseApiResponse {
     errmsg: String,
 }
 
+/// Future returned by [`SessionStore`] methods
+pub type SessionStoreFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<(), WechatError>> + Send + 'a>>;
+
+/// Application storage for user session keys
+///
+/// Used by [`AuthApi::ensure_fresh_session`] to persist a rotated key.
+pub trait SessionStore: Send + Sync {
+    /// Replace the stored session key of `openid` with `session_key`.
+    fn update_session_key<'a>(
+        &'a self,
+        openid: &'a str,
+        session_key: &'a SessionKey,
+    ) -> SessionStoreFuture<'a>;
+}
+
+/// Action taken by [`AuthApi::ensure_fresh_session`]
+#[derive(Debug, Clone)]
+pub enum SessionRefresh {
+    /// The current session key is still valid; nothing was changed
+    Valid,
+    /// The session key was invalid, has been reset and stored
+    Rotated {
+        /// Newly issued session key
+        session_key: SessionKey,
+    },
+}
+
 pub struct AuthApi {
     context: Arc<WechatContext>,
 }
@@ -175,18 +274,29 @@ impl AuthApi {
     ///
     /// # Returns
     /// LoginResponse containing openid, session_key, and optionally unionid
+    ///
+    /// # Errors
+    /// [`WechatError::InvalidJsCode`] (40029) or [`WechatError::CodeAlreadyUsed`]
+    /// (40163) when the code cannot be exchanged; see
+    /// [`WechatError::is_client_retryable`].
     pub async fn login(&self, js_code: &str) -> Result<LoginResponse, WechatError> {
         let path = "/sns/jscode2session";
         let query = [
             ("appid", self.context.client.appid()),
-            ("secret", self.context.client.secret()),
+            ("secret", self.context.client.secret()?),
             ("js_code", js_code),
             ("grant_type", "authorization_code"),
         ];
 
-        let response: LoginResponse = self.context.client.get(path, &query).await?;
+        let response: LoginResponse = self
+            .context
+            .client
+            .get(path, &query)
+            .await
+            .map_err(WechatError::into_login_error)?;
 
-        WechatError::check_api(response.errcode, &response.errmsg)?;
+        WechatError::check_api(response.errcode, &response.errmsg)
+            .map_err(WechatError::into_login_error)?;
 
         Ok(response)
     }
@@ -206,9 +316,9 @@ impl AuthApi {
     ) -> Result<StableAccessTokenResponse, WechatError> {
         let path = "/cgi-bin/stable_token";
         let body = StableAccessTokenRequest {
-            grant_type: "client_credential".to_string(),
-            appid: self.context.client.appid().to_string(),
-            secret: self.context.client.secret().to_string(),
+            grant_type: "client_credential",
+            appid: self.context.client.appid(),
+            secret: self.context.client.secret()?,
             force_refresh,
         };
         let response: StableAccessTokenResponse = self.context.client.post(path, &body).await?;
@@ -226,9 +336,9 @@ impl AuthApi {
         sig_method: &str,
     ) -> Result<(), WechatError> {
         let body = CheckSessionKeyRequest {
-            openid: openid.to_string(),
-            signature: signature.to_string(),
-            sig_method: sig_method.to_string(),
+            openid,
+            signature,
+            sig_method,
         };
         let response: BaseApiResponse =
             self.context.authed_post("/wxa/checksession", &body).await?;
@@ -246,9 +356,9 @@ impl AuthApi {
         sig_method: &str,
     ) -> Result<ResetSessionKeyResponse, WechatError> {
         let body = ResetUserSessionKeyRequest {
-            openid: openid.to_string(),
-            signature: signature.to_string(),
-            sig_method: sig_method.to_string(),
+            openid,
+            signature,
+            sig_method,
         };
         let response: ResetSessionKeyResponse = self
             .context
@@ -257,6 +367,41 @@ impl AuthApi {
         WechatError::check_api(response.errcode, &response.errmsg)?;
         Ok(response)
     }
+
+    /// Verify a user's session key and rotate it if it is no longer valid
+    ///
+    /// Calls [`check_session_key`](Self::check_session_key); when WeChat
+    /// rejects the signature (errcode 87009) the key is reset with
+    /// [`reset_user_session_key`](Self::reset_user_session_key) and the new
+    /// key is written to `store` before returning.
+    ///
+    /// # Errors
+    /// Other check failures, reset failures and store errors are returned
+    /// as-is. If the store update fails the new key is lost and the caller
+    /// should send the user through `wx.login()` again.
+    pub async fn ensure_fresh_session(
+        &self,
+        openid: &str,
+        signature: &str,
+        sig_method: &str,
+        store: &dyn SessionStore,
+    ) -> Result<SessionRefresh, WechatError> {
+        match self.check_session_key(openid, signature, sig_method).await {
+            Ok(()) => return Ok(SessionRefresh::Valid),
+            Err(WechatError::Api {
+                code: INVALID_SESSION_SIGNATURE,
+                ..
+            }) => {}
+            Err(error) => return Err(error),
+        }
+
+        let response = self
+            .reset_user_session_key(openid, signature, sig_method)
+            .await?;
+        let session_key = SessionKey::new(response.session_key)?;
+        store.update_session_key(openid, &session_key).await?;
+        Ok(SessionRefresh::Rotated { session_key })
+    }
 }
 
 impl WechatApi for AuthApi {
@@ -269,6 +414,21 @@ impl WechatApi for AuthApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    use super::common::ApiResponseBase;
+
+    schemas.response::<crate::token::TokenResponse>("accessToken.getAccessToken");
+    schemas.add::<StableAccessTokenRequest<'static>, StableAccessTokenResponse>(
+        "accessToken.getStableAccessToken",
+    );
+    schemas.response::<LoginResponse>("auth.code2Session");
+    schemas.add::<CheckSessionKeyRequest<'static>, ApiResponseBase>("auth.checkSessionKey");
+    schemas.add::<ResetUserSessionKeyRequest<'static>, ResetSessionKeyResponse>(
+        "auth.resetUserSessionKey",
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -306,6 +466,32 @@ mod tests {
         assert!(response.is_success());
     }
 
+    #[test]
+    fn test_login_response_unionid_status() {
+        let with_unionid = LoginResponse::new("oUser", "key", Some("uUnion".to_string()));
+        assert_eq!(
+            with_unionid.unionid_status(),
+            UnionIdStatus::Present("uUnion".to_string())
+        );
+        assert_eq!(
+            with_unionid.unionid_status_for(false).unionid(),
+            Some("uUnion")
+        );
+
+        let empty: LoginResponse =
+            serde_json::from_str(r#"{"openid":"oUser","session_key":"key","unionid":""}"#).unwrap();
+        assert_eq!(empty.unionid_status(), UnionIdStatus::Unknown);
+        assert_eq!(
+            empty.unionid_status_for(true),
+            UnionIdStatus::ScopeNotSatisfied
+        );
+        assert_eq!(
+            LoginResponse::new("oUser", "key", None).unionid_status_for(false),
+            UnionIdStatus::NotBoundToOpenPlatform
+        );
+        assert!(!empty.unionid_status().is_present());
+    }
+
     #[test]
     fn test_login_response_error_parse() {
         let json = r#"{
@@ -394,9 +580,9 @@ mod tests {
     #[test]
     fn test_stable_access_token_request_debug_redacts_secret() {
         let request = StableAccessTokenRequest {
-            grant_type: "client_credential".to_string(),
-            appid: "wx1234567890abcdef".to_string(),
-            secret: "top-secret-value".to_string(),
+            grant_type: "client_credential",
+            appid: "wx1234567890abcdef",
+            secret: "top-secret-value",
             force_refresh: false,
         };
 
@@ -409,9 +595,9 @@ mod tests {
     #[test]
     fn test_check_session_key_request_debug_redacts_signature() {
         let request = CheckSessionKeyRequest {
-            openid: "o123".to_string(),
-            signature: "sensitive-signature".to_string(),
-            sig_method: "hmac_sha256".to_string(),
+            openid: "o123",
+            signature: "sensitive-signature",
+            sig_method: "hmac_sha256",
         };
 
         let output = format!("{:?}", request);
@@ -423,9 +609,9 @@ mod tests {
     #[test]
     fn test_reset_user_session_key_request_debug_redacts_signature() {
         let request = ResetUserSessionKeyRequest {
-            openid: "o123".to_string(),
-            signature: "another-sensitive-signature".to_string(),
-            sig_method: "hmac_sha256".to_string(),
+            openid: "o123",
+            signature: "another-sensitive-signature",
+            sig_method: "hmac_sha256",
         };
 
         let output = format!("{:?}", request);
@@ -433,4 +619,121 @@ mod tests {
         assert!(output.contains("[REDACTED]"));
         assert!(!output.contains("another-sensitive-signature"));
     }
+
+    #[derive(Default)]
+    struct MemoryStore {
+        keys: std::sync::Mutex<std::collections::HashMap<String, String>>,
+    }
+
+    impl SessionStore for MemoryStore {
+        fn update_session_key<'a>(
+            &'a self,
+            openid: &'a str,
+            session_key: &'a SessionKey,
+        ) -> SessionStoreFuture<'a> {
+            Box::pin(async move {
+                self.keys
+                    .lock()
+                    .unwrap()
+                    .insert(openid.to_string(), session_key.as_str().to_string());
+                Ok(())
+            })
+        }
+    }
+
+    async fn session_test_api(check_errcode: i32) -> (wiremock::MockServer, AuthApi) {
+        use crate::client::WechatClient;
+        use crate::token::TokenManager;
+        use crate::types::{AppId, AppSecret};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/checksession"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": check_errcode,
+                "errmsg": if check_errcode == 0 { "ok" } else { "invalid signature" }
+            })))
+            .mount(&server)
+            .await;
+
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(server.uri())
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(TokenManager::new((*client).clone()));
+        let api = AuthApi::new(Arc::new(WechatContext::new(client, token_manager)));
+        (server, api)
+    }
+
+    #[tokio::test]
+    async fn test_ensure_fresh_session_keeps_valid_key() {
+        let (_server, api) = session_test_api(0).await;
+        let store = MemoryStore::default();
+        let action = api
+            .ensure_fresh_session("openid", "sig", "hmac_sha256", &store)
+            .await
+            .unwrap();
+        assert!(matches!(action, SessionRefresh::Valid));
+        assert!(store.keys.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ensure_fresh_session_rotates_and_stores_key() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        let (server, api) = session_test_api(87009).await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/resetusersessionkey"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "openid": "openid",
+                "session_key": "bmV3X3Nlc3Npb25fa2V5IQ=="
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let store = MemoryStore::default();
+        let action = api
+            .ensure_fresh_session("openid", "sig", "hmac_sha256", &store)
+            .await
+            .unwrap();
+        match action {
+            SessionRefresh::Rotated { session_key } => {
+                assert_eq!(session_key.as_str(), "bmV3X3Nlc3Npb25fa2V5IQ==")
+            }
+            other => panic!("expected rotation, got {other:?}"),
+        }
+        assert_eq!(
+            store.keys.lock().unwrap().get("openid").map(String::as_str),
+            Some("bmV3X3Nlc3Npb25fa2V5IQ==")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ensure_fresh_session_propagates_other_errors() {
+        let (_server, api) = session_test_api(40001).await;
+        let store = MemoryStore::default();
+        let error = api
+            .ensure_fresh_session("openid", "sig", "hmac_sha256", &store)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WechatError::Api { code: 40001, .. }));
+    }
 }
@@ -0,0 +1,420 @@
+//! Channel attribution for URL links, URL schemes and Mini Program codes
+//!
+//! [`ChannelTag`] writes a standard set of channel parameters into the
+//! `query` of URL links and schemes, the `path` of limited Mini Program codes
+//! and the `scene` of unlimited codes, and parses them back on the Mini
+//! Program side. [`ChannelReport`] aggregates parsed tags per link kind.
+//!
+//! Links and schemes use `utm_source`, `utm_medium`, `utm_campaign` and
+//! `utm_content`. The 32-character `scene` of unlimited codes uses the compact
+//! keys `cs`, `cm`, `cc` and `ct`; [`ChannelTag::parse`] accepts both forms.
+//!
+//! ```
+//! use wechat_mp_sdk::api::channel_tag::ChannelTag;
+//! use wechat_mp_sdk::api::qrcode::UrlLinkOptions;
+//!
+//! let tag = ChannelTag::new("poster").medium("offline").campaign("spring");
+//! let mut options = UrlLinkOptions {
+//!     path: Some("pages/index/index".into()),
+//!     query: Some("id=42".into()),
+//!     expire_type: None,
+//!     expire_time: None,
+//!     expire_interval: None,
+//! };
+//! tag.apply_to_url_link(&mut options);
+//! assert_eq!(
+//!     options.query.as_deref(),
+//!     Some("id=42&utm_source=poster&utm_medium=offline&utm_campaign=spring")
+//! );
+//! assert_eq!(ChannelTag::parse(options.query.as_deref().unwrap()), Some(tag));
+//! ```
+
+use std::collections::BTreeMap;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use super::qrcode::{QrcodeOptions, UnlimitQrcodeOptions, UrlLinkOptions, UrlSchemeOptions};
+use crate::error::WechatError;
+
+/// Query keys used in URL links, schemes and code paths
+const QUERY_KEYS: [&str; 4] = ["utm_source", "utm_medium", "utm_campaign", "utm_content"];
+
+/// Compact keys used in unlimited code scenes
+const SCENE_KEYS: [&str; 4] = ["cs", "cm", "cc", "ct"];
+
+/// Maximum length of an unlimited code `scene`
+const MAX_SCENE_LEN: usize = 32;
+
+const QUERY_VALUE_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Kind of link a channel tag was attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LinkKind {
+    /// `generate_urllink`
+    UrlLink,
+    /// `generate_scheme` / `generate_nfc_scheme`
+    UrlScheme,
+    /// `get_wxa_code` / `get_wxa_code_unlimit` / `create_qrcode`
+    WxaCode,
+}
+
+/// Standardized channel attribution parameters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChannelTag {
+    source: String,
+    medium: Option<String>,
+    campaign: Option<String>,
+    content: Option<String>,
+}
+
+impl ChannelTag {
+    /// Create a tag for the given traffic source.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn medium(mut self, medium: impl Into<String>) -> Self {
+        self.medium = Some(medium.into());
+        self
+    }
+
+    pub fn campaign(mut self, campaign: impl Into<String>) -> Self {
+        self.campaign = Some(campaign.into());
+        self
+    }
+
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    pub fn source_value(&self) -> &str {
+        &self.source
+    }
+
+    pub fn medium_value(&self) -> Option<&str> {
+        self.medium.as_deref()
+    }
+
+    pub fn campaign_value(&self) -> Option<&str> {
+        self.campaign.as_deref()
+    }
+
+    pub fn content_value(&self) -> Option<&str> {
+        self.content.as_deref()
+    }
+
+    fn values(&self) -> [Option<&str>; 4] {
+        [
+            Some(self.source.as_str()),
+            self.medium.as_deref(),
+            self.campaign.as_deref(),
+            self.content.as_deref(),
+        ]
+    }
+
+    /// Render the tag as a percent-encoded query string.
+    pub fn to_query(&self) -> String {
+        QUERY_KEYS
+            .iter()
+            .zip(self.values())
+            .filter_map(|(key, value)| {
+                value.map(|value| {
+                    format!(
+                        "{}={}",
+                        key,
+                        utf8_percent_encode(value, QUERY_VALUE_ENCODE_SET)
+                    )
+                })
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Append the tag to an existing query, replacing any previous channel keys.
+    pub fn append_to_query(&self, query: Option<&str>) -> String {
+        let mut pairs: Vec<&str> = query
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty() && !is_channel_pair(pair))
+            .collect();
+        let tag = self.to_query();
+        pairs.push(&tag);
+        pairs.join("&")
+    }
+
+    /// Render the tag in the compact form used by unlimited code scenes.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Config`] if a value contains characters other
+    /// than ASCII letters, digits, `-`, `.`, `_` and `~`, or if the scene would
+    /// exceed 32 characters.
+    pub fn to_scene(&self) -> Result<String, WechatError> {
+        let mut pairs = Vec::new();
+        for (key, value) in SCENE_KEYS.iter().zip(self.values()) {
+            let Some(value) = value else { continue };
+            if let Some(invalid) = value
+                .chars()
+                .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')))
+            {
+                return Err(WechatError::Config(format!(
+                    "channel value `{}` contains `{}`, which is not allowed in a scene",
+                    value, invalid
+                )));
+            }
+            pairs.push(format!("{}={}", key, value));
+        }
+        let scene = pairs.join("&");
+        if scene.len() > MAX_SCENE_LEN {
+            return Err(WechatError::Config(format!(
+                "channel scene `{}` exceeds {} characters",
+                scene, MAX_SCENE_LEN
+            )));
+        }
+        Ok(scene)
+    }
+
+    /// Add the tag to a URL link's `query`.
+    pub fn apply_to_url_link(&self, options: &mut UrlLinkOptions) {
+        options.query = Some(self.append_to_query(options.query.as_deref()));
+    }
+
+    /// Add the tag to a URL scheme's `query`.
+    pub fn apply_to_url_scheme(&self, options: &mut UrlSchemeOptions) {
+        options.query = Some(self.append_to_query(options.query.as_deref()));
+    }
+
+    /// Add the tag to the query part of a limited code's `path`.
+    pub fn apply_to_wxa_code(&self, options: &mut QrcodeOptions) {
+        let path = options.path.as_deref().unwrap_or_default();
+        let (page, query) = match path.split_once('?') {
+            Some((page, query)) => (page, Some(query)),
+            None => (path, None),
+        };
+        options.path = Some(format!("{}?{}", page, self.append_to_query(query)));
+    }
+
+    /// Replace an unlimited code's `scene` with the compact tag.
+    ///
+    /// # Errors
+    /// See [`ChannelTag::to_scene`].
+    pub fn apply_to_unlimit_wxa_code(
+        &self,
+        options: &mut UnlimitQrcodeOptions,
+    ) -> Result<(), WechatError> {
+        options.scene = self.to_scene()?;
+        Ok(())
+    }
+
+    /// Parse a tag from a query string or scene.
+    ///
+    /// Accepts both the `utm_*` and compact keys, with or without a leading
+    /// `?`. A `scene` still percent-encoded by the Mini Program runtime is
+    /// decoded as well. Returns `None` when no source is present.
+    pub fn parse(query: &str) -> Option<Self> {
+        let mut values: [Option<String>; 4] = Default::default();
+        for pair in query.trim_start_matches('?').split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = decode(key);
+            let Some(index) = QUERY_KEYS
+                .iter()
+                .position(|k| *k == key)
+                .or_else(|| SCENE_KEYS.iter().position(|k| *k == key))
+            else {
+                continue;
+            };
+            values[index] = Some(decode(value));
+        }
+
+        if values.iter().all(Option::is_none) && (query.contains("%3D") || query.contains("%3d")) {
+            return Self::parse(&decode(query));
+        }
+
+        let [source, medium, campaign, content] = values;
+        Some(Self {
+            source: source.filter(|source| !source.is_empty())?,
+            medium,
+            campaign,
+            content,
+        })
+    }
+}
+
+fn decode(value: &str) -> String {
+    percent_decode_str(&value.replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+fn is_channel_pair(pair: &str) -> bool {
+    let key = pair.split_once('=').map_or(pair, |(key, _)| key);
+    QUERY_KEYS.contains(&key)
+}
+
+/// Visit counts for one channel, split by link kind
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    counts: BTreeMap<LinkKind, u64>,
+}
+
+impl ChannelStats {
+    /// Count for one link kind
+    pub fn count(&self, kind: LinkKind) -> u64 {
+        self.counts.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Count across all link kinds
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+}
+
+/// Aggregated channel attribution across URL links, schemes and codes
+#[derive(Debug, Clone, Default)]
+pub struct ChannelReport {
+    channels: BTreeMap<ChannelTag, ChannelStats>,
+    untagged: u64,
+}
+
+impl ChannelReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one visit for `tag` via a link of the given kind.
+    pub fn record(&mut self, kind: LinkKind, tag: &ChannelTag) {
+        *self
+            .channels
+            .entry(tag.clone())
+            .or_default()
+            .counts
+            .entry(kind)
+            .or_default() += 1;
+    }
+
+    /// Parse `query` and record it, counting it as untagged if no tag is present.
+    pub fn record_query(&mut self, kind: LinkKind, query: &str) {
+        match ChannelTag::parse(query) {
+            Some(tag) => self.record(kind, &tag),
+            None => self.untagged += 1,
+        }
+    }
+
+    /// Stats for one tag
+    pub fn get(&self, tag: &ChannelTag) -> Option<&ChannelStats> {
+        self.channels.get(tag)
+    }
+
+    /// All recorded tags with their stats, ordered by tag
+    pub fn iter(&self) -> impl Iterator<Item = (&ChannelTag, &ChannelStats)> {
+        self.channels.iter()
+    }
+
+    /// Totals per source, summed across media, campaigns and content
+    pub fn by_source(&self) -> BTreeMap<&str, u64> {
+        let mut totals = BTreeMap::new();
+        for (tag, stats) in &self.channels {
+            *totals.entry(tag.source.as_str()).or_default() += stats.total();
+        }
+        totals
+    }
+
+    /// Number of visits recorded without a channel tag
+    pub fn untagged(&self) -> u64 {
+        self.untagged
+    }
+
+    /// Number of tagged visits
+    pub fn total(&self) -> u64 {
+        self.channels.values().map(ChannelStats::total).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_query_encodes_values() {
+        let tag = ChannelTag::new("wechat moments").content("a&b");
+        assert_eq!(
+            tag.to_query(),
+            "utm_source=wechat%20moments&utm_content=a%26b"
+        );
+        assert_eq!(ChannelTag::parse(&tag.to_query()), Some(tag));
+    }
+
+    #[test]
+    fn test_append_replaces_existing_channel_keys() {
+        let tag = ChannelTag::new("poster");
+        assert_eq!(
+            tag.append_to_query(Some("id=1&utm_source=old&utm_medium=x")),
+            "id=1&utm_source=poster"
+        );
+        assert_eq!(tag.append_to_query(None), "utm_source=poster");
+    }
+
+    #[test]
+    fn test_apply_to_wxa_code_path() {
+        let tag = ChannelTag::new("flyer");
+        let mut options = QrcodeOptions::new();
+        options.path = Some("pages/index?id=7".to_string());
+        tag.apply_to_wxa_code(&mut options);
+        assert_eq!(
+            options.path.as_deref(),
+            Some("pages/index?id=7&utm_source=flyer")
+        );
+    }
+
+    #[test]
+    fn test_scene_round_trip_and_limits() {
+        let tag = ChannelTag::new("store12").medium("qr").campaign("s24");
+        let mut options = UnlimitQrcodeOptions::new("");
+        tag.apply_to_unlimit_wxa_code(&mut options).unwrap();
+        assert_eq!(options.scene, "cs=store12&cm=qr&cc=s24");
+        assert_eq!(ChannelTag::parse(&options.scene), Some(tag));
+
+        // The runtime hands the scene over percent-encoded
+        assert_eq!(
+            ChannelTag::parse("cs%3Dstore12%26cm%3Dqr"),
+            Some(ChannelTag::new("store12").medium("qr"))
+        );
+
+        assert!(ChannelTag::new("has space").to_scene().is_err());
+        assert!(ChannelTag::new("a".repeat(40)).to_scene().is_err());
+    }
+
+    #[test]
+    fn test_parse_requires_source() {
+        assert_eq!(ChannelTag::parse("utm_medium=x"), None);
+        assert_eq!(ChannelTag::parse(""), None);
+        assert_eq!(
+            ChannelTag::parse("?utm_source=a"),
+            Some(ChannelTag::new("a"))
+        );
+    }
+
+    #[test]
+    fn test_report_aggregates_by_kind_and_source() {
+        let mut report = ChannelReport::new();
+        report.record_query(LinkKind::UrlLink, "utm_source=poster&utm_medium=a");
+        report.record_query(LinkKind::WxaCode, "cs=poster&cm=a");
+        report.record_query(LinkKind::UrlScheme, "utm_source=poster&utm_medium=b");
+        report.record_query(LinkKind::UrlLink, "id=1");
+
+        let stats = report.get(&ChannelTag::new("poster").medium("a")).unwrap();
+        assert_eq!(stats.count(LinkKind::UrlLink), 1);
+        assert_eq!(stats.count(LinkKind::WxaCode), 1);
+        assert_eq!(stats.total(), 2);
+        assert_eq!(report.by_source().get("poster"), Some(&3));
+        assert_eq!(report.untagged(), 1);
+        assert_eq!(report.total(), 3);
+    }
+}
@@ -1,16 +1,34 @@
 //! Cloud Base API
+//!
+//! Storage housekeeping is limited to what the TCB HTTP API exposes: it has
+//! no listing endpoint, so [`CloudApi::get_file_info`] works on known file ids
+//! (for example those recorded at upload time).
+//!
+//! Uploads take two requests: `/tcb/uploadfile` returns a signed storage
+//! URL, and the file is then posted to that URL as a form.
+//! [`CloudApi::upload_file`] does both; [`CloudApi::download_file`] and
+//! [`CloudApi::download_files`] do the same for downloads through
+//! `/tcb/batchdownloadfile`.
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::cloud_database::{
+    Collection, DatabaseAddResponse, DatabaseCollectionsResponse, DatabaseDeleteResponse,
+    DatabaseQueryResponse, DatabaseUpdateResponse,
+};
+use super::endpoint_inventory::TimeoutClass;
 use super::{WechatApi, WechatContext};
 use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct InvokeCloudFunctionRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -18,6 +36,7 @@ pub struct InvokeCloudFunctionRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DelayedFunctionTaskRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -25,33 +44,81 @@ pub struct DelayedFunctionTaskRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CloudDatabaseRequest {
     pub query: String,
 }
 
+impl CloudDatabaseRequest {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UploadFileLinkRequest {
     pub env: String,
     pub path: String,
 }
 
+impl UploadFileLinkRequest {
+    pub fn new(env: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            env: env.into(),
+            path: path.into(),
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DownloadFileLinkRequest {
     pub env: String,
     pub file_list: Vec<String>,
 }
 
+impl DownloadFileLinkRequest {
+    pub fn new<I, S>(env: impl Into<String>, file_list: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            env: env.into(),
+            file_list: file_list.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DeleteCloudFileRequest {
     pub env: String,
     pub fileid_list: Vec<String>,
 }
 
+impl DeleteCloudFileRequest {
+    pub fn new<I, S>(env: impl Into<String>, fileid_list: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            env: env.into(),
+            fileid_list: fileid_list.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SendCloudBaseSmsRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -59,6 +126,7 @@ pub struct SendCloudBaseSmsRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CloudResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -68,6 +136,130 @@ pub struct CloudResponse {
     pub extra: HashMap<String, Value>,
 }
 
+/// Metadata of one cloud storage file
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudFileInfo {
+    /// Cloud file id (`cloud://...`)
+    pub file_id: String,
+    /// Per-file status from WeChat; 0 when the file exists
+    pub status: i32,
+    /// Size in bytes, when the storage backend reported it
+    pub size: Option<u64>,
+    /// Upload time as a Unix timestamp, from the object's `Last-Modified`
+    pub created: Option<i64>,
+}
+
+impl CloudFileInfo {
+    /// Whether WeChat reported the file as present
+    pub fn exists(&self) -> bool {
+        self.status == 0
+    }
+
+    /// Whether the file was uploaded more than `age` before `now`
+    ///
+    /// Files without a known upload time are never considered stale.
+    pub fn is_older_than(&self, age: Duration, now: SystemTime) -> bool {
+        let Some(created) = self.created else {
+            return false;
+        };
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() as i64)
+            .unwrap_or_default();
+        now.saturating_sub(created) > age.as_secs() as i64
+    }
+}
+
+/// Outcome of one file of [`CloudApi::download_files`]
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct CloudDownload {
+    pub file_id: String,
+    /// File content, or why WeChat or storage did not return it
+    pub content: Result<Bytes, WechatError>,
+}
+
+/// Lifetime of the temporary links used to read file metadata and content
+const FILE_LINK_MAX_AGE_SECS: u64 = 60;
+
+/// Most file ids `/tcb/batchdownloadfile` accepts per request
+const BATCH_DOWNLOAD_LIMIT: usize = 50;
+
+#[derive(Debug, Serialize)]
+struct FileInfoLinkRequest<'a> {
+    env: &'a str,
+    file_list: Vec<FileInfoLinkEntry<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct FileInfoLinkEntry<'a> {
+    fileid: &'a str,
+    max_age: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileInfoLinkResponse {
+    #[serde(default)]
+    errcode: i32,
+    #[serde(default)]
+    errmsg: String,
+    #[serde(default)]
+    file_list: Vec<FileInfoLink>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileInfoLink {
+    #[serde(default)]
+    fileid: String,
+    #[serde(default)]
+    download_url: String,
+    #[serde(default)]
+    status: i32,
+    #[serde(default)]
+    errmsg: String,
+}
+
+/// Signed storage upload target returned by `/tcb/uploadfile`
+#[derive(Debug, Deserialize)]
+struct UploadLinkResponse {
+    #[serde(default)]
+    errcode: i32,
+    #[serde(default)]
+    errmsg: String,
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    token: String,
+    #[serde(default)]
+    authorization: String,
+    #[serde(default)]
+    file_id: String,
+    #[serde(default)]
+    cos_file_id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DatabaseStatement<'a> {
+    env: &'a str,
+    query: String,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct CollectionRequest<'a> {
+    env: &'a str,
+    collection_name: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct CollectionListRequest<'a> {
+    env: &'a str,
+    limit: u32,
+    offset: u32,
+}
+
 pub struct CloudApi {
     context: Arc<WechatContext>,
 }
@@ -119,6 +311,120 @@ impl CloudApi {
         self.post_json("/tcb/databasequery", request).await
     }
 
+    /// Run [`Collection::query`] in environment `env`
+    pub async fn query_documents(
+        &self,
+        env: &str,
+        collection: &Collection,
+    ) -> Result<DatabaseQueryResponse, WechatError> {
+        let body = DatabaseStatement {
+            env,
+            query: collection.query(),
+        };
+        let response: DatabaseQueryResponse = self
+            .context
+            .authed_post("/tcb/databasequery", &body)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    /// Insert `documents` into `collection`
+    pub async fn add_documents<T: Serialize>(
+        &self,
+        env: &str,
+        collection: &Collection,
+        documents: &[T],
+    ) -> Result<DatabaseAddResponse, WechatError> {
+        let body = DatabaseStatement {
+            env,
+            query: collection.add_query(documents)?,
+        };
+        let response: DatabaseAddResponse =
+            self.context.authed_post("/tcb/databaseadd", &body).await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    /// Set the fields of `data` on the documents matching `collection`'s
+    /// filter; see [`Collection::update_query`]
+    pub async fn update_documents<T: Serialize>(
+        &self,
+        env: &str,
+        collection: &Collection,
+        data: &T,
+    ) -> Result<DatabaseUpdateResponse, WechatError> {
+        let body = DatabaseStatement {
+            env,
+            query: collection.update_query(data)?,
+        };
+        let response: DatabaseUpdateResponse = self
+            .context
+            .authed_post("/tcb/databaseupdate", &body)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    /// Delete the documents matching `collection`'s filter; see
+    /// [`Collection::remove_query`]
+    pub async fn delete_documents(
+        &self,
+        env: &str,
+        collection: &Collection,
+    ) -> Result<DatabaseDeleteResponse, WechatError> {
+        let body = DatabaseStatement {
+            env,
+            query: collection.remove_query()?,
+        };
+        let response: DatabaseDeleteResponse = self
+            .context
+            .authed_post("/tcb/databasedelete", &body)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    pub async fn database_collection_add(
+        &self,
+        env: &str,
+        collection_name: &str,
+    ) -> Result<CloudResponse, WechatError> {
+        let body = CollectionRequest {
+            env,
+            collection_name,
+        };
+        self.post_json("/tcb/databasecollectionadd", &body).await
+    }
+
+    pub async fn database_collection_delete(
+        &self,
+        env: &str,
+        collection_name: &str,
+    ) -> Result<CloudResponse, WechatError> {
+        let body = CollectionRequest {
+            env,
+            collection_name,
+        };
+        self.post_json("/tcb/databasecollectiondelete", &body).await
+    }
+
+    /// List collections of `env`, `limit` at a time starting at `offset`
+    pub async fn database_collection_get(
+        &self,
+        env: &str,
+        limit: u32,
+        offset: u32,
+    ) -> Result<DatabaseCollectionsResponse, WechatError> {
+        let body = CollectionListRequest { env, limit, offset };
+        let response: DatabaseCollectionsResponse = self
+            .context
+            .authed_post("/tcb/databasecollectionget", &body)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
     pub async fn get_upload_file_link(
         &self,
         request: &UploadFileLinkRequest,
@@ -126,6 +432,53 @@ impl CloudApi {
         self.post_json("/tcb/uploadfile", request).await
     }
 
+    /// Upload `data` to `cloud_path` of environment `env` and return the
+    /// new file id (`cloud://...`)
+    ///
+    /// Requests the signed link, then posts the file to storage with the
+    /// `key`, `Signature`, `x-cos-security-token` and `x-cos-meta-fileid`
+    /// fields it requires. The storage request uses the
+    /// [`TimeoutClass::Slow`] timeout.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Api`] when WeChat refuses the link and
+    /// [`WechatError::Http`] when storage rejects the upload.
+    pub async fn upload_file(
+        &self,
+        env: &str,
+        cloud_path: &str,
+        data: &[u8],
+    ) -> Result<String, WechatError> {
+        let link: UploadLinkResponse = self
+            .context
+            .authed_post(
+                "/tcb/uploadfile",
+                &UploadFileLinkRequest::new(env, cloud_path),
+            )
+            .await?;
+        WechatError::check_api(link.errcode, &link.errmsg)?;
+
+        let filename = cloud_path.rsplit('/').next().unwrap_or(cloud_path);
+        let form = reqwest::multipart::Form::new()
+            .text("key", cloud_path.to_string())
+            .text("Signature", link.authorization)
+            .text("x-cos-security-token", link.token)
+            .text("x-cos-meta-fileid", link.cos_file_id)
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(data.to_vec()).file_name(filename.to_string()),
+            );
+        let client = &self.context.client;
+        let request = client
+            .http()
+            .post(&link.url)
+            .multipart(form)
+            .timeout(client.class_timeout(TimeoutClass::Slow))
+            .build()?;
+        client.send_external(request).await?.error_for_status()?;
+        Ok(link.file_id)
+    }
+
     pub async fn get_download_file_link(
         &self,
         request: &DownloadFileLinkRequest,
@@ -140,6 +493,152 @@ impl CloudApi {
         self.post_json("/tcb/batchdeletefile", request).await
     }
 
+    /// Batch get cloud storage file metadata
+    ///
+    /// Resolves short-lived download links through `/tcb/batchdownloadfile`
+    /// and reads size and upload time with a `HEAD` request per existing
+    /// file. Missing files are returned with a non-zero `status`.
+    pub async fn get_file_info(
+        &self,
+        env: &str,
+        file_ids: &[&str],
+    ) -> Result<Vec<CloudFileInfo>, WechatError> {
+        let links = self.download_links(env, file_ids).await?;
+
+        let mut files = Vec::with_capacity(links.len());
+        for link in links {
+            let mut info = CloudFileInfo {
+                file_id: link.fileid,
+                status: link.status,
+                size: None,
+                created: None,
+            };
+            if info.exists() && !link.download_url.is_empty() {
+                let client = &self.context.client;
+                let request = client.http().head(&link.download_url).build()?;
+                let head = client.send_external(request).await?.error_for_status()?;
+                let headers = head.headers();
+                info.size = headers
+                    .get(reqwest::header::CONTENT_LENGTH)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok());
+                info.created = headers
+                    .get(reqwest::header::LAST_MODIFIED)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| httpdate::parse_http_date(value).ok())
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|elapsed| elapsed.as_secs() as i64);
+            }
+            files.push(info);
+        }
+        Ok(files)
+    }
+
+    /// Download the content of `file_id`
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Api`] with the file's `status` when WeChat
+    /// cannot link it (e.g. -503003 for a missing file).
+    pub async fn download_file(&self, env: &str, file_id: &str) -> Result<Bytes, WechatError> {
+        Ok(self
+            .download_file_stream(env, file_id)
+            .await?
+            .bytes()
+            .await?)
+    }
+
+    /// Like [`download_file`](Self::download_file), but returns the storage
+    /// response as soon as its headers arrive, so large files can be read
+    /// piece by piece with [`chunk`](reqwest::Response::chunk)
+    pub async fn download_file_stream(
+        &self,
+        env: &str,
+        file_id: &str,
+    ) -> Result<reqwest::Response, WechatError> {
+        let link = self
+            .download_links(env, &[file_id])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| WechatError::Decode {
+                path: "file_list".to_string(),
+                snippet: String::new(),
+                message: format!("no download link returned for {file_id}"),
+            })?;
+        WechatError::check_api(link.status, &link.errmsg)?;
+        self.fetch(&link.download_url).await
+    }
+
+    /// Download several files, requesting links 50 ids at a time
+    ///
+    /// Results follow the order of `file_ids`. A file WeChat cannot link or
+    /// storage fails to serve is reported in its
+    /// [`content`](CloudDownload::content); errors of the link requests
+    /// themselves fail the whole call.
+    pub async fn download_files(
+        &self,
+        env: &str,
+        file_ids: &[&str],
+    ) -> Result<Vec<CloudDownload>, WechatError> {
+        let links = self.download_links(env, file_ids).await?;
+        let mut downloads = Vec::with_capacity(links.len());
+        for link in links {
+            let content = match WechatError::check_api(link.status, &link.errmsg) {
+                Ok(()) => match self.fetch(&link.download_url).await {
+                    Ok(response) => response.bytes().await.map_err(WechatError::from),
+                    Err(error) => Err(error),
+                },
+                Err(error) => Err(error),
+            };
+            downloads.push(CloudDownload {
+                file_id: link.fileid,
+                content,
+            });
+        }
+        Ok(downloads)
+    }
+
+    /// Temporary download links for `file_ids`, in
+    /// [`BATCH_DOWNLOAD_LIMIT`] sized requests
+    async fn download_links(
+        &self,
+        env: &str,
+        file_ids: &[&str],
+    ) -> Result<Vec<FileInfoLink>, WechatError> {
+        let mut links = Vec::with_capacity(file_ids.len());
+        for chunk in file_ids.chunks(BATCH_DOWNLOAD_LIMIT) {
+            let request = FileInfoLinkRequest {
+                env,
+                file_list: chunk
+                    .iter()
+                    .map(|fileid| FileInfoLinkEntry {
+                        fileid,
+                        max_age: FILE_LINK_MAX_AGE_SECS,
+                    })
+                    .collect(),
+            };
+            let response: FileInfoLinkResponse = self
+                .context
+                .authed_post("/tcb/batchdownloadfile", &request)
+                .await?;
+            WechatError::check_api(response.errcode, &response.errmsg)?;
+            links.extend(response.file_list);
+        }
+        Ok(links)
+    }
+
+    /// GET a storage URL with the [`TimeoutClass::Slow`] timeout, outside
+    /// the middleware stack and rate limiter
+    async fn fetch(&self, url: &str) -> Result<reqwest::Response, WechatError> {
+        let client = &self.context.client;
+        let request = client
+            .http()
+            .get(url)
+            .timeout(client.class_timeout(TimeoutClass::Slow))
+            .build()?;
+        Ok(client.send_external(request).await?.error_for_status()?)
+    }
+
     pub async fn new_send_cloud_base_sms(
         &self,
         request: &SendCloudBaseSmsRequest,
@@ -168,6 +667,25 @@ impl WechatApi for CloudApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<InvokeCloudFunctionRequest, CloudResponse>("cloud.invokeCloudFunction");
+    schemas.add::<DelayedFunctionTaskRequest, CloudResponse>("cloud.addDelayedFunctionTask");
+    schemas.add::<CloudDatabaseRequest, CloudResponse>("cloud.databaseAdd");
+    schemas.add::<CloudDatabaseRequest, CloudResponse>("cloud.databaseDelete");
+    schemas.add::<CloudDatabaseRequest, CloudResponse>("cloud.databaseUpdate");
+    schemas.add::<CloudDatabaseRequest, CloudResponse>("cloud.databaseQuery");
+    schemas.add::<CollectionRequest<'static>, CloudResponse>("cloud.databaseCollectionAdd");
+    schemas.add::<CollectionRequest<'static>, CloudResponse>("cloud.databaseCollectionDelete");
+    schemas.add::<CollectionListRequest<'static>, DatabaseCollectionsResponse>(
+        "cloud.databaseCollectionGet",
+    );
+    schemas.add::<UploadFileLinkRequest, CloudResponse>("cloud.getUploadFileLink");
+    schemas.add::<DownloadFileLinkRequest, CloudResponse>("cloud.getDownloadFileLink");
+    schemas.add::<DeleteCloudFileRequest, CloudResponse>("cloud.deleteCloudFile");
+    schemas.add::<SendCloudBaseSmsRequest, CloudResponse>("cloud.newSendCloudBaseSms");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,4 +697,425 @@ mod tests {
         assert_eq!(response.errcode, 0);
         assert!(response.extra.contains_key("request_id"));
     }
+
+    #[test]
+    fn cloud_file_info_staleness() {
+        let info = CloudFileInfo {
+            file_id: "cloud://env.bucket/a.png".to_string(),
+            status: 0,
+            size: Some(10),
+            created: Some(1_000),
+        };
+        let now = UNIX_EPOCH + Duration::from_secs(1_000 + 3_600);
+        assert!(info.is_older_than(Duration::from_secs(60), now));
+        assert!(!info.is_older_than(Duration::from_secs(7_200), now));
+
+        let unknown = CloudFileInfo {
+            created: None,
+            ..info
+        };
+        assert!(!unknown.is_older_than(Duration::ZERO, now));
+    }
+
+    #[tokio::test]
+    async fn upload_file_posts_signed_form_to_storage() {
+        use crate::types::{AppId, AppSecret};
+        use crate::WechatClient;
+        use wiremock::matchers::{body_json, body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "tok",
+                "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/tcb/uploadfile"))
+            .and(body_json(
+                serde_json::json!({"env": "prod", "path": "avatars/a.png"}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "url": format!("{}/bucket", server.uri()),
+                "token": "security-token",
+                "authorization": "q-sign-algorithm=sha1",
+                "file_id": "cloud://prod.bucket/avatars/a.png",
+                "cos_file_id": "cos-id"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bucket"))
+            .and(body_string_contains("name=\"key\"\r\n\r\navatars/a.png"))
+            .and(body_string_contains(
+                "name=\"Signature\"\r\n\r\nq-sign-algorithm=sha1",
+            ))
+            .and(body_string_contains(
+                "name=\"x-cos-security-token\"\r\n\r\nsecurity-token",
+            ))
+            .and(body_string_contains(
+                "name=\"x-cos-meta-fileid\"\r\n\r\ncos-id",
+            ))
+            .and(body_string_contains("filename=\"a.png\""))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(server.uri())
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(crate::token::TokenManager::new((*client).clone()));
+        let api = CloudApi::new(Arc::new(WechatContext::new(client, token_manager)));
+
+        let file_id = api
+            .upload_file("prod", "avatars/a.png", b"png")
+            .await
+            .unwrap();
+        assert_eq!(file_id, "cloud://prod.bucket/avatars/a.png");
+    }
+
+    #[tokio::test]
+    async fn download_files_chunks_link_requests() {
+        use crate::types::{AppId, AppSecret};
+        use crate::WechatClient;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "tok",
+                "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        let storage = server.uri();
+        Mock::given(method("POST"))
+            .and(path("/tcb/batchdownloadfile"))
+            .respond_with(move |request: &Request| {
+                let body: Value = serde_json::from_slice(&request.body).unwrap();
+                let file_list: Vec<Value> = body["file_list"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|entry| {
+                        let fileid = entry["fileid"].as_str().unwrap();
+                        if fileid.ends_with("gone") {
+                            serde_json::json!({"fileid": fileid, "status": -503003, "errmsg": "file not exist"})
+                        } else {
+                            serde_json::json!({
+                                "fileid": fileid,
+                                "status": 0,
+                                "download_url": format!("{storage}/objects/{}", fileid.rsplit('/').next().unwrap())
+                            })
+                        }
+                    })
+                    .collect();
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "errcode": 0,
+                    "errmsg": "ok",
+                    "file_list": file_list
+                }))
+            })
+            // Two chunks for the batch, one for the single download
+            .expect(3)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(wiremock::matchers::path_regex("^/objects/"))
+            .respond_with(|request: &Request| {
+                ResponseTemplate::new(200).set_body_string(request.url.path().to_string())
+            })
+            .mount(&server)
+            .await;
+
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(server.uri())
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(crate::token::TokenManager::new((*client).clone()));
+        let api = CloudApi::new(Arc::new(WechatContext::new(client, token_manager)));
+
+        let mut file_ids: Vec<String> = (0..51).map(|i| format!("cloud://prod/{i}")).collect();
+        file_ids.push("cloud://prod/gone".to_string());
+        let file_ids: Vec<&str> = file_ids.iter().map(String::as_str).collect();
+        let downloads = api.download_files("prod", &file_ids).await.unwrap();
+        assert_eq!(downloads.len(), 52);
+        assert_eq!(downloads[50].file_id, "cloud://prod/50");
+        assert_eq!(downloads[50].content.as_ref().unwrap(), "/objects/50");
+        assert!(matches!(
+            downloads[51].content,
+            Err(WechatError::Api { code: -503003, .. })
+        ));
+
+        assert_eq!(
+            api.download_file("prod", "cloud://prod/7").await.unwrap(),
+            "/objects/7"
+        );
+    }
+
+    #[tokio::test]
+    async fn download_file_stream_returns_before_body_arrives() {
+        use crate::types::{AppId, AppSecret};
+        use crate::WechatClient;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let storage = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let storage_addr = storage.local_addr().unwrap();
+        let (send_body, body_released) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let (mut socket, _) = storage.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n")
+                .await
+                .unwrap();
+            let _ = body_released.await;
+            socket.write_all(b"hello").await.unwrap();
+        });
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "tok",
+                "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/tcb/batchdownloadfile"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "file_list": [{
+                    "fileid": "cloud://prod/big",
+                    "status": 0,
+                    "download_url": format!("http://{storage_addr}/objects/big")
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        // Network timing reads other bodies before returning, but not this one
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(server.uri())
+                .network_timing(true)
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(crate::token::TokenManager::new((*client).clone()));
+        let api = CloudApi::new(Arc::new(WechatContext::new(client, token_manager)));
+
+        let response = tokio::time::timeout(
+            Duration::from_secs(5),
+            api.download_file_stream("prod", "cloud://prod/big"),
+        )
+        .await
+        .expect("headers arrive while the body is held back")
+        .unwrap();
+        assert_eq!(response.content_length(), Some(5));
+
+        send_body.send(()).unwrap();
+        assert_eq!(response.bytes().await.unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn storage_requests_skip_client_middleware() {
+        use crate::middleware::AuthMiddleware;
+        use crate::types::{AppId, AppSecret};
+        use crate::WechatClient;
+        use wiremock::matchers::{method, path, query_param_is_missing};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "tok",
+                "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/tcb/batchdownloadfile"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "file_list": [{
+                    "fileid": "cloud://prod/a.png",
+                    "download_url": format!("{}/objects/a.png", server.uri()),
+                    "status": 0,
+                    "errmsg": "ok"
+                }]
+            })))
+            .mount(&server)
+            .await;
+        // The signed storage URL must never receive the AppID's token
+        Mock::given(method("HEAD"))
+            .and(path("/objects/a.png"))
+            .and(query_param_is_missing("access_token"))
+            .respond_with(ResponseTemplate::new(200).insert_header("Content-Length", "2048"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/objects/a.png"))
+            .and(query_param_is_missing("access_token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("png"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/tcb/uploadfile"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "url": format!("{}/bucket", server.uri()),
+                "token": "security-token",
+                "authorization": "q-sign-algorithm=sha1",
+                "file_id": "cloud://prod.bucket/avatars/a.png",
+                "cos_file_id": "cos-id"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bucket"))
+            .and(query_param_is_missing("access_token"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let builder = || {
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(server.uri())
+        };
+        let token_manager = Arc::new(crate::token::TokenManager::new(builder().build().unwrap()));
+        let client = Arc::new(
+            builder()
+                .with_middleware(AuthMiddleware::new(Arc::clone(&token_manager)))
+                .build()
+                .unwrap(),
+        );
+        let api = CloudApi::new(Arc::new(WechatContext::new(client, token_manager)));
+
+        let files = api
+            .get_file_info("prod", &["cloud://prod/a.png"])
+            .await
+            .unwrap();
+        assert_eq!(files[0].size, Some(2048));
+        assert_eq!(
+            api.download_file("prod", "cloud://prod/a.png")
+                .await
+                .unwrap(),
+            "png"
+        );
+        api.upload_file("prod", "avatars/a.png", b"png")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn get_file_info_reads_object_headers() {
+        use crate::types::{AppId, AppSecret};
+        use crate::WechatClient;
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "tok",
+                "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/tcb/batchdownloadfile"))
+            .and(body_json(serde_json::json!({
+                "env": "prod",
+                "file_list": [
+                    {"fileid": "cloud://prod/a.png", "max_age": 60},
+                    {"fileid": "cloud://prod/gone.png", "max_age": 60}
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "file_list": [
+                    {
+                        "fileid": "cloud://prod/a.png",
+                        "download_url": format!("{}/objects/a.png", server.uri()),
+                        "status": 0,
+                        "errmsg": "ok"
+                    },
+                    {
+                        "fileid": "cloud://prod/gone.png",
+                        "download_url": "",
+                        "status": -503003,
+                        "errmsg": "file not exist"
+                    }
+                ]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/objects/a.png"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Length", "2048")
+                    .insert_header("Last-Modified", "Sun, 06 Nov 1994 08:49:37 GMT"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(server.uri())
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(crate::token::TokenManager::new((*client).clone()));
+        let api = CloudApi::new(Arc::new(WechatContext::new(client, token_manager)));
+
+        let files = api
+            .get_file_info("prod", &["cloud://prod/a.png", "cloud://prod/gone.png"])
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files[0].exists());
+        assert_eq!(files[0].size, Some(2048));
+        assert_eq!(files[0].created, Some(784_111_777));
+        assert!(!files[1].exists());
+        assert_eq!(files[1].size, None);
+    }
 }
@@ -0,0 +1,349 @@
+//! Cloud Base database queries
+//!
+//! The TCB database endpoints take a `query` string in the cloud database
+//! JavaScript syntax, e.g. `db.collection("books").where({"author":"x"}).get()`.
+//! [`Collection`] builds those strings: names and values are written as JSON
+//! literals, so user input cannot break out of the generated statement.
+//!
+//! ```rust,ignore
+//! let books = Collection::new("books")
+//!     .where_eq("author", "Lu Xun")
+//!     .order_by("price", SortOrder::Desc)
+//!     .skip(20)
+//!     .limit(10);
+//! let page = wechat.query_documents("prod-env", &books).await?;
+//! let rows: Vec<Book> = page.documents()?;
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::WechatError;
+
+/// Sort direction for [`Collection::order_by`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    fn as_str(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+}
+
+/// A collection with optional filters, sort order and paging
+///
+/// Filters added with [`where_eq`](Self::where_eq) are combined with AND.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collection {
+    name: String,
+    filter: Map<String, Value>,
+    order: Vec<(String, SortOrder)>,
+    skip: Option<u32>,
+    limit: Option<u32>,
+}
+
+impl Collection {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            filter: Map::new(),
+            order: Vec::new(),
+            skip: None,
+            limit: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Match documents whose `field` equals `value`; dotted paths such as
+    /// `address.city` address nested fields
+    pub fn where_eq(mut self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.filter.insert(field.into(), value.into());
+        self
+    }
+
+    /// Sort by `field`; later calls break ties of earlier ones
+    pub fn order_by(mut self, field: impl Into<String>, order: SortOrder) -> Self {
+        self.order.push((field.into(), order));
+        self
+    }
+
+    /// Skip the first `count` matches
+    pub fn skip(mut self, count: u32) -> Self {
+        self.skip = Some(count);
+        self
+    }
+
+    /// Return at most `count` documents; WeChat defaults to 10
+    pub fn limit(mut self, count: u32) -> Self {
+        self.limit = Some(count);
+        self
+    }
+
+    /// `db.collection(..).where(..).orderBy(..).skip(..).limit(..).get()`
+    pub fn query(&self) -> String {
+        let mut query = self.filtered();
+        for (field, order) in &self.order {
+            query.push_str(&format!(
+                ".orderBy({},\"{}\")",
+                literal(field),
+                order.as_str()
+            ));
+        }
+        if let Some(skip) = self.skip {
+            query.push_str(&format!(".skip({skip})"));
+        }
+        if let Some(limit) = self.limit {
+            query.push_str(&format!(".limit({limit})"));
+        }
+        query.push_str(".get()");
+        query
+    }
+
+    /// `db.collection(..).add({data: [..]})`; filters and paging are ignored
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Json`] when a document cannot be serialized.
+    pub fn add_query<T: Serialize>(&self, documents: &[T]) -> Result<String, WechatError> {
+        Ok(format!(
+            "db.collection({}).add({{data:{}}})",
+            literal(&self.name),
+            serde_json::to_string(documents)?
+        ))
+    }
+
+    /// `db.collection(..).where(..).update({data: ..})`
+    ///
+    /// # Errors
+    /// - [`WechatError::Validation`] without a [`where_eq`](Self::where_eq)
+    ///   filter, so a missing condition cannot rewrite the whole collection
+    /// - [`WechatError::Json`] when `data` cannot be serialized
+    pub fn update_query<T: Serialize>(&self, data: &T) -> Result<String, WechatError> {
+        self.require_filter("update")?;
+        Ok(format!(
+            "{}.update({{data:{}}})",
+            self.filtered(),
+            serde_json::to_string(data)?
+        ))
+    }
+
+    /// `db.collection(..).where(..).remove()`
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] without a [`where_eq`](Self::where_eq)
+    /// filter, so a missing condition cannot empty the collection.
+    pub fn remove_query(&self) -> Result<String, WechatError> {
+        self.require_filter("remove")?;
+        Ok(format!("{}.remove()", self.filtered()))
+    }
+
+    fn filtered(&self) -> String {
+        let mut query = format!("db.collection({})", literal(&self.name));
+        if !self.filter.is_empty() {
+            query.push_str(&format!(".where({})", Value::Object(self.filter.clone())));
+        }
+        query
+    }
+
+    fn require_filter(&self, operation: &str) -> Result<(), WechatError> {
+        if self.filter.is_empty() {
+            return Err(WechatError::validation(
+                "where",
+                format!(
+                    "refusing to {operation} every document of collection {}; add a where_eq filter",
+                    self.name
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A JSON string literal, which is also a valid JavaScript one
+fn literal(text: &str) -> String {
+    Value::String(text.to_string()).to_string()
+}
+
+/// Paging information of a query
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DatabasePager {
+    #[serde(default, rename = "Offset")]
+    pub offset: u64,
+    #[serde(default, rename = "Limit")]
+    pub limit: u64,
+    /// Number of matching documents, regardless of paging
+    #[serde(default, rename = "Total")]
+    pub total: u64,
+}
+
+impl DatabasePager {
+    /// Whether documents remain after this page
+    pub fn has_more(&self) -> bool {
+        self.offset + self.limit < self.total
+    }
+}
+
+/// Response of `/tcb/databasequery`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DatabaseQueryResponse {
+    #[serde(default)]
+    pub pager: DatabasePager,
+    /// Matching documents, each a JSON string
+    #[serde(default)]
+    pub data: Vec<String>,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+impl DatabaseQueryResponse {
+    /// Deserialize every document of [`data`](Self::data)
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Json`] for the first document that does not
+    /// match `T`.
+    pub fn documents<T: serde::de::DeserializeOwned>(&self) -> Result<Vec<T>, WechatError> {
+        self.data
+            .iter()
+            .map(|document| serde_json::from_str(document).map_err(WechatError::from))
+            .collect()
+    }
+}
+
+/// Response of `/tcb/databaseadd`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DatabaseAddResponse {
+    /// `_id`s of the new documents, in insertion order
+    #[serde(default)]
+    pub id_list: Vec<String>,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+/// Response of `/tcb/databaseupdate`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DatabaseUpdateResponse {
+    /// Documents matching the filter
+    #[serde(default)]
+    pub matched: u64,
+    /// Documents actually changed
+    #[serde(default)]
+    pub modified: u64,
+    /// `_id` of a document created by an upsert
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+/// Response of `/tcb/databasedelete`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DatabaseDeleteResponse {
+    #[serde(default)]
+    pub deleted: u64,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+/// One collection in [`DatabaseCollectionsResponse`]
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CollectionInfo {
+    #[serde(default)]
+    pub name: String,
+    /// Number of documents
+    #[serde(default)]
+    pub count: u64,
+    /// Size in bytes
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub index_count: u64,
+    /// Index size in bytes
+    #[serde(default)]
+    pub index_size: u64,
+}
+
+/// Response of `/tcb/databasecollectionget`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DatabaseCollectionsResponse {
+    #[serde(default)]
+    pub collections: Vec<CollectionInfo>,
+    #[serde(default)]
+    pub pager: DatabasePager,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collection_builds_escaped_queries() {
+        let books = Collection::new("books")
+            .where_eq("author", "a\"); db.collection(\"x\").remove(")
+            .where_eq("stock", 3)
+            .order_by("price", SortOrder::Desc)
+            .order_by("title", SortOrder::Asc)
+            .skip(20)
+            .limit(10);
+        assert_eq!(
+            books.query(),
+            r#"db.collection("books").where({"author":"a\"); db.collection(\"x\").remove(","stock":3}).orderBy("price","desc").orderBy("title","asc").skip(20).limit(10).get()"#
+        );
+        assert_eq!(
+            books
+                .update_query(&serde_json::json!({"stock": 2}))
+                .unwrap(),
+            r#"db.collection("books").where({"author":"a\"); db.collection(\"x\").remove(","stock":3}).update({data:{"stock":2}})"#
+        );
+        assert_eq!(
+            Collection::new("books")
+                .add_query(&[serde_json::json!({"title": "t"})])
+                .unwrap(),
+            r#"db.collection("books").add({data:[{"title":"t"}]})"#
+        );
+        assert!(matches!(
+            Collection::new("books").remove_query(),
+            Err(WechatError::Validation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_query_response_documents_and_pager() {
+        let json = r#"{"errcode":0,"errmsg":"ok","pager":{"Offset":0,"Limit":1,"Total":2},
+            "data":["{\"_id\":\"a\",\"title\":\"t\"}"]}"#;
+        let response: DatabaseQueryResponse = serde_json::from_str(json).unwrap();
+        assert!(response.pager.has_more());
+        let documents: Vec<Value> = response.documents().unwrap();
+        assert_eq!(documents[0]["title"], "t");
+    }
+}
@@ -11,6 +11,8 @@
 //! - [`ApiResponseBase`] struct for simple error-only responses
 //! - [`PaginatedRequest`] and [`PaginatedResponse`] for offset/count pagination
 //! - [`DateRangeRequest`] for analytics date range queries
+//! - Memoized typed accessors for `extra`-map responses (`list_as`, `field_as`, `i64`)
+//! - [`FIELD_ALIASES`], the alternate field names accepted when parsing
 //!
 //! ## Usage
 //!
@@ -23,9 +25,47 @@
 //! assert!(resp.check().is_ok());
 //! ```
 
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::error::WechatError;
+use crate::utils::{decode_json_value, lock};
+
+/// Alternate spellings of response fields, as `(documented, aliases)`
+///
+/// WeChat is inconsistent about some names across endpoints and doc
+/// revisions. Typed responses carrying one of these fields accept every
+/// alias through `#[serde(alias)]`, and lookups in `extra` maps fall back to
+/// them. Keep the two in sync: the tests check each typed field against
+/// this table.
+pub const FIELD_ALIASES: &[(&str, &[&str])] = &[
+    ("priTmplId", &["pri_tmpl_id", "priTmplID"]),
+    ("msgid", &["msg_id", "msgId"]),
+    ("trace_id", &["traceId"]),
+    ("media_id", &["mediaId"]),
+    ("created_at", &["createdAt"]),
+    ("category_id", &["categoryId"]),
+    ("cdnUrl", &["cdn_url"]),
+    ("pagePath", &["page_path"]),
+    ("posterUrl", &["poster_url"]),
+];
+
+/// Look up `key` with `get`, falling back to the other spellings listed
+/// for it in [`FIELD_ALIASES`]
+fn aliased<'a>(key: &str, get: impl Fn(&str) -> Option<&'a Value>) -> Option<&'a Value> {
+    get(key).or_else(|| {
+        FIELD_ALIASES
+            .iter()
+            .filter(|(name, aliases)| *name == key || aliases.contains(&key))
+            .flat_map(|(name, aliases)| std::iter::once(name).chain(aliases.iter()))
+            .find_map(|name| get(name))
+    })
+}
 
 /// Trait for WeChat API responses that carry `errcode` / `errmsg`.
 ///
@@ -99,6 +139,7 @@ pub trait WechatApiResponse {
 /// ```
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ApiResponseBase {
     /// Error code (`0` = success)
     #[serde(default)]
@@ -274,6 +315,86 @@ impl DateRangeRequest {
     }
 }
 
+/// Per-response cache of typed views over an `extra` field map
+///
+/// Each `(field, type)` pair is decoded at most once. Clones start empty.
+#[derive(Default)]
+pub(crate) struct FieldCache {
+    entries: Mutex<HashMap<(String, TypeId), Arc<dyn Any + Send + Sync>>>,
+}
+
+impl Clone for FieldCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::fmt::Debug for FieldCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FieldCache").finish_non_exhaustive()
+    }
+}
+
+impl FieldCache {
+    /// Decode `extra[key]` as `T`, memoizing the result.
+    pub(crate) fn field_as<T>(
+        &self,
+        extra: &HashMap<String, Value>,
+        key: &str,
+    ) -> Result<Arc<T>, WechatError>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        let cache_key = (key.to_string(), TypeId::of::<T>());
+        if let Some(hit) = self.lookup(&cache_key) {
+            return Ok(hit);
+        }
+
+        let value = aliased(key, |name| extra.get(name)).ok_or_else(|| WechatError::Decode {
+            path: key.to_string(),
+            snippet: String::new(),
+            message: format!("missing field `{}`", key),
+        })?;
+        let parsed: T = decode_json_value(value, value.to_string().as_bytes()).map_err(
+            |error| match error {
+                WechatError::Decode {
+                    path,
+                    snippet,
+                    message,
+                } => WechatError::Decode {
+                    path: match path.as_str() {
+                        "." => key.to_string(),
+                        p if p.starts_with('[') => format!("{}{}", key, p),
+                        p => format!("{}.{}", key, p),
+                    },
+                    snippet,
+                    message,
+                },
+                other => other,
+            },
+        )?;
+        let parsed = Arc::new(parsed);
+        lock(&self.entries).insert(cache_key, parsed.clone());
+        Ok(parsed)
+    }
+
+    fn lookup<T: Send + Sync + 'static>(&self, key: &(String, TypeId)) -> Option<Arc<T>> {
+        let entries = lock(&self.entries);
+        entries.get(key).cloned()?.downcast::<T>().ok()
+    }
+}
+
+/// Look up a scalar by name at the top level, falling back to the only
+/// entry of `list` (single-day datacube queries return one row).
+pub(crate) fn scalar<'a>(extra: &'a HashMap<String, Value>, key: &str) -> Option<&'a Value> {
+    aliased(key, |name| extra.get(name)).or_else(|| {
+        match extra.get("list")?.as_array()?.as_slice() {
+            [row] => aliased(key, |name| row.get(name)),
+            _ => None,
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -310,7 +431,7 @@ mod tests {
         assert!(!resp.is_success());
         let err = resp.check().unwrap_err();
         match err {
-            WechatError::Api { code, message } => {
+            WechatError::Api { code, message, .. } => {
                 assert_eq!(code, 40013);
                 assert_eq!(message, "invalid appid");
             }
@@ -475,11 +596,75 @@ mod tests {
         let resp = ApiResponseBase::error(-1, "system error");
         let err = resp.check().unwrap_err();
         match err {
-            WechatError::Api { code, message } => {
+            WechatError::Api { code, message, .. } => {
                 assert_eq!(code, -1);
                 assert_eq!(message, "system error");
             }
             _ => panic!("Expected WechatError::Api"),
         }
     }
+
+    /// Re-parse `sample` with each key listed in [`FIELD_ALIASES`] renamed to
+    /// every alias, and check the result is unchanged.
+    fn assert_accepts_aliases<T: DeserializeOwned + std::fmt::Debug>(sample: Value) {
+        let expected = format!("{:?}", serde_json::from_value::<T>(sample.clone()).unwrap());
+        let object = sample.as_object().unwrap();
+        let mut covered = 0;
+        for (name, aliases) in FIELD_ALIASES {
+            let Some(value) = object.get(*name) else {
+                continue;
+            };
+            covered += 1;
+            for alias in *aliases {
+                let mut renamed = object.clone();
+                renamed.remove(*name);
+                renamed.insert(alias.to_string(), value.clone());
+                let parsed: T = serde_json::from_value(Value::Object(renamed))
+                    .unwrap_or_else(|e| panic!("{name} as {alias}: {e}"));
+                assert_eq!(format!("{parsed:?}"), expected, "{name} as {alias}");
+            }
+        }
+        assert!(covered > 0, "sample has no aliased field");
+    }
+
+    #[test]
+    fn test_typed_responses_accept_field_aliases() {
+        use crate::api::live::LiveSharedCodeResponse;
+        use crate::api::media::MediaUploadResponse;
+        use crate::api::security::MediaCheckAsyncResponse;
+        use crate::api::subscribe::{AddTemplateResponse, PubTemplateTitleInfo, TemplateInfo};
+
+        assert_accepts_aliases::<TemplateInfo>(serde_json::json!({
+            "priTmplId": "tpl", "title": "t", "content": "c", "type": 2
+        }));
+        assert_accepts_aliases::<AddTemplateResponse>(serde_json::json!({ "priTmplId": "tpl" }));
+        assert_accepts_aliases::<PubTemplateTitleInfo>(serde_json::json!({
+            "tid": "1", "title": "t", "type": 2, "category_id": 7
+        }));
+        assert_accepts_aliases::<LiveSharedCodeResponse>(serde_json::json!({
+            "cdnUrl": "https://a/b.png", "pagePath": "pages/live", "posterUrl": "https://a/c.png"
+        }));
+        assert_accepts_aliases::<MediaUploadResponse>(serde_json::json!({
+            "type": "image", "media_id": "m", "created_at": 1
+        }));
+        assert_accepts_aliases::<MediaCheckAsyncResponse>(serde_json::json!({ "trace_id": "t" }));
+    }
+
+    #[test]
+    fn test_extra_lookups_fall_back_to_aliases() {
+        let extra: HashMap<String, Value> = serde_json::from_value(serde_json::json!({
+            "msg_id": 42,
+            "list": [{ "categoryId": 7 }]
+        }))
+        .unwrap();
+
+        let cache = FieldCache::default();
+        assert_eq!(*cache.field_as::<i64>(&extra, "msgid").unwrap(), 42);
+        assert_eq!(scalar(&extra, "msgId").and_then(Value::as_i64), Some(42));
+        assert_eq!(
+            scalar(&extra, "category_id").and_then(Value::as_i64),
+            Some(7)
+        );
+        assert!(scalar(&extra, "trace_id").is_none());
+    }
 }
@@ -26,14 +26,29 @@
 //! };
 //! api.send("user_openid", message).await?;
 //! ```
+//!
+//! # Ordered Delivery
+//!
+//! WeChat may reorder messages sent to the same user concurrently. Attach a
+//! shared [`SendQueue`] to deliver messages per OpenID in call order:
+//!
+//! ```rust,ignore
+//! use wechat_mp_sdk::api::customer_service::{CustomerServiceApi, OverflowPolicy, SendQueue};
+//!
+//! let queue = SendQueue::new(16, OverflowPolicy::Reject);
+//! let api = CustomerServiceApi::new(context).with_send_queue(queue.clone());
+//! ```
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use serde::{Deserialize, Serialize};
 
+use super::media::{MediaApi, MediaHandleCache, MediaType, INVALID_MEDIA_ID};
 use super::{WechatApi, WechatContext};
 use crate::error::WechatError;
 use crate::types::AppId;
+use crate::utils::lock;
 
 // ============================================================================
 // Message Types
@@ -41,6 +56,7 @@ use crate::types::AppId;
 
 /// Message types for customer service messages
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(tag = "msgtype", rename_all = "lowercase")]
 pub enum Message {
     /// Text message
@@ -58,6 +74,7 @@ pub enum Message {
 
 /// Text message content
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TextMessage {
     /// Message content
     pub content: String,
@@ -74,6 +91,7 @@ impl TextMessage {
 
 /// Media message (image) content
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MediaMessage {
     /// Media ID from upload API
     pub media_id: String,
@@ -90,6 +108,7 @@ impl MediaMessage {
 
 /// Link message content
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LinkMessage {
     /// Link title
     pub title: String,
@@ -120,12 +139,14 @@ impl LinkMessage {
 
 /// Mini Program page message content
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MiniProgramPageMessage {
     /// Page title
     pub title: String,
     /// Mini Program AppID (can be different from current Mini Program)
     pub appid: AppId,
-    /// Page path
+    /// Page path; may carry a query, see
+    /// [`MiniProgramPath`](crate::types::MiniProgramPath)
     pub pagepath: String,
     /// Thumbnail media ID
     pub thumb_media_id: String,
@@ -154,14 +175,16 @@ impl MiniProgramPageMessage {
 
 /// Request for sending customer service message
 #[derive(Debug, Clone, Serialize)]
-struct CustomerServiceMessageRequest {
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct CustomerServiceMessageRequest<'a> {
     #[serde(rename = "touser")]
-    touser: String,
+    touser: &'a str,
     #[serde(flatten)]
     msgtype: Message,
 }
 
 #[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct CustomerServiceMessageResponse {
     #[serde(default)]
     errcode: i32,
@@ -171,17 +194,131 @@ struct CustomerServiceMessageResponse {
 
 /// Typing command for customer service
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum TypingCommand {
     Typing,
     CancelTyping,
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct SetTypingRequest {
-    touser: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct SetTypingRequest<'a> {
+    touser: &'a str,
     command: TypingCommand,
 }
 
+// ============================================================================
+// Send Queue
+// ============================================================================
+
+/// What a [`SendQueue`] does when a user's queue is at capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait until a slot frees up
+    Wait,
+    /// Fail immediately with [`WechatError::QueueFull`]
+    Reject,
+}
+
+/// Bounded per-OpenID FIFO queue for customer service sends
+///
+/// Messages to the same OpenID are sent one at a time in the order `send` was
+/// called; different users proceed concurrently. Cloning shares the queue.
+#[derive(Debug, Clone)]
+pub struct SendQueue {
+    inner: Arc<SendQueueInner>,
+}
+
+#[derive(Debug)]
+struct SendQueueInner {
+    capacity: usize,
+    overflow: OverflowPolicy,
+    lanes: Mutex<HashMap<String, Arc<Lane>>>,
+}
+
+/// Per-OpenID state: `slots` bounds queued messages, `order` serializes sends.
+/// Both tokio primitives are fair, so waiters proceed in arrival order.
+#[derive(Debug)]
+struct Lane {
+    slots: tokio::sync::Semaphore,
+    order: tokio::sync::Mutex<()>,
+}
+
+impl SendQueue {
+    /// Create a queue holding at most `capacity` pending messages per OpenID
+    ///
+    /// A `capacity` of 0 is treated as 1.
+    pub fn new(capacity: usize, overflow: OverflowPolicy) -> Self {
+        Self {
+            inner: Arc::new(SendQueueInner {
+                capacity: capacity.max(1),
+                overflow,
+                lanes: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Maximum pending messages per OpenID
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+
+    /// Overflow policy applied when a user's queue is full
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.inner.overflow
+    }
+
+    /// Number of OpenIDs with queued or in-flight messages
+    pub fn active_users(&self) -> usize {
+        lock(&self.inner.lanes).len()
+    }
+
+    /// Run `send` after all earlier sends to `openid` have completed
+    async fn run<F, T>(&self, openid: &str, send: F) -> Result<T, WechatError>
+    where
+        F: std::future::Future<Output = Result<T, WechatError>>,
+    {
+        let lane = lock(&self.inner.lanes)
+            .entry(openid.to_string())
+            .or_insert_with(|| {
+                Arc::new(Lane {
+                    slots: tokio::sync::Semaphore::new(self.inner.capacity),
+                    order: tokio::sync::Mutex::new(()),
+                })
+            })
+            .clone();
+
+        let result = self.run_in_lane(&lane, openid, send).await;
+
+        drop(lane);
+        let mut lanes = lock(&self.inner.lanes);
+        if lanes
+            .get(openid)
+            .is_some_and(|lane| Arc::strong_count(lane) == 1)
+        {
+            lanes.remove(openid);
+        }
+
+        result
+    }
+
+    async fn run_in_lane<F, T>(&self, lane: &Lane, openid: &str, send: F) -> Result<T, WechatError>
+    where
+        F: std::future::Future<Output = Result<T, WechatError>>,
+    {
+        let queue_full = || WechatError::QueueFull {
+            openid: openid.to_string(),
+            capacity: self.inner.capacity,
+        };
+        let _slot = match self.inner.overflow {
+            OverflowPolicy::Reject => lane.slots.try_acquire().map_err(|_| queue_full())?,
+            OverflowPolicy::Wait => lane.slots.acquire().await.map_err(|_| queue_full())?,
+        };
+        let _turn = lane.order.lock().await;
+        send.await
+    }
+}
+
 // ============================================================================
 // CustomerServiceApi
 // ============================================================================
@@ -191,12 +328,25 @@ struct SetTypingRequest {
 /// Provides methods for sending customer service messages to users.
 pub struct CustomerServiceApi {
     context: Arc<WechatContext>,
+    send_queue: Option<SendQueue>,
 }
 
 impl CustomerServiceApi {
     /// Create a new CustomerServiceApi instance
     pub fn new(context: Arc<WechatContext>) -> Self {
-        Self { context }
+        Self {
+            context,
+            send_queue: None,
+        }
+    }
+
+    /// Route [`send`](Self::send) through a per-OpenID FIFO queue
+    ///
+    /// Share the same queue across API instances to keep ordering across
+    /// concurrent handlers.
+    pub fn with_send_queue(mut self, queue: SendQueue) -> Self {
+        self.send_queue = Some(queue);
+        self
     }
 
     /// Send customer service message
@@ -207,6 +357,9 @@ impl CustomerServiceApi {
     /// * `touser` - Recipient's OpenID
     /// * `message` - Message to send
     ///
+    /// With a [`SendQueue`] attached, waits for earlier messages to the same
+    /// user and may fail with [`WechatError::QueueFull`].
+    ///
     /// # Example
     ///
     /// ```rust,ignore
@@ -217,8 +370,51 @@ impl CustomerServiceApi {
     /// api.send("user_openid", message).await?;
     /// ```
     pub async fn send(&self, touser: &str, message: Message) -> Result<(), WechatError> {
+        match &self.send_queue {
+            Some(queue) => queue.run(touser, self.send_now(touser, message)).await,
+            None => self.send_now(touser, message).await,
+        }
+    }
+
+    /// Send an image, reusing an earlier upload of the same bytes
+    ///
+    /// The image is uploaded through `cache` (see
+    /// [`MediaApi::upload_temp_media_cached`]). If WeChat rejects a cached
+    /// `media_id` as expired, it is dropped from the cache and the image is
+    /// uploaded again once.
+    pub async fn send_image_cached(
+        &self,
+        touser: &str,
+        cache: &MediaHandleCache,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<(), WechatError> {
+        let media = MediaApi::new(Arc::clone(&self.context));
+        let media_id = media
+            .upload_temp_media_cached(cache, MediaType::Image, filename, data)
+            .await?;
+        let image = |media_id| Message::Image {
+            image: MediaMessage::new(media_id),
+        };
+
+        match self.send(touser, image(media_id.clone())).await {
+            Err(WechatError::Api {
+                code: INVALID_MEDIA_ID,
+                ..
+            }) => {
+                cache.remove(&media_id);
+                let media_id = media
+                    .upload_temp_media_cached(cache, MediaType::Image, filename, data)
+                    .await?;
+                self.send(touser, image(media_id)).await
+            }
+            result => result,
+        }
+    }
+
+    async fn send_now(&self, touser: &str, message: Message) -> Result<(), WechatError> {
         let request = CustomerServiceMessageRequest {
-            touser: touser.to_string(),
+            touser,
             msgtype: message,
         };
 
@@ -240,10 +436,7 @@ impl CustomerServiceApi {
         touser: &str,
         command: TypingCommand,
     ) -> Result<(), WechatError> {
-        let request = SetTypingRequest {
-            touser: touser.to_string(),
-            command,
-        };
+        let request = SetTypingRequest { touser, command };
         let response: CustomerServiceMessageResponse = self
             .context
             .authed_post("/cgi-bin/message/custom/typing", &request)
@@ -267,6 +460,16 @@ impl WechatApi for CustomerServiceApi {
 // Tests
 // ============================================================================
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<CustomerServiceMessageRequest<'static>, CustomerServiceMessageResponse>(
+        "customerService.sendCustomMessage",
+    );
+    schemas.add::<SetTypingRequest<'static>, CustomerServiceMessageResponse>(
+        "customerService.setTyping",
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -419,6 +622,74 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_send_image_cached_reuses_upload_and_recovers_from_expiry() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        let uploads = Arc::new(AtomicU32::new(0));
+        let upload_count = Arc::clone(&uploads);
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/media/upload"))
+            .respond_with(move |_: &wiremock::Request| {
+                let n = upload_count.fetch_add(1, Ordering::SeqCst);
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "type": "image",
+                    "media_id": format!("media_{n}"),
+                    "created_at": 1700000000
+                }))
+            })
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sent_log = Arc::clone(&sent);
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/custom/send"))
+            .respond_with(move |request: &wiremock::Request| {
+                let body: serde_json::Value = request.body_json().unwrap();
+                let mut sent = sent_log.lock().unwrap();
+                sent.push(body["image"]["media_id"].as_str().unwrap().to_string());
+                // The third send finds the cached media expired.
+                let errcode = if sent.len() == 3 { INVALID_MEDIA_ID } else { 0 };
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"errcode": errcode, "errmsg": "ok"}))
+            })
+            .mount(&mock_server)
+            .await;
+
+        let api = CustomerServiceApi::new(create_test_context(&mock_server.uri()));
+        let cache = MediaHandleCache::new();
+        for user in ["user_a", "user_b", "user_c"] {
+            api.send_image_cached(user, &cache, "reply.png", b"same picture")
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(uploads.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            *sent.lock().unwrap(),
+            ["media_0", "media_0", "media_0", "media_1"]
+        );
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache.get(MediaType::Image, b"same picture").as_deref(),
+            Some("media_1")
+        );
+        assert_eq!(cache.get(MediaType::Voice, b"same picture"), None);
+    }
+
     #[tokio::test]
     async fn test_send_message_api_error() {
         use wiremock::matchers::{method, path};
@@ -455,11 +726,122 @@ mod tests {
         let result = api.send("test_openid", message).await;
 
         assert!(result.is_err());
-        if let Err(WechatError::Api { code, message }) = result {
+        if let Err(WechatError::Api { code, message, .. }) = result {
             assert_eq!(code, 40001);
             assert_eq!(message, "invalid credential");
         } else {
             panic!("Expected WechatError::Api");
         }
     }
+
+    async fn mount_token_and_send(mock_server: &wiremock::MockServer, delay_ms: u64) {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/custom/send"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"errcode": 0, "errmsg": "ok"}))
+                    .set_delay(std::time::Duration::from_millis(delay_ms)),
+            )
+            .mount(mock_server)
+            .await;
+    }
+
+    fn text(content: &str) -> Message {
+        Message::Text {
+            text: TextMessage::new(content),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_queue_preserves_order_per_user() {
+        let mock_server = wiremock::MockServer::start().await;
+        mount_token_and_send(&mock_server, 50).await;
+
+        let queue = SendQueue::new(8, OverflowPolicy::Wait);
+        let api = CustomerServiceApi::new(create_test_context(&mock_server.uri()))
+            .with_send_queue(queue.clone());
+
+        let (first, second, third) = tokio::join!(
+            api.send("user_a", text("1")),
+            api.send("user_a", text("2")),
+            api.send("user_a", text("3")),
+        );
+        assert!(first.is_ok() && second.is_ok() && third.is_ok());
+
+        let contents: Vec<String> = mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|request| request.url.path() == "/cgi-bin/message/custom/send")
+            .map(|request| {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                body["text"]["content"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(contents, vec!["1", "2", "3"]);
+        assert_eq!(queue.active_users(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_send_queue_rejects_on_overflow() {
+        let mock_server = wiremock::MockServer::start().await;
+        mount_token_and_send(&mock_server, 50).await;
+
+        let queue = SendQueue::new(1, OverflowPolicy::Reject);
+        let api =
+            CustomerServiceApi::new(create_test_context(&mock_server.uri())).with_send_queue(queue);
+
+        let (first, second, other_user) = tokio::join!(
+            api.send("user_a", text("1")),
+            api.send("user_a", text("2")),
+            api.send("user_b", text("3")),
+        );
+        assert!(first.is_ok());
+        assert!(matches!(
+            second,
+            Err(WechatError::QueueFull { ref openid, capacity: 1 }) if openid == "user_a"
+        ));
+        assert!(other_user.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_queue_cancelled_waiter_releases_slot() {
+        use crate::utils::run_cancellable;
+        use tokio_util::sync::CancellationToken;
+
+        let mock_server = wiremock::MockServer::start().await;
+        mount_token_and_send(&mock_server, 200).await;
+
+        let queue = SendQueue::new(2, OverflowPolicy::Reject);
+        let api = CustomerServiceApi::new(create_test_context(&mock_server.uri()))
+            .with_send_queue(queue.clone());
+
+        let token = CancellationToken::new();
+        let trigger = token.clone();
+        let (first, cancelled, ()) = tokio::join!(
+            api.send("user_a", text("1")),
+            run_cancellable(Some(&token), api.send("user_a", text("2"))),
+            async move {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                trigger.cancel();
+            },
+        );
+        assert!(first.is_ok());
+        assert!(matches!(cancelled, Err(WechatError::Cancelled)));
+        assert_eq!(queue.active_users(), 0);
+        assert!(api.send("user_a", text("3")).await.is_ok());
+    }
 }
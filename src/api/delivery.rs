@@ -1,23 +1,45 @@
 //! Instant Delivery API
+//!
+//! Besides the generic [`DeliveryRequest`] methods, the order lifecycle has
+//! typed calls: [`add_delivery_order`](DeliveryApi::add_delivery_order),
+//! [`re_add_delivery_order`](DeliveryApi::re_add_delivery_order),
+//! [`get_delivery_order`](DeliveryApi::get_delivery_order),
+//! [`cancel_delivery_order`](DeliveryApi::cancel_delivery_order),
+//! [`confirm_delivery_return`](DeliveryApi::confirm_delivery_return) and, in
+//! the sandbox, [`mock_update_delivery_order`](DeliveryApi::mock_update_delivery_order).
+//! They take the [`DeliveryMerchant`] credentials issued by the delivery
+//! company and fill in `shopid` and `delivery_sign` themselves.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::callback::crypto::delivery_signature;
+
+use super::outbox::{self, OrderCreation, OrderOutbox};
 use super::{WechatApi, WechatContext};
 use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DeliveryRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
 }
 
+impl DeliveryRequest {
+    pub fn new(payload: HashMap<String, Value>) -> Self {
+        Self { payload }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DeliveryResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -27,6 +49,601 @@ pub struct DeliveryResponse {
     pub extra: HashMap<String, Value>,
 }
 
+impl DeliveryResponse {
+    /// `order_status` field of an order query result or status callback.
+    pub fn order_status(&self) -> Option<i32> {
+        self.extra
+            .get("order_status")
+            .and_then(Value::as_i64)
+            .and_then(|status| i32::try_from(status).ok())
+    }
+
+    /// `action_time` (Unix seconds) of a status callback, if present.
+    pub fn action_time(&self) -> Option<i64> {
+        self.extra.get("action_time").and_then(Value::as_i64)
+    }
+}
+
+/// Lifecycle stage of an instant delivery order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeliveryOrderState {
+    /// Order placed, waiting for a rider
+    Created,
+    /// A rider accepted the order (and may be at the store)
+    RiderAssigned,
+    /// Goods picked up and on the way
+    PickedUp,
+    Delivered,
+    Cancelled,
+}
+
+impl DeliveryOrderState {
+    /// Map a WeChat `order_status` code to its lifecycle stage.
+    ///
+    /// - 101 waiting for rider, 102 rider assigned, 103 cancelled by merchant
+    /// - 201 rider at store, 202 picked up, 203 cancelled by merchant
+    /// - 301 in delivery, 302 delivered, 303 cancelled by merchant
+    ///
+    /// Returns `None` for codes this SDK does not know.
+    pub fn from_status(status: i32) -> Option<Self> {
+        match status {
+            101 => Some(DeliveryOrderState::Created),
+            102 | 201 => Some(DeliveryOrderState::RiderAssigned),
+            202 | 301 => Some(DeliveryOrderState::PickedUp),
+            302 => Some(DeliveryOrderState::Delivered),
+            103 | 203 | 303 => Some(DeliveryOrderState::Cancelled),
+            _ => None,
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            DeliveryOrderState::Delivered | DeliveryOrderState::Cancelled
+        )
+    }
+
+    /// Whether an order may move from `self` to `next`.
+    ///
+    /// Forward moves may skip stages because polls and callbacks can be
+    /// missed. The only backward move is `RiderAssigned -> Created`, when a
+    /// rider drops the order and it is dispatched again. Terminal states are
+    /// final.
+    pub fn can_transition_to(&self, next: DeliveryOrderState) -> bool {
+        use DeliveryOrderState::*;
+        match (*self, next) {
+            (from, _) if from.is_terminal() => false,
+            (_, Cancelled) => true,
+            (RiderAssigned, Created) => true,
+            (from, to) => to > from,
+        }
+    }
+}
+
+/// A state change recorded by [`DeliveryOrderTracker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeliveryTransition {
+    pub from: DeliveryOrderState,
+    pub to: DeliveryOrderState,
+    /// Raw `order_status` code that caused the change
+    pub status: i32,
+    /// Unix seconds
+    pub timestamp: i64,
+}
+
+/// Typed state machine for one instant delivery order
+///
+/// Feed it `order_status` values from status callbacks or order queries.
+/// Repeated statuses and updates older than the last applied one are
+/// ignored, so redelivered or out-of-order callbacks are harmless; illegal
+/// moves (e.g. `Delivered -> PickedUp`) and unknown codes are rejected.
+#[derive(Debug, Clone)]
+pub struct DeliveryOrderTracker {
+    order_id: String,
+    state: DeliveryOrderState,
+    status: i32,
+    updated_at: i64,
+    history: Vec<DeliveryTransition>,
+}
+
+impl DeliveryOrderTracker {
+    /// Start tracking a freshly created order (status 101).
+    pub fn new(order_id: impl Into<String>, created_at: i64) -> Self {
+        Self {
+            order_id: order_id.into(),
+            state: DeliveryOrderState::Created,
+            status: 101,
+            updated_at: created_at,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn order_id(&self) -> &str {
+        &self.order_id
+    }
+
+    pub fn state(&self) -> DeliveryOrderState {
+        self.state
+    }
+
+    /// Raw `order_status` of the last applied update
+    pub fn status(&self) -> i32 {
+        self.status
+    }
+
+    /// Unix seconds of the last applied update
+    pub fn updated_at(&self) -> i64 {
+        self.updated_at
+    }
+
+    pub fn history(&self) -> &[DeliveryTransition] {
+        &self.history
+    }
+
+    /// Apply a status update observed at `timestamp` (Unix seconds).
+    ///
+    /// Returns the transition if the state changed, `None` if the update was
+    /// a duplicate or stale.
+    pub fn apply(
+        &mut self,
+        status: i32,
+        timestamp: i64,
+    ) -> Result<Option<DeliveryTransition>, WechatError> {
+        let next = DeliveryOrderState::from_status(status).ok_or_else(|| {
+            WechatError::Config(format!(
+                "delivery order {}: unknown order_status {}",
+                self.order_id, status
+            ))
+        })?;
+
+        if timestamp < self.updated_at {
+            return Ok(None);
+        }
+        if next == self.state {
+            self.status = status;
+            self.updated_at = timestamp;
+            return Ok(None);
+        }
+        if !self.state.can_transition_to(next) {
+            return Err(WechatError::Config(format!(
+                "delivery order {}: illegal transition {:?} -> {:?} (order_status {})",
+                self.order_id, self.state, next, status
+            )));
+        }
+
+        let transition = DeliveryTransition {
+            from: self.state,
+            to: next,
+            status,
+            timestamp,
+        };
+        self.state = next;
+        self.status = status;
+        self.updated_at = timestamp;
+        self.history.push(transition);
+        Ok(Some(transition))
+    }
+
+    /// Apply an order query result or status callback.
+    ///
+    /// Uses the payload's `action_time` when present, else `observed_at`.
+    pub fn apply_response(
+        &mut self,
+        response: &DeliveryResponse,
+        observed_at: i64,
+    ) -> Result<Option<DeliveryTransition>, WechatError> {
+        let status = response.order_status().ok_or_else(|| {
+            WechatError::Config(format!(
+                "delivery order {}: response has no order_status",
+                self.order_id
+            ))
+        })?;
+        self.apply(status, response.action_time().unwrap_or(observed_at))
+    }
+}
+
+/// Merchant account with a delivery company
+///
+/// `shopid` and `app_secret` are issued by the delivery company when the
+/// account is bound; they are not the mini program's AppID and AppSecret.
+#[derive(Clone)]
+pub struct DeliveryMerchant {
+    shopid: String,
+    app_secret: String,
+}
+
+impl fmt::Debug for DeliveryMerchant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeliveryMerchant")
+            .field("shopid", &self.shopid)
+            .field("app_secret", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl DeliveryMerchant {
+    pub fn new(shopid: impl Into<String>, app_secret: impl Into<String>) -> Self {
+        Self {
+            shopid: shopid.into(),
+            app_secret: app_secret.into(),
+        }
+    }
+
+    pub fn shopid(&self) -> &str {
+        &self.shopid
+    }
+
+    /// `delivery_sign` for `shop_order_id`, see [`delivery_signature`]
+    pub fn delivery_sign(&self, shop_order_id: &str) -> String {
+        delivery_signature(&self.shopid, shop_order_id, &self.app_secret)
+    }
+}
+
+/// Typed request bodies signed with a [`DeliveryMerchant`]
+trait ShopOrder: Serialize {
+    fn shop_order_id(&self) -> &str;
+}
+
+/// Body with `shopid` and `delivery_sign` added to a typed request
+#[derive(Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(rename = "Signed{T}"))]
+struct SignedRequest<'a, T> {
+    shopid: &'a str,
+    delivery_sign: String,
+    #[serde(flatten)]
+    request: &'a T,
+}
+
+impl<'a, T: ShopOrder> SignedRequest<'a, T> {
+    fn new(merchant: &'a DeliveryMerchant, request: &'a T) -> Self {
+        Self {
+            shopid: merchant.shopid(),
+            delivery_sign: merchant.delivery_sign(request.shop_order_id()),
+            request,
+        }
+    }
+}
+
+/// Pickup or drop-off party of a delivery order
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DeliveryContact {
+    pub name: String,
+    pub city: String,
+    pub address: String,
+    /// Floor, room and other details below `address`
+    pub address_detail: String,
+    pub phone: String,
+    pub lng: f64,
+    pub lat: f64,
+    /// 0 for Mars (GCJ-02) coordinates, 1 for Baidu
+    pub coordinate_type: i32,
+}
+
+/// One item in [`DeliveryCargo::goods_detail`]
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DeliveryGoods {
+    pub good_count: u32,
+    pub good_name: String,
+    /// Unit price in yuan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub good_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub good_unit: Option<String>,
+}
+
+/// List wrapper WeChat expects around [`DeliveryGoods`]
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DeliveryGoodsDetail {
+    pub goods: Vec<DeliveryGoods>,
+}
+
+/// Goods to deliver
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DeliveryCargo {
+    /// Value in yuan
+    pub goods_value: f64,
+    /// Height in cm
+    pub goods_height: f64,
+    /// Length in cm
+    pub goods_length: f64,
+    /// Width in cm
+    pub goods_width: f64,
+    /// Weight in kg
+    pub goods_weight: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_detail: Option<DeliveryGoodsDetail>,
+    /// Instructions for the rider at pickup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_pickup_info: Option<String>,
+    /// Instructions for the rider at drop-off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_delivery_info: Option<String>,
+    /// Category codes defined by WeChat, e.g. `美食` / `快餐`
+    pub cargo_first_class: String,
+    pub cargo_second_class: String,
+}
+
+/// Scheduling and payment options of a delivery order
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DeliveryOrderInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivery_service_code: Option<String>,
+    /// 0 deliver now, 1 scheduled
+    pub order_type: i32,
+    /// Unix seconds, for scheduled orders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_delivery_time: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_finish_time: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_pick_time: Option<i64>,
+    /// Pickup sequence number shown to the rider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poi_seq: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Time the user placed the order, Unix seconds
+    pub order_time: i64,
+    /// 1 to insure the goods
+    pub is_insured: i32,
+    /// Insured value in yuan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared_value: Option<f64>,
+    /// Tip for the rider in yuan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tips: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_direct_delivery: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_finish_code_needed: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_pickup_code_needed: Option<i32>,
+}
+
+/// Goods shown in the delivery notification
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DeliveryShop {
+    /// Mini program page opened from the notification
+    pub wxa_path: String,
+    pub img_url: String,
+    pub goods_name: String,
+    pub goods_count: u32,
+}
+
+/// Body of `order/add` and `order/readd`, without `shopid` and
+/// `delivery_sign`
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AddDeliveryOrderRequest {
+    /// Token returned by `pre_add_order`, if the order was quoted first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivery_token: Option<String>,
+    /// Shop order id, unique per `shopid`
+    pub shop_order_id: String,
+    /// Store number with the delivery company
+    pub shop_no: String,
+    /// Delivery company id, from `get_all_imme_delivery`
+    pub delivery_id: String,
+    /// Buyer's openid
+    pub openid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_biz_id: Option<String>,
+    pub sender: DeliveryContact,
+    pub receiver: DeliveryContact,
+    pub cargo: DeliveryCargo,
+    pub order_info: DeliveryOrderInfo,
+    pub shop: DeliveryShop,
+}
+
+impl ShopOrder for AddDeliveryOrderRequest {
+    fn shop_order_id(&self) -> &str {
+        &self.shop_order_id
+    }
+}
+
+/// Identifies an existing delivery order for `order/get`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GetDeliveryOrderRequest {
+    pub shop_order_id: String,
+    pub shop_no: String,
+}
+
+impl ShopOrder for GetDeliveryOrderRequest {
+    fn shop_order_id(&self) -> &str {
+        &self.shop_order_id
+    }
+}
+
+/// Body of `order/cancel`, without `shopid` and `delivery_sign`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CancelDeliveryOrderRequest {
+    pub shop_order_id: String,
+    pub shop_no: String,
+    pub delivery_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waybill_id: Option<String>,
+    /// 1 no longer needed, 2 goods unavailable, 3 rider timeout, 4 shop
+    /// error, 5 other (explain in `cancel_reason`)
+    pub cancel_reason_id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_reason: Option<String>,
+}
+
+impl ShopOrder for CancelDeliveryOrderRequest {
+    fn shop_order_id(&self) -> &str {
+        &self.shop_order_id
+    }
+}
+
+/// Body of `order/confirm_return`: confirms goods returned to the store
+/// after a delivery failed
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ConfirmReturnRequest {
+    pub shop_order_id: String,
+    pub shop_no: String,
+    pub waybill_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remark: Option<String>,
+}
+
+impl ShopOrder for ConfirmReturnRequest {
+    fn shop_order_id(&self) -> &str {
+        &self.shop_order_id
+    }
+}
+
+/// Body of the sandbox `test_update_order`, which moves a test order to
+/// `order_status` as if the delivery company had reported it
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MockUpdateOrderRequest {
+    /// `test_shop_id` in the sandbox
+    pub shopid: String,
+    pub shop_order_id: String,
+    /// Unix seconds
+    pub action_time: i64,
+    /// See [`DeliveryOrderState::from_status`]
+    pub order_status: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action_msg: Option<String>,
+}
+
+/// Outcome reported by the delivery company
+///
+/// WeChat answers with `errcode` 0 even when the delivery company rejects a
+/// request; `resultcode` carries the company's verdict.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DeliveryResult {
+    #[serde(default)]
+    pub resultcode: i32,
+    #[serde(default)]
+    pub resultmsg: String,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+impl DeliveryResult {
+    /// Returns true when the delivery company accepted the request.
+    pub fn is_ok(&self) -> bool {
+        self.resultcode == 0
+    }
+}
+
+impl AsRef<DeliveryResult> for DeliveryResult {
+    fn as_ref(&self) -> &DeliveryResult {
+        self
+    }
+}
+
+/// Response of `order/add` and `order/readd`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AddDeliveryOrderResponse {
+    #[serde(flatten)]
+    pub result: DeliveryResult,
+    /// Amount charged in yuan
+    #[serde(default)]
+    pub fee: f64,
+    /// Fee before coupons, in yuan
+    #[serde(default)]
+    pub deliverfee: f64,
+    #[serde(default)]
+    pub couponfee: f64,
+    #[serde(default)]
+    pub tips: f64,
+    #[serde(default)]
+    pub insurancefee: f64,
+    /// Distance in meters
+    #[serde(default)]
+    pub distance: f64,
+    #[serde(default)]
+    pub waybill_id: String,
+    #[serde(default)]
+    pub order_status: i32,
+    /// Code the receiver gives the rider, when requested
+    #[serde(default)]
+    pub finish_code: Option<i64>,
+    #[serde(default)]
+    pub pickup_code: Option<i64>,
+    /// Expected seconds until a rider accepts
+    #[serde(default)]
+    pub dispatch_duration: Option<i64>,
+}
+
+impl AsRef<DeliveryResult> for AddDeliveryOrderResponse {
+    fn as_ref(&self) -> &DeliveryResult {
+        &self.result
+    }
+}
+
+/// Response of `order/get`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GetDeliveryOrderResponse {
+    #[serde(flatten)]
+    pub result: DeliveryResult,
+    #[serde(default)]
+    pub order_status: i32,
+    #[serde(default)]
+    pub waybill_id: String,
+    #[serde(default)]
+    pub rider_name: String,
+    #[serde(default)]
+    pub rider_phone: String,
+    #[serde(default)]
+    pub rider_lng: f64,
+    #[serde(default)]
+    pub rider_lat: f64,
+    /// Expected arrival, Unix seconds
+    #[serde(default)]
+    pub reach_time: Option<i64>,
+}
+
+impl AsRef<DeliveryResult> for GetDeliveryOrderResponse {
+    fn as_ref(&self) -> &DeliveryResult {
+        &self.result
+    }
+}
+
+impl GetDeliveryOrderResponse {
+    /// Lifecycle stage of [`order_status`](Self::order_status)
+    pub fn state(&self) -> Option<DeliveryOrderState> {
+        DeliveryOrderState::from_status(self.order_status)
+    }
+}
+
+/// Response of `order/cancel`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CancelDeliveryOrderResponse {
+    #[serde(flatten)]
+    pub result: DeliveryResult,
+    /// Cancellation fee in yuan
+    #[serde(default)]
+    pub deduct_fee: f64,
+    #[serde(default)]
+    pub desc: String,
+}
+
+impl AsRef<DeliveryResult> for CancelDeliveryOrderResponse {
+    fn as_ref(&self) -> &DeliveryResult {
+        &self.result
+    }
+}
+
 pub struct DeliveryApi {
     context: Arc<WechatContext>,
 }
@@ -68,6 +685,65 @@ impl DeliveryApi {
             .await
     }
 
+    pub async fn get_local_order(
+        &self,
+        request: &DeliveryRequest,
+    ) -> Result<DeliveryResponse, WechatError> {
+        self.post_json("/cgi-bin/express/local/business/order/get", request)
+            .await
+    }
+
+    /// Create a local delivery order at most once per `shopid`/`shop_order_id`.
+    ///
+    /// The pair is the dedup key recorded in `outbox`. If an earlier attempt
+    /// may have created the order, it is queried via
+    /// [`get_local_order`](Self::get_local_order) before being re-created.
+    ///
+    /// ```no_run
+    /// # use std::collections::HashMap;
+    /// # use wechat_mp_sdk::api::delivery::{DeliveryApi, DeliveryRequest};
+    /// # use wechat_mp_sdk::api::MemoryOrderOutbox;
+    /// # async fn example(api: DeliveryApi) -> Result<(), wechat_mp_sdk::WechatError> {
+    /// let request = DeliveryRequest::new(HashMap::from([
+    ///     ("shopid".to_string(), serde_json::json!("shop-1")),
+    ///     ("shop_order_id".to_string(), serde_json::json!("order-1")),
+    /// ]));
+    /// let outbox = MemoryOrderOutbox::new();
+    /// let order = api.create_or_get_local_order(&outbox, &request).await?;
+    /// println!("created: {}", order.is_created());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_or_get_local_order(
+        &self,
+        outbox: &dyn OrderOutbox,
+        request: &DeliveryRequest,
+    ) -> Result<OrderCreation<DeliveryResponse>, WechatError> {
+        let field = |name: &str| request.payload.get(name).and_then(Value::as_str);
+        let (Some(shopid), Some(shop_order_id)) = (field("shopid"), field("shop_order_id")) else {
+            return Err(WechatError::Config(
+                "add_local_order request needs shopid and shop_order_id".to_string(),
+            ));
+        };
+        let key = format!("local:{shopid}:{shop_order_id}");
+        let query = DeliveryRequest::new(
+            ["shopid", "shop_order_id", "shop_no", "delivery_sign"]
+                .into_iter()
+                .filter_map(|name| {
+                    let value = request.payload.get(name)?;
+                    Some((name.to_string(), value.clone()))
+                })
+                .collect(),
+        );
+        outbox::create_or_get(
+            outbox,
+            &key,
+            || self.get_local_order(&query),
+            || self.add_local_order(request),
+        )
+        .await
+    }
+
     pub async fn cancel_local_order(
         &self,
         request: &DeliveryRequest,
@@ -76,6 +752,109 @@ impl DeliveryApi {
             .await
     }
 
+    /// Place a delivery order, signed for `merchant`
+    pub async fn add_delivery_order(
+        &self,
+        merchant: &DeliveryMerchant,
+        request: &AddDeliveryOrderRequest,
+    ) -> Result<AddDeliveryOrderResponse, WechatError> {
+        self.post_signed(
+            "/cgi-bin/express/local/business/order/add",
+            merchant,
+            request,
+        )
+        .await
+    }
+
+    /// Place a cancelled or failed order again with the same `shop_order_id`
+    #[doc(alias = "reOrder")]
+    pub async fn re_add_delivery_order(
+        &self,
+        merchant: &DeliveryMerchant,
+        request: &AddDeliveryOrderRequest,
+    ) -> Result<AddDeliveryOrderResponse, WechatError> {
+        self.post_signed(
+            "/cgi-bin/express/local/business/order/readd",
+            merchant,
+            request,
+        )
+        .await
+    }
+
+    pub async fn get_delivery_order(
+        &self,
+        merchant: &DeliveryMerchant,
+        request: &GetDeliveryOrderRequest,
+    ) -> Result<GetDeliveryOrderResponse, WechatError> {
+        self.post_signed(
+            "/cgi-bin/express/local/business/order/get",
+            merchant,
+            request,
+        )
+        .await
+    }
+
+    pub async fn cancel_delivery_order(
+        &self,
+        merchant: &DeliveryMerchant,
+        request: &CancelDeliveryOrderRequest,
+    ) -> Result<CancelDeliveryOrderResponse, WechatError> {
+        self.post_signed(
+            "/cgi-bin/express/local/business/order/cancel",
+            merchant,
+            request,
+        )
+        .await
+    }
+
+    /// Confirm that goods of a failed delivery were returned to the store
+    #[doc(alias = "abnormalConfirm")]
+    pub async fn confirm_delivery_return(
+        &self,
+        merchant: &DeliveryMerchant,
+        request: &ConfirmReturnRequest,
+    ) -> Result<DeliveryResult, WechatError> {
+        self.post_signed(
+            "/cgi-bin/express/local/business/order/confirm_return",
+            merchant,
+            request,
+        )
+        .await
+    }
+
+    /// Sandbox only: push a status update for a test order
+    #[doc(alias = "mockUpdateOrder")]
+    pub async fn mock_update_delivery_order(
+        &self,
+        request: &MockUpdateOrderRequest,
+    ) -> Result<DeliveryResult, WechatError> {
+        let response: DeliveryResult = self
+            .context
+            .authed_post("/cgi-bin/express/local/business/test_update_order", request)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    async fn post_signed<B, T>(
+        &self,
+        endpoint: &str,
+        merchant: &DeliveryMerchant,
+        request: &B,
+    ) -> Result<T, WechatError>
+    where
+        B: ShopOrder,
+        T: AsRef<DeliveryResult> + serde::de::DeserializeOwned + Send + 'static,
+    {
+        let response: T = self
+            .context
+            .authed_post(endpoint, &SignedRequest::new(merchant, request))
+            .await?;
+        let result = response.as_ref();
+        WechatError::check_api(result.errcode, &result.errmsg)?;
+        Ok(response)
+    }
+
     async fn post_json<B: Serialize>(
         &self,
         endpoint: &str,
@@ -97,6 +876,23 @@ impl WechatApi for DeliveryApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<DeliveryRequest, DeliveryResponse>("delivery.getAllImmeDelivery");
+    schemas.add::<DeliveryRequest, DeliveryResponse>("delivery.preAddOrder");
+    schemas.add::<DeliveryRequest, DeliveryResponse>("delivery.preCancelOrder");
+    schemas.add::<DeliveryRequest, DeliveryResponse>("delivery.addLocalOrder");
+    schemas.add::<DeliveryRequest, DeliveryResponse>("delivery.getLocalOrder");
+    schemas.add::<DeliveryRequest, DeliveryResponse>("delivery.cancelLocalOrder");
+    schemas.add::<SignedRequest<'static, AddDeliveryOrderRequest>, AddDeliveryOrderResponse>(
+        "delivery.reOrder",
+    );
+    schemas.add::<SignedRequest<'static, ConfirmReturnRequest>, DeliveryResult>(
+        "delivery.abnormalConfirm",
+    );
+    schemas.add::<MockUpdateOrderRequest, DeliveryResult>("delivery.mockUpdateOrder");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,4 +904,97 @@ mod tests {
         assert_eq!(response.errcode, 0);
         assert!(response.extra.contains_key("order_id"));
     }
+
+    #[test]
+    fn tracker_follows_happy_path() {
+        let mut tracker = DeliveryOrderTracker::new("order-1", 100);
+        let steps = [(102, 110), (201, 120), (202, 130), (301, 140), (302, 200)];
+        let mut changes = Vec::new();
+        for (status, at) in steps {
+            if let Some(transition) = tracker.apply(status, at).unwrap() {
+                changes.push(transition.to);
+            }
+        }
+        assert_eq!(
+            changes,
+            vec![
+                DeliveryOrderState::RiderAssigned,
+                DeliveryOrderState::PickedUp,
+                DeliveryOrderState::Delivered,
+            ]
+        );
+        assert_eq!(tracker.status(), 302);
+        assert_eq!(tracker.updated_at(), 200);
+        assert_eq!(tracker.history().len(), 3);
+    }
+
+    #[test]
+    fn tracker_ignores_stale_and_rejects_illegal_updates() {
+        let mut tracker = DeliveryOrderTracker::new("order-2", 100);
+        tracker.apply(202, 150).unwrap();
+        assert_eq!(tracker.apply(102, 120).unwrap(), None);
+        assert_eq!(tracker.state(), DeliveryOrderState::PickedUp);
+
+        assert!(matches!(
+            tracker.apply(102, 160),
+            Err(WechatError::Config(_))
+        ));
+        assert!(matches!(
+            tracker.apply(999, 170),
+            Err(WechatError::Config(_))
+        ));
+
+        tracker.apply(302, 180).unwrap();
+        assert!(tracker.apply(303, 190).is_err());
+    }
+
+    #[test]
+    fn tracker_allows_redispatch_and_reads_callbacks() {
+        let mut tracker = DeliveryOrderTracker::new("order-3", 100);
+        tracker.apply(102, 110).unwrap();
+        let back = tracker.apply(101, 120).unwrap().unwrap();
+        assert_eq!(back.to, DeliveryOrderState::Created);
+
+        let callback: DeliveryResponse = serde_json::from_str(
+            r#"{"errcode":0,"errmsg":"ok","order_status":103,"action_time":130}"#,
+        )
+        .unwrap();
+        let cancelled = tracker.apply_response(&callback, 999).unwrap().unwrap();
+        assert_eq!(cancelled.to, DeliveryOrderState::Cancelled);
+        assert_eq!(cancelled.timestamp, 130);
+        assert!(tracker.state().is_terminal());
+    }
+
+    #[test]
+    fn signed_request_adds_shopid_and_delivery_sign() {
+        let merchant = DeliveryMerchant::new("test_shop_id", "test_app_secrect");
+        assert_eq!(
+            merchant.delivery_sign("order-1"),
+            "47cbc3215cd6ebfe27273c21be1a233c4ff01eb1"
+        );
+        assert!(!format!("{merchant:?}").contains("test_app_secrect"));
+
+        let request = CancelDeliveryOrderRequest {
+            shop_order_id: "order-1".to_string(),
+            shop_no: "store-1".to_string(),
+            delivery_id: "TEST".to_string(),
+            cancel_reason_id: 1,
+            ..Default::default()
+        };
+        let body = serde_json::to_value(SignedRequest::new(&merchant, &request)).unwrap();
+        assert_eq!(body["shopid"], "test_shop_id");
+        assert_eq!(
+            body["delivery_sign"],
+            "47cbc3215cd6ebfe27273c21be1a233c4ff01eb1"
+        );
+        assert_eq!(body["shop_order_id"], "order-1");
+        assert!(body.get("waybill_id").is_none());
+
+        let response: GetDeliveryOrderResponse = serde_json::from_str(
+            r#"{"errcode":0,"resultcode":0,"resultmsg":"ok","order_status":202,"waybill_id":"wb"}"#,
+        )
+        .unwrap();
+        assert!(response.result.is_ok());
+        assert_eq!(response.state(), Some(DeliveryOrderState::PickedUp));
+    }
 }
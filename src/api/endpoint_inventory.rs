@@ -1,3 +1,8 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EndpointPriority {
     Core,
@@ -5,6 +10,32 @@ pub enum EndpointPriority {
     Vertical,
 }
 
+/// Recommended request timeout class for an endpoint.
+///
+/// The concrete duration for each class is configured on
+/// [`WechatClientBuilder::class_timeout`](crate::client::WechatClientBuilder::class_timeout).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeoutClass {
+    /// Cheap lookups such as token and session exchanges.
+    Fast,
+    /// Regular JSON endpoints.
+    Normal,
+    /// Endpoints that process or return media (OCR, image, QR codes, uploads).
+    Slow,
+}
+
+/// Protocol version of an endpoint
+///
+/// Endpoints with v1/v2 variants can be pinned with
+/// [`WechatClientBuilder::api_version`](crate::client::WechatClientBuilder::api_version)
+/// or per call with [`CallOptions`](crate::client::CallOptions).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ApiVersion {
+    #[default]
+    V1,
+    V2,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct EndpointInventoryItem {
     pub category: &'static str,
@@ -14,6 +45,14 @@ pub struct EndpointInventoryItem {
     pub deprecated: bool,
     pub implemented: bool,
     pub priority: EndpointPriority,
+    pub timeout_class: TimeoutClass,
+    /// Whether the endpoint has side effects (sends, orders, deletes, ...);
+    /// blocked by [`Mode::ReadOnly`](crate::client::Mode::ReadOnly)
+    pub mutating: bool,
+    /// Version used unless pinned otherwise
+    pub version: ApiVersion,
+    /// Whether another version of this endpoint can be selected
+    pub versioned: bool,
 }
 
 pub const DEPRECATION_POLICY: &str =
@@ -28,7 +67,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Fast),
     item(
         "mp-access-token",
         "accessToken.getStableAccessToken",
@@ -37,7 +77,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Fast),
     item(
         "openapi",
         "openapi.clearQuota",
@@ -46,7 +87,9 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .mutating()
+    .with_version(ApiVersion::V1),
     item(
         "openapi",
         "openapi.getApiQuota",
@@ -64,7 +107,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating(),
     item(
         "openapi",
         "openapi.clearQuotaByAppSecret",
@@ -73,7 +117,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating(),
     item(
         "openapi",
         "openapi.getRidInfo",
@@ -118,7 +163,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Fast),
     item(
         "login",
         "auth.checkSessionKey",
@@ -127,7 +173,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Fast),
     item(
         "login",
         "auth.resetUserSessionKey",
@@ -136,7 +183,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating(),
     item(
         "user-info",
         "user.getPluginOpenPId",
@@ -190,7 +238,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "qrcode-link",
         "qrcode.getUnlimitedQRCode",
@@ -199,7 +248,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "qrcode-link",
         "qrcode.createQRCode",
@@ -208,7 +258,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "qrcode-link",
         "qrcode.generateScheme",
@@ -271,7 +322,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .mutating(),
     item(
         "customer-service",
         "customerService.setTyping",
@@ -280,7 +332,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating(),
     item(
         "customer-service",
         "customerService.uploadTempMedia",
@@ -289,7 +342,9 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .mutating()
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "customer-service",
         "customerService.getTempMedia",
@@ -298,7 +353,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "wechat-kf",
         "kfWork.getKfWorkBound",
@@ -316,7 +372,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "wechat-kf",
         "kfWork.unbindKfWork",
@@ -325,7 +382,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "subscribe-message",
         "subscribe.sendMessage",
@@ -334,7 +392,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .mutating(),
     item(
         "subscribe-message",
         "subscribe.addMessageTemplate",
@@ -343,7 +402,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .mutating(),
     item(
         "subscribe-message",
         "subscribe.deleteMessageTemplate",
@@ -352,7 +412,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .mutating(),
     item(
         "subscribe-message",
         "subscribe.getCategory",
@@ -397,7 +458,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "subscribe-message",
         "subscribe.setUserNotifyExt",
@@ -406,7 +468,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "subscribe-message",
         "subscribe.getUserNotify",
@@ -424,7 +487,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .with_version(ApiVersion::V2),
     item(
         "security",
         "security.mediaCheckAsync",
@@ -433,7 +497,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Core,
-    ),
+    )
+    .with_version(ApiVersion::V2),
     item(
         "security",
         "security.getUserRiskRank",
@@ -586,7 +651,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "operations",
         "operations.getFeedback",
@@ -604,7 +670,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "operations",
         "operations.getJsErrDetail",
@@ -640,7 +707,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating(),
     item(
         "plugin",
         "plugin.managePlugin",
@@ -649,7 +717,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating(),
     item(
         "nearby",
         "nearby.addNearbyPoi",
@@ -658,7 +727,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "nearby",
         "nearby.deleteNearbyPoi",
@@ -667,7 +737,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "nearby",
         "nearby.getNearbyPoiList",
@@ -685,7 +756,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "cloud",
         "cloud.invokeCloudFunction",
@@ -694,7 +766,9 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating()
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "cloud",
         "cloud.addDelayedFunctionTask",
@@ -703,7 +777,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "cloud",
         "cloud.databaseAdd",
@@ -712,7 +787,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating(),
     item(
         "cloud",
         "cloud.databaseDelete",
@@ -721,7 +797,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating(),
     item(
         "cloud",
         "cloud.databaseUpdate",
@@ -730,7 +807,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating(),
     item(
         "cloud",
         "cloud.databaseQuery",
@@ -740,6 +818,35 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         true,
         EndpointPriority::Extended,
     ),
+    item(
+        "cloud",
+        "cloud.databaseCollectionAdd",
+        "POST",
+        "/tcb/databasecollectionadd",
+        false,
+        true,
+        EndpointPriority::Extended,
+    )
+    .mutating(),
+    item(
+        "cloud",
+        "cloud.databaseCollectionDelete",
+        "POST",
+        "/tcb/databasecollectiondelete",
+        false,
+        true,
+        EndpointPriority::Extended,
+    )
+    .mutating(),
+    item(
+        "cloud",
+        "cloud.databaseCollectionGet",
+        "POST",
+        "/tcb/databasecollectionget",
+        false,
+        true,
+        EndpointPriority::Extended,
+    ),
     item(
         "cloud",
         "cloud.getUploadFileLink",
@@ -766,7 +873,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Extended,
-    ),
+    )
+    .mutating(),
     item(
         "cloud",
         "cloud.newSendCloudBaseSms",
@@ -775,7 +883,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "cloud",
         "cloud.sendCloudBaseSms",
@@ -784,7 +893,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         true,
         false,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "live",
         "live.createRoom",
@@ -793,7 +903,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "live",
         "live.deleteRoom",
@@ -802,7 +913,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "live",
         "live.editRoom",
@@ -811,7 +923,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "live",
         "live.getLiveInfo",
@@ -829,7 +942,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "live",
         "live.updateGoodsInfo",
@@ -838,7 +952,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "live",
         "live.deleteGoodsInfo",
@@ -847,7 +962,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "live",
         "live.pushMessage",
@@ -856,7 +972,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "live",
         "live.getFollowers",
@@ -866,6 +983,24 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         true,
         EndpointPriority::Vertical,
     ),
+    item(
+        "live",
+        "live.getSharedCode",
+        "GET",
+        "/wxaapi/broadcast/room/getsharedcode",
+        false,
+        true,
+        EndpointPriority::Vertical,
+    ),
+    item(
+        "live",
+        "live.getApprovedGoods",
+        "GET",
+        "/wxaapi/broadcast/goods/getapproved",
+        false,
+        true,
+        EndpointPriority::Vertical,
+    ),
     item(
         "hardware",
         "hardware.sendHardwareDeviceMessage",
@@ -874,7 +1009,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "hardware",
         "hardware.getSnTicket",
@@ -892,7 +1028,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "hardware",
         "hardware.getIotGroupInfo",
@@ -910,7 +1047,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "hardware",
         "hardware.removeIotGroupDevice",
@@ -919,7 +1057,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "image-ocr",
         "image.aiCrop",
@@ -928,7 +1067,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "image-ocr",
         "image.scanQRCode",
@@ -937,7 +1077,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "image-ocr",
         "ocr.printedTextOCR",
@@ -946,7 +1087,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "image-ocr",
         "ocr.vehicleLicenseOCR",
@@ -955,7 +1097,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "image-ocr",
         "ocr.bankCardOCR",
@@ -964,7 +1107,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "image-ocr",
         "ocr.businessLicenseOCR",
@@ -973,7 +1117,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "image-ocr",
         "ocr.driverLicenseOCR",
@@ -982,7 +1127,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "image-ocr",
         "ocr.idCardOCR",
@@ -991,7 +1137,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .with_timeout_class(TimeoutClass::Slow),
     item(
         "instant-delivery",
         "delivery.getAllImmeDelivery",
@@ -1027,6 +1174,16 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
+    )
+    .mutating(),
+    item(
+        "instant-delivery",
+        "delivery.getLocalOrder",
+        "POST",
+        "/cgi-bin/express/local/business/order/get",
+        false,
+        true,
+        EndpointPriority::Vertical,
     ),
     item(
         "instant-delivery",
@@ -1036,7 +1193,38 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
+    item(
+        "instant-delivery",
+        "delivery.reOrder",
+        "POST",
+        "/cgi-bin/express/local/business/order/readd",
+        false,
+        true,
+        EndpointPriority::Vertical,
+    )
+    .mutating(),
+    item(
+        "instant-delivery",
+        "delivery.abnormalConfirm",
+        "POST",
+        "/cgi-bin/express/local/business/order/confirm_return",
+        false,
+        true,
+        EndpointPriority::Vertical,
+    )
+    .mutating(),
+    item(
+        "instant-delivery",
+        "delivery.mockUpdateOrder",
+        "POST",
+        "/cgi-bin/express/local/business/test_update_order",
+        false,
+        true,
+        EndpointPriority::Vertical,
+    )
+    .mutating(),
     item(
         "logistics",
         "express.bindAccount",
@@ -1045,7 +1233,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "logistics",
         "express.getAllAccount",
@@ -1081,7 +1270,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "logistics",
         "express.getPath",
@@ -1091,6 +1281,16 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         true,
         EndpointPriority::Vertical,
     ),
+    item(
+        "shipping",
+        "shipping.uploadShippingInfo",
+        "POST",
+        "/wxa/sec/order/upload_shipping_info",
+        false,
+        true,
+        EndpointPriority::Vertical,
+    )
+    .mutating(),
     item(
         "service-market",
         "serviceMarket.invokeService",
@@ -1099,7 +1299,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "soter",
         "soter.verifySignature",
@@ -1135,7 +1336,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "advertising",
         "ad.addUserAction",
@@ -1144,7 +1346,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "advertising",
         "ad.addUserActionSet",
@@ -1153,7 +1356,8 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         false,
         true,
         EndpointPriority::Vertical,
-    ),
+    )
+    .mutating(),
     item(
         "advertising",
         "ad.getUserActionSetReports",
@@ -1172,6 +1376,245 @@ pub const ENDPOINT_INVENTORY: &[EndpointInventoryItem] = &[
         true,
         EndpointPriority::Vertical,
     ),
+    item(
+        "account",
+        "account.getAccountBasicInfo",
+        "GET",
+        "/cgi-bin/account/getaccountbasicinfo",
+        false,
+        true,
+        EndpointPriority::Extended,
+    ),
+    item(
+        "account",
+        "account.setNickname",
+        "POST",
+        "/wxa/setnickname",
+        false,
+        true,
+        EndpointPriority::Extended,
+    )
+    .mutating(),
+    item(
+        "account",
+        "account.queryNickname",
+        "POST",
+        "/wxa/api_wxa_querynickname",
+        false,
+        true,
+        EndpointPriority::Extended,
+    ),
+    item(
+        "account",
+        "account.checkNickname",
+        "POST",
+        "/cgi-bin/wxverify/checkwxverifynickname",
+        false,
+        true,
+        EndpointPriority::Extended,
+    ),
+    item(
+        "account",
+        "account.modifyHeadImage",
+        "POST",
+        "/cgi-bin/account/modifyheadimage",
+        false,
+        true,
+        EndpointPriority::Extended,
+    )
+    .mutating(),
+    item(
+        "account",
+        "account.modifySignature",
+        "POST",
+        "/cgi-bin/account/modifysignature",
+        false,
+        true,
+        EndpointPriority::Extended,
+    )
+    .mutating(),
+];
+
+/// `(endpoint_id, facade method)` for every implemented endpoint
+///
+/// Names the [`WechatMp`](crate::WechatMp) method calling each endpoint, so
+/// metrics and rate limits keyed by endpoint can be labelled with the method
+/// names used in application code. Look entries up with
+/// [`facade_method_for_endpoint`] and [`endpoint_for_facade_method`].
+///
+/// When an endpoint is marked `implemented`, add its entry here together with
+/// the facade method; the parity guard tests fail otherwise.
+pub const FACADE_METHOD_MAP: &[(&str, &str)] = &[
+    ("accessToken.getAccessToken", "get_access_token"),
+    ("auth.code2Session", "auth_login"),
+    ("user.getPhoneNumber", "get_phone_number"),
+    ("qrcode.getQRCode", "get_wxa_code"),
+    ("qrcode.getUnlimitedQRCode", "get_wxa_code_unlimit"),
+    ("qrcode.createQRCode", "create_qrcode"),
+    ("qrcode.generateScheme", "generate_url_scheme"),
+    ("qrcode.generateUrlLink", "generate_url_link"),
+    ("qrcode.generateShortLink", "generate_short_link"),
+    (
+        "customerService.sendCustomMessage",
+        "send_customer_service_message",
+    ),
+    ("customerService.uploadTempMedia", "upload_temp_media"),
+    ("customerService.getTempMedia", "get_temp_media"),
+    ("subscribe.sendMessage", "send_subscribe_message"),
+    ("subscribe.addMessageTemplate", "add_template"),
+    ("subscribe.deleteMessageTemplate", "delete_template"),
+    ("subscribe.getCategory", "get_category"),
+    ("subscribe.getMessageTemplateList", "get_template_list"),
+    (
+        "accessToken.getStableAccessToken",
+        "get_stable_access_token",
+    ),
+    ("openapi.clearQuota", "clear_quota"),
+    ("openapi.getApiQuota", "get_api_quota"),
+    ("openapi.clearApiQuota", "clear_api_quota"),
+    ("openapi.clearQuotaByAppSecret", "clear_quota_by_app_secret"),
+    ("openapi.getRidInfo", "get_rid_info"),
+    ("openapi.callbackCheck", "callback_check"),
+    ("openapi.getApiDomainIp", "get_api_domain_ip"),
+    ("openapi.getCallbackIp", "get_callback_ip"),
+    // Wave 2: Security
+    ("security.msgSecCheck", "msg_sec_check"),
+    ("security.mediaCheckAsync", "media_check_async"),
+    ("security.getUserRiskRank", "get_user_risk_rank"),
+    // Wave 2: Auth extensions
+    ("auth.checkSessionKey", "check_session_key"),
+    ("auth.resetUserSessionKey", "reset_user_session_key"),
+    // Wave 2: User extensions
+    ("user.getPluginOpenPId", "get_plugin_open_pid"),
+    ("user.checkEncryptedData", "check_encrypted_data"),
+    ("user.getPaidUnionid", "get_paid_unionid"),
+    ("user.getUserEncryptKey", "get_user_encrypt_key"),
+    // Wave 2: QR Code extensions
+    ("qrcode.queryScheme", "query_scheme"),
+    ("qrcode.queryUrlLink", "query_url_link"),
+    ("qrcode.generateNFCScheme", "generate_nfc_scheme"),
+    // Wave 2: Customer service extension
+    ("customerService.setTyping", "set_typing"),
+    // Wave 2: WeChat KF
+    ("kfWork.getKfWorkBound", "get_kf_work_bound"),
+    ("kfWork.bindKfWork", "bind_kf_work"),
+    ("kfWork.unbindKfWork", "unbind_kf_work"),
+    (
+        "subscribe.getPubTemplateKeyWordsById",
+        "get_pub_template_keywords_by_id",
+    ),
+    (
+        "subscribe.getPubTemplateTitleList",
+        "get_pub_template_title_list",
+    ),
+    ("subscribe.setUserNotify", "update_user_notify"),
+    ("subscribe.setUserNotifyExt", "update_user_notify_ext"),
+    ("subscribe.getUserNotify", "query_user_notify"),
+    ("analytics.getDailySummary", "get_daily_summary"),
+    ("analytics.getDailyVisitTrend", "get_daily_visit_trend"),
+    ("analytics.getWeeklyVisitTrend", "get_weekly_visit_trend"),
+    ("analytics.getMonthlyVisitTrend", "get_monthly_visit_trend"),
+    ("analytics.getDailyRetain", "get_daily_retain"),
+    ("analytics.getWeeklyRetain", "get_weekly_retain"),
+    ("analytics.getMonthlyRetain", "get_monthly_retain"),
+    ("analytics.getVisitPage", "get_visit_page"),
+    ("analytics.getVisitDistribution", "get_visit_distribution"),
+    ("analytics.getUserPortrait", "get_user_portrait"),
+    ("analytics.getPerformanceData", "get_performance_data"),
+    ("operations.getDomainInfo", "get_domain_info"),
+    ("operations.getPerformance", "get_operations_performance"),
+    ("operations.getSceneList", "get_scene_list"),
+    ("operations.getVersionList", "get_version_list"),
+    ("operations.realtimeLogSearch", "realtime_log_search"),
+    ("operations.getFeedback", "get_feedback"),
+    ("operations.getFeedbackMedia", "get_feedback_media"),
+    ("operations.getJsErrDetail", "get_js_err_detail"),
+    ("operations.getJsErrList", "get_js_err_list"),
+    ("operations.getGrayReleasePlan", "get_gray_release_plan"),
+    (
+        "plugin.managePluginApplication",
+        "manage_plugin_application",
+    ),
+    ("plugin.managePlugin", "manage_plugin"),
+    ("nearby.addNearbyPoi", "add_nearby_poi"),
+    ("nearby.deleteNearbyPoi", "delete_nearby_poi"),
+    ("nearby.getNearbyPoiList", "get_nearby_poi_list"),
+    ("nearby.setShowStatus", "set_nearby_show_status"),
+    ("cloud.invokeCloudFunction", "invoke_cloud_function"),
+    ("cloud.addDelayedFunctionTask", "add_delayed_function_task"),
+    ("cloud.databaseAdd", "database_add"),
+    ("cloud.databaseDelete", "database_delete"),
+    ("cloud.databaseUpdate", "database_update"),
+    ("cloud.databaseQuery", "database_query"),
+    ("cloud.databaseCollectionAdd", "database_collection_add"),
+    (
+        "cloud.databaseCollectionDelete",
+        "database_collection_delete",
+    ),
+    ("cloud.databaseCollectionGet", "database_collection_get"),
+    ("cloud.getUploadFileLink", "get_upload_file_link"),
+    ("cloud.getDownloadFileLink", "get_download_file_link"),
+    ("cloud.deleteCloudFile", "delete_cloud_file"),
+    ("cloud.newSendCloudBaseSms", "new_send_cloud_base_sms"),
+    ("live.createRoom", "create_room"),
+    ("live.deleteRoom", "delete_room"),
+    ("live.editRoom", "edit_room"),
+    ("live.getLiveInfo", "get_live_info"),
+    ("live.addGoods", "add_goods"),
+    ("live.updateGoodsInfo", "update_goods_info"),
+    ("live.deleteGoodsInfo", "delete_goods_info"),
+    ("live.pushMessage", "push_message"),
+    ("live.getFollowers", "get_followers"),
+    ("live.getSharedCode", "get_live_shared_code"),
+    ("live.getApprovedGoods", "get_approved_live_goods"),
+    (
+        "hardware.sendHardwareDeviceMessage",
+        "send_hardware_device_message",
+    ),
+    ("hardware.getSnTicket", "get_sn_ticket"),
+    ("hardware.createIotGroupId", "create_iot_group_id"),
+    ("hardware.getIotGroupInfo", "get_iot_group_info"),
+    ("hardware.addIotGroupDevice", "add_iot_group_device"),
+    ("hardware.removeIotGroupDevice", "remove_iot_group_device"),
+    ("image.aiCrop", "ai_crop"),
+    ("image.scanQRCode", "scan_qr_code"),
+    ("ocr.printedTextOCR", "printed_text_ocr"),
+    ("ocr.vehicleLicenseOCR", "vehicle_license_ocr"),
+    ("ocr.bankCardOCR", "bank_card_ocr"),
+    ("ocr.businessLicenseOCR", "business_license_ocr"),
+    ("ocr.driverLicenseOCR", "driver_license_ocr"),
+    ("ocr.idCardOCR", "id_card_ocr"),
+    ("delivery.getAllImmeDelivery", "get_all_imme_delivery"),
+    ("delivery.preAddOrder", "pre_add_order"),
+    ("delivery.preCancelOrder", "pre_cancel_order"),
+    ("delivery.addLocalOrder", "add_local_order"),
+    ("delivery.getLocalOrder", "get_local_order"),
+    ("delivery.cancelLocalOrder", "cancel_local_order"),
+    ("delivery.reOrder", "re_add_delivery_order"),
+    ("delivery.abnormalConfirm", "confirm_delivery_return"),
+    ("delivery.mockUpdateOrder", "mock_update_delivery_order"),
+    ("express.bindAccount", "bind_account"),
+    ("express.getAllAccount", "get_all_account"),
+    ("express.getAllDelivery", "get_all_delivery"),
+    ("express.getOrder", "get_order"),
+    ("express.addOrder", "add_order"),
+    ("express.getPath", "get_path"),
+    ("shipping.uploadShippingInfo", "upload_shipping_info"),
+    ("serviceMarket.invokeService", "invoke_service"),
+    ("soter.verifySignature", "verify_signature"),
+    ("face.getVerifyId", "get_verify_id"),
+    ("face.queryVerifyInfo", "query_verify_info"),
+    ("wxsearch.submitPages", "submit_pages"),
+    ("account.getAccountBasicInfo", "get_account_basic_info"),
+    ("account.setNickname", "set_nickname"),
+    ("account.queryNickname", "query_nickname_audit"),
+    ("account.checkNickname", "check_nickname"),
+    ("account.modifyHeadImage", "modify_head_image"),
+    ("account.modifySignature", "modify_signature"),
+    ("ad.addUserAction", "add_user_action"),
+    ("ad.addUserActionSet", "add_user_action_set"),
+    ("ad.getUserActionSetReports", "get_user_action_set_reports"),
+    ("ad.getUserActionSets", "get_user_action_sets"),
 ];
 
 pub fn endpoint_inventory() -> &'static [EndpointInventoryItem] {
@@ -1182,6 +1625,368 @@ pub fn get_endpoint_inventory() -> &'static [EndpointInventoryItem] {
     ENDPOINT_INVENTORY
 }
 
+/// Returns the recommended timeout class for a request path.
+///
+/// Query strings are ignored. Paths missing from the inventory fall back to
+/// [`TimeoutClass::Normal`].
+pub fn timeout_class_for_path(path: &str) -> TimeoutClass {
+    let path = path.split('?').next().unwrap_or(path);
+    ENDPOINT_INVENTORY
+        .iter()
+        .find(|item| item.path == path)
+        .map_or(TimeoutClass::Normal, |item| item.timeout_class)
+}
+
+/// One endpoint in an external API catalog, e.g. exported from the WeChat docs.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct UpstreamEndpoint {
+    #[serde(alias = "http_method")]
+    pub method: String,
+    pub path: String,
+    #[serde(default, alias = "id", alias = "endpoint_id")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// An external endpoint catalog to diff [`ENDPOINT_INVENTORY`] against.
+///
+/// Accepts either a bare JSON array of endpoints or an object with an
+/// `endpoints` array. Paths are compared without query strings or trailing
+/// slashes, methods case-insensitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpstreamCatalog {
+    pub endpoints: Vec<UpstreamEndpoint>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CatalogDocument {
+    List(Vec<UpstreamEndpoint>),
+    Wrapped { endpoints: Vec<UpstreamEndpoint> },
+}
+
+impl UpstreamCatalog {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let endpoints = match serde_json::from_str(json)? {
+            CatalogDocument::List(endpoints) | CatalogDocument::Wrapped { endpoints } => endpoints,
+        };
+        Ok(Self { endpoints })
+    }
+
+    /// Compares the catalog with the built-in inventory.
+    pub fn diff(&self) -> InventoryDiff {
+        let inventory: HashSet<(String, &str)> = ENDPOINT_INVENTORY
+            .iter()
+            .map(|item| {
+                (
+                    item.http_method.to_ascii_uppercase(),
+                    normalize_path(item.path),
+                )
+            })
+            .collect();
+        let upstream: HashSet<(String, &str)> = self
+            .endpoints
+            .iter()
+            .map(|e| (e.method.to_ascii_uppercase(), normalize_path(&e.path)))
+            .collect();
+
+        let mut seen = HashSet::new();
+        let missing = self
+            .endpoints
+            .iter()
+            .filter(|e| {
+                let key = (e.method.to_ascii_uppercase(), normalize_path(&e.path));
+                !inventory.contains(&key) && seen.insert(key)
+            })
+            .cloned()
+            .collect();
+
+        let (tracked, not_in_catalog): (Vec<&EndpointInventoryItem>, Vec<_>) =
+            ENDPOINT_INVENTORY.iter().partition(|item| {
+                upstream.contains(&(
+                    item.http_method.to_ascii_uppercase(),
+                    normalize_path(item.path),
+                ))
+            });
+        let unimplemented = tracked
+            .into_iter()
+            .filter(|item| !item.implemented && !item.deprecated)
+            .collect();
+
+        InventoryDiff {
+            missing,
+            unimplemented,
+            not_in_catalog,
+        }
+    }
+}
+
+/// Result of [`UpstreamCatalog::diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InventoryDiff {
+    /// Upstream endpoints with no inventory entry, in catalog order.
+    pub missing: Vec<UpstreamEndpoint>,
+    /// Inventory entries listed upstream but not implemented yet.
+    pub unimplemented: Vec<&'static EndpointInventoryItem>,
+    /// Inventory entries the catalog does not list, e.g. retired upstream.
+    pub not_in_catalog: Vec<&'static EndpointInventoryItem>,
+}
+
+impl InventoryDiff {
+    /// `true` when every upstream endpoint is tracked and implemented.
+    pub fn is_covered(&self) -> bool {
+        self.missing.is_empty() && self.unimplemented.is_empty()
+    }
+}
+
+/// Diffs [`ENDPOINT_INVENTORY`] against a JSON catalog.
+///
+/// See [`UpstreamCatalog`] for the accepted format.
+pub fn diff_against_catalog(json: &str) -> Result<InventoryDiff, serde_json::Error> {
+    Ok(UpstreamCatalog::from_json(json)?.diff())
+}
+
+fn normalize_path(path: &str) -> &str {
+    let path = path.split('?').next().unwrap_or(path);
+    match path.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    }
+}
+
+/// Returns the inventory category of a request path, e.g. `"qrcode-link"`.
+///
+/// Query strings are ignored. Paths missing from the inventory return `None`.
+pub fn category_for_path(path: &str) -> Option<&'static str> {
+    let path = path.split('?').next().unwrap_or(path);
+    ENDPOINT_INVENTORY
+        .iter()
+        .find(|item| item.path == path)
+        .map(|item| item.category)
+}
+
+/// Returns the inventory entry with `endpoint_id`, e.g. `"security.msgSecCheck"`.
+pub fn endpoint_by_id(endpoint_id: &str) -> Option<&'static EndpointInventoryItem> {
+    ENDPOINT_INVENTORY
+        .iter()
+        .find(|item| item.endpoint_id == endpoint_id)
+}
+
+/// Returns the facade method calling `endpoint_id`, e.g. `"msg_sec_check"`.
+pub fn facade_method_for_endpoint(endpoint_id: &str) -> Option<&'static str> {
+    FACADE_METHOD_MAP
+        .iter()
+        .find(|(id, _)| *id == endpoint_id)
+        .map(|(_, method)| *method)
+}
+
+/// Returns the inventory entry called by the facade method `method`.
+pub fn endpoint_for_facade_method(method: &str) -> Option<&'static EndpointInventoryItem> {
+    FACADE_METHOD_MAP
+        .iter()
+        .find(|(_, name)| *name == method)
+        .and_then(|(id, _)| endpoint_by_id(id))
+}
+
+/// Returns whether a request path is a `mutating` inventory endpoint.
+///
+/// Query strings are ignored. Paths missing from the inventory return `false`.
+pub fn is_mutating_path(path: &str) -> bool {
+    let path = path.split('?').next().unwrap_or(path);
+    ENDPOINT_INVENTORY
+        .iter()
+        .any(|item| item.path == path && item.mutating)
+}
+
+/// Endpoints authenticated by appid/secret instead of an `access_token`.
+const SECRET_AUTH_PATHS: &[&str] = &[
+    "/cgi-bin/token",
+    "/cgi-bin/stable_token",
+    "/cgi-bin/clear_quota/v2",
+    "/sns/jscode2session",
+];
+
+/// Endpoints whose success body is binary rather than JSON.
+const BINARY_RESPONSE_PATHS: &[&str] = &[
+    "/wxa/getwxacode",
+    "/wxa/getwxacodeunlimit",
+    "/cgi-bin/wxaapp/createwxaqrcode",
+    "/cgi-bin/media/get",
+];
+
+/// Builds an OpenAPI 3.0 document describing [`ENDPOINT_INVENTORY`].
+///
+/// Each endpoint becomes an operation with `operationId` set to its
+/// `endpoint_id`, tagged by category. Inventory metadata is kept in
+/// `x-priority`, `x-timeout-class` and `x-implemented`. With the `schemars`
+/// feature, request and response bodies reference component schemas generated
+/// from the crate's request and response types; otherwise, and for bodies
+/// built ad hoc, they are described by the common `errcode`/`errmsg`
+/// envelope. When two endpoints share a path and method, the first one is
+/// emitted and the others are listed in `x-alias-operation-ids`.
+pub fn openapi_spec() -> Value {
+    let (bodies, mut schemas) = body_schemas();
+    schemas.insert(
+        "WechatResponse".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "errcode": { "type": "integer", "description": "0 on success" },
+                "errmsg": { "type": "string" }
+            },
+            "additionalProperties": true
+        }),
+    );
+
+    let mut paths: BTreeMap<&str, BTreeMap<String, Map<String, Value>>> = BTreeMap::new();
+    for item in ENDPOINT_INVENTORY {
+        let method = item.http_method.to_ascii_lowercase();
+        let path_item = paths.entry(item.path).or_default();
+        if let Some(existing) = path_item.get_mut(&method) {
+            if let Some(Value::Array(aliases)) = existing.get_mut("x-alias-operation-ids") {
+                aliases.push(json!(item.endpoint_id));
+            } else {
+                existing.insert(
+                    "x-alias-operation-ids".to_string(),
+                    json!([item.endpoint_id]),
+                );
+            }
+            continue;
+        }
+        let body = bodies.get(item.endpoint_id).cloned().unwrap_or_default();
+        path_item.insert(method, openapi_operation(item, body));
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "WeChat Mini Program Server API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": DEPRECATION_POLICY,
+        },
+        "servers": [{ "url": "https://api.weixin.qq.com" }],
+        "security": [{ "accessToken": [] }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "accessToken": { "type": "apiKey", "in": "query", "name": "access_token" }
+            },
+            "schemas": schemas,
+        }
+    })
+}
+
+/// Typed body schemas of one operation
+#[derive(Debug, Clone, Default)]
+struct OperationBodies {
+    request: Option<Value>,
+    response: Option<Value>,
+}
+
+/// Collects the body schemas API modules register for their endpoints
+#[cfg(feature = "schemars")]
+pub(crate) struct OperationSchemas {
+    generator: schemars::SchemaGenerator,
+    bodies: HashMap<&'static str, OperationBodies>,
+}
+
+#[cfg(feature = "schemars")]
+impl OperationSchemas {
+    /// Describe the JSON request and response bodies of `endpoint_id`
+    pub(crate) fn add<Req, Resp>(&mut self, endpoint_id: &'static str)
+    where
+        Req: schemars::JsonSchema,
+        Resp: schemars::JsonSchema,
+    {
+        self.request::<Req>(endpoint_id);
+        self.response::<Resp>(endpoint_id);
+    }
+
+    /// Describe the JSON request body of `endpoint_id`
+    pub(crate) fn request<T: schemars::JsonSchema>(&mut self, endpoint_id: &'static str) {
+        let schema = self.generator.subschema_for::<T>().to_value();
+        self.bodies.entry(endpoint_id).or_default().request = Some(schema);
+    }
+
+    /// Describe the JSON response body of `endpoint_id`
+    pub(crate) fn response<T: schemars::JsonSchema>(&mut self, endpoint_id: &'static str) {
+        let schema = self.generator.subschema_for::<T>().to_value();
+        self.bodies.entry(endpoint_id).or_default().response = Some(schema);
+    }
+}
+
+/// Body schemas by `endpoint_id` and the component schemas they reference
+#[cfg(feature = "schemars")]
+fn body_schemas() -> (HashMap<&'static str, OperationBodies>, Map<String, Value>) {
+    let mut schemas = OperationSchemas {
+        generator: schemars::SchemaGenerator::new(schemars::generate::SchemaSettings::openapi3()),
+        bodies: HashMap::new(),
+    };
+    super::openapi_schemas(&mut schemas);
+    let components = schemas.generator.take_definitions(true);
+    (schemas.bodies, components)
+}
+
+#[cfg(not(feature = "schemars"))]
+fn body_schemas() -> (HashMap<&'static str, OperationBodies>, Map<String, Value>) {
+    (HashMap::new(), Map::new())
+}
+
+fn openapi_operation(item: &EndpointInventoryItem, body: OperationBodies) -> Map<String, Value> {
+    let envelope = json!({ "$ref": "#/components/schemas/WechatResponse" });
+    let mut content = Map::new();
+    content.insert(
+        "application/json".to_string(),
+        json!({ "schema": body.response.unwrap_or(envelope) }),
+    );
+    if BINARY_RESPONSE_PATHS.contains(&item.path) {
+        content.insert(
+            "application/octet-stream".to_string(),
+            json!({ "schema": { "type": "string", "format": "binary" } }),
+        );
+    }
+
+    let priority = match item.priority {
+        EndpointPriority::Core => "core",
+        EndpointPriority::Extended => "extended",
+        EndpointPriority::Vertical => "vertical",
+    };
+    let timeout_class = match item.timeout_class {
+        TimeoutClass::Fast => "fast",
+        TimeoutClass::Normal => "normal",
+        TimeoutClass::Slow => "slow",
+    };
+
+    let mut operation = Map::new();
+    operation.insert("operationId".to_string(), json!(item.endpoint_id));
+    operation.insert("tags".to_string(), json!([item.category]));
+    operation.insert("deprecated".to_string(), json!(item.deprecated));
+    operation.insert(
+        "responses".to_string(),
+        json!({
+            "200": {
+                "description": "Success, or a non-zero `errcode` on failure",
+                "content": content,
+            }
+        }),
+    );
+    operation.insert("x-priority".to_string(), json!(priority));
+    operation.insert("x-timeout-class".to_string(), json!(timeout_class));
+    operation.insert("x-implemented".to_string(), json!(item.implemented));
+    if item.http_method == "POST" {
+        let schema = body.request.unwrap_or_else(|| json!({ "type": "object" }));
+        operation.insert(
+            "requestBody".to_string(),
+            json!({ "content": { "application/json": { "schema": schema } } }),
+        );
+    }
+    if SECRET_AUTH_PATHS.contains(&item.path) {
+        operation.insert("security".to_string(), json!([]));
+    }
+    operation
+}
+
 const fn item(
     category: &'static str,
     endpoint_id: &'static str,
@@ -1199,5 +2004,205 @@ const fn item(
         deprecated,
         implemented,
         priority,
+        timeout_class: TimeoutClass::Normal,
+        mutating: false,
+        version: ApiVersion::V1,
+        versioned: false,
+    }
+}
+
+impl EndpointInventoryItem {
+    const fn with_timeout_class(self, timeout_class: TimeoutClass) -> Self {
+        EndpointInventoryItem {
+            timeout_class,
+            ..self
+        }
+    }
+
+    const fn mutating(self) -> Self {
+        EndpointInventoryItem {
+            mutating: true,
+            ..self
+        }
+    }
+
+    /// Default to `version` and allow pinning another one
+    const fn with_version(self, version: ApiVersion) -> Self {
+        EndpointInventoryItem {
+            version,
+            versioned: true,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_against_catalog_reports_missing_endpoints() {
+        let catalog = r#"{"endpoints": [
+            {"method": "get", "path": "/cgi-bin/token?grant_type=client_credential"},
+            {"method": "POST", "path": "/wxa/brand_new_api/", "id": "brandNew"},
+            {"method": "POST", "path": "/wxa/brand_new_api"}
+        ]}"#;
+        let diff = diff_against_catalog(catalog).unwrap();
+
+        assert_eq!(diff.missing.len(), 1);
+        assert_eq!(diff.missing[0].name.as_deref(), Some("brandNew"));
+        assert!(!diff.is_covered());
+        assert!(diff
+            .not_in_catalog
+            .iter()
+            .all(|item| item.path != "/cgi-bin/token"));
+        assert!(diff
+            .not_in_catalog
+            .iter()
+            .any(|item| item.path == "/sns/jscode2session"));
+    }
+
+    #[test]
+    fn test_diff_against_full_inventory_is_empty() {
+        let endpoints: Vec<Value> = ENDPOINT_INVENTORY
+            .iter()
+            .map(|item| json!({ "method": item.http_method, "path": item.path }))
+            .collect();
+        let diff = diff_against_catalog(&Value::Array(endpoints).to_string()).unwrap();
+
+        assert!(diff.missing.is_empty());
+        assert!(diff.not_in_catalog.is_empty());
+        assert!(diff
+            .unimplemented
+            .iter()
+            .all(|item| !item.implemented && !item.deprecated));
+    }
+
+    #[test]
+    fn test_diff_against_catalog_rejects_malformed_json() {
+        assert!(diff_against_catalog(r#"{"paths": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_timeout_class_for_path_uses_inventory() {
+        assert_eq!(timeout_class_for_path("/cgi-bin/token"), TimeoutClass::Fast);
+        assert_eq!(timeout_class_for_path("/cv/ocr/idcard"), TimeoutClass::Slow);
+        assert_eq!(
+            timeout_class_for_path("/wxa/getwxacodeunlimit?access_token=abc"),
+            TimeoutClass::Slow
+        );
+        assert_eq!(
+            timeout_class_for_path("/cgi-bin/message/subscribe/send"),
+            TimeoutClass::Normal
+        );
+    }
+
+    #[test]
+    fn test_timeout_class_for_unknown_path_is_normal() {
+        assert_eq!(
+            timeout_class_for_path("/not/in/inventory"),
+            TimeoutClass::Normal
+        );
+    }
+
+    #[test]
+    fn test_duplicate_paths_share_timeout_class() {
+        for a in ENDPOINT_INVENTORY {
+            for b in ENDPOINT_INVENTORY.iter().filter(|b| b.path == a.path) {
+                assert_eq!(a.timeout_class, b.timeout_class, "{}", a.path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_openapi_spec_covers_inventory() {
+        let spec = openapi_spec();
+        assert_eq!(spec["openapi"], "3.0.3");
+
+        let paths = spec["paths"].as_object().unwrap();
+        let mut operation_ids: Vec<&str> = paths
+            .values()
+            .flat_map(|path_item| path_item.as_object().unwrap().values())
+            .flat_map(|operation| {
+                std::iter::once(&operation["operationId"]).chain(
+                    operation["x-alias-operation-ids"]
+                        .as_array()
+                        .into_iter()
+                        .flatten(),
+                )
+            })
+            .map(|id| id.as_str().unwrap())
+            .collect();
+        operation_ids.sort_unstable();
+        let mut expected: Vec<&str> = ENDPOINT_INVENTORY.iter().map(|i| i.endpoint_id).collect();
+        expected.sort_unstable();
+        assert_eq!(operation_ids, expected);
+
+        let token = &spec["paths"]["/cgi-bin/token"]["get"];
+        assert_eq!(token["security"], json!([]));
+        assert_eq!(token["x-timeout-class"], "fast");
+
+        let qrcode = &spec["paths"]["/wxa/getwxacodeunlimit"]["post"];
+        assert!(qrcode["security"].is_null());
+        assert!(qrcode["responses"]["200"]["content"]["application/octet-stream"].is_object());
+        assert!(qrcode["requestBody"].is_object());
+
+        let plugin = &spec["paths"]["/wxa/plugin"]["post"];
+        assert_eq!(plugin["operationId"], "plugin.managePluginApplication");
+        assert_eq!(
+            plugin["x-alias-operation-ids"],
+            json!(["plugin.managePlugin"])
+        );
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_openapi_spec_references_typed_bodies() {
+        fn refs<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+            match value {
+                Value::Object(map) => {
+                    if let Some(Value::String(reference)) = map.get("$ref") {
+                        found.push(reference);
+                    }
+                    map.values().for_each(|value| refs(value, found));
+                }
+                Value::Array(values) => values.iter().for_each(|value| refs(value, found)),
+                _ => {}
+            }
+        }
+
+        let spec = openapi_spec();
+        let quota = &spec["paths"]["/cgi-bin/openapi/quota/get"]["post"];
+        assert_eq!(
+            quota["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/GetApiQuotaRequest"
+        );
+        assert_eq!(
+            quota["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/ApiQuotaResponse"
+        );
+        let schemas = &spec["components"]["schemas"];
+        assert_eq!(
+            schemas["GetApiQuotaRequest"]["required"],
+            json!(["cgi_path"])
+        );
+        assert!(schemas["ApiQuotaResponse"]["properties"]["quota"].is_object());
+
+        let qrcode = &spec["paths"]["/wxa/getwxacodeunlimit"]["post"];
+        assert_eq!(
+            qrcode["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/UnlimitQrcodeOptions"
+        );
+        assert_eq!(
+            qrcode["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/WechatResponse"
+        );
+
+        let mut found = Vec::new();
+        refs(&spec, &mut found);
+        for reference in found {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            assert!(schemas[name].is_object(), "unresolved {reference}");
+        }
     }
 }
@@ -11,6 +11,7 @@ use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct GetVerifyIdRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -18,12 +19,22 @@ pub struct GetVerifyIdRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct QueryVerifyInfoRequest {
     pub verify_token: String,
 }
 
+impl QueryVerifyInfoRequest {
+    pub fn new(verify_token: impl Into<String>) -> Self {
+        Self {
+            verify_token: verify_token.into(),
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FaceResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -42,6 +53,7 @@ impl FaceApi {
         Self { context }
     }
 
+    #[doc(alias = "获取用户人脸核身会话唯一标识")]
     pub async fn get_verify_id(
         &self,
         request: &GetVerifyIdRequest,
@@ -50,6 +62,7 @@ impl FaceApi {
             .await
     }
 
+    #[doc(alias = "获取用户人脸核身验证结果")]
     pub async fn query_verify_info(
         &self,
         request: &QueryVerifyInfoRequest,
@@ -79,6 +92,12 @@ impl WechatApi for FaceApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<GetVerifyIdRequest, FaceResponse>("face.getVerifyId");
+    schemas.add::<QueryVerifyInfoRequest, FaceResponse>("face.queryVerifyInfo");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
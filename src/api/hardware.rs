@@ -11,6 +11,7 @@ use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct HardwareRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -18,6 +19,7 @@ pub struct HardwareRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct HardwareResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -36,6 +38,7 @@ impl HardwareApi {
         Self { context }
     }
 
+    #[doc(alias = "发送设备消息")]
     pub async fn send_hardware_device_message(
         &self,
         request: &HardwareRequest,
@@ -44,6 +47,7 @@ impl HardwareApi {
             .await
     }
 
+    #[doc(alias = "获取设备票据")]
     pub async fn get_sn_ticket(
         &self,
         request: &HardwareRequest,
@@ -52,6 +56,7 @@ impl HardwareApi {
             .await
     }
 
+    #[doc(alias = "创建设备组")]
     pub async fn create_iot_group_id(
         &self,
         request: &HardwareRequest,
@@ -60,6 +65,7 @@ impl HardwareApi {
             .await
     }
 
+    #[doc(alias = "查询设备组信息")]
     pub async fn get_iot_group_info(
         &self,
         request: &HardwareRequest,
@@ -68,6 +74,7 @@ impl HardwareApi {
             .await
     }
 
+    #[doc(alias = "设备组添加设备")]
     pub async fn add_iot_group_device(
         &self,
         request: &HardwareRequest,
@@ -76,6 +83,7 @@ impl HardwareApi {
             .await
     }
 
+    #[doc(alias = "设备组删除设备")]
     pub async fn remove_iot_group_device(
         &self,
         request: &HardwareRequest,
@@ -105,6 +113,16 @@ impl WechatApi for HardwareApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<HardwareRequest, HardwareResponse>("hardware.sendHardwareDeviceMessage");
+    schemas.add::<HardwareRequest, HardwareResponse>("hardware.getSnTicket");
+    schemas.add::<HardwareRequest, HardwareResponse>("hardware.createIotGroupId");
+    schemas.add::<HardwareRequest, HardwareResponse>("hardware.getIotGroupInfo");
+    schemas.add::<HardwareRequest, HardwareResponse>("hardware.addIotGroupDevice");
+    schemas.add::<HardwareRequest, HardwareResponse>("hardware.removeIotGroupDevice");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
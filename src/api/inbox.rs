@@ -0,0 +1,257 @@
+//! Replay-safe store for pushed results
+//!
+//! WeChat delivers callbacks such as async media check results
+//! (`wxa_media_check`) at least once, so the same `trace_id` can arrive
+//! several times. A [`ResultInbox`] records each `trace_id` so only the first
+//! delivery is processed and later ones are recognised as replays.
+//!
+//! ```
+//! use wechat_mp_sdk::api::inbox::{process_once, MemoryResultInbox};
+//!
+//! # async fn handle(trace_id: &str) -> Result<(), wechat_mp_sdk::WechatError> {
+//! let inbox = MemoryResultInbox::new();
+//! let processed = process_once(&inbox, trace_id, || async {
+//!     // act on the result
+//!     Ok(())
+//! })
+//! .await?;
+//! if processed.is_none() {
+//!     // replay: already handled or being handled
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::WechatError;
+use crate::utils::lock;
+
+/// How long [`MemoryResultInbox`] remembers a `trace_id` by default
+pub const DEFAULT_INBOX_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Outcome of [`ResultInbox::claim`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// First delivery; the caller now owns processing it
+    New,
+    /// The `trace_id` was seen before
+    Replay {
+        /// Whether processing of an earlier delivery completed
+        processed: bool,
+        /// Deliveries seen so far, including this one
+        deliveries: u32,
+    },
+}
+
+impl Delivery {
+    pub fn is_new(&self) -> bool {
+        matches!(self, Delivery::New)
+    }
+}
+
+/// Future returned by [`ResultInbox`] methods
+pub type ResultInboxFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<T, WechatError>> + Send + 'a>>;
+
+/// Idempotency store for pushed results, keyed by `trace_id`
+///
+/// Implement this over shared storage when several processes receive
+/// callbacks; [`MemoryResultInbox`] covers a single process.
+pub trait ResultInbox: Send + Sync {
+    /// Record a delivery of `trace_id`.
+    ///
+    /// Must be atomic: of concurrent claims for one `trace_id`, exactly one
+    /// returns [`Delivery::New`].
+    fn claim<'a>(&'a self, trace_id: &'a str) -> ResultInboxFuture<'a, Delivery>;
+
+    /// Mark a claimed `trace_id` as fully processed.
+    fn complete<'a>(&'a self, trace_id: &'a str) -> ResultInboxFuture<'a, ()>;
+
+    /// Forget a claimed `trace_id` after failed processing, so the next
+    /// delivery is treated as new.
+    fn release<'a>(&'a self, trace_id: &'a str) -> ResultInboxFuture<'a, ()>;
+}
+
+/// Run `process` only for the first delivery of `trace_id`.
+///
+/// Returns `None` for replays. On success the `trace_id` is completed; on
+/// error it is released so WeChat's redelivery is processed again.
+pub async fn process_once<T, F, Fut>(
+    inbox: &dyn ResultInbox,
+    trace_id: &str,
+    process: F,
+) -> Result<Option<T>, WechatError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, WechatError>>,
+{
+    if !inbox.claim(trace_id).await?.is_new() {
+        return Ok(None);
+    }
+    match process().await {
+        Ok(output) => {
+            inbox.complete(trace_id).await?;
+            Ok(Some(output))
+        }
+        Err(error) => {
+            inbox.release(trace_id).await?;
+            Err(error)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct InboxEntry {
+    first_seen: Instant,
+    deliveries: u32,
+    processed: bool,
+}
+
+/// In-process [`ResultInbox`]; cloning shares the records
+///
+/// Entries are kept for the retention period (default
+/// [`DEFAULT_INBOX_RETENTION`]) and dropped by [`purge_expired`](Self::purge_expired)
+/// or the next claim after they expire.
+#[derive(Debug, Clone)]
+pub struct MemoryResultInbox {
+    entries: Arc<Mutex<HashMap<String, InboxEntry>>>,
+    retention: Duration,
+}
+
+impl Default for MemoryResultInbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryResultInbox {
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::default(),
+            retention: DEFAULT_INBOX_RETENTION,
+        }
+    }
+
+    /// Keep each `trace_id` for `retention` after its first delivery
+    pub fn retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Drop entries older than the retention period
+    pub fn purge_expired(&self) {
+        let retention = self.retention;
+        lock(&self.entries).retain(|_, entry| entry.first_seen.elapsed() < retention);
+    }
+
+    /// Number of remembered `trace_id`s
+    pub fn len(&self) -> usize {
+        lock(&self.entries).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        lock(&self.entries).is_empty()
+    }
+}
+
+impl ResultInbox for MemoryResultInbox {
+    fn claim<'a>(&'a self, trace_id: &'a str) -> ResultInboxFuture<'a, Delivery> {
+        let mut entries = lock(&self.entries);
+        let delivery = match entries.get_mut(trace_id) {
+            Some(entry) if entry.first_seen.elapsed() < self.retention => {
+                entry.deliveries += 1;
+                Delivery::Replay {
+                    processed: entry.processed,
+                    deliveries: entry.deliveries,
+                }
+            }
+            _ => {
+                entries.insert(
+                    trace_id.to_string(),
+                    InboxEntry {
+                        first_seen: Instant::now(),
+                        deliveries: 1,
+                        processed: false,
+                    },
+                );
+                Delivery::New
+            }
+        };
+        Box::pin(async move { Ok(delivery) })
+    }
+
+    fn complete<'a>(&'a self, trace_id: &'a str) -> ResultInboxFuture<'a, ()> {
+        if let Some(entry) = lock(&self.entries).get_mut(trace_id) {
+            entry.processed = true;
+        }
+        Box::pin(async { Ok(()) })
+    }
+
+    fn release<'a>(&'a self, trace_id: &'a str) -> ResultInboxFuture<'a, ()> {
+        lock(&self.entries).remove(trace_id);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_replayed_trace_id_is_processed_once() {
+        let inbox = MemoryResultInbox::new();
+        let mut runs = 0;
+        let mut outputs = Vec::new();
+
+        for _ in 0..3 {
+            let output = process_once(&inbox, "trace_1", || {
+                runs += 1;
+                async { Ok(runs) }
+            })
+            .await
+            .unwrap();
+            outputs.push(output);
+        }
+
+        assert_eq!(outputs, [Some(1), None, None]);
+        assert_eq!(
+            inbox.claim("trace_1").await.unwrap(),
+            Delivery::Replay {
+                processed: true,
+                deliveries: 4
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_processing_is_released_for_redelivery() {
+        let inbox = MemoryResultInbox::new();
+
+        let failed: Result<Option<()>, _> = process_once(&inbox, "trace_2", || async {
+            Err(WechatError::Config("downstream unavailable".to_string()))
+        })
+        .await;
+        assert!(failed.is_err());
+        assert!(inbox.is_empty());
+
+        let retried = process_once(&inbox, "trace_2", || async { Ok("done") })
+            .await
+            .unwrap();
+        assert_eq!(retried, Some("done"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_forgotten() {
+        let inbox = MemoryResultInbox::new().retention(Duration::ZERO);
+        assert!(inbox.claim("trace_3").await.unwrap().is_new());
+        assert!(inbox.claim("trace_3").await.unwrap().is_new());
+
+        inbox.purge_expired();
+        assert!(inbox.is_empty());
+    }
+}
@@ -1,16 +1,26 @@
 //! Live Streaming API
+//!
+//! Errors from the live component are returned as [`WechatError::Live`]
+//! with a remediation hint; see [`LIVE_ERRCODES`] and
+//! [`LiveApi::check_prerequisites`].
+//!
+//! [`LiveApi::import_goods`] syncs a product catalog into the goods library,
+//! adding only products not already approved or in review.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::endpoint_inventory::TimeoutClass;
 use super::{WechatApi, WechatContext};
 use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LiveRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -18,12 +28,14 @@ pub struct LiveRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DeleteRoomRequest {
     pub id: i32,
 }
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct GetLiveInfoRequest {
     pub start: i32,
     pub limit: i32,
@@ -31,6 +43,7 @@ pub struct GetLiveInfoRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LiveResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -40,6 +53,411 @@ pub struct LiveResponse {
     pub extra: HashMap<String, Value>,
 }
 
+/// Sharing assets of a live room
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LiveSharedCodeResponse {
+    /// Sharing QR code image URL
+    #[serde(rename = "cdnUrl", alias = "cdn_url", default)]
+    pub cdn_url: String,
+    /// Mini program page path of the room
+    #[serde(rename = "pagePath", alias = "page_path", default)]
+    pub page_path: String,
+    /// Sharing poster image URL
+    #[serde(rename = "posterUrl", alias = "poster_url", default)]
+    pub poster_url: String,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+/// Review state of live goods, as filtered by `goods/getapproved`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LiveGoodsStatus {
+    Unreviewed,
+    Reviewing,
+    Approved,
+    Rejected,
+}
+
+impl LiveGoodsStatus {
+    fn code(self) -> i32 {
+        match self {
+            LiveGoodsStatus::Unreviewed => 0,
+            LiveGoodsStatus::Reviewing => 1,
+            LiveGoodsStatus::Approved => 2,
+            LiveGoodsStatus::Rejected => 3,
+        }
+    }
+}
+
+/// A goods item in the live goods library
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LiveGoods {
+    #[serde(rename = "goodsId", default)]
+    pub goods_id: i64,
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "coverImgUrl", default)]
+    pub cover_img_url: String,
+    /// Mini program page of the product
+    #[serde(default)]
+    pub url: String,
+    /// 1 fixed, 2 range, 3 discount; see [`LiveGoodsPrice`]
+    #[serde(rename = "priceType", default)]
+    pub price_type: i32,
+    #[serde(default)]
+    pub price: f64,
+    #[serde(default)]
+    pub price2: f64,
+    #[serde(rename = "thirdPartyAppid", default)]
+    pub third_party_appid: String,
+}
+
+/// Response of `goods/getapproved`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LiveGoodsListResponse {
+    /// Goods with the requested status, regardless of paging
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub goods: Vec<LiveGoods>,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+/// Price of a live goods item, in yuan
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LiveGoodsPrice {
+    Fixed(f64),
+    Range {
+        low: f64,
+        high: f64,
+    },
+    /// `original` is shown struck through next to `price`
+    Discount {
+        original: f64,
+        price: f64,
+    },
+}
+
+impl LiveGoodsPrice {
+    /// `(priceType, price, price2)`
+    fn fields(self) -> (i32, f64, f64) {
+        match self {
+            LiveGoodsPrice::Fixed(price) => (1, price, 0.0),
+            LiveGoodsPrice::Range { low, high } => (2, low, high),
+            LiveGoodsPrice::Discount { original, price } => (3, original, price),
+        }
+    }
+}
+
+/// A catalog product to import with [`LiveApi::import_goods`]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoodsSpec {
+    /// Product id in our catalog
+    pub out_product_id: String,
+    pub name: String,
+    /// `media_id` of the cover image, from `upload_temp_media`
+    pub cover_img_media_id: String,
+    pub price: LiveGoodsPrice,
+    /// Mini program page of the product; identifies it in the library
+    pub url: String,
+    /// Appid of the mini program owning `url`, when it is not this one
+    pub third_party_appid: Option<String>,
+}
+
+impl GoodsSpec {
+    pub fn new(
+        out_product_id: impl Into<String>,
+        name: impl Into<String>,
+        cover_img_media_id: impl Into<String>,
+        price: LiveGoodsPrice,
+        url: impl Into<String>,
+    ) -> Self {
+        Self {
+            out_product_id: out_product_id.into(),
+            name: name.into(),
+            cover_img_media_id: cover_img_media_id.into(),
+            price,
+            url: url.into(),
+            third_party_appid: None,
+        }
+    }
+
+    pub fn third_party_appid(mut self, appid: impl Into<String>) -> Self {
+        self.third_party_appid = Some(appid.into());
+        self
+    }
+}
+
+/// Body of `goods/add`
+#[derive(Serialize)]
+struct AddGoodsBody<'a> {
+    #[serde(rename = "goodsInfo")]
+    goods_info: GoodsInfo<'a>,
+}
+
+#[derive(Serialize)]
+struct GoodsInfo<'a> {
+    #[serde(rename = "coverImgUrl")]
+    cover_img_url: &'a str,
+    name: &'a str,
+    #[serde(rename = "priceType")]
+    price_type: i32,
+    price: f64,
+    price2: f64,
+    url: &'a str,
+    #[serde(rename = "thirdPartyAppid", skip_serializing_if = "Option::is_none")]
+    third_party_appid: Option<&'a str>,
+}
+
+impl<'a> From<&'a GoodsSpec> for AddGoodsBody<'a> {
+    fn from(spec: &'a GoodsSpec) -> Self {
+        let (price_type, price, price2) = spec.price.fields();
+        Self {
+            goods_info: GoodsInfo {
+                cover_img_url: &spec.cover_img_media_id,
+                name: &spec.name,
+                price_type,
+                price,
+                price2,
+                url: &spec.url,
+                third_party_appid: spec.third_party_appid.as_deref(),
+            },
+        }
+    }
+}
+
+/// Outcome of [`LiveApi::import_goods`], in catalog order within each list
+#[non_exhaustive]
+#[derive(Debug, Clone, Default)]
+pub struct GoodsImportReport {
+    /// Products added and submitted for review
+    pub added: Vec<ImportedGoods>,
+    pub skipped: Vec<SkippedGoods>,
+    /// Products whose `goods/add` call failed; the rest were still imported
+    pub failed: Vec<FailedGoods>,
+}
+
+impl GoodsImportReport {
+    /// Whether every product is now in the library
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedGoods {
+    pub out_product_id: String,
+    pub goods_id: i64,
+    pub audit_id: i64,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedGoods {
+    pub out_product_id: String,
+    pub reason: GoodsSkipReason,
+}
+
+/// Why [`LiveApi::import_goods`] did not add a product
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoodsSkipReason {
+    /// The library already has goods with the product's `url`
+    Existing {
+        goods_id: i64,
+        status: LiveGoodsStatus,
+    },
+    /// An earlier catalog entry has the same `out_product_id` or `url`
+    DuplicateInCatalog,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct FailedGoods {
+    pub out_product_id: String,
+    pub error: WechatError,
+}
+
+/// Page size of `goods/getapproved`, which allows at most 100
+const GOODS_PAGE_LIMIT: u32 = 100;
+
+/// errcode of `getliveinfo` when the account has no live rooms yet
+const NO_ROOMS: i32 = 1;
+
+/// A live component errcode with its meaning and fix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiveErrcode {
+    pub code: i32,
+    pub reason: &'static str,
+    pub remediation: &'static str,
+}
+
+const fn errcode(code: i32, reason: &'static str, remediation: &'static str) -> LiveErrcode {
+    LiveErrcode {
+        code,
+        reason,
+        remediation,
+    }
+}
+
+/// Errcodes mapped to [`WechatError::Live`]
+pub const LIVE_ERRCODES: &[LiveErrcode] = &[
+    errcode(
+        48001,
+        "no live permission",
+        "enable the live component for this mini program in the admin console",
+    ),
+    errcode(
+        300001,
+        "creating or editing is forbidden",
+        "check that the live permission is active and the account is not restricted",
+    ),
+    errcode(
+        300002,
+        "name length is invalid",
+        "keep goods names within 3–14 characters (one CJK character counts as two)",
+    ),
+    errcode(
+        300003,
+        "price is invalid",
+        "send prices in yuan with at most two decimals; price2 must exceed price for range prices",
+    ),
+    errcode(
+        300004,
+        "goods name violates content rules",
+        "rename the goods",
+    ),
+    errcode(
+        300005,
+        "goods image violates content rules",
+        "replace the goods image",
+    ),
+    errcode(
+        300006,
+        "image upload failed",
+        "upload the image again as temporary media and retry with the new media_id",
+    ),
+    errcode(
+        300007,
+        "page path is not in the released version",
+        "use a page path that exists in the online version of the mini program",
+    ),
+    errcode(300008, "adding goods failed", "retry later"),
+    errcode(300009, "withdrawing the goods review failed", "retry later"),
+    errcode(
+        300010,
+        "goods review state does not allow this operation",
+        "query the goods state before withdrawing or resubmitting",
+    ),
+    errcode(
+        300011,
+        "goods were not created through the API",
+        "manage goods created in the admin console there",
+    ),
+    errcode(
+        300012,
+        "no review quota left today",
+        "wait for the daily quota of 500 submissions to reset",
+    ),
+    errcode(300013, "submitting for review failed", "retry later"),
+    errcode(
+        300014,
+        "goods under review cannot be deleted",
+        "withdraw the review first",
+    ),
+    errcode(
+        300017,
+        "goods were never submitted for review",
+        "submit the goods for review first",
+    ),
+    errcode(
+        300018,
+        "goods image is too large",
+        "use an image of at most 300x300 pixels",
+    ),
+    errcode(
+        300021,
+        "goods were added but failed review",
+        "fix the goods details and resubmit",
+    ),
+    errcode(300022, "room does not exist", "check the room id"),
+    errcode(
+        300023,
+        "room state does not allow this operation",
+        "only edit rooms that have not started",
+    ),
+    errcode(300024, "goods do not exist", "check the goods id"),
+    errcode(
+        300025,
+        "goods have not passed review",
+        "import only approved goods",
+    ),
+    errcode(
+        300026,
+        "room goods list is full",
+        "remove goods from the room first",
+    ),
+    errcode(300027, "importing goods failed", "retry later"),
+    errcode(
+        300028,
+        "room name violates content rules",
+        "rename the room",
+    ),
+    errcode(
+        300029,
+        "anchor nickname violates content rules",
+        "change the anchor nickname",
+    ),
+    errcode(
+        300030,
+        "anchor WeChat ID is invalid",
+        "check the anchor WeChat ID",
+    ),
+    errcode(
+        300031,
+        "room cover image violates content rules",
+        "replace the cover image",
+    ),
+    errcode(
+        300032,
+        "room share image violates content rules",
+        "replace the share image",
+    ),
+    errcode(300033, "too many goods for this room", "add fewer goods"),
+    errcode(
+        300034,
+        "anchor nickname length is invalid",
+        "keep the anchor nickname within 2–15 characters",
+    ),
+    errcode(
+        300035,
+        "anchor WeChat ID does not exist",
+        "check the anchor WeChat ID",
+    ),
+    errcode(
+        300036,
+        "anchor WeChat ID has not completed real-name verification",
+        "have the anchor complete real-name verification in WeChat",
+    ),
+];
+
+/// Look up a live component errcode in [`LIVE_ERRCODES`]
+pub fn live_errcode(code: i32) -> Option<&'static LiveErrcode> {
+    LIVE_ERRCODES.iter().find(|known| known.code == code)
+}
+
 pub struct LiveApi {
     context: Arc<WechatContext>,
 }
@@ -49,11 +467,13 @@ impl LiveApi {
         Self { context }
     }
 
+    #[doc(alias = "创建直播间")]
     pub async fn create_room(&self, request: &LiveRequest) -> Result<LiveResponse, WechatError> {
         self.post_json("/wxaapi/broadcast/room/create", request)
             .await
     }
 
+    #[doc(alias = "删除直播间")]
     pub async fn delete_room(
         &self,
         request: &DeleteRoomRequest,
@@ -62,11 +482,14 @@ impl LiveApi {
             .await
     }
 
+    #[doc(alias = "编辑直播间")]
     pub async fn edit_room(&self, request: &LiveRequest) -> Result<LiveResponse, WechatError> {
         self.post_json("/wxaapi/broadcast/room/editroom", request)
             .await
     }
 
+    #[doc(alias = "获取直播间列表")]
+    #[doc(alias = "获取直播间回放")]
     pub async fn get_live_info(
         &self,
         request: &GetLiveInfoRequest,
@@ -74,10 +497,12 @@ impl LiveApi {
         self.post_json("/wxa/business/getliveinfo", request).await
     }
 
+    #[doc(alias = "商品添加并提审")]
     pub async fn add_goods(&self, request: &LiveRequest) -> Result<LiveResponse, WechatError> {
         self.post_json("/wxaapi/broadcast/goods/add", request).await
     }
 
+    #[doc(alias = "更新商品")]
     pub async fn update_goods_info(
         &self,
         request: &LiveRequest,
@@ -86,6 +511,7 @@ impl LiveApi {
             .await
     }
 
+    #[doc(alias = "删除商品")]
     pub async fn delete_goods_info(
         &self,
         request: &LiveRequest,
@@ -94,27 +520,215 @@ impl LiveApi {
             .await
     }
 
+    #[doc(alias = "向长期订阅用户群发直播间开始事件")]
     pub async fn push_message(&self, request: &LiveRequest) -> Result<LiveResponse, WechatError> {
         self.post_json("/wxaapi/broadcast/subscribe/send", request)
             .await
     }
 
+    #[doc(alias = "获取长期订阅用户")]
     pub async fn get_followers(&self, request: &LiveRequest) -> Result<LiveResponse, WechatError> {
         self.post_json("/wxaapi/broadcast/subscribe/get", request)
             .await
     }
 
+    /// Get the sharing QR code and poster of a live room
+    ///
+    /// GET /wxaapi/broadcast/room/getsharedcode?access_token=ACCESS_TOKEN
+    ///
+    /// # Arguments
+    /// * `room_id` - Live room id
+    /// * `params` - Custom parameters appended to the room page path
+    #[doc(alias = "获取直播间分享二维码")]
+    pub async fn get_shared_code(
+        &self,
+        room_id: i64,
+        params: Option<&str>,
+    ) -> Result<LiveSharedCodeResponse, WechatError> {
+        let room_id = room_id.to_string();
+        let mut query = vec![("roomId", room_id.as_str())];
+        if let Some(params) = params {
+            query.push(("params", params));
+        }
+        self.context
+            .authed_get("/wxaapi/broadcast/room/getsharedcode", &query)
+            .await
+            .and_then(|response: LiveSharedCodeResponse| {
+                WechatError::check_api(response.errcode, &response.errmsg)?;
+                Ok(response)
+            })
+            .map_err(WechatError::into_live_error)
+    }
+
+    /// Download the poster image referenced by [`get_shared_code`](Self::get_shared_code)
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Config`] when the response has no poster URL.
+    pub async fn download_shared_poster(
+        &self,
+        shared: &LiveSharedCodeResponse,
+    ) -> Result<Bytes, WechatError> {
+        if shared.poster_url.is_empty() {
+            return Err(WechatError::Config(
+                "live shared code response has no posterUrl".to_string(),
+            ));
+        }
+        let client = &self.context.client;
+        let request = client
+            .http()
+            .get(&shared.poster_url)
+            .timeout(client.class_timeout(TimeoutClass::Slow))
+            .build()?;
+        let response = client.send_external(request).await?;
+        client.binary_body(response).await
+    }
+
+    /// Check that the account can use the live component
+    ///
+    /// Lists one room; an account with no rooms yet passes.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Live`] with a remediation hint when the
+    /// account lacks live permission, or the underlying error.
+    pub async fn check_prerequisites(&self) -> Result<(), WechatError> {
+        let request = GetLiveInfoRequest { start: 0, limit: 1 };
+        match self.get_live_info(&request).await {
+            Ok(_) => Ok(()),
+            Err(WechatError::Api { code: NO_ROOMS, .. }) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// List goods of the library with the given review status
+    ///
+    /// GET /wxaapi/broadcast/goods/getapproved?access_token=ACCESS_TOKEN
+    ///
+    /// # Arguments
+    /// * `offset` - Index of the first goods item
+    /// * `limit` - Page size, at most 100
+    #[doc(alias = "获取商品列表")]
+    pub async fn get_approved_goods(
+        &self,
+        offset: u32,
+        limit: u32,
+        status: LiveGoodsStatus,
+    ) -> Result<LiveGoodsListResponse, WechatError> {
+        let offset = offset.to_string();
+        let limit = limit.to_string();
+        let status = status.code().to_string();
+        let query = [
+            ("offset", offset.as_str()),
+            ("limit", limit.as_str()),
+            ("status", status.as_str()),
+        ];
+        self.context
+            .authed_get("/wxaapi/broadcast/goods/getapproved", &query)
+            .await
+            .and_then(|response: LiveGoodsListResponse| {
+                WechatError::check_api(response.errcode, &response.errmsg)?;
+                Ok(response)
+            })
+            .map_err(WechatError::into_live_error)
+    }
+
+    /// Add catalog products missing from the goods library
+    ///
+    /// WeChat keeps no external id for live goods, so a product is
+    /// recognised by its page `url`, which should carry the
+    /// `out_product_id` (e.g. `pages/goods/detail?id=123`). Goods approved
+    /// or still in review count as existing; rejected ones do not, so a
+    /// fixed product is submitted again. Later catalog entries repeating an
+    /// `out_product_id` or `url` are skipped. Products are added one at a
+    /// time and a failed add does not stop the import.
+    ///
+    /// # Errors
+    /// Returns the error of listing the library; nothing is added then.
+    /// Errors of individual adds are in [`GoodsImportReport::failed`].
+    pub async fn import_goods(
+        &self,
+        catalog: Vec<GoodsSpec>,
+    ) -> Result<GoodsImportReport, WechatError> {
+        let mut existing = HashMap::new();
+        for status in [LiveGoodsStatus::Approved, LiveGoodsStatus::Reviewing] {
+            let mut offset = 0;
+            loop {
+                let page = self
+                    .get_approved_goods(offset, GOODS_PAGE_LIMIT, status)
+                    .await?;
+                let fetched = page.goods.len() as u32;
+                for goods in page.goods {
+                    existing
+                        .entry(goods_key(&goods.url).to_string())
+                        .or_insert((goods.goods_id, status));
+                }
+                offset += fetched;
+                if fetched == 0 || u64::from(offset) >= page.total {
+                    break;
+                }
+            }
+        }
+
+        let mut report = GoodsImportReport::default();
+        let mut seen_ids = HashSet::new();
+        let mut seen_urls = HashSet::new();
+        for spec in catalog {
+            let key = goods_key(&spec.url).to_string();
+            if let Some(&(goods_id, status)) = existing.get(&key) {
+                report.skipped.push(SkippedGoods {
+                    out_product_id: spec.out_product_id,
+                    reason: GoodsSkipReason::Existing { goods_id, status },
+                });
+                continue;
+            }
+            if !seen_ids.insert(spec.out_product_id.clone()) || !seen_urls.insert(key) {
+                report.skipped.push(SkippedGoods {
+                    out_product_id: spec.out_product_id,
+                    reason: GoodsSkipReason::DuplicateInCatalog,
+                });
+                continue;
+            }
+            match self
+                .post_json("/wxaapi/broadcast/goods/add", &AddGoodsBody::from(&spec))
+                .await
+            {
+                Ok(response) => {
+                    let id = |name: &str| response.extra.get(name).and_then(Value::as_i64);
+                    report.added.push(ImportedGoods {
+                        goods_id: id("goodsId").unwrap_or_default(),
+                        audit_id: id("auditId").unwrap_or_default(),
+                        out_product_id: spec.out_product_id,
+                    });
+                }
+                Err(error) => report.failed.push(FailedGoods {
+                    out_product_id: spec.out_product_id,
+                    error,
+                }),
+            }
+        }
+        Ok(report)
+    }
+
     async fn post_json<B: Serialize>(
         &self,
         endpoint: &str,
         body: &B,
     ) -> Result<LiveResponse, WechatError> {
-        let response: LiveResponse = self.context.authed_post(endpoint, body).await?;
-        WechatError::check_api(response.errcode, &response.errmsg)?;
-        Ok(response)
+        self.context
+            .authed_post(endpoint, body)
+            .await
+            .and_then(|response: LiveResponse| {
+                WechatError::check_api(response.errcode, &response.errmsg)?;
+                Ok(response)
+            })
+            .map_err(WechatError::into_live_error)
     }
 }
 
+/// Page path compared by [`LiveApi::import_goods`]
+fn goods_key(url: &str) -> &str {
+    url.trim().trim_start_matches('/')
+}
+
 impl WechatApi for LiveApi {
     fn context(&self) -> &WechatContext {
         &self.context
@@ -125,6 +739,21 @@ impl WechatApi for LiveApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<LiveRequest, LiveResponse>("live.createRoom");
+    schemas.add::<DeleteRoomRequest, LiveResponse>("live.deleteRoom");
+    schemas.add::<LiveRequest, LiveResponse>("live.editRoom");
+    schemas.add::<GetLiveInfoRequest, LiveResponse>("live.getLiveInfo");
+    schemas.add::<LiveRequest, LiveResponse>("live.addGoods");
+    schemas.add::<LiveRequest, LiveResponse>("live.updateGoodsInfo");
+    schemas.add::<LiveRequest, LiveResponse>("live.deleteGoodsInfo");
+    schemas.add::<LiveRequest, LiveResponse>("live.pushMessage");
+    schemas.add::<LiveRequest, LiveResponse>("live.getFollowers");
+    schemas.response::<LiveSharedCodeResponse>("live.getSharedCode");
+    schemas.response::<LiveGoodsListResponse>("live.getApprovedGoods");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -136,4 +765,285 @@ mod tests {
         assert_eq!(response.errcode, 0);
         assert!(response.extra.contains_key("roomid"));
     }
+
+    #[tokio::test]
+    async fn test_get_shared_code_and_download_poster() {
+        use crate::client::WechatClient;
+        use crate::middleware::AuthMiddleware;
+        use crate::token::TokenManager;
+        use crate::types::{AppId, AppSecret};
+        use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/wxaapi/broadcast/room/getsharedcode"))
+            .and(query_param("access_token", "test_token"))
+            .and(query_param("roomId", "39"))
+            .and(query_param("params", "from=ad"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "cdnUrl": "https://cdn.example.com/code.jpg",
+                "pagePath": "plugin-private://wx2b03c6e691cd7370/pages/live-player-plugin?room_id=39",
+                "posterUrl": format!("{}/poster.jpg", mock_server.uri())
+            })))
+            .mount(&mock_server)
+            .await;
+        // The CDN poster URL must not receive the access token
+        Mock::given(method("GET"))
+            .and(path("/poster.jpg"))
+            .and(query_param_is_missing("access_token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "image/jpeg")
+                    .set_body_bytes(b"\xff\xd8poster".to_vec()),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let builder = || {
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(mock_server.uri())
+        };
+        let token_manager = Arc::new(TokenManager::new(builder().build().unwrap()));
+        let client = Arc::new(
+            builder()
+                .with_middleware(AuthMiddleware::new(Arc::clone(&token_manager)))
+                .build()
+                .unwrap(),
+        );
+        let api = LiveApi::new(Arc::new(WechatContext::new(client, token_manager)));
+
+        let shared = api.get_shared_code(39, Some("from=ad")).await.unwrap();
+        assert_eq!(shared.cdn_url, "https://cdn.example.com/code.jpg");
+        assert!(shared.page_path.ends_with("room_id=39"));
+
+        let poster = api.download_shared_poster(&shared).await.unwrap();
+        assert_eq!(poster, &b"\xff\xd8poster"[..]);
+    }
+
+    #[test]
+    fn live_errcodes_cover_component_family() {
+        assert!(live_errcode(300001).is_some());
+        assert!(live_errcode(300036).is_some());
+        assert!(live_errcode(300015).is_none());
+        assert!(live_errcode(40001).is_none());
+
+        let err = WechatError::api(300022, "room not exist rid: 1-2-3").into_live_error();
+        match &err {
+            WechatError::Live {
+                code: 300022,
+                reason,
+                rid,
+                ..
+            } => {
+                assert_eq!(*reason, "room does not exist");
+                assert_eq!(rid.as_deref(), Some("1-2-3"));
+            }
+            other => panic!("expected Live error, got {other:?}"),
+        }
+        assert!(err.to_string().contains("check the room id"));
+        assert!(matches!(
+            WechatError::api(40001, "invalid credential").into_live_error(),
+            WechatError::Api { code: 40001, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_prerequisites() {
+        use crate::client::WechatClient;
+        use crate::token::TokenManager;
+        use crate::types::{AppId, AppSecret};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/business/getliveinfo"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 1,
+                "errmsg": "no room"
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/business/getliveinfo"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 48001,
+                "errmsg": "api unauthorized"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(mock_server.uri())
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(TokenManager::new((*client).clone()));
+        let api = LiveApi::new(Arc::new(WechatContext::new(client, token_manager)));
+
+        api.check_prerequisites().await.unwrap();
+        let err = api.check_prerequisites().await.unwrap_err();
+        assert!(
+            matches!(err, WechatError::Live { code: 48001, .. }),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_goods_skips_existing_and_duplicates() {
+        use crate::client::WechatClient;
+        use crate::token::TokenManager;
+        use crate::types::{AppId, AppSecret};
+        use wiremock::matchers::{body_partial_json, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/wxaapi/broadcast/goods/getapproved"))
+            .and(query_param("status", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "total": 1,
+                "goods": [{"goodsId": 11, "name": "tea", "url": "/pages/goods/detail?id=1"}]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/wxaapi/broadcast/goods/getapproved"))
+            .and(query_param("status", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "total": 1,
+                "goods": [{"goodsId": 12, "name": "cup", "url": "pages/goods/detail?id=2"}]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxaapi/broadcast/goods/add"))
+            .and(body_partial_json(serde_json::json!({
+                "goodsInfo": {"url": "pages/goods/detail?id=3", "priceType": 2, "price": 5.0, "price2": 9.5}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "goodsId": 13,
+                "auditId": 513
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxaapi/broadcast/goods/add"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 300018,
+                "errmsg": "image size too large"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(mock_server.uri())
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(TokenManager::new((*client).clone()));
+        let api = LiveApi::new(Arc::new(WechatContext::new(client, token_manager)));
+
+        let spec = |id: &str, price| {
+            GoodsSpec::new(
+                id,
+                "goods",
+                "media",
+                price,
+                format!("pages/goods/detail?id={id}"),
+            )
+        };
+        let report = api
+            .import_goods(vec![
+                spec("1", LiveGoodsPrice::Fixed(1.0)),
+                spec("2", LiveGoodsPrice::Fixed(1.0)),
+                spec(
+                    "3",
+                    LiveGoodsPrice::Range {
+                        low: 5.0,
+                        high: 9.5,
+                    },
+                ),
+                spec("3", LiveGoodsPrice::Fixed(1.0)),
+                spec("4", LiveGoodsPrice::Fixed(1.0)),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(report.added.len(), 1);
+        assert_eq!(report.added[0].out_product_id, "3");
+        assert_eq!(report.added[0].goods_id, 13);
+        assert_eq!(report.added[0].audit_id, 513);
+        let reasons: Vec<_> = report
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.out_product_id.as_str(), skipped.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (
+                    "1",
+                    GoodsSkipReason::Existing {
+                        goods_id: 11,
+                        status: LiveGoodsStatus::Approved
+                    }
+                ),
+                (
+                    "2",
+                    GoodsSkipReason::Existing {
+                        goods_id: 12,
+                        status: LiveGoodsStatus::Reviewing
+                    }
+                ),
+                ("3", GoodsSkipReason::DuplicateInCatalog),
+            ]
+        );
+        assert!(!report.is_complete());
+        assert_eq!(report.failed[0].out_product_id, "4");
+        assert!(matches!(
+            report.failed[0].error,
+            WechatError::Live { code: 300018, .. }
+        ));
+    }
 }
@@ -6,18 +6,27 @@ use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::outbox::{self, OrderCreation, OrderOutbox};
 use super::{WechatApi, WechatContext};
 use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LogisticsRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
 }
 
+impl LogisticsRequest {
+    pub fn new(payload: HashMap<String, Value>) -> Self {
+        Self { payload }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LogisticsResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -27,6 +36,280 @@ pub struct LogisticsResponse {
     pub extra: HashMap<String, Value>,
 }
 
+/// Sender or receiver of an express order
+///
+/// WeChat needs at least one of `tel` and `mobile`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogisticsContact {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tel: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mobile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub company: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    pub province: String,
+    pub city: String,
+    pub area: String,
+    /// Street address below `area`
+    pub address: String,
+}
+
+/// Package contents and dimensions
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogisticsCargo {
+    /// Number of packages
+    pub count: u32,
+    /// Total weight in kg
+    pub weight: f64,
+    /// Length in cm
+    pub space_x: f64,
+    /// Width in cm
+    pub space_y: f64,
+    /// Height in cm
+    pub space_z: f64,
+    pub detail_list: Vec<CargoDetail>,
+}
+
+/// One kind of goods in a package
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CargoDetail {
+    pub name: String,
+    pub count: u32,
+}
+
+/// Goods shown in the logistics notification and on the waybill
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogisticsShop {
+    /// Mini program page opened from the notification
+    pub wxa_path: String,
+    /// Goods thumbnail URL
+    pub img_url: String,
+    pub goods_name: String,
+    pub goods_count: u32,
+}
+
+/// Shipping insurance; the default leaves the package uninsured
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LogisticsInsured {
+    /// 1 to insure the package
+    pub use_insured: i32,
+    /// Insured value in fen
+    pub insured_value: i64,
+}
+
+impl LogisticsInsured {
+    /// Insure the package for `insured_value` fen
+    pub fn new(insured_value: i64) -> Self {
+        Self {
+            use_insured: 1,
+            insured_value,
+        }
+    }
+}
+
+/// Carrier service type, from `get_all_delivery`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogisticsService {
+    pub service_type: i32,
+    pub service_name: String,
+}
+
+/// Body of `/cgi-bin/express/business/order/add`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AddOrderRequest {
+    /// 0 for an order placed in the mini program, 2 for one placed in an app
+    pub add_source: i32,
+    /// AppID of the app when `add_source` is 2
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wx_appid: Option<String>,
+    /// Shop order id, unique per shop
+    pub order_id: String,
+    /// Buyer's openid, required when `add_source` is 0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openid: Option<String>,
+    /// Carrier id, from `get_all_delivery`
+    pub delivery_id: String,
+    /// Account id with the carrier, from `get_all_account`
+    pub biz_id: String,
+    /// Remark printed on the waybill
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_remark: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tagid: Option<i64>,
+    pub sender: LogisticsContact,
+    pub receiver: LogisticsContact,
+    pub cargo: LogisticsCargo,
+    pub shop: LogisticsShop,
+    pub insured: LogisticsInsured,
+    pub service: LogisticsService,
+    /// Expected pickup time, Unix seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expect_time: Option<i64>,
+}
+
+impl AddOrderRequest {
+    /// Query for the order this request creates, once `waybill_id` is known
+    pub fn order_query(&self, waybill_id: impl Into<String>) -> GetOrderRequest {
+        GetOrderRequest {
+            order_id: self.order_id.clone(),
+            openid: self.openid.clone(),
+            delivery_id: self.delivery_id.clone(),
+            waybill_id: waybill_id.into(),
+        }
+    }
+}
+
+/// Key/value pair the carrier needs to print the waybill
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WaybillData {
+    #[serde(default)]
+    pub key: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+/// Response of `/cgi-bin/express/business/order/add`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AddOrderResponse {
+    #[serde(default)]
+    pub order_id: String,
+    /// Waybill number assigned by the carrier
+    #[serde(default)]
+    pub waybill_id: String,
+    #[serde(default)]
+    pub waybill_data: Vec<WaybillData>,
+    /// Carrier result, 0 on success
+    #[serde(default)]
+    pub delivery_resultcode: i32,
+    #[serde(default)]
+    pub delivery_resultmsg: String,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+/// Body of `/cgi-bin/express/business/order/get`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GetOrderRequest {
+    pub order_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openid: Option<String>,
+    pub delivery_id: String,
+    /// Omitted when empty, e.g. before the waybill number is known
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub waybill_id: String,
+}
+
+impl GetOrderRequest {
+    /// Query for the tracking path of the same waybill
+    pub fn path_query(&self) -> GetPathRequest {
+        GetPathRequest {
+            order_id: self.order_id.clone(),
+            openid: self.openid.clone(),
+            delivery_id: self.delivery_id.clone(),
+            waybill_id: self.waybill_id.clone(),
+        }
+    }
+}
+
+/// Response of `/cgi-bin/express/business/order/get`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GetOrderResponse {
+    #[serde(default)]
+    pub order_id: String,
+    #[serde(default)]
+    pub delivery_id: String,
+    #[serde(default)]
+    pub waybill_id: String,
+    /// Base64-encoded HTML of the waybill, for printing
+    #[serde(default)]
+    pub print_html: String,
+    #[serde(default)]
+    pub waybill_data: Vec<WaybillData>,
+    /// Order status reported by the carrier
+    #[serde(default)]
+    pub order_status: i32,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+impl From<GetOrderResponse> for AddOrderResponse {
+    /// The creation result of an order found by
+    /// [`LogisticsApi::create_or_get_express_order`]
+    fn from(order: GetOrderResponse) -> Self {
+        Self {
+            order_id: order.order_id,
+            waybill_id: order.waybill_id,
+            waybill_data: order.waybill_data,
+            ..Default::default()
+        }
+    }
+}
+
+/// Body of `/cgi-bin/express/business/path/get`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GetPathRequest {
+    pub order_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openid: Option<String>,
+    pub delivery_id: String,
+    pub waybill_id: String,
+}
+
+/// One tracking event of a waybill
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PathItem {
+    /// Unix seconds
+    #[serde(default)]
+    pub action_time: i64,
+    /// Event type, e.g. 100001 picked up, 300003 signed for, 400001 cancelled
+    #[serde(default)]
+    pub action_type: i32,
+    #[serde(default)]
+    pub action_msg: String,
+}
+
+/// Response of `/cgi-bin/express/business/path/get`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GetPathResponse {
+    #[serde(default)]
+    pub openid: String,
+    #[serde(default)]
+    pub delivery_id: String,
+    #[serde(default)]
+    pub waybill_id: String,
+    #[serde(default)]
+    pub path_item_num: u32,
+    #[serde(default)]
+    pub path_item_list: Vec<PathItem>,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
+impl GetPathResponse {
+    /// Most recent tracking event
+    pub fn latest(&self) -> Option<&PathItem> {
+        self.path_item_list
+            .iter()
+            .max_by_key(|item| item.action_time)
+    }
+}
+
 pub struct LogisticsApi {
     context: Arc<WechatContext>,
 }
@@ -76,6 +359,40 @@ impl LogisticsApi {
             .await
     }
 
+    /// Create an express order at most once per `order_id`.
+    ///
+    /// The shop `order_id` in `request` is the dedup key recorded in
+    /// `outbox`. If an earlier attempt may have created the order, it is
+    /// queried via [`get_order`](Self::get_order) before being re-created.
+    pub async fn create_or_get_order(
+        &self,
+        outbox: &dyn OrderOutbox,
+        request: &LogisticsRequest,
+    ) -> Result<OrderCreation<LogisticsResponse>, WechatError> {
+        let order_id = request
+            .payload
+            .get("order_id")
+            .and_then(Value::as_str)
+            .ok_or_else(|| WechatError::Config("add_order request has no order_id".to_string()))?;
+        let key = format!("express:{order_id}");
+        let query = LogisticsRequest {
+            payload: ["order_id", "openid", "delivery_id", "waybill_id"]
+                .into_iter()
+                .filter_map(|field| {
+                    let value = request.payload.get(field)?;
+                    Some((field.to_string(), value.clone()))
+                })
+                .collect(),
+        };
+        outbox::create_or_get(
+            outbox,
+            &key,
+            || self.get_order(&query),
+            || self.add_order(request),
+        )
+        .await
+    }
+
     pub async fn get_path(
         &self,
         request: &LogisticsRequest,
@@ -84,6 +401,65 @@ impl LogisticsApi {
             .await
     }
 
+    /// Typed [`add_order`](Self::add_order)
+    pub async fn add_express_order(
+        &self,
+        request: &AddOrderRequest,
+    ) -> Result<AddOrderResponse, WechatError> {
+        let response: AddOrderResponse = self
+            .context
+            .authed_post("/cgi-bin/express/business/order/add", request)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    /// Typed [`create_or_get_order`](Self::create_or_get_order)
+    ///
+    /// An existing order is queried by `order_id`, `openid` and
+    /// `delivery_id` and reported with its `waybill_id` and `waybill_data`.
+    pub async fn create_or_get_express_order(
+        &self,
+        outbox: &dyn OrderOutbox,
+        request: &AddOrderRequest,
+    ) -> Result<OrderCreation<AddOrderResponse>, WechatError> {
+        let key = format!("express:{}", request.order_id);
+        let query = request.order_query(String::new());
+        outbox::create_or_get(
+            outbox,
+            &key,
+            || async { self.get_express_order(&query).await.map(Into::into) },
+            || self.add_express_order(request),
+        )
+        .await
+    }
+
+    /// Typed [`get_order`](Self::get_order)
+    pub async fn get_express_order(
+        &self,
+        request: &GetOrderRequest,
+    ) -> Result<GetOrderResponse, WechatError> {
+        let response: GetOrderResponse = self
+            .context
+            .authed_post("/cgi-bin/express/business/order/get", request)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
+    /// Typed [`get_path`](Self::get_path)
+    pub async fn get_express_path(
+        &self,
+        request: &GetPathRequest,
+    ) -> Result<GetPathResponse, WechatError> {
+        let response: GetPathResponse = self
+            .context
+            .authed_post("/cgi-bin/express/business/path/get", request)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
     async fn post_json<B: Serialize>(
         &self,
         endpoint: &str,
@@ -105,6 +481,16 @@ impl WechatApi for LogisticsApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<LogisticsRequest, LogisticsResponse>("express.bindAccount");
+    schemas.add::<LogisticsRequest, LogisticsResponse>("express.getAllAccount");
+    schemas.add::<LogisticsRequest, LogisticsResponse>("express.getAllDelivery");
+    schemas.add::<LogisticsRequest, LogisticsResponse>("express.getOrder");
+    schemas.add::<LogisticsRequest, LogisticsResponse>("express.addOrder");
+    schemas.add::<LogisticsRequest, LogisticsResponse>("express.getPath");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,4 +501,72 @@ mod tests {
         let response: LogisticsResponse = serde_json::from_str(json).unwrap();
         assert_eq!(response.errcode, 0);
     }
+
+    #[test]
+    fn add_order_request_serializes_nested_sections() {
+        let request = AddOrderRequest {
+            order_id: "order-1".to_string(),
+            openid: Some("oBuyer".to_string()),
+            delivery_id: "SF".to_string(),
+            biz_id: "SF_CASH".to_string(),
+            sender: LogisticsContact {
+                name: "Sender".to_string(),
+                mobile: Some("13800000000".to_string()),
+                province: "广东省".to_string(),
+                city: "广州市".to_string(),
+                area: "海珠区".to_string(),
+                address: "新港中路 397 号".to_string(),
+                ..Default::default()
+            },
+            cargo: LogisticsCargo {
+                count: 1,
+                weight: 1.2,
+                detail_list: vec![CargoDetail {
+                    name: "T-shirt".to_string(),
+                    count: 2,
+                }],
+                ..Default::default()
+            },
+            insured: LogisticsInsured::new(10_000),
+            service: LogisticsService {
+                service_type: 0,
+                service_name: "标准快递".to_string(),
+            },
+            ..Default::default()
+        };
+
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["sender"]["mobile"], "13800000000");
+        assert!(body["sender"].get("tel").is_none());
+        assert!(body.get("wx_appid").is_none());
+        assert_eq!(body["cargo"]["detail_list"][0]["count"], 2);
+        assert_eq!(body["insured"]["use_insured"], 1);
+        assert_eq!(body["insured"]["insured_value"], 10_000);
+
+        let query = request.order_query("SF123").path_query();
+        assert_eq!(query.waybill_id, "SF123");
+        assert_eq!(query.openid.as_deref(), Some("oBuyer"));
+    }
+
+    #[test]
+    fn waybill_responses_deserialize() {
+        let added: AddOrderResponse = serde_json::from_str(
+            r#"{"errcode":0,"errmsg":"ok","order_id":"order-1","waybill_id":"SF123",
+                "waybill_data":[{"key":"SF_bagAddr","value":"广州"}],
+                "delivery_resultcode":0,"delivery_resultmsg":""}"#,
+        )
+        .unwrap();
+        assert_eq!(added.waybill_id, "SF123");
+        assert_eq!(added.waybill_data[0].key, "SF_bagAddr");
+
+        let path: GetPathResponse = serde_json::from_str(
+            r#"{"openid":"oBuyer","delivery_id":"SF","waybill_id":"SF123","path_item_num":2,
+                "path_item_list":[
+                    {"action_time":1533052800,"action_type":300003,"action_msg":"已签收"},
+                    {"action_time":1532966400,"action_type":100001,"action_msg":"已揽件"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(path.path_item_list.len(), 2);
+        assert_eq!(path.latest().unwrap().action_type, 300003);
+    }
 }
@@ -32,17 +32,44 @@
 //! // Download media
 //! let data = media_api.get_temp_media(&response.media_id).await?;
 //! ```
+//!
+//! ## Reusing Uploads
+//!
+//! A shared [`MediaHandleCache`] maps content hashes to media IDs, so sending
+//! the same picture to many users uploads it once per three days:
+//!
+//! ```ignore
+//! use wechat_mp_sdk::api::media::MediaHandleCache;
+//!
+//! let cache = MediaHandleCache::new();
+//! let media_id = media_api
+//!     .upload_temp_media_cached(&cache, MediaType::Image, "reply.png", &image_data)
+//!     .await?;
+//! ```
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use crate::error::{HttpError, WechatError};
+use crate::error::WechatError;
+use crate::utils::lock;
 
 use super::{WechatApi, WechatContext};
 
+/// How long WeChat keeps temporary media
+const TEMP_MEDIA_LIFETIME: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+/// Cached handles are dropped this long before WeChat expires the media
+const DEFAULT_EXPIRY_MARGIN: Duration = Duration::from_secs(60 * 60);
+/// errcode for an unknown or expired `media_id`
+pub(crate) const INVALID_MEDIA_ID: i32 = 40007;
+
 /// Media type for temporary media upload
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MediaType {
     /// Image file (jpg, png)
     Image,
@@ -68,14 +95,17 @@ impl MediaType {
 
 /// Response from temporary media upload
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MediaUploadResponse {
     /// Type of the uploaded media
     #[serde(rename = "type")]
     pub media_type: String,
     /// Unique identifier for the uploaded media
+    #[serde(alias = "mediaId")]
     pub media_id: String,
     /// Unix timestamp when the media was created
+    #[serde(alias = "createdAt")]
     pub created_at: i64,
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -93,6 +123,106 @@ impl MediaUploadResponse {
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct ImageUploadResponse {
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    errcode: i32,
+    #[serde(default)]
+    errmsg: String,
+}
+
+/// Content-hash → `media_id` map for temporary media, with expiry tracking
+///
+/// Entries expire one hour (see [`expiry_margin`](Self::expiry_margin))
+/// before WeChat's three-day limit. Cloning shares the cache. Concurrent
+/// first uploads of the same content may each upload once.
+#[derive(Debug, Clone)]
+pub struct MediaHandleCache {
+    entries: Arc<Mutex<MediaEntries>>,
+    margin: Duration,
+}
+
+/// Keyed by media type and SHA-256 of the content
+type MediaEntries = HashMap<(MediaType, [u8; 32]), CachedMedia>;
+
+#[derive(Debug)]
+struct CachedMedia {
+    media_id: String,
+    expires_at: Instant,
+}
+
+impl Default for MediaHandleCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MediaHandleCache {
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            margin: DEFAULT_EXPIRY_MARGIN,
+        }
+    }
+
+    /// Stop reusing a handle this long before WeChat expires it
+    pub fn expiry_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin.min(TEMP_MEDIA_LIFETIME);
+        self
+    }
+
+    /// Cached `media_id` for `data`, if still valid
+    pub fn get(&self, media_type: MediaType, data: &[u8]) -> Option<String> {
+        let key = (media_type, content_hash(data));
+        let mut entries = lock(&self.entries);
+        match entries.get(&key) {
+            Some(cached) if cached.expires_at > Instant::now() => Some(cached.media_id.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, media_type: MediaType, data: &[u8], media_id: &str) {
+        let cached = CachedMedia {
+            media_id: media_id.to_string(),
+            expires_at: Instant::now() + (TEMP_MEDIA_LIFETIME - self.margin),
+        };
+        lock(&self.entries).insert((media_type, content_hash(data)), cached);
+    }
+
+    /// Forget `media_id`, e.g. after WeChat rejected it as expired
+    pub fn remove(&self, media_id: &str) {
+        lock(&self.entries).retain(|_, cached| cached.media_id != media_id);
+    }
+
+    /// Drop expired entries and return how many were removed
+    pub fn purge_expired(&self) -> usize {
+        let now = Instant::now();
+        let mut entries = lock(&self.entries);
+        let before = entries.len();
+        entries.retain(|_, cached| cached.expires_at > now);
+        before - entries.len()
+    }
+
+    /// Number of cached handles, including expired ones not yet purged
+    pub fn len(&self) -> usize {
+        lock(&self.entries).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn content_hash(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
 /// Temporary Media API
 ///
 /// Provides methods for uploading and downloading temporary media files.
@@ -141,44 +271,73 @@ impl MediaApi {
         filename: &str,
         data: &[u8],
     ) -> Result<MediaUploadResponse, WechatError> {
-        let access_token = self.context.token_manager.get_token().await?;
-        let url = format!(
-            "{}{}",
-            self.context.client.base_url(),
-            "/cgi-bin/media/upload"
-        );
-        let query = [
-            ("access_token", access_token.as_str()),
-            ("type", media_type.as_str()),
-        ];
+        let result: MediaUploadResponse = self
+            .upload_multipart(
+                "/cgi-bin/media/upload",
+                &[("type", media_type.as_str())],
+                filename,
+                data,
+            )
+            .await?;
 
-        let part = reqwest::multipart::Part::bytes(data.to_vec()).file_name(filename.to_string());
-        let form = reqwest::multipart::Form::new().part("media", part);
+        WechatError::check_api(result.errcode(), result.errmsg())?;
 
-        let request = self
-            .context
-            .client
-            .http()
-            .post(&url)
-            .query(&query)
-            .multipart(form)
-            .build()?;
-        let response = self.context.client.send_request(request).await?;
-        if let Err(error) = response.error_for_status_ref() {
-            return Err(error.into());
-        }
+        Ok(result)
+    }
 
-        let value: serde_json::Value = response.json().await?;
-        if let Some((code, message)) = parse_api_error_from_json_value(&value) {
-            return Err(WechatError::Api { code, message });
+    /// Upload temporary media once per content and return its `media_id`
+    ///
+    /// Returns the cached `media_id` when `cache` already holds an unexpired
+    /// upload of identical `data`; otherwise uploads and records it.
+    pub async fn upload_temp_media_cached(
+        &self,
+        cache: &MediaHandleCache,
+        media_type: MediaType,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<String, WechatError> {
+        if let Some(media_id) = cache.get(media_type, data) {
+            return Ok(media_id);
         }
 
-        let result: MediaUploadResponse = serde_json::from_value(value)
-            .map_err(|error| WechatError::Http(HttpError::Decode(error.to_string())))?;
+        let response = self.upload_temp_media(media_type, filename, data).await?;
+        cache.insert(media_type, data, &response.media_id);
+        Ok(response.media_id)
+    }
 
-        WechatError::check_api(result.errcode(), result.errmsg())?;
+    /// Upload a permanent image and return its URL
+    ///
+    /// POST /cgi-bin/media/uploadimg?access_token=ACCESS_TOKEN
+    ///
+    /// The returned URL can be referenced from other APIs that accept image
+    /// URLs, such as the nearby POI `pic_list`.
+    ///
+    /// # Arguments
+    /// * `filename` - Name of the file (used for reference)
+    /// * `data` - Raw image content (jpg or png)
+    ///
+    /// # Errors
+    /// Returns `WechatError` if the upload fails or API returns an error
+    pub async fn upload_image(&self, filename: &str, data: &[u8]) -> Result<String, WechatError> {
+        let result: ImageUploadResponse = self
+            .upload_multipart("/cgi-bin/media/uploadimg", &[], filename, data)
+            .await?;
 
-        Ok(result)
+        WechatError::check_api(result.errcode, &result.errmsg)?;
+
+        Ok(result.url)
+    }
+
+    async fn upload_multipart<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        extra_query: &[(&str, &str)],
+        filename: &str,
+        data: &[u8],
+    ) -> Result<T, WechatError> {
+        self.context
+            .authed_upload(endpoint, extra_query, "media", filename, data)
+            .await
     }
 
     /// Get temporary media
@@ -191,7 +350,8 @@ impl MediaApi {
     /// * `media_id` - Media ID returned from upload_temp_media
     ///
     /// # Returns
-    /// Raw bytes of the media file
+    /// Raw bytes of the media file, reference-counted so they can be handed to
+    /// an HTTP response body without copying
     ///
     /// # Errors
     /// Returns `WechatError` if the download fails or media is not found
@@ -202,53 +362,25 @@ impl MediaApi {
     /// let data = media_api.get_temp_media("media_id_123").await?;
     /// std::fs::write("downloaded.jpg", &data)?;
     /// ```
-    pub async fn get_temp_media(&self, media_id: &str) -> Result<Vec<u8>, WechatError> {
-        let access_token = self.context.token_manager.get_token().await?;
-        let url = format!("{}{}", self.context.client.base_url(), "/cgi-bin/media/get");
+    pub async fn get_temp_media(&self, media_id: &str) -> Result<Bytes, WechatError> {
+        let access_token = self.context.token_for("/cgi-bin/media/get").await?;
+        let url = self.context.client.url_for("/cgi-bin/media/get")?;
         let query = [
             ("access_token", access_token.as_str()),
             ("media_id", media_id),
         ];
 
-        let request = self.context.client.http().get(&url).query(&query).build()?;
+        let request = self
+            .context
+            .client
+            .http()
+            .get(url)
+            .query(&query)
+            .timeout(self.context.client.timeout_for_path("/cgi-bin/media/get"))
+            .build()?;
         let response = self.context.client.send_request(request).await?;
-        if let Err(error) = response.error_for_status_ref() {
-            return Err(error.into());
-        }
-
-        let bytes = response.bytes().await?;
-        if let Some((code, message)) = parse_api_error_from_json_bytes(&bytes) {
-            return Err(WechatError::Api { code, message });
-        }
-
-        Ok(bytes.to_vec())
-    }
-}
-
-fn parse_api_error_from_json_bytes(bytes: &[u8]) -> Option<(i32, String)> {
-    let value: serde_json::Value = serde_json::from_slice(bytes).ok()?;
-    parse_api_error_from_json_value(&value)
-}
-
-fn parse_api_error_from_json_value(value: &serde_json::Value) -> Option<(i32, String)> {
-    let raw_code = value.get("errcode")?.as_i64()?;
-    if raw_code == 0 {
-        return None;
+        self.context.client.binary_body(response).await
     }
-
-    let code = i32::try_from(raw_code).unwrap_or_else(|_| {
-        if raw_code.is_negative() {
-            i32::MIN
-        } else {
-            i32::MAX
-        }
-    });
-    let message = value
-        .get("errmsg")
-        .and_then(|v| v.as_str())
-        .unwrap_or("unknown error")
-        .to_string();
-    Some((code, message))
 }
 
 impl WechatApi for MediaApi {
@@ -261,6 +393,11 @@ impl WechatApi for MediaApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.response::<MediaUploadResponse>("customerService.uploadTempMedia");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -372,7 +509,7 @@ mod tests {
             .await;
 
         assert!(result.is_err());
-        if let Err(WechatError::Api { code, message }) = result {
+        if let Err(WechatError::Api { code, message, .. }) = result {
             assert_eq!(code, 40001);
             assert_eq!(message, "invalid credential");
         } else {
@@ -412,7 +549,7 @@ mod tests {
 
         assert!(result.is_ok());
         let data = result.unwrap();
-        assert_eq!(data, b"media_binary_data");
+        assert_eq!(data, &b"media_binary_data"[..]);
     }
 
     #[tokio::test]
@@ -448,7 +585,7 @@ mod tests {
 
         assert!(result.is_err());
         match result {
-            Err(WechatError::Api { code, message }) => {
+            Err(WechatError::Api { code, message, .. }) => {
                 assert_eq!(code, 40007);
                 assert_eq!(message, "invalid media_id");
             }
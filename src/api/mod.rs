@@ -7,7 +7,9 @@
 //! - [`customer_service`] - Customer service messages
 //! - [`media`] - Temporary media upload and download
 //! - [`subscribe`] - Subscribe messages and template management
+//! - [`subscribe_quota`] - Local subscribe authorization counts
 //! - [`qrcode`] - Mini Program codes, QR codes, and URL links
+//! - [`channel_tag`] - Channel attribution for links, schemes, and codes
 //! - [`template`] - Template message management
 //! - [`user`] - User information and phone number
 //! - [`openapi`] - OpenAPI quota and diagnostic management
@@ -18,16 +20,21 @@
 //! - [`plugin`] - Plugin application management
 //! - [`nearby`] - Nearby points of interest
 //! - [`cloud`] - WeChat Cloud Base (TCB)
+//! - [`cloud_database`] - Cloud Base database query builder
 //! - [`live`] - Live streaming rooms and goods
 //! - [`hardware`] - IoT hardware device messaging
 //! - [`ocr`] - Image processing and OCR
 //! - [`delivery`] - Instant/local delivery orders
 //! - [`logistics`] - Express delivery and tracking
+//! - [`outbox`] - Dedup keys for retry-safe order creation
+//! - [`inbox`] - Replay protection for pushed results
+//! - [`shipping`] - Shipping information of WeChat Pay orders
 //! - [`service_market`] - WeChat service marketplace
 //! - [`soter`] - SOTER biometric authentication
 //! - [`face`] - Face identity verification
 //! - [`wxsearch`] - WeChat search page submission
 //! - [`advertising`] - Advertising user action tracking
+//! - [`account`] - Account basic info, nickname, avatar, and signature
 //!
 //! ## Usage
 //!
@@ -35,16 +42,20 @@
 //! use wechat_mp_sdk::{WechatMp, types::{AppId, AppSecret}};
 //! ```
 
+pub mod account;
 pub mod advertising;
 pub mod analytics;
 pub mod auth;
+pub mod channel_tag;
 pub mod cloud;
+pub mod cloud_database;
 pub mod common;
 pub mod customer_service;
 pub mod delivery;
 pub mod endpoint_inventory;
 pub mod face;
 pub mod hardware;
+pub mod inbox;
 pub mod live;
 pub mod logistics;
 pub mod media;
@@ -52,67 +63,157 @@ pub mod nearby;
 pub mod ocr;
 pub mod openapi;
 pub mod operations;
+pub mod outbox;
 pub mod plugin;
 pub mod qrcode;
+#[cfg(all(test, feature = "snapshots"))]
+mod request_snapshots;
 pub mod security;
 pub mod service_market;
+pub mod shipping;
 pub mod soter;
 pub mod subscribe;
+pub mod subscribe_quota;
 pub mod template;
 pub mod r#trait;
 pub mod user;
 pub mod wechat_kf;
 pub mod wxsearch;
 
+pub use account::{
+    AccountApi, AccountBasicInfo, CheckNicknameResponse, HeadImageCrop, HeadImageInfo,
+    NicknameAuditResponse, NicknameAuditState, NicknameInfo, SetNicknameRequest,
+    SetNicknameResponse, SignatureInfo, WxVerifyInfo,
+};
 pub use advertising::{AdvertisingApi, AdvertisingRequest, AdvertisingResponse};
 pub use analytics::{
-    AnalyticsApi, AnalyticsDateRangeRequest, AnalyticsResponse, PerformanceDataRequest,
+    AnalyticsApi, AnalyticsDateRangeRequest, AnalyticsResponse, DailySummaryItem,
+    PerformanceDataRequest, VisitTrendItem,
 };
+pub use channel_tag::{ChannelReport, ChannelStats, ChannelTag, LinkKind};
 pub use cloud::{
-    CloudApi, CloudDatabaseRequest, CloudResponse, DelayedFunctionTaskRequest,
-    DeleteCloudFileRequest, DownloadFileLinkRequest, InvokeCloudFunctionRequest,
-    SendCloudBaseSmsRequest, UploadFileLinkRequest,
+    CloudApi, CloudDatabaseRequest, CloudDownload, CloudFileInfo, CloudResponse,
+    DelayedFunctionTaskRequest, DeleteCloudFileRequest, DownloadFileLinkRequest,
+    InvokeCloudFunctionRequest, SendCloudBaseSmsRequest, UploadFileLinkRequest,
+};
+pub use cloud_database::{
+    Collection, CollectionInfo, DatabaseAddResponse, DatabaseCollectionsResponse,
+    DatabaseDeleteResponse, DatabasePager, DatabaseQueryResponse, DatabaseUpdateResponse,
+    SortOrder,
 };
 pub use common::{
     ApiResponseBase, DateRangeRequest, PaginatedRequest, PaginatedResponse, WechatApiResponse,
+    FIELD_ALIASES,
 };
 pub use customer_service::{
-    CustomerServiceApi, LinkMessage, MediaMessage, Message, MiniProgramPageMessage, TextMessage,
+    CustomerServiceApi, LinkMessage, MediaMessage, Message, MiniProgramPageMessage, OverflowPolicy,
+    SendQueue, TextMessage,
+};
+pub use delivery::{
+    AddDeliveryOrderRequest, AddDeliveryOrderResponse, CancelDeliveryOrderRequest,
+    CancelDeliveryOrderResponse, ConfirmReturnRequest, DeliveryApi, DeliveryCargo, DeliveryContact,
+    DeliveryGoods, DeliveryGoodsDetail, DeliveryMerchant, DeliveryOrderInfo, DeliveryOrderState,
+    DeliveryOrderTracker, DeliveryRequest, DeliveryResponse, DeliveryResult, DeliveryShop,
+    DeliveryTransition, GetDeliveryOrderRequest, GetDeliveryOrderResponse, MockUpdateOrderRequest,
 };
-pub use delivery::{DeliveryApi, DeliveryRequest, DeliveryResponse};
 pub use face::{FaceApi, FaceResponse, GetVerifyIdRequest, QueryVerifyInfoRequest};
 pub use hardware::{HardwareApi, HardwareRequest, HardwareResponse};
-pub use live::{DeleteRoomRequest, GetLiveInfoRequest, LiveApi, LiveRequest, LiveResponse};
-pub use logistics::{LogisticsApi, LogisticsRequest, LogisticsResponse};
-pub use media::{MediaApi, MediaType, MediaUploadResponse};
+pub use inbox::{process_once, Delivery, MemoryResultInbox, ResultInbox, ResultInboxFuture};
+pub use live::{
+    live_errcode, DeleteRoomRequest, FailedGoods, GetLiveInfoRequest, GoodsImportReport,
+    GoodsSkipReason, GoodsSpec, ImportedGoods, LiveApi, LiveErrcode, LiveGoods,
+    LiveGoodsListResponse, LiveGoodsPrice, LiveGoodsStatus, LiveRequest, LiveResponse,
+    LiveSharedCodeResponse, SkippedGoods, LIVE_ERRCODES,
+};
+pub use logistics::{
+    AddOrderRequest, AddOrderResponse, CargoDetail, GetOrderRequest, GetOrderResponse,
+    GetPathRequest, GetPathResponse, LogisticsApi, LogisticsCargo, LogisticsContact,
+    LogisticsInsured, LogisticsRequest, LogisticsResponse, LogisticsService, LogisticsShop,
+    PathItem, WaybillData,
+};
+pub use media::{MediaApi, MediaHandleCache, MediaType, MediaUploadResponse};
 pub use nearby::{
-    AddNearbyPoiRequest, DeleteNearbyPoiRequest, NearbyApi, NearbyPoiListRequest, NearbyResponse,
-    NearbyShowStatusRequest,
+    AddNearbyPoiRequest, DeleteNearbyPoiRequest, NearbyApi, NearbyPoiListRequest,
+    NearbyPoiMaterial, NearbyPoiUploads, NearbyResponse, NearbyShowStatusRequest,
+};
+pub use ocr::{
+    BankCardOcrResult, BusinessLicenseOcrResult, CertPosition, CodeRegion, DriverLicenseOcrResult,
+    IdCardOcrRequest, IdCardOcrResult, ImageSize, OcrApi, OcrImageRequest, OcrResponse, Point,
+    Quad, Rect, TextRegion, VehicleLicenseOcrResult,
 };
-pub use ocr::{IdCardOcrRequest, OcrApi, OcrImageRequest, OcrResponse};
 pub use openapi::{
-    ApiQuotaResponse, CallbackCheckResponse, DnsInfo, IpListResponse, OpenApiApi, PingInfo,
-    QuotaInfo, RidInfoResponse, RidRequestInfo,
+    ApiQuotaResponse, CallbackCheckAction, CallbackCheckResponse, CallbackHealth, CheckOperator,
+    DnsInfo, IpListResponse, OpenApiApi, PingInfo, QuotaInfo, RidInfoResponse, RidRequestInfo,
 };
 pub use operations::{
-    EmptyRequest, FeedbackMediaRequest, FeedbackRequest, JsErrDetailRequest, JsErrListRequest,
-    OperationsApi, OperationsResponse, RealtimeLogSearchRequest,
+    EmptyRequest, FeedbackMediaRequest, FeedbackRequest, GrayReleaseProgress, GrayReleaseStatus,
+    GrayReleaseWatch, GrayReleaseWatcher, JsErrDetailRequest, JsErrListRequest, OperationsApi,
+    OperationsResponse, RealtimeLogSearchRequest,
+};
+pub use outbox::{MemoryOrderOutbox, OrderCreation, OrderOutbox, OrderOutboxFuture, OutboxState};
+pub use plugin::{
+    ManagePluginApplicationRequest, ManagePluginRequest, PluginApi, PluginApplicant,
+    PluginApplicantCategory, PluginApplicantPage, PluginApplicantStatus, PluginResponse,
 };
-pub use plugin::{ManagePluginApplicationRequest, ManagePluginRequest, PluginApi, PluginResponse};
 pub use r#trait::{WechatApi, WechatContext};
 pub use security::{
     MediaCheckAsyncResponse, MsgSecCheckDetail, MsgSecCheckResponse, MsgSecCheckResult,
-    SecurityApi, UserRiskRankOptions, UserRiskRankResponse,
+    RiskAssessment, RiskDecision, RiskPolicy, RiskScene, Scene, SecurityApi, UserRiskRankOptions,
+    UserRiskRankResponse,
 };
 pub use service_market::{InvokeServiceRequest, ServiceMarketApi, ServiceMarketResponse};
+pub use shipping::{
+    LogisticsType, ShippingApi, ShippingContact, ShippingItem, ShippingOrderKey,
+    UploadShippingInfoRequest,
+};
 pub use soter::{SoterApi, VerifySignatureRequest, VerifySignatureResponse};
 pub use subscribe::{
-    AddTemplateResponse, CategoryInfo, CategoryListResponse, GetUserNotifyRequest, Lang,
-    MiniProgramState, PubTemplateKeywordInfo, PubTemplateKeywordResponse, PubTemplateTitleInfo,
-    PubTemplateTitleListResponse, SubscribeApi, SubscribeMessageData, SubscribeMessageOptions,
-    SubscribeMessageValue, TemplateInfo, TemplateListResponse, UserNotifyExtRequest,
-    UserNotifyRequest, UserNotifyResponse,
+    AddTemplateResponse, BatchRecipient, CategoryInfo, CategoryListResponse, DesiredTemplate,
+    ExpectedTemplate, Lang, MetadataCacheStats, MiniProgramState, PubTemplateKeywordInfo,
+    PubTemplateKeywordResponse, PubTemplateTitleInfo, PubTemplateTitleListResponse,
+    SetUserNotifyExtRequest, SetUserNotifyRequest, SubscribeApi, SubscribeBatch,
+    SubscribeBatchEntry, SubscribeBatchReport, SubscribeMessageData, SubscribeMessageOptions,
+    SubscribeMessageValue, SubscribeValidation, SyncedTemplate, TemplateDrift, TemplateDriftReport,
+    TemplateInfo, TemplateListResponse, TemplateSyncReport, TemplateType, UserNotifyInfo,
+    UserNotifyInfoResponse, UserNotifyQuery, UserNotifyResponse,
+};
+#[allow(deprecated)]
+pub use subscribe::{GetUserNotifyRequest, UserNotifyExtRequest, UserNotifyRequest};
+pub use subscribe_quota::{
+    MemorySubscribeQuotaStore, SubscribeQuotaFuture, SubscribeQuotaPolicy, SubscribeQuotaStore,
 };
 pub use template::TemplateApi;
 pub use wechat_kf::{KfWorkBoundResponse, KfWorkInfo, WechatKfApi};
 pub use wxsearch::{SubmitPagesRequest, SubmitPagesResponse, WxsearchApi};
+
+/// Register the typed request and response bodies of every API module for
+/// [`endpoint_inventory::openapi_spec`]
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut endpoint_inventory::OperationSchemas) {
+    account::openapi_schemas(schemas);
+    advertising::openapi_schemas(schemas);
+    analytics::openapi_schemas(schemas);
+    auth::openapi_schemas(schemas);
+    cloud::openapi_schemas(schemas);
+    customer_service::openapi_schemas(schemas);
+    delivery::openapi_schemas(schemas);
+    face::openapi_schemas(schemas);
+    hardware::openapi_schemas(schemas);
+    live::openapi_schemas(schemas);
+    logistics::openapi_schemas(schemas);
+    media::openapi_schemas(schemas);
+    nearby::openapi_schemas(schemas);
+    ocr::openapi_schemas(schemas);
+    openapi::openapi_schemas(schemas);
+    operations::openapi_schemas(schemas);
+    plugin::openapi_schemas(schemas);
+    qrcode::openapi_schemas(schemas);
+    security::openapi_schemas(schemas);
+    service_market::openapi_schemas(schemas);
+    shipping::openapi_schemas(schemas);
+    soter::openapi_schemas(schemas);
+    subscribe::openapi_schemas(schemas);
+    user::openapi_schemas(schemas);
+    wechat_kf::openapi_schemas(schemas);
+    wxsearch::openapi_schemas(schemas);
+}
@@ -5,27 +5,129 @@ use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio_util::sync::CancellationToken;
 
+use super::media::{MediaApi, MediaType};
 use super::{WechatApi, WechatContext};
 use crate::error::WechatError;
+use crate::utils::run_cancellable;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AddNearbyPoiRequest {
     pub poi_id: String,
     pub related_name: String,
     pub related_credential: String,
     pub related_address: String,
+    /// Store pictures as a JSON string of the form `{"list":["url", ...]}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pic_list: Option<String>,
+    /// Qualification media ids joined by `|`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qualification_list: Option<String>,
+}
+
+impl AddNearbyPoiRequest {
+    pub fn new(
+        poi_id: impl Into<String>,
+        related_name: impl Into<String>,
+        related_credential: impl Into<String>,
+        related_address: impl Into<String>,
+    ) -> Self {
+        Self {
+            poi_id: poi_id.into(),
+            related_name: related_name.into(),
+            related_credential: related_credential.into(),
+            related_address: related_address.into(),
+            pic_list: None,
+            qualification_list: None,
+        }
+    }
+
+    /// Set store pictures from URLs returned by `MediaApi::upload_image`.
+    pub fn with_pic_urls<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let list: Vec<String> = urls.into_iter().map(Into::into).collect();
+        self.pic_list = Some(serde_json::json!({ "list": list }).to_string());
+        self
+    }
+
+    /// Set qualification materials from temporary media ids.
+    pub fn with_qualification_media_ids<I, S>(mut self, media_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let ids: Vec<String> = media_ids.into_iter().map(Into::into).collect();
+        self.qualification_list = Some(ids.join("|"));
+        self
+    }
+}
+
+/// Results of [`NearbyApi::upload_poi_materials`]
+#[non_exhaustive]
+#[derive(Debug, Clone, Default)]
+pub struct NearbyPoiUploads {
+    /// URLs of the uploaded store pictures, in input order
+    pub pic_urls: Vec<String>,
+    /// Media ids of the uploaded qualifications, in input order
+    pub qualification_media_ids: Vec<String>,
+    /// Whether the uploads stopped early because of cancellation
+    pub cancelled: bool,
+}
+
+impl NearbyPoiUploads {
+    /// Slot the uploaded materials into `request`, skipping empty lists.
+    pub fn apply_to(&self, mut request: AddNearbyPoiRequest) -> AddNearbyPoiRequest {
+        if !self.pic_urls.is_empty() {
+            request = request.with_pic_urls(self.pic_urls.iter().cloned());
+        }
+        if !self.qualification_media_ids.is_empty() {
+            request =
+                request.with_qualification_media_ids(self.qualification_media_ids.iter().cloned());
+        }
+        request
+    }
+}
+
+/// Local file to upload as part of a nearby POI application
+#[derive(Debug, Clone)]
+pub struct NearbyPoiMaterial {
+    pub filename: String,
+    pub data: Vec<u8>,
+}
+
+impl NearbyPoiMaterial {
+    pub fn new(filename: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            filename: filename.into(),
+            data: data.into(),
+        }
+    }
 }
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DeleteNearbyPoiRequest {
     pub poi_id: String,
 }
 
+impl DeleteNearbyPoiRequest {
+    pub fn new(poi_id: impl Into<String>) -> Self {
+        Self {
+            poi_id: poi_id.into(),
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct NearbyPoiListRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub page: Option<i32>,
@@ -35,12 +137,14 @@ pub struct NearbyPoiListRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct NearbyShowStatusRequest {
     pub is_open: i32,
 }
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct NearbyResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -59,6 +163,7 @@ impl NearbyApi {
         Self { context }
     }
 
+    #[doc(alias = "添加地点")]
     pub async fn add_nearby_poi(
         &self,
         request: &AddNearbyPoiRequest,
@@ -66,6 +171,67 @@ impl NearbyApi {
         self.post_json("/wxa/addnearbypoi", request).await
     }
 
+    /// Upload store pictures and qualification materials, then add the POI.
+    ///
+    /// Pictures go through `/cgi-bin/media/uploadimg` and fill `pic_list`;
+    /// qualifications are uploaded as temporary images and fill
+    /// `qualification_list`. Empty slices leave the request fields untouched.
+    pub async fn add_nearby_poi_with_materials(
+        &self,
+        request: AddNearbyPoiRequest,
+        pictures: &[NearbyPoiMaterial],
+        qualifications: &[NearbyPoiMaterial],
+    ) -> Result<NearbyResponse, WechatError> {
+        let uploads = self
+            .upload_poi_materials(pictures, qualifications, None)
+            .await?;
+        self.add_nearby_poi(&uploads.apply_to(request)).await
+    }
+
+    /// Upload store pictures and qualification materials without submitting.
+    ///
+    /// When `cancel` fires, the upload in flight is aborted and the returned
+    /// [`NearbyPoiUploads`] holds what finished before it, with `cancelled`
+    /// set. Picture URLs are permanent and can be reused on a later attempt.
+    pub async fn upload_poi_materials(
+        &self,
+        pictures: &[NearbyPoiMaterial],
+        qualifications: &[NearbyPoiMaterial],
+        cancel: Option<&CancellationToken>,
+    ) -> Result<NearbyPoiUploads, WechatError> {
+        let media = MediaApi::new(self.context.clone());
+        let mut uploads = NearbyPoiUploads::default();
+
+        for picture in pictures {
+            match run_cancellable(cancel, media.upload_image(&picture.filename, &picture.data))
+                .await
+            {
+                Ok(url) => uploads.pic_urls.push(url),
+                Err(WechatError::Cancelled) => {
+                    uploads.cancelled = true;
+                    return Ok(uploads);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        for material in qualifications {
+            let upload =
+                media.upload_temp_media(MediaType::Image, &material.filename, &material.data);
+            match run_cancellable(cancel, upload).await {
+                Ok(uploaded) => uploads.qualification_media_ids.push(uploaded.media_id),
+                Err(WechatError::Cancelled) => {
+                    uploads.cancelled = true;
+                    return Ok(uploads);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(uploads)
+    }
+
+    #[doc(alias = "删除地点")]
     pub async fn delete_nearby_poi(
         &self,
         request: &DeleteNearbyPoiRequest,
@@ -73,6 +239,7 @@ impl NearbyApi {
         self.post_json("/wxa/delnearbypoi", request).await
     }
 
+    #[doc(alias = "查看地点列表")]
     pub async fn get_nearby_poi_list(
         &self,
         request: &NearbyPoiListRequest,
@@ -80,6 +247,7 @@ impl NearbyApi {
         self.post_json("/wxa/getnearbypoilist", request).await
     }
 
+    #[doc(alias = "展示或取消展示附近小程序")]
     pub async fn set_show_status(
         &self,
         request: &NearbyShowStatusRequest,
@@ -108,9 +276,22 @@ impl WechatApi for NearbyApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<AddNearbyPoiRequest, NearbyResponse>("nearby.addNearbyPoi");
+    schemas.add::<DeleteNearbyPoiRequest, NearbyResponse>("nearby.deleteNearbyPoi");
+    schemas.add::<NearbyPoiListRequest, NearbyResponse>("nearby.getNearbyPoiList");
+    schemas.add::<NearbyShowStatusRequest, NearbyResponse>("nearby.setShowStatus");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::{AppId, AppSecret};
+    use crate::WechatClient;
+    use std::time::Duration;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[test]
     fn nearby_response_deserializes() {
@@ -119,4 +300,146 @@ mod tests {
         assert_eq!(response.errcode, 0);
         assert!(response.extra.contains_key("poi_list"));
     }
+
+    #[test]
+    fn add_request_serializes_materials() {
+        let request = AddNearbyPoiRequest::new("poi", "name", "cred", "addr")
+            .with_pic_urls(["https://mmbiz/a", "https://mmbiz/b"])
+            .with_qualification_media_ids(["m1", "m2"]);
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            json["pic_list"],
+            r#"{"list":["https://mmbiz/a","https://mmbiz/b"]}"#
+        );
+        assert_eq!(json["qualification_list"], "m1|m2");
+
+        let bare = serde_json::to_value(AddNearbyPoiRequest::new("p", "n", "c", "a")).unwrap();
+        assert!(bare.get("pic_list").is_none());
+        assert!(bare.get("qualification_list").is_none());
+    }
+
+    async fn mock_token(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "tok",
+                "expires_in": 7200
+            })))
+            .mount(server)
+            .await;
+    }
+
+    fn create_test_api(base_url: &str) -> NearbyApi {
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(base_url)
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(crate::token::TokenManager::new((*client).clone()));
+        NearbyApi::new(Arc::new(WechatContext::new(client, token_manager)))
+    }
+
+    #[tokio::test]
+    async fn add_with_materials_uploads_then_submits() {
+        let server = MockServer::start().await;
+        mock_token(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/media/uploadimg"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "url": "https://mmbiz.qpic.cn/pic"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/media/upload"))
+            .and(query_param("type", "image"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "type": "image",
+                "media_id": "qual_1",
+                "created_at": 1
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/addnearbypoi"))
+            .and(body_partial_json(serde_json::json!({
+                "poi_id": "poi",
+                "pic_list": r#"{"list":["https://mmbiz.qpic.cn/pic"]}"#,
+                "qualification_list": "qual_1|qual_1"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "data": {"audit_id": "a1"}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = create_test_api(&server.uri());
+
+        let response = api
+            .add_nearby_poi_with_materials(
+                AddNearbyPoiRequest::new("poi", "name", "cred", "addr"),
+                &[NearbyPoiMaterial::new("store.jpg", b"pic".to_vec())],
+                &[
+                    NearbyPoiMaterial::new("license.jpg", b"a".to_vec()),
+                    NearbyPoiMaterial::new("permit.jpg", b"b".to_vec()),
+                ],
+            )
+            .await
+            .unwrap();
+        assert!(response.extra.contains_key("data"));
+    }
+
+    #[tokio::test]
+    async fn upload_materials_returns_partial_results_on_cancel() {
+        let server = MockServer::start().await;
+        mock_token(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/media/uploadimg"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "url": "https://mmbiz.qpic.cn/pic"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/media/upload"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "type": "image",
+                        "media_id": "late",
+                        "created_at": 1
+                    }))
+                    .set_delay(Duration::from_secs(10)),
+            )
+            .mount(&server)
+            .await;
+
+        let api = create_test_api(&server.uri());
+        let token = CancellationToken::new();
+        let trigger = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            trigger.cancel();
+        });
+
+        let uploads = api
+            .upload_poi_materials(
+                &[NearbyPoiMaterial::new("store.jpg", b"pic".to_vec())],
+                &[NearbyPoiMaterial::new("license.jpg", b"a".to_vec())],
+                Some(&token),
+            )
+            .await
+            .unwrap();
+        assert!(uploads.cancelled);
+        assert_eq!(uploads.pic_urls, vec!["https://mmbiz.qpic.cn/pic"]);
+        assert!(uploads.qualification_media_ids.is_empty());
+    }
 }
@@ -11,6 +11,7 @@ use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct OcrImageRequest {
     pub img_url: String,
 }
@@ -25,14 +26,31 @@ impl OcrImageRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct IdCardOcrRequest {
     pub img_url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub r#type: Option<String>,
 }
 
+impl IdCardOcrRequest {
+    pub fn new(img_url: impl Into<String>) -> Self {
+        Self {
+            img_url: img_url.into(),
+            r#type: None,
+        }
+    }
+
+    /// Set the recognition mode, e.g. `"photo"` or `"scan"`.
+    pub fn with_type(mut self, r#type: impl Into<String>) -> Self {
+        self.r#type = Some(r#type.into());
+        self
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct OcrResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -42,6 +60,363 @@ pub struct OcrResponse {
     pub extra: HashMap<String, Value>,
 }
 
+impl OcrResponse {
+    /// Recognised text lines with their positions (`items`, returned by
+    /// printed text OCR)
+    pub fn text_regions(&self) -> Result<Vec<TextRegion>, WechatError> {
+        self.list("items")
+    }
+
+    /// Decoded QR/bar codes with their positions (`code_results`, returned by
+    /// `scan_qr_code`)
+    pub fn code_regions(&self) -> Result<Vec<CodeRegion>, WechatError> {
+        self.list("code_results")
+    }
+
+    /// Suggested crop rectangles (`results`, returned by `ai_crop`)
+    pub fn crop_rects(&self) -> Result<Vec<Rect>, WechatError> {
+        self.list("results")
+    }
+
+    /// Size of the analysed image (`img_size`), if reported
+    pub fn image_size(&self) -> Result<Option<ImageSize>, WechatError> {
+        self.extra
+            .get("img_size")
+            .map(|value| serde_json::from_value(value.clone()))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// Read the result fields into a typed result such as [`IdCardOcrResult`]
+    pub fn parse<T: serde::de::DeserializeOwned>(&self) -> Result<T, WechatError> {
+        let fields = self
+            .extra
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Ok(serde_json::from_value(Value::Object(fields))?)
+    }
+
+    fn list<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Vec<T>, WechatError> {
+        match self.extra.get(key) {
+            Some(value) => Ok(serde_json::from_value(value.clone())?),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Pixel coordinate in an OCR image
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Point {
+    #[serde(default)]
+    pub x: i32,
+    #[serde(default)]
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+/// Axis-aligned rectangle; `right` and `bottom` are exclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Rect {
+    #[serde(alias = "crop_left", default)]
+    pub left: i32,
+    #[serde(alias = "crop_top", default)]
+    pub top: i32,
+    #[serde(alias = "crop_right", default)]
+    pub right: i32,
+    #[serde(alias = "crop_bottom", default)]
+    pub bottom: i32,
+}
+
+impl Rect {
+    pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        (self.right - self.left).max(0)
+    }
+
+    pub fn height(&self) -> i32 {
+        (self.bottom - self.top).max(0)
+    }
+
+    pub fn area(&self) -> i64 {
+        i64::from(self.width()) * i64::from(self.height())
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        (self.left..self.right).contains(&point.x) && (self.top..self.bottom).contains(&point.y)
+    }
+
+    /// Overlapping part of two rectangles, if any
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect::new(
+            self.left.max(other.left),
+            self.top.max(other.top),
+            self.right.min(other.right),
+            self.bottom.min(other.bottom),
+        );
+        (rect.area() > 0).then_some(rect)
+    }
+}
+
+/// Four corners of a detected region (`pos`); may be rotated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Quad {
+    #[serde(default)]
+    pub left_top: Point,
+    #[serde(default)]
+    pub right_top: Point,
+    #[serde(default)]
+    pub right_bottom: Point,
+    #[serde(default)]
+    pub left_bottom: Point,
+}
+
+impl Quad {
+    pub fn corners(&self) -> [Point; 4] {
+        [
+            self.left_top,
+            self.right_top,
+            self.right_bottom,
+            self.left_bottom,
+        ]
+    }
+
+    /// Smallest axis-aligned rectangle containing all four corners
+    pub fn bounding_rect(&self) -> Rect {
+        let corners = self.corners();
+        let xs = corners.iter().map(|p| p.x);
+        let ys = corners.iter().map(|p| p.y);
+        Rect::new(
+            xs.clone().min().unwrap_or_default(),
+            ys.clone().min().unwrap_or_default(),
+            xs.max().unwrap_or_default(),
+            ys.max().unwrap_or_default(),
+        )
+    }
+}
+
+/// Image dimensions reported as `img_size`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct ImageSize {
+    #[serde(default)]
+    pub w: i32,
+    #[serde(default)]
+    pub h: i32,
+}
+
+/// A recognised text line
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct TextRegion {
+    #[serde(default)]
+    pub text: String,
+    /// Position of the text, when WeChat reports one
+    #[serde(default)]
+    pub pos: Option<Quad>,
+    /// Recognition confidence in `0.0..=1.0`, when WeChat reports one
+    #[serde(default)]
+    pub confidence: Option<f64>,
+}
+
+/// A decoded QR or bar code
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct CodeRegion {
+    /// Code type, e.g. `QR_CODE` or `EAN_13`
+    #[serde(default)]
+    pub type_name: String,
+    #[serde(default)]
+    pub data: String,
+    #[serde(default)]
+    pub pos: Option<Quad>,
+}
+
+/// Result of `/cv/ocr/idcard`
+///
+/// The front side fills the personal fields; the back side fills only
+/// `valid_date`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IdCardOcrResult {
+    /// `Front` or `Back`
+    #[serde(default)]
+    pub r#type: String,
+    #[serde(default)]
+    pub name: String,
+    /// ID card number
+    #[serde(default, rename = "id")]
+    pub id_number: String,
+    /// Registered address
+    #[serde(default, rename = "addr")]
+    pub address: String,
+    #[serde(default)]
+    pub gender: String,
+    #[serde(default)]
+    pub nationality: String,
+    /// Validity period, e.g. `20070105-20270105`
+    #[serde(default)]
+    pub valid_date: String,
+}
+
+impl IdCardOcrResult {
+    /// Returns true when the photo shows the side with the holder's details.
+    pub fn is_front(&self) -> bool {
+        self.r#type.eq_ignore_ascii_case("front")
+    }
+
+    /// Start and end of `valid_date`; the end may read `长期` (no expiry).
+    pub fn validity(&self) -> Option<(&str, &str)> {
+        self.valid_date.split_once('-')
+    }
+}
+
+/// Result of `/cv/ocr/bankcard`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BankCardOcrResult {
+    /// Card number
+    #[serde(default)]
+    pub number: String,
+}
+
+/// Result of `/cv/ocr/drivinglicense` (driver's license)
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DriverLicenseOcrResult {
+    /// License number, the holder's ID number
+    #[serde(default)]
+    pub id_num: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub sex: String,
+    #[serde(default)]
+    pub nationality: String,
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub birth_date: String,
+    /// Date of first issue
+    #[serde(default)]
+    pub issue_date: String,
+    /// Permitted vehicle class, e.g. `C1`
+    #[serde(default)]
+    pub car_class: String,
+    #[serde(default)]
+    pub valid_from: String,
+    #[serde(default)]
+    pub valid_to: String,
+    /// Issuing authority
+    #[serde(default)]
+    pub official_seal: String,
+}
+
+/// Result of `/cv/ocr/bizlicense`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BusinessLicenseOcrResult {
+    /// Registration number or unified social credit code
+    #[serde(default)]
+    pub reg_num: String,
+    /// Certificate serial number
+    #[serde(default)]
+    pub serial: String,
+    #[serde(default)]
+    pub legal_representative: String,
+    #[serde(default)]
+    pub enterprise_name: String,
+    #[serde(default)]
+    pub type_of_organization: String,
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub type_of_enterprise: String,
+    #[serde(default)]
+    pub business_scope: String,
+    #[serde(default)]
+    pub registered_capital: String,
+    #[serde(default)]
+    pub paid_in_capital: String,
+    #[serde(default)]
+    pub valid_period: String,
+    #[serde(default)]
+    pub registered_date: String,
+    /// Position of the license in the image
+    #[serde(default)]
+    pub cert_position: Option<CertPosition>,
+    #[serde(default)]
+    pub img_size: Option<ImageSize>,
+}
+
+/// Position of a recognised document (`cert_position`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct CertPosition {
+    #[serde(default)]
+    pub pos: Quad,
+}
+
+/// Result of `/cv/ocr/driving` (vehicle license)
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct VehicleLicenseOcrResult {
+    /// Plate number
+    #[serde(default)]
+    pub plate_num: String,
+    #[serde(default)]
+    pub vehicle_type: String,
+    #[serde(default)]
+    pub owner: String,
+    #[serde(default)]
+    pub addr: String,
+    /// Use character, e.g. `非营运`
+    #[serde(default)]
+    pub use_character: String,
+    #[serde(default)]
+    pub model: String,
+    /// Vehicle identification number
+    #[serde(default)]
+    pub vin: String,
+    #[serde(default)]
+    pub engine_num: String,
+    #[serde(default)]
+    pub register_date: String,
+    #[serde(default)]
+    pub issue_date: String,
+    /// Plate number printed on the back page
+    #[serde(default)]
+    pub plate_num_b: String,
+    /// Inspection record
+    #[serde(default)]
+    pub record: String,
+    #[serde(default)]
+    pub passengers_num: String,
+    #[serde(default)]
+    pub total_quality: String,
+    #[serde(default)]
+    pub prepare_quality: String,
+    #[serde(default)]
+    pub overall_size: String,
+    #[serde(default)]
+    pub card_position_front: Option<CertPosition>,
+    #[serde(default)]
+    pub card_position_back: Option<CertPosition>,
+    #[serde(default)]
+    pub img_size: Option<ImageSize>,
+}
+
 pub struct OcrApi {
     context: Arc<WechatContext>,
 }
@@ -104,6 +479,137 @@ impl OcrApi {
         self.post_json("/cv/ocr/idcard", request).await
     }
 
+    /// [`id_card_ocr`](Self::id_card_ocr) read into an [`IdCardOcrResult`]
+    pub async fn recognize_id_card(
+        &self,
+        request: &IdCardOcrRequest,
+    ) -> Result<IdCardOcrResult, WechatError> {
+        self.id_card_ocr(request).await?.parse()
+    }
+
+    /// [`bank_card_ocr`](Self::bank_card_ocr) read into a [`BankCardOcrResult`]
+    pub async fn recognize_bank_card(
+        &self,
+        request: &OcrImageRequest,
+    ) -> Result<BankCardOcrResult, WechatError> {
+        self.bank_card_ocr(request).await?.parse()
+    }
+
+    /// [`driver_license_ocr`](Self::driver_license_ocr) read into a
+    /// [`DriverLicenseOcrResult`]
+    pub async fn recognize_driver_license(
+        &self,
+        request: &OcrImageRequest,
+    ) -> Result<DriverLicenseOcrResult, WechatError> {
+        self.driver_license_ocr(request).await?.parse()
+    }
+
+    /// [`business_license_ocr`](Self::business_license_ocr) read into a
+    /// [`BusinessLicenseOcrResult`]
+    pub async fn recognize_business_license(
+        &self,
+        request: &OcrImageRequest,
+    ) -> Result<BusinessLicenseOcrResult, WechatError> {
+        self.business_license_ocr(request).await?.parse()
+    }
+
+    /// [`vehicle_license_ocr`](Self::vehicle_license_ocr) read into a
+    /// [`VehicleLicenseOcrResult`]
+    pub async fn recognize_vehicle_license(
+        &self,
+        request: &OcrImageRequest,
+    ) -> Result<VehicleLicenseOcrResult, WechatError> {
+        self.vehicle_license_ocr(request).await?.parse()
+    }
+
+    /// [`ai_crop`](Self::ai_crop) on uploaded image bytes instead of an
+    /// `img_url`
+    ///
+    /// Each `*_from_bytes` method posts `data` as the multipart `img` field,
+    /// so the image does not need a public URL. WeChat accepts images up to
+    /// 2 MB.
+    pub async fn ai_crop_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        self.upload("/cv/img/aicrop", &[], filename, data).await
+    }
+
+    pub async fn scan_qr_code_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        self.upload("/cv/img/qrcode", &[], filename, data).await
+    }
+
+    pub async fn printed_text_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        self.upload("/cv/ocr/comm", &[], filename, data).await
+    }
+
+    pub async fn vehicle_license_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        self.upload("/cv/ocr/driving", &[], filename, data).await
+    }
+
+    pub async fn bank_card_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        self.upload("/cv/ocr/bankcard", &[], filename, data).await
+    }
+
+    pub async fn business_license_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        self.upload("/cv/ocr/bizlicense", &[], filename, data).await
+    }
+
+    pub async fn driver_license_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        self.upload("/cv/ocr/drivinglicense", &[], filename, data)
+            .await
+    }
+
+    /// [`id_card_ocr`](Self::id_card_ocr) on uploaded image bytes;
+    /// `r#type` sets the recognition mode like
+    /// [`IdCardOcrRequest::with_type`]
+    pub async fn id_card_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+        r#type: Option<&str>,
+    ) -> Result<OcrResponse, WechatError> {
+        let query: Vec<_> = r#type.map(|mode| ("type", mode)).into_iter().collect();
+        self.upload("/cv/ocr/idcard", &query, filename, data).await
+    }
+
+    async fn upload(
+        &self,
+        endpoint: &str,
+        extra_query: &[(&str, &str)],
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        self.context
+            .authed_upload(endpoint, extra_query, "img", filename, data)
+            .await
+    }
+
     async fn post_json<B: Serialize>(
         &self,
         endpoint: &str,
@@ -125,6 +631,18 @@ impl WechatApi for OcrApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<OcrImageRequest, OcrResponse>("image.aiCrop");
+    schemas.add::<OcrImageRequest, OcrResponse>("image.scanQRCode");
+    schemas.add::<OcrImageRequest, OcrResponse>("ocr.printedTextOCR");
+    schemas.add::<OcrImageRequest, OcrResponse>("ocr.vehicleLicenseOCR");
+    schemas.add::<OcrImageRequest, OcrResponse>("ocr.bankCardOCR");
+    schemas.add::<OcrImageRequest, OcrResponse>("ocr.businessLicenseOCR");
+    schemas.add::<OcrImageRequest, OcrResponse>("ocr.driverLicenseOCR");
+    schemas.add::<IdCardOcrRequest, OcrResponse>("ocr.idCardOCR");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,5 +653,89 @@ mod tests {
         let response: OcrResponse = serde_json::from_str(json).unwrap();
         assert_eq!(response.errcode, 0);
         assert!(response.extra.contains_key("items"));
+        let regions = response.text_regions().unwrap();
+        assert_eq!(regions[0].text, "abc");
+        assert!(regions[0].pos.is_none());
+    }
+
+    #[test]
+    fn ocr_response_typed_geometry() {
+        let json = r#"{
+            "errcode": 0,
+            "errmsg": "ok",
+            "items": [{
+                "text": "姓名 张三",
+                "pos": {
+                    "left_top": {"x": 10, "y": 20},
+                    "right_top": {"x": 110, "y": 22},
+                    "right_bottom": {"x": 108, "y": 50},
+                    "left_bottom": {"x": 8, "y": 48}
+                }
+            }],
+            "img_size": {"w": 640, "h": 480}
+        }"#;
+        let response: OcrResponse = serde_json::from_str(json).unwrap();
+        let regions = response.text_regions().unwrap();
+        let rect = regions[0].pos.unwrap().bounding_rect();
+        assert_eq!(rect, Rect::new(8, 20, 110, 50));
+        assert_eq!(rect.area(), 102 * 30);
+        assert!(rect.contains(Point::new(50, 30)));
+        assert!(!rect.contains(Point::new(110, 30)));
+        assert_eq!(
+            response.image_size().unwrap(),
+            Some(ImageSize { w: 640, h: 480 })
+        );
+        assert!(response.code_regions().unwrap().is_empty());
+
+        let crop: OcrResponse = serde_json::from_str(
+            r#"{"results":[{"crop_left":0,"crop_top":10,"crop_right":100,"crop_bottom":60}]}"#,
+        )
+        .unwrap();
+        let crops = crop.crop_rects().unwrap();
+        assert_eq!(crops, vec![Rect::new(0, 10, 100, 60)]);
+        assert_eq!(
+            crops[0].intersection(&rect),
+            Some(Rect::new(8, 20, 100, 50))
+        );
+        assert_eq!(crops[0].intersection(&Rect::new(200, 200, 300, 300)), None);
+    }
+
+    #[test]
+    fn ocr_response_parses_document_results() {
+        let front: OcrResponse = serde_json::from_str(
+            r#"{"errcode":0,"errmsg":"ok","type":"Front","name":"张三",
+                "id":"110101199001011234","addr":"北京市东城区","gender":"男","nationality":"汉"}"#,
+        )
+        .unwrap();
+        let id_card: IdCardOcrResult = front.parse().unwrap();
+        assert!(id_card.is_front());
+        assert_eq!(id_card.id_number, "110101199001011234");
+        assert_eq!(id_card.address, "北京市东城区");
+        assert_eq!(id_card.validity(), None);
+
+        let back: OcrResponse = serde_json::from_str(
+            r#"{"errcode":0,"errmsg":"ok","type":"Back","valid_date":"20070105-20270105"}"#,
+        )
+        .unwrap();
+        let id_card: IdCardOcrResult = back.parse().unwrap();
+        assert!(!id_card.is_front());
+        assert_eq!(id_card.validity(), Some(("20070105", "20270105")));
+
+        let license: OcrResponse = serde_json::from_str(
+            r#"{"errcode":0,"errmsg":"ok","reg_num":"91110000MA00000000",
+                "enterprise_name":"示例科技有限公司",
+                "cert_position":{"pos":{"left_top":{"x":1,"y":2},"right_top":{"x":90,"y":2},
+                    "right_bottom":{"x":90,"y":60},"left_bottom":{"x":1,"y":60}}},
+                "img_size":{"w":100,"h":80}}"#,
+        )
+        .unwrap();
+        let license: BusinessLicenseOcrResult = license.parse().unwrap();
+        assert_eq!(license.reg_num, "91110000MA00000000");
+        assert_eq!(
+            license.cert_position.unwrap().pos.bounding_rect(),
+            Rect::new(1, 2, 90, 60)
+        );
+        assert_eq!(license.img_size, Some(ImageSize { w: 100, h: 80 }));
+        assert!(license.business_scope.is_empty());
     }
 }
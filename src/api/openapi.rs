@@ -9,7 +9,8 @@
 //! - [`OpenApiApi::clear_api_quota`] - Reset quota for a specific endpoint
 //! - [`OpenApiApi::clear_quota_by_app_secret`] - Reset quota using AppSecret (no token)
 //! - [`OpenApiApi::get_rid_info`] - Get request debug information by rid
-//! - [`OpenApiApi::callback_check`] - Check callback URL connectivity
+//! - [`OpenApiApi::callback_check`] - Check callback URL connectivity, summarized by
+//!   [`CallbackCheckResponse::health`]
 //! - [`OpenApiApi::get_api_domain_ip`] - Get WeChat API server IP addresses
 //! - [`OpenApiApi::get_callback_ip`] - Get WeChat callback server IP addresses
 
@@ -18,6 +19,7 @@ use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
+use super::endpoint_inventory::ApiVersion;
 use super::{WechatApi, WechatContext};
 use crate::error::WechatError;
 
@@ -26,27 +28,31 @@ use crate::error::WechatError;
 // ============================================================================
 
 #[derive(Debug, Clone, Serialize)]
-struct ClearQuotaRequest {
-    appid: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct ClearQuotaRequest<'a> {
+    appid: &'a str,
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct GetApiQuotaRequest {
-    cgi_path: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct GetApiQuotaRequest<'a> {
+    cgi_path: &'a str,
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct ClearApiQuotaRequest {
-    cgi_path: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct ClearApiQuotaRequest<'a> {
+    cgi_path: &'a str,
 }
 
 #[derive(Clone, Serialize)]
-struct ClearQuotaByAppSecretRequest {
-    appid: String,
-    appsecret: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct ClearQuotaByAppSecretRequest<'a> {
+    appid: &'a str,
+    appsecret: &'a str,
 }
 
-impl fmt::Debug for ClearQuotaByAppSecretRequest {
+impl fmt::Debug for ClearQuotaByAppSecretRequest<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ClearQuotaByAppSecretRequest")
             .field("appid", &self.appid)
@@ -56,14 +62,69 @@ impl fmt::Debug for ClearQuotaByAppSecretRequest {
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct GetRidInfoRequest {
-    rid: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct GetRidInfoRequest<'a> {
+    rid: &'a str,
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct CallbackCheckRequest {
-    action: String,
-    check_operator: String,
+    action: CallbackCheckAction,
+    check_operator: CheckOperator,
+}
+
+// ============================================================================
+// Public Request Types
+// ============================================================================
+
+/// Check to perform in [`OpenApiApi::callback_check`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum CallbackCheckAction {
+    /// DNS resolution and ping
+    All,
+    /// DNS resolution only
+    Dns,
+    /// Ping only
+    Ping,
+}
+
+impl CallbackCheckAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallbackCheckAction::All => "all",
+            CallbackCheckAction::Dns => "dns",
+            CallbackCheckAction::Ping => "ping",
+        }
+    }
+}
+
+/// Network operator used by [`OpenApiApi::callback_check`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CheckOperator {
+    /// China Telecom
+    Chinanet,
+    /// China Unicom
+    Unicom,
+    /// China Mobile
+    Cap,
+    /// Let WeChat pick the operator of the callback server
+    Default,
+}
+
+impl CheckOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckOperator::Chinanet => "CHINANET",
+            CheckOperator::Unicom => "UNICOM",
+            CheckOperator::Cap => "CAP",
+            CheckOperator::Default => "DEFAULT",
+        }
+    }
 }
 
 // ============================================================================
@@ -86,6 +147,7 @@ struct BaseApiResponse {
 /// API quota details
 #[non_exhaustive]
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct QuotaInfo {
     /// Daily API call limit
     #[serde(default)]
@@ -101,6 +163,7 @@ pub struct QuotaInfo {
 /// Response from getApiQuota
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ApiQuotaResponse {
     /// Quota details
     #[serde(default)]
@@ -116,6 +179,7 @@ pub struct ApiQuotaResponse {
 /// Request debug information
 #[non_exhaustive]
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct RidRequestInfo {
     /// Request invocation timestamp (Unix epoch seconds)
     #[serde(default)]
@@ -140,6 +204,7 @@ pub struct RidRequestInfo {
 /// Response from getRidInfo
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct RidInfoResponse {
     /// Request debug information
     #[serde(default)]
@@ -155,6 +220,7 @@ pub struct RidInfoResponse {
 /// DNS check result entry
 #[non_exhaustive]
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DnsInfo {
     /// IP address
     #[serde(default)]
@@ -167,6 +233,7 @@ pub struct DnsInfo {
 /// Ping check result entry
 #[non_exhaustive]
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PingInfo {
     /// IP address
     #[serde(default)]
@@ -182,9 +249,27 @@ pub struct PingInfo {
     pub time: String,
 }
 
+impl PingInfo {
+    /// Response time in milliseconds, parsed from values like `"20.536ms"`
+    pub fn time_ms(&self) -> Option<f64> {
+        self.time.trim().trim_end_matches("ms").trim().parse().ok()
+    }
+
+    /// Packet loss in percent, parsed from values like `"0%"`
+    pub fn packet_loss_percent(&self) -> Option<f64> {
+        self.package_loss
+            .trim()
+            .trim_end_matches('%')
+            .trim()
+            .parse()
+            .ok()
+    }
+}
+
 /// Response from callbackCheck
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CallbackCheckResponse {
     /// DNS check results
     #[serde(default)]
@@ -200,9 +285,55 @@ pub struct CallbackCheckResponse {
     pub(crate) errmsg: String,
 }
 
+impl CallbackCheckResponse {
+    /// Aggregate the DNS and ping results into a single verdict
+    pub fn health(&self) -> CallbackHealth {
+        CallbackHealth {
+            dns_ok: !self.dns.is_empty() && self.dns.iter().all(|dns| !dns.ip.is_empty()),
+            worst_ping_ms: self
+                .ping
+                .iter()
+                .filter_map(PingInfo::time_ms)
+                .reduce(f64::max),
+            packet_loss_max: self
+                .ping
+                .iter()
+                .filter_map(PingInfo::packet_loss_percent)
+                .reduce(f64::max),
+        }
+    }
+}
+
+/// Aggregated verdict of a callbackCheck run
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CallbackHealth {
+    /// Every DNS entry resolved to an IP; `false` when no DNS results were
+    /// returned, including for [`CallbackCheckAction::Ping`] checks
+    pub dns_ok: bool,
+    /// Slowest ping response in milliseconds, `None` without ping results
+    pub worst_ping_ms: Option<f64>,
+    /// Highest packet loss in percent, `None` without ping results
+    pub packet_loss_max: Option<f64>,
+}
+
+impl CallbackHealth {
+    /// Whether DNS resolved and every ping stayed within the given limits
+    ///
+    /// Missing ping results count as unhealthy.
+    pub fn is_healthy(&self, max_ping_ms: f64, max_packet_loss_percent: f64) -> bool {
+        self.dns_ok
+            && self.worst_ping_ms.is_some_and(|ms| ms <= max_ping_ms)
+            && self
+                .packet_loss_max
+                .is_some_and(|loss| loss <= max_packet_loss_percent)
+    }
+}
+
 /// Response from getApiDomainIp and getCallbackIp
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct IpListResponse {
     /// List of IP addresses
     #[serde(default)]
@@ -236,11 +367,17 @@ impl OpenApiApi {
     ///
     /// POST /cgi-bin/clear_quota?access_token=ACCESS_TOKEN
     ///
+    /// Pinned to [`ApiVersion::V2`], calls
+    /// [`clear_quota_by_app_secret`](Self::clear_quota_by_app_secret) instead.
+    ///
     /// # Returns
     /// `Ok(())` on success
     pub async fn clear_quota(&self) -> Result<(), WechatError> {
+        if self.context.client.api_version("openapi.clearQuota") == ApiVersion::V2 {
+            return self.clear_quota_by_app_secret().await;
+        }
         let body = ClearQuotaRequest {
-            appid: self.context.client.appid().to_string(),
+            appid: self.context.client.appid(),
         };
         let response: BaseApiResponse = self
             .context
@@ -257,9 +394,7 @@ impl OpenApiApi {
     /// # Arguments
     /// * `cgi_path` - The API path to query (e.g., "/cgi-bin/message/custom/send")
     pub async fn get_api_quota(&self, cgi_path: &str) -> Result<ApiQuotaResponse, WechatError> {
-        let body = GetApiQuotaRequest {
-            cgi_path: cgi_path.to_string(),
-        };
+        let body = GetApiQuotaRequest { cgi_path };
         let response: ApiQuotaResponse = self
             .context
             .authed_post("/cgi-bin/openapi/quota/get", &body)
@@ -275,9 +410,7 @@ impl OpenApiApi {
     /// # Arguments
     /// * `cgi_path` - The API path to clear quota for
     pub async fn clear_api_quota(&self, cgi_path: &str) -> Result<(), WechatError> {
-        let body = ClearApiQuotaRequest {
-            cgi_path: cgi_path.to_string(),
-        };
+        let body = ClearApiQuotaRequest { cgi_path };
         let response: BaseApiResponse = self
             .context
             .authed_post("/cgi-bin/openapi/quota/clear", &body)
@@ -295,8 +428,8 @@ impl OpenApiApi {
     pub async fn clear_quota_by_app_secret(&self) -> Result<(), WechatError> {
         let path = "/cgi-bin/clear_quota/v2";
         let body = ClearQuotaByAppSecretRequest {
-            appid: self.context.client.appid().to_string(),
-            appsecret: self.context.client.secret().to_string(),
+            appid: self.context.client.appid(),
+            appsecret: self.context.client.secret()?,
         };
         let response: BaseApiResponse = self.context.client.post(path, &body).await?;
         WechatError::check_api(response.errcode, &response.errmsg)?;
@@ -310,9 +443,7 @@ impl OpenApiApi {
     /// # Arguments
     /// * `rid` - The request ID to look up
     pub async fn get_rid_info(&self, rid: &str) -> Result<RidInfoResponse, WechatError> {
-        let body = GetRidInfoRequest {
-            rid: rid.to_string(),
-        };
+        let body = GetRidInfoRequest { rid };
         let response: RidInfoResponse = self
             .context
             .authed_post("/cgi-bin/openapi/rid/get", &body)
@@ -326,16 +457,18 @@ impl OpenApiApi {
     /// POST /cgi-bin/callback/check?access_token=ACCESS_TOKEN
     ///
     /// # Arguments
-    /// * `action` - Check action type (e.g., "all", "dns", "ping")
-    /// * `check_operator` - Operator to check (e.g., "DEFAULT", "CHINANET", "UNICOM", "CAP")
+    /// * `action` - Checks to run
+    /// * `check_operator` - Network operator to check from
+    ///
+    /// Use [`CallbackCheckResponse::health`] for an aggregated verdict.
     pub async fn callback_check(
         &self,
-        action: &str,
-        check_operator: &str,
+        action: CallbackCheckAction,
+        check_operator: CheckOperator,
     ) -> Result<CallbackCheckResponse, WechatError> {
         let body = CallbackCheckRequest {
-            action: action.to_string(),
-            check_operator: check_operator.to_string(),
+            action,
+            check_operator,
         };
         let response: CallbackCheckResponse = self
             .context
@@ -384,6 +517,22 @@ impl WechatApi for OpenApiApi {
 // Tests
 // ============================================================================
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    use super::common::ApiResponseBase;
+
+    schemas.add::<ClearQuotaRequest<'static>, ApiResponseBase>("openapi.clearQuota");
+    schemas.add::<GetApiQuotaRequest<'static>, ApiQuotaResponse>("openapi.getApiQuota");
+    schemas.add::<ClearApiQuotaRequest<'static>, ApiResponseBase>("openapi.clearApiQuota");
+    schemas.add::<ClearQuotaByAppSecretRequest<'static>, ApiResponseBase>(
+        "openapi.clearQuotaByAppSecret",
+    );
+    schemas.add::<GetRidInfoRequest<'static>, RidInfoResponse>("openapi.getRidInfo");
+    schemas.add::<CallbackCheckRequest, CallbackCheckResponse>("openapi.callbackCheck");
+    schemas.response::<IpListResponse>("openapi.getApiDomainIp");
+    schemas.response::<IpListResponse>("openapi.getCallbackIp");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -497,6 +646,51 @@ mod tests {
         assert!(response.ping.is_empty());
     }
 
+    #[test]
+    fn test_callback_check_health() {
+        let json = r#"{
+            "dns": [
+                {"ip": "1.2.3.4", "real_operator": "unicom"},
+                {"ip": "5.6.7.8", "real_operator": "chinanet"}
+            ],
+            "ping": [
+                {"ip": "1.2.3.4", "from_operator": "cap", "package_loss": "0%", "time": "20.536ms"},
+                {"ip": "5.6.7.8", "from_operator": "unicom", "package_loss": "12.5%", "time": "148.2ms"}
+            ]
+        }"#;
+        let health = serde_json::from_str::<CallbackCheckResponse>(json)
+            .unwrap()
+            .health();
+        assert!(health.dns_ok);
+        assert_eq!(health.worst_ping_ms, Some(148.2));
+        assert_eq!(health.packet_loss_max, Some(12.5));
+        assert!(health.is_healthy(200.0, 20.0));
+        assert!(!health.is_healthy(100.0, 20.0));
+
+        let empty = serde_json::from_str::<CallbackCheckResponse>("{}")
+            .unwrap()
+            .health();
+        assert!(!empty.dns_ok);
+        assert_eq!(empty.worst_ping_ms, None);
+        assert!(!empty.is_healthy(1000.0, 100.0));
+    }
+
+    #[test]
+    fn test_callback_check_enums_serialize() {
+        assert_eq!(
+            serde_json::to_value(CallbackCheckAction::Dns).unwrap(),
+            CallbackCheckAction::Dns.as_str()
+        );
+        for operator in [
+            CheckOperator::Chinanet,
+            CheckOperator::Unicom,
+            CheckOperator::Cap,
+            CheckOperator::Default,
+        ] {
+            assert_eq!(serde_json::to_value(operator).unwrap(), operator.as_str());
+        }
+    }
+
     #[test]
     fn test_ip_list_response_parse() {
         let json = r#"{
@@ -587,7 +781,7 @@ mod tests {
         let api = OpenApiApi::new(context);
         let result = api.clear_quota().await;
         assert!(result.is_err());
-        if let Err(WechatError::Api { code, message }) = result {
+        if let Err(WechatError::Api { code, message, .. }) = result {
             assert_eq!(code, 40013);
             assert_eq!(message, "invalid appid");
         } else {
@@ -650,6 +844,35 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_clear_quota_call_options_select_v2() {
+        use crate::client::CallOptions;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        setup_token_mock(&mock_server).await;
+        for endpoint in ["/cgi-bin/clear_quota", "/cgi-bin/clear_quota/v2"] {
+            Mock::given(method("POST"))
+                .and(path(endpoint))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "errcode": 0,
+                    "errmsg": "ok"
+                })))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+
+        let api = OpenApiApi::new(create_test_context(&mock_server.uri()));
+        api.clear_quota().await.unwrap();
+        CallOptions::new()
+            .api_version("openapi.clearQuota", ApiVersion::V2)
+            .scope(api.clear_quota())
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn test_get_rid_info_success() {
         use wiremock::matchers::{method, path, query_param};
@@ -690,7 +913,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_callback_check_success() {
-        use wiremock::matchers::{method, path, query_param};
+        use wiremock::matchers::{body_json, method, path, query_param};
         use wiremock::{Mock, MockServer, ResponseTemplate};
 
         let mock_server = MockServer::start().await;
@@ -699,6 +922,10 @@ mod tests {
         Mock::given(method("POST"))
             .and(path("/cgi-bin/callback/check"))
             .and(query_param("access_token", "test_token"))
+            .and(body_json(serde_json::json!({
+                "action": "all",
+                "check_operator": "DEFAULT"
+            })))
             .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                 "dns": [{"ip": "1.2.3.4", "real_operator": "unicom"}],
                 "ping": [{"ip": "1.2.3.4", "from_operator": "cap", "package_loss": "0%", "time": "20.536ms"}],
@@ -710,7 +937,9 @@ mod tests {
 
         let context = create_test_context(&mock_server.uri());
         let api = OpenApiApi::new(context);
-        let result = api.callback_check("all", "DEFAULT").await;
+        let result = api
+            .callback_check(CallbackCheckAction::All, CheckOperator::Default)
+            .await;
         assert!(result.is_ok());
         let response = result.unwrap();
         assert_eq!(response.dns.len(), 1);
@@ -776,8 +1005,8 @@ mod tests {
     #[test]
     fn test_clear_quota_by_app_secret_request_debug_redacts_secret() {
         let request = ClearQuotaByAppSecretRequest {
-            appid: "wx1234567890abcdef".to_string(),
-            appsecret: "top-secret-appsecret".to_string(),
+            appid: "wx1234567890abcdef",
+            appsecret: "top-secret-appsecret",
         };
 
         let output = format!("{:?}", request);
@@ -2,19 +2,25 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio::sync::mpsc;
 
+use super::common::{scalar, FieldCache};
 use super::{WechatApi, WechatContext};
 use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct EmptyRequest {}
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct RealtimeLogSearchRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -22,6 +28,7 @@ pub struct RealtimeLogSearchRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FeedbackRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -29,6 +36,7 @@ pub struct FeedbackRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FeedbackMediaRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -36,6 +44,7 @@ pub struct FeedbackMediaRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct JsErrDetailRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -43,6 +52,7 @@ pub struct JsErrDetailRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct JsErrListRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -50,6 +60,7 @@ pub struct JsErrListRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct OperationsResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -57,6 +68,182 @@ pub struct OperationsResponse {
     pub(crate) errmsg: String,
     #[serde(flatten)]
     pub extra: HashMap<String, Value>,
+    #[serde(skip)]
+    cache: FieldCache,
+}
+
+impl OperationsResponse {
+    /// Decode the `list` array into typed rows, memoized per row type.
+    pub fn list_as<T>(&self) -> Result<Arc<Vec<T>>, WechatError>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        self.cache.field_as(&self.extra, "list")
+    }
+
+    /// Decode any field into `T`, memoized per field and type.
+    pub fn field_as<T>(&self, key: &str) -> Result<Arc<T>, WechatError>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        self.cache.field_as(&self.extra, key)
+    }
+
+    /// Integer field, read from the top level or the single `list` row.
+    pub fn i64(&self, key: &str) -> Option<i64> {
+        scalar(&self.extra, key).and_then(Value::as_i64)
+    }
+
+    /// Numeric field, read from the top level or the single `list` row.
+    pub fn f64(&self, key: &str) -> Option<f64> {
+        scalar(&self.extra, key).and_then(Value::as_f64)
+    }
+
+    /// String field, read from the top level or the single `list` row.
+    pub fn str(&self, key: &str) -> Option<&str> {
+        scalar(&self.extra, key).and_then(Value::as_str)
+    }
+
+    /// Parse the `gray_release_plan` object returned by `get_gray_release_plan`.
+    pub fn gray_release_plan(&self) -> Option<GrayReleaseProgress> {
+        let plan = self.extra.get("gray_release_plan")?;
+        let status = plan.get("status")?.as_i64()?;
+        Some(GrayReleaseProgress {
+            status: GrayReleaseStatus::from(i32::try_from(status).unwrap_or(i32::MAX)),
+            percentage: plan
+                .get("gray_percentage")
+                .and_then(Value::as_u64)
+                .map_or(0, |p| p.min(100) as u32),
+            create_timestamp: plan.get("create_timestamp").and_then(Value::as_i64),
+            support_debuger_first: plan
+                .get("support_debuger_first")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            support_experiencer_first: plan
+                .get("support_experiencer_first")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        })
+    }
+}
+
+/// Status of a gray (phased) release plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrayReleaseStatus {
+    /// 0: plan created, not started
+    Initial,
+    /// 1: rolling out
+    Executing,
+    /// 2: paused
+    Paused,
+    /// 3: rolled out to everyone
+    Finished,
+    /// 4: plan deleted
+    Deleted,
+    /// Status code not known to this SDK
+    Other(i32),
+}
+
+impl GrayReleaseStatus {
+    /// Returns true once the plan can no longer progress.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            GrayReleaseStatus::Finished | GrayReleaseStatus::Deleted
+        )
+    }
+}
+
+impl From<i32> for GrayReleaseStatus {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => GrayReleaseStatus::Initial,
+            1 => GrayReleaseStatus::Executing,
+            2 => GrayReleaseStatus::Paused,
+            3 => GrayReleaseStatus::Finished,
+            4 => GrayReleaseStatus::Deleted,
+            other => GrayReleaseStatus::Other(other),
+        }
+    }
+}
+
+/// Snapshot of a gray release plan
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrayReleaseProgress {
+    pub status: GrayReleaseStatus,
+    /// Rollout percentage, 0-100
+    pub percentage: u32,
+    pub create_timestamp: Option<i64>,
+    pub support_debuger_first: bool,
+    pub support_experiencer_first: bool,
+}
+
+/// Polling settings for [`OperationsApi::watch_gray_release`]
+///
+/// The interval starts at `min_interval`, doubles while the plan is
+/// unchanged, caps at `max_interval`, and drops back to `min_interval` as
+/// soon as progress moves.
+#[derive(Debug, Clone, Copy)]
+pub struct GrayReleaseWatch {
+    min_interval: Duration,
+    max_interval: Duration,
+}
+
+impl Default for GrayReleaseWatch {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(5),
+            max_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+impl GrayReleaseWatch {
+    /// Default: 5 seconds
+    pub fn min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// Default: 60 seconds
+    pub fn max_interval(mut self, interval: Duration) -> Self {
+        self.max_interval = interval;
+        self
+    }
+
+    fn next_interval(&self, current: Duration, changed: bool) -> Duration {
+        if changed {
+            self.min_interval
+        } else {
+            current
+                .saturating_mul(2)
+                .min(self.max_interval.max(self.min_interval))
+        }
+    }
+}
+
+/// Progress updates produced by [`OperationsApi::watch_gray_release`]
+///
+/// Yields the first snapshot, then one item per change in status or
+/// percentage. Ends after a terminal status or a non-transient error;
+/// transient errors are yielded and polling continues. Dropping the watcher
+/// stops the background poller.
+#[derive(Debug)]
+pub struct GrayReleaseWatcher {
+    receiver: mpsc::Receiver<Result<GrayReleaseProgress, WechatError>>,
+}
+
+impl GrayReleaseWatcher {
+    /// Wait for the next update; `None` once watching has finished.
+    pub async fn next(&mut self) -> Option<Result<GrayReleaseProgress, WechatError>> {
+        self.receiver.recv().await
+    }
+
+    /// Hand back the underlying channel, e.g. to wrap in a `ReceiverStream`.
+    pub fn into_receiver(self) -> mpsc::Receiver<Result<GrayReleaseProgress, WechatError>> {
+        self.receiver
+    }
 }
 
 pub struct OperationsApi {
@@ -126,6 +313,56 @@ impl OperationsApi {
         self.get_json("/wxa/getgrayreleaseplan").await
     }
 
+    /// Poll `get_gray_release_plan` in the background and report progress.
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn watch_gray_release(&self, watch: GrayReleaseWatch) -> GrayReleaseWatcher {
+        let (sender, receiver) = mpsc::channel(8);
+        let api = OperationsApi::new(self.context.clone());
+        tokio::spawn(async move {
+            let mut last: Option<(GrayReleaseStatus, u32)> = None;
+            let mut interval = watch.min_interval;
+            loop {
+                let (item, done, changed) = match api.get_gray_release_plan().await {
+                    Ok(response) => match response.gray_release_plan() {
+                        Some(progress) => {
+                            let key = (progress.status, progress.percentage);
+                            let changed = last != Some(key);
+                            last = Some(key);
+                            let done = progress.status.is_terminal();
+                            (changed.then_some(Ok(progress)), done, changed)
+                        }
+                        None => (
+                            Some(Err(WechatError::Config(
+                                "getgrayreleaseplan response has no gray_release_plan".to_string(),
+                            ))),
+                            true,
+                            false,
+                        ),
+                    },
+                    Err(error) => {
+                        let done = !error.is_transient();
+                        (Some(Err(error)), done, false)
+                    }
+                };
+                if let Some(item) = item {
+                    if sender.send(item).await.is_err() {
+                        return;
+                    }
+                }
+                if done {
+                    return;
+                }
+                interval = watch.next_interval(interval, changed);
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = sender.closed() => return,
+                }
+            }
+        });
+        GrayReleaseWatcher { receiver }
+    }
+
     async fn get_json(&self, endpoint: &str) -> Result<OperationsResponse, WechatError> {
         let response: OperationsResponse = self.context.authed_get(endpoint, &[]).await?;
         WechatError::check_api(response.errcode, &response.errmsg)?;
@@ -153,9 +390,121 @@ impl WechatApi for OperationsApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<EmptyRequest, OperationsResponse>("operations.getDomainInfo");
+    schemas.add::<EmptyRequest, OperationsResponse>("operations.getPerformance");
+    schemas.response::<OperationsResponse>("operations.getSceneList");
+    schemas.response::<OperationsResponse>("operations.getVersionList");
+    schemas.add::<RealtimeLogSearchRequest, OperationsResponse>("operations.realtimeLogSearch");
+    schemas.add::<FeedbackRequest, OperationsResponse>("operations.getFeedback");
+    schemas.add::<FeedbackMediaRequest, OperationsResponse>("operations.getFeedbackMedia");
+    schemas.add::<JsErrDetailRequest, OperationsResponse>("operations.getJsErrDetail");
+    schemas.add::<JsErrListRequest, OperationsResponse>("operations.getJsErrList");
+    schemas.response::<OperationsResponse>("operations.getGrayReleasePlan");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::WechatClient;
+    use crate::types::{AppId, AppSecret};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn create_test_api(base_url: &str) -> OperationsApi {
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(base_url)
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(crate::token::TokenManager::new((*client).clone()));
+        OperationsApi::new(Arc::new(WechatContext::new(client, token_manager)))
+    }
+
+    async fn mount_plan(server: &MockServer, status: i32, percentage: u32, times: u64) {
+        Mock::given(method("GET"))
+            .and(path("/wxa/getgrayreleaseplan"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "gray_release_plan": {
+                    "status": status,
+                    "create_timestamp": 1526913290,
+                    "gray_percentage": percentage,
+                    "support_debuger_first": true,
+                    "support_experiencer_first": false
+                }
+            })))
+            .up_to_n_times(times)
+            .mount(server)
+            .await;
+    }
+
+    #[test]
+    fn gray_release_plan_parses() {
+        let json = r#"{"errcode":0,"errmsg":"ok","gray_release_plan":{"status":1,"create_timestamp":1526913290,"gray_percentage":30,"support_debuger_first":true,"support_experiencer_first":true}}"#;
+        let response: OperationsResponse = serde_json::from_str(json).unwrap();
+        let plan = response.gray_release_plan().unwrap();
+        assert_eq!(plan.status, GrayReleaseStatus::Executing);
+        assert_eq!(plan.percentage, 30);
+        assert_eq!(plan.create_timestamp, Some(1526913290));
+        assert!(plan.support_experiencer_first);
+        assert!(!plan.status.is_terminal());
+        assert_eq!(GrayReleaseStatus::from(9), GrayReleaseStatus::Other(9));
+    }
+
+    #[test]
+    fn watch_interval_backs_off_and_resets() {
+        let watch = GrayReleaseWatch::default()
+            .min_interval(Duration::from_secs(1))
+            .max_interval(Duration::from_secs(3));
+        let next = watch.next_interval(Duration::from_secs(1), false);
+        assert_eq!(next, Duration::from_secs(2));
+        let next = watch.next_interval(next, false);
+        assert_eq!(next, Duration::from_secs(3));
+        assert_eq!(watch.next_interval(next, true), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn watch_gray_release_reports_changes_until_finished() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        mount_plan(&server, 1, 10, 2).await;
+        mount_plan(&server, 1, 50, 1).await;
+        mount_plan(&server, 3, 100, 1).await;
+
+        let api = create_test_api(&server.uri());
+        let mut watcher = api.watch_gray_release(
+            GrayReleaseWatch::default()
+                .min_interval(Duration::from_millis(5))
+                .max_interval(Duration::from_millis(20)),
+        );
+
+        let mut seen = Vec::new();
+        while let Some(update) = watcher.next().await {
+            let update = update.unwrap();
+            seen.push((update.status, update.percentage));
+        }
+        assert_eq!(
+            seen,
+            vec![
+                (GrayReleaseStatus::Executing, 10),
+                (GrayReleaseStatus::Executing, 50),
+                (GrayReleaseStatus::Finished, 100),
+            ]
+        );
+    }
 
     #[test]
     fn operations_response_deserializes() {
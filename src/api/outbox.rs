@@ -0,0 +1,248 @@
+//! Order outbox for retry-safe order creation
+//!
+//! Order creation endpoints (`express/business/order/add`,
+//! `express/local/business/order/add`) are not idempotent on the wire: when a
+//! request times out the order may or may not exist. The outbox records each
+//! client-generated dedup key (the shop order id) before calling WeChat, so a
+//! retry can query the order first instead of creating a duplicate.
+//!
+//! See [`LogisticsApi::create_or_get_order`](crate::api::LogisticsApi::create_or_get_order)
+//! and [`DeliveryApi::create_or_get_local_order`](crate::api::DeliveryApi::create_or_get_local_order).
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use crate::error::WechatError;
+use crate::utils::lock;
+
+/// Progress of an order recorded in an [`OrderOutbox`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutboxState {
+    /// A create request was sent but its outcome is unknown
+    Pending,
+    /// WeChat confirmed the order exists
+    Created,
+}
+
+/// Future returned by [`OrderOutbox`] methods
+pub type OrderOutboxFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<T, WechatError>> + Send + 'a>>;
+
+/// Durable store of order dedup keys
+///
+/// Implement this over the application database so in-flight orders survive
+/// restarts; [`MemoryOrderOutbox`] is enough for a single process.
+pub trait OrderOutbox: Send + Sync {
+    /// Current state of `key`, or `None` if it was never recorded.
+    fn get<'a>(&'a self, key: &'a str) -> OrderOutboxFuture<'a, Option<OutboxState>>;
+
+    /// Record `state` for `key`, replacing any previous state.
+    fn put<'a>(&'a self, key: &'a str, state: OutboxState) -> OrderOutboxFuture<'a, ()>;
+
+    /// Forget `key`, e.g. after WeChat rejected the create request.
+    fn remove<'a>(&'a self, key: &'a str) -> OrderOutboxFuture<'a, ()>;
+}
+
+/// In-process [`OrderOutbox`]; cloning shares the records
+#[derive(Debug, Clone, Default)]
+pub struct MemoryOrderOutbox {
+    records: Arc<Mutex<HashMap<String, OutboxState>>>,
+}
+
+impl MemoryOrderOutbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of recorded keys
+    pub fn len(&self) -> usize {
+        lock(&self.records).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        lock(&self.records).is_empty()
+    }
+}
+
+impl OrderOutbox for MemoryOrderOutbox {
+    fn get<'a>(&'a self, key: &'a str) -> OrderOutboxFuture<'a, Option<OutboxState>> {
+        let state = lock(&self.records).get(key).copied();
+        Box::pin(async move { Ok(state) })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, state: OutboxState) -> OrderOutboxFuture<'a, ()> {
+        lock(&self.records).insert(key.to_string(), state);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> OrderOutboxFuture<'a, ()> {
+        lock(&self.records).remove(key);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Outcome of a create-or-get call
+#[derive(Debug, Clone)]
+pub enum OrderCreation<R> {
+    /// The order was created by this call
+    Created(R),
+    /// The order already existed; holds the query result
+    Existing(R),
+}
+
+impl<R> OrderCreation<R> {
+    pub fn is_created(&self) -> bool {
+        matches!(self, OrderCreation::Created(_))
+    }
+
+    pub fn response(&self) -> &R {
+        match self {
+            OrderCreation::Created(response) | OrderCreation::Existing(response) => response,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        match self {
+            OrderCreation::Created(response) | OrderCreation::Existing(response) => response,
+        }
+    }
+}
+
+/// Runs the outbox protocol for one dedup key.
+///
+/// - `Created`: query and return the existing order.
+/// - `Pending`: query first; an API error means the earlier attempt did not
+///   create the order, so create it now.
+/// - unknown: mark `Pending`, then create.
+///
+/// A successful create marks the key `Created`. A non-transient create error
+/// removes the key; a transient one leaves it `Pending` for the next retry.
+pub(crate) async fn create_or_get<R, Q, QF, C, CF>(
+    outbox: &dyn OrderOutbox,
+    key: &str,
+    query: Q,
+    create: C,
+) -> Result<OrderCreation<R>, WechatError>
+where
+    Q: FnOnce() -> QF,
+    QF: Future<Output = Result<R, WechatError>>,
+    C: FnOnce() -> CF,
+    CF: Future<Output = Result<R, WechatError>>,
+{
+    match outbox.get(key).await? {
+        Some(OutboxState::Created) => return query().await.map(OrderCreation::Existing),
+        Some(OutboxState::Pending) => match query().await {
+            Ok(existing) => {
+                outbox.put(key, OutboxState::Created).await?;
+                return Ok(OrderCreation::Existing(existing));
+            }
+            Err(error) if error.is_transient() => return Err(error),
+            Err(_) => {}
+        },
+        None => outbox.put(key, OutboxState::Pending).await?,
+    }
+
+    match create().await {
+        Ok(created) => {
+            outbox.put(key, OutboxState::Created).await?;
+            Ok(OrderCreation::Created(created))
+        }
+        Err(error) => {
+            if !error.is_transient() {
+                outbox.remove(key).await?;
+            }
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn gateway_timeout() -> WechatError {
+        WechatError::Gateway {
+            code: 504,
+            message: "Gateway Timeout".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_timeout_queries_instead_of_recreating() {
+        let outbox = MemoryOrderOutbox::new();
+        let creates = AtomicUsize::new(0);
+
+        let first: Result<OrderCreation<&str>, _> = create_or_get(
+            &outbox,
+            "express:o1",
+            || async { unreachable!("first attempt must not query") },
+            || async {
+                creates.fetch_add(1, Ordering::SeqCst);
+                Err(gateway_timeout())
+            },
+        )
+        .await;
+        assert!(first.is_err());
+        assert_eq!(
+            outbox.get("express:o1").await.unwrap(),
+            Some(OutboxState::Pending)
+        );
+
+        let retry = create_or_get(
+            &outbox,
+            "express:o1",
+            || async { Ok("waybill-1") },
+            || async {
+                creates.fetch_add(1, Ordering::SeqCst);
+                Ok("duplicate")
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!retry.is_created());
+        assert_eq!(retry.into_inner(), "waybill-1");
+        assert_eq!(creates.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            outbox.get("express:o1").await.unwrap(),
+            Some(OutboxState::Created)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pending_order_not_found_is_created() {
+        let outbox = MemoryOrderOutbox::new();
+        outbox.put("local:s:1", OutboxState::Pending).await.unwrap();
+
+        let result = create_or_get(
+            &outbox,
+            "local:s:1",
+            || async { Err(WechatError::api(930564, "order not exist")) },
+            || async { Ok(7) },
+        )
+        .await
+        .unwrap();
+        assert!(result.is_created());
+        assert_eq!(
+            outbox.get("local:s:1").await.unwrap(),
+            Some(OutboxState::Created)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejected_create_forgets_key() {
+        let outbox = MemoryOrderOutbox::new();
+        let result: Result<OrderCreation<()>, _> = create_or_get(
+            &outbox,
+            "express:bad",
+            || async { unreachable!() },
+            || async { Err(WechatError::api(9300501, "invalid delivery_id")) },
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(outbox.is_empty());
+    }
+}
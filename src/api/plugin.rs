@@ -3,7 +3,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 
 use super::{WechatApi, WechatContext};
@@ -11,22 +11,53 @@ use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ManagePluginApplicationRequest {
     pub action: String,
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
 }
 
+impl ManagePluginApplicationRequest {
+    pub fn new(action: impl Into<String>) -> Self {
+        Self {
+            action: action.into(),
+            payload: HashMap::new(),
+        }
+    }
+
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.payload.insert(key.into(), value.into());
+        self
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ManagePluginRequest {
     pub action: String,
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
 }
 
+impl ManagePluginRequest {
+    pub fn new(action: impl Into<String>) -> Self {
+        Self {
+            action: action.into(),
+            payload: HashMap::new(),
+        }
+    }
+
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.payload.insert(key.into(), value.into());
+        self
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PluginResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -36,6 +67,140 @@ pub struct PluginResponse {
     pub extra: HashMap<String, Value>,
 }
 
+/// Review status of a plugin applicant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginApplicantStatus {
+    /// 1: awaiting the plugin owner's decision
+    Applying,
+    /// 2: approved
+    Approved,
+    /// 3: refused
+    Refused,
+    /// 5: expired without a decision
+    Expired,
+    /// Status code not known to this SDK
+    Other(i32),
+}
+
+impl PluginApplicantStatus {
+    /// Returns true while the application still needs a decision.
+    pub fn is_pending(&self) -> bool {
+        matches!(self, PluginApplicantStatus::Applying)
+    }
+}
+
+impl From<i32> for PluginApplicantStatus {
+    fn from(value: i32) -> Self {
+        match value {
+            1 => PluginApplicantStatus::Applying,
+            2 => PluginApplicantStatus::Approved,
+            3 => PluginApplicantStatus::Refused,
+            5 => PluginApplicantStatus::Expired,
+            other => PluginApplicantStatus::Other(other),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PluginApplicantStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        i32::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl From<PluginApplicantStatus> for i32 {
+    fn from(value: PluginApplicantStatus) -> Self {
+        match value {
+            PluginApplicantStatus::Applying => 1,
+            PluginApplicantStatus::Approved => 2,
+            PluginApplicantStatus::Refused => 3,
+            PluginApplicantStatus::Expired => 5,
+            PluginApplicantStatus::Other(other) => other,
+        }
+    }
+}
+
+impl Serialize for PluginApplicantStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32((*self).into())
+    }
+}
+
+/// Service category of an applicant mini program
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PluginApplicantCategory {
+    #[serde(default)]
+    pub first: String,
+    #[serde(default)]
+    pub second: String,
+}
+
+/// A mini program that applied to use the plugin
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PluginApplicant {
+    pub appid: String,
+    pub status: PluginApplicantStatus,
+    #[serde(default)]
+    pub nickname: String,
+    #[serde(default)]
+    pub headimgurl: String,
+    #[serde(default)]
+    pub categories: Vec<PluginApplicantCategory>,
+    /// Application time as a Unix timestamp (WeChat sends it as a string)
+    #[serde(default, deserialize_with = "timestamp")]
+    pub create_time: Option<i64>,
+    /// Link to the applicant's mini program
+    #[serde(default)]
+    pub apply_url: String,
+    /// Reason given by the applicant
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// One page of plugin applicants from `dev_apply_list`
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PluginApplicantPage {
+    pub applicants: Vec<PluginApplicant>,
+    /// 1-based page number that was requested
+    pub page: u32,
+    /// Page size that was requested
+    pub num: u32,
+}
+
+impl PluginApplicantPage {
+    /// Returns true when the page was full, so the next page may have more.
+    pub fn has_more(&self) -> bool {
+        self.num > 0 && self.applicants.len() >= self.num as usize
+    }
+}
+
+#[derive(Deserialize)]
+struct DevApplyListResponse {
+    #[serde(default)]
+    errcode: i32,
+    #[serde(default)]
+    errmsg: String,
+    #[serde(default)]
+    apply_list: Vec<PluginApplicant>,
+}
+
+fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Int(i64),
+        Str(String),
+    }
+
+    Ok(match Option::<Raw>::deserialize(deserializer)? {
+        Some(Raw::Int(value)) => Some(value),
+        Some(Raw::Str(value)) => value.trim().parse().ok(),
+        None => None,
+    })
+}
+
 pub struct PluginApi {
     context: Arc<WechatContext>,
 }
@@ -45,6 +210,7 @@ impl PluginApi {
         Self { context }
     }
 
+    #[doc(alias = "修改插件使用申请的状态")]
     pub async fn manage_plugin_application(
         &self,
         request: &ManagePluginApplicationRequest,
@@ -52,6 +218,9 @@ impl PluginApi {
         self.post_plugin(request).await
     }
 
+    #[doc(alias = "申请使用插件")]
+    #[doc(alias = "删除已添加的插件")]
+    #[doc(alias = "查询已添加的插件")]
     pub async fn manage_plugin(
         &self,
         request: &ManagePluginRequest,
@@ -59,6 +228,50 @@ impl PluginApi {
         self.post_plugin(request).await
     }
 
+    /// List mini programs that applied to use this plugin
+    ///
+    /// Sends the `dev_apply_list` action; `page` is 1-based and `num` is the
+    /// page size.
+    #[doc(alias = "获取当前所有插件使用方")]
+    pub async fn dev_apply_list(
+        &self,
+        page: u32,
+        num: u32,
+    ) -> Result<PluginApplicantPage, WechatError> {
+        let request = ManagePluginRequest::new("dev_apply_list")
+            .with_field("page", page)
+            .with_field("num", num);
+        let response: DevApplyListResponse =
+            self.context.authed_post("/wxa/plugin", &request).await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(PluginApplicantPage {
+            applicants: response.apply_list,
+            page,
+            num,
+        })
+    }
+
+    /// Collect every applicant by walking `dev_apply_list` pages of `num`
+    pub async fn dev_apply_list_all(&self, num: u32) -> Result<Vec<PluginApplicant>, WechatError> {
+        if num == 0 {
+            return Err(WechatError::Config(
+                "page size must be positive".to_string(),
+            ));
+        }
+
+        let mut applicants = Vec::new();
+        let mut page = 1;
+        loop {
+            let current = self.dev_apply_list(page, num).await?;
+            let has_more = current.has_more();
+            applicants.extend(current.applicants);
+            if !has_more {
+                return Ok(applicants);
+            }
+            page += 1;
+        }
+    }
+
     async fn post_plugin<B: Serialize>(&self, body: &B) -> Result<PluginResponse, WechatError> {
         let response: PluginResponse = self.context.authed_post("/wxa/plugin", body).await?;
         WechatError::check_api(response.errcode, &response.errmsg)?;
@@ -76,9 +289,18 @@ impl WechatApi for PluginApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<ManagePluginApplicationRequest, PluginResponse>("plugin.managePluginApplication");
+    schemas.add::<ManagePluginRequest, PluginResponse>("plugin.managePlugin");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::WechatClient;
+    use crate::token::TokenManager;
+    use crate::types::{AppId, AppSecret};
 
     #[test]
     fn plugin_response_deserializes() {
@@ -86,4 +308,100 @@ mod tests {
         let response: PluginResponse = serde_json::from_str(json).unwrap();
         assert_eq!(response.errcode, 0);
     }
+
+    #[test]
+    fn manage_plugin_request_flattens_fields() {
+        let request = ManagePluginRequest::new("apply").with_field("plugin_appid", "wxplugin");
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({"action": "apply", "plugin_appid": "wxplugin"})
+        );
+    }
+
+    fn create_test_context(base_url: &str) -> Arc<WechatContext> {
+        let appid = AppId::new("wx1234567890abcdef").unwrap();
+        let secret = AppSecret::new("secret1234567890ab").unwrap();
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(appid)
+                .secret(secret)
+                .base_url(base_url)
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(TokenManager::new((*client).clone()));
+        Arc::new(WechatContext::new(client, token_manager))
+    }
+
+    #[test]
+    fn plugin_applicant_deserializes_string_timestamp_and_status() {
+        let json = r#"{
+            "appid": "wxapplicant",
+            "status": 1,
+            "nickname": "Demo",
+            "headimgurl": "https://example.com/a.png",
+            "categories": [{"first": "IT科技", "second": "硬件与设备"}],
+            "create_time": "1536305096",
+            "apply_url": "https://example.com/apply",
+            "reason": "testing"
+        }"#;
+        let applicant: PluginApplicant = serde_json::from_str(json).unwrap();
+        assert_eq!(applicant.status, PluginApplicantStatus::Applying);
+        assert!(applicant.status.is_pending());
+        assert_eq!(applicant.create_time, Some(1536305096));
+        assert_eq!(applicant.categories[0].second, "硬件与设备");
+
+        let applicant: PluginApplicant =
+            serde_json::from_str(r#"{"appid": "wx", "status": 9, "create_time": 7}"#).unwrap();
+        assert_eq!(applicant.status, PluginApplicantStatus::Other(9));
+        assert_eq!(applicant.create_time, Some(7));
+    }
+
+    #[tokio::test]
+    async fn dev_apply_list_all_walks_pages() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+
+        for (page, appids) in [(1, vec!["wxa", "wxb"]), (2, vec!["wxc"])] {
+            let list: Vec<_> = appids
+                .iter()
+                .map(|appid| serde_json::json!({"appid": appid, "status": 2}))
+                .collect();
+            Mock::given(method("POST"))
+                .and(path("/wxa/plugin"))
+                .and(body_partial_json(serde_json::json!({
+                    "action": "dev_apply_list",
+                    "page": page,
+                    "num": 2
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "errcode": 0,
+                    "errmsg": "ok",
+                    "apply_list": list
+                })))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+
+        let api = PluginApi::new(create_test_context(&mock_server.uri()));
+        let applicants = api.dev_apply_list_all(2).await.unwrap();
+
+        let appids: Vec<_> = applicants.iter().map(|a| a.appid.as_str()).collect();
+        assert_eq!(appids, ["wxa", "wxb", "wxc"]);
+        assert!(applicants
+            .iter()
+            .all(|a| a.status == PluginApplicantStatus::Approved));
+    }
 }
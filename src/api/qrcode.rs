@@ -1,12 +1,40 @@
+use std::future::Future;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bytes::Bytes;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
 
 use crate::api::r#trait::{WechatApi, WechatContext};
+use crate::client::{current_call_options, with_call_options};
 use crate::error::WechatError;
+use crate::types::request::MAX_QUERY_LEN;
+use crate::types::MiniProgramPath;
+use crate::utils::{cancelled, BinaryBody};
+use crate::CancellationToken;
+
+/// Mini program version a code opens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum EnvVersion {
+    /// Published version (WeChat's default)
+    #[default]
+    Release,
+    /// Trial version
+    Trial,
+    /// Development version
+    Develop,
+}
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct QrcodeOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
@@ -18,6 +46,10 @@ pub struct QrcodeOptions {
     pub line_color: Option<LineColor>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_hyaline: Option<bool>,
+    /// Version to open; `None` uses the client's
+    /// [`env_version`](crate::WechatMpBuilder::env_version)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_version: Option<EnvVersion>,
 }
 
 impl QrcodeOptions {
@@ -28,11 +60,19 @@ impl QrcodeOptions {
             auto_color: None,
             line_color: None,
             is_hyaline: None,
+            env_version: None,
         }
     }
+
+    /// Set `path` from a validated [`MiniProgramPath`], query included
+    pub fn with_path(mut self, path: MiniProgramPath) -> Self {
+        self.path = Some(path.into());
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LineColor {
     pub r: u8,
     pub g: u8,
@@ -41,6 +81,7 @@ pub struct LineColor {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UnlimitQrcodeOptions {
     pub scene: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -53,6 +94,15 @@ pub struct UnlimitQrcodeOptions {
     pub line_color: Option<LineColor>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_hyaline: Option<bool>,
+    /// Whether WeChat checks that `page` exists in the published version;
+    /// `None` sends `false` for trial and develop codes, where the page may
+    /// not be published yet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub check_path: Option<bool>,
+    /// Version to open; `None` uses the client's
+    /// [`env_version`](crate::WechatMpBuilder::env_version)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_version: Option<EnvVersion>,
 }
 
 impl UnlimitQrcodeOptions {
@@ -64,11 +114,266 @@ impl UnlimitQrcodeOptions {
             auto_color: None,
             line_color: None,
             is_hyaline: None,
+            check_path: None,
+            env_version: None,
+        }
+    }
+
+    /// Set `page` from a validated [`MiniProgramPath`]
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] if the path has a query:
+    /// `getwxacodeunlimit` takes parameters only through `scene`.
+    pub fn with_page(mut self, page: MiniProgramPath) -> Result<Self, WechatError> {
+        if page.query().is_some() {
+            return Err(WechatError::validation(
+                "page",
+                format!(
+                    "unlimited code page must not carry a query (use scene), got {:?}",
+                    page.to_string()
+                ),
+            ));
+        }
+        self.page = Some(page.into());
+        Ok(self)
+    }
+
+    /// Fill unset `env_version`/`check_path` from the client default
+    fn with_env_default(mut self, default: Option<EnvVersion>) -> Self {
+        self.env_version = self.env_version.or(default);
+        if self.check_path.is_none()
+            && self
+                .env_version
+                .is_some_and(|env| env != EnvVersion::Release)
+        {
+            self.check_path = Some(false);
+        }
+        self
+    }
+
+    /// Build options whose `scene` and `width` are checked up front
+    ///
+    /// ```rust
+    /// use wechat_mp_sdk::api::qrcode::{EnvVersion, UnlimitQrcodeOptions};
+    /// use wechat_mp_sdk::types::MiniProgramPath;
+    ///
+    /// let options = UnlimitQrcodeOptions::builder()
+    ///     .scene("table=12")
+    ///     .page(MiniProgramPath::new("pages/order/order")?)
+    ///     .env_version(EnvVersion::Trial)
+    ///     .build()?;
+    /// assert_eq!(options.scene, "table=12");
+    ///
+    /// let too_long = UnlimitQrcodeOptions::builder().scene("x".repeat(33)).build();
+    /// assert!(too_long.is_err());
+    /// # Ok::<(), wechat_mp_sdk::WechatError>(())
+    /// ```
+    pub fn builder() -> UnlimitQrcodeOptionsBuilder {
+        UnlimitQrcodeOptionsBuilder::default()
+    }
+
+    /// Check the options against the rules of `getwxacodeunlimit`
+    ///
+    /// [`QrcodeApi::get_wxa_code_unlimit`] runs this before sending.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] when `scene` is empty, longer than
+    /// [`MAX_SCENE_LEN`] or holds characters other than ASCII letters, digits
+    /// and `!#$&'()*+,/:;=?@-._~`, or when `width` is outside 280–1280.
+    pub fn validate(&self) -> Result<(), WechatError> {
+        validate_scene(&self.scene)?;
+        match self.width {
+            Some(width) if !(280..=1280).contains(&width) => Err(WechatError::validation(
+                "width",
+                format!("{width}px is outside the supported 280–1280px"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Most characters a `getwxacodeunlimit` scene may have
+pub const MAX_SCENE_LEN: usize = 32;
+
+/// Punctuation allowed in a scene besides ASCII letters and digits
+const SCENE_PUNCTUATION: &str = "!#$&'()*+,/:;=?@-._~";
+
+fn validate_scene(scene: &str) -> Result<(), WechatError> {
+    let invalid = |message: String| Err(WechatError::validation("scene", message));
+    if scene.is_empty() {
+        return invalid("must not be empty".to_string());
+    }
+    if let Some((position, c)) = scene
+        .chars()
+        .enumerate()
+        .find(|(_, c)| !c.is_ascii_alphanumeric() && !SCENE_PUNCTUATION.contains(*c))
+    {
+        return invalid(format!(
+            "{c:?} at position {position} is not allowed; use ASCII letters, digits and \
+             {SCENE_PUNCTUATION}, e.g. percent-encode or map longer values to an id"
+        ));
+    }
+    if scene.len() > MAX_SCENE_LEN {
+        return invalid(format!(
+            "{} characters exceed the limit of {MAX_SCENE_LEN}; store the data server-side \
+             and pass its id",
+            scene.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Longest expiry interval of a URL scheme or link, in days
+pub const MAX_EXPIRE_INTERVAL_DAYS: i64 = 30;
+
+/// Check the page and query of a URL scheme or link
+fn validate_jump(path: Option<&str>, query: Option<&str>) -> Result<(), WechatError> {
+    if let Some(path) = path {
+        if path.contains('?') {
+            return Err(WechatError::validation(
+                "path",
+                format!(
+                    "{path:?} carries a query; pass it in query, e.g. with \
+                     for_path(MiniProgramPath::new(..)?)"
+                ),
+            ));
         }
     }
+    if let Some(query) = query {
+        let len = query.chars().count();
+        if len > MAX_QUERY_LEN {
+            return Err(WechatError::validation(
+                "query",
+                format!("{len} characters exceed the limit of {MAX_QUERY_LEN}"),
+            ));
+        }
+        if query.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(WechatError::validation(
+                "query",
+                "must not contain whitespace or control characters; percent-encode them",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check that an expiry of type 0 has a timestamp and one of type 1 an
+/// interval of 1–30 days
+fn validate_expire(
+    expire_type: u8,
+    expire_time: Option<i64>,
+    expire_interval: Option<i64>,
+) -> Result<(), WechatError> {
+    match (expire_type, expire_time, expire_interval) {
+        (0, Some(_), _) => Ok(()),
+        (0, None, _) => Err(WechatError::validation(
+            "expire_time",
+            "is required when expire_type is 0",
+        )),
+        (1, _, Some(days)) if (1..=MAX_EXPIRE_INTERVAL_DAYS).contains(&days) => Ok(()),
+        (1, _, Some(days)) => Err(WechatError::validation(
+            "expire_interval",
+            format!("{days} days is outside 1–{MAX_EXPIRE_INTERVAL_DAYS}"),
+        )),
+        (1, _, None) => Err(WechatError::validation(
+            "expire_interval",
+            "is required when expire_type is 1",
+        )),
+        (other, _, _) => Err(WechatError::validation(
+            "expire_type",
+            format!("{other} is neither 0 (expire_time) nor 1 (expire_interval)"),
+        )),
+    }
+}
+
+/// Builder for [`UnlimitQrcodeOptions`], see [`UnlimitQrcodeOptions::builder`]
+#[derive(Debug, Clone, Default)]
+pub struct UnlimitQrcodeOptionsBuilder {
+    scene: Option<String>,
+    page: Option<MiniProgramPath>,
+    width: Option<u32>,
+    auto_color: Option<bool>,
+    line_color: Option<LineColor>,
+    is_hyaline: Option<bool>,
+    check_path: Option<bool>,
+    env_version: Option<EnvVersion>,
+}
+
+impl UnlimitQrcodeOptionsBuilder {
+    /// Parameters passed to the page, read there from `options.scene`
+    pub fn scene(mut self, scene: impl Into<String>) -> Self {
+        self.scene = Some(scene.into());
+        self
+    }
+
+    /// Page to open, without a query; the home page by default
+    pub fn page(mut self, page: MiniProgramPath) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    /// Image width in px, 280–1280 (WeChat's default is 430)
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn auto_color(mut self, auto_color: bool) -> Self {
+        self.auto_color = Some(auto_color);
+        self
+    }
+
+    /// Line color; turns `auto_color` off unless it was set explicitly
+    pub fn line_color(mut self, color: LineColor) -> Self {
+        self.line_color = Some(color);
+        self.auto_color = self.auto_color.or(Some(false));
+        self
+    }
+
+    /// Transparent background
+    pub fn is_hyaline(mut self, is_hyaline: bool) -> Self {
+        self.is_hyaline = Some(is_hyaline);
+        self
+    }
+
+    /// Whether WeChat checks that `page` exists in the published version
+    pub fn check_path(mut self, check_path: bool) -> Self {
+        self.check_path = Some(check_path);
+        self
+    }
+
+    /// Version to open instead of the client's default
+    pub fn env_version(mut self, env_version: EnvVersion) -> Self {
+        self.env_version = Some(env_version);
+        self
+    }
+
+    /// # Errors
+    /// Returns [`WechatError::Validation`] without a scene, when the page has
+    /// a query, or when [`UnlimitQrcodeOptions::validate`] fails.
+    pub fn build(self) -> Result<UnlimitQrcodeOptions, WechatError> {
+        let scene = self
+            .scene
+            .ok_or_else(|| WechatError::validation("scene", "is required; set it with scene()"))?;
+        let mut options = UnlimitQrcodeOptions {
+            width: self.width,
+            auto_color: self.auto_color,
+            line_color: self.line_color,
+            is_hyaline: self.is_hyaline,
+            check_path: self.check_path,
+            env_version: self.env_version,
+            ..UnlimitQrcodeOptions::new(scene)
+        };
+        if let Some(page) = self.page {
+            options = options.with_page(page)?;
+        }
+        options.validate()?;
+        Ok(options)
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UrlSchemeExpire {
     #[serde(rename = "type")]
     pub expire_type: u8,
@@ -79,6 +384,7 @@ pub struct UrlSchemeExpire {
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UrlSchemeOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
@@ -88,8 +394,40 @@ pub struct UrlSchemeOptions {
     pub expire: Option<UrlSchemeExpire>,
 }
 
+impl UrlSchemeOptions {
+    /// Scheme opening `path`, with its query passed separately
+    pub fn for_path(path: MiniProgramPath) -> Self {
+        Self {
+            query: path.query().map(str::to_string),
+            path: Some(path.page().to_string()),
+            expire: None,
+        }
+    }
+
+    /// Check the options against the rules of `generatescheme`
+    ///
+    /// [`QrcodeApi::generate_url_scheme`] runs this before sending.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] when `path` carries a query, when
+    /// `query` is longer than [`MAX_QUERY_LEN`] or holds whitespace, or when
+    /// `expire` lacks the field its type needs.
+    pub fn validate(&self) -> Result<(), WechatError> {
+        validate_jump(self.path.as_deref(), self.query.as_deref())?;
+        match &self.expire {
+            Some(expire) => validate_expire(
+                expire.expire_type,
+                expire.expire_time,
+                expire.expire_interval,
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UrlSchemeResponse {
     pub openlink: String,
     #[serde(default)]
@@ -99,6 +437,7 @@ pub struct UrlSchemeResponse {
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UrlLinkOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
@@ -112,8 +451,39 @@ pub struct UrlLinkOptions {
     pub expire_interval: Option<i64>,
 }
 
+impl UrlLinkOptions {
+    /// Link opening `path`, with its query passed separately
+    pub fn for_path(path: MiniProgramPath) -> Self {
+        Self {
+            query: path.query().map(str::to_string),
+            path: Some(path.page().to_string()),
+            expire_type: None,
+            expire_time: None,
+            expire_interval: None,
+        }
+    }
+
+    /// Check the options against the rules of `generate_urllink`, the same
+    /// as [`UrlSchemeOptions::validate`]
+    ///
+    /// [`QrcodeApi::generate_url_link`] runs this before sending.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] on the first violation.
+    pub fn validate(&self) -> Result<(), WechatError> {
+        validate_jump(self.path.as_deref(), self.query.as_deref())?;
+        match self.expire_type {
+            Some(expire_type) => {
+                validate_expire(expire_type, self.expire_time, self.expire_interval)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UrlLinkResponse {
     pub link: String,
     #[serde(default)]
@@ -123,12 +493,14 @@ pub struct UrlLinkResponse {
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ShortLinkOptions {
     pub page_url: String,
 }
 
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ShortLinkResponse {
     pub link: String,
     #[serde(default)]
@@ -153,19 +525,28 @@ impl QrcodeApi {
     /// Generate a Mini Program code (limited usage, up to 100,000 codes).
     ///
     /// POST /wxa/getwxacode
-    pub async fn get_wxa_code(&self, options: QrcodeOptions) -> Result<Vec<u8>, WechatError> {
-        self.get_image_bytes("/wxa/getwxacode", &options).await
+    pub async fn get_wxa_code(
+        &self,
+        mut options: QrcodeOptions,
+    ) -> Result<WxaCodeImage, WechatError> {
+        options.env_version = options.env_version.or(self.context.client.env_version());
+        self.get_image("/wxa/getwxacode", &options).await
     }
 
     /// Generate an unlimited Mini Program code (no usage limit).
     ///
     /// POST /wxa/getwxacodeunlimit
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] without sending when
+    /// [`UnlimitQrcodeOptions::validate`] fails.
     pub async fn get_wxa_code_unlimit(
         &self,
         options: UnlimitQrcodeOptions,
-    ) -> Result<Vec<u8>, WechatError> {
-        self.get_image_bytes("/wxa/getwxacodeunlimit", &options)
-            .await
+    ) -> Result<WxaCodeImage, WechatError> {
+        options.validate()?;
+        let options = options.with_env_default(self.context.client.env_version());
+        self.get_image("/wxa/getwxacodeunlimit", &options).await
     }
 
     /// Create a Mini Program QR code for a given page path.
@@ -175,19 +556,16 @@ impl QrcodeApi {
         &self,
         path: &str,
         width: Option<u32>,
-    ) -> Result<Vec<u8>, WechatError> {
+    ) -> Result<WxaCodeImage, WechatError> {
         #[derive(Serialize)]
-        struct Request {
-            path: String,
+        struct Request<'a> {
+            path: &'a str,
             #[serde(skip_serializing_if = "Option::is_none")]
             width: Option<u32>,
         }
 
-        let request = Request {
-            path: path.to_string(),
-            width,
-        };
-        self.get_image_bytes("/cgi-bin/wxaapp/createwxaqrcode", &request)
+        let request = Request { path, width };
+        self.get_image("/cgi-bin/wxaapp/createwxaqrcode", &request)
             .await
     }
 
@@ -198,6 +576,7 @@ impl QrcodeApi {
         &self,
         options: UrlSchemeOptions,
     ) -> Result<String, WechatError> {
+        options.validate()?;
         let response: UrlSchemeResponse = self
             .context
             .authed_post("/wxa/generatescheme", &options)
@@ -212,6 +591,7 @@ impl QrcodeApi {
     ///
     /// POST /wxa/generate_urllink
     pub async fn generate_url_link(&self, options: UrlLinkOptions) -> Result<String, WechatError> {
+        options.validate()?;
         let response: UrlLinkResponse = self
             .context
             .authed_post("/wxa/generate_urllink", &options)
@@ -244,13 +624,11 @@ impl QrcodeApi {
     /// POST /wxa/queryscheme?access_token=ACCESS_TOKEN
     pub async fn query_scheme(&self, scheme: &str) -> Result<QuerySchemeResponse, WechatError> {
         #[derive(Serialize)]
-        struct Request {
-            scheme: String,
+        struct Request<'a> {
+            scheme: &'a str,
         }
 
-        let body = Request {
-            scheme: scheme.to_string(),
-        };
+        let body = Request { scheme };
         let response: QuerySchemeResponse =
             self.context.authed_post("/wxa/queryscheme", &body).await?;
         WechatError::check_api(response.errcode, &response.errmsg)?;
@@ -265,13 +643,11 @@ impl QrcodeApi {
         url_link: &str,
     ) -> Result<QueryUrlLinkResponse, WechatError> {
         #[derive(Serialize)]
-        struct Request {
-            url_link: String,
+        struct Request<'a> {
+            url_link: &'a str,
         }
 
-        let body = Request {
-            url_link: url_link.to_string(),
-        };
+        let body = Request { url_link };
         let response: QueryUrlLinkResponse = self
             .context
             .authed_post("/wxa/query_urllink", &body)
@@ -295,21 +671,87 @@ impl QrcodeApi {
         Ok(response)
     }
 
-    async fn get_image_bytes<T: Serialize>(
+    /// Generate an unlimited code for every scene and write it to `sink`
+    ///
+    /// Codes are generated with at most
+    /// [`QrcodePipeline::concurrency`] requests in flight. A failed scene is
+    /// recorded in the manifest and does not stop the batch.
+    ///
+    /// When `cancel` fires, no further code is requested, the scenes in
+    /// flight are aborted, and the manifest holds the scenes finished before
+    /// it, with `cancelled` set.
+    pub async fn run_pipeline<I>(
         &self,
-        endpoint: &str,
-        body: &T,
-    ) -> Result<Vec<u8>, WechatError> {
-        let response = self.context.authed_post_raw(endpoint, body).await?;
-        if let Err(error) = response.error_for_status_ref() {
-            return Err(error.into());
+        pipeline: &QrcodePipeline,
+        scenes: I,
+        sink: Arc<dyn QrcodeSink>,
+        cancel: Option<&CancellationToken>,
+    ) -> QrcodeManifest
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let mut scenes = scenes.into_iter().map(Into::into).enumerate();
+        let call_options = current_call_options();
+        let mut tasks = JoinSet::new();
+        let mut entries = Vec::new();
+        let mut stopped = false;
+
+        loop {
+            if cancel.is_some_and(CancellationToken::is_cancelled) {
+                stopped = true;
+                break;
+            }
+            while tasks.len() < pipeline.concurrency {
+                let Some((index, scene)) = scenes.next() else {
+                    break;
+                };
+                let api = QrcodeApi::new(Arc::clone(&self.context));
+                let mut options = pipeline.template.clone();
+                options.scene = scene.clone();
+                let sink = Arc::clone(&sink);
+                let call = async move {
+                    let result = match api.get_wxa_code_unlimit(options).await {
+                        Ok(image) => sink.put(&scene, image.into_bytes()).await,
+                        Err(error) => Err(error),
+                    };
+                    (index, QrcodeManifestEntry { scene, result })
+                };
+                tasks.spawn(with_call_options(call_options.clone(), call));
+            }
+
+            let joined = tokio::select! {
+                biased;
+                _ = cancelled(cancel) => {
+                    stopped = true;
+                    break;
+                }
+                joined = tasks.join_next() => joined,
+            };
+            match joined {
+                Some(Ok(entry)) => entries.push(entry),
+                Some(Err(error)) => std::panic::resume_unwind(error.into_panic()),
+                None => break,
+            }
         }
+        tasks.abort_all();
 
-        let bytes = response.bytes().await?;
-        if let Some((code, message)) = parse_api_error_from_json_bytes(&bytes) {
-            return Err(WechatError::Api { code, message });
+        entries.sort_by_key(|(index, _)| *index);
+        QrcodeManifest {
+            entries: entries.into_iter().map(|(_, entry)| entry).collect(),
+            cancelled: stopped,
         }
-        Ok(bytes.to_vec())
+    }
+
+    /// Shared by the three code endpoints: a JSON `errcode` body becomes an
+    /// error, anything else the image
+    async fn get_image<T: Serialize>(
+        &self,
+        endpoint: &str,
+        body: &T,
+    ) -> Result<WxaCodeImage, WechatError> {
+        let body = self.context.authed_post_binary(endpoint, body).await?;
+        Ok(WxaCodeImage::from_body(body))
     }
 }
 
@@ -323,31 +765,211 @@ impl WechatApi for QrcodeApi {
     }
 }
 
-fn parse_api_error_from_json_bytes(bytes: &[u8]) -> Option<(i32, String)> {
-    let value: serde_json::Value = serde_json::from_slice(bytes).ok()?;
-    let raw_code = value.get("errcode")?.as_i64()?;
-    if raw_code == 0 {
-        return None;
+/// Image returned by the Mini Program code endpoints
+///
+/// Dereferences to the image bytes.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WxaCodeImage {
+    pub bytes: Bytes,
+    /// Detected from the image signature, else taken from `Content-Type`
+    pub content_type: String,
+    /// Size WeChat declared in `Content-Length`, when sent
+    pub buffer_size: Option<u64>,
+}
+
+impl WxaCodeImage {
+    fn from_body(body: BinaryBody) -> Self {
+        let content_type = sniff_image_type(&body.bytes)
+            .map(str::to_string)
+            .or_else(|| {
+                let header = body.content_type?;
+                let essence = header.split(';').next().unwrap_or_default().trim();
+                (!essence.is_empty()).then(|| essence.to_ascii_lowercase())
+            })
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        Self {
+            bytes: body.bytes,
+            content_type,
+            buffer_size: body.content_length,
+        }
+    }
+
+    pub fn into_bytes(self) -> Bytes {
+        self.bytes
+    }
+
+    /// `data:<content type>;base64,...`, e.g. for an `<img src>`
+    pub fn as_base64_data_url(&self) -> String {
+        format!(
+            "data:{};base64,{}",
+            self.content_type,
+            BASE64.encode(&self.bytes)
+        )
     }
 
-    let code = i32::try_from(raw_code).unwrap_or_else(|_| {
-        if raw_code.is_negative() {
-            i32::MIN
-        } else {
-            i32::MAX
+    /// Write the image to `path`, replacing any existing file
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Config`] when the file cannot be written.
+    pub async fn save_to(&self, path: impl AsRef<Path>) -> Result<(), WechatError> {
+        let path = path.as_ref().to_path_buf();
+        let bytes = self.bytes.clone();
+        tokio::task::spawn_blocking(move || {
+            std::fs::write(&path, &bytes).map_err(|error| {
+                WechatError::Config(format!("failed to write {}: {error}", path.display()))
+            })
+        })
+        .await
+        .map_err(|error| WechatError::Config(format!("qrcode save task failed: {error}")))?
+    }
+}
+
+impl Deref for WxaCodeImage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl AsRef<[u8]> for WxaCodeImage {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Content type of the image formats WeChat returns, by file signature
+fn sniff_image_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else {
+        None
+    }
+}
+
+/// Future returned by [`QrcodeSink::put`]
+pub type QrcodeSinkFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<String, WechatError>> + Send + 'a>>;
+
+/// Storage for images produced by [`QrcodeApi::run_pipeline`]
+///
+/// Implement this for object stores (S3, COS, OSS); [`FsQrcodeSink`] writes
+/// to a local directory.
+pub trait QrcodeSink: Send + Sync {
+    /// Store the code image for `scene` and return the object key it was
+    /// written under.
+    fn put<'a>(&'a self, scene: &'a str, image: Bytes) -> QrcodeSinkFuture<'a>;
+}
+
+/// [`QrcodeSink`] writing one `<scene>.png` file per code into a directory
+///
+/// Scenes are percent-encoded so that any scene maps to a distinct, safe
+/// file name.
+#[derive(Debug, Clone)]
+pub struct FsQrcodeSink {
+    dir: PathBuf,
+}
+
+impl FsQrcodeSink {
+    /// Write into `dir`, creating it on first use
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl QrcodeSink for FsQrcodeSink {
+    fn put<'a>(&'a self, scene: &'a str, image: Bytes) -> QrcodeSinkFuture<'a> {
+        let key = format!("{}.png", utf8_percent_encode(scene, NON_ALPHANUMERIC));
+        let path = self.dir.join(&key);
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, &image)
+            })
+            .await
+            .map_err(|error| WechatError::Config(format!("qrcode sink task failed: {error}")))?
+            .map_err(|error| WechatError::Config(format!("failed to write {key}: {error}")))?;
+            Ok(key)
+        })
+    }
+}
+
+/// Settings for [`QrcodeApi::run_pipeline`]
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct QrcodePipeline {
+    /// Options applied to every code; `scene` is replaced per code
+    pub template: UnlimitQrcodeOptions,
+    /// Maximum number of codes generated at once (default 4)
+    pub concurrency: usize,
+}
+
+impl QrcodePipeline {
+    pub fn new(template: UnlimitQrcodeOptions) -> Self {
+        Self {
+            template,
+            concurrency: 4,
         }
-    });
-    let message = value
-        .get("errmsg")
-        .and_then(|v| v.as_str())
-        .unwrap_or("unknown error")
-        .to_string();
-    Some((code, message))
+    }
+
+    /// Set the number of codes generated at once (at least 1)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+}
+
+/// Outcome of one scene in a [`QrcodeManifest`]
+#[derive(Debug, Clone)]
+pub struct QrcodeManifestEntry {
+    pub scene: String,
+    /// Object key returned by the sink, or why the scene failed
+    pub result: Result<String, WechatError>,
+}
+
+/// Scene → object key mapping produced by [`QrcodeApi::run_pipeline`],
+/// in input order
+#[derive(Debug, Clone, Default)]
+pub struct QrcodeManifest {
+    pub entries: Vec<QrcodeManifestEntry>,
+    /// Whether the pipeline stopped early because of cancellation
+    pub cancelled: bool,
+}
+
+impl QrcodeManifest {
+    /// Scenes that were stored, with their object keys
+    pub fn keys(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().filter_map(|entry| match &entry.result {
+            Ok(key) => Some((entry.scene.as_str(), key.as_str())),
+            Err(_) => None,
+        })
+    }
+
+    /// Scenes that failed, with their errors
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &WechatError)> {
+        self.entries.iter().filter_map(|entry| match &entry.result {
+            Ok(_) => None,
+            Err(error) => Some((entry.scene.as_str(), error)),
+        })
+    }
+
+    /// Returns true when every scene was stored.
+    pub fn is_complete(&self) -> bool {
+        !self.cancelled && self.entries.iter().all(|entry| entry.result.is_ok())
+    }
 }
 
 /// Scheme info from queryScheme
 #[non_exhaustive]
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SchemeInfo {
     #[serde(default)]
     pub appid: String,
@@ -366,6 +988,7 @@ pub struct SchemeInfo {
 /// Scheme quota info
 #[non_exhaustive]
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SchemeQuota {
     #[serde(default)]
     pub long_time_used: i64,
@@ -376,6 +999,7 @@ pub struct SchemeQuota {
 /// Response from queryScheme
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct QuerySchemeResponse {
     #[serde(default)]
     pub scheme_info: SchemeInfo,
@@ -390,6 +1014,7 @@ pub struct QuerySchemeResponse {
 /// URL Link info from queryUrlLink
 #[non_exhaustive]
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UrlLinkInfo {
     #[serde(default)]
     pub appid: String,
@@ -408,6 +1033,7 @@ pub struct UrlLinkInfo {
 /// URL Link quota info
 #[non_exhaustive]
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UrlLinkQuota {
     #[serde(default)]
     pub long_time_used: i64,
@@ -418,6 +1044,7 @@ pub struct UrlLinkQuota {
 /// Response from queryUrlLink
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct QueryUrlLinkResponse {
     #[serde(default)]
     pub url_link_info: UrlLinkInfo,
@@ -431,6 +1058,7 @@ pub struct QueryUrlLinkResponse {
 
 /// Jump target for NFC Scheme
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct NfcSchemeJumpWxa {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
@@ -442,6 +1070,7 @@ pub struct NfcSchemeJumpWxa {
 
 /// Options for generating NFC Scheme
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct NfcSchemeOptions {
     pub jump_wxa: NfcSchemeJumpWxa,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -453,6 +1082,7 @@ pub struct NfcSchemeOptions {
 /// Response from generateNFCScheme
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct NfcSchemeResponse {
     #[serde(default)]
     pub openlink: String,
@@ -462,10 +1092,40 @@ pub struct NfcSchemeResponse {
     pub(crate) errmsg: String,
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.request::<QrcodeOptions>("qrcode.getQRCode");
+    schemas.request::<UnlimitQrcodeOptions>("qrcode.getUnlimitedQRCode");
+    schemas.add::<UrlSchemeOptions, UrlSchemeResponse>("qrcode.generateScheme");
+    schemas.response::<QuerySchemeResponse>("qrcode.queryScheme");
+    schemas.add::<NfcSchemeOptions, NfcSchemeResponse>("qrcode.generateNFCScheme");
+    schemas.add::<UrlLinkOptions, UrlLinkResponse>("qrcode.generateUrlLink");
+    schemas.response::<QueryUrlLinkResponse>("qrcode.queryUrlLink");
+    schemas.add::<ShortLinkOptions, ShortLinkResponse>("qrcode.generateShortLink");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_options_from_mini_program_path() {
+        let path = MiniProgramPath::new("/pages/detail/detail?id=42").unwrap();
+
+        let options = QrcodeOptions::new().with_path(path.clone());
+        assert_eq!(options.path.as_deref(), Some("pages/detail/detail?id=42"));
+
+        let link = UrlLinkOptions::for_path(path.clone());
+        assert_eq!(link.path.as_deref(), Some("pages/detail/detail"));
+        assert_eq!(link.query.as_deref(), Some("id=42"));
+
+        let unlimited = UnlimitQrcodeOptions::new("id=42").with_page(path);
+        assert!(matches!(unlimited, Err(WechatError::Validation { .. })));
+        let page = MiniProgramPath::new("pages/detail/detail").unwrap();
+        let unlimited = UnlimitQrcodeOptions::new("id=42").with_page(page).unwrap();
+        assert_eq!(unlimited.page.as_deref(), Some("pages/detail/detail"));
+    }
+
     #[test]
     fn test_qrcode_options_defaults() {
         let mut options = QrcodeOptions::new();
@@ -488,6 +1148,8 @@ mod tests {
             auto_color: None,
             line_color: None,
             is_hyaline: None,
+            check_path: None,
+            env_version: None,
         };
         assert_eq!(options.scene, "abc");
     }
@@ -556,4 +1218,309 @@ mod tests {
         assert!(response.scheme_info.appid.is_empty());
         assert_eq!(response.scheme_quota.long_time_used, 0);
     }
+
+    fn create_test_context(base_url: &str) -> Arc<WechatContext> {
+        use crate::client::WechatClient;
+        use crate::token::TokenManager;
+        use crate::types::{AppId, AppSecret};
+
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(base_url)
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(TokenManager::new((*client).clone()));
+        Arc::new(WechatContext::new(client, token_manager))
+    }
+
+    #[tokio::test]
+    async fn test_wxa_code_image_detects_type_and_saves() {
+        let image = WxaCodeImage::from_body(BinaryBody {
+            bytes: Bytes::from_static(b"\xff\xd8\xff\xe0jpeg"),
+            content_type: Some("image/png".to_string()),
+            content_length: Some(8),
+        });
+        assert_eq!(image.content_type, "image/jpeg");
+        assert_eq!(image.buffer_size, Some(8));
+        assert_eq!(
+            image.as_base64_data_url(),
+            "data:image/jpeg;base64,/9j/4GpwZWc="
+        );
+
+        let unknown = WxaCodeImage::from_body(BinaryBody {
+            bytes: Bytes::from_static(b"raw"),
+            content_type: Some("Image/WebP; q=1".to_string()),
+            content_length: None,
+        });
+        assert_eq!(unknown.content_type, "image/webp");
+
+        let path = std::env::temp_dir().join(format!("wxa-code-{}.jpg", std::process::id()));
+        image.save_to(&path).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), &image[..]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_pipeline_writes_sink_and_records_failures() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        struct CountingSink {
+            in_flight: AtomicUsize,
+            peak: AtomicUsize,
+        }
+
+        impl QrcodeSink for CountingSink {
+            fn put<'a>(&'a self, scene: &'a str, image: Bytes) -> QrcodeSinkFuture<'a> {
+                Box::pin(async move {
+                    let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    self.peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                    self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                    assert_eq!(&image[..], b"\x89PNG");
+                    Ok(format!("codes/{scene}.png"))
+                })
+            }
+        }
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/getwxacodeunlimit"))
+            .and(body_partial_json(serde_json::json!({"scene": "bad"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 41030,
+                "errmsg": "invalid page"
+            })))
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/getwxacodeunlimit"))
+            .and(body_partial_json(
+                serde_json::json!({"page": "pages/table"}),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "image/png")
+                    .set_body_bytes(b"\x89PNG".to_vec()),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let sink = Arc::new(CountingSink {
+            in_flight: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        });
+        let mut template = UnlimitQrcodeOptions::new("");
+        template.page = Some("pages/table".to_string());
+        let pipeline = QrcodePipeline::new(template).concurrency(2);
+        let scenes = ["t1", "t2", "bad", "t3", "t4"];
+
+        let api = QrcodeApi::new(create_test_context(&mock_server.uri()));
+        let manifest = api
+            .run_pipeline(&pipeline, scenes, sink.clone(), None)
+            .await;
+
+        let order: Vec<_> = manifest.entries.iter().map(|e| e.scene.as_str()).collect();
+        assert_eq!(order, scenes);
+        let keys: Vec<_> = manifest.keys().collect();
+        assert_eq!(keys[0], ("t1", "codes/t1.png"));
+        assert_eq!(keys.len(), 4);
+        let failures: Vec<_> = manifest.failures().collect();
+        assert_eq!(failures.len(), 1);
+        assert!(matches!(
+            failures[0],
+            ("bad", WechatError::Api { code: 41030, .. })
+        ));
+        assert!(!manifest.is_complete());
+        assert!(sink.peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_unlimit_code_uses_client_env_version_default() {
+        use crate::client::WechatClient;
+        use crate::token::TokenManager;
+        use crate::types::{AppId, AppSecret};
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        for body in [
+            serde_json::json!({"scene": "a", "check_path": false, "env_version": "trial"}),
+            serde_json::json!({"scene": "b", "check_path": true, "env_version": "develop"}),
+            serde_json::json!({"scene": "c", "env_version": "release"}),
+        ] {
+            Mock::given(method("POST"))
+                .and(path("/wxa/getwxacodeunlimit"))
+                .and(body_json(body))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("content-type", "image/png")
+                        .set_body_bytes(b"png".to_vec()),
+                )
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(mock_server.uri())
+                .env_version(EnvVersion::Trial)
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(TokenManager::new((*client).clone()));
+        let api = QrcodeApi::new(Arc::new(WechatContext::new(client, token_manager)));
+
+        api.get_wxa_code_unlimit(UnlimitQrcodeOptions::new("a"))
+            .await
+            .unwrap();
+        let explicit = UnlimitQrcodeOptions::builder()
+            .scene("b")
+            .env_version(EnvVersion::Develop)
+            .check_path(true)
+            .build()
+            .unwrap();
+        api.get_wxa_code_unlimit(explicit).await.unwrap();
+        let mut release = UnlimitQrcodeOptions::new("c");
+        release.env_version = Some(EnvVersion::Release);
+        api.get_wxa_code_unlimit(release).await.unwrap();
+
+        // Rejected locally; no mock matches, so a sent request would fail differently
+        let unsent = api
+            .get_wxa_code_unlimit(UnlimitQrcodeOptions::new("订单=1"))
+            .await;
+        assert!(matches!(
+            unsent,
+            Err(WechatError::Validation { ref field, .. }) if field == "scene"
+        ));
+    }
+
+    #[test]
+    fn test_unlimit_builder_validates_scene_and_width() {
+        let error = |result: Result<UnlimitQrcodeOptions, WechatError>| match result {
+            Err(WechatError::Validation { field, message }) => format!("{field}: {message}"),
+            other => panic!("expected validation error, got {other:?}"),
+        };
+
+        let options = UnlimitQrcodeOptions::builder()
+            .scene("a=1&b=~x_y.z")
+            .width(430)
+            .line_color(LineColor { r: 0, g: 0, b: 0 })
+            .build()
+            .unwrap();
+        assert_eq!(options.auto_color, Some(false));
+        UnlimitQrcodeOptions::builder()
+            .scene("x".repeat(MAX_SCENE_LEN))
+            .build()
+            .unwrap();
+
+        assert!(error(UnlimitQrcodeOptions::builder().build()).starts_with("scene: is required"));
+        assert!(error(UnlimitQrcodeOptions::builder().scene("").build()).contains("empty"));
+        assert!(error(
+            UnlimitQrcodeOptions::builder()
+                .scene("x".repeat(MAX_SCENE_LEN + 1))
+                .build()
+        )
+        .contains("33 characters"));
+        assert!(error(UnlimitQrcodeOptions::builder().scene("id 1").build())
+            .contains("' ' at position 2"));
+        assert!(error(
+            UnlimitQrcodeOptions::builder()
+                .scene("a")
+                .width(100)
+                .build()
+        )
+        .starts_with("width:"));
+
+        let page = MiniProgramPath::new("pages/detail/detail?id=1").unwrap();
+        assert!(matches!(
+            UnlimitQrcodeOptions::builder()
+                .scene("a")
+                .page(page)
+                .build(),
+            Err(WechatError::Validation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_url_scheme_and_link_options_validate() {
+        let field = |result: Result<(), WechatError>| match result {
+            Err(WechatError::Validation { field, .. }) => field,
+            other => panic!("expected validation error, got {other:?}"),
+        };
+        let path = MiniProgramPath::new("pages/detail/detail?id=1&from=商品").unwrap();
+        let mut scheme = UrlSchemeOptions::for_path(path.clone());
+        scheme.expire = Some(UrlSchemeExpire {
+            expire_type: 1,
+            expire_time: None,
+            expire_interval: Some(30),
+        });
+        scheme.validate().unwrap();
+        UrlLinkOptions::for_path(path).validate().unwrap();
+
+        scheme.expire = Some(UrlSchemeExpire {
+            expire_type: 1,
+            expire_time: None,
+            expire_interval: Some(31),
+        });
+        assert_eq!(field(scheme.validate()), "expire_interval");
+        scheme.expire = Some(UrlSchemeExpire {
+            expire_type: 0,
+            expire_time: None,
+            expire_interval: Some(7),
+        });
+        assert_eq!(field(scheme.validate()), "expire_time");
+        scheme.expire = None;
+        scheme.query = Some("q=a b".to_string());
+        assert_eq!(field(scheme.validate()), "query");
+
+        let mut link = UrlLinkOptions::for_path(MiniProgramPath::new("pages/index").unwrap());
+        link.path = Some("pages/index?id=1".to_string());
+        assert_eq!(field(link.validate()), "path");
+        link.path = None;
+        link.query = Some("x".repeat(MAX_QUERY_LEN + 1));
+        assert_eq!(field(link.validate()), "query");
+        link.query = None;
+        link.expire_type = Some(2);
+        assert_eq!(field(link.validate()), "expire_type");
+    }
+
+    #[tokio::test]
+    async fn test_fs_sink_encodes_scene_into_file_name() {
+        let dir = std::env::temp_dir().join(format!("wechat-qrcode-sink-{}", std::process::id()));
+        let sink = FsQrcodeSink::new(&dir);
+
+        let key = sink
+            .put("a=1&b/2", Bytes::from_static(b"png"))
+            .await
+            .unwrap();
+
+        assert_eq!(key, "a%3D1%26b%2F2.png");
+        assert_eq!(std::fs::read(dir.join(&key)).unwrap(), b"png");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
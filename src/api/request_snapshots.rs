@@ -0,0 +1,667 @@
+//! Canonical request body snapshots
+//!
+//! Each case calls a [`WechatMp`] method against a mock server and snapshots
+//! the JSON body sent on the wire into `fixtures/requests/<method>.snap`,
+//! with the endpoint path in the snapshot header. Object keys are sorted, so
+//! other implementations can compare bodies as parsed JSON. Run with
+//! `cargo test --features snapshots`; after an intended body change, review
+//! and accept with `cargo insta review` (or `INSTA_UPDATE=always`).
+//!
+//! Coverage is one case per typed request struct or SDK-built body, about
+//! 60 bodies, not one per POST endpoint. Methods that reuse a struct already
+//! recorded here, or forward a caller-built payload map unchanged, have no
+//! snapshot of their own.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::api::account::{HeadImageCrop, SetNicknameRequest};
+use crate::api::advertising::AdvertisingRequest;
+use crate::api::analytics::{AnalyticsDateRangeRequest, PerformanceDataRequest};
+use crate::api::cloud::{
+    CloudDatabaseRequest, DeleteCloudFileRequest, DownloadFileLinkRequest,
+    InvokeCloudFunctionRequest, UploadFileLinkRequest,
+};
+use crate::api::customer_service::{Message, MiniProgramPageMessage, TextMessage, TypingCommand};
+use crate::api::delivery::DeliveryRequest;
+use crate::api::face::QueryVerifyInfoRequest;
+use crate::api::hardware::HardwareRequest;
+use crate::api::live::{DeleteRoomRequest, GetLiveInfoRequest, LiveRequest};
+use crate::api::logistics::LogisticsRequest;
+use crate::api::nearby::{
+    AddNearbyPoiRequest, DeleteNearbyPoiRequest, NearbyPoiListRequest, NearbyShowStatusRequest,
+};
+use crate::api::ocr::{IdCardOcrRequest, OcrImageRequest};
+use crate::api::openapi::{CallbackCheckAction, CheckOperator};
+use crate::api::plugin::ManagePluginRequest;
+use crate::api::qrcode::{
+    EnvVersion, LineColor, NfcSchemeJumpWxa, NfcSchemeOptions, QrcodeOptions, ShortLinkOptions,
+    UnlimitQrcodeOptions, UrlLinkOptions, UrlSchemeExpire, UrlSchemeOptions,
+};
+use crate::api::security::{RiskScene, Scene, UserRiskRankOptions};
+use crate::api::subscribe::{
+    Lang, MiniProgramState, SetUserNotifyRequest, SubscribeMessageOptions, SubscribeMessageValue,
+};
+use crate::api::wxsearch::SubmitPagesRequest;
+use crate::types::{AppId, AppSecret, OpenId};
+use crate::WechatMp;
+
+const OPENID: &str = "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o";
+
+/// Header metadata of each snapshot
+#[derive(Serialize)]
+struct Endpoint {
+    path: String,
+}
+
+struct Recorder {
+    server: MockServer,
+    wechat: WechatMp,
+}
+
+impl Recorder {
+    async fn start() -> Self {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "snapshot_token",
+                "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"errcode": 0, "errmsg": "ok"})),
+            )
+            .mount(&server)
+            .await;
+        let wechat = WechatMp::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        Self { server, wechat }
+    }
+
+    async fn posts(&self) -> Vec<wiremock::Request> {
+        let requests = self.server.received_requests().await.unwrap();
+        requests
+            .into_iter()
+            .filter(|request| request.method.as_str() == "POST")
+            .collect()
+    }
+
+    /// Run `call`, which must send exactly one POST, and snapshot its body
+    /// as `name`
+    async fn record<F, T>(&self, name: &str, call: F)
+    where
+        F: std::future::Future<Output = T>,
+    {
+        let before = self.posts().await.len();
+        // Only the request matters; binary endpoints reject the JSON reply.
+        let _ = call.await;
+        let posts = self.posts().await;
+        assert_eq!(posts.len(), before + 1, "{name} must send one POST");
+
+        let request = &posts[before];
+        let body: Value = serde_json::from_slice(&request.body).unwrap();
+        let mut settings = insta::Settings::clone_current();
+        settings.set_snapshot_path("../../fixtures/requests");
+        settings.set_prepend_module_to_snapshot(false);
+        settings.set_omit_expression(true);
+        settings.set_info(&Endpoint {
+            path: request.url.path().to_string(),
+        });
+        settings.bind(|| insta::assert_json_snapshot!(name, body));
+    }
+}
+
+fn payload(value: Value) -> HashMap<String, Value> {
+    serde_json::from_value(value).unwrap()
+}
+
+fn openid() -> OpenId {
+    OpenId::new(OPENID).unwrap()
+}
+
+#[tokio::test]
+async fn snapshot_message_requests() {
+    let recorder = Recorder::start().await;
+    let wechat = &recorder.wechat;
+
+    let mut options = SubscribeMessageOptions {
+        touser: openid(),
+        template_id: "TEMPLATE_ID".to_string(),
+        data: HashMap::from([
+            (
+                "thing1".to_string(),
+                SubscribeMessageValue::new("Order shipped"),
+            ),
+            (
+                "time2".to_string(),
+                SubscribeMessageValue::new("2024-01-01 12:00"),
+            ),
+        ]),
+        page: Some("pages/order/index?id=42".to_string()),
+        miniprogram_state: Some(MiniProgramState::Formal),
+        lang: Some(Lang::ZhCN),
+    };
+    recorder
+        .record(
+            "send_subscribe_message",
+            wechat.send_subscribe_message(options.clone()),
+        )
+        .await;
+    options.page = None;
+    options.miniprogram_state = None;
+    options.lang = None;
+    recorder
+        .record(
+            "send_subscribe_message_minimal",
+            wechat.send_subscribe_message(options),
+        )
+        .await;
+
+    recorder
+        .record(
+            "send_customer_service_message_text",
+            wechat.send_customer_service_message(
+                OPENID,
+                Message::Text {
+                    text: TextMessage::new("Hello"),
+                },
+            ),
+        )
+        .await;
+    recorder
+        .record(
+            "send_customer_service_message_miniprogrampage",
+            wechat.send_customer_service_message(
+                OPENID,
+                Message::MiniProgramPage {
+                    miniprogrampage: MiniProgramPageMessage::new(
+                        "Order",
+                        AppId::new("wx1234567890abcdef").unwrap(),
+                        "pages/order/index",
+                        "THUMB_MEDIA_ID",
+                    ),
+                },
+            ),
+        )
+        .await;
+    recorder
+        .record(
+            "set_typing",
+            wechat.set_typing(OPENID, TypingCommand::Typing),
+        )
+        .await;
+    recorder
+        .record(
+            "update_user_notify",
+            wechat.update_user_notify(
+                &SetUserNotifyRequest::new(OPENID, 1, "NOTIFY_CODE", &json!({"order_id": "42"}))
+                    .unwrap(),
+            ),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn snapshot_qrcode_requests() {
+    let recorder = Recorder::start().await;
+    let wechat = &recorder.wechat;
+
+    let mut qrcode = QrcodeOptions::new();
+    qrcode.path = Some("pages/index/index?id=42".to_string());
+    qrcode.width = Some(430);
+    qrcode.auto_color = Some(false);
+    qrcode.line_color = Some(LineColor { r: 0, g: 0, b: 0 });
+    qrcode.is_hyaline = Some(true);
+    qrcode.env_version = Some(EnvVersion::Trial);
+    recorder
+        .record("get_wxa_code", wechat.get_wxa_code(qrcode))
+        .await;
+
+    let mut unlimit = UnlimitQrcodeOptions::new("id=42");
+    unlimit.page = Some("pages/index/index".to_string());
+    unlimit.width = Some(280);
+    unlimit.check_path = Some(false);
+    unlimit.env_version = Some(EnvVersion::Develop);
+    recorder
+        .record("get_wxa_code_unlimit", wechat.get_wxa_code_unlimit(unlimit))
+        .await;
+
+    recorder
+        .record(
+            "create_qrcode",
+            wechat.create_qrcode("pages/index/index", Some(430)),
+        )
+        .await;
+    recorder
+        .record(
+            "generate_url_scheme",
+            wechat.generate_url_scheme(UrlSchemeOptions {
+                path: Some("pages/index/index".to_string()),
+                query: Some("id=42".to_string()),
+                expire: Some(UrlSchemeExpire {
+                    expire_type: 1,
+                    expire_time: None,
+                    expire_interval: Some(30),
+                }),
+            }),
+        )
+        .await;
+    recorder
+        .record(
+            "generate_url_link",
+            wechat.generate_url_link(UrlLinkOptions {
+                path: Some("pages/index/index".to_string()),
+                query: Some("id=42".to_string()),
+                expire_type: Some(0),
+                expire_time: Some(1_704_153_600),
+                expire_interval: None,
+            }),
+        )
+        .await;
+    recorder
+        .record(
+            "generate_short_link",
+            wechat.generate_short_link(ShortLinkOptions {
+                page_url: "pages/index/index?id=42".to_string(),
+            }),
+        )
+        .await;
+    recorder
+        .record(
+            "generate_nfc_scheme",
+            wechat.generate_nfc_scheme(NfcSchemeOptions {
+                jump_wxa: NfcSchemeJumpWxa {
+                    path: Some("pages/index/index".to_string()),
+                    query: Some("id=42".to_string()),
+                    env_version: Some("release".to_string()),
+                },
+                model_id: Some("MODEL_ID".to_string()),
+                sn: Some("SN0001".to_string()),
+            }),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn snapshot_security_and_openapi_requests() {
+    let recorder = Recorder::start().await;
+    let wechat = &recorder.wechat;
+
+    recorder
+        .record(
+            "msg_sec_check",
+            wechat.msg_sec_check(OPENID, Scene::Comment, "hello world"),
+        )
+        .await;
+    recorder
+        .record(
+            "media_check_async",
+            wechat.media_check_async("https://example.com/image.png", 2, OPENID, Scene::Profile),
+        )
+        .await;
+    recorder
+        .record(
+            "get_user_risk_rank",
+            wechat.get_user_risk_rank(
+                OPENID,
+                RiskScene::Marketing,
+                Some(UserRiskRankOptions {
+                    client_ip: Some("203.0.113.7".to_string()),
+                    is_test: Some(true),
+                    ..UserRiskRankOptions::default()
+                }),
+            ),
+        )
+        .await;
+    recorder
+        .record(
+            "get_api_quota",
+            wechat.get_api_quota("/cgi-bin/message/custom/send"),
+        )
+        .await;
+    recorder
+        .record(
+            "callback_check",
+            wechat.callback_check(CallbackCheckAction::All, CheckOperator::Default),
+        )
+        .await;
+    recorder
+        .record("get_phone_number", wechat.get_phone_number("PHONE_CODE"))
+        .await;
+}
+
+#[tokio::test]
+async fn snapshot_analytics_and_operations_requests() {
+    let recorder = Recorder::start().await;
+    let wechat = &recorder.wechat;
+
+    recorder
+        .record(
+            "get_daily_visit_trend",
+            wechat.get_daily_visit_trend(&AnalyticsDateRangeRequest::new("20240101", "20240101")),
+        )
+        .await;
+    recorder
+        .record(
+            "get_performance_data",
+            wechat.get_performance_data(&PerformanceDataRequest {
+                cost_time_type: 2,
+                default_start_time: 1_704_067_200,
+                default_end_time: 1_704_153_600,
+                device: Some("@_all".to_string()),
+                networktype: Some("wifi".to_string()),
+                scene: None,
+            }),
+        )
+        .await;
+    recorder
+        .record(
+            "manage_plugin",
+            wechat.manage_plugin(&ManagePluginRequest {
+                action: "apply".to_string(),
+                payload: payload(json!({
+                    "plugin_appid": "wxabcdef1234567890",
+                    "reason": "order tracking"
+                })),
+            }),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn snapshot_nearby_and_cloud_requests() {
+    let recorder = Recorder::start().await;
+    let wechat = &recorder.wechat;
+
+    let mut poi =
+        AddNearbyPoiRequest::new("POI_ID", "Example Store", "91110000XXXXXXXX", "Beijing");
+    poi.pic_list = Some("MEDIA_1|MEDIA_2".to_string());
+    recorder
+        .record("add_nearby_poi", wechat.add_nearby_poi(&poi))
+        .await;
+    recorder
+        .record(
+            "delete_nearby_poi",
+            wechat.delete_nearby_poi(&DeleteNearbyPoiRequest::new("POI_ID")),
+        )
+        .await;
+    recorder
+        .record(
+            "get_nearby_poi_list",
+            wechat.get_nearby_poi_list(&NearbyPoiListRequest {
+                page: Some(1),
+                page_rows: Some(20),
+            }),
+        )
+        .await;
+    recorder
+        .record(
+            "set_nearby_show_status",
+            wechat.set_nearby_show_status(&NearbyShowStatusRequest { is_open: 1 }),
+        )
+        .await;
+
+    recorder
+        .record(
+            "invoke_cloud_function",
+            wechat.invoke_cloud_function(&InvokeCloudFunctionRequest {
+                payload: payload(json!({"env": "prod-1a2b", "name": "login", "data": {"a": 1}})),
+            }),
+        )
+        .await;
+    recorder
+        .record(
+            "database_query",
+            wechat.database_query(&CloudDatabaseRequest::new(
+                "db.collection(\"orders\").where({status: 1}).limit(10).get()",
+            )),
+        )
+        .await;
+    recorder
+        .record(
+            "get_upload_file_link",
+            wechat.get_upload_file_link(&UploadFileLinkRequest::new("prod-1a2b", "images/a.png")),
+        )
+        .await;
+    recorder
+        .record(
+            "get_download_file_link",
+            wechat.get_download_file_link(&DownloadFileLinkRequest::new(
+                "prod-1a2b",
+                ["cloud://prod-1a2b.7072-prod/images/a.png"],
+            )),
+        )
+        .await;
+    recorder
+        .record(
+            "delete_cloud_file",
+            wechat.delete_cloud_file(&DeleteCloudFileRequest::new(
+                "prod-1a2b",
+                ["cloud://prod-1a2b.7072-prod/images/a.png"],
+            )),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn snapshot_commerce_requests() {
+    let recorder = Recorder::start().await;
+    let wechat = &recorder.wechat;
+
+    recorder
+        .record(
+            "create_room",
+            wechat.create_room(&LiveRequest {
+                payload: payload(json!({
+                    "name": "Launch",
+                    "coverImg": "MEDIA_ID",
+                    "startTime": 1_704_067_200,
+                    "endTime": 1_704_074_400,
+                    "anchorName": "Host",
+                    "anchorWechat": "host_wechat",
+                    "type": 0,
+                    "screenType": 0,
+                    "closeLike": 0,
+                    "closeGoods": 0,
+                    "closeComment": 0
+                })),
+            }),
+        )
+        .await;
+    recorder
+        .record(
+            "delete_room",
+            wechat.delete_room(&DeleteRoomRequest { id: 42 }),
+        )
+        .await;
+    recorder
+        .record(
+            "get_live_info",
+            wechat.get_live_info(&GetLiveInfoRequest {
+                start: 0,
+                limit: 10,
+            }),
+        )
+        .await;
+    recorder
+        .record(
+            "add_local_order",
+            wechat.add_local_order(&DeliveryRequest {
+                payload: payload(json!({
+                    "shopid": "SHOP_ID",
+                    "shop_order_id": "ORDER_42",
+                    "delivery_id": "SFTC",
+                    "openid": OPENID
+                })),
+            }),
+        )
+        .await;
+    recorder
+        .record(
+            "add_order",
+            wechat.add_order(&LogisticsRequest::new(payload(json!({
+                "order_id": "ORDER_42",
+                "openid": OPENID,
+                "delivery_id": "SF",
+                "biz_id": "BIZ_ID",
+                "add_source": 0
+            })))),
+        )
+        .await;
+    recorder
+        .record(
+            "add_user_action",
+            wechat.add_user_action(&AdvertisingRequest {
+                payload: payload(json!({
+                    "user_action_set_id": 1_100_000_000,
+                    "actions": [{"action_type": "COMPLETE_ORDER", "action_time": 1_704_067_200}]
+                })),
+            }),
+        )
+        .await;
+    recorder
+        .record(
+            "send_hardware_device_message",
+            wechat.send_hardware_device_message(&HardwareRequest {
+                payload: payload(json!({
+                    "to_openid_list": [OPENID],
+                    "sn": "SN0001",
+                    "template_id": "TEMPLATE_ID",
+                    "modelId": "MODEL_ID"
+                })),
+            }),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn snapshot_service_requests() {
+    let recorder = Recorder::start().await;
+    let wechat = &recorder.wechat;
+
+    recorder
+        .record(
+            "printed_text_ocr",
+            wechat.printed_text_ocr(&OcrImageRequest::new("https://example.com/text.png")),
+        )
+        .await;
+    let mut id_card = IdCardOcrRequest::new("https://example.com/id.png");
+    id_card.r#type = Some("photo".to_string());
+    recorder
+        .record("id_card_ocr", wechat.id_card_ocr(&id_card))
+        .await;
+    recorder
+        .record(
+            "query_verify_info",
+            wechat.query_verify_info(&QueryVerifyInfoRequest::new("VERIFY_TOKEN")),
+        )
+        .await;
+    recorder
+        .record(
+            "submit_pages",
+            wechat.submit_pages(&SubmitPagesRequest::new(["pages/index/index?id=42"])),
+        )
+        .await;
+
+    let mut nickname = SetNicknameRequest::new("Example Store");
+    nickname.license = Some("LICENSE_MEDIA_ID".to_string());
+    recorder
+        .record("set_nickname", wechat.set_nickname(&nickname))
+        .await;
+    recorder
+        .record(
+            "modify_head_image",
+            wechat.modify_head_image("MEDIA_ID", HeadImageCrop::FULL),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn snapshot_scalar_argument_requests() {
+    let recorder = Recorder::start().await;
+    let wechat = &recorder.wechat;
+
+    recorder
+        .record(
+            "add_template",
+            wechat.add_template("TID", Some(vec![3, 1, 2]), Some("order updates")),
+        )
+        .await;
+    recorder
+        .record("delete_template", wechat.delete_template("PRI_TMPL_ID"))
+        .await;
+    recorder
+        .record(
+            "clear_api_quota",
+            wechat.clear_api_quota("/cgi-bin/message/custom/send"),
+        )
+        .await;
+    recorder
+        .record("get_rid_info", wechat.get_rid_info("RID"))
+        .await;
+    recorder
+        .record("get_plugin_open_pid", wechat.get_plugin_open_pid("CODE"))
+        .await;
+    recorder
+        .record(
+            "check_encrypted_data",
+            wechat.check_encrypted_data("ENCRYPTED_MSG_HASH"),
+        )
+        .await;
+    recorder
+        .record(
+            "get_user_encrypt_key",
+            wechat.get_user_encrypt_key(OPENID, "SIGNATURE", "hmac_sha256"),
+        )
+        .await;
+    recorder
+        .record(
+            "query_scheme",
+            wechat.query_scheme("weixin://dl/business/?t=XTSkBZlzqmn"),
+        )
+        .await;
+    recorder
+        .record(
+            "query_url_link",
+            wechat.query_url_link("https://wxaurl.cn/BQZRrcFCPvg"),
+        )
+        .await;
+    recorder
+        .record("bind_kf_work", wechat.bind_kf_work(OPENID, "OPEN_KFID"))
+        .await;
+    recorder
+        .record("unbind_kf_work", wechat.unbind_kf_work(OPENID, "OPEN_KFID"))
+        .await;
+    recorder
+        .record("plugin_dev_apply_list", wechat.plugin_dev_apply_list(1, 20))
+        .await;
+    recorder
+        .record("check_nickname", wechat.check_nickname("Example Store"))
+        .await;
+    recorder
+        .record(
+            "modify_signature",
+            wechat.modify_signature("Fresh every day"),
+        )
+        .await;
+    recorder
+        .record("query_nickname_audit", wechat.query_nickname_audit(42))
+        .await;
+    recorder
+        .record(
+            "get_cloud_file_info",
+            wechat.get_cloud_file_info("prod-1a2b", &["cloud://prod-1a2b.7072-prod/a.png"]),
+        )
+        .await;
+}
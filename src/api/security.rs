@@ -7,40 +7,141 @@
 //! - [`SecurityApi::msg_sec_check`] - Check text content for policy violations
 //! - [`SecurityApi::media_check_async`] - Async check media for policy violations
 //! - [`SecurityApi::get_user_risk_rank`] - Get user risk rank score
+//! - [`SecurityApi::assess_risk`] - Risk rank interpreted as a [`RiskPolicy`]
 
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
+use super::endpoint_inventory::ApiVersion;
 use super::{WechatApi, WechatContext};
 use crate::error::WechatError;
 
+// ============================================================================
+// Scene Types
+// ============================================================================
+
+/// Content scene for [`SecurityApi::msg_sec_check`] and
+/// [`SecurityApi::media_check_async`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scene {
+    /// User profile (资料)
+    Profile,
+    /// Comment (评论)
+    Comment,
+    /// Forum post (论坛)
+    Forum,
+    /// Social log (社交日志)
+    SocialLog,
+}
+
+impl From<Scene> for u8 {
+    fn from(scene: Scene) -> Self {
+        match scene {
+            Scene::Profile => 1,
+            Scene::Comment => 2,
+            Scene::Forum => 3,
+            Scene::SocialLog => 4,
+        }
+    }
+}
+
+impl TryFrom<u8> for Scene {
+    type Error = WechatError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Scene::Profile),
+            2 => Ok(Scene::Comment),
+            3 => Ok(Scene::Forum),
+            4 => Ok(Scene::SocialLog),
+            other => Err(WechatError::Config(format!(
+                "invalid content security scene: {other}"
+            ))),
+        }
+    }
+}
+
+impl Serialize for Scene {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8((*self).into())
+    }
+}
+
+/// Scene for [`SecurityApi::get_user_risk_rank`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RiskScene {
+    /// Registration (注册)
+    Registration,
+    /// Marketing campaign (营销作弊)
+    Marketing,
+}
+
+impl From<RiskScene> for u8 {
+    fn from(scene: RiskScene) -> Self {
+        match scene {
+            RiskScene::Registration => 0,
+            RiskScene::Marketing => 1,
+        }
+    }
+}
+
+impl TryFrom<u8> for RiskScene {
+    type Error = WechatError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(RiskScene::Registration),
+            1 => Ok(RiskScene::Marketing),
+            other => Err(WechatError::Config(format!("invalid risk scene: {other}"))),
+        }
+    }
+}
+
+impl Serialize for RiskScene {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8((*self).into())
+    }
+}
+
 // ============================================================================
 // Request Types (internal)
 // ============================================================================
 
 #[derive(Debug, Clone, Serialize)]
-struct MsgSecCheckRequest {
-    version: u8,
-    openid: String,
-    scene: u8,
-    content: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct MsgSecCheckRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    openid: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<u8>"))]
+    scene: Option<Scene>,
+    content: &'a str,
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct MediaCheckAsyncRequest {
-    media_url: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct MediaCheckAsyncRequest<'a> {
+    media_url: &'a str,
     media_type: u8,
-    version: u8,
-    openid: String,
-    scene: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    openid: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<u8>"))]
+    scene: Option<Scene>,
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct UserRiskRankRequest {
-    appid: String,
-    openid: String,
-    scene: u8,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct UserRiskRankRequest<'a> {
+    appid: &'a str,
+    openid: &'a str,
+    #[cfg_attr(feature = "schemars", schemars(with = "u8"))]
+    scene: RiskScene,
     #[serde(skip_serializing_if = "Option::is_none")]
     client_ip: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -59,7 +160,8 @@ struct UserRiskRankRequest {
 
 /// Detail item from message security check
 #[non_exhaustive]
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MsgSecCheckDetail {
     /// Strategy used
     #[serde(default)]
@@ -83,7 +185,8 @@ pub struct MsgSecCheckDetail {
 
 /// Result summary from message security check
 #[non_exhaustive]
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MsgSecCheckResult {
     /// Suggestion: "pass", "risky", or "review"
     #[serde(default)]
@@ -93,10 +196,21 @@ pub struct MsgSecCheckResult {
     pub label: i32,
 }
 
+impl MsgSecCheckDetail {
+    /// Returns true when this strategy found nothing to flag.
+    pub fn is_pass(&self) -> bool {
+        self.suggest == "pass"
+    }
+}
+
 /// Response from msgSecCheck
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MsgSecCheckResponse {
+    /// Request ID to quote when appealing a verdict (v2 only)
+    #[serde(default, alias = "traceId")]
+    pub trace_id: String,
     /// Overall result
     #[serde(default)]
     pub result: MsgSecCheckResult,
@@ -111,12 +225,35 @@ pub struct MsgSecCheckResponse {
     pub(crate) errmsg: String,
 }
 
+impl MsgSecCheckResponse {
+    /// Highest `prob` reported by any strategy
+    pub fn max_prob(&self) -> Option<i32> {
+        self.detail.iter().map(|detail| detail.prob).max()
+    }
+
+    /// Strategy with the highest `prob`; the first one on ties
+    pub fn top_detail(&self) -> Option<&MsgSecCheckDetail> {
+        self.detail.iter().rev().max_by_key(|detail| detail.prob)
+    }
+
+    /// Result of the named strategy, e.g. `content_model` or `keyword`
+    pub fn strategy(&self, name: &str) -> Option<&MsgSecCheckDetail> {
+        self.detail.iter().find(|detail| detail.strategy == name)
+    }
+
+    /// Strategies whose suggestion is not `pass`
+    pub fn flagged(&self) -> impl Iterator<Item = &MsgSecCheckDetail> {
+        self.detail.iter().filter(|detail| !detail.is_pass())
+    }
+}
+
 /// Response from mediaCheckAsync
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MediaCheckAsyncResponse {
     /// Trace ID for querying result
-    #[serde(default)]
+    #[serde(default, alias = "traceId")]
     pub trace_id: String,
     /// Error code (0 means success)
     #[serde(default)]
@@ -129,6 +266,7 @@ pub struct MediaCheckAsyncResponse {
 /// Response from getUserRiskRank
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UserRiskRankResponse {
     /// Risk rank: 0-4 (0=no risk, 4=highest risk)
     #[serde(default)]
@@ -160,6 +298,145 @@ pub struct UserRiskRankOptions {
     pub is_test: Option<bool>,
 }
 
+// ============================================================================
+// Risk Assessment
+// ============================================================================
+
+/// Action to take for a user, derived from their risk rank
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RiskPolicy {
+    /// Proceed normally
+    Allow,
+    /// Require extra verification (captcha, SMS code, ...)
+    Challenge,
+    /// Refuse the operation
+    Deny,
+}
+
+/// Request context for [`SecurityApi::assess_risk`]
+///
+/// Collects what the app knows about the login or registration attempt and
+/// the rank thresholds used to turn the 0-4 risk rank into a [`RiskPolicy`].
+/// By default ranks 0-1 allow, 2 challenges and 3-4 deny.
+///
+/// ```
+/// use wechat_mp_sdk::api::{RiskAssessment, RiskPolicy, RiskScene};
+///
+/// let assessment = RiskAssessment::new("openid", RiskScene::Registration)
+///     .client_ip("203.0.113.7")
+///     .mobile_no("13800000000");
+/// assert_eq!(assessment.policy_for(2), RiskPolicy::Challenge);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RiskAssessment {
+    openid: String,
+    scene: RiskScene,
+    options: UserRiskRankOptions,
+    challenge_at: i32,
+    deny_at: i32,
+}
+
+impl RiskAssessment {
+    /// Create an assessment for `openid` with the default thresholds
+    pub fn new(openid: impl Into<String>, scene: RiskScene) -> Self {
+        Self {
+            openid: openid.into(),
+            scene,
+            options: UserRiskRankOptions::default(),
+            challenge_at: 2,
+            deny_at: 3,
+        }
+    }
+
+    /// Client IP address of the request
+    pub fn client_ip(mut self, client_ip: impl Into<String>) -> Self {
+        self.options.client_ip = Some(client_ip.into());
+        self
+    }
+
+    /// Mobile number the user signed in with
+    pub fn mobile_no(mut self, mobile_no: impl Into<String>) -> Self {
+        self.options.mobile_no = Some(mobile_no.into());
+        self
+    }
+
+    /// Email address the user signed in with
+    pub fn email_address(mut self, email_address: impl Into<String>) -> Self {
+        self.options.email_address = Some(email_address.into());
+        self
+    }
+
+    /// Extended info string forwarded to WeChat
+    pub fn extended_info(mut self, extended_info: impl Into<String>) -> Self {
+        self.options.extended_info = Some(extended_info.into());
+        self
+    }
+
+    /// Mark the request as a test request
+    pub fn is_test(mut self, is_test: bool) -> Self {
+        self.options.is_test = Some(is_test);
+        self
+    }
+
+    /// Lowest ranks that trigger [`RiskPolicy::Challenge`] and
+    /// [`RiskPolicy::Deny`]
+    ///
+    /// `deny_at` is raised to `challenge_at` if it is lower.
+    pub fn thresholds(mut self, challenge_at: i32, deny_at: i32) -> Self {
+        self.challenge_at = challenge_at;
+        self.deny_at = deny_at.max(challenge_at);
+        self
+    }
+
+    /// Policy for a given risk rank under this assessment's thresholds
+    pub fn policy_for(&self, risk_rank: i32) -> RiskPolicy {
+        if risk_rank >= self.deny_at {
+            RiskPolicy::Deny
+        } else if risk_rank >= self.challenge_at {
+            RiskPolicy::Challenge
+        } else {
+            RiskPolicy::Allow
+        }
+    }
+}
+
+/// Outcome of [`SecurityApi::assess_risk`]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiskDecision {
+    /// Policy derived from `risk_rank`
+    pub policy: RiskPolicy,
+    /// Risk rank returned by WeChat (0-4)
+    pub risk_rank: i32,
+    /// Union ID of the query, for correlating with WeChat support
+    pub unoin_id: i32,
+}
+
+/// Most characters `msg_sec_check` accepts in one `content`
+pub const MAX_MSG_SEC_CHECK_LEN: usize = 2500;
+
+fn validate_msg_sec_check(openid: Option<&str>, content: &str) -> Result<(), WechatError> {
+    if openid.is_some_and(|openid| openid.trim().is_empty()) {
+        return Err(WechatError::validation(
+            "openid",
+            "must not be empty; the user must have visited the mini program in the last 2 hours",
+        ));
+    }
+    if content.trim().is_empty() {
+        return Err(WechatError::validation("content", "must not be empty"));
+    }
+    let len = content.chars().count();
+    if len > MAX_MSG_SEC_CHECK_LEN {
+        return Err(WechatError::validation(
+            "content",
+            format!(
+                "{len} characters exceed the limit of {MAX_MSG_SEC_CHECK_LEN}; check the text in parts"
+            ),
+        ));
+    }
+    Ok(())
+}
+
 // ============================================================================
 // SecurityApi
 // ============================================================================
@@ -184,19 +461,29 @@ impl SecurityApi {
     ///
     /// # Arguments
     /// * `openid` - User's OpenID
-    /// * `scene` - Scene value (1=profile, 2=comment, 3=forum, 4=social log)
+    /// * `scene` - Content scene
     /// * `content` - Text content to check
+    ///
+    /// Sends the 2.0 request by default. Pinned to [`ApiVersion::V1`], only
+    /// `content` is sent and the response has no `result` or `detail`.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] before any request when `content`
+    /// is empty or longer than [`MAX_MSG_SEC_CHECK_LEN`] characters, or when
+    /// the 2.0 request has an empty `openid`.
     pub async fn msg_sec_check(
         &self,
         openid: &str,
-        scene: u8,
+        scene: Scene,
         content: &str,
     ) -> Result<MsgSecCheckResponse, WechatError> {
+        let v2 = self.context.client.api_version("security.msgSecCheck") == ApiVersion::V2;
+        validate_msg_sec_check(v2.then_some(openid), content)?;
         let body = MsgSecCheckRequest {
-            version: 2,
-            openid: openid.to_string(),
-            scene,
-            content: content.to_string(),
+            version: v2.then_some(2),
+            openid: v2.then_some(openid),
+            scene: v2.then_some(scene),
+            content,
         };
         let response: MsgSecCheckResponse = self
             .context
@@ -214,20 +501,23 @@ impl SecurityApi {
     /// * `media_url` - URL of the media to check
     /// * `media_type` - Media type (1=audio, 2=image)
     /// * `openid` - User's OpenID
-    /// * `scene` - Scene value
+    /// * `scene` - Content scene
+    ///
+    /// Pinned to [`ApiVersion::V1`], `openid` and `scene` are not sent.
     pub async fn media_check_async(
         &self,
         media_url: &str,
         media_type: u8,
         openid: &str,
-        scene: u8,
+        scene: Scene,
     ) -> Result<MediaCheckAsyncResponse, WechatError> {
+        let v2 = self.context.client.api_version("security.mediaCheckAsync") == ApiVersion::V2;
         let body = MediaCheckAsyncRequest {
-            media_url: media_url.to_string(),
+            media_url,
             media_type,
-            version: 2,
-            openid: openid.to_string(),
-            scene,
+            version: v2.then_some(2),
+            openid: v2.then_some(openid),
+            scene: v2.then_some(scene),
         };
         let response: MediaCheckAsyncResponse = self
             .context
@@ -243,18 +533,18 @@ impl SecurityApi {
     ///
     /// # Arguments
     /// * `openid` - User's OpenID
-    /// * `scene` - Scene value (0=registration, 1=marketing)
+    /// * `scene` - Risk scene
     /// * `options` - Additional optional parameters
     pub async fn get_user_risk_rank(
         &self,
         openid: &str,
-        scene: u8,
+        scene: RiskScene,
         options: Option<UserRiskRankOptions>,
     ) -> Result<UserRiskRankResponse, WechatError> {
         let opts = options.unwrap_or_default();
         let body = UserRiskRankRequest {
-            appid: self.context.client.appid().to_string(),
-            openid: openid.to_string(),
+            appid: self.context.client.appid(),
+            openid,
             scene,
             client_ip: opts.client_ip,
             mobile_no: opts.mobile_no,
@@ -269,6 +559,37 @@ impl SecurityApi {
         WechatError::check_api(response.errcode, &response.errmsg)?;
         Ok(response)
     }
+
+    /// Query the user's risk rank and map it to a [`RiskPolicy`]
+    ///
+    /// The decision is logged at `info` level with the openid, scene, rank and
+    /// union ID; mobile number and email are never logged.
+    pub async fn assess_risk(
+        &self,
+        assessment: &RiskAssessment,
+    ) -> Result<RiskDecision, WechatError> {
+        let response = self
+            .get_user_risk_rank(
+                &assessment.openid,
+                assessment.scene,
+                Some(assessment.options.clone()),
+            )
+            .await?;
+        let decision = RiskDecision {
+            policy: assessment.policy_for(response.risk_rank),
+            risk_rank: response.risk_rank,
+            unoin_id: response.unoin_id,
+        };
+        log::info!(
+            "risk assessment: openid={} scene={:?} risk_rank={} unoin_id={} policy={:?}",
+            assessment.openid,
+            assessment.scene,
+            decision.risk_rank,
+            decision.unoin_id,
+            decision.policy
+        );
+        Ok(decision)
+    }
 }
 
 impl WechatApi for SecurityApi {
@@ -285,6 +606,15 @@ impl WechatApi for SecurityApi {
 // Tests
 // ============================================================================
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<MsgSecCheckRequest<'static>, MsgSecCheckResponse>("security.msgSecCheck");
+    schemas.add::<MediaCheckAsyncRequest<'static>, MediaCheckAsyncResponse>(
+        "security.mediaCheckAsync",
+    );
+    schemas.add::<UserRiskRankRequest<'static>, UserRiskRankResponse>("security.getUserRiskRank");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -307,6 +637,38 @@ mod tests {
         Arc::new(WechatContext::new(client, token_manager))
     }
 
+    #[test]
+    fn test_scene_u8_conversions() {
+        for scene in [
+            Scene::Profile,
+            Scene::Comment,
+            Scene::Forum,
+            Scene::SocialLog,
+        ] {
+            assert_eq!(Scene::try_from(u8::from(scene)).unwrap(), scene);
+        }
+        assert_eq!(u8::from(Scene::SocialLog), 4);
+        assert!(Scene::try_from(0).is_err());
+        assert!(Scene::try_from(5).is_err());
+
+        assert_eq!(u8::from(RiskScene::Registration), 0);
+        assert_eq!(RiskScene::try_from(1).unwrap(), RiskScene::Marketing);
+        assert!(RiskScene::try_from(2).is_err());
+    }
+
+    #[test]
+    fn test_scene_serializes_as_number() {
+        let body = MsgSecCheckRequest {
+            version: Some(2),
+            openid: Some("openid123"),
+            scene: Some(Scene::Forum),
+            content: "hello",
+        };
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(json["scene"], 3);
+        assert_eq!(serde_json::to_value(RiskScene::Marketing).unwrap(), 1);
+    }
+
     async fn setup_token_mock(mock_server: &wiremock::MockServer) {
         use wiremock::matchers::{method, path};
         use wiremock::{Mock, ResponseTemplate};
@@ -355,12 +717,35 @@ mod tests {
         assert_eq!(response.errcode, 0);
     }
 
+    #[test]
+    fn test_msg_sec_check_response_trace_id_and_strategy_helpers() {
+        let json = r#"{
+            "errcode": 0,
+            "errmsg": "ok",
+            "trace_id": "60ae120f-371d5872-7941a05b",
+            "result": {"suggest": "risky", "label": 20001},
+            "detail": [
+                {"strategy": "content_model", "errcode": 0, "suggest": "risky", "label": 20001, "prob": 90},
+                {"strategy": "keyword", "errcode": 0, "suggest": "pass", "label": 100, "keyword": "x", "prob": 40},
+                {"strategy": "keyword", "errcode": 0, "suggest": "review", "label": 20002, "keyword": "y", "prob": 90}
+            ]
+        }"#;
+        let response: MsgSecCheckResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.trace_id, "60ae120f-371d5872-7941a05b");
+        assert_eq!(response.max_prob(), Some(90));
+        assert_eq!(response.top_detail().unwrap().strategy, "content_model");
+        assert_eq!(response.strategy("keyword").unwrap().keyword, "x");
+        assert_eq!(response.flagged().count(), 2);
+    }
+
     #[test]
     fn test_msg_sec_check_response_defaults() {
         let json = r#"{"errcode": 0, "errmsg": "ok"}"#;
         let response: MsgSecCheckResponse = serde_json::from_str(json).unwrap();
         assert!(response.detail.is_empty());
         assert!(response.result.suggest.is_empty());
+        assert!(response.trace_id.is_empty());
+        assert_eq!(response.max_prob(), None);
     }
 
     #[test]
@@ -430,12 +815,63 @@ mod tests {
 
         let context = create_test_context(&mock_server.uri());
         let api = SecurityApi::new(context);
-        let result = api.msg_sec_check("openid123", 1, "hello world").await;
+        let result = api
+            .msg_sec_check("openid123", Scene::Profile, "hello world")
+            .await;
         assert!(result.is_ok());
         let response = result.unwrap();
         assert_eq!(response.result.suggest, "pass");
     }
 
+    #[tokio::test]
+    async fn test_msg_sec_check_pinned_to_v1_sends_content_only() {
+        use crate::client::CallOptions;
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        setup_token_mock(&mock_server).await;
+
+        Mock::given(method("POST"))
+            .and(path("/wxa/msg_sec_check"))
+            .and(body_json(serde_json::json!({ "content": "hello world" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let client = Arc::new(
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(mock_server.uri())
+                .api_version("security.msgSecCheck", ApiVersion::V1)
+                .build()
+                .unwrap(),
+        );
+        let token_manager = Arc::new(TokenManager::new((*client).clone()));
+        let api = SecurityApi::new(Arc::new(WechatContext::new(client, token_manager)));
+
+        api.msg_sec_check("openid123", Scene::Profile, "hello world")
+            .await
+            .unwrap();
+
+        // A call-scoped pin wins over the client default.
+        let pinned_v1 = CallOptions::new().api_version("security.msgSecCheck", ApiVersion::V1);
+        let context = create_test_context(&mock_server.uri());
+        pinned_v1
+            .scope(SecurityApi::new(context).msg_sec_check(
+                "openid123",
+                Scene::Profile,
+                "hello world",
+            ))
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn test_msg_sec_check_api_error() {
         use wiremock::matchers::{method, path};
@@ -455,9 +891,11 @@ mod tests {
 
         let context = create_test_context(&mock_server.uri());
         let api = SecurityApi::new(context);
-        let result = api.msg_sec_check("openid123", 1, "bad content").await;
+        let result = api
+            .msg_sec_check("openid123", Scene::Profile, "bad content")
+            .await;
         assert!(result.is_err());
-        if let Err(WechatError::Api { code, message }) = result {
+        if let Err(WechatError::Api { code, message, .. }) = result {
             assert_eq!(code, 87014);
             assert_eq!(message, "risky content");
         } else {
@@ -465,6 +903,31 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_msg_sec_check_validates_before_request() {
+        use wiremock::MockServer;
+
+        let mock_server = MockServer::start().await;
+        let api = SecurityApi::new(create_test_context(&mock_server.uri()));
+        let field = |result: Result<MsgSecCheckResponse, WechatError>| match result {
+            Err(WechatError::Validation { field, message }) => format!("{field}: {message}"),
+            other => panic!("expected validation error, got {other:?}"),
+        };
+
+        let oversize = "字".repeat(MAX_MSG_SEC_CHECK_LEN + 1);
+        assert!(field(
+            api.msg_sec_check("openid123", Scene::Comment, &oversize)
+                .await
+        )
+        .starts_with("content: 2501 characters"));
+        assert!(
+            field(api.msg_sec_check("openid123", Scene::Comment, "  ").await)
+                .starts_with("content:")
+        );
+        assert!(field(api.msg_sec_check("", Scene::Comment, "hello").await).starts_with("openid:"));
+        assert!(mock_server.received_requests().await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_media_check_async_success() {
         use wiremock::matchers::{method, path, query_param};
@@ -487,7 +950,12 @@ mod tests {
         let context = create_test_context(&mock_server.uri());
         let api = SecurityApi::new(context);
         let result = api
-            .media_check_async("https://example.com/image.jpg", 2, "openid123", 1)
+            .media_check_async(
+                "https://example.com/image.jpg",
+                2,
+                "openid123",
+                Scene::Profile,
+            )
             .await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap().trace_id, "trace_123");
@@ -515,10 +983,68 @@ mod tests {
 
         let context = create_test_context(&mock_server.uri());
         let api = SecurityApi::new(context);
-        let result = api.get_user_risk_rank("openid123", 0, None).await;
+        let result = api
+            .get_user_risk_rank("openid123", RiskScene::Registration, None)
+            .await;
         assert!(result.is_ok());
         let response = result.unwrap();
         assert_eq!(response.risk_rank, 1);
         assert_eq!(response.unoin_id, 99);
     }
+
+    #[test]
+    fn test_risk_assessment_policy_thresholds() {
+        let assessment = RiskAssessment::new("openid123", RiskScene::Marketing);
+        let policies: Vec<_> = (0..=4).map(|rank| assessment.policy_for(rank)).collect();
+        assert_eq!(
+            policies,
+            [
+                RiskPolicy::Allow,
+                RiskPolicy::Allow,
+                RiskPolicy::Challenge,
+                RiskPolicy::Deny,
+                RiskPolicy::Deny
+            ]
+        );
+
+        let strict = assessment.thresholds(1, 0);
+        assert_eq!(strict.policy_for(0), RiskPolicy::Allow);
+        assert_eq!(strict.policy_for(1), RiskPolicy::Deny);
+    }
+
+    #[tokio::test]
+    async fn test_assess_risk_sends_context_and_maps_rank() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        setup_token_mock(&mock_server).await;
+
+        Mock::given(method("POST"))
+            .and(path("/wxa/getuserriskrank"))
+            .and(body_partial_json(serde_json::json!({
+                "openid": "openid123",
+                "scene": 0,
+                "client_ip": "203.0.113.7",
+                "mobile_no": "13800000000"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "risk_rank": 2,
+                "unoin_id": 7,
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let api = SecurityApi::new(create_test_context(&mock_server.uri()));
+        let assessment = RiskAssessment::new("openid123", RiskScene::Registration)
+            .client_ip("203.0.113.7")
+            .mobile_no("13800000000");
+        let decision = api.assess_risk(&assessment).await.unwrap();
+        assert_eq!(decision.policy, RiskPolicy::Challenge);
+        assert_eq!(decision.risk_rank, 2);
+        assert_eq!(decision.unoin_id, 7);
+    }
 }
@@ -11,6 +11,7 @@ use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct InvokeServiceRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -18,6 +19,7 @@ pub struct InvokeServiceRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ServiceMarketResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -36,6 +38,7 @@ impl ServiceMarketApi {
         Self { context }
     }
 
+    #[doc(alias = "调用服务平台提供的服务")]
     pub async fn invoke_service(
         &self,
         request: &InvokeServiceRequest,
@@ -59,6 +62,11 @@ impl WechatApi for ServiceMarketApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<InvokeServiceRequest, ServiceMarketResponse>("serviceMarket.invokeService");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
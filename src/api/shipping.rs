@@ -0,0 +1,416 @@
+//! Order shipping information
+//!
+//! Mini programs that collect payments through WeChat Pay report how every
+//! paid order was fulfilled; funds settle only after the upload. An order is
+//! named either by its WeChat Pay `transaction_id` or by the merchant id and
+//! `out_trade_no`:
+//!
+//! ```rust,ignore
+//! let request = UploadShippingInfoRequest::new(
+//!     ShippingOrderKey::transaction_id("4200001234202212150000000001"),
+//!     LogisticsType::Express,
+//!     "o_buyer_openid",
+//!     vec![ShippingItem::express("SF1234567890", "SF", "T-shirt x2")
+//!         .receiver_contact("189****1234")],
+//! );
+//! wechat.upload_shipping_info(&request).await?;
+//! ```
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Serializer};
+
+use super::common::ApiResponseBase;
+use super::{WechatApi, WechatContext};
+use crate::error::WechatError;
+
+/// The paid order a shipment belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShippingOrderKey {
+    /// WeChat Pay transaction id (`order_number_type` 2)
+    TransactionId(String),
+    /// Merchant id and merchant order number (`order_number_type` 1)
+    OutTradeNo { mchid: String, out_trade_no: String },
+}
+
+impl ShippingOrderKey {
+    pub fn transaction_id(transaction_id: impl Into<String>) -> Self {
+        ShippingOrderKey::TransactionId(transaction_id.into())
+    }
+
+    pub fn out_trade_no(mchid: impl Into<String>, out_trade_no: impl Into<String>) -> Self {
+        ShippingOrderKey::OutTradeNo {
+            mchid: mchid.into(),
+            out_trade_no: out_trade_no.into(),
+        }
+    }
+}
+
+/// `order_key` object of a shipping upload
+#[derive(Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct OrderKeyBody<'a> {
+    order_number_type: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mchid: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    out_trade_no: Option<&'a str>,
+}
+
+impl Serialize for ShippingOrderKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ShippingOrderKey::TransactionId(transaction_id) => OrderKeyBody {
+                order_number_type: 2,
+                transaction_id: Some(transaction_id),
+                mchid: None,
+                out_trade_no: None,
+            },
+            ShippingOrderKey::OutTradeNo {
+                mchid,
+                out_trade_no,
+            } => OrderKeyBody {
+                order_number_type: 1,
+                transaction_id: None,
+                mchid: Some(mchid),
+                out_trade_no: Some(out_trade_no),
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+/// How the goods reach the buyer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogisticsType {
+    /// Express company with a tracking number
+    Express,
+    /// Same-city delivery
+    LocalDelivery,
+    /// Virtual goods, nothing is shipped
+    Virtual,
+    /// Picked up by the buyer
+    SelfPickup,
+}
+
+impl LogisticsType {
+    pub fn code(self) -> u8 {
+        match self {
+            LogisticsType::Express => 1,
+            LogisticsType::LocalDelivery => 2,
+            LogisticsType::Virtual => 3,
+            LogisticsType::SelfPickup => 4,
+        }
+    }
+}
+
+impl Serialize for LogisticsType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.code())
+    }
+}
+
+/// Masked phone numbers, required by some express companies (e.g. SF)
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ShippingContact {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub consignor_contact: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receiver_contact: Option<String>,
+}
+
+/// One package of a shipment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ShippingItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_no: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    express_company: Option<String>,
+    item_desc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contact: Option<ShippingContact>,
+}
+
+impl ShippingItem {
+    /// A package without a waybill, for [`LogisticsType`]s other than
+    /// [`Express`](LogisticsType::Express)
+    pub fn new(item_desc: impl Into<String>) -> Self {
+        Self {
+            tracking_no: None,
+            express_company: None,
+            item_desc: item_desc.into(),
+            contact: None,
+        }
+    }
+
+    /// A package sent with `express_company` (its delivery id, e.g. `SF`)
+    pub fn express(
+        tracking_no: impl Into<String>,
+        express_company: impl Into<String>,
+        item_desc: impl Into<String>,
+    ) -> Self {
+        Self {
+            tracking_no: Some(tracking_no.into()),
+            express_company: Some(express_company.into()),
+            ..Self::new(item_desc)
+        }
+    }
+
+    /// Sender phone number, masked like `189****1234`
+    pub fn consignor_contact(mut self, phone: impl Into<String>) -> Self {
+        self.contact
+            .get_or_insert_with(Default::default)
+            .consignor_contact = Some(phone.into());
+        self
+    }
+
+    /// Receiver phone number, masked like `189****1234`
+    pub fn receiver_contact(mut self, phone: impl Into<String>) -> Self {
+        self.contact
+            .get_or_insert_with(Default::default)
+            .receiver_contact = Some(phone.into());
+        self
+    }
+
+    pub fn tracking_no(&self) -> Option<&str> {
+        self.tracking_no.as_deref()
+    }
+}
+
+/// Body of `/wxa/sec/order/upload_shipping_info`
+///
+/// The whole order ships at once unless [`split`](Self::split) is called.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadShippingInfoRequest {
+    order_key: ShippingOrderKey,
+    logistics_type: LogisticsType,
+    payer_openid: String,
+    shipping_list: Vec<ShippingItem>,
+    is_all_delivered: Option<bool>,
+    upload_time: SystemTime,
+}
+
+impl UploadShippingInfoRequest {
+    pub fn new(
+        order_key: ShippingOrderKey,
+        logistics_type: LogisticsType,
+        payer_openid: impl Into<String>,
+        shipping_list: Vec<ShippingItem>,
+    ) -> Self {
+        Self {
+            order_key,
+            logistics_type,
+            payer_openid: payer_openid.into(),
+            shipping_list,
+            is_all_delivered: None,
+            upload_time: SystemTime::now(),
+        }
+    }
+
+    /// Ship the order in several packages; `is_all_delivered` marks the
+    /// upload of the last one
+    pub fn split(mut self, is_all_delivered: bool) -> Self {
+        self.is_all_delivered = Some(is_all_delivered);
+        self
+    }
+
+    /// When the goods were handed over (default: when the request was built)
+    pub fn upload_time(mut self, at: SystemTime) -> Self {
+        self.upload_time = at;
+        self
+    }
+
+    pub fn order_key(&self) -> &ShippingOrderKey {
+        &self.order_key
+    }
+
+    pub fn shipping_list(&self) -> &[ShippingItem] {
+        &self.shipping_list
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct ShippingPayer<'a> {
+    openid: &'a str,
+}
+
+/// Body sent for an [`UploadShippingInfoRequest`]
+#[derive(Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct UploadShippingInfoBody<'a> {
+    #[cfg_attr(feature = "schemars", schemars(with = "OrderKeyBody<'static>"))]
+    order_key: &'a ShippingOrderKey,
+    #[cfg_attr(feature = "schemars", schemars(with = "u8"))]
+    logistics_type: LogisticsType,
+    delivery_mode: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_all_delivered: Option<bool>,
+    shipping_list: &'a [ShippingItem],
+    upload_time: String,
+    payer: ShippingPayer<'a>,
+}
+
+impl Serialize for UploadShippingInfoRequest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        UploadShippingInfoBody {
+            order_key: &self.order_key,
+            logistics_type: self.logistics_type,
+            delivery_mode: if self.is_all_delivered.is_some() {
+                2
+            } else {
+                1
+            },
+            is_all_delivered: self.is_all_delivered,
+            shipping_list: &self.shipping_list,
+            upload_time: beijing_rfc3339(self.upload_time),
+            payer: ShippingPayer {
+                openid: &self.payer_openid,
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+/// `time` as RFC 3339 in China Standard Time, e.g. `2022-12-15T13:29:35+08:00`
+fn beijing_rfc3339(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+        + 8 * 60 * 60;
+    let (days, clock) = (seconds / 86_400, seconds % 86_400);
+
+    // Civil date from days since 1970-01-01 (proleptic Gregorian calendar)
+    let shifted = days + 719_468;
+    let era = shifted / 146_097;
+    let day_of_era = shifted % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}+08:00",
+        clock / 3_600,
+        clock % 3_600 / 60,
+        clock % 60
+    )
+}
+
+pub struct ShippingApi {
+    context: Arc<WechatContext>,
+}
+
+impl ShippingApi {
+    pub fn new(context: Arc<WechatContext>) -> Self {
+        Self { context }
+    }
+
+    /// Report how a paid order was fulfilled
+    ///
+    /// Uploading again for the same order replaces the earlier information.
+    #[doc(alias = "发货信息录入")]
+    pub async fn upload_shipping_info(
+        &self,
+        request: &UploadShippingInfoRequest,
+    ) -> Result<(), WechatError> {
+        let response: ApiResponseBase = self
+            .context
+            .authed_post("/wxa/sec/order/upload_shipping_info", request)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)
+    }
+}
+
+impl WechatApi for ShippingApi {
+    fn context(&self) -> &WechatContext {
+        &self.context
+    }
+
+    fn api_name(&self) -> &'static str {
+        "shipping"
+    }
+}
+
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<UploadShippingInfoBody<'static>, ApiResponseBase>("shipping.uploadShippingInfo");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn upload_shipping_info_request_serializes() {
+        let request =
+            UploadShippingInfoRequest::new(
+                ShippingOrderKey::out_trade_no("1230000109", "order-1"),
+                LogisticsType::Express,
+                "oBuyer",
+                vec![ShippingItem::express("SF123", "SF", "T-shirt x2")
+                    .receiver_contact("189****1234")],
+            )
+            .split(false)
+            .upload_time(UNIX_EPOCH + Duration::from_secs(1_671_082_175));
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "order_key": {"order_number_type": 1, "mchid": "1230000109", "out_trade_no": "order-1"},
+                "logistics_type": 1,
+                "delivery_mode": 2,
+                "is_all_delivered": false,
+                "shipping_list": [{
+                    "tracking_no": "SF123",
+                    "express_company": "SF",
+                    "item_desc": "T-shirt x2",
+                    "contact": {"receiver_contact": "189****1234"}
+                }],
+                "upload_time": "2022-12-15T13:29:35+08:00",
+                "payer": {"openid": "oBuyer"}
+            })
+        );
+
+        let virtual_goods = UploadShippingInfoRequest::new(
+            ShippingOrderKey::transaction_id("4200001"),
+            LogisticsType::Virtual,
+            "oBuyer",
+            vec![ShippingItem::new("membership")],
+        );
+        let body = serde_json::to_value(&virtual_goods).unwrap();
+        assert_eq!(
+            body["order_key"],
+            serde_json::json!({"order_number_type": 2, "transaction_id": "4200001"})
+        );
+        assert_eq!(body["delivery_mode"], 1);
+        assert!(body.get("is_all_delivered").is_none());
+        assert_eq!(
+            body["shipping_list"],
+            serde_json::json!([{"item_desc": "membership"}])
+        );
+    }
+
+    #[test]
+    fn beijing_rfc3339_handles_leap_days_and_year_ends() {
+        let at = |seconds| beijing_rfc3339(UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(at(0), "1970-01-01T08:00:00+08:00");
+        assert_eq!(at(951_782_400), "2000-02-29T08:00:00+08:00");
+        assert_eq!(at(1_704_038_399), "2023-12-31T23:59:59+08:00");
+        assert_eq!(at(1_735_660_800), "2025-01-01T00:00:00+08:00");
+    }
+}
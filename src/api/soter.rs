@@ -11,6 +11,7 @@ use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct VerifySignatureRequest {
     #[serde(flatten)]
     pub payload: HashMap<String, Value>,
@@ -18,6 +19,7 @@ pub struct VerifySignatureRequest {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct VerifySignatureResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -36,6 +38,7 @@ impl SoterApi {
         Self { context }
     }
 
+    #[doc(alias = "生物认证秘钥签名验证")]
     pub async fn verify_signature(
         &self,
         request: &VerifySignatureRequest,
@@ -59,6 +62,11 @@ impl WechatApi for SoterApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<VerifySignatureRequest, VerifySignatureResponse>("soter.verifySignature");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -32,20 +32,28 @@
 //! subscribe_api.send(options).await?;
 //! ```
 
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
 
+use super::subscribe_quota::SubscribeQuota;
 use super::{WechatApi, WechatContext};
+use crate::client::{current_call_options, with_call_options};
 use crate::error::WechatError;
 use crate::types::OpenId;
+use crate::utils::{cancelled, lock};
+use crate::CancellationToken;
 
 /// Subscribe message data (key-value pairs)
 pub type SubscribeMessageData = HashMap<String, SubscribeMessageValue>;
 
 /// Value for subscribe message field
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SubscribeMessageValue {
     pub value: String,
 }
@@ -61,6 +69,7 @@ impl SubscribeMessageValue {
 
 /// Request for sending subscribe message
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct SubscribeMessageRequest {
     #[serde(rename = "touser")]
     touser: OpenId,
@@ -76,6 +85,7 @@ struct SubscribeMessageRequest {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum MiniProgramState {
     Developer,
@@ -84,6 +94,7 @@ pub enum MiniProgramState {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Lang {
     #[serde(rename = "zh_CN")]
     ZhCN,
@@ -104,7 +115,8 @@ pub struct SubscribeMessageOptions {
     pub template_id: String,
     /// Template data
     pub data: SubscribeMessageData,
-    /// Page to navigate to (optional)
+    /// Page to navigate to (optional); may carry a query, see
+    /// [`MiniProgramPath`](crate::types::MiniProgramPath)
     pub page: Option<String>,
     /// Mini program state: "developer", "trial", or "formal" (optional)
     pub miniprogram_state: Option<MiniProgramState>,
@@ -112,8 +124,45 @@ pub struct SubscribeMessageOptions {
     pub lang: Option<Lang>,
 }
 
+impl SubscribeMessageOptions {
+    /// Check for fields WeChat would reject as missing
+    ///
+    /// Every send of [`SubscribeApi`] runs this before the request.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] when `touser`, `template_id`,
+    /// `data` or one of its values is empty.
+    pub fn validate(&self) -> Result<(), WechatError> {
+        if self.touser.as_str().is_empty() {
+            return Err(WechatError::validation("touser", "must not be empty"));
+        }
+        if self.template_id.trim().is_empty() {
+            return Err(WechatError::validation("template_id", "must not be empty"));
+        }
+        if self.data.is_empty() {
+            return Err(WechatError::validation(
+                "data",
+                "must fill the template's keywords, e.g. thing1",
+            ));
+        }
+        if let Some(key) = self
+            .data
+            .iter()
+            .find(|(_, value)| value.value.trim().is_empty())
+            .map(|(key, _)| key)
+        {
+            return Err(WechatError::validation(
+                &format!("data.{key}"),
+                "must not be empty",
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Response from subscribe message API
 #[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct SubscribeMessageResponse {
     #[serde(default)]
     errcode: i32,
@@ -121,12 +170,166 @@ struct SubscribeMessageResponse {
     errmsg: String,
 }
 
+/// One recipient of [`SubscribeApi::send_batch`]
+#[derive(Debug, Clone)]
+pub struct BatchRecipient {
+    /// Recipient's OpenID
+    pub touser: OpenId,
+    /// Template data for this recipient
+    pub data: SubscribeMessageData,
+}
+
+impl BatchRecipient {
+    pub fn new(touser: OpenId, data: SubscribeMessageData) -> Self {
+        Self { touser, data }
+    }
+}
+
+/// Longest pause [`SubscribeApi::send_batch`] takes after a 45009
+pub const MAX_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Pause before retry number `retry` (from 1): `backoff` doubled per
+/// earlier retry, capped at [`MAX_RATE_LIMIT_BACKOFF`]
+fn rate_limit_delay(backoff: Duration, retry: u32) -> Duration {
+    let factor = 2u32
+        .checked_pow(retry.saturating_sub(1))
+        .unwrap_or(u32::MAX);
+    backoff.saturating_mul(factor).min(MAX_RATE_LIMIT_BACKOFF)
+}
+
+/// Template and pacing settings for [`SubscribeApi::send_batch`]
+#[derive(Debug, Clone)]
+pub struct SubscribeBatch {
+    /// Template ID sent to every recipient
+    pub template_id: String,
+    /// Page to navigate to (optional)
+    pub page: Option<String>,
+    /// Mini program state (optional)
+    pub miniprogram_state: Option<MiniProgramState>,
+    /// Language (optional)
+    pub lang: Option<Lang>,
+    /// Maximum number of messages in flight (default 8)
+    pub concurrency: usize,
+    /// Retries per recipient after a 45009 rate-limit error (default 3)
+    pub rate_limit_retries: u32,
+    /// Pause after the first 45009, doubled on each further retry up to
+    /// [`MAX_RATE_LIMIT_BACKOFF`] (default 1s)
+    pub rate_limit_backoff: Duration,
+}
+
+impl SubscribeBatch {
+    pub fn new(template_id: impl Into<String>) -> Self {
+        Self {
+            template_id: template_id.into(),
+            page: None,
+            miniprogram_state: None,
+            lang: None,
+            concurrency: 8,
+            rate_limit_retries: 3,
+            rate_limit_backoff: Duration::from_secs(1),
+        }
+    }
+
+    pub fn page(mut self, page: impl Into<String>) -> Self {
+        self.page = Some(page.into());
+        self
+    }
+
+    pub fn miniprogram_state(mut self, state: MiniProgramState) -> Self {
+        self.miniprogram_state = Some(state);
+        self
+    }
+
+    pub fn lang(mut self, lang: Lang) -> Self {
+        self.lang = Some(lang);
+        self
+    }
+
+    /// Set the number of messages in flight (at least 1)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn rate_limit_retries(mut self, retries: u32) -> Self {
+        self.rate_limit_retries = retries;
+        self
+    }
+
+    pub fn rate_limit_backoff(mut self, backoff: Duration) -> Self {
+        self.rate_limit_backoff = backoff;
+        self
+    }
+
+    fn options_for(&self, recipient: BatchRecipient) -> SubscribeMessageOptions {
+        SubscribeMessageOptions {
+            touser: recipient.touser,
+            template_id: self.template_id.clone(),
+            data: recipient.data,
+            page: self.page.clone(),
+            miniprogram_state: self.miniprogram_state.clone(),
+            lang: self.lang.clone(),
+        }
+    }
+}
+
+/// Outcome for one recipient in a [`SubscribeBatchReport`]
+#[derive(Debug, Clone)]
+pub struct SubscribeBatchEntry {
+    pub recipient: BatchRecipient,
+    /// Requests made, including rate-limit retries
+    pub attempts: u32,
+    pub result: Result<(), WechatError>,
+}
+
+/// Per-recipient results of [`SubscribeApi::send_batch`], in input order
+#[derive(Debug, Clone, Default)]
+pub struct SubscribeBatchReport {
+    pub entries: Vec<SubscribeBatchEntry>,
+    /// Whether the batch stopped early because of cancellation
+    pub cancelled: bool,
+}
+
+impl SubscribeBatchReport {
+    /// Number of recipients the message was sent to
+    pub fn sent(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.result.is_ok())
+            .count()
+    }
+
+    /// Recipients that failed, with their errors
+    pub fn failures(&self) -> impl Iterator<Item = (&OpenId, &WechatError)> {
+        self.entries.iter().filter_map(|entry| match &entry.result {
+            Ok(()) => None,
+            Err(error) => Some((&entry.recipient.touser, error)),
+        })
+    }
+
+    /// Failed recipients whose error is transient, ready to pass to another
+    /// [`send_batch`](SubscribeApi::send_batch)
+    pub fn retryable(&self) -> Vec<BatchRecipient> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(&entry.result, Err(error) if error.is_transient()))
+            .map(|entry| entry.recipient.clone())
+            .collect()
+    }
+
+    /// Returns true when every recipient was sent to.
+    pub fn is_complete(&self) -> bool {
+        !self.cancelled && self.entries.iter().all(|entry| entry.result.is_ok())
+    }
+}
+
 /// Template info
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TemplateInfo {
     /// Private template ID
-    #[serde(rename = "priTmplId")]
+    #[serde(rename = "priTmplId", alias = "pri_tmpl_id", alias = "priTmplID")]
     pub private_template_id: String,
     /// Template title
     pub title: String,
@@ -137,12 +340,59 @@ pub struct TemplateInfo {
     pub example: Option<String>,
     /// Template type
     #[serde(rename = "type")]
-    pub template_type: i32,
+    #[cfg_attr(feature = "schemars", schemars(with = "i32"))]
+    pub template_type: TemplateType,
+}
+
+/// Subscription type of a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TemplateType {
+    /// One-time subscription (一次性订阅), type 2: each user authorization
+    /// allows a single message
+    OneTime,
+    /// Long-term subscription (长期订阅), type 3: only available to accounts
+    /// in approved public-service categories
+    LongTerm,
+    /// Type value not known to this SDK
+    Other(i32),
+}
+
+impl From<i32> for TemplateType {
+    fn from(value: i32) -> Self {
+        match value {
+            2 => TemplateType::OneTime,
+            3 => TemplateType::LongTerm,
+            other => TemplateType::Other(other),
+        }
+    }
+}
+
+impl From<TemplateType> for i32 {
+    fn from(value: TemplateType) -> Self {
+        match value {
+            TemplateType::OneTime => 2,
+            TemplateType::LongTerm => 3,
+            TemplateType::Other(other) => other,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TemplateType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        i32::deserialize(deserializer).map(TemplateType::from)
+    }
+}
+
+impl Serialize for TemplateType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32((*self).into())
+    }
 }
 
 /// Response from get template list
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TemplateListResponse {
     pub data: Vec<TemplateInfo>,
     #[serde(default)]
@@ -153,19 +403,21 @@ pub struct TemplateListResponse {
 
 /// Request for add template
 #[derive(Debug, Clone, Serialize)]
-struct AddTemplateRequest {
-    tid: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct AddTemplateRequest<'a> {
+    tid: &'a str,
     #[serde(skip_serializing_if = "Option::is_none")]
     kid_list: Option<Vec<i32>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    scene_desc: Option<String>,
+    scene_desc: Option<&'a str>,
 }
 
 /// Response from add template
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AddTemplateResponse {
-    #[serde(rename = "priTmplId")]
+    #[serde(rename = "priTmplId", alias = "pri_tmpl_id", alias = "priTmplID")]
     pub private_template_id: String,
     #[serde(default)]
     errcode: i32,
@@ -175,7 +427,8 @@ pub struct AddTemplateResponse {
 
 /// Category info
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CategoryInfo {
     /// Category ID
     pub id: i32,
@@ -185,7 +438,8 @@ pub struct CategoryInfo {
 
 /// Response from get category
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CategoryListResponse {
     pub data: Vec<CategoryInfo>,
     #[serde(default)]
@@ -196,6 +450,7 @@ pub struct CategoryListResponse {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PubTemplateKeywordInfo {
     #[serde(default)]
     pub kid: i32,
@@ -207,6 +462,7 @@ pub struct PubTemplateKeywordInfo {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PubTemplateKeywordResponse {
     #[serde(default)]
     pub data: Vec<PubTemplateKeywordInfo>,
@@ -218,6 +474,7 @@ pub struct PubTemplateKeywordResponse {
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PubTemplateTitleInfo {
     #[serde(default)]
     pub tid: String,
@@ -225,12 +482,13 @@ pub struct PubTemplateTitleInfo {
     pub title: String,
     #[serde(default)]
     pub r#type: i32,
-    #[serde(default)]
+    #[serde(default, alias = "categoryId")]
     pub category_id: i32,
 }
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PubTemplateTitleListResponse {
     #[serde(default)]
     pub data: Vec<PubTemplateTitleInfo>,
@@ -240,6 +498,7 @@ pub struct PubTemplateTitleListResponse {
     pub(crate) errmsg: String,
 }
 
+#[deprecated(note = "use SetUserNotifyRequest with SubscribeApi::update_user_notify")]
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
 pub struct UserNotifyRequest {
@@ -247,6 +506,7 @@ pub struct UserNotifyRequest {
     pub payload: HashMap<String, serde_json::Value>,
 }
 
+#[deprecated(note = "use SetUserNotifyExtRequest with SubscribeApi::update_user_notify_ext")]
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
 pub struct UserNotifyExtRequest {
@@ -254,6 +514,7 @@ pub struct UserNotifyExtRequest {
     pub payload: HashMap<String, serde_json::Value>,
 }
 
+#[deprecated(note = "use UserNotifyQuery with SubscribeApi::query_user_notify")]
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
 pub struct GetUserNotifyRequest {
@@ -261,8 +522,137 @@ pub struct GetUserNotifyRequest {
     pub payload: HashMap<String, serde_json::Value>,
 }
 
+/// Body of `setUserNotify`: updates a template card a user subscribed to
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SetUserNotifyRequest {
+    pub openid: String,
+    /// Card type, from the template configured in the MP console
+    pub notify_type: i32,
+    /// Code the mini program received when the user subscribed to the card
+    pub notify_code: String,
+    /// Card content as a JSON string, with the fields of `notify_type`
+    pub content_json: String,
+    /// JSON string WeChat checks the update against, e.g. the previous state
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub check_json: Option<String>,
+}
+
+impl SetUserNotifyRequest {
+    /// # Errors
+    /// Returns [`WechatError::Json`] when `content` cannot be serialized.
+    pub fn new<T: Serialize>(
+        openid: impl Into<String>,
+        notify_type: i32,
+        notify_code: impl Into<String>,
+        content: &T,
+    ) -> Result<Self, WechatError> {
+        Ok(Self {
+            openid: openid.into(),
+            notify_type,
+            notify_code: notify_code.into(),
+            content_json: serde_json::to_string(content)?,
+            check_json: None,
+        })
+    }
+
+    /// # Errors
+    /// Returns [`WechatError::Json`] when `check` cannot be serialized.
+    pub fn check<T: Serialize>(mut self, check: &T) -> Result<Self, WechatError> {
+        self.check_json = Some(serde_json::to_string(check)?);
+        Ok(self)
+    }
+}
+
+/// Body of `setUserNotifyExt`: updates the extended fields of a template card
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SetUserNotifyExtRequest {
+    pub openid: String,
+    pub notify_type: i32,
+    pub notify_code: String,
+    /// Extended fields as a JSON string
+    pub ext_json: String,
+}
+
+impl SetUserNotifyExtRequest {
+    /// # Errors
+    /// Returns [`WechatError::Json`] when `ext` cannot be serialized.
+    pub fn new<T: Serialize>(
+        openid: impl Into<String>,
+        notify_type: i32,
+        notify_code: impl Into<String>,
+        ext: &T,
+    ) -> Result<Self, WechatError> {
+        Ok(Self {
+            openid: openid.into(),
+            notify_type,
+            notify_code: notify_code.into(),
+            ext_json: serde_json::to_string(ext)?,
+        })
+    }
+}
+
+/// Body of `getUserNotify`: identifies a template card
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct UserNotifyQuery {
+    pub openid: String,
+    pub notify_type: i32,
+    pub notify_code: String,
+}
+
+impl UserNotifyQuery {
+    pub fn new(
+        openid: impl Into<String>,
+        notify_type: i32,
+        notify_code: impl Into<String>,
+    ) -> Self {
+        Self {
+            openid: openid.into(),
+            notify_type,
+            notify_code: notify_code.into(),
+        }
+    }
+}
+
+/// Current state of a template card
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct UserNotifyInfo {
+    #[serde(default)]
+    pub notify_type: i32,
+    /// Card content as last set, a JSON string
+    #[serde(default)]
+    pub content_json: String,
+    /// State of the `notify_code`
+    #[serde(default)]
+    pub code_state: i32,
+    /// When the `notify_code` expires, Unix seconds
+    #[serde(default)]
+    pub code_expire_time: i64,
+}
+
+/// Response of `getUserNotify`
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct UserNotifyInfoResponse {
+    #[serde(default)]
+    pub notify_info: UserNotifyInfo,
+    #[serde(default)]
+    pub(crate) errcode: i32,
+    #[serde(default)]
+    pub(crate) errmsg: String,
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UserNotifyResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -272,17 +662,482 @@ pub struct UserNotifyResponse {
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+/// Template definition an application expects to exist in the MP console.
+///
+/// Used with [`SubscribeApi::verify_templates`] to detect console edits that
+/// would break the payload keys an application sends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedTemplate {
+    /// Private template ID
+    pub private_template_id: String,
+    /// Expected template title (optional)
+    pub title: Option<String>,
+    /// Expected template content, compared verbatim (optional)
+    pub content: Option<String>,
+    /// Expected keyword names such as `thing1` or `time2`
+    pub keywords: Vec<String>,
+}
+
+impl ExpectedTemplate {
+    /// Create an expectation that only requires the template to exist
+    pub fn new(private_template_id: impl Into<String>) -> Self {
+        Self {
+            private_template_id: private_template_id.into(),
+            title: None,
+            content: None,
+            keywords: Vec::new(),
+        }
+    }
+
+    /// Require the template title to match
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Require the template content to match exactly
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    /// Require the template to expose exactly these keyword names
+    pub fn keywords<I, K>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.keywords = keywords.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// A difference between an [`ExpectedTemplate`] and the live template list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateDrift {
+    /// The template no longer exists in the account
+    Missing { private_template_id: String },
+    /// The template title changed
+    TitleChanged {
+        private_template_id: String,
+        expected: String,
+        actual: String,
+    },
+    /// The template content changed
+    ContentChanged {
+        private_template_id: String,
+        expected: String,
+        actual: String,
+    },
+    /// The keyword names in the template content changed
+    KeywordsChanged {
+        private_template_id: String,
+        /// Expected keywords absent from the live template
+        missing: Vec<String>,
+        /// Live keywords that were not expected
+        unexpected: Vec<String>,
+    },
+}
+
+impl TemplateDrift {
+    /// Private template ID the drift applies to
+    pub fn private_template_id(&self) -> &str {
+        match self {
+            Self::Missing {
+                private_template_id,
+            }
+            | Self::TitleChanged {
+                private_template_id,
+                ..
+            }
+            | Self::ContentChanged {
+                private_template_id,
+                ..
+            }
+            | Self::KeywordsChanged {
+                private_template_id,
+                ..
+            } => private_template_id,
+        }
+    }
+}
+
+/// Result of [`SubscribeApi::verify_templates`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateDriftReport {
+    pub drifts: Vec<TemplateDrift>,
+}
+
+impl TemplateDriftReport {
+    /// Returns `true` when every expected template matches the live list
+    pub fn is_clean(&self) -> bool {
+        self.drifts.is_empty()
+    }
+
+    /// Compare expectations against a fetched template list
+    pub fn compare(expected: &[ExpectedTemplate], live: &[TemplateInfo]) -> Self {
+        let mut drifts = Vec::new();
+
+        for expectation in expected {
+            let id = &expectation.private_template_id;
+            let Some(template) = live.iter().find(|t| &t.private_template_id == id) else {
+                drifts.push(TemplateDrift::Missing {
+                    private_template_id: id.clone(),
+                });
+                continue;
+            };
+
+            if let Some(title) = &expectation.title {
+                if title != &template.title {
+                    drifts.push(TemplateDrift::TitleChanged {
+                        private_template_id: id.clone(),
+                        expected: title.clone(),
+                        actual: template.title.clone(),
+                    });
+                }
+            }
+
+            if let Some(content) = &expectation.content {
+                if content != &template.content {
+                    drifts.push(TemplateDrift::ContentChanged {
+                        private_template_id: id.clone(),
+                        expected: content.clone(),
+                        actual: template.content.clone(),
+                    });
+                }
+            }
+
+            if !expectation.keywords.is_empty() {
+                let actual = template.keywords();
+                let missing: Vec<String> = expectation
+                    .keywords
+                    .iter()
+                    .filter(|k| !actual.contains(k))
+                    .cloned()
+                    .collect();
+                let unexpected: Vec<String> = actual
+                    .into_iter()
+                    .filter(|k| !expectation.keywords.contains(k))
+                    .collect();
+                if !missing.is_empty() || !unexpected.is_empty() {
+                    drifts.push(TemplateDrift::KeywordsChanged {
+                        private_template_id: id.clone(),
+                        missing,
+                        unexpected,
+                    });
+                }
+            }
+        }
+
+        Self { drifts }
+    }
+}
+
+/// Template an account should have, by public template and keyword ids
+///
+/// Used with [`SubscribeApi::sync_templates`]. A live template matches when
+/// its keyword names (rule + kid, e.g. `thing1`) equal those selected by
+/// `kid_list`, and its title equals `title` when one is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesiredTemplate {
+    /// Public template ID (`tid`)
+    pub tid: String,
+    /// Selected keyword ids, in message order
+    pub kid_list: Vec<i32>,
+    /// Scene description sent when the template is added
+    pub scene_desc: Option<String>,
+    /// Expected template title (optional)
+    pub title: Option<String>,
+}
+
+impl DesiredTemplate {
+    pub fn new(tid: impl Into<String>, kid_list: impl IntoIterator<Item = i32>) -> Self {
+        Self {
+            tid: tid.into(),
+            kid_list: kid_list.into_iter().collect(),
+            scene_desc: None,
+            title: None,
+        }
+    }
+
+    pub fn scene_desc(mut self, scene_desc: impl Into<String>) -> Self {
+        self.scene_desc = Some(scene_desc.into());
+        self
+    }
+
+    /// Only match live templates with this title
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+}
+
+/// A desired template and the private template serving it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncedTemplate {
+    pub tid: String,
+    pub private_template_id: String,
+}
+
+/// Result of [`SubscribeApi::sync_templates`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateSyncReport {
+    /// Desired templates that already existed
+    pub unchanged: Vec<SyncedTemplate>,
+    /// Desired templates that were added
+    pub added: Vec<SyncedTemplate>,
+    /// Extraneous private templates that were deleted
+    pub deleted: Vec<String>,
+    /// Extraneous private templates left in place (sync without pruning)
+    pub extraneous: Vec<String>,
+}
+
+impl TemplateSyncReport {
+    /// Returns `true` when the account already matched the desired state
+    pub fn is_noop(&self) -> bool {
+        self.added.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// Category name fragments eligible for long-term subscriptions
+///
+/// WeChat opens long-term subscribe messages to offline public services:
+/// government and livelihood, healthcare, transport, finance and education.
+pub const DEFAULT_LONG_TERM_CATEGORIES: &[&str] = &["政务", "医疗", "交通", "金融", "教育"];
+
+/// Local checks applied by [`SubscribeApi::send`] before calling WeChat
+///
+/// Built from the account's template list and categories, typically with
+/// [`SubscribeApi::load_validation`]. Catches unknown template ids and
+/// long-term templates sent from accounts outside the approved categories,
+/// which WeChat otherwise reports as a generic 43101.
+#[derive(Debug, Clone)]
+pub struct SubscribeValidation {
+    templates: HashMap<String, TemplateType>,
+    categories: Vec<String>,
+    long_term_categories: Vec<String>,
+}
+
+impl SubscribeValidation {
+    pub fn new(templates: &[TemplateInfo], categories: &[CategoryInfo]) -> Self {
+        Self {
+            templates: templates
+                .iter()
+                .map(|template| (template.private_template_id.clone(), template.template_type))
+                .collect(),
+            categories: categories
+                .iter()
+                .map(|category| category.name.clone())
+                .collect(),
+            long_term_categories: DEFAULT_LONG_TERM_CATEGORIES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+
+    /// Replace the category name fragments that allow long-term templates.
+    pub fn long_term_categories<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.long_term_categories = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Type of a known template
+    pub fn template_type(&self, template_id: &str) -> Option<TemplateType> {
+        self.templates.get(template_id).copied()
+    }
+
+    /// Whether any account category is eligible for long-term templates
+    pub fn allows_long_term(&self) -> bool {
+        self.categories.iter().any(|category| {
+            self.long_term_categories
+                .iter()
+                .any(|allowed| category.contains(allowed.as_str()))
+        })
+    }
+
+    /// Check `options` against the template's type constraints.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] on `template_id` when the template
+    /// is not on the account, or is long-term while no account category
+    /// allows it.
+    pub fn check(&self, options: &SubscribeMessageOptions) -> Result<TemplateType, WechatError> {
+        self.check_template(&options.template_id)
+    }
+
+    fn check_template(&self, template_id: &str) -> Result<TemplateType, WechatError> {
+        let template_type = self.template_type(template_id).ok_or_else(|| {
+            WechatError::validation(
+                "template_id",
+                format!("{} is not in the account's template list", template_id),
+            )
+        })?;
+
+        if template_type == TemplateType::LongTerm && !self.allows_long_term() {
+            return Err(WechatError::validation(
+                "template_id",
+                format!(
+                    "{} is long-term, but none of the account categories ({}) are approved \
+                     for long-term subscriptions",
+                    template_id,
+                    self.categories.join(", ")
+                ),
+            ));
+        }
+
+        Ok(template_type)
+    }
+}
+
+impl TemplateInfo {
+    /// Keyword names referenced by the content, e.g. `thing1` in `{{thing1.DATA}}`
+    pub fn keywords(&self) -> Vec<String> {
+        let mut keywords = Vec::new();
+        let mut rest = self.content.as_str();
+        while let Some(start) = rest.find("{{") {
+            rest = &rest[start + 2..];
+            let Some(end) = rest.find("}}") else {
+                break;
+            };
+            let placeholder = &rest[..end];
+            let name = placeholder
+                .strip_suffix(".DATA")
+                .unwrap_or(placeholder)
+                .trim();
+            if !name.is_empty() && !keywords.iter().any(|k| k == name) {
+                keywords.push(name.to_string());
+            }
+            rest = &rest[end + 2..];
+        }
+        keywords
+    }
+}
+
+/// Default lifetime of cached template categories and keywords
+pub(crate) const DEFAULT_METADATA_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Counters of the subscribe metadata cache
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetadataCacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that went to WeChat
+    pub misses: u64,
+    /// Calls to `invalidate_metadata`
+    pub invalidations: u64,
+}
+
+/// Cache of template categories and pub-template keywords, shared by every
+/// [`SubscribeApi`] built from the same [`WechatContext`]
+pub(crate) struct MetadataCache {
+    ttl: Duration,
+    categories: Mutex<Option<(Instant, Vec<CategoryInfo>)>>,
+    keywords: Mutex<HashMap<String, (Instant, PubTemplateKeywordResponse)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl MetadataCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            categories: Mutex::new(None),
+            keywords: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    fn categories(&self) -> Option<Vec<CategoryInfo>> {
+        let cached = lock(&self.categories)
+            .as_ref()
+            .filter(|(stored, _)| stored.elapsed() < self.ttl)
+            .map(|(_, categories)| categories.clone());
+        self.record(cached.is_some());
+        cached
+    }
+
+    fn store_categories(&self, categories: &[CategoryInfo]) {
+        *lock(&self.categories) = Some((Instant::now(), categories.to_vec()));
+    }
+
+    fn keywords(&self, tid: &str) -> Option<PubTemplateKeywordResponse> {
+        let cached = lock(&self.keywords)
+            .get(tid)
+            .filter(|(stored, _)| stored.elapsed() < self.ttl)
+            .map(|(_, response)| response.clone());
+        self.record(cached.is_some());
+        cached
+    }
+
+    fn store_keywords(&self, tid: &str, response: &PubTemplateKeywordResponse) {
+        lock(&self.keywords).insert(tid.to_string(), (Instant::now(), response.clone()));
+    }
+
+    fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn invalidate(&self) {
+        *lock(&self.categories) = None;
+        lock(&self.keywords).clear();
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> MetadataCacheStats {
+        MetadataCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+        }
+    }
+}
+
 /// Subscribe Message API
 ///
 /// Provides methods for sending subscribe messages and managing templates.
+///
+/// Template categories and pub-template keywords rarely change, so
+/// [`get_category`](Self::get_category) and
+/// [`get_pub_template_keywords_by_id`](Self::get_pub_template_keywords_by_id)
+/// are cached per client (one hour by default, see
+/// `WechatMpBuilder::subscribe_metadata_ttl`). Call
+/// [`invalidate_metadata`](Self::invalidate_metadata) after editing them in
+/// the console.
 pub struct SubscribeApi {
     context: Arc<WechatContext>,
+    validation: Option<SubscribeValidation>,
 }
 
 impl SubscribeApi {
     /// Create a new SubscribeApi instance
     pub fn new(context: Arc<WechatContext>) -> Self {
-        Self { context }
+        Self {
+            context,
+            validation: None,
+        }
+    }
+
+    /// Check template type constraints locally before every [`send`](Self::send)
+    pub fn with_validation(mut self, validation: SubscribeValidation) -> Self {
+        self.validation = Some(validation);
+        self
+    }
+
+    /// Build a [`SubscribeValidation`] from the live template list and categories
+    pub async fn load_validation(&self) -> Result<SubscribeValidation, WechatError> {
+        let templates = self.get_template_list().await?;
+        let categories = self.get_category().await?;
+        Ok(SubscribeValidation::new(&templates, &categories))
     }
 
     /// Send subscribe message
@@ -313,8 +1168,235 @@ impl SubscribeApi {
     ///
     /// subscribe_api.send(options).await?;
     /// ```
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] before any request when
+    /// [`SubscribeMessageOptions::validate`] fails. With
+    /// [`with_validation`](Self::with_validation), also returns it when the
+    /// template fails its type constraints.
     pub async fn send(&self, options: SubscribeMessageOptions) -> Result<(), WechatError> {
-        let request = SubscribeMessageRequest {
+        if let Some(validation) = &self.validation {
+            validation.check(&options)?;
+        }
+        self.post_message(options).await
+    }
+
+    /// Send the same template to several users with per-user data
+    ///
+    /// `data_fn` builds each user's data, e.g. to personalise names. With
+    /// [`with_validation`](Self::with_validation) the template is checked once
+    /// for the whole batch. Repeated OpenIDs are sent to once. Messages are
+    /// sent one after another, and a failure for one user does not stop the
+    /// others.
+    ///
+    /// # Returns
+    /// One `(openid, result)` pair per distinct user, in input order
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] without sending anything when the
+    /// template fails validation.
+    pub async fn send_to_many<F>(
+        &self,
+        users: &[OpenId],
+        template_id: &str,
+        data_fn: F,
+    ) -> Result<Vec<(OpenId, Result<(), WechatError>)>, WechatError>
+    where
+        F: Fn(&OpenId) -> SubscribeMessageData,
+    {
+        if let Some(validation) = &self.validation {
+            validation.check_template(template_id)?;
+        }
+
+        let mut seen = HashSet::new();
+        let mut outcomes = Vec::with_capacity(users.len());
+        for user in users.iter().filter(|user| seen.insert(*user)) {
+            let options = SubscribeMessageOptions {
+                touser: user.clone(),
+                template_id: template_id.to_string(),
+                data: data_fn(user),
+                page: None,
+                miniprogram_state: None,
+                lang: None,
+            };
+            outcomes.push((user.clone(), self.post_message(options).await));
+        }
+        Ok(outcomes)
+    }
+
+    /// Send one template to many recipients concurrently
+    ///
+    /// At most [`SubscribeBatch::concurrency`] messages are in flight. A
+    /// 45009 rate-limit error pauses every sender for
+    /// [`SubscribeBatch::rate_limit_backoff`] (doubled per retry) before the
+    /// recipient is retried, up to
+    /// [`SubscribeBatch::rate_limit_retries`] times. Repeated OpenIDs are
+    /// sent to once, and a failure for one recipient does not stop the
+    /// others.
+    ///
+    /// When `cancel` fires, no further message is sent, the sends in flight
+    /// are aborted, and the report holds the recipients finished before it,
+    /// with `cancelled` set. Recipients missing from it may or may not have
+    /// received the message if their send was in flight.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] without sending anything when the
+    /// template fails [`with_validation`](Self::with_validation).
+    pub async fn send_batch<I>(
+        &self,
+        batch: &SubscribeBatch,
+        recipients: I,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<SubscribeBatchReport, WechatError>
+    where
+        I: IntoIterator<Item = BatchRecipient>,
+    {
+        if let Some(validation) = &self.validation {
+            validation.check_template(&batch.template_id)?;
+        }
+
+        let mut seen = HashSet::new();
+        let mut recipients = recipients
+            .into_iter()
+            .filter(|recipient| seen.insert(recipient.touser.clone()))
+            .enumerate();
+        let resume_at = Arc::new(tokio::sync::Mutex::new(None::<tokio::time::Instant>));
+        let call_options = current_call_options();
+        let mut tasks = JoinSet::new();
+        let mut entries = Vec::new();
+        let mut stopped = false;
+
+        loop {
+            if cancel.is_some_and(CancellationToken::is_cancelled) {
+                stopped = true;
+                break;
+            }
+            while tasks.len() < batch.concurrency.max(1) {
+                let Some((index, recipient)) = recipients.next() else {
+                    break;
+                };
+                let api = SubscribeApi::new(Arc::clone(&self.context));
+                let batch = batch.clone();
+                let resume_at = Arc::clone(&resume_at);
+                let call = async move {
+                    let mut attempts = 0;
+                    let result = loop {
+                        let pause = *resume_at.lock().await;
+                        if let Some(pause) = pause {
+                            tokio::time::sleep_until(pause).await;
+                        }
+
+                        attempts += 1;
+                        let options = batch.options_for(recipient.clone());
+                        match api.post_message(options).await {
+                            Err(WechatError::Api { code: 45009, .. })
+                                if attempts <= batch.rate_limit_retries =>
+                            {
+                                let backoff = rate_limit_delay(batch.rate_limit_backoff, attempts);
+                                if let Some(until) =
+                                    tokio::time::Instant::now().checked_add(backoff)
+                                {
+                                    let mut pause = resume_at.lock().await;
+                                    *pause =
+                                        Some(pause.map_or(until, |current| current.max(until)));
+                                }
+                            }
+                            result => break result,
+                        }
+                    };
+                    (
+                        index,
+                        SubscribeBatchEntry {
+                            recipient,
+                            attempts,
+                            result,
+                        },
+                    )
+                };
+                tasks.spawn(with_call_options(call_options.clone(), call));
+            }
+
+            let joined = tokio::select! {
+                biased;
+                _ = cancelled(cancel) => {
+                    stopped = true;
+                    break;
+                }
+                joined = tasks.join_next() => joined,
+            };
+            match joined {
+                Some(Ok(entry)) => entries.push(entry),
+                Some(Err(error)) => std::panic::resume_unwind(error.into_panic()),
+                None => break,
+            }
+        }
+        tasks.abort_all();
+
+        entries.sort_by_key(|(index, _)| *index);
+        Ok(SubscribeBatchReport {
+            entries: entries.into_iter().map(|(_, entry)| entry).collect(),
+            cancelled: stopped,
+        })
+    }
+
+    /// Subscribe authorizations left for `openid` on `template_id`
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Config`] when no
+    /// [`SubscribeQuotaStore`](crate::api::subscribe_quota::SubscribeQuotaStore)
+    /// is configured, or the store's error.
+    pub async fn remaining_quota(
+        &self,
+        openid: &str,
+        template_id: &str,
+    ) -> Result<u32, WechatError> {
+        self.quota()?.store.remaining(openid, template_id).await
+    }
+
+    /// Record that `openid` accepted each of `template_ids` in
+    /// `wx.requestSubscribeMessage`
+    ///
+    /// # Errors
+    /// Same as [`remaining_quota`](Self::remaining_quota).
+    pub async fn record_acceptance(
+        &self,
+        openid: &str,
+        template_ids: &[&str],
+    ) -> Result<(), WechatError> {
+        let quota = self.quota()?;
+        for template_id in template_ids {
+            quota.store.grant(openid, template_id, 1).await?;
+        }
+        Ok(())
+    }
+
+    fn quota(&self) -> Result<&SubscribeQuota, WechatError> {
+        self.context
+            .subscribe_quota
+            .as_ref()
+            .ok_or_else(|| WechatError::Config("no subscribe quota store configured".to_string()))
+    }
+
+    /// Send one message, through the quota store when one is configured
+    async fn post_message(&self, options: SubscribeMessageOptions) -> Result<(), WechatError> {
+        options.validate()?;
+        match &self.context.subscribe_quota {
+            Some(quota) => {
+                let openid = options.touser.as_str().to_string();
+                let template_id = options.template_id.clone();
+                quota
+                    .send(&openid, &template_id, self.post_message_unchecked(options))
+                    .await
+            }
+            None => self.post_message_unchecked(options).await,
+        }
+    }
+
+    async fn post_message_unchecked(
+        &self,
+        options: SubscribeMessageOptions,
+    ) -> Result<(), WechatError> {
+        let request = SubscribeMessageRequest {
             touser: options.touser,
             template_id: options.template_id,
             page: options.page,
@@ -358,9 +1440,9 @@ impl SubscribeApi {
         scene_desc: Option<&str>,
     ) -> Result<String, WechatError> {
         let request = AddTemplateRequest {
-            tid: tid.to_string(),
+            tid,
             kid_list,
-            scene_desc: scene_desc.map(|s| s.to_string()),
+            scene_desc,
         };
 
         let response: AddTemplateResponse = self
@@ -399,6 +1481,122 @@ impl SubscribeApi {
         Ok(response.data)
     }
 
+    /// Verify templates against the live template list
+    ///
+    /// Fetches the account's templates and reports missing templates and
+    /// changed titles, content or keywords. Intended for startup checks so
+    /// console edits are caught before payloads stop matching.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use wechat_mp_sdk::api::subscribe::ExpectedTemplate;
+    ///
+    /// let report = subscribe_api
+    ///     .verify_templates(&[ExpectedTemplate::new("pri_tmpl_id").keywords(["thing1", "time2"])])
+    ///     .await?;
+    /// assert!(report.is_clean(), "template drift: {:?}", report.drifts);
+    /// ```
+    pub async fn verify_templates(
+        &self,
+        expected: &[ExpectedTemplate],
+    ) -> Result<TemplateDriftReport, WechatError> {
+        let live = self.get_template_list().await?;
+        Ok(TemplateDriftReport::compare(expected, &live))
+    }
+
+    /// Reconcile the account's templates with a desired-state list
+    ///
+    /// Adds every desired template without a matching live template. With
+    /// `prune`, live templates not matched by any desired entry are deleted;
+    /// otherwise they are only reported. After changing the account's
+    /// categories, call [`invalidate_metadata`](Self::invalidate_metadata)
+    /// first so keyword rules are re-fetched.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Config`] if a `kid` is not a keyword of its
+    /// `tid`. Templates added or deleted before a failing call stay changed.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use wechat_mp_sdk::api::subscribe::DesiredTemplate;
+    ///
+    /// let report = subscribe_api
+    ///     .sync_templates(&[DesiredTemplate::new("1234", [1, 3]).scene_desc("order shipped")], false)
+    ///     .await?;
+    /// for added in &report.added {
+    ///     println!("{} -> {}", added.tid, added.private_template_id);
+    /// }
+    /// ```
+    pub async fn sync_templates(
+        &self,
+        desired: &[DesiredTemplate],
+        prune: bool,
+    ) -> Result<TemplateSyncReport, WechatError> {
+        let mut live = self.get_template_list().await?;
+        let mut report = TemplateSyncReport::default();
+
+        for template in desired {
+            let keywords = self.get_pub_template_keywords_by_id(&template.tid).await?;
+            let mut expected = Vec::with_capacity(template.kid_list.len());
+            for kid in &template.kid_list {
+                let keyword = keywords
+                    .data
+                    .iter()
+                    .find(|keyword| keyword.kid == *kid)
+                    .ok_or_else(|| {
+                        WechatError::Config(format!(
+                            "kid {kid} is not a keyword of template {}",
+                            template.tid
+                        ))
+                    })?;
+                expected.push(format!("{}{}", keyword.rule, keyword.kid));
+            }
+            expected.sort();
+
+            let matched = live.iter().position(|info| {
+                let mut actual = info.keywords();
+                actual.sort();
+                actual == expected
+                    && template
+                        .title
+                        .as_ref()
+                        .map_or(true, |title| title == &info.title)
+            });
+            if let Some(index) = matched {
+                report.unchanged.push(SyncedTemplate {
+                    tid: template.tid.clone(),
+                    private_template_id: live.swap_remove(index).private_template_id,
+                });
+                continue;
+            }
+
+            let private_template_id = self
+                .add_template(
+                    &template.tid,
+                    Some(template.kid_list.clone()),
+                    template.scene_desc.as_deref(),
+                )
+                .await?;
+            report.added.push(SyncedTemplate {
+                tid: template.tid.clone(),
+                private_template_id,
+            });
+        }
+
+        for info in live {
+            if prune {
+                self.delete_template(&info.private_template_id).await?;
+                report.deleted.push(info.private_template_id);
+            } else {
+                report.extraneous.push(info.private_template_id);
+            }
+        }
+
+        Ok(report)
+    }
+
     /// Delete template
     ///
     /// POST /wxaapi/newtmpl/deltemplate?access_token=ACCESS_TOKEN
@@ -413,19 +1611,14 @@ impl SubscribeApi {
     /// ```
     pub async fn delete_template(&self, pri_tmpl_id: &str) -> Result<(), WechatError> {
         #[derive(Serialize)]
-        struct Request {
+        struct Request<'a> {
             #[serde(rename = "pri_tmpl_id")]
-            pri_tmpl_id: String,
+            pri_tmpl_id: &'a str,
         }
 
         let response: SubscribeMessageResponse = self
             .context
-            .authed_post(
-                "/wxaapi/newtmpl/deltemplate",
-                &Request {
-                    pri_tmpl_id: pri_tmpl_id.to_string(),
-                },
-            )
+            .authed_post("/wxaapi/newtmpl/deltemplate", &Request { pri_tmpl_id })
             .await?;
 
         WechatError::check_api(response.errcode, &response.errmsg)?;
@@ -449,6 +1642,11 @@ impl SubscribeApi {
     /// }
     /// ```
     pub async fn get_category(&self) -> Result<Vec<CategoryInfo>, WechatError> {
+        let cache = &self.context.subscribe_metadata;
+        if let Some(categories) = cache.categories() {
+            return Ok(categories);
+        }
+
         let response: CategoryListResponse = self
             .context
             .authed_get("/wxaapi/newtmpl/getcategory", &[])
@@ -456,6 +1654,7 @@ impl SubscribeApi {
 
         WechatError::check_api(response.errcode, &response.errmsg)?;
 
+        cache.store_categories(&response.data);
         Ok(response.data)
     }
 
@@ -463,6 +1662,11 @@ impl SubscribeApi {
         &self,
         tid: &str,
     ) -> Result<PubTemplateKeywordResponse, WechatError> {
+        let cache = &self.context.subscribe_metadata;
+        if let Some(response) = cache.keywords(tid) {
+            return Ok(response);
+        }
+
         let response: PubTemplateKeywordResponse = self
             .context
             .authed_get("/wxaapi/newtmpl/getpubtemplatekeywords", &[("tid", tid)])
@@ -470,9 +1674,20 @@ impl SubscribeApi {
 
         WechatError::check_api(response.errcode, &response.errmsg)?;
 
+        cache.store_keywords(tid, &response);
         Ok(response)
     }
 
+    /// Drop cached categories and pub-template keywords
+    pub fn invalidate_metadata(&self) {
+        self.context.subscribe_metadata.invalidate();
+    }
+
+    /// Hit, miss and invalidation counts of the metadata cache
+    pub fn metadata_cache_stats(&self) -> MetadataCacheStats {
+        self.context.subscribe_metadata.stats()
+    }
+
     pub async fn get_pub_template_title_list(
         &self,
         ids: &[i32],
@@ -503,6 +1718,8 @@ impl SubscribeApi {
         Ok(response)
     }
 
+    #[deprecated(note = "use update_user_notify")]
+    #[allow(deprecated)]
     pub async fn set_user_notify(
         &self,
         request: &UserNotifyRequest,
@@ -511,6 +1728,8 @@ impl SubscribeApi {
             .await
     }
 
+    #[deprecated(note = "use update_user_notify_ext")]
+    #[allow(deprecated)]
     pub async fn set_user_notify_ext(
         &self,
         request: &UserNotifyExtRequest,
@@ -519,6 +1738,8 @@ impl SubscribeApi {
             .await
     }
 
+    #[deprecated(note = "use query_user_notify")]
+    #[allow(deprecated)]
     pub async fn get_user_notify(
         &self,
         request: &GetUserNotifyRequest,
@@ -527,6 +1748,46 @@ impl SubscribeApi {
             .await
     }
 
+    /// Update the content of a template card
+    ///
+    /// POST /cgi-bin/message/update_template_card?access_token=ACCESS_TOKEN
+    #[doc(alias = "setUserNotify")]
+    pub async fn update_user_notify(
+        &self,
+        request: &SetUserNotifyRequest,
+    ) -> Result<UserNotifyResponse, WechatError> {
+        self.post_user_notify("/cgi-bin/message/update_template_card", request)
+            .await
+    }
+
+    /// Update the extended fields of a template card
+    ///
+    /// POST /cgi-bin/message/update_template_card_ext?access_token=ACCESS_TOKEN
+    #[doc(alias = "setUserNotifyExt")]
+    pub async fn update_user_notify_ext(
+        &self,
+        request: &SetUserNotifyExtRequest,
+    ) -> Result<UserNotifyResponse, WechatError> {
+        self.post_user_notify("/cgi-bin/message/update_template_card_ext", request)
+            .await
+    }
+
+    /// Read the current state of a template card
+    ///
+    /// POST /cgi-bin/message/get_template_card?access_token=ACCESS_TOKEN
+    #[doc(alias = "getUserNotify")]
+    pub async fn query_user_notify(
+        &self,
+        query: &UserNotifyQuery,
+    ) -> Result<UserNotifyInfoResponse, WechatError> {
+        let response: UserNotifyInfoResponse = self
+            .context
+            .authed_post("/cgi-bin/message/get_template_card", query)
+            .await?;
+        WechatError::check_api(response.errcode, &response.errmsg)?;
+        Ok(response)
+    }
+
     async fn post_user_notify<B: Serialize>(
         &self,
         endpoint: &str,
@@ -550,6 +1811,20 @@ impl WechatApi for SubscribeApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<SubscribeMessageRequest, SubscribeMessageResponse>("subscribe.sendMessage");
+    schemas.add::<AddTemplateRequest<'static>, AddTemplateResponse>("subscribe.addMessageTemplate");
+    schemas.response::<SubscribeMessageResponse>("subscribe.deleteMessageTemplate");
+    schemas.response::<CategoryListResponse>("subscribe.getCategory");
+    schemas.response::<PubTemplateKeywordResponse>("subscribe.getPubTemplateKeyWordsById");
+    schemas.response::<PubTemplateTitleListResponse>("subscribe.getPubTemplateTitleList");
+    schemas.response::<TemplateListResponse>("subscribe.getMessageTemplateList");
+    schemas.add::<SetUserNotifyRequest, UserNotifyResponse>("subscribe.setUserNotify");
+    schemas.add::<SetUserNotifyExtRequest, UserNotifyResponse>("subscribe.setUserNotifyExt");
+    schemas.add::<UserNotifyQuery, UserNotifyInfoResponse>("subscribe.getUserNotify");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -572,6 +1847,29 @@ mod tests {
         Arc::new(WechatContext::new(client, token_manager))
     }
 
+    #[test]
+    fn test_user_notify_typed_requests() {
+        let request = SetUserNotifyRequest::new("openid", 1, "code", &serde_json::json!({"n": 1}))
+            .unwrap()
+            .check(&serde_json::json!({"n": 0}))
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "openid": "openid",
+                "notify_type": 1,
+                "notify_code": "code",
+                "content_json": "{\"n\":1}",
+                "check_json": "{\"n\":0}"
+            })
+        );
+
+        let json = r#"{"errcode":0,"errmsg":"ok","notify_info":{"notify_type":1,
+            "content_json":"{}","code_state":0,"code_expire_time":1700000000}}"#;
+        let response: UserNotifyInfoResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.notify_info.code_expire_time, 1_700_000_000);
+    }
+
     #[test]
     fn test_subscribe_message_value() {
         let value = SubscribeMessageValue::new("test value");
@@ -611,6 +1909,32 @@ mod tests {
         assert_eq!(options.touser.as_str(), "o6_bmjrPTlm6_2sgVt7hMZOPfL2M");
         assert_eq!(options.template_id, "template_id_456");
         assert_eq!(options.page, Some("pages/index/index".to_string()));
+        options.validate().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_rejects_missing_fields_before_request() {
+        use wiremock::MockServer;
+
+        let mock_server = MockServer::start().await;
+        let subscribe_api = SubscribeApi::new(create_test_context(&mock_server.uri()));
+        let field = |result: Result<(), WechatError>| match result {
+            Err(WechatError::Validation { field, .. }) => field,
+            other => panic!("expected validation error, got {other:?}"),
+        };
+
+        assert_eq!(field(subscribe_api.send(options_for("t1")).await), "data");
+        let mut options = options_for("t1");
+        options
+            .data
+            .insert("thing1".to_string(), SubscribeMessageValue::new(" "));
+        assert_eq!(field(subscribe_api.send(options).await), "data.thing1");
+        let mut options = options_for("");
+        options
+            .data
+            .insert("thing1".to_string(), SubscribeMessageValue::new("Parcel"));
+        assert_eq!(field(subscribe_api.send(options).await), "template_id");
+        assert!(mock_server.received_requests().await.unwrap().is_empty());
     }
 
     #[test]
@@ -635,6 +1959,214 @@ mod tests {
         assert_eq!(response.extra.get("status").unwrap(), "success");
     }
 
+    fn template(id: &str, title: &str, content: &str) -> TemplateInfo {
+        TemplateInfo {
+            private_template_id: id.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            example: None,
+            template_type: TemplateType::OneTime,
+        }
+    }
+
+    #[test]
+    fn test_template_keywords_parse() {
+        let info = template(
+            "t1",
+            "Order shipped",
+            "Order:{{character_string1.DATA}}\nItem:{{thing2.DATA}}\n",
+        );
+        assert_eq!(info.keywords(), vec!["character_string1", "thing2"]);
+    }
+
+    #[test]
+    fn test_template_drift_report() {
+        let live = vec![template(
+            "t1",
+            "Order shipped",
+            "Order:{{character_string1.DATA}}\nItem:{{thing3.DATA}}\n",
+        )];
+        let expected = vec![
+            ExpectedTemplate::new("t1")
+                .title("Order delivered")
+                .keywords(["character_string1", "thing2"]),
+            ExpectedTemplate::new("t2"),
+        ];
+
+        let report = TemplateDriftReport::compare(&expected, &live);
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.drifts,
+            vec![
+                TemplateDrift::TitleChanged {
+                    private_template_id: "t1".to_string(),
+                    expected: "Order delivered".to_string(),
+                    actual: "Order shipped".to_string(),
+                },
+                TemplateDrift::KeywordsChanged {
+                    private_template_id: "t1".to_string(),
+                    missing: vec!["thing2".to_string()],
+                    unexpected: vec!["thing3".to_string()],
+                },
+                TemplateDrift::Missing {
+                    private_template_id: "t2".to_string(),
+                },
+            ]
+        );
+        assert_eq!(report.drifts[2].private_template_id(), "t2");
+    }
+
+    #[tokio::test]
+    async fn test_sync_templates_adds_missing_and_prunes_extraneous() {
+        use wiremock::matchers::{body_json, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/wxaapi/newtmpl/gettemplate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "data": [
+                    {
+                        "priTmplId": "kept",
+                        "title": "Order shipped",
+                        "content": "Order:{{character_string1.DATA}}\nTime:{{time3.DATA}}\n",
+                        "type": 2
+                    },
+                    {
+                        "priTmplId": "stale",
+                        "title": "Old promo",
+                        "content": "Promo:{{thing1.DATA}}\n",
+                        "type": 2
+                    }
+                ]
+            })))
+            .mount(&mock_server)
+            .await;
+        for (tid, keywords) in [
+            (
+                "100",
+                serde_json::json!([
+                    { "kid": 1, "name": "Order", "rule": "character_string" },
+                    { "kid": 3, "name": "Time", "rule": "time" }
+                ]),
+            ),
+            (
+                "200",
+                serde_json::json!([{ "kid": 2, "name": "Amount", "rule": "amount" }]),
+            ),
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/wxaapi/newtmpl/getpubtemplatekeywords"))
+                .and(query_param("tid", tid))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "errcode": 0, "data": keywords })),
+                )
+                .mount(&mock_server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/wxaapi/newtmpl/addtemplate"))
+            .and(body_json(serde_json::json!({
+                "tid": "200",
+                "kid_list": [2],
+                "scene_desc": "refund"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "priTmplId": "added"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxaapi/newtmpl/deltemplate"))
+            .and(body_json(serde_json::json!({ "pri_tmpl_id": "stale" })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "errcode": 0 })),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let subscribe_api = SubscribeApi::new(create_test_context(&mock_server.uri()));
+        let desired = [
+            DesiredTemplate::new("100", [3, 1]).title("Order shipped"),
+            DesiredTemplate::new("200", [2]).scene_desc("refund"),
+        ];
+
+        let report = subscribe_api.sync_templates(&desired, true).await.unwrap();
+        assert_eq!(
+            report.unchanged,
+            [SyncedTemplate {
+                tid: "100".to_string(),
+                private_template_id: "kept".to_string()
+            }]
+        );
+        assert_eq!(report.added[0].private_template_id, "added");
+        assert_eq!(report.deleted, ["stale"]);
+        assert!(report.extraneous.is_empty());
+        assert!(!report.is_noop());
+
+        let unknown_kid = subscribe_api
+            .sync_templates(&[DesiredTemplate::new("200", [9])], false)
+            .await;
+        assert!(matches!(unknown_kid, Err(WechatError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_templates_clean() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/wxaapi/newtmpl/gettemplate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok",
+                "data": [{
+                    "priTmplId": "t1",
+                    "title": "Order shipped",
+                    "content": "Order:{{character_string1.DATA}}\n",
+                    "example": "Order:123\n",
+                    "type": 2
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let context = create_test_context(&mock_server.uri());
+        let subscribe_api = SubscribeApi::new(context);
+
+        let report = subscribe_api
+            .verify_templates(&[ExpectedTemplate::new("t1")
+                .title("Order shipped")
+                .keywords(["character_string1"])])
+            .await
+            .unwrap();
+        assert!(report.is_clean());
+    }
+
     #[tokio::test]
     async fn test_send_success() {
         use wiremock::matchers::{body_json, method, path, query_param};
@@ -691,4 +2223,463 @@ mod tests {
         let result = subscribe_api.send(options).await;
         assert!(result.is_ok());
     }
+
+    fn options_for(template_id: &str) -> SubscribeMessageOptions {
+        SubscribeMessageOptions {
+            touser: OpenId::new("o6_bmjrPTlm6_2sgVt7hMZOPfL2M").unwrap(),
+            template_id: template_id.to_string(),
+            data: SubscribeMessageData::new(),
+            page: None,
+            miniprogram_state: None,
+            lang: None,
+        }
+    }
+
+    #[test]
+    fn test_template_type_from_wire() {
+        let info: TemplateInfo = serde_json::from_value(serde_json::json!({
+            "priTmplId": "t1",
+            "title": "Reminder",
+            "content": "",
+            "type": 3
+        }))
+        .unwrap();
+        assert_eq!(info.template_type, TemplateType::LongTerm);
+        assert_eq!(TemplateType::from(7), TemplateType::Other(7));
+        assert_eq!(i32::from(TemplateType::OneTime), 2);
+    }
+
+    #[test]
+    fn test_validation_rejects_long_term_outside_approved_categories() {
+        let mut long_term = template("long", "Reminder", "");
+        long_term.template_type = TemplateType::LongTerm;
+        let templates = vec![template("once", "Order shipped", ""), long_term];
+        let retail = vec![CategoryInfo {
+            id: 1,
+            name: "商家自营 > 服装/鞋/箱包".to_string(),
+        }];
+
+        let validation = SubscribeValidation::new(&templates, &retail);
+        assert_eq!(
+            validation.check(&options_for("once")).unwrap(),
+            TemplateType::OneTime
+        );
+        let error = validation.check(&options_for("long")).unwrap_err();
+        assert!(matches!(
+            error,
+            WechatError::Validation { ref field, ref message }
+                if field == "template_id" && message.contains("long-term")
+        ));
+        assert!(matches!(
+            validation.check(&options_for("unknown")),
+            Err(WechatError::Validation { .. })
+        ));
+
+        let medical = vec![CategoryInfo {
+            id: 2,
+            name: "医疗 > 公立医疗机构".to_string(),
+        }];
+        let validation = SubscribeValidation::new(&templates, &medical);
+        assert_eq!(
+            validation.check(&options_for("long")).unwrap(),
+            TemplateType::LongTerm
+        );
+        assert!(!validation.long_term_categories(["政务"]).allows_long_term());
+    }
+
+    #[tokio::test]
+    async fn test_send_with_validation_fails_before_request() {
+        use wiremock::MockServer;
+
+        let mock_server = MockServer::start().await;
+        let mut long_term = template("long", "Reminder", "");
+        long_term.template_type = TemplateType::LongTerm;
+
+        let subscribe_api = SubscribeApi::new(create_test_context(&mock_server.uri()))
+            .with_validation(SubscribeValidation::new(&[long_term], &[]));
+
+        let result = subscribe_api.send(options_for("long")).await;
+        assert!(matches!(result, Err(WechatError::Validation { .. })));
+        assert!(mock_server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_send_to_many_personalises_and_reports_per_user() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .and(body_partial_json(serde_json::json!({
+                "touser": "o6_bmjrPTlm6_2sgVt7hMZOPfL2M",
+                "data": {"thing1": {"value": "Hi o6_bmj"}}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .and(body_partial_json(serde_json::json!({
+                "touser": "o6_bmjrPTlm6_2sgVt7hMZOPfL3N"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 43101,
+                "errmsg": "user refuse to accept the msg"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let first = OpenId::new("o6_bmjrPTlm6_2sgVt7hMZOPfL2M").unwrap();
+        let second = OpenId::new("o6_bmjrPTlm6_2sgVt7hMZOPfL3N").unwrap();
+        let api = SubscribeApi::new(create_test_context(&mock_server.uri()));
+        let outcomes = api
+            .send_to_many(
+                &[first.clone(), second.clone(), first.clone()],
+                "template_id",
+                |user| {
+                    let mut data = SubscribeMessageData::new();
+                    data.insert(
+                        "thing1".to_string(),
+                        SubscribeMessageValue::new(format!("Hi {}", &user.as_str()[..6])),
+                    );
+                    data
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[0].0, first);
+        assert!(outcomes[0].1.is_ok());
+        assert_eq!(outcomes[1].0, second);
+        assert!(matches!(
+            outcomes[1].1,
+            Err(WechatError::Api { code: 43101, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_to_many_rejects_unknown_template_before_sending() {
+        let api = SubscribeApi::new(create_test_context("http://127.0.0.1:9"))
+            .with_validation(SubscribeValidation::new(&[], &[]));
+        let users = [OpenId::new("o6_bmjrPTlm6_2sgVt7hMZOPfL2M").unwrap()];
+        let result = api
+            .send_to_many(&users, "missing", |_| SubscribeMessageData::new())
+            .await;
+        assert!(matches!(result, Err(WechatError::Validation { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_send_batch_retries_rate_limit_and_reports_in_order() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .and(body_partial_json(serde_json::json!({
+                "touser": "o6_bmjrPTlm6_2sgVt7hMZOPfL2M"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 45009,
+                "errmsg": "reach max api daily quota limit"
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .and(body_partial_json(serde_json::json!({
+                "touser": "o6_bmjrPTlm6_2sgVt7hMZOPfL3N"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 43101,
+                "errmsg": "user refuse to accept the msg"
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .and(body_partial_json(serde_json::json!({
+                "template_id": "template_id",
+                "page": "pages/order/index"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let users = [
+            "o6_bmjrPTlm6_2sgVt7hMZOPfL2M",
+            "o6_bmjrPTlm6_2sgVt7hMZOPfL3N",
+            "o6_bmjrPTlm6_2sgVt7hMZOPfL4P",
+            "o6_bmjrPTlm6_2sgVt7hMZOPfL2M",
+        ];
+        let recipients = users.iter().map(|user| {
+            let data = SubscribeMessageData::from([(
+                "thing1".to_string(),
+                SubscribeMessageValue::new("Order shipped"),
+            )]);
+            BatchRecipient::new(OpenId::new(*user).unwrap(), data)
+        });
+        let batch = SubscribeBatch::new("template_id")
+            .page("pages/order/index")
+            .concurrency(2)
+            .rate_limit_backoff(Duration::from_millis(1));
+
+        let api = SubscribeApi::new(create_test_context(&mock_server.uri()));
+        let report = api.send_batch(&batch, recipients, None).await.unwrap();
+
+        assert_eq!(report.entries.len(), 3);
+        assert_eq!(report.entries[0].recipient.touser.as_str(), users[0]);
+        assert_eq!(report.entries[0].attempts, 2);
+        assert!(report.entries[0].result.is_ok());
+        assert_eq!(report.entries[1].attempts, 1);
+        assert!(matches!(
+            report.entries[1].result,
+            Err(WechatError::Api { code: 43101, .. })
+        ));
+        assert!(report.entries[2].result.is_ok());
+        assert_eq!(report.sent(), 2);
+        assert!(!report.is_complete());
+        assert_eq!(report.failures().count(), 1);
+        assert!(report.retryable().is_empty());
+    }
+
+    #[test]
+    fn test_rate_limit_delay_saturates_for_many_retries() {
+        let backoff = Duration::from_secs(1);
+        assert_eq!(rate_limit_delay(backoff, 1), backoff);
+        assert_eq!(rate_limit_delay(backoff, 3), Duration::from_secs(4));
+        for retry in [7, 33, 64, u32::MAX] {
+            assert_eq!(rate_limit_delay(backoff, retry), MAX_RATE_LIMIT_BACKOFF);
+        }
+        assert_eq!(rate_limit_delay(Duration::MAX, 1), MAX_RATE_LIMIT_BACKOFF);
+
+        let batch = SubscribeBatch::new("template_id").rate_limit_retries(u32::MAX);
+        assert_eq!(
+            rate_limit_delay(batch.rate_limit_backoff, batch.rate_limit_retries),
+            MAX_RATE_LIMIT_BACKOFF
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_batch_cancel_returns_finished_recipients() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .and(body_partial_json(serde_json::json!({
+                "touser": "o6_bmjrPTlm6_2sgVt7hMZOPfL2M"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"errcode": 0, "errmsg": "ok"}))
+                    .set_delay(Duration::from_secs(30)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let users = [
+            "o6_bmjrPTlm6_2sgVt7hMZOPfL2M",
+            "o6_bmjrPTlm6_2sgVt7hMZOPfL3N",
+            "o6_bmjrPTlm6_2sgVt7hMZOPfL4P",
+        ];
+        let recipients = users.iter().map(|user| {
+            let data = SubscribeMessageData::from([(
+                "thing1".to_string(),
+                SubscribeMessageValue::new("Order shipped"),
+            )]);
+            BatchRecipient::new(OpenId::new(*user).unwrap(), data)
+        });
+        let batch = SubscribeBatch::new("template_id").concurrency(1);
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            trigger.cancel();
+        });
+
+        let api = SubscribeApi::new(create_test_context(&mock_server.uri()));
+        let started = std::time::Instant::now();
+        let report = api
+            .send_batch(&batch, recipients, Some(&cancel))
+            .await
+            .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(report.cancelled);
+        assert_eq!(report.entries.len(), 1);
+        assert_eq!(report.entries[0].recipient.touser.as_str(), users[0]);
+        assert_eq!(report.sent(), 1);
+        assert!(!report.is_complete());
+
+        let already = api
+            .send_batch(&batch, Vec::new(), Some(&cancel))
+            .await
+            .unwrap();
+        assert!(already.cancelled && already.entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_send_batch_keeps_scoped_base_url() {
+        use crate::client::CallOptions;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let default_server = MockServer::start().await;
+        let gateway = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&default_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .expect(0)
+            .mount(&default_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .expect(2)
+            .mount(&gateway)
+            .await;
+
+        let recipients = [
+            "o6_bmjrPTlm6_2sgVt7hMZOPfL2M",
+            "o6_bmjrPTlm6_2sgVt7hMZOPfL3N",
+        ]
+        .into_iter()
+        .map(|user| {
+            let data = SubscribeMessageData::from([(
+                "thing1".to_string(),
+                SubscribeMessageValue::new("Order shipped"),
+            )]);
+            BatchRecipient::new(OpenId::new(user).unwrap(), data)
+        });
+        let batch = SubscribeBatch::new("template_id").concurrency(2);
+
+        let api = SubscribeApi::new(create_test_context(&default_server.uri()));
+        let report = CallOptions::new()
+            .base_url(gateway.uri())
+            .scope(api.send_batch(&batch, recipients, None))
+            .await
+            .unwrap();
+
+        assert_eq!(report.sent(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_cache_serves_repeat_lookups_until_invalidated() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/wxaapi/newtmpl/getcategory"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{"id": 616, "name": "IT"}],
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/wxaapi/newtmpl/getpubtemplatekeywords"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{"kid": 1, "name": "thing1", "rule": "thing"}],
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let context = create_test_context(&mock_server.uri());
+        for _ in 0..3 {
+            let api = SubscribeApi::new(Arc::clone(&context));
+            assert_eq!(api.get_category().await.unwrap()[0].id, 616);
+        }
+        let api = SubscribeApi::new(Arc::clone(&context));
+        api.get_pub_template_keywords_by_id("99").await.unwrap();
+        api.get_pub_template_keywords_by_id("99").await.unwrap();
+        assert_eq!(
+            api.metadata_cache_stats(),
+            MetadataCacheStats {
+                hits: 3,
+                misses: 2,
+                invalidations: 0
+            }
+        );
+
+        api.invalidate_metadata();
+        api.get_category().await.unwrap();
+        api.get_pub_template_keywords_by_id("99").await.unwrap();
+        assert_eq!(api.metadata_cache_stats().misses, 4);
+        assert_eq!(api.metadata_cache_stats().invalidations, 1);
+    }
 }
@@ -0,0 +1,246 @@
+//! Local subscribe message quota
+//!
+//! A one-time subscribe template can be sent to a user once per
+//! authorization they grant in `wx.requestSubscribeMessage`; sending without
+//! one fails with errcode 43101. A [`SubscribeQuotaStore`] counts the
+//! authorizations reported by the front end per `(openid, template_id)` so
+//! senders can check [`remaining`](SubscribeQuotaStore::remaining) first.
+//!
+//! Configure a store with
+//! [`WechatMpBuilder::subscribe_quota`](crate::WechatMpBuilder::subscribe_quota)
+//! and every subscribe send keeps it up to date; with
+//! [`SubscribeQuotaPolicy::Enforce`] sends without a remaining authorization
+//! fail locally with [`WechatError::SubscribeQuotaExhausted`].
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use crate::error::WechatError;
+use crate::utils::lock;
+
+/// errcode for a send without an accepted subscription
+const SUBSCRIPTION_REFUSED: i32 = 43101;
+
+/// What a configured [`SubscribeQuotaStore`] does on send
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SubscribeQuotaPolicy {
+    /// Count sends and 43101 rejections, but send regardless
+    #[default]
+    Track,
+    /// Also refuse sends without a remaining authorization before calling
+    /// WeChat
+    Enforce,
+}
+
+/// Future returned by [`SubscribeQuotaStore`] methods
+pub type SubscribeQuotaFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<T, WechatError>> + Send + 'a>>;
+
+/// Authorization counts per `(openid, template_id)`
+///
+/// Implement this over shared storage when several processes send for the
+/// same users; [`MemorySubscribeQuotaStore`] covers a single process.
+pub trait SubscribeQuotaStore: Send + Sync {
+    /// Add `count` authorizations, e.g. when the front end reports an
+    /// `accept` result.
+    fn grant<'a>(
+        &'a self,
+        openid: &'a str,
+        template_id: &'a str,
+        count: u32,
+    ) -> SubscribeQuotaFuture<'a, ()>;
+
+    /// Take one authorization if any remain.
+    ///
+    /// Must be atomic: concurrent takes never drive the count below zero.
+    /// Returns whether one was taken.
+    fn take<'a>(&'a self, openid: &'a str, template_id: &'a str) -> SubscribeQuotaFuture<'a, bool>;
+
+    /// Authorizations left.
+    fn remaining<'a>(
+        &'a self,
+        openid: &'a str,
+        template_id: &'a str,
+    ) -> SubscribeQuotaFuture<'a, u32>;
+
+    /// Drop all authorizations, e.g. after WeChat answered 43101.
+    fn clear<'a>(&'a self, openid: &'a str, template_id: &'a str) -> SubscribeQuotaFuture<'a, ()>;
+}
+
+/// A [`SubscribeQuotaStore`] with the policy applied to sends
+#[derive(Clone)]
+pub(crate) struct SubscribeQuota {
+    pub(crate) store: Arc<dyn SubscribeQuotaStore>,
+    pub(crate) policy: SubscribeQuotaPolicy,
+}
+
+impl std::fmt::Debug for SubscribeQuota {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubscribeQuota")
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SubscribeQuota {
+    /// Run `send` for one message, updating the store around it.
+    pub(crate) async fn send<F>(
+        &self,
+        openid: &str,
+        template_id: &str,
+        send: F,
+    ) -> Result<(), WechatError>
+    where
+        F: Future<Output = Result<(), WechatError>>,
+    {
+        let enforce = self.policy == SubscribeQuotaPolicy::Enforce;
+        if enforce && !self.store.take(openid, template_id).await? {
+            return Err(WechatError::SubscribeQuotaExhausted {
+                openid: openid.to_string(),
+                template_id: template_id.to_string(),
+            });
+        }
+
+        let result = send.await;
+        match &result {
+            Ok(()) if !enforce => {
+                self.store.take(openid, template_id).await?;
+            }
+            Ok(()) => {}
+            Err(WechatError::Api {
+                code: SUBSCRIPTION_REFUSED,
+                ..
+            }) => self.store.clear(openid, template_id).await?,
+            // Not sent: give the authorization back.
+            Err(_) if enforce => self.store.grant(openid, template_id, 1).await?,
+            Err(_) => {}
+        }
+        result
+    }
+}
+
+/// In-process [`SubscribeQuotaStore`]; cloning shares the counts
+#[derive(Debug, Clone, Default)]
+pub struct MemorySubscribeQuotaStore {
+    counts: Arc<Mutex<HashMap<(String, String), u32>>>,
+}
+
+impl MemorySubscribeQuotaStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn key(openid: &str, template_id: &str) -> (String, String) {
+    (openid.to_string(), template_id.to_string())
+}
+
+impl SubscribeQuotaStore for MemorySubscribeQuotaStore {
+    fn grant<'a>(
+        &'a self,
+        openid: &'a str,
+        template_id: &'a str,
+        count: u32,
+    ) -> SubscribeQuotaFuture<'a, ()> {
+        let mut counts = lock(&self.counts);
+        let remaining = counts.entry(key(openid, template_id)).or_default();
+        *remaining = remaining.saturating_add(count);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn take<'a>(&'a self, openid: &'a str, template_id: &'a str) -> SubscribeQuotaFuture<'a, bool> {
+        let mut counts = lock(&self.counts);
+        let taken = match counts.get_mut(&key(openid, template_id)) {
+            Some(remaining) if *remaining > 0 => {
+                *remaining -= 1;
+                true
+            }
+            _ => false,
+        };
+        Box::pin(async move { Ok(taken) })
+    }
+
+    fn remaining<'a>(
+        &'a self,
+        openid: &'a str,
+        template_id: &'a str,
+    ) -> SubscribeQuotaFuture<'a, u32> {
+        let remaining = lock(&self.counts)
+            .get(&key(openid, template_id))
+            .copied()
+            .unwrap_or(0);
+        Box::pin(async move { Ok(remaining) })
+    }
+
+    fn clear<'a>(&'a self, openid: &'a str, template_id: &'a str) -> SubscribeQuotaFuture<'a, ()> {
+        lock(&self.counts).remove(&key(openid, template_id));
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota(store: &MemorySubscribeQuotaStore, policy: SubscribeQuotaPolicy) -> SubscribeQuota {
+        SubscribeQuota {
+            store: Arc::new(store.clone()),
+            policy,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enforced_quota_refuses_without_authorization() {
+        let store = MemorySubscribeQuotaStore::new();
+        let quota = quota(&store, SubscribeQuotaPolicy::Enforce);
+        store.grant("openid", "tmpl", 1).await.unwrap();
+
+        quota
+            .send("openid", "tmpl", async { Ok(()) })
+            .await
+            .unwrap();
+        let refused = quota.send("openid", "tmpl", async { Ok(()) }).await;
+        assert!(matches!(
+            refused,
+            Err(WechatError::SubscribeQuotaExhausted { .. })
+        ));
+
+        store.grant("openid", "tmpl", 1).await.unwrap();
+        let failed = quota
+            .send("openid", "tmpl", async {
+                Err(WechatError::Config("offline".to_string()))
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(store.remaining("openid", "tmpl").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tracked_quota_clears_on_43101() {
+        let store = MemorySubscribeQuotaStore::new();
+        let quota = quota(&store, SubscribeQuotaPolicy::Track);
+        store.grant("openid", "tmpl", 3).await.unwrap();
+
+        quota
+            .send("openid", "tmpl", async { Ok(()) })
+            .await
+            .unwrap();
+        assert_eq!(store.remaining("openid", "tmpl").await.unwrap(), 2);
+
+        let refused = quota
+            .send("openid", "tmpl", async {
+                Err(WechatError::api(43101, "user refuse to accept the msg"))
+            })
+            .await;
+        assert!(refused.is_err());
+        assert_eq!(store.remaining("openid", "tmpl").await.unwrap(), 0);
+
+        // Tracking never blocks a send.
+        quota
+            .send("openid", "tmpl", async { Ok(()) })
+            .await
+            .unwrap();
+    }
+}
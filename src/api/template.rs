@@ -46,6 +46,7 @@ use crate::error::WechatError;
 // Re-export shared types from subscribe module for backward compatibility
 pub use super::subscribe::{
     AddTemplateResponse, CategoryInfo, CategoryListResponse, TemplateInfo, TemplateListResponse,
+    TemplateType,
 };
 
 /// Template Message Management API
@@ -147,7 +148,7 @@ mod tests {
         assert_eq!(info.title, "Test Template");
         assert_eq!(info.content, "Content here");
         assert_eq!(info.example, Some("Example content".to_string()));
-        assert_eq!(info.template_type, 2);
+        assert_eq!(info.template_type, TemplateType::OneTime);
     }
 
     #[test]
@@ -2,25 +2,48 @@
 //!
 //! Provides the base trait and context for all WeChat API implementations.
 
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use super::subscribe::{MetadataCache, DEFAULT_METADATA_TTL};
+use super::subscribe_quota::SubscribeQuota;
 use crate::client::WechatClient;
 use crate::error::WechatError;
-use crate::token::TokenManager;
+use crate::token::{TokenManager, TokenRouter};
+use crate::utils::{api_error_from_value, decode_json, decode_json_value, BinaryBody};
+
+/// Errcodes WeChat returns for a rejected access token: 40001 (invalid or
+/// not the latest credential) and 42001 (expired)
+const REJECTED_TOKEN_ERROR_CODES: &[i32] = &[40001, 42001];
 
 /// Context holding shared resources for WeChat API implementations.
 ///
 /// Contains references to the HTTP client and token manager that
 /// API implementations need to make requests.
+///
+/// Requests are sent with the timeout of the endpoint's
+/// [`TimeoutClass`](crate::api::endpoint_inventory::TimeoutClass), so slow
+/// media and OCR calls do not share the budget of quick JSON lookups.
+///
+/// Calls rejected with 40001 or 42001, e.g. after another process
+/// refreshed the token, invalidate the cached token and are retried once
+/// with a fresh one. This covers JSON, binary and multipart upload calls.
 #[derive(Clone)]
 pub struct WechatContext {
     /// The WeChat HTTP client for making API requests
     pub(crate) client: Arc<WechatClient>,
     /// The token manager for access token lifecycle
     pub(crate) token_manager: Arc<TokenManager>,
+    /// Token manager per endpoint category, defaulting to `token_manager`
+    pub(crate) token_router: Arc<TokenRouter>,
+    /// Subscribe template categories and keywords
+    pub(crate) subscribe_metadata: Arc<MetadataCache>,
+    /// Authorization counts updated by subscribe sends, if configured
+    pub(crate) subscribe_quota: Option<SubscribeQuota>,
 }
 
 impl std::fmt::Debug for WechatContext {
@@ -37,10 +60,33 @@ impl WechatContext {
     pub fn new(client: Arc<WechatClient>, token_manager: Arc<TokenManager>) -> Self {
         Self {
             client,
+            token_router: Arc::new(TokenRouter::new(Arc::clone(&token_manager))),
             token_manager,
+            subscribe_metadata: Arc::new(MetadataCache::new(DEFAULT_METADATA_TTL)),
+            subscribe_quota: None,
         }
     }
 
+    /// Fetch tokens for each endpoint category through `router`
+    ///
+    /// The router's default manager replaces the one given to
+    /// [`new`](Self::new).
+    pub fn with_token_router(mut self, router: TokenRouter) -> Self {
+        self.token_manager = Arc::clone(router.default_manager());
+        self.token_router = Arc::new(router);
+        self
+    }
+
+    pub(crate) fn with_subscribe_quota(mut self, quota: SubscribeQuota) -> Self {
+        self.subscribe_quota = Some(quota);
+        self
+    }
+
+    pub(crate) fn with_subscribe_metadata_ttl(mut self, ttl: Duration) -> Self {
+        self.subscribe_metadata = Arc::new(MetadataCache::new(ttl));
+        self
+    }
+
     /// Get a reference to the WeChat HTTP client.
     pub fn client(&self) -> &WechatClient {
         &self.client
@@ -51,36 +97,121 @@ impl WechatContext {
         &self.token_manager
     }
 
-    pub(crate) async fn authed_get<T: DeserializeOwned>(
+    /// Get a reference to the per-category token router.
+    pub fn token_router(&self) -> &TokenRouter {
+        &self.token_router
+    }
+
+    /// Access token for the endpoint at `path`
+    pub(crate) async fn token_for(&self, path: &str) -> Result<String, WechatError> {
+        self.token_router.manager_for_path(path).get_token().await
+    }
+
+    /// Run `call` with the path carrying an access token, retrying once
+    /// with a fresh token when WeChat rejects it
+    async fn with_token_retry<T, F, Fut>(&self, path: &str, call: F) -> Result<T, WechatError>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T, WechatError>>,
+    {
+        let manager = self.token_router.manager_for_path(path);
+        let token = manager.get_token().await?;
+        match call(WechatClient::append_access_token(path, &token)).await {
+            Err(WechatError::Api { code, .. }) if REJECTED_TOKEN_ERROR_CODES.contains(&code) => {
+                log::warn!(
+                    "access token rejected with {} on {}; refreshing and retrying",
+                    code,
+                    path
+                );
+                manager.invalidate_rejected(&token).await;
+                let token = manager.get_token().await?;
+                call(WechatClient::append_access_token(path, &token)).await
+            }
+            result => result,
+        }
+    }
+
+    pub(crate) async fn authed_get<T: DeserializeOwned + Send + 'static>(
         &self,
         path: &str,
         extra_query: &[(&str, &str)],
     ) -> Result<T, WechatError> {
-        let token = self.token_manager.get_token().await?;
-        let authed_path = WechatClient::append_access_token(path, &token);
-        self.client.get(&authed_path, extra_query).await
+        self.with_token_retry(path, |authed_path| async move {
+            self.client.get(&authed_path, extra_query).await
+        })
+        .await
     }
 
-    pub(crate) async fn authed_post<T: DeserializeOwned, B: Serialize>(
+    pub(crate) async fn authed_post<T: DeserializeOwned + Send + 'static, B: Serialize>(
         &self,
         path: &str,
         body: &B,
     ) -> Result<T, WechatError> {
-        let token = self.token_manager.get_token().await?;
-        let authed_path = WechatClient::append_access_token(path, &token);
-        self.client.post(&authed_path, body).await
+        self.with_token_retry(path, |authed_path| async move {
+            self.client.post(&authed_path, body).await
+        })
+        .await
     }
 
-    pub(crate) async fn authed_post_raw<B: Serialize>(
+    /// POST `body` and read the reply as binary, mapping an `errcode` body
+    /// to [`WechatError::Api`]
+    pub(crate) async fn authed_post_binary<B: Serialize>(
         &self,
         path: &str,
         body: &B,
-    ) -> Result<reqwest::Response, WechatError> {
-        let token = self.token_manager.get_token().await?;
-        let authed_path = WechatClient::append_access_token(path, &token);
-        let url = format!("{}{}", self.client.base_url(), &authed_path);
-        let request = self.client.http().post(&url).json(body).build()?;
-        Ok(self.client.send_request(request).await?)
+    ) -> Result<BinaryBody, WechatError> {
+        self.with_token_retry(path, |authed_path| async move {
+            let url = self.client.url_for(&authed_path)?;
+            let request = self
+                .client
+                .json_body(self.client.http().post(url), body)?
+                .timeout(self.client.timeout_for_path(path))
+                .build()?;
+            let response = self.client.send_request(request).await?;
+            self.client.binary_body_parts(response).await
+        })
+        .await
+    }
+
+    /// POST `data` as the multipart file field `field` and decode the JSON
+    /// reply, mapping an `errcode` body to [`WechatError::Api`]
+    pub(crate) async fn authed_upload<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        extra_query: &[(&str, &str)],
+        field: &str,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<T, WechatError> {
+        self.with_token_retry(path, |authed_path| async move {
+            let url = self.client.url_for(&authed_path)?;
+            // A sent form is consumed, so every attempt builds its own
+            let part =
+                reqwest::multipart::Part::bytes(data.to_vec()).file_name(filename.to_string());
+            let form = reqwest::multipart::Form::new().part(field.to_string(), part);
+
+            let request = self
+                .client
+                .http()
+                .post(url)
+                .query(extra_query)
+                .multipart(form)
+                .timeout(self.client.timeout_for_path(path))
+                .build()?;
+            let response = self.client.send_request(request).await?;
+            if let Err(error) = response.error_for_status_ref() {
+                return Err(error.into());
+            }
+
+            let body = self.client.json_response_body(response).await?;
+            let value: serde_json::Value = decode_json(&body)?;
+            if let Some(error) = api_error_from_value(&value) {
+                return Err(self.client.classify_error(error));
+            }
+
+            decode_json_value(&value, &body)
+        })
+        .await
     }
 }
 
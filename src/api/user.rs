@@ -1,8 +1,10 @@
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
 use crate::api::r#trait::{WechatApi, WechatContext};
+use crate::crypto::secure::{session_signature, UserKeyCipher, SIG_METHOD_HMAC_SHA256};
 use crate::error::WechatError;
 use crate::types::Watermark;
 
@@ -50,6 +52,7 @@ impl UserInfo {
 /// Phone number information from getPhoneNumber API
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PhoneInfo {
     /// User's phone number (with country code, e.g., +8613800138000)
     pub phone_number: String,
@@ -80,6 +83,7 @@ impl PhoneInfo {
 /// Response from getPhoneNumber API
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PhoneNumberResponse {
     /// Phone info
     pub phone_info: PhoneInfo,
@@ -111,21 +115,25 @@ impl PhoneNumberResponse {
 }
 
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct PhoneNumberRequest {
     code: String,
 }
 
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct PluginOpenPIdRequest {
     code: String,
 }
 
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct CheckEncryptedDataRequest {
     encrypted_msg_hash: String,
 }
 
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct UserEncryptKeyRequest {
     openid: String,
     signature: String,
@@ -135,6 +143,7 @@ struct UserEncryptKeyRequest {
 /// Response from getPluginOpenPId
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PluginOpenPIdResponse {
     #[serde(default)]
     pub openpid: String,
@@ -149,6 +158,7 @@ pub struct PluginOpenPIdResponse {
 /// Note: WeChat API returns "vaild" (typo), not "valid"
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CheckEncryptedDataResponse {
     /// Whether the encrypted data is valid (WeChat uses "vaild" in their API)
     #[serde(default)]
@@ -162,6 +172,7 @@ pub struct CheckEncryptedDataResponse {
 /// Response from getPaidUnionid
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PaidUnionIdResponse {
     #[serde(default)]
     pub unionid: String,
@@ -171,25 +182,68 @@ pub struct PaidUnionIdResponse {
     pub(crate) errmsg: String,
 }
 
-/// Encrypt key info entry
+/// One version of the user encrypt key
+///
+/// WeChat rotates the key and returns the latest versions; each stays valid
+/// for `expire_in` seconds after `create_time`.
 #[non_exhaustive]
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct EncryptKeyInfo {
+    /// Base64 encoded AES-128 key
     #[serde(default)]
     pub encrypt_key: String,
+    /// Key version, reported by the mini program with encrypted data
     #[serde(default)]
     pub version: i32,
+    /// Lifetime in seconds, counted from `create_time`
     #[serde(default)]
     pub expire_in: i64,
     #[serde(default)]
     pub iv: String,
+    /// Unix timestamp (seconds) the key was created
     #[serde(default)]
     pub create_time: i64,
 }
 
+impl std::fmt::Debug for EncryptKeyInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptKeyInfo")
+            .field("encrypt_key", &"[REDACTED]")
+            .field("version", &self.version)
+            .field("expire_in", &self.expire_in)
+            .field("iv", &"[REDACTED]")
+            .field("create_time", &self.create_time)
+            .finish()
+    }
+}
+
+impl EncryptKeyInfo {
+    /// When WeChat created this key version
+    pub fn created_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.create_time.max(0) as u64)
+    }
+
+    /// When this key version stops being valid
+    pub fn expires_at(&self) -> SystemTime {
+        self.created_at() + Duration::from_secs(self.expire_in.max(0) as u64)
+    }
+
+    /// Returns true if the key is expired at `now`.
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        self.expires_at() <= now
+    }
+
+    /// Returns true if the key has expired.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(SystemTime::now())
+    }
+}
+
 /// Response from getUserEncryptKey
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UserEncryptKeyResponse {
     #[serde(default)]
     pub key_info_list: Vec<EncryptKeyInfo>,
@@ -199,6 +253,42 @@ pub struct UserEncryptKeyResponse {
     pub(crate) errmsg: String,
 }
 
+impl UserEncryptKeyResponse {
+    /// Highest key version still valid at `now`
+    pub fn newest_unexpired_key_at(&self, now: SystemTime) -> Option<&EncryptKeyInfo> {
+        self.key_info_list
+            .iter()
+            .filter(|info| !info.is_expired_at(now))
+            .max_by_key(|info| info.version)
+    }
+
+    /// Highest key version that has not expired, the one
+    /// `getLatestUserKey()` returns to the mini program
+    pub fn newest_unexpired_key(&self) -> Option<&EncryptKeyInfo> {
+        self.newest_unexpired_key_at(SystemTime::now())
+    }
+
+    /// Key `version`, e.g. to decrypt data the mini program encrypted with
+    /// an older key
+    pub fn key_for_version(&self, version: i32) -> Option<&EncryptKeyInfo> {
+        self.key_info_list
+            .iter()
+            .find(|info| info.version == version)
+    }
+
+    /// Cipher for the [newest unexpired key](Self::newest_unexpired_key)
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] when every key has expired or the
+    /// key is malformed.
+    pub fn cipher(&self) -> Result<UserKeyCipher, WechatError> {
+        let info = self
+            .newest_unexpired_key()
+            .ok_or_else(|| WechatError::Crypto("no unexpired key in key_info_list".to_string()))?;
+        UserKeyCipher::from_key_info(info)
+    }
+}
+
 pub struct UserApi {
     context: Arc<WechatContext>,
 }
@@ -310,6 +400,22 @@ impl UserApi {
         WechatError::check_api(response.errcode, &response.errmsg)?;
         Ok(response)
     }
+
+    /// Get user's encrypt key, signing the request with the session key
+    ///
+    /// Computes the `hmac_sha256` signature of an empty message as required by
+    /// WeChat. [`UserEncryptKeyResponse::cipher`] builds the cipher for the
+    /// newest unexpired key, matching `getLatestUserKey()` on the mini
+    /// program side.
+    pub async fn get_user_encrypt_key_with_session_key(
+        &self,
+        openid: &str,
+        session_key: &str,
+    ) -> Result<UserEncryptKeyResponse, WechatError> {
+        let signature = session_signature(session_key, "")?;
+        self.get_user_encrypt_key(openid, &signature, SIG_METHOD_HMAC_SHA256)
+            .await
+    }
 }
 
 impl WechatApi for UserApi {
@@ -322,6 +428,15 @@ impl WechatApi for UserApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<PluginOpenPIdRequest, PluginOpenPIdResponse>("user.getPluginOpenPId");
+    schemas.add::<CheckEncryptedDataRequest, CheckEncryptedDataResponse>("user.checkEncryptedData");
+    schemas.response::<PaidUnionIdResponse>("user.getPaidUnionid");
+    schemas.add::<UserEncryptKeyRequest, UserEncryptKeyResponse>("user.getUserEncryptKey");
+    schemas.add::<PhoneNumberRequest, PhoneNumberResponse>("user.getPhoneNumber");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -462,4 +577,48 @@ mod tests {
         let response: UserEncryptKeyResponse = serde_json::from_str(json).unwrap();
         assert!(response.key_info_list.is_empty());
     }
+
+    #[test]
+    fn test_user_encrypt_key_rotation_picks_newest_unexpired() {
+        let json = r#"{
+            "key_info_list": [
+                {"encrypt_key": "a2V5MS1rZXkxLWtleTEtaw==", "version": 8, "expire_in": 600,
+                 "iv": "iv8iv8iv8iv8iv8i", "create_time": 1700007200},
+                {"encrypt_key": "a2V5Mi1rZXkyLWtleTItaw==", "version": 7, "expire_in": 7200,
+                 "iv": "iv7iv7iv7iv7iv7i", "create_time": 1700003600},
+                {"encrypt_key": "a2V5My1rZXkzLWtleTMtaw==", "version": 6, "expire_in": 3600,
+                 "iv": "iv6iv6iv6iv6iv6i", "create_time": 1700000000}
+            ],
+            "errcode": 0,
+            "errmsg": "ok"
+        }"#;
+        let response: UserEncryptKeyResponse = serde_json::from_str(json).unwrap();
+        let at = |secs: u64| UNIX_EPOCH + Duration::from_secs(secs);
+
+        assert_eq!(response.key_info_list[2].expires_at(), at(1_700_003_600));
+        assert_eq!(
+            response
+                .newest_unexpired_key_at(at(1_700_007_500))
+                .unwrap()
+                .version,
+            8
+        );
+        assert_eq!(
+            response
+                .newest_unexpired_key_at(at(1_700_008_000))
+                .unwrap()
+                .version,
+            7
+        );
+        assert!(response
+            .newest_unexpired_key_at(at(1_700_010_800))
+            .is_none());
+        assert_eq!(response.key_for_version(6).unwrap().iv, "iv6iv6iv6iv6iv6i");
+        assert!(response.key_for_version(5).is_none());
+        assert!(matches!(response.cipher(), Err(WechatError::Crypto(_))));
+
+        let debug = format!("{:?}", response.key_info_list[0]);
+        assert!(!debug.contains("a2V5MS1rZXkxLWtleTEtaw=="));
+        assert!(!debug.contains("iv8iv8iv8iv8iv8i"));
+    }
 }
@@ -20,17 +20,20 @@ use crate::error::WechatError;
 // ============================================================================
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct GetKfWorkBoundRequest {
     openid: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct BindKfWorkRequest {
     openid: String,
     open_kfid: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct UnbindKfWorkRequest {
     openid: String,
     open_kfid: String,
@@ -51,6 +54,7 @@ struct BaseApiResponse {
 /// KF work info entry
 #[non_exhaustive]
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct KfWorkInfo {
     /// Open KF account ID
     #[serde(default)]
@@ -63,6 +67,7 @@ pub struct KfWorkInfo {
 /// Response from getKfWorkBound
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct KfWorkBoundResponse {
     /// List of bound KF accounts
     #[serde(default)]
@@ -168,6 +173,15 @@ impl WechatApi for WechatKfApi {
 // Tests
 // ============================================================================
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    use super::common::ApiResponseBase;
+
+    schemas.add::<GetKfWorkBoundRequest, KfWorkBoundResponse>("kfWork.getKfWorkBound");
+    schemas.add::<BindKfWorkRequest, ApiResponseBase>("kfWork.bindKfWork");
+    schemas.add::<UnbindKfWorkRequest, ApiResponseBase>("kfWork.unbindKfWork");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -306,7 +320,7 @@ mod tests {
         let api = WechatKfApi::new(context);
         let result = api.get_kf_work_bound("openid123").await;
         assert!(result.is_err());
-        if let Err(WechatError::Api { code, message }) = result {
+        if let Err(WechatError::Api { code, message, .. }) = result {
             assert_eq!(code, 40001);
             assert_eq!(message, "invalid credential");
         } else {
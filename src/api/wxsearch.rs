@@ -11,12 +11,26 @@ use crate::error::WechatError;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SubmitPagesRequest {
     pub pages: Vec<String>,
 }
 
+impl SubmitPagesRequest {
+    pub fn new<I, S>(pages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            pages: pages.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SubmitPagesResponse {
     #[serde(default)]
     pub(crate) errcode: i32,
@@ -35,6 +49,7 @@ impl WxsearchApi {
         Self { context }
     }
 
+    #[doc(alias = "提交小程序页面url及参数信息")]
     pub async fn submit_pages(
         &self,
         request: &SubmitPagesRequest,
@@ -58,6 +73,11 @@ impl WechatApi for WxsearchApi {
     }
 }
 
+#[cfg(feature = "schemars")]
+pub(crate) fn openapi_schemas(schemas: &mut super::endpoint_inventory::OperationSchemas) {
+    schemas.add::<SubmitPagesRequest, SubmitPagesResponse>("wxsearch.submitPages");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,4 +88,14 @@ mod tests {
         let response: SubmitPagesResponse = serde_json::from_str(json).unwrap();
         assert_eq!(response.errcode, 0);
     }
+
+    #[test]
+    fn submit_pages_request_accepts_borrowed_and_owned() {
+        let owned = String::from("pages/detail/detail");
+        let request = SubmitPagesRequest::new(["pages/index/index", owned.as_str()]);
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({"pages": ["pages/index/index", "pages/detail/detail"]})
+        );
+    }
 }
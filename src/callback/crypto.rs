@@ -0,0 +1,272 @@
+//! Signatures and encrypted-mode (AES) bodies for push messages
+//!
+//! Every push request carries `signature = sha1(sort(token, timestamp, nonce))`.
+//! In encrypted (safe) mode the body holds an `Encrypt` field signed as
+//! `msg_signature = sha1(sort(token, timestamp, nonce, encrypt))` and
+//! encrypted with AES-256-CBC: the key is the base64 decoded 43-character
+//! EncodingAESKey, the IV its first 16 bytes, and the plaintext is
+//! `random(16) || msg_len(4, big-endian) || msg || appid`, PKCS#7 padded to
+//! 32-byte blocks.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::Aes256;
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD as BASE64};
+use base64::Engine;
+use cbc::cipher::block_padding::NoPadding;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::error::WechatError;
+
+type Aes256CbcEncryptor = cbc::Encryptor<Aes256>;
+type Aes256CbcDecryptor = cbc::Decryptor<Aes256>;
+
+/// Block size WeChat pads encrypted-mode messages to
+const PAD_BLOCK: usize = 32;
+
+/// Lowercase hex SHA-1 of the lexicographically sorted, concatenated `parts`.
+///
+/// Pass `[token, timestamp, nonce]` for `signature`, and add the `Encrypt`
+/// value for `msg_signature`.
+pub fn callback_signature(parts: &[&str]) -> String {
+    let mut parts = parts.to_vec();
+    parts.sort_unstable();
+    Sha1::digest(parts.concat().as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// `delivery_sign` of instant delivery requests and status pushes: lowercase
+/// hex SHA-1 of `shopid + shop_order_id + app_secret`, concatenated in that
+/// order.
+///
+/// `shopid` and `app_secret` are issued by the delivery company.
+pub fn delivery_signature(shopid: &str, shop_order_id: &str, app_secret: &str) -> String {
+    Sha1::digest(format!("{shopid}{shop_order_id}{app_secret}").as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Compare signatures without short-circuiting on the first mismatch.
+pub(crate) fn signature_matches(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .bytes()
+            .zip(actual.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b.to_ascii_lowercase()))
+            == 0
+}
+
+/// AES-256-CBC cipher for encrypted-mode push messages
+#[derive(Clone)]
+pub struct CallbackCipher {
+    key: [u8; 32],
+    iv: [u8; 16],
+    appid: String,
+}
+
+impl std::fmt::Debug for CallbackCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackCipher")
+            .field("key", &"[REDACTED]")
+            .field("appid", &self.appid)
+            .finish()
+    }
+}
+
+impl CallbackCipher {
+    /// Create a cipher from the 43-character EncodingAESKey configured in the
+    /// mini program console.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] if the key does not decode to 32 bytes.
+    pub fn new(encoding_aes_key: &str, appid: impl Into<String>) -> Result<Self, WechatError> {
+        // Console keys need not end on canonical base64 bits
+        let lenient = GeneralPurpose::new(
+            &alphabet::STANDARD,
+            GeneralPurposeConfig::new().with_decode_allow_trailing_bits(true),
+        );
+        let key = lenient
+            .decode(format!("{}=", encoding_aes_key.trim_end_matches('=')))
+            .map_err(|e| WechatError::Crypto(format!("Invalid EncodingAESKey: {}", e)))?;
+        let key: [u8; 32] = key.as_slice().try_into().map_err(|_| {
+            WechatError::Crypto(format!(
+                "Invalid EncodingAESKey length: expected 32 bytes, got {}",
+                key.len()
+            ))
+        })?;
+        let mut iv = [0u8; 16];
+        iv.copy_from_slice(&key[..16]);
+        Ok(Self {
+            key,
+            iv,
+            appid: appid.into(),
+        })
+    }
+
+    /// AppID the decrypted messages must end with
+    pub fn appid(&self) -> &str {
+        &self.appid
+    }
+
+    /// Decrypt an `Encrypt` value and return the inner message.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] on invalid base64, padding or length,
+    /// and [`WechatError::Signature`] when the trailing AppID differs.
+    pub fn decrypt(&self, encrypted: &str) -> Result<String, WechatError> {
+        let mut buffer = BASE64
+            .decode(encrypted.trim())
+            .map_err(|e| WechatError::Crypto(format!("Invalid Encrypt: {}", e)))?;
+        let decryptor = Aes256CbcDecryptor::new(&self.key.into(), &self.iv.into());
+        let decrypted = decryptor
+            .decrypt_padded_mut::<NoPadding>(&mut buffer)
+            .map_err(|e| WechatError::Crypto(format!("Decryption failed: {:?}", e)))?;
+
+        let pad = decrypted.last().copied().unwrap_or(0) as usize;
+        if pad == 0 || pad > PAD_BLOCK || pad > decrypted.len() {
+            return Err(WechatError::Crypto("Invalid padding".to_string()));
+        }
+        let plain = &decrypted[..decrypted.len() - pad];
+        if plain.len() < 20 {
+            return Err(WechatError::Crypto(
+                "Decrypted message too short".to_string(),
+            ));
+        }
+
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&plain[16..20]);
+        let invalid_length = || WechatError::Crypto("Invalid message length".to_string());
+        let len = u32::from_be_bytes(len) as usize;
+        let end = 20usize.checked_add(len).ok_or_else(invalid_length)?;
+        let body = plain.get(20..end).ok_or_else(invalid_length)?;
+        let appid = plain.get(end..).ok_or_else(invalid_length)?;
+        if appid != self.appid.as_bytes() {
+            return Err(WechatError::Signature(format!(
+                "Callback appid mismatch: expected {}, got {}",
+                self.appid,
+                String::from_utf8_lossy(appid)
+            )));
+        }
+
+        String::from_utf8(body.to_vec())
+            .map_err(|e| WechatError::Crypto(format!("Invalid UTF-8: {}", e)))
+    }
+
+    /// Encrypt `message` into an `Encrypt` value, e.g. for a passive reply
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] if encryption fails.
+    pub fn encrypt(&self, message: &str) -> Result<String, WechatError> {
+        let mut plain = random_prefix(message).to_vec();
+        plain.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        plain.extend_from_slice(message.as_bytes());
+        plain.extend_from_slice(self.appid.as_bytes());
+        let pad = PAD_BLOCK - plain.len() % PAD_BLOCK;
+        plain.resize(plain.len() + pad, pad as u8);
+
+        let len = plain.len();
+        let encryptor = Aes256CbcEncryptor::new(&self.key.into(), &self.iv.into());
+        let encrypted = encryptor
+            .encrypt_padded_mut::<NoPadding>(&mut plain, len)
+            .map_err(|e| WechatError::Crypto(format!("Encryption failed: {:?}", e)))?;
+        Ok(BASE64.encode(encrypted))
+    }
+}
+
+/// 16 non-repeating bytes; WeChat only requires them to vary per message
+fn random_prefix(message: &str) -> [u8; 16] {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    let mut hasher = Sha256::new();
+    hasher.update(nanos.to_le_bytes());
+    hasher.update(COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    hasher.update(message.as_bytes());
+    let digest = hasher.finalize();
+    let mut prefix = [0u8; 16];
+    prefix.copy_from_slice(&digest[..16]);
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AES_KEY: &str = "abcdefghijklmnopqrstuvwxyz0123456789ABCDEFG";
+
+    #[test]
+    fn test_callback_signature_sorts_parts() {
+        assert_eq!(
+            callback_signature(&["token", "1409304348", "xxxxxx"]),
+            callback_signature(&["xxxxxx", "token", "1409304348"])
+        );
+        assert_eq!(callback_signature(&["a"]).len(), 40);
+        let signature = callback_signature(&["token", "1", "n"]);
+        assert!(signature_matches(&signature, &signature.to_uppercase()));
+        assert!(!signature_matches(&signature, "deadbeef"));
+        assert_eq!(
+            delivery_signature("test_shop_id", "order-1", "test_app_secrect"),
+            "47cbc3215cd6ebfe27273c21be1a233c4ff01eb1"
+        );
+    }
+
+    #[test]
+    fn test_cipher_round_trip_and_appid_check() {
+        let cipher = CallbackCipher::new(AES_KEY, "wx1234567890abcdef").unwrap();
+        let encrypted = cipher
+            .encrypt(r#"{"MsgType":"text","Content":"你好"}"#)
+            .unwrap();
+        assert_eq!(
+            cipher.decrypt(&encrypted).unwrap(),
+            r#"{"MsgType":"text","Content":"你好"}"#
+        );
+        assert_ne!(
+            encrypted,
+            cipher
+                .encrypt(r#"{"MsgType":"text","Content":"你好"}"#)
+                .unwrap()
+        );
+
+        let other = CallbackCipher::new(AES_KEY, "wxabcdef1234567890").unwrap();
+        assert!(matches!(
+            other.decrypt(&encrypted),
+            Err(WechatError::Signature(_))
+        ));
+    }
+
+    #[test]
+    fn test_cipher_rejects_oversized_length_prefix() {
+        let cipher = CallbackCipher::new(AES_KEY, "wx1234567890abcdef").unwrap();
+        let mut plain = [0u8; 16].to_vec();
+        plain.extend_from_slice(&u32::MAX.to_be_bytes());
+        plain.extend_from_slice(b"wx1234567890abcdef");
+        let pad = PAD_BLOCK - plain.len() % PAD_BLOCK;
+        plain.resize(plain.len() + pad, pad as u8);
+        let len = plain.len();
+        let encrypted = Aes256CbcEncryptor::new(&cipher.key.into(), &cipher.iv.into())
+            .encrypt_padded_mut::<NoPadding>(&mut plain, len)
+            .unwrap();
+        let encrypted = BASE64.encode(encrypted);
+
+        assert!(matches!(
+            cipher.decrypt(&encrypted),
+            Err(WechatError::Crypto(message)) if message == "Invalid message length"
+        ));
+    }
+
+    #[test]
+    fn test_cipher_rejects_bad_key() {
+        assert!(matches!(
+            CallbackCipher::new("short", "wx1234567890abcdef"),
+            Err(WechatError::Crypto(_))
+        ));
+    }
+}
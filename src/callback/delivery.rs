@@ -0,0 +1,249 @@
+//! Order status pushes from instant delivery and express logistics
+//!
+//! Delivery companies report progress through WeChat, which forwards it to
+//! the message server:
+//!
+//! - `update_waybill_status` for instant delivery orders, parsed into
+//!   [`DeliveryStatusUpdateEvent`]. The push repeats the order's
+//!   `delivery_sign`; check it with
+//!   [`verify`](DeliveryStatusUpdateEvent::verify) before trusting the status.
+//! - `add_express_path` for express waybills, parsed into
+//!   [`ExpressPathUpdateEvent`].
+//!
+//! ```rust,ignore
+//! let message = handler.parse(&query, &body)?;
+//! if let CallbackEvent::DeliveryStatusUpdate(update) = &message.kind {
+//!     update.verify(&merchant)?;
+//!     tracker.apply(update.order_status, update.action_time)?;
+//! }
+//! ```
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::crypto::signature_matches;
+use super::message::{int, string};
+use crate::api::delivery::{DeliveryMerchant, DeliveryOrderState};
+use crate::api::logistics::PathItem;
+use crate::error::WechatError;
+
+/// Rider handling an instant delivery order
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DeliveryAgent {
+    pub name: String,
+    pub phone: String,
+    /// Expected arrival, Unix seconds; 0 when not reported
+    pub reach_time: i64,
+}
+
+/// Instant delivery order status change (`update_waybill_status` event)
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DeliveryStatusUpdateEvent {
+    pub shopid: String,
+    pub shop_order_id: String,
+    pub shop_no: String,
+    pub waybill_id: String,
+    /// Unix seconds
+    pub action_time: i64,
+    /// See [`DeliveryOrderState::from_status`]
+    pub order_status: i32,
+    pub action_msg: String,
+    /// `sha1(shopid + shop_order_id + app_secret)`, as sent with the order
+    pub delivery_sign: String,
+    /// Present once a rider is assigned
+    pub agent: Option<DeliveryAgent>,
+}
+
+impl DeliveryStatusUpdateEvent {
+    /// Read the fields of an `update_waybill_status` push
+    ///
+    /// Numbers may arrive as strings in XML mode; missing fields default.
+    pub fn from_object(object: &Map<String, Value>) -> Self {
+        Self {
+            shopid: string(object, "shopid"),
+            shop_order_id: string(object, "shop_order_id"),
+            shop_no: string(object, "shop_no"),
+            waybill_id: string(object, "waybill_id"),
+            action_time: int(object, "action_time"),
+            order_status: int(object, "order_status") as i32,
+            action_msg: string(object, "action_msg"),
+            delivery_sign: string(object, "delivery_sign"),
+            agent: object
+                .get("agent")
+                .and_then(Value::as_object)
+                .map(|agent| DeliveryAgent {
+                    name: string(agent, "name"),
+                    phone: string(agent, "phone"),
+                    reach_time: int(agent, "reach_time"),
+                }),
+        }
+    }
+
+    /// Lifecycle stage of [`order_status`](Self::order_status)
+    pub fn state(&self) -> Option<DeliveryOrderState> {
+        DeliveryOrderState::from_status(self.order_status)
+    }
+
+    /// Check that the push belongs to `merchant` and carries the order's
+    /// `delivery_sign`.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Signature`] when the shop id or signature does
+    /// not match.
+    pub fn verify(&self, merchant: &DeliveryMerchant) -> Result<(), WechatError> {
+        if self.shopid != merchant.shopid() {
+            return Err(WechatError::Signature(format!(
+                "Delivery shopid mismatch: expected {}, got {}",
+                merchant.shopid(),
+                self.shopid
+            )));
+        }
+        if signature_matches(
+            &merchant.delivery_sign(&self.shop_order_id),
+            &self.delivery_sign,
+        ) {
+            Ok(())
+        } else {
+            Err(WechatError::Signature("Delivery sign mismatch".to_string()))
+        }
+    }
+}
+
+/// New tracking events for an express waybill (`add_express_path` event)
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ExpressPathUpdateEvent {
+    pub delivery_id: String,
+    pub waybill_id: String,
+    /// Order id passed to `add_express_order`
+    pub order_id: String,
+    /// Increases with every push for the waybill
+    pub version: i64,
+    /// Number of events WeChat says the push carries
+    pub count: i64,
+    /// Events in the order pushed, oldest first
+    pub actions: Vec<PathItem>,
+}
+
+impl ExpressPathUpdateEvent {
+    /// Read the fields of an `add_express_path` push
+    ///
+    /// `Actions` may be a single object or repeated elements in XML mode.
+    pub fn from_object(object: &Map<String, Value>) -> Self {
+        let actions = match object.get("Actions") {
+            Some(Value::Array(items)) => items.iter().filter_map(Value::as_object).collect(),
+            Some(Value::Object(item)) => vec![item],
+            _ => Vec::new(),
+        };
+        Self {
+            delivery_id: string(object, "DeliveryID"),
+            waybill_id: string(object, "WayBillId"),
+            order_id: string(object, "OrderId"),
+            version: int(object, "Version"),
+            count: int(object, "Count"),
+            actions: actions
+                .into_iter()
+                .map(|action| PathItem {
+                    action_time: int(action, "ActionTime"),
+                    action_type: int(action, "ActionType") as i32,
+                    action_msg: string(action, "ActionMsg"),
+                })
+                .collect(),
+        }
+    }
+
+    /// Most recent event of the push
+    pub fn latest(&self) -> Option<&PathItem> {
+        self.actions.iter().max_by_key(|action| action.action_time)
+    }
+
+    /// Check that the push names a waybill and holds the `Count` events it
+    /// announces.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Decode`] for an incomplete push.
+    pub fn validate(&self) -> Result<(), WechatError> {
+        let problem = if self.waybill_id.is_empty() {
+            "express path push has no WayBillId".to_string()
+        } else if self.count != self.actions.len() as i64 {
+            format!(
+                "express path push announces {} actions but carries {}",
+                self.count,
+                self.actions.len()
+            )
+        } else {
+            return Ok(());
+        };
+        Err(WechatError::Decode {
+            path: String::new(),
+            snippet: String::new(),
+            message: problem,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callback::{body_to_value, CallbackEvent, CallbackMessage};
+
+    #[test]
+    fn test_delivery_status_update_from_xml_push_verifies_sign() {
+        let merchant = DeliveryMerchant::new("test_shop_id", "test_app_secrect");
+        let sign = merchant.delivery_sign("order-1");
+        let xml = format!(
+            "<xml><ToUserName>gh_abc</ToUserName><FromUserName>oSystem</FromUserName>\
+             <CreateTime>1546924844</CreateTime><MsgType>event</MsgType>\
+             <Event>update_waybill_status</Event><shopid>test_shop_id</shopid>\
+             <shop_order_id>order-1</shop_order_id><shop_no>store-1</shop_no>\
+             <waybill_id>wb-1</waybill_id><action_time>1546924844</action_time>\
+             <order_status>202</order_status><action_msg>picked up</action_msg>\
+             <delivery_sign>{sign}</delivery_sign>\
+             <agent><name>rider</name><phone>13800000000</phone></agent></xml>"
+        );
+        let message = CallbackMessage::from_value(body_to_value(&xml).unwrap()).unwrap();
+        let CallbackEvent::DeliveryStatusUpdate(update) = message.kind else {
+            panic!("expected delivery status update");
+        };
+        assert_eq!(update.order_status, 202);
+        assert_eq!(update.state(), Some(DeliveryOrderState::PickedUp));
+        assert_eq!(update.agent.as_ref().unwrap().phone, "13800000000");
+        update.verify(&merchant).unwrap();
+
+        let forged = DeliveryMerchant::new("test_shop_id", "other");
+        assert!(matches!(
+            update.verify(&forged),
+            Err(WechatError::Signature(_))
+        ));
+        assert!(matches!(
+            update.verify(&DeliveryMerchant::new("other_shop", "test_app_secrect")),
+            Err(WechatError::Signature(_))
+        ));
+    }
+
+    #[test]
+    fn test_express_path_update_from_json_push() {
+        let body = r#"{"ToUserName":"gh_abc","FromUserName":"oSystem","CreateTime":1546924844,
+            "MsgType":"event","Event":"add_express_path","DeliveryID":"SF",
+            "WayBillId":"SF123","OrderId":"order-1","Version":3,"Count":2,
+            "Actions":[{"ActionTime":1546924840,"ActionType":100001,"ActionMsg":"picked up"},
+                       {"ActionTime":1546924844,"ActionType":200001,"ActionMsg":"in transit"}]}"#;
+        let message = CallbackMessage::from_value(body_to_value(body).unwrap()).unwrap();
+        let CallbackEvent::ExpressPathUpdate(update) = message.kind else {
+            panic!("expected express path update");
+        };
+        assert_eq!(update.waybill_id, "SF123");
+        assert_eq!(update.version, 3);
+        assert_eq!(update.latest().unwrap().action_type, 200001);
+        update.validate().unwrap();
+
+        let mut truncated = update.clone();
+        truncated.actions.pop();
+        assert!(matches!(
+            truncated.validate(),
+            Err(WechatError::Decode { .. })
+        ));
+    }
+}
@@ -0,0 +1,273 @@
+//! `media_check_async` results delivered through the message server
+//!
+//! [`SecurityApi::media_check_async`](crate::api::security::SecurityApi::media_check_async)
+//! only returns a `trace_id`; the verdict arrives later as a
+//! `wxa_media_check` push. [`MediaCheckDispatcher`] routes each pushed
+//! [`MediaCheckResult`] to the handler registered for its `trace_id`.
+//!
+//! ```rust,ignore
+//! let dispatcher = MediaCheckDispatcher::new();
+//!
+//! let trace_id = wechat.media_check_async(url, 2, openid, Scene::Profile).await?.trace_id;
+//! let verdict = dispatcher.wait(&trace_id);
+//!
+//! // in the message server handler
+//! let message = handler.parse(&query, &body)?;
+//! dispatcher.dispatch_message(&message);
+//!
+//! if verdict.await.is_some_and(|result| result.is_risky()) { /* take down */ }
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tokio::sync::oneshot;
+
+use super::message::{int, string, CallbackEvent, CallbackMessage};
+use crate::api::security::{MsgSecCheckDetail, MsgSecCheckResult};
+use crate::utils::lock;
+
+/// Verdict of an asynchronous media check (`wxa_media_check` event)
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MediaCheckResult {
+    pub appid: String,
+    /// `trace_id` returned by `media_check_async`
+    pub trace_id: String,
+    /// API version of the check, 2 for the current interface
+    pub version: i32,
+    /// Overall suggestion and main label
+    pub result: MsgSecCheckResult,
+    /// Per-strategy results
+    pub detail: Vec<MsgSecCheckDetail>,
+    /// 0 when the check ran
+    pub errcode: i32,
+    pub errmsg: String,
+}
+
+impl MediaCheckResult {
+    /// Read the result fields of a `wxa_media_check` push
+    ///
+    /// Numbers may arrive as strings in XML mode; missing fields default.
+    pub fn from_object(object: &Map<String, Value>) -> Self {
+        let result = object.get("result").and_then(Value::as_object);
+        let detail = match object.get("detail") {
+            Some(Value::Array(items)) => items.iter().filter_map(Value::as_object).collect(),
+            Some(Value::Object(item)) => vec![item],
+            _ => Vec::new(),
+        };
+        Self {
+            appid: string(object, "appid"),
+            trace_id: string(object, "trace_id"),
+            version: int(object, "version") as i32,
+            result: MsgSecCheckResult {
+                suggest: result.map(|r| string(r, "suggest")).unwrap_or_default(),
+                label: result.map_or(0, |r| int(r, "label")) as i32,
+            },
+            detail: detail
+                .into_iter()
+                .map(|item| MsgSecCheckDetail {
+                    strategy: string(item, "strategy"),
+                    errcode: int(item, "errcode") as i32,
+                    suggest: string(item, "suggest"),
+                    label: int(item, "label") as i32,
+                    keyword: string(item, "keyword"),
+                    prob: int(item, "prob") as i32,
+                })
+                .collect(),
+            errcode: int(object, "errcode") as i32,
+            errmsg: string(object, "errmsg"),
+        }
+    }
+
+    /// Returns true when WeChat found nothing to flag.
+    pub fn is_pass(&self) -> bool {
+        self.errcode == 0 && self.result.suggest == "pass"
+    }
+
+    /// Returns true when the media should be taken down.
+    pub fn is_risky(&self) -> bool {
+        self.result.suggest == "risky"
+    }
+
+    /// Returns true when WeChat recommends a manual review.
+    pub fn needs_review(&self) -> bool {
+        self.result.suggest == "review"
+    }
+}
+
+type TraceHandler = Box<dyn FnOnce(MediaCheckResult) + Send>;
+type FallbackHandler = Arc<dyn Fn(MediaCheckResult) + Send + Sync>;
+
+/// Routes [`MediaCheckResult`]s to handlers registered by `trace_id`
+///
+/// Each handler runs once: it is removed when its result is dispatched.
+/// Results without a handler, including repeated pushes WeChat sends when
+/// the first was not acknowledged, go to the [`fallback`](Self::fallback).
+/// Clones share their registrations.
+#[derive(Clone, Default)]
+pub struct MediaCheckDispatcher {
+    handlers: Arc<Mutex<HashMap<String, TraceHandler>>>,
+    fallback: Option<FallbackHandler>,
+}
+
+impl std::fmt::Debug for MediaCheckDispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MediaCheckDispatcher")
+            .field("pending", &self.pending())
+            .field("fallback", &self.fallback.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+impl MediaCheckDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle results that no `trace_id` handler is waiting for
+    pub fn fallback<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(MediaCheckResult) + Send + Sync + 'static,
+    {
+        self.fallback = Some(Arc::new(handler));
+        self
+    }
+
+    /// Run `handler` when the result for `trace_id` arrives
+    ///
+    /// Replaces a handler already registered for the same `trace_id`.
+    pub fn on_trace<F>(&self, trace_id: impl Into<String>, handler: F)
+    where
+        F: FnOnce(MediaCheckResult) + Send + 'static,
+    {
+        lock(&self.handlers).insert(trace_id.into(), Box::new(handler));
+    }
+
+    /// Wait for the result for `trace_id`
+    ///
+    /// Resolves to `None` if the registration is dropped with
+    /// [`cancel`](Self::cancel) or replaced before a result arrives.
+    pub fn wait(
+        &self,
+        trace_id: impl Into<String>,
+    ) -> impl Future<Output = Option<MediaCheckResult>> + Send + 'static {
+        let (sender, receiver) = oneshot::channel();
+        self.on_trace(trace_id, move |result| {
+            let _ = sender.send(result);
+        });
+        async move { receiver.await.ok() }
+    }
+
+    /// Drop the handler for `trace_id`; returns true if one was registered.
+    pub fn cancel(&self, trace_id: &str) -> bool {
+        lock(&self.handlers).remove(trace_id).is_some()
+    }
+
+    /// Number of `trace_id`s still waiting for a result
+    pub fn pending(&self) -> usize {
+        lock(&self.handlers).len()
+    }
+
+    /// Deliver `result` to its handler, or to the fallback
+    ///
+    /// Returns true when a `trace_id` handler received it.
+    pub fn dispatch(&self, result: MediaCheckResult) -> bool {
+        let handler = lock(&self.handlers).remove(&result.trace_id);
+        match handler {
+            Some(handler) => {
+                handler(result);
+                true
+            }
+            None => {
+                if let Some(fallback) = &self.fallback {
+                    fallback(result);
+                }
+                false
+            }
+        }
+    }
+
+    /// Dispatch `message` if it is a `wxa_media_check` push
+    ///
+    /// Returns true when a `trace_id` handler received it; other messages
+    /// are ignored.
+    pub fn dispatch_message(&self, message: &CallbackMessage) -> bool {
+        match &message.kind {
+            CallbackEvent::MediaCheck(result) => self.dispatch(result.clone()),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::callback::body_to_value;
+
+    fn push(trace_id: &str, suggest: &str) -> CallbackMessage {
+        let xml = format!(
+            "<xml><ToUserName>gh_abc</ToUserName><FromUserName>oSystem</FromUserName>\
+             <CreateTime>1626959646</CreateTime><MsgType>event</MsgType>\
+             <Event>wxa_media_check</Event><appid>wx8f16a5e5a49ab2a6</appid>\
+             <trace_id>{trace_id}</trace_id><version>2</version>\
+             <detail><strategy>content_model</strategy><errcode>0</errcode>\
+             <suggest>{suggest}</suggest><label>20002</label><prob>90</prob></detail>\
+             <errcode>0</errcode><errmsg>ok</errmsg>\
+             <result><suggest>{suggest}</suggest><label>20002</label></result></xml>"
+        );
+        CallbackMessage::from_value(body_to_value(&xml).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_media_check_result_from_xml_push() {
+        let CallbackEvent::MediaCheck(result) = push("trace-1", "risky").kind else {
+            panic!("expected media check event");
+        };
+        assert_eq!(result.trace_id, "trace-1");
+        assert_eq!(result.version, 2);
+        assert_eq!(result.result.label, 20002);
+        assert_eq!(result.detail.len(), 1);
+        assert_eq!(result.detail[0].prob, 90);
+        assert!(result.is_risky());
+        assert!(!result.is_pass());
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_routes_by_trace_id_once() {
+        let unclaimed = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&unclaimed);
+        let dispatcher = MediaCheckDispatcher::new().fallback(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let verdict = dispatcher.wait("trace-1");
+        let routed = Arc::new(Mutex::new(None));
+        let slot = Arc::clone(&routed);
+        dispatcher.on_trace("trace-2", move |result| {
+            *slot.lock().unwrap() = Some(result.result.suggest);
+        });
+        assert_eq!(dispatcher.pending(), 2);
+
+        assert!(dispatcher
+            .clone()
+            .dispatch_message(&push("trace-2", "pass")));
+        assert!(dispatcher.dispatch_message(&push("trace-1", "risky")));
+        assert!(!dispatcher.dispatch_message(&push("trace-1", "risky")));
+        assert!(!dispatcher.dispatch_message(&push("trace-3", "review")));
+
+        assert!(verdict.await.unwrap().is_risky());
+        assert_eq!(routed.lock().unwrap().as_deref(), Some("pass"));
+        assert_eq!(unclaimed.load(Ordering::SeqCst), 2);
+        assert_eq!(dispatcher.pending(), 0);
+
+        let abandoned = dispatcher.wait("trace-4");
+        assert!(dispatcher.cancel("trace-4"));
+        assert!(abandoned.await.is_none());
+    }
+}
@@ -0,0 +1,409 @@
+//! Typed push messages
+//!
+//! Both data formats the message server can be configured with are
+//! accepted: XML bodies are converted to the same JSON shape as JSON bodies
+//! (element text becomes a string, repeated elements an array), and numeric
+//! fields are read from either numbers or strings.
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+
+use super::delivery::{DeliveryStatusUpdateEvent, ExpressPathUpdateEvent};
+use super::media_check::MediaCheckResult;
+use crate::error::WechatError;
+
+/// A push message from WeChat with its common header fields
+#[derive(Debug, Clone)]
+pub struct CallbackMessage {
+    /// Original ID of the mini program (`gh_...`)
+    pub to_user_name: String,
+    /// Sender: the user's OpenID, or a system account for events
+    pub from_user_name: String,
+    /// Unix timestamp of the message
+    pub create_time: i64,
+    /// `text`, `image`, `miniprogrampage`, `event`, ...
+    pub msg_type: String,
+    /// Event name when `msg_type` is `event`
+    pub event: Option<String>,
+    /// Typed payload
+    pub kind: CallbackEvent,
+    /// The decrypted message as JSON, for fields not modelled here
+    pub raw: Value,
+}
+
+/// Payload of a [`CallbackMessage`]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallbackEvent {
+    /// Customer service text message
+    Text { msg_id: i64, content: String },
+    /// Customer service image message
+    Image {
+        msg_id: i64,
+        pic_url: String,
+        media_id: String,
+    },
+    /// Customer service mini program card
+    MiniProgramPage {
+        msg_id: i64,
+        title: String,
+        app_id: String,
+        page_path: String,
+        thumb_url: String,
+        thumb_media_id: String,
+    },
+    /// User opened a customer service session (`user_enter_tempsession`)
+    UserEnterTempSession { session_from: String },
+    /// Result of `media_check_async` (`wxa_media_check`)
+    MediaCheck(MediaCheckResult),
+    /// User answered `wx.requestSubscribeMessage` (`subscribe_msg_popup_event`)
+    SubscribePopup(Vec<SubscribeStatusItem>),
+    /// User changed subscriptions in settings (`subscribe_msg_change_event`)
+    SubscribeChange(Vec<SubscribeStatusItem>),
+    /// Delivery report for a sent subscribe message (`subscribe_msg_sent_event`)
+    SubscribeSent(Vec<SubscribeSentItem>),
+    /// Instant delivery order status change (`update_waybill_status`)
+    DeliveryStatusUpdate(DeliveryStatusUpdateEvent),
+    /// New tracking events of an express waybill (`add_express_path`)
+    ExpressPathUpdate(ExpressPathUpdateEvent),
+    /// Message or event type not modelled here; see [`CallbackMessage::raw`]
+    Other,
+}
+
+/// A template's status in a subscribe popup or settings change event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscribeStatusItem {
+    pub template_id: String,
+    /// `accept`, `reject`, `ban` or `acceptWithAudio`
+    pub subscribe_status: String,
+    /// 0 for a popup in the mini program, 1 for the payment result page
+    pub popup_scene: Option<i64>,
+}
+
+impl SubscribeStatusItem {
+    /// Returns true when the user allowed the template.
+    pub fn is_accepted(&self) -> bool {
+        self.subscribe_status.starts_with("accept")
+    }
+}
+
+/// Delivery status of one sent subscribe message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscribeSentItem {
+    pub template_id: String,
+    pub msg_id: String,
+    /// 0 on success
+    pub error_code: i64,
+    pub error_status: String,
+}
+
+impl CallbackMessage {
+    /// Build a typed message from its JSON form
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Decode`] when the body is not an object or
+    /// lacks `MsgType`.
+    pub fn from_value(raw: Value) -> Result<Self, WechatError> {
+        let Some(object) = raw.as_object() else {
+            return Err(decode_error("callback body is not an object"));
+        };
+        let msg_type = string(object, "MsgType");
+        if msg_type.is_empty() {
+            return Err(decode_error("callback body has no MsgType"));
+        }
+        let event = Some(string(object, "Event")).filter(|event| !event.is_empty());
+
+        let kind = match (msg_type.as_str(), event.as_deref()) {
+            ("text", _) => CallbackEvent::Text {
+                msg_id: int(object, "MsgId"),
+                content: string(object, "Content"),
+            },
+            ("image", _) => CallbackEvent::Image {
+                msg_id: int(object, "MsgId"),
+                pic_url: string(object, "PicUrl"),
+                media_id: string(object, "MediaId"),
+            },
+            ("miniprogrampage", _) => CallbackEvent::MiniProgramPage {
+                msg_id: int(object, "MsgId"),
+                title: string(object, "Title"),
+                app_id: string(object, "AppId"),
+                page_path: string(object, "PagePath"),
+                thumb_url: string(object, "ThumbUrl"),
+                thumb_media_id: string(object, "ThumbMediaId"),
+            },
+            ("event", Some("user_enter_tempsession")) => CallbackEvent::UserEnterTempSession {
+                session_from: string(object, "SessionFrom"),
+            },
+            ("event", Some("wxa_media_check")) => {
+                CallbackEvent::MediaCheck(MediaCheckResult::from_object(object))
+            }
+            ("event", Some("subscribe_msg_popup_event")) => CallbackEvent::SubscribePopup(
+                list(object, "SubscribeMsgPopupEvent")
+                    .map(status_item)
+                    .collect(),
+            ),
+            ("event", Some("subscribe_msg_change_event")) => CallbackEvent::SubscribeChange(
+                list(object, "SubscribeMsgChangeEvent")
+                    .map(status_item)
+                    .collect(),
+            ),
+            ("event", Some("subscribe_msg_sent_event")) => CallbackEvent::SubscribeSent(
+                list(object, "SubscribeMsgSentEvent")
+                    .map(|item| SubscribeSentItem {
+                        template_id: string(item, "TemplateId"),
+                        msg_id: string(item, "MsgID"),
+                        error_code: int(item, "ErrorCode"),
+                        error_status: string(item, "ErrorStatus"),
+                    })
+                    .collect(),
+            ),
+            ("event", Some("update_waybill_status")) => {
+                CallbackEvent::DeliveryStatusUpdate(DeliveryStatusUpdateEvent::from_object(object))
+            }
+            ("event", Some("add_express_path")) => {
+                CallbackEvent::ExpressPathUpdate(ExpressPathUpdateEvent::from_object(object))
+            }
+            _ => CallbackEvent::Other,
+        };
+
+        Ok(Self {
+            to_user_name: string(object, "ToUserName"),
+            from_user_name: string(object, "FromUserName"),
+            create_time: int(object, "CreateTime"),
+            msg_type,
+            event,
+            kind,
+            raw,
+        })
+    }
+}
+
+/// Parse a push body in either format into JSON
+///
+/// # Errors
+/// Returns [`WechatError::Decode`] on malformed XML or JSON.
+pub fn body_to_value(body: &str) -> Result<Value, WechatError> {
+    let body = body.trim_start_matches('\u{feff}').trim();
+    if body.starts_with('<') {
+        xml_to_value(body)
+    } else {
+        serde_json::from_str(body).map_err(|e| decode_error(&e.to_string()))
+    }
+}
+
+/// Convert a flat-ish WeChat XML document into a JSON object.
+///
+/// The root element (`<xml>`) becomes the object; attributes are ignored.
+fn xml_to_value(xml: &str) -> Result<Value, WechatError> {
+    struct Frame {
+        name: String,
+        children: Map<String, Value>,
+        text: String,
+    }
+
+    fn close(frame: Frame) -> Value {
+        if frame.children.is_empty() {
+            Value::String(frame.text)
+        } else {
+            Value::Object(frame.children)
+        }
+    }
+
+    fn attach(parent: &mut Map<String, Value>, name: String, value: Value) {
+        match parent.get_mut(&name) {
+            Some(Value::Array(items)) => items.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                parent.insert(name, value);
+            }
+        }
+    }
+
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut stack: Vec<Frame> = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| decode_error(&format!("invalid XML: {}", e)))?;
+        match event {
+            Event::Start(start) => stack.push(Frame {
+                name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+                children: Map::new(),
+                text: String::new(),
+            }),
+            Event::Empty(empty) => {
+                let name = String::from_utf8_lossy(empty.name().as_ref()).into_owned();
+                if let Some(parent) = stack.last_mut() {
+                    attach(&mut parent.children, name, Value::String(String::new()));
+                }
+            }
+            Event::Text(text) => {
+                if let Some(frame) = stack.last_mut() {
+                    let text = text
+                        .unescape()
+                        .map_err(|e| decode_error(&format!("invalid XML: {}", e)))?;
+                    frame.text.push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(frame) = stack.last_mut() {
+                    frame
+                        .text
+                        .push_str(&String::from_utf8_lossy(&data.into_inner()));
+                }
+            }
+            Event::End(_) => {
+                let frame = stack
+                    .pop()
+                    .ok_or_else(|| decode_error("invalid XML: unbalanced end tag"))?;
+                match stack.last_mut() {
+                    Some(parent) => {
+                        let name = frame.name.clone();
+                        attach(&mut parent.children, name, close(frame));
+                    }
+                    None => return Ok(Value::Object(frame.children)),
+                }
+            }
+            Event::Eof => return Err(decode_error("invalid XML: missing root element")),
+            _ => {}
+        }
+    }
+}
+
+/// Items of a subscribe event, from the top-level `List` (JSON mode) or
+/// `<Wrapper><List>` (XML mode); a single item may not be wrapped in an array.
+fn list<'a>(
+    object: &'a Map<String, Value>,
+    wrapper: &str,
+) -> impl Iterator<Item = &'a Map<String, Value>> {
+    let list = object
+        .get("List")
+        .or_else(|| object.get(wrapper).and_then(|inner| inner.get("List")));
+    let items: Vec<&Value> = match list {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(item) => vec![item],
+        None => Vec::new(),
+    };
+    items.into_iter().filter_map(Value::as_object)
+}
+
+fn status_item(item: &Map<String, Value>) -> SubscribeStatusItem {
+    SubscribeStatusItem {
+        template_id: string(item, "TemplateId"),
+        subscribe_status: string(item, "SubscribeStatusString"),
+        popup_scene: item.get("PopupScene").and_then(as_int),
+    }
+}
+
+pub(super) fn string(object: &Map<String, Value>, key: &str) -> String {
+    match object.get(key) {
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Number(value)) => value.to_string(),
+        _ => String::new(),
+    }
+}
+
+pub(super) fn int(object: &Map<String, Value>, key: &str) -> i64 {
+    object.get(key).and_then(as_int).unwrap_or_default()
+}
+
+fn as_int(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(number) => number.as_i64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn decode_error(message: &str) -> WechatError {
+    WechatError::Decode {
+        path: String::new(),
+        snippet: String::new(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xml_text_message() {
+        let xml = "<xml><ToUserName><![CDATA[gh_abc]]></ToUserName>\
+            <FromUserName><![CDATA[oUser]]></FromUserName>\
+            <CreateTime>1482048670</CreateTime><MsgType><![CDATA[text]]></MsgType>\
+            <Content><![CDATA[this is a test &amp; more]]></Content><MsgId>1234567890123456</MsgId></xml>";
+        let message = CallbackMessage::from_value(body_to_value(xml).unwrap()).unwrap();
+        assert_eq!(message.to_user_name, "gh_abc");
+        assert_eq!(message.from_user_name, "oUser");
+        assert_eq!(message.create_time, 1482048670);
+        assert_eq!(
+            message.kind,
+            CallbackEvent::Text {
+                msg_id: 1234567890123456,
+                content: "this is a test &amp; more".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_subscribe_popup_in_both_formats() {
+        let xml = "<xml><ToUserName>gh_abc</ToUserName><FromUserName>oUser</FromUserName>\
+            <CreateTime>1620963428</CreateTime><MsgType>event</MsgType>\
+            <Event>subscribe_msg_popup_event</Event><SubscribeMsgPopupEvent>\
+            <List><TemplateId>tpl1</TemplateId><SubscribeStatusString>accept</SubscribeStatusString><PopupScene>2</PopupScene></List>\
+            <List><TemplateId>tpl2</TemplateId><SubscribeStatusString>reject</SubscribeStatusString><PopupScene>2</PopupScene></List>\
+            </SubscribeMsgPopupEvent></xml>";
+        let json = r#"{"ToUserName":"gh_abc","FromUserName":"oUser","CreateTime":"1620963428",
+            "MsgType":"event","Event":"subscribe_msg_popup_event",
+            "List":{"TemplateId":"tpl1","SubscribeStatusString":"accept","PopupScene":"2"}}"#;
+
+        let from_xml = CallbackMessage::from_value(body_to_value(xml).unwrap()).unwrap();
+        let CallbackEvent::SubscribePopup(items) = from_xml.kind else {
+            panic!("expected popup event");
+        };
+        assert_eq!(items.len(), 2);
+        assert!(items[0].is_accepted());
+        assert!(!items[1].is_accepted());
+        assert_eq!(items[0].popup_scene, Some(2));
+
+        let from_json = CallbackMessage::from_value(body_to_value(json).unwrap()).unwrap();
+        assert_eq!(from_json.create_time, 1620963428);
+        assert_eq!(
+            from_json.kind,
+            CallbackEvent::SubscribePopup(vec![items[0].clone()])
+        );
+    }
+
+    #[test]
+    fn test_media_check_and_unknown_events() {
+        let json = r#"{"ToUserName":"gh_abc","FromUserName":"oSystem","CreateTime":1626959646,
+            "MsgType":"event","Event":"wxa_media_check","appid":"wx8f16a5e5a49ab2a6",
+            "trace_id":"60f96f1d-3845297a-1976a3ac","version":2,"errcode":0,"errmsg":"ok",
+            "result":{"suggest":"risky","label":20001}}"#;
+        let message = CallbackMessage::from_value(body_to_value(json).unwrap()).unwrap();
+        let CallbackEvent::MediaCheck(result) = message.kind else {
+            panic!("expected media check event");
+        };
+        assert_eq!(result.trace_id, "60f96f1d-3845297a-1976a3ac");
+        assert_eq!(result.result.suggest, "risky");
+        assert_eq!(result.result.label, 20001);
+
+        let json = r#"{"MsgType":"event","Event":"something_new","Extra":1}"#;
+        let message = CallbackMessage::from_value(body_to_value(json).unwrap()).unwrap();
+        assert_eq!(message.kind, CallbackEvent::Other);
+        assert_eq!(message.raw["Extra"], 1);
+
+        assert!(matches!(
+            body_to_value("<xml><a></xml>"),
+            Err(WechatError::Decode { .. })
+        ));
+        assert!(CallbackMessage::from_value(serde_json::json!({"a": 1})).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! Inbound push messages from WeChat (message server)
+//!
+//! The SDK's other modules call WeChat; this one handles the requests WeChat
+//! sends to the message server URL configured in the mini program console:
+//!
+//! - the `GET` handshake, answered with `echostr` after checking `signature`;
+//! - `POST` pushes in plaintext, compatible or encrypted (safe) mode, with
+//!   either data format (XML or JSON), parsed into [`CallbackMessage`];
+//! - routing `media_check_async` verdicts by `trace_id` with
+//!   [`MediaCheckDispatcher`];
+//! - instant delivery and express status pushes, with `delivery_sign`
+//!   verification for delivery orders.
+//!
+//! The module is framework agnostic: pass the query string and body from any
+//! HTTP server and reply `success` (or an empty body) once a push is handled.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use wechat_mp_sdk::callback::{CallbackEvent, CallbackHandler, CallbackQuery};
+//!
+//! let handler = CallbackHandler::new("token").with_aes_key(encoding_aes_key, "wx1234567890abcdef")?;
+//!
+//! // GET: URL verification
+//! let echostr = handler.handshake(&CallbackQuery::parse(query_string))?;
+//!
+//! // POST: a push
+//! let message = handler.parse(&CallbackQuery::parse(query_string), &body)?;
+//! if let CallbackEvent::Text { content, .. } = &message.kind {
+//!     println!("{} says {}", message.from_user_name, content);
+//! }
+//! ```
+
+pub mod crypto;
+pub mod delivery;
+pub mod media_check;
+pub mod message;
+
+use percent_encoding::percent_decode_str;
+
+use crate::error::WechatError;
+
+pub use crypto::{callback_signature, delivery_signature, CallbackCipher};
+pub use delivery::{DeliveryAgent, DeliveryStatusUpdateEvent, ExpressPathUpdateEvent};
+pub use media_check::{MediaCheckDispatcher, MediaCheckResult};
+pub use message::{
+    body_to_value, CallbackEvent, CallbackMessage, SubscribeSentItem, SubscribeStatusItem,
+};
+
+use crypto::signature_matches;
+
+/// Query parameters WeChat appends to message server requests
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallbackQuery {
+    pub signature: String,
+    pub timestamp: String,
+    pub nonce: String,
+    /// Present on the handshake request only
+    pub echostr: Option<String>,
+    /// `aes` in encrypted and compatible mode
+    pub encrypt_type: Option<String>,
+    /// Signature over the `Encrypt` field in encrypted and compatible mode
+    pub msg_signature: Option<String>,
+}
+
+impl CallbackQuery {
+    /// Read the parameters from a raw query string (with or without `?`).
+    ///
+    /// Unknown parameters such as `openid` are ignored.
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = Self::default();
+        for pair in query.trim_start_matches('?').split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode_str(&value.replace('+', " "))
+                .decode_utf8_lossy()
+                .into_owned();
+            match key {
+                "signature" => parsed.signature = value,
+                "timestamp" => parsed.timestamp = value,
+                "nonce" => parsed.nonce = value,
+                "echostr" => parsed.echostr = Some(value),
+                "encrypt_type" => parsed.encrypt_type = Some(value),
+                "msg_signature" => parsed.msg_signature = Some(value),
+                _ => {}
+            }
+        }
+        parsed
+    }
+}
+
+/// Verifies and parses requests sent to the message server
+///
+/// Configure it with the console's Token, and for encrypted or compatible
+/// mode with the EncodingAESKey via [`with_aes_key`](Self::with_aes_key).
+#[derive(Debug, Clone)]
+pub struct CallbackHandler {
+    token: String,
+    cipher: Option<CallbackCipher>,
+}
+
+impl CallbackHandler {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            cipher: None,
+        }
+    }
+
+    /// Decrypt encrypted-mode pushes with `encoding_aes_key`; decrypted
+    /// messages must carry `appid`.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] for a malformed key.
+    pub fn with_aes_key(
+        mut self,
+        encoding_aes_key: &str,
+        appid: impl Into<String>,
+    ) -> Result<Self, WechatError> {
+        self.cipher = Some(CallbackCipher::new(encoding_aes_key, appid)?);
+        Ok(self)
+    }
+
+    /// Cipher configured with [`with_aes_key`](Self::with_aes_key), e.g. to
+    /// encrypt a passive reply
+    pub fn cipher(&self) -> Option<&CallbackCipher> {
+        self.cipher.as_ref()
+    }
+
+    /// Check `signature` against the token, timestamp and nonce.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Signature`] on a mismatch.
+    pub fn verify(&self, query: &CallbackQuery) -> Result<(), WechatError> {
+        let expected = callback_signature(&[&self.token, &query.timestamp, &query.nonce]);
+        if signature_matches(&expected, &query.signature) {
+            Ok(())
+        } else {
+            Err(WechatError::Signature(
+                "Callback signature mismatch".to_string(),
+            ))
+        }
+    }
+
+    /// Answer the URL verification request: returns the `echostr` to send
+    /// back as the response body.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Signature`] when the signature does not match
+    /// or `echostr` is missing.
+    pub fn handshake(&self, query: &CallbackQuery) -> Result<String, WechatError> {
+        self.verify(query)?;
+        query
+            .echostr
+            .clone()
+            .ok_or_else(|| WechatError::Signature("Callback echostr missing".to_string()))
+    }
+
+    /// Verify a push and parse its body into a [`CallbackMessage`]
+    ///
+    /// Bodies with an `Encrypt` field are checked against `msg_signature`
+    /// and decrypted; the plaintext fields of compatible mode are ignored.
+    ///
+    /// # Errors
+    /// - [`WechatError::Signature`] when a signature or the AppID does not match
+    /// - [`WechatError::Config`] for an encrypted body without
+    ///   [`with_aes_key`](Self::with_aes_key)
+    /// - [`WechatError::Crypto`] when decryption fails
+    /// - [`WechatError::Decode`] for a malformed body
+    pub fn parse(&self, query: &CallbackQuery, body: &str) -> Result<CallbackMessage, WechatError> {
+        self.verify(query)?;
+        let outer = body_to_value(body)?;
+        let Some(encrypted) = outer.get("Encrypt").and_then(|value| value.as_str()) else {
+            return CallbackMessage::from_value(outer);
+        };
+
+        let cipher = self.cipher.as_ref().ok_or_else(|| {
+            WechatError::Config("encrypted callback received without an EncodingAESKey".to_string())
+        })?;
+        let expected =
+            callback_signature(&[&self.token, &query.timestamp, &query.nonce, encrypted]);
+        let actual = query.msg_signature.as_deref().unwrap_or_default();
+        if !signature_matches(&expected, actual) {
+            return Err(WechatError::Signature(
+                "Callback msg_signature mismatch".to_string(),
+            ));
+        }
+
+        let inner = cipher.decrypt(encrypted)?;
+        CallbackMessage::from_value(body_to_value(&inner)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "token";
+    const AES_KEY: &str = "abcdefghijklmnopqrstuvwxyz0123456789ABCDEFG";
+    const APPID: &str = "wx1234567890abcdef";
+
+    fn signed_query(extra: &str) -> CallbackQuery {
+        let signature = callback_signature(&[TOKEN, "1409304348", "xxxxxx"]);
+        CallbackQuery::parse(&format!(
+            "?signature={signature}&timestamp=1409304348&nonce=xxxxxx&openid=oUser{extra}"
+        ))
+    }
+
+    #[test]
+    fn test_handshake_returns_echostr_for_valid_signature() {
+        let handler = CallbackHandler::new(TOKEN);
+        let query = signed_query("&echostr=hello%20world");
+        assert_eq!(handler.handshake(&query).unwrap(), "hello world");
+
+        let mut forged = query.clone();
+        forged.nonce = "other".to_string();
+        assert!(matches!(
+            handler.handshake(&forged),
+            Err(WechatError::Signature(_))
+        ));
+        assert!(matches!(
+            handler.handshake(&signed_query("")),
+            Err(WechatError::Signature(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_plaintext_json_push() {
+        let handler = CallbackHandler::new(TOKEN);
+        let body = r#"{"ToUserName":"gh_abc","FromUserName":"oUser","CreateTime":1482048670,
+            "MsgType":"event","Event":"user_enter_tempsession","SessionFrom":"order"}"#;
+        let message = handler.parse(&signed_query(""), body).unwrap();
+        assert_eq!(message.event.as_deref(), Some("user_enter_tempsession"));
+        assert_eq!(
+            message.kind,
+            CallbackEvent::UserEnterTempSession {
+                session_from: "order".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_encrypted_xml_push() {
+        let handler = CallbackHandler::new(TOKEN)
+            .with_aes_key(AES_KEY, APPID)
+            .unwrap();
+        let inner = "<xml><ToUserName><![CDATA[gh_abc]]></ToUserName>\
+            <FromUserName><![CDATA[oUser]]></FromUserName><CreateTime>1482048670</CreateTime>\
+            <MsgType><![CDATA[image]]></MsgType><PicUrl><![CDATA[https://example.com/a.png]]></PicUrl>\
+            <MediaId><![CDATA[media]]></MediaId><MsgId>42</MsgId></xml>";
+        let encrypted = handler.cipher().unwrap().encrypt(inner).unwrap();
+        let body = format!(
+            "<xml><ToUserName><![CDATA[gh_abc]]></ToUserName><Encrypt><![CDATA[{encrypted}]]></Encrypt></xml>"
+        );
+        let msg_signature = callback_signature(&[TOKEN, "1409304348", "xxxxxx", &encrypted]);
+        let query = signed_query(&format!("&encrypt_type=aes&msg_signature={msg_signature}"));
+
+        let message = handler.parse(&query, &body).unwrap();
+        assert_eq!(
+            message.kind,
+            CallbackEvent::Image {
+                msg_id: 42,
+                pic_url: "https://example.com/a.png".to_string(),
+                media_id: "media".to_string(),
+            }
+        );
+
+        let tampered = signed_query("&encrypt_type=aes&msg_signature=0000");
+        assert!(matches!(
+            handler.parse(&tampered, &body),
+            Err(WechatError::Signature(_))
+        ));
+        assert!(matches!(
+            CallbackHandler::new(TOKEN).parse(&query, &body),
+            Err(WechatError::Config(_))
+        ));
+    }
+}
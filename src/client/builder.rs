@@ -1,33 +1,69 @@
 use std::future::Future;
-use std::pin::Pin;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
 use reqwest::{Request as ReqwestRequest, Response as ReqwestResponse};
 use tower::{Layer, Service};
 
+use super::call_report::{CallReport, CallReportHook};
+use super::trace_context::{TraceContext, TraceContextProvider};
+#[cfg(feature = "gzip")]
+use super::Compression;
+#[cfg(feature = "charset")]
+use super::ResponseCharset;
+use super::{ErrorSniffing, GatewayErrcodes};
+use crate::api::endpoint_inventory::{ApiVersion, TimeoutClass};
+use crate::api::qrcode::EnvVersion;
+use crate::api::subscribe_quota::{SubscribeQuota, SubscribeQuotaPolicy, SubscribeQuotaStore};
 use crate::api::WechatContext;
 use crate::error::WechatError;
-use crate::token::TokenManager;
-use crate::types::{AppId, AppSecret};
+use crate::limit::RateLimiter;
+use crate::token::{
+    boxed_provider, AccessTokenProvider, FatalAuthHook, TokenManager, TokenRouter, TokenSource,
+    TokenStore, TokenStrategy,
+};
+use crate::types::{AppId, AppIdValidation, AppSecret};
 
 use super::wechat_client::{
     WechatClient, DEFAULT_BASE_URL, DEFAULT_CONNECT_TIMEOUT_SECS, DEFAULT_TIMEOUT_SECS,
 };
 use super::WechatMp;
 
-type MiddlewareFuture =
-    Pin<Box<dyn Future<Output = Result<ReqwestResponse, reqwest::Error>> + Send>>;
-type MiddlewareExecutor = Arc<dyn Fn(ReqwestRequest) -> MiddlewareFuture + Send + Sync>;
-
 #[must_use]
 #[derive(Default)]
 pub struct WechatMpBuilder<M = ()> {
     appid: Option<AppId>,
+    appid_validation: Option<AppIdValidation>,
     secret: Option<AppSecret>,
     base_url: Option<String>,
+    allow_insecure_base_url: bool,
     timeout: Option<Duration>,
     connect_timeout: Option<Duration>,
+    class_timeouts: Vec<(TimeoutClass, Duration)>,
+    api_versions: Vec<(String, ApiVersion)>,
+    call_report_hook: Option<CallReportHook>,
+    #[cfg(feature = "gzip")]
+    compression: Option<Compression>,
+    #[cfg(feature = "charset")]
+    response_charset: Option<ResponseCharset>,
+    error_sniffing: Option<ErrorSniffing>,
+    gateway_errcodes: Option<GatewayErrcodes>,
+    access_token_provider: Option<AccessTokenProvider>,
+    token_sources: Vec<TokenSource>,
+    token_strategy: TokenStrategy,
+    token_routes: Vec<(Vec<String>, Vec<TokenSource>)>,
+    token_store: Option<Arc<dyn TokenStore>>,
+    fatal_auth_hook: Option<FatalAuthHook>,
+    subscribe_metadata_ttl: Option<Duration>,
+    subscribe_quota: Option<SubscribeQuota>,
+    env_version: Option<EnvVersion>,
+    rate_limiter: Option<RateLimiter>,
+    network_timing: bool,
+    propagate_trace_context: bool,
+    trace_context_provider: Option<TraceContextProvider>,
+    dns_overrides: Vec<(String, SocketAddr)>,
+    blocking_decode_threshold: Option<usize>,
     middleware: Option<M>,
 }
 
@@ -35,9 +71,37 @@ impl<M> std::fmt::Debug for WechatMpBuilder<M> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("WechatMpBuilder")
             .field("appid", &self.appid)
+            .field("appid_validation", &self.appid_validation)
             .field("base_url", &self.base_url)
+            .field("allow_insecure_base_url", &self.allow_insecure_base_url)
             .field("timeout", &self.timeout)
             .field("connect_timeout", &self.connect_timeout)
+            .field("class_timeouts", &self.class_timeouts)
+            .field("api_versions", &self.api_versions)
+            .field(
+                "call_report_hook",
+                &self.call_report_hook.as_ref().map(|_| ".."),
+            )
+            .field(
+                "access_token_provider",
+                &self.access_token_provider.as_ref().map(|_| ".."),
+            )
+            .field("token_sources", &self.token_sources)
+            .field("token_strategy", &self.token_strategy)
+            .field("token_routes", &self.token_routes)
+            .field("token_store", &self.token_store.as_ref().map(|_| ".."))
+            .field(
+                "fatal_auth_hook",
+                &self.fatal_auth_hook.as_ref().map(|_| ".."),
+            )
+            .field("subscribe_metadata_ttl", &self.subscribe_metadata_ttl)
+            .field("subscribe_quota", &self.subscribe_quota)
+            .field("env_version", &self.env_version)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("network_timing", &self.network_timing)
+            .field("propagate_trace_context", &self.propagate_trace_context)
+            .field("dns_overrides", &self.dns_overrides)
+            .field("blocking_decode_threshold", &self.blocking_decode_threshold)
             .field("middleware", &self.middleware.as_ref().map(|_| ".."))
             .finish_non_exhaustive()
     }
@@ -49,16 +113,34 @@ impl<M> WechatMpBuilder<M> {
         self
     }
 
+    /// Re-check the appid at [`build`](Self::build) with `validation`.
+    ///
+    /// Useful with [`AppId::new_unchecked`] for appids read from
+    /// configuration. Without this, the appid is used as constructed.
+    pub fn appid_validation(mut self, validation: AppIdValidation) -> Self {
+        self.appid_validation = Some(validation);
+        self
+    }
+
     pub fn secret(mut self, secret: AppSecret) -> Self {
         self.secret = Some(secret);
         self
     }
 
+    /// See [`WechatClientBuilder::base_url`](crate::client::WechatClientBuilder::base_url).
     pub fn base_url(mut self, url: impl Into<String>) -> Self {
         self.base_url = Some(url.into());
         self
     }
 
+    /// Accept a plain `http` base URL on non-loopback hosts
+    ///
+    /// See [`WechatClientBuilder::allow_insecure_base_url`](crate::client::WechatClientBuilder::allow_insecure_base_url).
+    pub fn allow_insecure_base_url(mut self, allow: bool) -> Self {
+        self.allow_insecure_base_url = allow;
+        self
+    }
+
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.timeout = Some(timeout);
         self
@@ -69,16 +151,301 @@ impl<M> WechatMpBuilder<M> {
         self
     }
 
+    /// Override the request timeout for endpoints of a [`TimeoutClass`].
+    ///
+    /// See [`WechatClientBuilder::class_timeout`](super::WechatClientBuilder::class_timeout).
+    pub fn class_timeout(mut self, class: TimeoutClass, timeout: Duration) -> Self {
+        self.class_timeouts.push((class, timeout));
+        self
+    }
+
+    /// Pin the inventory endpoint `endpoint_id` to `version`
+    ///
+    /// See [`WechatClientBuilder::api_version`](super::WechatClientBuilder::api_version).
+    pub fn api_version(mut self, endpoint_id: impl Into<String>, version: ApiVersion) -> Self {
+        self.api_versions.push((endpoint_id.into(), version));
+        self
+    }
+
+    /// Register a hook receiving the [`CallReport`] of every request.
+    pub fn on_call_report<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&CallReport) + Send + Sync + 'static,
+    {
+        self.call_report_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set how binary endpoints detect JSON error bodies.
+    ///
+    /// See [`WechatClientBuilder::error_sniffing`](super::WechatClientBuilder::error_sniffing).
+    pub fn error_sniffing(mut self, policy: ErrorSniffing) -> Self {
+        self.error_sniffing = Some(policy);
+        self
+    }
+
+    /// Register errcodes that a gateway in front of WeChat uses for its own
+    /// failures.
+    ///
+    /// See [`WechatClientBuilder::gateway_errcodes`](super::WechatClientBuilder::gateway_errcodes).
+    pub fn gateway_errcodes(mut self, gateway_errcodes: GatewayErrcodes) -> Self {
+        self.gateway_errcodes = Some(gateway_errcodes);
+        self
+    }
+
+    cfg_gzip! {
+        /// Set request and response body compression.
+        ///
+        /// See [`WechatClientBuilder::compression`](super::WechatClientBuilder::compression).
+        pub fn compression(mut self, compression: Compression) -> Self {
+            self.compression = Some(compression);
+            self
+        }
+    }
+
+    cfg_charset! {
+        /// Set how JSON responses that are not UTF-8 are decoded.
+        ///
+        /// See [`WechatClientBuilder::response_charset`](super::WechatClientBuilder::response_charset).
+        pub fn response_charset(mut self, charset: ResponseCharset) -> Self {
+            self.response_charset = Some(charset);
+            self
+        }
+    }
+
+    /// Take access tokens from `provider` instead of fetching them with an
+    /// AppSecret.
+    ///
+    /// For workers that receive tokens from a central token service. With a
+    /// provider set, [`secret`](Self::secret) becomes optional; endpoints
+    /// that authenticate with the secret itself (`login`,
+    /// `get_stable_access_token`, `clear_quota_by_app_secret`) then return
+    /// [`WechatError::Config`]. The provider is called for every request and
+    /// is responsible for its own caching.
+    pub fn access_token_provider<F, Fut>(mut self, provider: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, WechatError>> + Send + 'static,
+    {
+        self.access_token_provider = Some(boxed_provider(provider));
+        self
+    }
+
+    /// Fetch tokens from an ordered chain of sources, failing over to the
+    /// next one (with a warning) when a source keeps erroring.
+    ///
+    /// See [`TokenManagerBuilder::sources`](crate::token::TokenManagerBuilder::sources).
+    /// When every source is a [`TokenSource::Provider`], [`secret`](Self::secret)
+    /// becomes optional.
+    pub fn token_sources(mut self, sources: impl IntoIterator<Item = TokenSource>) -> Self {
+        self.token_sources = sources.into_iter().collect();
+        self
+    }
+
+    /// Fetch tokens from `/cgi-bin/token` or `stable_token`
+    ///
+    /// [`TokenStrategy::Stable`] is recommended when several instances share
+    /// one AppID: fetching a stable token does not revoke the token the
+    /// other instances hold. Applies to every request authenticated by the
+    /// default token manager, including through
+    /// [`AuthMiddleware`](crate::middleware::AuthMiddleware) built on it.
+    /// See [`TokenManagerBuilder::strategy`](crate::token::TokenManagerBuilder::strategy).
+    ///
+    /// ```
+    /// use wechat_mp_sdk::token::TokenStrategy;
+    /// # fn build(builder: wechat_mp_sdk::WechatMpBuilder) -> wechat_mp_sdk::WechatMpBuilder {
+    /// builder.token_strategy(TokenStrategy::Stable {
+    ///     force_refresh_on_invalidate: false,
+    /// })
+    /// # }
+    /// ```
+    pub fn token_strategy(mut self, strategy: TokenStrategy) -> Self {
+        self.token_strategy = strategy;
+        self
+    }
+
+    /// Fetch tokens for endpoints in `categories` from their own token
+    /// manager using `sources`
+    ///
+    /// Categories are those of the
+    /// [endpoint inventory](crate::api::endpoint_inventory::ENDPOINT_INVENTORY);
+    /// everything else keeps using [`token_sources`](Self::token_sources).
+    /// Each call creates one manager, shared by its categories. Since a
+    /// classic token fetch revokes the previous classic token, routes should
+    /// not use [`TokenSource::Classic`] alongside a classic default.
+    ///
+    /// ```
+    /// use wechat_mp_sdk::token::TokenSource;
+    /// # fn build(builder: wechat_mp_sdk::WechatMpBuilder) -> wechat_mp_sdk::WechatMpBuilder {
+    /// builder.token_route(["image-ocr", "live"], [TokenSource::Stable])
+    /// # }
+    /// ```
+    pub fn token_route<C>(
+        mut self,
+        categories: impl IntoIterator<Item = C>,
+        sources: impl IntoIterator<Item = TokenSource>,
+    ) -> Self
+    where
+        C: Into<String>,
+    {
+        self.token_routes.push((
+            categories.into_iter().map(Into::into).collect(),
+            sources.into_iter().collect(),
+        ));
+        self
+    }
+
+    /// Cache access tokens in `store`, shared by every instance using it
+    ///
+    /// Applies to the default token manager; managers added with
+    /// [`token_route`](Self::token_route) keep their tokens in memory. See
+    /// [`TokenStore`](crate::token::TokenStore).
+    pub fn token_store(mut self, store: impl TokenStore + 'static) -> Self {
+        self.token_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Call `hook` when a token refresh fails because WeChat rejected the
+    /// AppID or AppSecret (errcodes 40013, 40125, 41004)
+    ///
+    /// Applies to every token manager, including
+    /// [`token_route`](Self::token_route) ones. See
+    /// [`TokenManagerBuilder::on_fatal_auth_error`](crate::token::TokenManagerBuilder::on_fatal_auth_error).
+    pub fn on_fatal_auth_error<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&WechatError) + Send + Sync + 'static,
+    {
+        self.fatal_auth_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// How long subscribe template categories and pub-template keywords stay
+    /// cached (default one hour; `Duration::ZERO` disables the cache)
+    ///
+    /// See [`SubscribeApi`](crate::api::SubscribeApi).
+    pub fn subscribe_metadata_ttl(mut self, ttl: Duration) -> Self {
+        self.subscribe_metadata_ttl = Some(ttl);
+        self
+    }
+
+    /// Track subscribe message authorizations in `store`
+    ///
+    /// Successful sends consume one authorization and errcode 43101 clears
+    /// the pair. With [`SubscribeQuotaPolicy::Enforce`], sends without a
+    /// remaining authorization fail locally with
+    /// [`WechatError::SubscribeQuotaExhausted`].
+    pub fn subscribe_quota(
+        mut self,
+        store: impl SubscribeQuotaStore + 'static,
+        policy: SubscribeQuotaPolicy,
+    ) -> Self {
+        self.subscribe_quota = Some(SubscribeQuota {
+            store: Arc::new(store),
+            policy,
+        });
+        self
+    }
+
+    /// Set the mini program version that generated codes open by default
+    ///
+    /// See [`WechatClientBuilder::env_version`](crate::client::WechatClientBuilder::env_version).
+    pub fn env_version(mut self, env_version: EnvVersion) -> Self {
+        self.env_version = Some(env_version);
+        self
+    }
+
+    /// Record connection-level timing in every call report
+    ///
+    /// See [`WechatClientBuilder::network_timing`](crate::client::WechatClientBuilder::network_timing).
+    pub fn network_timing(mut self, enabled: bool) -> Self {
+        self.network_timing = enabled;
+        self
+    }
+
+    /// Send `traceparent`/`tracestate` headers with every request
+    ///
+    /// See [`WechatClientBuilder::propagate_trace_context`](crate::client::WechatClientBuilder::propagate_trace_context).
+    pub fn propagate_trace_context(mut self, enabled: bool) -> Self {
+        self.propagate_trace_context = enabled;
+        self
+    }
+
+    /// Read the caller's trace context when a request is sent
+    ///
+    /// See [`WechatClientBuilder::trace_context_provider`](crate::client::WechatClientBuilder::trace_context_provider).
+    pub fn trace_context_provider<F>(mut self, provider: F) -> Self
+    where
+        F: Fn() -> Option<TraceContext> + Send + Sync + 'static,
+    {
+        self.trace_context_provider = Some(Arc::new(provider));
+        self.propagate_trace_context = true;
+        self
+    }
+
+    /// Connect to `addr` instead of resolving `host` through DNS
+    ///
+    /// See [`WechatClientBuilder::resolve`](crate::client::WechatClientBuilder::resolve).
+    pub fn resolve(mut self, host: impl Into<String>, addr: SocketAddr) -> Self {
+        self.dns_overrides.push((host.into(), addr));
+        self
+    }
+
+    /// Deserialize JSON bodies of at least `bytes` off the async runtime
+    ///
+    /// See [`WechatClientBuilder::blocking_decode_threshold`](crate::client::WechatClientBuilder::blocking_decode_threshold).
+    pub fn blocking_decode_threshold(mut self, bytes: usize) -> Self {
+        self.blocking_decode_threshold = Some(bytes);
+        self
+    }
+
+    /// Pace every request, including token refreshes, through `limiter`
+    ///
+    /// See [`WechatClientBuilder::rate_limiter`](crate::client::WechatClientBuilder::rate_limiter).
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Route every API call through a Tower layer stack
+    ///
+    /// See [`WechatClientBuilder::with_middleware`](crate::client::WechatClientBuilder::with_middleware);
+    /// compose several layers with [`ServiceBuilder`](tower::ServiceBuilder).
     pub fn with_middleware<M2>(self, middleware: M2) -> WechatMpBuilder<M2>
     where
         M2: Layer<WechatClient> + Clone + Send + Sync + 'static,
     {
         WechatMpBuilder {
             appid: self.appid,
+            appid_validation: self.appid_validation,
             secret: self.secret,
             base_url: self.base_url,
+            allow_insecure_base_url: self.allow_insecure_base_url,
             timeout: self.timeout,
             connect_timeout: self.connect_timeout,
+            class_timeouts: self.class_timeouts,
+            api_versions: self.api_versions,
+            call_report_hook: self.call_report_hook,
+            #[cfg(feature = "gzip")]
+            compression: self.compression,
+            #[cfg(feature = "charset")]
+            response_charset: self.response_charset,
+            error_sniffing: self.error_sniffing,
+            gateway_errcodes: self.gateway_errcodes,
+            access_token_provider: self.access_token_provider,
+            token_sources: self.token_sources,
+            token_strategy: self.token_strategy,
+            token_routes: self.token_routes,
+            token_store: self.token_store,
+            fatal_auth_hook: self.fatal_auth_hook,
+            subscribe_metadata_ttl: self.subscribe_metadata_ttl,
+            subscribe_quota: self.subscribe_quota,
+            env_version: self.env_version,
+            rate_limiter: self.rate_limiter,
+            network_timing: self.network_timing,
+            propagate_trace_context: self.propagate_trace_context,
+            trace_context_provider: self.trace_context_provider,
+            dns_overrides: self.dns_overrides,
+            blocking_decode_threshold: self.blocking_decode_threshold,
             middleware: Some(middleware),
         }
     }
@@ -87,30 +454,34 @@ impl<M> WechatMpBuilder<M> {
     where
         M: Layer<WechatClient> + Clone + Send + Sync + 'static,
         M::Service: Service<ReqwestRequest, Response = ReqwestResponse, Error = reqwest::Error>
-            + Clone
             + Send
-            + Sync
             + 'static,
         <M::Service as Service<ReqwestRequest>>::Future: Send + 'static,
     {
         let appid = self
             .appid
             .ok_or_else(|| WechatError::Config("appid is required".to_string()))?;
-        let secret = self
-            .secret
-            .ok_or_else(|| WechatError::Config("secret is required".to_string()))?;
+        if let Some(validation) = self.appid_validation {
+            validation.validate(appid.as_str())?;
+        }
+        let sources_need_secret = self.token_sources.is_empty()
+            || self
+                .token_routes
+                .iter()
+                .any(|(_, sources)| sources.is_empty())
+            || self
+                .token_sources
+                .iter()
+                .chain(self.token_routes.iter().flat_map(|(_, sources)| sources))
+                .any(TokenSource::needs_secret);
+        if self.secret.is_none() && self.access_token_provider.is_none() && sources_need_secret {
+            return Err(WechatError::Config("secret is required".to_string()));
+        }
 
         let base_url = self
             .base_url
             .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
 
-        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
-            return Err(WechatError::Config(format!(
-                "base_url must start with http:// or https://, got: {}",
-                base_url
-            )));
-        }
-
         let timeout = self
             .timeout
             .unwrap_or(Duration::from_secs(DEFAULT_TIMEOUT_SECS));
@@ -118,46 +489,93 @@ impl<M> WechatMpBuilder<M> {
             .connect_timeout
             .unwrap_or(Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS));
 
-        let mut client = WechatClient::builder()
-            .appid(appid)
-            .secret(secret)
+        let mut client_builder = WechatClient::builder().appid(appid);
+        client_builder = match self.secret {
+            Some(secret) => client_builder.secret(secret),
+            None => client_builder.secret_optional(),
+        };
+        client_builder = client_builder
             .base_url(base_url)
+            .allow_insecure_base_url(self.allow_insecure_base_url)
             .timeout(timeout)
             .connect_timeout(connect_timeout)
-            .build()?;
-
+            .call_report_hook(self.call_report_hook);
+        for (class, class_timeout) in self.class_timeouts {
+            client_builder = client_builder.class_timeout(class, class_timeout);
+        }
+        for (endpoint_id, version) in self.api_versions {
+            client_builder = client_builder.api_version(endpoint_id, version);
+        }
+        if let Some(env_version) = self.env_version {
+            client_builder = client_builder.env_version(env_version);
+        }
+        if let Some(limiter) = self.rate_limiter {
+            client_builder = client_builder.rate_limiter(limiter);
+        }
+        client_builder = client_builder
+            .network_timing(self.network_timing)
+            .trace_context_source(self.propagate_trace_context, self.trace_context_provider);
+        for (host, addr) in self.dns_overrides {
+            client_builder = client_builder.resolve(host, addr);
+        }
+        if let Some(bytes) = self.blocking_decode_threshold {
+            client_builder = client_builder.blocking_decode_threshold(bytes);
+        }
+        if let Some(policy) = self.error_sniffing {
+            client_builder = client_builder.error_sniffing(policy);
+        }
+        if let Some(gateway_errcodes) = self.gateway_errcodes {
+            client_builder = client_builder.gateway_errcodes(gateway_errcodes);
+        }
+        #[cfg(feature = "gzip")]
+        if let Some(compression) = self.compression {
+            client_builder = client_builder.compression(compression);
+        }
+        #[cfg(feature = "charset")]
+        if let Some(charset) = self.response_charset {
+            client_builder = client_builder.response_charset(charset);
+        }
         if let Some(middleware) = self.middleware {
-            let service = middleware.layer(client.clone());
-            let executor = make_middleware_executor(service);
-            client = client.with_middleware_executor(executor);
+            client_builder = client_builder.with_middleware(middleware);
         }
+        let client = client_builder.build()?;
 
         let client_arc = Arc::new(client);
         // Clone via deref—Arc<WechatClient> → &WechatClient → WechatClient::clone
-        let token_manager = Arc::new(TokenManager::new(WechatClient::clone(&client_arc)));
-        let context = Arc::new(WechatContext::new(client_arc, token_manager));
+        let token_manager = Arc::new(
+            TokenManager::builder(WechatClient::clone(&client_arc))
+                .provider(self.access_token_provider)
+                .strategy(self.token_strategy)
+                .sources(self.token_sources)
+                .store(self.token_store)
+                .fatal_auth_hook(self.fatal_auth_hook.clone())
+                .build(),
+        );
+        let mut router = TokenRouter::new(Arc::clone(&token_manager));
+        for (categories, sources) in self.token_routes {
+            let manager = Arc::new(
+                TokenManager::builder(WechatClient::clone(&client_arc))
+                    .sources(sources)
+                    .fatal_auth_hook(self.fatal_auth_hook.clone())
+                    .build(),
+            );
+            for category in categories {
+                router = router.route(category, Arc::clone(&manager));
+            }
+        }
+        let mut context = WechatContext::new(client_arc, token_manager).with_token_router(router);
+        if let Some(ttl) = self.subscribe_metadata_ttl {
+            context = context.with_subscribe_metadata_ttl(ttl);
+        }
+        if let Some(quota) = self.subscribe_quota {
+            context = context.with_subscribe_quota(quota);
+        }
+        let context = Arc::new(context);
 
         Ok(WechatMp::from(context))
     }
 }
 
-fn make_middleware_executor<S>(service: S) -> MiddlewareExecutor
-where
-    S: Service<ReqwestRequest, Response = ReqwestResponse, Error = reqwest::Error>
-        + Clone
-        + Send
-        + Sync
-        + 'static,
-    S::Future: Send + 'static,
-{
-    let service = Arc::new(service);
-
-    Arc::new(move |request: ReqwestRequest| {
-        let mut service = (*service).clone();
-        Box::pin(async move { service.call(request).await })
-    })
-}
-
 #[cfg(test)]
 mod tests {
     use std::future::Future;
@@ -276,6 +694,113 @@ mod tests {
         assert!(middleware_invoked.load(Ordering::SeqCst));
     }
 
+    #[tokio::test]
+    async fn test_call_report_counts_middleware_attempts() {
+        #[derive(Clone)]
+        struct TwiceLayer;
+
+        impl Layer<WechatClient> for TwiceLayer {
+            type Service = TwiceService;
+
+            fn layer(&self, inner: WechatClient) -> Self::Service {
+                TwiceService { inner }
+            }
+        }
+
+        #[derive(Clone)]
+        struct TwiceService {
+            inner: WechatClient,
+        }
+
+        impl Service<ReqwestRequest> for TwiceService {
+            type Response = ReqwestResponse;
+            type Error = reqwest::Error;
+            type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, req: ReqwestRequest) -> Self::Future {
+                let mut inner = self.inner.clone();
+                Box::pin(async move {
+                    let retry = req.try_clone().expect("GET request is cloneable");
+                    let _ = inner.call(req).await?;
+                    inner.call(retry).await
+                })
+            }
+        }
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/sns/jscode2session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "openid": "test_openid",
+                "session_key": "test_session_key"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let hook_reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let hook_sink = Arc::clone(&hook_reports);
+        let wechat = WechatMp::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(mock_server.uri())
+            .on_call_report(move |report| hook_sink.lock().unwrap().push(report.clone()))
+            .with_middleware(TwiceLayer)
+            .build()
+            .unwrap();
+
+        let (result, reports) = wechat
+            .with_call_report(wechat.auth_login("mock_js_code"))
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].method, "GET");
+        assert_eq!(reports[0].path, "/sns/jscode2session");
+        assert_eq!(reports[0].attempts, 2);
+        assert!(reports[0].retried());
+        assert_eq!(reports[0].hosts, vec!["127.0.0.1".to_string()]);
+        assert_eq!(reports[0].status, Some(200));
+        assert_eq!(*hook_reports.lock().unwrap(), reports);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_readiness_limits_concurrent_calls() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/sns/jscode2session"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "openid": "test_openid",
+                        "session_key": "test_session_key"
+                    }))
+                    .set_delay(Duration::from_millis(100)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let wechat = WechatMp::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(mock_server.uri())
+            .with_middleware(tower::limit::ConcurrencyLimitLayer::new(1))
+            .build()
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let (first, second, third) = tokio::join!(
+            wechat.auth_login("code_a"),
+            wechat.auth_login("code_b"),
+            wechat.auth_login("code_c"),
+        );
+        assert!(first.is_ok() && second.is_ok() && third.is_ok());
+        // One request at a time: each waits for the previous permit
+        assert!(started.elapsed() >= Duration::from_millis(300));
+    }
+
     #[tokio::test]
     async fn test_builder_with_logging_middleware_builds() {
         let appid = AppId::new("wx1234567890abcdef").unwrap();
@@ -306,6 +831,25 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_appid_validation_on_builder() {
+        let build = |validation| {
+            WechatMp::builder()
+                .appid(AppId::new_unchecked("sandbox-app"))
+                .secret(AppSecret::new("secret").unwrap())
+                .appid_validation(validation)
+                .build()
+        };
+        assert!(matches!(
+            build(AppIdValidation::Strict),
+            Err(WechatError::InvalidAppId(_))
+        ));
+        assert_eq!(
+            build(AppIdValidation::Lenient).unwrap().appid(),
+            "sandbox-app"
+        );
+    }
+
     #[test]
     fn test_missing_appid() {
         let secret = AppSecret::new("secret1234567890ab").unwrap();
@@ -323,4 +867,184 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_access_token_provider_replaces_secret() {
+        use std::sync::atomic::AtomicUsize;
+        use wiremock::matchers::query_param;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/getcallbackip"))
+            .and(query_param("access_token", "central-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ip_list": ["101.226.103.0/25"]
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let wechat = WechatMp::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .base_url(server.uri())
+            .access_token_provider(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { Ok("central-token".to_string()) }
+            })
+            .build()
+            .unwrap();
+
+        wechat.get_callback_ip().await.unwrap();
+        wechat.get_callback_ip().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let err = wechat.auth_login("js-code").await.unwrap_err();
+        assert!(matches!(err, WechatError::Config(_)));
+    }
+
+    #[tokio::test]
+    async fn test_access_token_provider_errors_propagate() {
+        let wechat = WechatMp::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .base_url("http://127.0.0.1:9")
+            .access_token_provider(|| async {
+                Err(WechatError::Token("token service unavailable".to_string()))
+            })
+            .build()
+            .unwrap();
+
+        let err = wechat.get_callback_ip().await.unwrap_err();
+        assert!(matches!(err, WechatError::Token(ref msg) if msg == "token service unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_token_route_uses_separate_manager_per_category() {
+        use wiremock::matchers::query_param;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "classic-token",
+                "expires_in": 7200
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/stable_token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "stable-token",
+                "expires_in": 7200
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/getcallbackip"))
+            .and(query_param("access_token", "classic-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ip_list": ["101.226.103.0/25"]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cv/ocr/comm"))
+            .and(query_param("access_token", "stable-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let wechat = WechatMp::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .token_route(["image-ocr"], [TokenSource::Stable])
+            .build()
+            .unwrap();
+
+        let request = crate::api::ocr::OcrImageRequest::new("https://example.com/a.png");
+        wechat.printed_text_ocr(&request).await.unwrap();
+        wechat.printed_text_ocr(&request).await.unwrap();
+        wechat.get_callback_ip().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_quota_enforce_blocks_send_without_authorization() {
+        use crate::api::subscribe::{
+            SubscribeMessageData, SubscribeMessageOptions, SubscribeMessageValue,
+        };
+        use crate::api::subscribe_quota::MemorySubscribeQuotaStore;
+        use crate::types::OpenId;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test-token",
+                "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "errmsg": "ok"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let store = MemorySubscribeQuotaStore::new();
+        let wechat = WechatMp::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .subscribe_quota(store.clone(), SubscribeQuotaPolicy::Enforce)
+            .build()
+            .unwrap();
+        let options = SubscribeMessageOptions {
+            touser: OpenId::new("o6_bmjrPTlm6_2sgVt7hMZOPfL2M").unwrap(),
+            template_id: "tpl".to_string(),
+            data: SubscribeMessageData::from([(
+                "thing1".to_string(),
+                SubscribeMessageValue::new("Parcel"),
+            )]),
+            page: None,
+            miniprogram_state: None,
+            lang: None,
+        };
+
+        wechat
+            .record_subscribe_acceptance("o6_bmjrPTlm6_2sgVt7hMZOPfL2M", &["tpl"])
+            .await
+            .unwrap();
+        wechat
+            .send_subscribe_message(options.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            wechat
+                .subscribe_remaining_quota("o6_bmjrPTlm6_2sgVt7hMZOPfL2M", "tpl")
+                .await
+                .unwrap(),
+            0
+        );
+        let err = wechat.send_subscribe_message(options).await.unwrap_err();
+        assert!(matches!(err, WechatError::SubscribeQuotaExhausted { .. }));
+    }
 }
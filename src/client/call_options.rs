@@ -0,0 +1,122 @@
+//! Request-scoped overrides
+
+use std::collections::HashMap;
+use std::future::Future;
+
+use super::trace_context::TraceContext;
+use crate::api::endpoint_inventory::ApiVersion;
+
+/// Overrides for the calls awaited inside [`scope`](Self::scope)
+///
+/// Options follow the task: calls made from tasks spawned inside the scope
+/// use the client defaults. The SDK's own concurrent helpers, such as
+/// [`SubscribeApi::send_batch`](crate::api::subscribe::SubscribeApi::send_batch)
+/// and [`QrcodeApi::run_pipeline`](crate::api::qrcode::QrcodeApi::run_pipeline),
+/// carry the options into the tasks they spawn.
+///
+/// ```
+/// # use wechat_mp_sdk::api::endpoint_inventory::ApiVersion;
+/// # use wechat_mp_sdk::client::CallOptions;
+/// # async fn example(wechat: wechat_mp_sdk::WechatMp) -> Result<(), wechat_mp_sdk::WechatError> {
+/// CallOptions::new()
+///     .api_version("openapi.clearQuota", ApiVersion::V2)
+///     .scope(wechat.clear_quota())
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    api_versions: HashMap<String, ApiVersion>,
+    base_url: Option<String>,
+    trace_context: Option<TraceContext>,
+}
+
+tokio::task_local! {
+    static CURRENT: CallOptions;
+}
+
+impl CallOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `version` of the inventory endpoint `endpoint_id`
+    ///
+    /// Takes precedence over
+    /// [`WechatClientBuilder::api_version`](super::WechatClientBuilder::api_version).
+    /// Ignored for endpoints without versions.
+    pub fn api_version(mut self, endpoint_id: impl Into<String>, version: ApiVersion) -> Self {
+        self.api_versions.insert(endpoint_id.into(), version);
+        self
+    }
+
+    /// Send requests to `url` instead of the client's base URL
+    ///
+    /// Lets part of the traffic go through a new gateway while the rest keeps
+    /// the default. The URL is checked like
+    /// [`WechatClientBuilder::base_url`](super::WechatClientBuilder::base_url)
+    /// when a request is built; an invalid one fails the call with
+    /// [`WechatError::Config`](crate::WechatError::Config). Token refreshes
+    /// run on a background task and keep the default.
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    /// Send `context` as `traceparent`/`tracestate`
+    ///
+    /// Takes precedence over the builder's
+    /// [`trace_context_provider`](super::WechatClientBuilder::trace_context_provider);
+    /// only sent when the client was built with
+    /// [`propagate_trace_context`](super::WechatClientBuilder::propagate_trace_context).
+    pub fn trace_context(mut self, context: TraceContext) -> Self {
+        self.trace_context = Some(context);
+        self
+    }
+
+    /// Run `call` with these options
+    pub async fn scope<F: Future>(self, call: F) -> F::Output {
+        CURRENT.scope(self, call).await
+    }
+}
+
+/// Options of the enclosing [`CallOptions`] scope, to carry into spawned tasks
+pub(crate) fn current_call_options() -> Option<CallOptions> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+/// Run `call` in the scope of `options`, or as is without options
+pub(crate) async fn with_call_options<F: Future>(
+    options: Option<CallOptions>,
+    call: F,
+) -> F::Output {
+    match options {
+        Some(options) => options.scope(call).await,
+        None => call.await,
+    }
+}
+
+/// Version pinned for `endpoint_id` by the enclosing [`CallOptions`] scope.
+pub(crate) fn scoped_api_version(endpoint_id: &str) -> Option<ApiVersion> {
+    CURRENT
+        .try_with(|options| options.api_versions.get(endpoint_id).copied())
+        .ok()
+        .flatten()
+}
+
+/// Base URL set by the enclosing [`CallOptions`] scope.
+pub(crate) fn scoped_base_url() -> Option<String> {
+    CURRENT
+        .try_with(|options| options.base_url.clone())
+        .ok()
+        .flatten()
+}
+
+/// Trace context set by the enclosing [`CallOptions`] scope.
+pub(crate) fn scoped_trace_context() -> Option<TraceContext> {
+    CURRENT
+        .try_with(|options| options.trace_context.clone())
+        .ok()
+        .flatten()
+}
@@ -0,0 +1,239 @@
+//! Per-call attempt metadata
+//!
+//! Every request sent by [`WechatClient`](super::WechatClient) produces a
+//! [`CallReport`] describing how many attempts it took (including retries
+//! performed by middleware), which hosts were contacted and the total latency.
+//! Reports are delivered to the hook registered with `on_call_report` and
+//! collected by [`WechatMp::with_call_report`](super::WechatMp::with_call_report).
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::network_timing::NetworkTiming;
+use crate::utils::lock;
+
+/// Callback invoked with the report of every completed request
+pub type CallReportHook = Arc<dyn Fn(&CallReport) + Send + Sync>;
+
+/// Attempt metadata for a single logical request
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallReport {
+    /// HTTP method
+    pub method: String,
+    /// Request path without the query string
+    pub path: String,
+    /// Number of HTTP attempts, including middleware retries
+    pub attempts: u32,
+    /// Hosts contacted, in attempt order, without duplicates
+    pub hosts: Vec<String>,
+    /// Wall-clock time from the first attempt to the final response
+    pub latency: Duration,
+    /// Final HTTP status, or `None` when the request failed in transport
+    pub status: Option<u16>,
+    /// Connection-level timing, when the client was built with
+    /// `network_timing(true)` and the request got a response
+    pub timing: Option<NetworkTiming>,
+}
+
+impl CallReport {
+    pub(crate) fn new(
+        method: &reqwest::Method,
+        url: &reqwest::Url,
+        log: AttemptLog,
+        latency: Duration,
+        status: Option<u16>,
+    ) -> Self {
+        Self {
+            method: method.to_string(),
+            path: url.path().to_string(),
+            attempts: log.attempts.max(1),
+            hosts: log.hosts,
+            latency,
+            status,
+            timing: None,
+        }
+    }
+
+    /// Returns `true` when the call needed more than one attempt
+    pub fn retried(&self) -> bool {
+        self.attempts > 1
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct AttemptLog {
+    attempts: u32,
+    hosts: Vec<String>,
+    /// Start of the latest attempt and its DNS, connect and TLS durations
+    attempt_started: Option<Instant>,
+    dns: Option<Duration>,
+    connect: Option<Duration>,
+    tls: Option<Duration>,
+    /// When the TLS handshake of the connection being opened started
+    tls_started: Option<Instant>,
+}
+
+impl AttemptLog {
+    /// HTTP attempts made, at least one
+    pub(crate) fn attempts(&self) -> u32 {
+        self.attempts.max(1)
+    }
+
+    /// Timing of the latest attempt, given when its headers arrived and
+    /// how long its body took to read.
+    pub(crate) fn timing(&self, headers_at: Instant, body: Duration) -> NetworkTiming {
+        NetworkTiming {
+            dns: self.dns,
+            connect: self
+                .connect
+                .map(|connect| connect.saturating_sub(self.dns.unwrap_or_default())),
+            tls: self.tls,
+            ttfb: self
+                .attempt_started
+                .map_or(Duration::ZERO, |started| headers_at - started),
+            body,
+        }
+    }
+}
+
+type SharedAttemptLog = Arc<Mutex<AttemptLog>>;
+type SharedReports = Arc<Mutex<Vec<CallReport>>>;
+
+tokio::task_local! {
+    static ATTEMPTS: SharedAttemptLog;
+    static COLLECTED: SharedReports;
+}
+
+/// Record one HTTP attempt against the request currently being reported.
+pub(crate) fn record_attempt(request: &reqwest::Request) {
+    let _ = ATTEMPTS.try_with(|log| {
+        let mut log = lock(log);
+        log.attempts += 1;
+        log.attempt_started = Some(Instant::now());
+        log.dns = None;
+        log.connect = None;
+        log.tls = None;
+        log.tls_started = None;
+        if let Some(host) = request.url().host_str() {
+            if !log.hosts.iter().any(|known| known == host) {
+                log.hosts.push(host.to_string());
+            }
+        }
+    });
+}
+
+/// Attempts recorded so far for the request currently being reported.
+pub(crate) fn current_attempts() -> Option<u32> {
+    ATTEMPTS.try_with(|log| lock(log).attempts()).ok()
+}
+
+/// Record a DNS lookup made for the current attempt.
+pub(crate) fn record_dns(elapsed: Duration) {
+    let _ = ATTEMPTS.try_with(|log| {
+        lock(log).dns = Some(elapsed);
+    });
+}
+
+/// Record the start of a TLS handshake for the current attempt.
+#[cfg(all(feature = "rustls-tls", not(feature = "native-tls")))]
+pub(crate) fn record_tls_start() {
+    let _ = ATTEMPTS.try_with(|log| {
+        lock(log).tls_started.get_or_insert_with(Instant::now);
+    });
+}
+
+/// Record a new connection opened for the current attempt since `started`,
+/// splitting off the TLS handshake when its start was recorded.
+pub(crate) fn record_connect(started: Instant) {
+    let finished = Instant::now();
+    let _ = ATTEMPTS.try_with(|log| {
+        let mut log = lock(log);
+        let tls = log
+            .tls_started
+            .take()
+            .filter(|tls_started| *tls_started >= started)
+            .map(|tls_started| finished - tls_started);
+        log.connect = Some((finished - started).saturating_sub(tls.unwrap_or_default()));
+        log.tls = tls;
+    });
+}
+
+/// Run `send` while collecting its attempts into a fresh log.
+pub(crate) async fn track_attempts<F: Future>(send: F) -> (F::Output, AttemptLog) {
+    let log = SharedAttemptLog::default();
+    let output = ATTEMPTS.scope(Arc::clone(&log), send).await;
+    let log = std::mem::take(&mut *lock(&log));
+    (output, log)
+}
+
+/// Hand a finished report to the enclosing [`collect_reports`] scope, if any.
+pub(crate) fn publish(report: &CallReport) {
+    let _ = COLLECTED.try_with(|reports| {
+        lock(reports).push(report.clone());
+    });
+}
+
+/// Run `future` and return the reports of every request it sent on this task.
+pub(crate) async fn collect_reports<F: Future>(future: F) -> (F::Output, Vec<CallReport>) {
+    let reports = SharedReports::default();
+    let output = COLLECTED.scope(Arc::clone(&reports), future).await;
+    let reports = std::mem::take(&mut *lock(&reports));
+    (output, reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str) -> reqwest::Request {
+        reqwest::Client::new().get(url).build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_track_attempts_counts_and_dedups_hosts() {
+        let ((), log) = track_attempts(async {
+            record_attempt(&request("https://api.weixin.qq.com/cgi-bin/token"));
+            record_attempt(&request("https://api2.weixin.qq.com/cgi-bin/token"));
+            record_attempt(&request("https://api.weixin.qq.com/cgi-bin/token"));
+        })
+        .await;
+
+        assert_eq!(log.attempts, 3);
+        assert_eq!(log.hosts, vec!["api.weixin.qq.com", "api2.weixin.qq.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_record_attempt_outside_scope_is_noop() {
+        record_attempt(&request("https://api.weixin.qq.com/cgi-bin/token"));
+        publish(&CallReport::new(
+            &reqwest::Method::GET,
+            &reqwest::Url::parse("https://api.weixin.qq.com/cgi-bin/token").unwrap(),
+            AttemptLog::default(),
+            Duration::ZERO,
+            None,
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_collect_reports_strips_query() {
+        let url =
+            reqwest::Url::parse("https://api.weixin.qq.com/wxa/x?access_token=secret").unwrap();
+        let ((), reports) = collect_reports(async {
+            publish(&CallReport::new(
+                &reqwest::Method::POST,
+                &url,
+                AttemptLog::default(),
+                Duration::from_millis(5),
+                Some(200),
+            ));
+        })
+        .await;
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].path, "/wxa/x");
+        assert_eq!(reports[0].attempts, 1);
+        assert!(!reports[0].retried());
+    }
+}
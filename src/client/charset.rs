@@ -0,0 +1,142 @@
+//! Decoding of JSON responses that are not UTF-8
+//!
+//! Available with the `charset` feature. A few legacy endpoints send `errmsg`
+//! in GBK; `serde_json` rejects such bodies, so the call failed with
+//! [`WechatError::Decode`] instead of the WeChat error. With the feature,
+//! JSON bodies that are not valid UTF-8 are transcoded before decoding,
+//! from the `charset` of their `Content-Type` or else from GB18030, a
+//! superset of GB2312 and GBK. Valid UTF-8 bodies are never touched.
+
+use std::borrow::Cow;
+
+use encoding_rs::{Encoding, GB18030, UTF_8};
+
+use crate::error::WechatError;
+
+/// Charset handling of JSON responses for [`WechatClient`](super::WechatClient)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseCharset {
+    use_content_type: bool,
+    fallback: Option<&'static Encoding>,
+}
+
+impl Default for ResponseCharset {
+    fn default() -> Self {
+        Self {
+            use_content_type: true,
+            fallback: Some(GB18030),
+        }
+    }
+}
+
+impl ResponseCharset {
+    /// Leave every body as received, so invalid UTF-8 fails to decode
+    pub fn utf8_only() -> Self {
+        Self {
+            use_content_type: false,
+            fallback: None,
+        }
+    }
+
+    /// Decode unlabelled bodies as the encoding named by `label`, a WHATWG
+    /// label such as `gbk` or `big5`
+    ///
+    /// Default: `gb18030`
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Config`] for an unknown label.
+    pub fn fallback(mut self, label: &str) -> Result<Self, WechatError> {
+        let encoding = Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| {
+            WechatError::Config(format!("unknown response charset label {label:?}"))
+        })?;
+        self.fallback = Some(encoding);
+        Ok(self)
+    }
+
+    /// Prefer the `charset` parameter of the response `Content-Type` over
+    /// the fallback
+    ///
+    /// Default: `true`
+    pub fn use_content_type(mut self, enabled: bool) -> Self {
+        self.use_content_type = enabled;
+        self
+    }
+
+    /// `body` as UTF-8, transcoded when it is not valid UTF-8 already
+    pub(crate) fn transcode<'a>(&self, content_type: Option<&str>, body: &'a [u8]) -> Cow<'a, [u8]> {
+        if std::str::from_utf8(body).is_ok() {
+            return Cow::Borrowed(body);
+        }
+        let declared = content_type
+            .filter(|_| self.use_content_type)
+            .and_then(charset_label)
+            .and_then(|label| Encoding::for_label(label.as_bytes()))
+            .filter(|encoding| *encoding != UTF_8);
+        let Some(encoding) = declared.or(self.fallback) else {
+            return Cow::Borrowed(body);
+        };
+
+        let (text, _, had_errors) = encoding.decode(body);
+        if had_errors {
+            log::debug!(
+                "response body is not valid {}; undecodable bytes were replaced",
+                encoding.name()
+            );
+        }
+        Cow::Owned(text.into_owned().into_bytes())
+    }
+}
+
+/// The `charset` parameter of a `Content-Type` value
+fn charset_label(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_charset_transcodes_only_invalid_utf8() {
+        // {"errcode":40001,"errmsg":"无效"} with the message in GBK
+        let mut gbk = br#"{"errcode":40001,"errmsg":""#.to_vec();
+        gbk.extend_from_slice(&[0xce, 0xde, 0xd0, 0xa7]);
+        gbk.extend_from_slice(br#""}"#);
+        let expected = r#"{"errcode":40001,"errmsg":"无效"}"#.as_bytes();
+
+        let charset = ResponseCharset::default();
+        assert_eq!(charset.transcode(None, &gbk), expected);
+        assert_eq!(
+            charset.transcode(Some("application/json; charset=\"GBK\""), &gbk),
+            expected
+        );
+        assert!(matches!(
+            charset.transcode(Some("application/json; charset=gbk"), expected),
+            Cow::Borrowed(_)
+        ));
+
+        // A declared charset wins over the fallback
+        let big5 = ResponseCharset::default().fallback("big5").unwrap();
+        assert_ne!(big5.transcode(None, &gbk), expected);
+        assert_eq!(
+            big5.transcode(Some("text/plain;charset=gb2312"), &gbk),
+            expected
+        );
+        assert_ne!(
+            big5.use_content_type(false)
+                .transcode(Some("text/plain;charset=gb2312"), &gbk),
+            expected
+        );
+
+        assert_eq!(ResponseCharset::utf8_only().transcode(None, &gbk), gbk);
+        assert!(matches!(
+            ResponseCharset::default().fallback("klingon"),
+            Err(WechatError::Config(_))
+        ));
+    }
+}
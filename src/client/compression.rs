@@ -0,0 +1,106 @@
+//! Request and response body compression
+//!
+//! Available with the `gzip` feature. Response decompression is on by default
+//! and lets WeChat send gzip-encoded bodies. Request compression is off by
+//! default: WeChat does not document accepting gzip-encoded request bodies, so
+//! only enable it for endpoints or gateways known to decode them.
+
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use serde::Serialize;
+
+use crate::error::WechatError;
+
+/// Body compression settings for [`WechatClient`](super::WechatClient)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    gzip_request_min_bytes: Option<usize>,
+    decompress_responses: bool,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            gzip_request_min_bytes: None,
+            decompress_responses: true,
+        }
+    }
+}
+
+impl Compression {
+    /// Gzip JSON request bodies of at least `min_bytes`
+    pub fn gzip_requests_over(mut self, min_bytes: usize) -> Self {
+        self.gzip_request_min_bytes = Some(min_bytes);
+        self
+    }
+
+    /// Send `Accept-Encoding: gzip` and decode compressed responses
+    ///
+    /// Default: `true`
+    pub fn decompress_responses(mut self, enabled: bool) -> Self {
+        self.decompress_responses = enabled;
+        self
+    }
+
+    pub(crate) fn decompresses_responses(&self) -> bool {
+        self.decompress_responses
+    }
+
+    /// Serialize `body` as JSON, gzipping it when it reaches the threshold.
+    ///
+    /// Returns the encoded bytes and whether they are compressed.
+    pub(crate) fn encode_json<B: Serialize + ?Sized>(
+        &self,
+        body: &B,
+    ) -> Result<(Vec<u8>, bool), WechatError> {
+        let json = serde_json::to_vec(body)?;
+        match self.gzip_request_min_bytes {
+            Some(min_bytes) if json.len() >= min_bytes => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder
+                    .write_all(&json)
+                    .and_then(|()| encoder.finish())
+                    .map(|compressed| (compressed, true))
+                    .map_err(|e| WechatError::Config(format!("gzip encoding failed: {}", e)))
+            }
+            _ => Ok((json, false)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_encode_json_respects_threshold() {
+        let compression = Compression::default().gzip_requests_over(64);
+
+        let (small, compressed) = compression
+            .encode_json(&serde_json::json!({"a": 1}))
+            .unwrap();
+        assert!(!compressed);
+        assert_eq!(small, br#"{"a":1}"#);
+
+        let payload = serde_json::json!({"rows": vec!["same value"; 100]});
+        let (large, compressed) = compression.encode_json(&payload).unwrap();
+        assert!(compressed);
+        assert!(large.len() < serde_json::to_vec(&payload).unwrap().len());
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(large.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, payload.to_string());
+    }
+
+    #[test]
+    fn test_default_leaves_requests_uncompressed() {
+        let payload = serde_json::json!({"rows": vec!["same value"; 100]});
+        let (_, compressed) = Compression::default().encode_json(&payload).unwrap();
+        assert!(!compressed);
+        assert!(Compression::default().decompresses_responses());
+    }
+}
@@ -0,0 +1,46 @@
+//! Detection of JSON error bodies on binary endpoints
+//!
+//! Binary endpoints (QR codes, media downloads) answer failures with HTTP 200
+//! and an `errcode` JSON body. The content type of that body cannot be
+//! trusted: besides `application/json` WeChat has been seen labelling it
+//! `text/plain` and even `image/jpeg`.
+
+/// How binary responses are checked for a JSON `errcode` body
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ErrorSniffing {
+    /// Parse the body when the content type is JSON or text, or when its first
+    /// bytes (after any UTF-8 BOM and whitespace) are `{`, whatever the
+    /// content type claims
+    #[default]
+    Auto,
+    /// Parse the body only when the content type is JSON or text
+    ContentType,
+    /// Return every 2xx body as binary data
+    Disabled,
+}
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+impl ErrorSniffing {
+    /// Whether `body` should be parsed as a possible JSON error.
+    pub(crate) fn should_parse(self, content_type: Option<&str>, body: &[u8]) -> bool {
+        let json_content_type = || {
+            content_type.is_some_and(|ct| {
+                let ct = ct.trim_start().to_ascii_lowercase();
+                ct.starts_with("application/json") || ct.starts_with("text/")
+            })
+        };
+        match self {
+            ErrorSniffing::Auto => json_content_type() || starts_like_json_object(body),
+            ErrorSniffing::ContentType => json_content_type(),
+            ErrorSniffing::Disabled => false,
+        }
+    }
+}
+
+fn starts_like_json_object(body: &[u8]) -> bool {
+    let body = body.strip_prefix(UTF8_BOM).unwrap_or(body);
+    body.iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_some_and(|&b| b == b'{')
+}
@@ -0,0 +1,107 @@
+//! Errcodes injected by gateways in front of WeChat
+//!
+//! Corporate proxies that wrap the WeChat API sometimes report their own
+//! failures in the `errcode` field. Registering those codes turns them into
+//! [`WechatError::Gateway`], which is retryable, instead of
+//! [`WechatError::Api`] business errors.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use crate::error::WechatError;
+
+type ErrcodeClassifier = Arc<dyn Fn(i32) -> bool + Send + Sync>;
+
+/// Errcodes that belong to a gateway rather than to WeChat
+///
+/// ```
+/// use wechat_mp_sdk::client::GatewayErrcodes;
+///
+/// let gateway = GatewayErrcodes::new()
+///     .range(100_000_000..=199_999_999)
+///     .classifier(|code| code == -9999);
+/// ```
+#[derive(Clone, Default)]
+pub struct GatewayErrcodes {
+    ranges: Vec<RangeInclusive<i32>>,
+    classifiers: Vec<ErrcodeClassifier>,
+}
+
+impl GatewayErrcodes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treat every errcode in `range` as a gateway failure
+    pub fn range(mut self, range: RangeInclusive<i32>) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Treat errcodes for which `classifier` returns `true` as gateway failures
+    pub fn classifier<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(i32) -> bool + Send + Sync + 'static,
+    {
+        self.classifiers.push(Arc::new(classifier));
+        self
+    }
+
+    /// Whether `code` is a registered gateway errcode
+    pub fn matches(&self, code: i32) -> bool {
+        self.ranges.iter().any(|range| range.contains(&code))
+            || self.classifiers.iter().any(|classifier| classifier(code))
+    }
+
+    /// Rewrite an `Api` error carrying a gateway errcode into `Gateway`.
+    pub(crate) fn classify(&self, error: WechatError) -> WechatError {
+        match error {
+            WechatError::Api { code, message, .. } if self.matches(code) => {
+                WechatError::Gateway { code, message }
+            }
+            other => other,
+        }
+    }
+}
+
+impl fmt::Debug for GatewayErrcodes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GatewayErrcodes")
+            .field("ranges", &self.ranges)
+            .field("classifiers", &self.classifiers.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_only_registered_codes() {
+        let gateway = GatewayErrcodes::new()
+            .range(100_000_000..=199_999_999)
+            .classifier(|code| code == -9999);
+
+        let error = gateway.classify(WechatError::api(100_000_502, "upstream timeout"));
+        assert!(matches!(
+            &error,
+            WechatError::Gateway { code: 100_000_502, message } if message == "upstream timeout"
+        ));
+        assert!(error.is_transient());
+
+        assert!(matches!(
+            gateway.classify(WechatError::api(-9999, "proxy down")),
+            WechatError::Gateway { code: -9999, .. }
+        ));
+        assert!(matches!(
+            gateway.classify(WechatError::api(40001, "invalid credential")),
+            WechatError::Api { code: 40001, .. }
+        ));
+        assert!(matches!(
+            GatewayErrcodes::default().classify(WechatError::api(100_000_502, "x")),
+            WechatError::Api { .. }
+        ));
+    }
+}
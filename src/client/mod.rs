@@ -2,7 +2,47 @@
 //!
 //! This module contains the WechatClient and related types.
 
+mod call_options;
+pub use call_options::CallOptions;
+pub(crate) use call_options::{current_call_options, with_call_options};
+
+mod call_report;
+pub(crate) use call_report::current_attempts;
+pub use call_report::{CallReport, CallReportHook};
+
+cfg_gzip! {
+    mod compression;
+    pub use compression::Compression;
+}
+
+cfg_charset! {
+    mod charset;
+    pub use charset::ResponseCharset;
+}
+
+mod mode;
+pub use mode::Mode;
+
+mod support_bundle;
+pub use support_bundle::RECENT_ERRCODES_CAPACITY;
+
+mod stats;
+pub use stats::{ClientStats, DecodeStats, TrafficStats, UNCATEGORIZED};
+
+mod network_timing;
+pub use network_timing::NetworkTiming;
+
+mod trace_context;
+pub use trace_context::{TraceContext, TraceContextProvider};
+
+mod error_sniffing;
+pub use error_sniffing::ErrorSniffing;
+
+mod gateway_errcodes;
+pub use gateway_errcodes::GatewayErrcodes;
+
 mod wechat_client;
+pub(crate) use wechat_client::buffer_response_with_body;
 pub use wechat_client::{WechatClient, WechatClientBuilder};
 
 mod wechat_mp;
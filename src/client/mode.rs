@@ -0,0 +1,29 @@
+//! SDK-wide operating mode
+
+/// Which endpoints a client may call
+///
+/// Switched at runtime with [`WechatMp::set_mode`](super::WechatMp::set_mode),
+/// e.g. to freeze side effects during an incident without a deploy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Mode {
+    /// Every endpoint is allowed
+    #[default]
+    Normal = 0,
+    /// Endpoints marked `mutating` in the
+    /// [endpoint inventory](crate::api::endpoint_inventory::ENDPOINT_INVENTORY)
+    /// (sends, orders, deletes, ...) fail with
+    /// [`WechatError::ReadOnly`](crate::WechatError::ReadOnly) without
+    /// reaching WeChat. Reads and token refreshes keep working, as do paths
+    /// outside the inventory.
+    ReadOnly = 1,
+}
+
+impl Mode {
+    pub(crate) fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Mode::ReadOnly,
+            _ => Mode::Normal,
+        }
+    }
+}
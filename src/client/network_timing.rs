@@ -0,0 +1,227 @@
+//! Connection-level timing for [`CallReport`](super::CallReport)
+//!
+//! Enabled with `network_timing(true)` on the client builders. DNS lookups
+//! go through [`TimingResolver`] and new connections through
+//! [`TimingConnectLayer`]; both record into the attempt being reported.
+//! reqwest has no hook between TCP connect and the TLS handshake, so with
+//! the `rustls-tls` backend the client gets its own rustls configuration
+//! whose session store marks when the ClientHello is built. With
+//! `native-tls` the handshake stays part of [`NetworkTiming::connect`].
+
+use std::future::Future;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use tower::{Layer, Service};
+
+use super::call_report::{record_connect, record_dns};
+
+/// Network timing of the final attempt of a request
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NetworkTiming {
+    /// DNS resolution, or `None` when no lookup was made (pooled
+    /// connection or IP address host)
+    pub dns: Option<Duration>,
+    /// TCP connect, excluding DNS and [`tls`](Self::tls), or `None` when a
+    /// pooled connection was reused
+    pub connect: Option<Duration>,
+    /// TLS handshake of a new `https` connection, or `None` for plain
+    /// `http`, reused connections and the `native-tls` backend, where it is
+    /// included in [`connect`](Self::connect)
+    pub tls: Option<Duration>,
+    /// From sending the attempt to receiving response headers, including
+    /// `dns` and `connect`
+    pub ttfb: Duration,
+    /// Reading the response body
+    pub body: Duration,
+}
+
+/// System resolver that records how long each lookup takes
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TimingResolver;
+
+impl Resolve for TimingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let started = Instant::now();
+            let addrs = tokio::task::spawn_blocking(move || (host.as_str(), 0).to_socket_addrs())
+                .await??
+                .collect::<Vec<SocketAddr>>();
+            record_dns(started.elapsed());
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Connector layer that records how long new connections take to open
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TimingConnectLayer;
+
+impl<S> Layer<S> for TimingConnectLayer {
+    type Service = TimingConnect<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimingConnect { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TimingConnect<S> {
+    inner: S,
+}
+
+impl<S, R> Service<R> for TimingConnect<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let connecting = self.inner.call(request);
+        Box::pin(async move {
+            let started = Instant::now();
+            let result = connecting.await;
+            if result.is_ok() {
+                record_connect(started);
+            }
+            result
+        })
+    }
+}
+
+/// Handshake timing for the `rustls-tls` backend
+#[cfg(all(feature = "rustls-tls", not(feature = "native-tls")))]
+mod tls {
+    use std::sync::Arc;
+
+    use rustls::client::{
+        ClientSessionMemoryCache, ClientSessionStore, Resumption, Tls12ClientSessionValue,
+        Tls13ClientSessionValue,
+    };
+    use rustls::pki_types::ServerName;
+    use rustls::NamedGroup;
+
+    use crate::client::call_report::record_tls_start;
+    use crate::error::WechatError;
+
+    /// The rustls configuration reqwest builds for `rustls-tls` by default,
+    /// with a session store that records when each handshake starts
+    pub(crate) fn timing_tls_config() -> Result<rustls::ClientConfig, WechatError> {
+        let provider = rustls::crypto::CryptoProvider::get_default()
+            .cloned()
+            .unwrap_or_else(|| Arc::new(rustls::crypto::ring::default_provider()));
+        let mut roots = rustls::RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let mut config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_protocol_versions(rustls::ALL_VERSIONS)
+            .map_err(|e| WechatError::Config(format!("TLS configuration: {e}")))?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        config.resumption = Resumption::store(Arc::new(TimingSessionStore(
+            ClientSessionMemoryCache::new(256),
+        )));
+        Ok(config)
+    }
+
+    /// Session store that marks the TLS start when rustls looks up the
+    /// server while building the ClientHello
+    #[derive(Debug)]
+    struct TimingSessionStore(ClientSessionMemoryCache);
+
+    impl ClientSessionStore for TimingSessionStore {
+        fn set_kx_hint(&self, server_name: ServerName<'static>, group: NamedGroup) {
+            self.0.set_kx_hint(server_name, group);
+        }
+
+        fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<NamedGroup> {
+            record_tls_start();
+            self.0.kx_hint(server_name)
+        }
+
+        fn set_tls12_session(
+            &self,
+            server_name: ServerName<'static>,
+            value: Tls12ClientSessionValue,
+        ) {
+            self.0.set_tls12_session(server_name, value);
+        }
+
+        fn tls12_session(&self, server_name: &ServerName<'_>) -> Option<Tls12ClientSessionValue> {
+            record_tls_start();
+            self.0.tls12_session(server_name)
+        }
+
+        fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+            self.0.remove_tls12_session(server_name);
+        }
+
+        fn insert_tls13_ticket(
+            &self,
+            server_name: ServerName<'static>,
+            value: Tls13ClientSessionValue,
+        ) {
+            self.0.insert_tls13_ticket(server_name, value);
+        }
+
+        fn take_tls13_ticket(
+            &self,
+            server_name: &ServerName<'static>,
+        ) -> Option<Tls13ClientSessionValue> {
+            record_tls_start();
+            self.0.take_tls13_ticket(server_name)
+        }
+    }
+}
+
+#[cfg(all(feature = "rustls-tls", not(feature = "native-tls")))]
+pub(crate) use tls::timing_tls_config;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(all(feature = "rustls-tls", not(feature = "native-tls")))]
+    #[tokio::test]
+    async fn test_tls_handshake_is_split_from_connect() {
+        use std::sync::Arc;
+
+        use super::super::call_report::{record_attempt, record_connect, track_attempts};
+        use super::*;
+
+        let ((), log) = track_attempts(async {
+            let request = reqwest::Request::new(
+                reqwest::Method::GET,
+                "https://api.weixin.qq.com/".parse().unwrap(),
+            );
+            record_attempt(&request);
+            let started = Instant::now();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            // Building the ClientHello marks the start of the handshake
+            let config = Arc::new(timing_tls_config().unwrap());
+            let _connection =
+                rustls::ClientConnection::new(config, "api.weixin.qq.com".try_into().unwrap())
+                    .unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            record_connect(started);
+        })
+        .await;
+
+        let timing = log.timing(Instant::now(), Duration::ZERO);
+        let connect = timing.connect.expect("connection opened");
+        let tls = timing.tls.expect("handshake started");
+        assert!(connect >= Duration::from_millis(20));
+        assert!(tls >= Duration::from_millis(20));
+        assert!(connect + tls <= timing.ttfb);
+    }
+}
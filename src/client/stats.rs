@@ -0,0 +1,258 @@
+//! Per-category traffic accounting
+//!
+//! Every request sent by [`WechatClient`](super::WechatClient) adds its
+//! request and response body sizes to the endpoint category it belongs to
+//! (the `category` of its [`ENDPOINT_INVENTORY`](crate::api::endpoint_inventory::ENDPOINT_INVENTORY)
+//! entry, or [`UNCATEGORIZED`] for paths outside the inventory), and the
+//! time spent deserializing JSON responses to [`DecodeStats`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body::{Body, Frame, SizeHint};
+
+use crate::utils::lock;
+
+/// Category used for paths missing from the endpoint inventory
+pub const UNCATEGORIZED: &str = "other";
+
+/// Traffic counters of one endpoint category
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrafficStats {
+    /// Requests sent, counting each retry attempt once
+    pub requests: u64,
+    /// Request body bytes
+    pub bytes_sent: u64,
+    /// Response body bytes, as transferred when the server sent a
+    /// `Content-Length`, otherwise after decompression and once the body
+    /// has been read
+    pub bytes_received: u64,
+}
+
+impl TrafficStats {
+    fn add(&mut self, other: TrafficStats) {
+        self.requests += other.requests;
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+    }
+}
+
+/// JSON deserialization timing of one endpoint category
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeStats {
+    /// Response bodies deserialized
+    pub decodes: u64,
+    /// Bodies deserialized on the blocking thread pool, see
+    /// [`WechatClientBuilder::blocking_decode_threshold`](super::WechatClientBuilder::blocking_decode_threshold)
+    pub offloaded: u64,
+    /// Body bytes deserialized
+    pub bytes: u64,
+    /// Time spent deserializing, summed over all bodies
+    pub total: Duration,
+    /// Longest single deserialization
+    pub max: Duration,
+}
+
+impl DecodeStats {
+    pub(crate) fn single(bytes: usize, elapsed: Duration, offloaded: bool) -> Self {
+        Self {
+            decodes: 1,
+            offloaded: u64::from(offloaded),
+            bytes: bytes as u64,
+            total: elapsed,
+            max: elapsed,
+        }
+    }
+
+    fn add(&mut self, other: DecodeStats) {
+        self.decodes += other.decodes;
+        self.offloaded += other.offloaded;
+        self.bytes += other.bytes;
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+}
+
+/// Snapshot of a client's traffic, by endpoint category
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    pub categories: BTreeMap<&'static str, TrafficStats>,
+    pub decoding: BTreeMap<&'static str, DecodeStats>,
+}
+
+impl ClientStats {
+    /// Counters of `category`, zero if it saw no traffic
+    pub fn category(&self, category: &str) -> TrafficStats {
+        self.categories.get(category).copied().unwrap_or_default()
+    }
+
+    /// Deserialization timing of `category`, zero if nothing was decoded
+    pub fn decode(&self, category: &str) -> DecodeStats {
+        self.decoding.get(category).copied().unwrap_or_default()
+    }
+
+    /// Counters summed over every category
+    pub fn total(&self) -> TrafficStats {
+        let mut total = TrafficStats::default();
+        for stats in self.categories.values() {
+            total.add(*stats);
+        }
+        total
+    }
+}
+
+/// Shared, cloneable counters behind [`ClientStats`]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TrafficCounters {
+    inner: Arc<Mutex<Counters>>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    traffic: HashMap<&'static str, TrafficStats>,
+    decoding: HashMap<&'static str, DecodeStats>,
+}
+
+impl TrafficCounters {
+    pub(crate) fn record(&self, category: &'static str, traffic: TrafficStats) {
+        lock(&self.inner)
+            .traffic
+            .entry(category)
+            .or_default()
+            .add(traffic);
+    }
+
+    pub(crate) fn record_decode(&self, category: &'static str, decode: DecodeStats) {
+        lock(&self.inner)
+            .decoding
+            .entry(category)
+            .or_default()
+            .add(decode);
+    }
+
+    pub(crate) fn snapshot(&self) -> ClientStats {
+        let counters = lock(&self.inner);
+        ClientStats {
+            categories: counters.traffic.iter().map(|(k, v)| (*k, *v)).collect(),
+            decoding: counters.decoding.iter().map(|(k, v)| (*k, *v)).collect(),
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        let mut counters = lock(&self.inner);
+        counters.traffic.clear();
+        counters.decoding.clear();
+    }
+}
+
+/// Response body that adds the bytes read through it to the
+/// `bytes_received` of its category when dropped
+pub(crate) struct CountingBody {
+    inner: reqwest::Body,
+    counters: TrafficCounters,
+    category: &'static str,
+    received: u64,
+}
+
+impl CountingBody {
+    pub(crate) fn new(
+        inner: reqwest::Body,
+        counters: TrafficCounters,
+        category: &'static str,
+    ) -> Self {
+        Self {
+            inner,
+            counters,
+            category,
+            received: 0,
+        }
+    }
+}
+
+impl Body for CountingBody {
+    type Data = Bytes;
+    type Error = reqwest::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, reqwest::Error>>> {
+        let polled = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &polled {
+            if let Some(data) = frame.data_ref() {
+                self.received += data.len() as u64;
+            }
+        }
+        polled
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for CountingBody {
+    fn drop(&mut self) {
+        self.counters.record(
+            self.category,
+            TrafficStats {
+                bytes_received: self.received,
+                ..TrafficStats::default()
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_accumulate_per_category() {
+        let counters = TrafficCounters::default();
+        let traffic = |bytes_sent, bytes_received| TrafficStats {
+            requests: 1,
+            bytes_sent,
+            bytes_received,
+        };
+        counters.record("qrcode-link", traffic(40, 2_000));
+        counters.clone().record("qrcode-link", traffic(40, 3_000));
+        counters.record(UNCATEGORIZED, traffic(0, 10));
+
+        let stats = counters.snapshot();
+        assert_eq!(
+            stats.category("qrcode-link"),
+            TrafficStats {
+                requests: 2,
+                bytes_sent: 80,
+                bytes_received: 5_000,
+            }
+        );
+        assert_eq!(stats.total().bytes_received, 5_010);
+        assert_eq!(stats.category("login"), TrafficStats::default());
+
+        let millis = Duration::from_millis;
+        counters.record_decode("analytics", DecodeStats::single(100, millis(2), false));
+        counters.record_decode("analytics", DecodeStats::single(4_000, millis(9), true));
+        let decode = counters.snapshot().decode("analytics");
+        assert_eq!(decode.decodes, 2);
+        assert_eq!(decode.offloaded, 1);
+        assert_eq!(decode.bytes, 4_100);
+        assert_eq!(decode.total, millis(11));
+        assert_eq!(decode.max, millis(9));
+
+        counters.reset();
+        assert_eq!(counters.snapshot(), ClientStats::default());
+    }
+}
@@ -0,0 +1,235 @@
+//! W3C Trace Context headers on outgoing requests
+//!
+//! With [`propagate_trace_context`](super::WechatClientBuilder::propagate_trace_context)
+//! enabled, every request (including retries and token fetches) carries the
+//! `traceparent` and `tracestate` of the caller's trace. WeChat ignores them,
+//! but egress proxies and tracing backends can join the call to the trace.
+//!
+//! The context comes from the enclosing
+//! [`CallOptions::trace_context`](super::CallOptions::trace_context) scope,
+//! or else from the [`TraceContextProvider`] registered on the builder. The
+//! provider is where the current span's context is read. With the
+//! `opentelemetry` feature, `TraceContext::from_current_span` reads it from
+//! the current `tracing` span through `tracing-opentelemetry`:
+//!
+//! ```rust,ignore
+//! let client = WechatClient::builder()
+//!     .appid(appid)
+//!     .secret(secret)
+//!     .trace_context_provider(TraceContext::from_current_span)
+//!     .build()?;
+//! ```
+
+use std::sync::Arc;
+
+use http::{HeaderMap, HeaderValue};
+
+use crate::error::WechatError;
+
+/// Returns the trace context of the caller, if it has one
+pub type TraceContextProvider = Arc<dyn Fn() -> Option<TraceContext> + Send + Sync>;
+
+/// A validated `traceparent` with an optional `tracestate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    traceparent: String,
+    tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// Build a version `00` `traceparent`
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Config`] when either id is zero, which the
+    /// specification reserves as invalid.
+    pub fn new(trace_id: u128, parent_id: u64, sampled: bool) -> Result<Self, WechatError> {
+        Self::parse(&format!(
+            "00-{trace_id:032x}-{parent_id:016x}-{:02x}",
+            u8::from(sampled)
+        ))
+    }
+
+    /// Accept a `traceparent` header value, e.g. one received upstream
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Config`] unless the value is
+    /// `version-trace_id-parent_id-flags` in lowercase hex with non-zero ids
+    /// and a version other than `ff`.
+    pub fn parse(traceparent: &str) -> Result<Self, WechatError> {
+        let fields: Vec<&str> = traceparent.split('-').collect();
+        let valid = match fields.as_slice() {
+            [version, trace_id, parent_id, flags, rest @ ..] => {
+                is_hex(version, 2)
+                    && *version != "ff"
+                    && (*version != "00" || rest.is_empty())
+                    && is_hex(trace_id, 32)
+                    && is_hex(parent_id, 16)
+                    && is_hex(flags, 2)
+                    && !is_zero(trace_id)
+                    && !is_zero(parent_id)
+            }
+            _ => false,
+        };
+        if !valid {
+            return Err(WechatError::Config(format!(
+                "invalid traceparent: {traceparent:?}"
+            )));
+        }
+        Ok(Self {
+            traceparent: traceparent.to_string(),
+            tracestate: None,
+        })
+    }
+
+    /// Attach vendor data sent as `tracestate`; empty values are dropped
+    pub fn with_tracestate(mut self, tracestate: impl Into<String>) -> Self {
+        self.tracestate = Some(tracestate.into()).filter(|state| !state.trim().is_empty());
+        self
+    }
+
+    pub fn traceparent(&self) -> &str {
+        &self.traceparent
+    }
+
+    pub fn tracestate(&self) -> Option<&str> {
+        self.tracestate.as_deref()
+    }
+
+    /// The 32 hex digit trace id
+    pub fn trace_id(&self) -> &str {
+        &self.traceparent[3..35]
+    }
+
+    /// Whether the caller sampled the trace (flag `01`)
+    pub fn sampled(&self) -> bool {
+        u8::from_str_radix(&self.traceparent[53..55], 16).is_ok_and(|flags| flags & 1 == 1)
+    }
+
+    /// Set `traceparent` and `tracestate`, replacing earlier values
+    pub(crate) fn apply(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.traceparent) {
+            headers.insert("traceparent", value);
+        }
+        match self
+            .tracestate
+            .as_deref()
+            .and_then(|state| HeaderValue::from_str(state).ok())
+        {
+            Some(value) => {
+                headers.insert("tracestate", value);
+            }
+            None => {
+                headers.remove("tracestate");
+            }
+        }
+    }
+}
+
+cfg_opentelemetry! {
+    impl TraceContext {
+        /// The OpenTelemetry context of the current `tracing` span, with its
+        /// `tracestate`
+        ///
+        /// Meant for [`trace_context_provider`](crate::client::WechatClientBuilder::trace_context_provider).
+        /// Returns `None` outside a span, or when no `tracing-opentelemetry`
+        /// layer gives the span a valid context.
+        pub fn from_current_span() -> Option<Self> {
+            use opentelemetry::trace::TraceContextExt;
+            use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+            let context = tracing::Span::current().context();
+            let span = context.span();
+            let span_context = span.span_context();
+            if !span_context.is_valid() {
+                return None;
+            }
+            let trace = Self::new(
+                u128::from_be_bytes(span_context.trace_id().to_bytes()),
+                u64::from_be_bytes(span_context.span_id().to_bytes()),
+                span_context.is_sampled(),
+            )
+            .ok()?;
+            Some(trace.with_tracestate(span_context.trace_state().header()))
+        }
+    }
+}
+
+fn is_hex(field: &str, len: usize) -> bool {
+    field.len() == len
+        && field
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+}
+
+fn is_zero(field: &str) -> bool {
+    field.bytes().all(|byte| byte == b'0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_context_builds_and_validates_traceparent() {
+        let context =
+            TraceContext::new(0x4bf92f3577b34da6a3ce929d0e0e4736, 0x00f067aa0ba902b7, true)
+                .unwrap()
+                .with_tracestate("rojo=00f067aa0ba902b7");
+        assert_eq!(
+            context.traceparent(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+        assert_eq!(context.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert!(context.sampled());
+
+        let mut headers = HeaderMap::new();
+        context.apply(&mut headers);
+        assert_eq!(headers["tracestate"], "rojo=00f067aa0ba902b7");
+
+        for invalid in [
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        ] {
+            assert!(TraceContext::parse(invalid).is_err(), "{invalid}");
+        }
+        assert!(TraceContext::parse(
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-future"
+        )
+        .is_ok_and(|context| !context.sampled()));
+    }
+
+    #[cfg(feature = "opentelemetry")]
+    #[test]
+    fn test_from_current_span_reads_opentelemetry_context() {
+        use opentelemetry::trace::{
+            SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState,
+        };
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let remote = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::from_key_value([("rojo", "00f067aa0ba902b7")]).unwrap(),
+        );
+        let subscriber = tracing_subscriber::registry().with(tracing_opentelemetry::layer());
+        tracing::subscriber::with_default(subscriber, || {
+            assert_eq!(TraceContext::from_current_span(), None);
+
+            let span = tracing::info_span!("wechat_call");
+            span.set_parent(opentelemetry::Context::new().with_remote_span_context(remote))
+                .unwrap();
+            let _entered = span.enter();
+            let context = TraceContext::from_current_span().expect("span has a context");
+            assert_eq!(context.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+            assert!(context.sampled());
+            assert_eq!(context.tracestate(), Some("rojo=00f067aa0ba902b7"));
+        });
+    }
+}
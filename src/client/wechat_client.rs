@@ -2,26 +2,61 @@
 //!
 //! Provides HTTP client wrapper for WeChat API calls.
 
+use bytes::Bytes;
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
-use reqwest::Client;
+use reqwest::{Client, ResponseBuilderExt};
 use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::time::Duration;
-use tower::Service;
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
 
-use crate::error::{HttpError, WechatError};
+use super::call_options::{scoped_api_version, scoped_base_url, scoped_trace_context};
+use super::call_report::{publish, record_attempt, track_attempts, CallReport, CallReportHook};
+#[cfg(feature = "charset")]
+use super::charset::ResponseCharset;
+#[cfg(feature = "gzip")]
+use super::compression::Compression;
+use super::mode::Mode;
+#[cfg(all(feature = "rustls-tls", not(feature = "native-tls")))]
+use super::network_timing::timing_tls_config;
+use super::network_timing::{TimingConnectLayer, TimingResolver};
+use super::stats::{
+    ClientStats, CountingBody, DecodeStats, TrafficCounters, TrafficStats, UNCATEGORIZED,
+};
+use super::support_bundle::{redact_url, ErrcodeLog};
+use super::trace_context::{TraceContext, TraceContextProvider};
+use super::{ErrorSniffing, GatewayErrcodes};
+use crate::api::endpoint_inventory::{
+    category_for_path, endpoint_by_id, is_mutating_path, timeout_class_for_path, ApiVersion,
+    TimeoutClass,
+};
+use crate::api::qrcode::EnvVersion;
+use crate::error::WechatError;
+use crate::limit::RateLimiter;
 use crate::types::{AppId, AppSecret};
+use crate::utils::{
+    api_error_from_value, binary_body, binary_body_parts, decode_json, decode_json_value,
+    BinaryBody,
+};
 
 pub(crate) const DEFAULT_BASE_URL: &str = "https://api.weixin.qq.com";
 pub(crate) const DEFAULT_TIMEOUT_SECS: u64 = 30;
 pub(crate) const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+pub(crate) const DEFAULT_FAST_TIMEOUT_SECS: u64 = 10;
+pub(crate) const DEFAULT_SLOW_TIMEOUT_SECS: u64 = 60;
 
 type MiddlewareFuture =
     Pin<Box<dyn Future<Output = Result<reqwest::Response, reqwest::Error>> + Send>>;
 type MiddlewareExecutor = Arc<dyn Fn(reqwest::Request) -> MiddlewareFuture + Send + Sync>;
+/// Wraps a freshly built client in the configured layer stack.
+type MiddlewareFactory = Arc<dyn Fn(WechatClient) -> MiddlewareExecutor + Send + Sync>;
 
 /// WeChat API Client
 ///
@@ -31,9 +66,49 @@ type MiddlewareExecutor = Arc<dyn Fn(reqwest::Request) -> MiddlewareFuture + Sen
 pub struct WechatClient {
     http: Client,
     appid: AppId,
-    secret: AppSecret,
-    base_url: String,
+    secret: Option<AppSecret>,
+    /// Parsed base URL; its path always ends with `/`
+    base_url: reqwest::Url,
+    allow_insecure_base_url: bool,
+    class_timeouts: ClassTimeouts,
     middleware_executor: Option<MiddlewareExecutor>,
+    call_report_hook: Option<CallReportHook>,
+    #[cfg(feature = "gzip")]
+    compression: Compression,
+    #[cfg(feature = "charset")]
+    response_charset: ResponseCharset,
+    error_sniffing: ErrorSniffing,
+    gateway_errcodes: GatewayErrcodes,
+    env_version: Option<EnvVersion>,
+    rate_limiter: Option<RateLimiter>,
+    network_timing: bool,
+    trace_context: Option<TraceContextSource>,
+    blocking_decode_threshold: Option<usize>,
+    api_versions: Arc<HashMap<String, ApiVersion>>,
+    traffic: TrafficCounters,
+    mode: Arc<AtomicU8>,
+    middleware_name: Option<&'static str>,
+    errcodes: ErrcodeLog,
+}
+
+/// Where propagated trace contexts come from; see [`TraceContext`].
+#[derive(Clone)]
+struct TraceContextSource {
+    provider: Option<TraceContextProvider>,
+}
+
+impl TraceContextSource {
+    fn current(&self) -> Option<TraceContext> {
+        scoped_trace_context().or_else(|| self.provider.as_ref().and_then(|provider| provider()))
+    }
+}
+
+/// Resolved request timeout for each [`TimeoutClass`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ClassTimeouts {
+    fast: Duration,
+    normal: Duration,
+    slow: Duration,
 }
 
 impl std::fmt::Debug for WechatClient {
@@ -41,10 +116,15 @@ impl std::fmt::Debug for WechatClient {
         f.debug_struct("WechatClient")
             .field("appid", &self.appid)
             .field("base_url", &self.base_url)
+            .field("class_timeouts", &self.class_timeouts)
             .field(
                 "middleware_executor",
                 &self.middleware_executor.as_ref().map(|_| ".."),
             )
+            .field(
+                "call_report_hook",
+                &self.call_report_hook.as_ref().map(|_| ".."),
+            )
             .finish_non_exhaustive()
     }
 }
@@ -61,81 +141,398 @@ impl WechatClient {
     }
 
     /// Get the app secret
-    pub(crate) fn secret(&self) -> &str {
-        self.secret.as_str()
+    ///
+    /// Fails for clients built around an access token provider, which never
+    /// see the secret.
+    pub(crate) fn secret(&self) -> Result<&str, WechatError> {
+        self.secret.as_ref().map(AppSecret::as_str).ok_or_else(|| {
+            WechatError::Config(
+                "no AppSecret configured: this client gets access tokens from a provider"
+                    .to_string(),
+            )
+        })
     }
 
     /// Get the base URL
+    /// Rewrite errors carrying a registered gateway errcode.
+    ///
+    /// Errcode errors are also kept for the support bundle.
+    pub(crate) fn classify_error(&self, error: WechatError) -> WechatError {
+        let error = self.gateway_errcodes.classify(error);
+        self.errcodes.record(&error);
+        error
+    }
+
+    /// Redacted client configuration and recent errcodes, for
+    /// [`WechatMp::support_bundle`](super::WechatMp::support_bundle)
+    pub(crate) fn support_info(&self) -> serde_json::Value {
+        serde_json::json!({
+            "appid": self.appid.as_str(),
+            "secret_configured": self.secret.is_some(),
+            "base_url": redact_url(self.base_url()),
+            "timeouts_ms": {
+                "fast": self.class_timeouts.fast.as_millis() as u64,
+                "normal": self.class_timeouts.normal.as_millis() as u64,
+                "slow": self.class_timeouts.slow.as_millis() as u64,
+            },
+            "middleware": self.middleware_name,
+            "mode": format!("{:?}", self.mode()),
+            "rate_limited": self.rate_limiter.is_some(),
+            "network_timing": self.network_timing,
+            "blocking_decode_threshold": self.blocking_decode_threshold,
+            "error_sniffing": format!("{:?}", self.error_sniffing),
+            "recent_errcodes": self.errcodes.to_json(),
+        })
+    }
+
+    /// Read a binary response body using the configured [`ErrorSniffing`]
+    /// policy and gateway errcodes.
+    pub(crate) async fn binary_body(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<Bytes, WechatError> {
+        binary_body(response, self.error_sniffing)
+            .await
+            .map_err(|error| self.classify_error(error))
+    }
+
+    /// Like [`binary_body`](Self::binary_body), keeping the content headers
+    pub(crate) async fn binary_body_parts(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<BinaryBody, WechatError> {
+        binary_body_parts(response, self.error_sniffing)
+            .await
+            .map_err(|error| self.classify_error(error))
+    }
+
+    /// Default mini program version for generated codes, if configured
+    pub fn env_version(&self) -> Option<EnvVersion> {
+        self.env_version
+    }
+
+    /// Bytes sent and received and JSON decode time so far, by endpoint
+    /// category
+    ///
+    /// Clones of this client share the counters.
+    pub fn stats(&self) -> ClientStats {
+        self.traffic.snapshot()
+    }
+
+    /// Reset the counters behind [`stats`](Self::stats)
+    pub fn reset_stats(&self) {
+        self.traffic.reset();
+    }
+
+    /// Current [`Mode`]; clones of this client share it
+    pub fn mode(&self) -> Mode {
+        Mode::from_u8(self.mode.load(Ordering::Relaxed))
+    }
+
+    /// Switch every clone of this client to `mode`, effective for the next
+    /// request
+    pub fn set_mode(&self, mode: Mode) {
+        self.mode.store(mode as u8, Ordering::Relaxed);
+    }
+
+    /// Path of `url` relative to the base URL in effect
+    fn relative_path(&self, url: &reqwest::Url) -> String {
+        let base_url = self
+            .effective_base_url()
+            .unwrap_or(Cow::Borrowed(&self.base_url));
+        let base_path = base_url.path().trim_end_matches('/');
+        let path = url.path();
+        path.strip_prefix(base_path).unwrap_or(path).to_string()
+    }
+
+    /// Base URL of the enclosing [`CallOptions`](super::CallOptions) scope,
+    /// or the configured one
+    fn effective_base_url(&self) -> Result<Cow<'_, reqwest::Url>, WechatError> {
+        match scoped_base_url() {
+            Some(raw) => parse_base_url(&raw, self.allow_insecure_base_url).map(Cow::Owned),
+            None => Ok(Cow::Borrowed(&self.base_url)),
+        }
+    }
+
+    /// Version to call of the inventory endpoint `endpoint_id`
+    ///
+    /// The enclosing [`CallOptions`](super::CallOptions) scope wins over
+    /// [`WechatClientBuilder::api_version`], which wins over the inventory
+    /// default.
+    pub fn api_version(&self, endpoint_id: &str) -> ApiVersion {
+        scoped_api_version(endpoint_id)
+            .or_else(|| self.api_versions.get(endpoint_id).copied())
+            .or_else(|| endpoint_by_id(endpoint_id).map(|item| item.version))
+            .unwrap_or_default()
+    }
+
+    /// Limiter every request waits on, if configured
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Configured base URL, without a trailing slash
     pub fn base_url(&self) -> &str {
-        &self.base_url
+        self.base_url.as_str().trim_end_matches('/')
+    }
+
+    /// Full URL of an API `path` (e.g. `/wxa/getwxadevinfo?access_token=..`)
+    ///
+    /// The path is resolved below the base URL's own path, so a base of
+    /// `https://gateway.example.com/wechat/` keeps its `/wechat` prefix. A
+    /// [`CallOptions::base_url`](super::CallOptions::base_url) scope replaces
+    /// the configured base.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Config`] if `path` cannot be joined or the
+    /// scoped base URL is invalid.
+    pub fn url_for(&self, path: &str) -> Result<reqwest::Url, WechatError> {
+        self.effective_base_url()?
+            .join(path.trim_start_matches('/'))
+            .map_err(|e| WechatError::Config(format!("invalid request path {path:?}: {e}")))
+    }
+
+    /// Get the request timeout applied to endpoints of the given class
+    pub fn class_timeout(&self, class: TimeoutClass) -> Duration {
+        match class {
+            TimeoutClass::Fast => self.class_timeouts.fast,
+            TimeoutClass::Normal => self.class_timeouts.normal,
+            TimeoutClass::Slow => self.class_timeouts.slow,
+        }
+    }
+
+    /// Resolve the request timeout for a path via the endpoint inventory.
+    pub(crate) fn timeout_for_path(&self, path: &str) -> Duration {
+        self.class_timeout(timeout_class_for_path(path))
     }
 
+    /// `path` with the percent-encoded token filled into an
+    /// `access_token={}` placeholder or appended, built in one allocation
     pub(crate) fn append_access_token(path: &str, access_token: &str) -> String {
         let encoded = utf8_percent_encode(access_token, NON_ALPHANUMERIC);
+        let mut authed = String::with_capacity(path.len() + 14 + access_token.len() * 3);
 
-        if path.contains("access_token={}") {
-            return path.replacen("access_token={}", &format!("access_token={encoded}"), 1);
+        if let Some((head, tail)) = path.split_once("access_token={}") {
+            authed.push_str(head);
+            authed.push_str("access_token=");
+            authed.extend(encoded);
+            authed.push_str(tail);
+            return authed;
         }
 
-        let separator = if path.contains('?') { '&' } else { '?' };
-        format!("{path}{separator}access_token={encoded}")
+        authed.push_str(path);
+        authed.push(if path.contains('?') { '&' } else { '?' });
+        authed.push_str("access_token=");
+        authed.extend(encoded);
+        authed
     }
 
     /// Returns the underlying [`reqwest::Client`] for raw HTTP requests.
     ///
-    /// Note: requests made through this client bypass the middleware pipeline.
-    /// Use [`get`](Self::get) or [`post`](Self::post) for middleware-aware requests.
+    /// Note: requests made through this client bypass the middleware stack
+    /// installed with [`WechatClientBuilder::with_middleware`]. Use
+    /// [`get`](Self::get) or [`post`](Self::post) for middleware-aware requests.
     pub fn http(&self) -> &Client {
         &self.http
     }
 
+    /// Attach `body` as JSON, applying the configured request compression.
+    pub(crate) fn json_body<B: serde::Serialize + ?Sized>(
+        &self,
+        request: reqwest::RequestBuilder,
+        body: &B,
+    ) -> Result<reqwest::RequestBuilder, WechatError> {
+        #[cfg(feature = "gzip")]
+        {
+            let (bytes, compressed) = self.compression.encode_json(body)?;
+            let request = request
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(bytes);
+            if compressed {
+                return Ok(request.header(reqwest::header::CONTENT_ENCODING, "gzip"));
+            }
+            Ok(request)
+        }
+        #[cfg(not(feature = "gzip"))]
+        {
+            Ok(request.json(body))
+        }
+    }
+
     pub(crate) fn with_middleware_executor(mut self, executor: MiddlewareExecutor) -> Self {
         self.middleware_executor = Some(executor);
         self
     }
 
+    /// Send a request to a host other than the WeChat API, e.g. signed
+    /// cloud storage or CDN URLs
+    ///
+    /// Goes straight to the transport: no middleware (which could append the
+    /// access token), read-only check, rate limiter, trace headers or
+    /// [`CallReport`].
+    pub(crate) async fn send_external(
+        &self,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response, WechatError> {
+        Ok(self.http.execute(request).await?)
+    }
+
+    /// Send a request through the middleware pipeline and emit its [`CallReport`].
     pub(crate) async fn send_request(
         &self,
         request: reqwest::Request,
-    ) -> Result<reqwest::Response, reqwest::Error> {
-        if let Some(executor) = &self.middleware_executor {
-            (executor)(request).await
-        } else {
-            self.http.execute(request).await
+    ) -> Result<reqwest::Response, WechatError> {
+        let mut request = request;
+        if let Some(context) = self
+            .trace_context
+            .as_ref()
+            .and_then(TraceContextSource::current)
+        {
+            context.apply(request.headers_mut());
+        }
+        let path = self.relative_path(request.url());
+        if self.mode() == Mode::ReadOnly && is_mutating_path(&path) {
+            return Err(WechatError::ReadOnly { path });
         }
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        let method = request.method().clone();
+        let url = request.url().clone();
+        let body_len = request
+            .body()
+            .and_then(reqwest::Body::as_bytes)
+            .map(<[u8]>::len)
+            .or_else(|| content_length(request.headers()))
+            .unwrap_or(0) as u64;
+        let started = Instant::now();
+
+        let (result, log) = track_attempts(async {
+            if let Some(executor) = &self.middleware_executor {
+                (executor)(request).await
+            } else {
+                record_attempt(&request);
+                self.http.execute(request).await
+            }
+        })
+        .await;
+
+        let headers_at = Instant::now();
+        let latency = headers_at - started;
+        let status = match &result {
+            Ok(response) => Some(response.status().as_u16()),
+            Err(error) => error.status().map(|status| status.as_u16()),
+        };
+        let category = category_for_path(&path).unwrap_or(UNCATEGORIZED);
+        let mut timing = None;
+        let mut bytes_received = 0;
+        let result = match result {
+            // HEAD responses carry a Content-Length but no body.
+            Ok(response) if method == reqwest::Method::HEAD => Ok(response),
+            // Timing the body means reading it before the report goes out.
+            Ok(response) if self.network_timing => {
+                let wire_len = response.content_length();
+                let buffered = buffer_response(response).await;
+                if let Ok(response) = &buffered {
+                    bytes_received = wire_len.or(response.content_length()).unwrap_or(0);
+                }
+                timing = Some(log.timing(headers_at, headers_at.elapsed()));
+                buffered
+            }
+            Ok(response) => match response.content_length() {
+                Some(wire_len) => {
+                    bytes_received = wire_len;
+                    Ok(response)
+                }
+                // Without a length, count the body as the caller reads it.
+                None => Ok(map_response_body(response, |body| {
+                    reqwest::Body::wrap(CountingBody::new(body, self.traffic.clone(), category))
+                })),
+            },
+            Err(error) => Err(error),
+        };
+        let attempts = u64::from(log.attempts());
+        self.traffic.record(
+            category,
+            TrafficStats {
+                requests: attempts,
+                bytes_sent: body_len * attempts,
+                bytes_received,
+            },
+        );
+        let mut report = CallReport::new(&method, &url, log, latency, status);
+        report.timing = timing;
+        if let Some(hook) = &self.call_report_hook {
+            hook(&report);
+        }
+        publish(&report);
+
+        Ok(result?)
     }
 
-    async fn execute<T: DeserializeOwned>(
+    /// Read a JSON response body, transcoded to UTF-8 by the configured
+    /// `ResponseCharset` when the `charset` feature is enabled
+    pub(crate) async fn json_response_body(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<Bytes, WechatError> {
+        #[cfg(feature = "charset")]
+        {
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned);
+            let body = response.bytes().await?;
+            let transcoded = match self
+                .response_charset
+                .transcode(content_type.as_deref(), &body)
+            {
+                Cow::Borrowed(_) => None,
+                Cow::Owned(utf8) => Some(Bytes::from(utf8)),
+            };
+            Ok(transcoded.unwrap_or(body))
+        }
+        #[cfg(not(feature = "charset"))]
+        Ok(response.bytes().await?)
+    }
+
+    async fn execute<T: DeserializeOwned + Send + 'static>(
         &self,
         request: reqwest::Request,
     ) -> Result<T, WechatError> {
+        let category = category_for_path(&self.relative_path(request.url()));
         let response = self.send_request(request).await?;
 
         if let Err(e) = response.error_for_status_ref() {
             return Err(e.into());
         }
 
-        let value: serde_json::Value = response.json().await?;
-
-        if let Some(errcode) = value.get("errcode").and_then(|v| v.as_i64()) {
-            if errcode != 0 {
-                let errmsg = value
-                    .get("errmsg")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("unknown error");
-                return Err(WechatError::Api {
-                    code: errcode.try_into().unwrap_or(i32::MAX),
-                    message: errmsg.to_string(),
-                });
-            }
-        }
+        let body = self.json_response_body(response).await?;
+        let len = body.len();
+        let offload = self
+            .blocking_decode_threshold
+            .is_some_and(|threshold| len >= threshold);
+        let started = Instant::now();
+        let decoded = if offload {
+            blocking_decode_result(
+                tokio::task::spawn_blocking(move || decode_body::<T>(&body)).await,
+            )?
+        } else {
+            decode_body(&body)
+        };
+        self.traffic.record_decode(
+            category.unwrap_or(UNCATEGORIZED),
+            DecodeStats::single(len, started.elapsed(), offload),
+        );
 
-        serde_json::from_value(value)
-            .map_err(|e| WechatError::Http(HttpError::Decode(e.to_string())))
+        decoded?.map_err(|error| self.classify_error(error))
     }
 
     /// Make a GET request to WeChat API
     ///
+    /// The request timeout follows the endpoint's [`TimeoutClass`].
+    ///
     /// # Arguments
     /// * `path` - API endpoint path (e.g., "/cgi-bin/token")
     /// * `query` - Query parameters as key-value pairs
@@ -144,20 +541,28 @@ impl WechatClient {
     /// Deserialized response of type T
     ///
     /// # Errors
-    /// - Returns `WechatError::Http` for non-2xx HTTP status codes or decode failures
+    /// - Returns `WechatError::Http` for non-2xx HTTP status codes or transport failures
+    /// - Returns `WechatError::Decode` when the body does not match `T`
     /// - Returns `WechatError::Api` when WeChat API returns errcode != 0
-    pub async fn get<T: DeserializeOwned>(
+    pub async fn get<T: DeserializeOwned + Send + 'static>(
         &self,
         path: &str,
         query: &[(&str, &str)],
     ) -> Result<T, WechatError> {
-        let url = format!("{}{}", self.base_url, path);
-        let request = self.http.get(url).query(query).build()?;
+        let url = self.url_for(path)?;
+        let request = self
+            .http
+            .get(url)
+            .query(query)
+            .timeout(self.timeout_for_path(path))
+            .build()?;
         self.execute(request).await
     }
 
     /// Make a POST request to WeChat API
     ///
+    /// The request timeout follows the endpoint's [`TimeoutClass`].
+    ///
     /// # Arguments
     /// * `path` - API endpoint path (e.g., "/wxa/getwxadevinfo")
     /// * `body` - Request body to serialize as JSON
@@ -166,17 +571,78 @@ impl WechatClient {
     /// Deserialized response of type T
     ///
     /// # Errors
-    /// - Returns `WechatError::Http` for non-2xx HTTP status codes or decode failures
+    /// - Returns `WechatError::Http` for non-2xx HTTP status codes or transport failures
+    /// - Returns `WechatError::Decode` when the body does not match `T`
     /// - Returns `WechatError::Api` when WeChat API returns errcode != 0
-    pub async fn post<T: DeserializeOwned, B: serde::Serialize>(
+    pub async fn post<T: DeserializeOwned + Send + 'static, B: serde::Serialize>(
         &self,
         path: &str,
         body: &B,
     ) -> Result<T, WechatError> {
-        let url = format!("{}{}", self.base_url, path);
-        let request = self.http.post(url).json(body).build()?;
+        let url = self.url_for(path)?;
+        let request = self
+            .json_body(self.http.post(url), body)?
+            .timeout(self.timeout_for_path(path))
+            .build()?;
         self.execute(request).await
     }
+
+    /// Make a POST request with a raw body and explicit content type
+    ///
+    /// For endpoints that take binary or otherwise non-JSON payloads, e.g.
+    /// `application/octet-stream`. The response body is returned as
+    /// reference-counted [`Bytes`], without copying.
+    ///
+    /// # Errors
+    /// - Returns `WechatError::Http` for non-2xx HTTP status codes or transport failures
+    /// - Returns `WechatError::Api` when the response is a JSON body with errcode != 0
+    pub async fn post_bytes(
+        &self,
+        path: &str,
+        content_type: &str,
+        body: impl Into<Bytes>,
+    ) -> Result<Bytes, WechatError> {
+        let url = self.url_for(path)?;
+        let request = self
+            .http
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body.into())
+            .timeout(self.timeout_for_path(path))
+            .build()?;
+        self.binary_body(self.send_request(request).await?).await
+    }
+
+    /// Make a POST request with an `application/x-www-form-urlencoded` body
+    ///
+    /// The response body is returned as bytes, with the same error detection
+    /// as [`post_bytes`](Self::post_bytes).
+    pub async fn post_form(
+        &self,
+        path: &str,
+        fields: &[(&str, &str)],
+    ) -> Result<Bytes, WechatError> {
+        self.post_bytes(
+            path,
+            "application/x-www-form-urlencoded",
+            encode_form(fields),
+        )
+        .await
+    }
+}
+
+fn encode_form(fields: &[(&str, &str)]) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                utf8_percent_encode(key, NON_ALPHANUMERIC),
+                utf8_percent_encode(value, NON_ALPHANUMERIC)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&")
 }
 
 impl Service<reqwest::Request> for WechatClient {
@@ -189,6 +655,7 @@ impl Service<reqwest::Request> for WechatClient {
     }
 
     fn call(&mut self, req: reqwest::Request) -> Self::Future {
+        record_attempt(&req);
         let client = self.http.clone();
         Box::pin(async move { client.execute(req).await })
     }
@@ -215,13 +682,55 @@ impl Service<reqwest::Request> for WechatClient {
 ///     Ok(())
 /// }
 /// ```
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct WechatClientBuilder {
     appid: Option<AppId>,
     secret: Option<AppSecret>,
     base_url: Option<String>,
+    allow_insecure_base_url: bool,
     timeout: Option<Duration>,
     connect_timeout: Option<Duration>,
+    fast_timeout: Option<Duration>,
+    slow_timeout: Option<Duration>,
+    call_report_hook: Option<CallReportHook>,
+    #[cfg(feature = "gzip")]
+    compression: Option<Compression>,
+    #[cfg(feature = "charset")]
+    response_charset: ResponseCharset,
+    error_sniffing: ErrorSniffing,
+    gateway_errcodes: GatewayErrcodes,
+    env_version: Option<EnvVersion>,
+    rate_limiter: Option<RateLimiter>,
+    network_timing: bool,
+    propagate_trace_context: bool,
+    trace_context_provider: Option<TraceContextProvider>,
+    dns_overrides: Vec<(String, SocketAddr)>,
+    blocking_decode_threshold: Option<usize>,
+    api_versions: HashMap<String, ApiVersion>,
+    secret_optional: bool,
+    middleware: Option<MiddlewareFactory>,
+    middleware_name: Option<&'static str>,
+}
+
+impl std::fmt::Debug for WechatClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WechatClientBuilder")
+            .field("appid", &self.appid)
+            .field("secret", &self.secret)
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("fast_timeout", &self.fast_timeout)
+            .field("slow_timeout", &self.slow_timeout)
+            .field("propagate_trace_context", &self.propagate_trace_context)
+            .field("dns_overrides", &self.dns_overrides)
+            .field(
+                "call_report_hook",
+                &self.call_report_hook.as_ref().map(|_| ".."),
+            )
+            .field("middleware", &self.middleware.as_ref().map(|_| ".."))
+            .finish_non_exhaustive()
+    }
 }
 
 impl WechatClientBuilder {
@@ -239,20 +748,51 @@ impl WechatClientBuilder {
 
     /// Set the base URL for API calls
     ///
+    /// Must be `https`, except for loopback hosts (`localhost`, `127.0.0.1`,
+    /// `[::1]`) used by tests; see
+    /// [`allow_insecure_base_url`](Self::allow_insecure_base_url). A path
+    /// prefix such as `https://gateway.example.com/wechat` is kept for every
+    /// endpoint.
+    ///
     /// Default: `<https://api.weixin.qq.com>`
     pub fn base_url(mut self, url: impl Into<String>) -> Self {
         self.base_url = Some(url.into());
         self
     }
 
+    /// Accept a plain `http` base URL on non-loopback hosts
+    ///
+    /// Default: `false`
+    pub fn allow_insecure_base_url(mut self, allow: bool) -> Self {
+        self.allow_insecure_base_url = allow;
+        self
+    }
+
     /// Set the total timeout for requests
     ///
+    /// This is the timeout of [`TimeoutClass::Normal`] endpoints.
+    ///
     /// Default: 30 seconds
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.timeout = Some(timeout);
         self
     }
 
+    /// Set the request timeout for endpoints of a [`TimeoutClass`]
+    ///
+    /// Setting [`TimeoutClass::Normal`] is equivalent to [`timeout`](Self::timeout).
+    ///
+    /// Default: 10 seconds for `Fast` (capped at the total timeout) and
+    /// 60 seconds for `Slow` (raised to the total timeout if that is longer)
+    pub fn class_timeout(mut self, class: TimeoutClass, timeout: Duration) -> Self {
+        match class {
+            TimeoutClass::Fast => self.fast_timeout = Some(timeout),
+            TimeoutClass::Normal => self.timeout = Some(timeout),
+            TimeoutClass::Slow => self.slow_timeout = Some(timeout),
+        }
+        self
+    }
+
     /// Set the connection timeout
     ///
     /// Default: 10 seconds
@@ -261,6 +801,221 @@ impl WechatClientBuilder {
         self
     }
 
+    /// Register a hook receiving the [`CallReport`] of every request
+    ///
+    /// Useful for feeding SLO dashboards with attempt counts and latency.
+    pub fn on_call_report<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&CallReport) + Send + Sync + 'static,
+    {
+        self.call_report_hook = Some(Arc::new(hook));
+        self
+    }
+
+    pub(crate) fn call_report_hook(mut self, hook: Option<CallReportHook>) -> Self {
+        self.call_report_hook = hook;
+        self
+    }
+
+    /// Allow building without a secret, for clients whose tokens come from
+    /// an access token provider.
+    pub(crate) fn secret_optional(mut self) -> Self {
+        self.secret_optional = true;
+        self
+    }
+
+    /// Set how binary endpoints detect JSON error bodies
+    ///
+    /// Default: [`ErrorSniffing::Auto`]
+    pub fn error_sniffing(mut self, policy: ErrorSniffing) -> Self {
+        self.error_sniffing = policy;
+        self
+    }
+
+    /// Set the mini program version that generated codes open by default
+    ///
+    /// Applies to `get_wxa_code` and `get_wxa_code_unlimit` calls whose
+    /// options leave `env_version` unset. Default: unset (WeChat uses
+    /// [`EnvVersion::Release`]).
+    pub fn env_version(mut self, env_version: EnvVersion) -> Self {
+        self.env_version = Some(env_version);
+        self
+    }
+
+    /// Pace every request through a shared [`RateLimiter`]
+    ///
+    /// Requests wait for a permit before being sent; clones of `limiter` held
+    /// by the application draw from the same budget.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Record DNS, connect, TTFB and body timing in every [`CallReport`]
+    ///
+    /// Installs a timing DNS resolver and connector (with `rustls-tls`, also
+    /// the client's own rustls configuration, to time TLS handshakes), and
+    /// includes the response body download in the report. See
+    /// [`NetworkTiming`](super::NetworkTiming). Default: off.
+    pub fn network_timing(mut self, enabled: bool) -> Self {
+        self.network_timing = enabled;
+        self
+    }
+
+    /// Send `traceparent`/`tracestate` headers with every request
+    ///
+    /// The context comes from the enclosing
+    /// [`CallOptions::trace_context`](super::CallOptions::trace_context)
+    /// scope, or else from the
+    /// [`trace_context_provider`](Self::trace_context_provider); requests
+    /// with neither go out without the headers. See [`TraceContext`].
+    /// Default: off.
+    pub fn propagate_trace_context(mut self, enabled: bool) -> Self {
+        self.propagate_trace_context = enabled;
+        self
+    }
+
+    /// Read the caller's trace context when a request is sent, e.g. from
+    /// the current `tracing` span with `TraceContext::from_current_span`
+    /// (`opentelemetry` feature); enables
+    /// [`propagate_trace_context`](Self::propagate_trace_context)
+    ///
+    /// The provider runs on the task sending the request, once per request
+    /// (not per middleware retry).
+    pub fn trace_context_provider<F>(mut self, provider: F) -> Self
+    where
+        F: Fn() -> Option<TraceContext> + Send + Sync + 'static,
+    {
+        self.trace_context_provider = Some(Arc::new(provider));
+        self.propagate_trace_context = true;
+        self
+    }
+
+    pub(crate) fn trace_context_source(
+        mut self,
+        enabled: bool,
+        provider: Option<TraceContextProvider>,
+    ) -> Self {
+        self.propagate_trace_context = enabled;
+        self.trace_context_provider = provider;
+        self
+    }
+
+    /// Connect to `addr` instead of resolving `host` through DNS
+    ///
+    /// Pins hosts such as `api.weixin.qq.com` to approved egress IPs in
+    /// air-gapped or split-DNS networks. Calling it again for the same host
+    /// adds an address; the connector tries them in order. TLS still
+    /// verifies the certificate against `host`.
+    ///
+    /// Overrides match the host of each request URL, so they follow
+    /// [`base_url`](Self::base_url) and per-call
+    /// [`CallOptions::base_url`](super::CallOptions::base_url): every
+    /// gateway or alternate WeChat host calls may switch to needs its own
+    /// entry, otherwise it is resolved normally. The port of `addr` is
+    /// ignored in favour of the URL's. Pinned hosts skip the
+    /// [`network_timing`](Self::network_timing) resolver, so their reports
+    /// carry no DNS time.
+    pub fn resolve(mut self, host: impl Into<String>, addr: SocketAddr) -> Self {
+        self.dns_overrides
+            .push((host.into().to_ascii_lowercase(), addr));
+        self
+    }
+
+    /// Deserialize JSON bodies of at least `bytes` on the blocking thread
+    /// pool
+    ///
+    /// Multi-megabyte responses (e.g. analytics backfills) otherwise parse
+    /// on the async worker that awaited them. Decode time is reported in
+    /// [`ClientStats::decoding`] either way. Default: off.
+    pub fn blocking_decode_threshold(mut self, bytes: usize) -> Self {
+        self.blocking_decode_threshold = Some(bytes);
+        self
+    }
+
+    /// Pin the inventory endpoint `endpoint_id` to `version`
+    ///
+    /// For endpoints with v1/v2 variants (see
+    /// [`EndpointInventoryItem::versioned`](crate::api::endpoint_inventory::EndpointInventoryItem::versioned)),
+    /// e.g. to stay on v1 during a WeChat migration window.
+    /// [`CallOptions`](super::CallOptions) override this per call.
+    ///
+    /// `build` fails for endpoints that have no versions.
+    pub fn api_version(mut self, endpoint_id: impl Into<String>, version: ApiVersion) -> Self {
+        self.api_versions.insert(endpoint_id.into(), version);
+        self
+    }
+
+    /// Register errcodes that a gateway in front of WeChat uses for its own
+    /// failures
+    ///
+    /// Matching errcodes surface as the retryable [`WechatError::Gateway`]
+    /// instead of [`WechatError::Api`].
+    pub fn gateway_errcodes(mut self, gateway_errcodes: GatewayErrcodes) -> Self {
+        self.gateway_errcodes = gateway_errcodes;
+        self
+    }
+
+    cfg_charset! {
+        /// Set how JSON responses that are not UTF-8 are decoded
+        ///
+        /// Default: [`ResponseCharset::default`], transcoding from the
+        /// declared charset or GB18030
+        pub fn response_charset(mut self, charset: ResponseCharset) -> Self {
+            self.response_charset = charset;
+            self
+        }
+    }
+
+    cfg_gzip! {
+        /// Set request and response body compression
+        ///
+        /// Default: responses decompressed, requests sent uncompressed
+        pub fn compression(mut self, compression: Compression) -> Self {
+            self.compression = Some(compression);
+            self
+        }
+    }
+
+    /// Route every API call through a Tower layer stack
+    ///
+    /// `layer` wraps the client's own transport, so rate limiting, audit or
+    /// circuit-breaker layers see each HTTP attempt made by `get`, `post`,
+    /// media uploads and token fetches. Compose several layers with
+    /// [`ServiceBuilder`](tower::ServiceBuilder); the first layer added is
+    /// the outermost. Calling this again replaces the previous stack.
+    ///
+    /// ```
+    /// use tower::ServiceBuilder;
+    /// use wechat_mp_sdk::client::WechatClient;
+    /// use wechat_mp_sdk::middleware::LoggingMiddleware;
+    /// use wechat_mp_sdk::types::{AppId, AppSecret};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = WechatClient::builder()
+    ///     .appid(AppId::new("wx1234567890abcdef")?)
+    ///     .secret(AppSecret::new("abc1234567890abcdef")?)
+    ///     .with_middleware(ServiceBuilder::new().layer(LoggingMiddleware::new()))
+    ///     .build()?;
+    /// # let _ = client;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_middleware<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<WechatClient> + Send + Sync + 'static,
+        L::Service: Service<reqwest::Request, Response = reqwest::Response, Error = reqwest::Error>
+            + Send
+            + 'static,
+        <L::Service as Service<reqwest::Request>>::Future: Send + 'static,
+    {
+        self.middleware = Some(Arc::new(move |client| {
+            middleware_executor(layer.layer(client))
+        }));
+        self.middleware_name = Some(std::any::type_name::<L>());
+        self
+    }
+
     /// Build the WechatClient
     ///
     /// # Errors
@@ -269,13 +1024,25 @@ impl WechatClientBuilder {
         let appid = self
             .appid
             .ok_or_else(|| WechatError::Config("appid is required".to_string()))?;
-        let secret = self
-            .secret
-            .ok_or_else(|| WechatError::Config("secret is required".to_string()))?;
+        if self.secret.is_none() && !self.secret_optional {
+            return Err(WechatError::Config("secret is required".to_string()));
+        }
+        let secret = self.secret;
 
-        let base_url = self
-            .base_url
-            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        if let Some(endpoint_id) = self
+            .api_versions
+            .keys()
+            .find(|id| !endpoint_by_id(id).is_some_and(|item| item.versioned))
+        {
+            return Err(WechatError::Config(format!(
+                "endpoint {endpoint_id:?} has no selectable API versions"
+            )));
+        }
+
+        let base_url = parse_base_url(
+            self.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL),
+            self.allow_insecure_base_url,
+        )?;
 
         let timeout = self
             .timeout
@@ -284,25 +1051,255 @@ impl WechatClientBuilder {
             .connect_timeout
             .unwrap_or(Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS));
 
+        let class_timeouts = ClassTimeouts {
+            fast: self
+                .fast_timeout
+                .unwrap_or_else(|| timeout.min(Duration::from_secs(DEFAULT_FAST_TIMEOUT_SECS))),
+            normal: timeout,
+            slow: self
+                .slow_timeout
+                .unwrap_or_else(|| timeout.max(Duration::from_secs(DEFAULT_SLOW_TIMEOUT_SECS))),
+        };
+
+        #[cfg(feature = "gzip")]
+        let compression = self.compression.unwrap_or_default();
+
         let client = Client::builder()
             .timeout(timeout)
-            .connect_timeout(connect_timeout)
-            .build()?;
+            .connect_timeout(connect_timeout);
+        #[cfg(feature = "gzip")]
+        let client = client.gzip(compression.decompresses_responses());
+        let client = if self.network_timing {
+            client
+                .dns_resolver(Arc::new(TimingResolver))
+                .connector_layer(TimingConnectLayer)
+        } else {
+            client
+        };
+        #[cfg(all(feature = "rustls-tls", not(feature = "native-tls")))]
+        let client = if self.network_timing {
+            client.use_preconfigured_tls(timing_tls_config()?)
+        } else {
+            client
+        };
+        let mut pinned: Vec<(String, Vec<SocketAddr>)> = Vec::new();
+        for (host, addr) in self.dns_overrides {
+            match pinned
+                .iter_mut()
+                .find(|(pinned_host, _)| *pinned_host == host)
+            {
+                Some((_, addrs)) => addrs.push(addr),
+                None => pinned.push((host, vec![addr])),
+            }
+        }
+        let client = pinned.iter().fold(client, |client, (host, addrs)| {
+            client.resolve_to_addrs(host, addrs)
+        });
+        let client = client.build()?;
 
-        Ok(WechatClient {
+        let client = WechatClient {
             http: client,
             appid,
             secret,
             base_url,
+            allow_insecure_base_url: self.allow_insecure_base_url,
+            class_timeouts,
             middleware_executor: None,
+            call_report_hook: self.call_report_hook,
+            #[cfg(feature = "gzip")]
+            compression,
+            #[cfg(feature = "charset")]
+            response_charset: self.response_charset,
+            error_sniffing: self.error_sniffing,
+            gateway_errcodes: self.gateway_errcodes,
+            env_version: self.env_version,
+            rate_limiter: self.rate_limiter,
+            network_timing: self.network_timing,
+            trace_context: self.propagate_trace_context.then(|| TraceContextSource {
+                provider: self.trace_context_provider,
+            }),
+            blocking_decode_threshold: self.blocking_decode_threshold,
+            api_versions: Arc::new(self.api_versions),
+            traffic: TrafficCounters::default(),
+            mode: Arc::new(AtomicU8::new(Mode::Normal as u8)),
+            middleware_name: self.middleware_name,
+            errcodes: ErrcodeLog::default(),
+        };
+
+        Ok(match &self.middleware {
+            Some(factory) => {
+                let executor = factory(client.clone());
+                client.with_middleware_executor(executor)
+            }
+            None => client,
         })
     }
 }
 
+/// Decode a JSON response body; the inner error is a non-zero `errcode`.
+fn decode_body<T: DeserializeOwned>(body: &[u8]) -> Result<Result<T, WechatError>, WechatError> {
+    let value: serde_json::Value = decode_json(body)?;
+    if let Some(error) = api_error_from_value(&value) {
+        return Ok(Err(error));
+    }
+    decode_json_value(&value, body).map(Ok)
+}
+
+/// Output of a blocking decode task; its panics propagate, while a task
+/// cancelled by runtime shutdown becomes a [`WechatError::Decode`]
+fn blocking_decode_result<T>(joined: Result<T, tokio::task::JoinError>) -> Result<T, WechatError> {
+    match joined {
+        Ok(decoded) => Ok(decoded),
+        Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+        Err(error) => Err(WechatError::Decode {
+            path: String::new(),
+            snippet: String::new(),
+            message: format!("decode task cancelled: {error}"),
+        }),
+    }
+}
+
+fn content_length(headers: &http::HeaderMap) -> Option<usize> {
+    headers
+        .get(http::header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Read the body of `response` and return an equivalent, already buffered
+/// response, so the download is measured before the report is emitted.
+async fn buffer_response(response: reqwest::Response) -> Result<reqwest::Response, reqwest::Error> {
+    Ok(buffer_response_with_body(response).await?.0)
+}
+
+/// [`buffer_response`], also returning the buffered body
+pub(crate) async fn buffer_response_with_body(
+    response: reqwest::Response,
+) -> Result<(reqwest::Response, Bytes), reqwest::Error> {
+    let (mut parts, ()) = http::Response::new(()).into_parts();
+    parts.status = response.status();
+    parts.version = response.version();
+    parts.headers = response.headers().clone();
+    let url = response.url().clone();
+    let body = response.bytes().await?;
+
+    // The body is already decoded and its length may have changed.
+    parts.headers.remove(http::header::CONTENT_ENCODING);
+    parts.headers.remove(http::header::CONTENT_LENGTH);
+    let rebuilt = response_from_parts(parts, body.clone().into(), url);
+    Ok((rebuilt, body))
+}
+
+/// `response` with its body replaced by `map(body)`
+fn map_response_body(
+    response: reqwest::Response,
+    map: impl FnOnce(reqwest::Body) -> reqwest::Body,
+) -> reqwest::Response {
+    let url = response.url().clone();
+    let (parts, body) = http::Response::<reqwest::Body>::from(response).into_parts();
+    response_from_parts(parts, map(body), url)
+}
+
+/// A `reqwest::Response` from `parts` and `body` that reports `url`
+fn response_from_parts(
+    mut parts: http::response::Parts,
+    body: reqwest::Body,
+    url: reqwest::Url,
+) -> reqwest::Response {
+    // reqwest keeps the URL in an extension only `ResponseBuilderExt::url`
+    // can set; take it from a builder.
+    if let Some(extensions) = http::Response::builder().url(url).extensions_mut() {
+        parts.extensions.extend(std::mem::take(extensions));
+    }
+    http::Response::from_parts(parts, body).into()
+}
+
+/// Parse and check a configured base URL
+///
+/// Requires `https` unless the host is loopback or `allow_insecure` is set,
+/// rejects queries and fragments, and gives the path a trailing `/` so
+/// [`Url::join`](reqwest::Url::join) resolves endpoints below it.
+fn parse_base_url(raw: &str, allow_insecure: bool) -> Result<reqwest::Url, WechatError> {
+    let invalid = |reason: &str| WechatError::Config(format!("invalid base_url {raw:?}: {reason}"));
+    let mut url = reqwest::Url::parse(raw).map_err(|e| invalid(&e.to_string()))?;
+    match url.scheme() {
+        "https" => {}
+        "http" if allow_insecure || is_loopback(&url) => {}
+        "http" => {
+            return Err(invalid(
+                "http is only allowed for loopback hosts unless allow_insecure_base_url is set",
+            ))
+        }
+        _ => return Err(invalid("scheme must be http or https")),
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(invalid("query and fragment are not allowed"));
+    }
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    Ok(url)
+}
+
+fn is_loopback(url: &reqwest::Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+fn middleware_executor<S>(service: S) -> MiddlewareExecutor
+where
+    S: Service<reqwest::Request, Response = reqwest::Response, Error = reqwest::Error>
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    // One service for the whole client, so limits kept in its state apply
+    // across requests. The lock covers only readiness and `call`; the
+    // response futures run concurrently.
+    let service = Arc::new(tokio::sync::Mutex::new(service));
+
+    Arc::new(move |request: reqwest::Request| {
+        let service = Arc::clone(&service);
+        Box::pin(async move {
+            let response = {
+                let mut service = service.lock().await;
+                std::future::poll_fn(|cx| service.poll_ready(cx)).await?;
+                service.call(request)
+            };
+            response.await
+        })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_append_access_token_fills_placeholder_or_appends() {
+        assert_eq!(
+            WechatClient::append_access_token("/wxa/getwxacode", "a+b/c"),
+            "/wxa/getwxacode?access_token=a%2Bb%2Fc"
+        );
+        assert_eq!(
+            WechatClient::append_access_token("/cgi-bin/media/get?media_id=1", "tok"),
+            "/cgi-bin/media/get?media_id=1&access_token=tok"
+        );
+        assert_eq!(
+            WechatClient::append_access_token("/x?access_token={}&type=2", "tok"),
+            "/x?access_token=tok&type=2"
+        );
+    }
+
     #[test]
     fn test_builder_default_values() {
         let appid = AppId::new("wx1234567890abcdef").unwrap();
@@ -333,6 +1330,93 @@ mod tests {
         assert_eq!(client.base_url(), "https://custom.api.example.com");
     }
 
+    #[test]
+    fn test_api_version_pins_only_versioned_endpoints() {
+        let builder = || {
+            WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+        };
+
+        let client = builder()
+            .api_version("openapi.clearQuota", ApiVersion::V2)
+            .build()
+            .unwrap();
+        assert_eq!(client.api_version("openapi.clearQuota"), ApiVersion::V2);
+        assert_eq!(client.api_version("security.msgSecCheck"), ApiVersion::V2);
+        assert_eq!(client.api_version("openapi.getApiQuota"), ApiVersion::V1);
+
+        let err = builder()
+            .api_version("openapi.getApiQuota", ApiVersion::V2)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, WechatError::Config(_)));
+    }
+
+    #[test]
+    fn test_url_for_joins_below_base_path() {
+        let join = |base: &str, path: &str| {
+            parse_base_url(base, false)
+                .unwrap()
+                .join(path.trim_start_matches('/'))
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            join("https://api.example.com", "/cgi-bin/token"),
+            "https://api.example.com/cgi-bin/token"
+        );
+        assert_eq!(
+            join("https://api.example.com/", "/cgi-bin/token"),
+            "https://api.example.com/cgi-bin/token"
+        );
+        assert_eq!(
+            join(
+                "https://gw.example.com/wechat",
+                "/wxa/getpaidunionid?access_token=t"
+            ),
+            "https://gw.example.com/wechat/wxa/getpaidunionid?access_token=t"
+        );
+        assert_eq!(
+            join("https://gw.example.com/wechat/", "cgi-bin/token"),
+            "https://gw.example.com/wechat/cgi-bin/token"
+        );
+    }
+
+    #[test]
+    fn test_parse_base_url_enforces_https_outside_loopback() {
+        for base in [
+            "http://localhost:8080",
+            "http://127.0.0.1:9",
+            "http://[::1]:9",
+            "https://api.example.com",
+        ] {
+            assert!(parse_base_url(base, false).is_ok(), "{base}");
+        }
+        for base in [
+            "http://api.example.com",
+            "ftp://api.example.com",
+            "api.weixin.qq.com",
+            "https://api.example.com/?proxy=1",
+            "https://api.example.com/#top",
+        ] {
+            assert!(
+                matches!(parse_base_url(base, false), Err(WechatError::Config(_))),
+                "{base}"
+            );
+        }
+        assert!(parse_base_url("http://api.example.com", true).is_ok());
+
+        let result = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url("http://internal-proxy")
+            .allow_insecure_base_url(true)
+            .build();
+        assert_eq!(result.unwrap().base_url(), "http://internal-proxy");
+    }
+
     #[test]
     fn test_builder_custom_timeouts() {
         let appid = AppId::new("wx1234567890abcdef").unwrap();
@@ -353,6 +1437,56 @@ mod tests {
         assert_eq!(client.appid(), "wx1234567890abcdef");
     }
 
+    #[test]
+    fn test_builder_default_class_timeouts() {
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            client.class_timeout(TimeoutClass::Fast),
+            Duration::from_secs(DEFAULT_FAST_TIMEOUT_SECS)
+        );
+        assert_eq!(
+            client.class_timeout(TimeoutClass::Normal),
+            Duration::from_secs(DEFAULT_TIMEOUT_SECS)
+        );
+        assert_eq!(
+            client.class_timeout(TimeoutClass::Slow),
+            Duration::from_secs(DEFAULT_SLOW_TIMEOUT_SECS)
+        );
+        assert_eq!(
+            client.timeout_for_path("/cv/ocr/idcard?access_token=t"),
+            Duration::from_secs(DEFAULT_SLOW_TIMEOUT_SECS)
+        );
+    }
+
+    #[test]
+    fn test_builder_class_timeouts_follow_total_timeout() {
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .timeout(Duration::from_secs(90))
+            .class_timeout(TimeoutClass::Fast, Duration::from_secs(3))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            client.class_timeout(TimeoutClass::Fast),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            client.class_timeout(TimeoutClass::Normal),
+            Duration::from_secs(90)
+        );
+        assert_eq!(
+            client.class_timeout(TimeoutClass::Slow),
+            Duration::from_secs(90)
+        );
+    }
+
     #[test]
     fn test_builder_missing_appid() {
         let secret = AppSecret::new("secret1234567890ab").unwrap();
@@ -370,4 +1504,766 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_encode_form() {
+        assert_eq!(
+            encode_form(&[("scene", "a=1&b"), ("page", "pages/index")]),
+            "scene=a%3D1%26b&page=pages%2Findex"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_network_timing_reports_dns_connect_and_body() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ping"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "ok": true }))
+                    .set_delay(Duration::from_millis(20)),
+            )
+            .mount(&server)
+            .await;
+
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reports);
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri().replace("127.0.0.1", "localhost"))
+            .network_timing(true)
+            .on_call_report(move |report| sink.lock().unwrap().push(report.clone()))
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            let body: serde_json::Value = client.get("/ping", &[]).await.unwrap();
+            assert_eq!(body["ok"], true);
+        }
+
+        let reports = reports.lock().unwrap();
+        let first = reports[0].timing.expect("timing enabled");
+        assert!(first.dns.is_some());
+        assert!(first.connect.is_some());
+        // Plain http: no handshake to split off
+        assert_eq!(first.tls, None);
+        assert!(first.ttfb >= Duration::from_millis(20));
+
+        let reused = reports[1].timing.expect("timing enabled");
+        assert_eq!(reused.dns, None);
+        assert_eq!(reused.connect, None);
+    }
+
+    #[tokio::test]
+    async fn test_trace_context_headers_follow_scope_then_provider() {
+        use super::super::{CallOptions, TraceContext};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ping"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "ok": true })),
+            )
+            .mount(&server)
+            .await;
+
+        let build = |provider: bool| {
+            let builder = WechatClient::builder()
+                .appid(AppId::new("wx1234567890abcdef").unwrap())
+                .secret(AppSecret::new("secret1234567890ab").unwrap())
+                .base_url(server.uri());
+            if provider {
+                builder
+                    .trace_context_provider(|| TraceContext::new(1, 2, true).ok())
+                    .build()
+                    .unwrap()
+            } else {
+                builder.build().unwrap()
+            }
+        };
+        let upstream =
+            TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00")
+                .unwrap()
+                .with_tracestate("vendor=1");
+
+        let traced = build(true);
+        let _: serde_json::Value = traced.get("/ping", &[]).await.unwrap();
+        let _: serde_json::Value = CallOptions::new()
+            .trace_context(upstream.clone())
+            .scope(traced.get("/ping", &[]))
+            .await
+            .unwrap();
+        let _: serde_json::Value = CallOptions::new()
+            .trace_context(upstream.clone())
+            .scope(build(false).get("/ping", &[]))
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let header = |index: usize, name: &str| {
+            requests[index]
+                .headers
+                .get(name)
+                .map(|value| value.to_str().unwrap().to_string())
+        };
+        assert_eq!(
+            header(0, "traceparent").as_deref(),
+            Some("00-00000000000000000000000000000001-0000000000000002-01")
+        );
+        assert_eq!(header(0, "tracestate"), None);
+        assert_eq!(
+            header(1, "traceparent").as_deref(),
+            Some(upstream.traceparent())
+        );
+        assert_eq!(header(1, "tracestate").as_deref(), Some("vendor=1"));
+        assert_eq!(header(2, "traceparent"), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_pins_host_to_address() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ping"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "ok": true })),
+            )
+            .mount(&server)
+            .await;
+
+        let port = server.address().port();
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(format!("http://wechat.invalid:{port}"))
+            .allow_insecure_base_url(true)
+            .resolve("WECHAT.invalid", "127.0.0.1:1".parse().unwrap())
+            .build()
+            .unwrap();
+
+        let body: serde_json::Value = client.get("/ping", &[]).await.unwrap();
+        assert_eq!(body["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn test_call_options_base_url_routes_scoped_calls() {
+        use super::super::CallOptions;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let default = MockServer::start().await;
+        let canary = MockServer::start().await;
+        for (server, route, gateway) in [
+            (&default, "/ping", "default"),
+            (&canary, "/gw/ping", "canary"),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(route))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(serde_json::json!({ "gw": gateway })),
+                )
+                .mount(server)
+                .await;
+        }
+
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(default.uri())
+            .build()
+            .unwrap();
+
+        let scoped: serde_json::Value = CallOptions::new()
+            .base_url(format!("{}/gw", canary.uri()))
+            .scope(client.get("/ping", &[]))
+            .await
+            .unwrap();
+        assert_eq!(scoped["gw"], "canary");
+
+        let unscoped: serde_json::Value = client.get("/ping", &[]).await.unwrap();
+        assert_eq!(unscoped["gw"], "default");
+        assert_eq!(client.base_url(), default.uri());
+
+        let insecure = CallOptions::new()
+            .base_url("http://gateway.example.com")
+            .scope(client.get::<serde_json::Value>("/ping", &[]))
+            .await;
+        assert!(matches!(insecure, Err(WechatError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_bytes_per_category() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/gateway/wxa/getwxacodeunlimit"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(vec![0u8; 1_000], "image/png"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/gateway/ping"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(&server)
+            .await;
+
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(format!("{}/gateway", server.uri()))
+            .build()
+            .unwrap();
+
+        let body = br#"{"scene":"a=1"}"#;
+        for _ in 0..2 {
+            client
+                .post_bytes("/wxa/getwxacodeunlimit", "application/json", &body[..])
+                .await
+                .unwrap();
+        }
+        client.get::<serde_json::Value>("/ping", &[]).await.unwrap();
+
+        let stats = client.clone().stats();
+        assert_eq!(
+            stats.category("qrcode-link"),
+            TrafficStats {
+                requests: 2,
+                bytes_sent: 2 * body.len() as u64,
+                bytes_received: 2_000,
+            }
+        );
+        assert_eq!(stats.category(UNCATEGORIZED).bytes_received, 2);
+        assert_eq!(stats.total().requests, 3);
+
+        client.reset_stats();
+        assert_eq!(client.stats(), ClientStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_chunked_bodies_as_read() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let response = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                            Transfer-Encoding: chunked\r\n\r\n\
+                            6\r\n{\"a\":1\r\n1\r\n}\r\n0\r\n\r\n";
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(format!("http://{addr}"))
+            .build()
+            .unwrap();
+
+        let request = client
+            .http()
+            .get(format!("http://{addr}/ping"))
+            .build()
+            .unwrap();
+        let response = client.send_request(request).await.unwrap();
+        assert_eq!(client.stats().category(UNCATEGORIZED).bytes_received, 0);
+        assert_eq!(response.bytes().await.unwrap(), &br#"{"a":1}"#[..]);
+        assert_eq!(client.stats().category(UNCATEGORIZED).bytes_received, 7);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_decode_task_is_an_error() {
+        let task = tokio::spawn(std::future::pending::<()>());
+        task.abort();
+        let result = blocking_decode_result(task.await);
+        assert!(
+            matches!(result, Err(WechatError::Decode { ref message, .. }) if message.starts_with("decode task cancelled"))
+        );
+
+        let panicked = tokio::spawn(async { panic!("boom") }).await;
+        let unwound = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            blocking_decode_result(panicked)
+        }));
+        assert!(unwound.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_large_bodies_decode_on_blocking_pool() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let rows: Vec<_> = (0..2_000)
+            .map(|day| serde_json::json!({"ref_date": format!("day{day}"), "visit_pv": day}))
+            .collect();
+        Mock::given(method("POST"))
+            .and(path("/datacube/getweanalysisappiddailyvisittrend"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "list": rows })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/ping"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(&server)
+            .await;
+
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .blocking_decode_threshold(16 * 1024)
+            .build()
+            .unwrap();
+
+        let body: serde_json::Value = client
+            .post(
+                "/datacube/getweanalysisappiddailyvisittrend",
+                &serde_json::json!({}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(body["list"].as_array().unwrap().len(), 2_000);
+        client.get::<serde_json::Value>("/ping", &[]).await.unwrap();
+
+        let stats = client.stats();
+        let analytics = stats.decode("analytics");
+        assert_eq!(analytics.decodes, 1);
+        assert_eq!(analytics.offloaded, 1);
+        assert!(analytics.bytes > 16 * 1024);
+        assert_eq!(analytics.max, analytics.total);
+        let small = stats.decode(UNCATEGORIZED);
+        assert_eq!((small.decodes, small.offloaded, small.bytes), (1, 0, 2));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mode_blocks_mutating_endpoints() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/message/subscribe/send"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxaapi/newtmpl/gettemplate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let send = |client: WechatClient| async move {
+            client
+                .post::<serde_json::Value, _>(
+                    "/cgi-bin/message/subscribe/send",
+                    &serde_json::json!({}),
+                )
+                .await
+        };
+        let read = |client: WechatClient| async move {
+            client
+                .post::<serde_json::Value, _>("/wxaapi/newtmpl/gettemplate", &serde_json::json!({}))
+                .await
+        };
+
+        client.clone().set_mode(Mode::ReadOnly);
+        assert_eq!(client.mode(), Mode::ReadOnly);
+        let blocked = send(client.clone()).await.unwrap_err();
+        assert!(
+            matches!(&blocked, WechatError::ReadOnly { path } if path == "/cgi-bin/message/subscribe/send")
+        );
+        read(client.clone()).await.unwrap();
+
+        client.set_mode(Mode::Normal);
+        send(client.clone()).await.unwrap();
+        read(client).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_is_shared_with_application() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ping"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let limiter = RateLimiter::new(2, Duration::from_secs(3600));
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .rate_limiter(limiter.clone())
+            .build()
+            .unwrap();
+
+        client.get::<serde_json::Value>("/ping", &[]).await.unwrap();
+        assert!(limiter.try_acquire());
+
+        let starved = tokio::time::timeout(
+            Duration::from_millis(50),
+            client.get::<serde_json::Value>("/ping", &[]),
+        )
+        .await;
+        assert!(starved.is_err(), "request must wait for the shared budget");
+    }
+
+    #[tokio::test]
+    async fn test_gateway_errcodes_map_to_gateway_error() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/msg_sec_check"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 100000504,
+                "errmsg": "gateway upstream timeout"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/getwxacode"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", "image/jpeg")
+                    .set_body_string(r#"{"errcode":100000503,"errmsg":"busy"}"#),
+            )
+            .mount(&server)
+            .await;
+
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .gateway_errcodes(GatewayErrcodes::new().range(100_000_000..=199_999_999))
+            .build()
+            .unwrap();
+
+        let error = client
+            .post::<serde_json::Value, _>("/wxa/msg_sec_check", &serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            WechatError::Gateway {
+                code: 100000504,
+                ..
+            }
+        ));
+        assert!(error.is_transient());
+
+        let error = client
+            .post_bytes("/wxa/getwxacode", "application/json", "{}")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            WechatError::Gateway {
+                code: 100000503,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_post_bytes_sends_content_type_and_detects_errcode() {
+        use wiremock::matchers::{body_bytes, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/upload/ok"))
+            .and(header("content-type", "application/octet-stream"))
+            .and(body_bytes(vec![0u8, 1, 2]))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", "application/octet-stream")
+                    .set_body_bytes(vec![9u8, 8, 7]),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/upload/fail"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 40007,
+                "errmsg": "invalid media_id"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/form"))
+            .and(header("content-type", "application/x-www-form-urlencoded"))
+            .and(body_bytes(b"a=1&b=x%20y".to_vec()))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&server)
+            .await;
+
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+
+        let bytes = client
+            .post_bytes("/upload/ok", "application/octet-stream", vec![0u8, 1, 2])
+            .await
+            .unwrap();
+        assert_eq!(bytes, vec![9, 8, 7]);
+
+        let err = client
+            .post_bytes("/upload/fail", "application/octet-stream", vec![0u8])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WechatError::Api { code: 40007, .. }));
+
+        let bytes = client
+            .post_form("/form", &[("a", "1"), ("b", "x y")])
+            .await
+            .unwrap();
+        assert_eq!(bytes, &b"ok"[..]);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn test_post_gzips_large_bodies_and_decodes_gzip_responses() {
+        use std::io::{Read, Write};
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(br#"{"errcode":0,"errmsg":"ok"}"#)
+            .unwrap();
+        let compressed_response = encoder.finish().unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/datacube/getweanalysisappiddailyretaininfo"))
+            .and(header("content-encoding", "gzip"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Encoding", "gzip")
+                    .insert_header("Content-Type", "application/json")
+                    .set_body_bytes(compressed_response),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .compression(Compression::default().gzip_requests_over(256))
+            .build()
+            .unwrap();
+
+        let body = serde_json::json!({"rows": vec!["20240101"; 64]});
+        let response: serde_json::Value = client
+            .post("/datacube/getweanalysisappiddailyretaininfo", &body)
+            .await
+            .unwrap();
+        assert_eq!(response["errmsg"], "ok");
+
+        let requests = server.received_requests().await.unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(requests[0].body.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, body.to_string());
+    }
+
+    #[cfg(feature = "charset")]
+    #[tokio::test]
+    async fn test_gbk_errmsg_surfaces_as_api_error() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let (gbk, _, _) = encoding_rs::GBK
+            .encode(r#"{"errcode":40001,"errmsg":"获取access_token时AppSecret错误"}"#);
+        let server = MockServer::start().await;
+        for (route, content_type) in [
+            ("/labelled", "application/json; charset=GBK"),
+            ("/unlabelled", "application/json"),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(route))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("Content-Type", content_type)
+                        .set_body_bytes(gbk.to_vec()),
+                )
+                .mount(&server)
+                .await;
+        }
+
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        for route in ["/labelled", "/unlabelled"] {
+            let error = client
+                .get::<serde_json::Value>(route, &[])
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(
+                    &error,
+                    WechatError::Api { code: 40001, message, .. }
+                        if message == "获取access_token时AppSecret错误"
+                ),
+                "{route}: {error:?}"
+            );
+        }
+
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .response_charset(ResponseCharset::utf8_only())
+            .build()
+            .unwrap();
+        let error = client
+            .get::<serde_json::Value>("/labelled", &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WechatError::Decode { .. }), "{error:?}");
+    }
+
+    #[tokio::test]
+    async fn test_with_middleware_stack_wraps_every_call() {
+        use std::sync::Mutex;
+        use tower::ServiceBuilder;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[derive(Clone)]
+        struct TraceLayer {
+            name: &'static str,
+            trace: Arc<Mutex<Vec<String>>>,
+        }
+
+        impl<S> Layer<S> for TraceLayer {
+            type Service = TraceService<S>;
+
+            fn layer(&self, inner: S) -> Self::Service {
+                TraceService {
+                    inner,
+                    layer: self.clone(),
+                }
+            }
+        }
+
+        #[derive(Clone)]
+        struct TraceService<S> {
+            inner: S,
+            layer: TraceLayer,
+        }
+
+        impl<S> Service<reqwest::Request> for TraceService<S>
+        where
+            S: Service<reqwest::Request, Response = reqwest::Response, Error = reqwest::Error>,
+            S::Future: Send + 'static,
+        {
+            type Response = reqwest::Response;
+            type Error = reqwest::Error;
+            type Future = MiddlewareFuture;
+
+            fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                self.inner.poll_ready(cx)
+            }
+
+            fn call(&mut self, req: reqwest::Request) -> Self::Future {
+                self.layer.trace.lock().unwrap().push(format!(
+                    "{} {}",
+                    self.layer.name,
+                    req.url().path()
+                ));
+                Box::pin(self.inner.call(req))
+            }
+        }
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test_token",
+                "expires_in": 7200
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/ping"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({"errcode": 0})),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let layer = |name| TraceLayer {
+            name,
+            trace: Arc::clone(&trace),
+        };
+        let client = WechatClient::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(mock_server.uri())
+            .with_middleware(
+                ServiceBuilder::new()
+                    .layer(layer("outer"))
+                    .layer(layer("inner")),
+            )
+            .build()
+            .unwrap();
+
+        let token = crate::token::TokenManager::new(client.clone())
+            .get_token()
+            .await
+            .unwrap();
+        assert_eq!(token, "test_token");
+        let _: serde_json::Value = client
+            .post("/wxa/ping", &serde_json::json!({}))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *trace.lock().unwrap(),
+            [
+                "outer /cgi-bin/token",
+                "inner /cgi-bin/token",
+                "outer /wxa/ping",
+                "inner /wxa/ping",
+            ]
+        );
+    }
 }
@@ -1,53 +1,99 @@
 //! Unified WeChat Mini Program SDK client
 
+use std::future::Future;
 use std::sync::Arc;
+use std::time::SystemTime;
 
+use bytes::Bytes;
+
+use super::call_report::{collect_reports, CallReport};
+use super::mode::Mode;
+use super::stats::ClientStats;
+use super::support_bundle::unix_secs;
+use crate::api::account::{
+    AccountApi, AccountBasicInfo, CheckNicknameResponse, HeadImageCrop, NicknameAuditResponse,
+    SetNicknameRequest, SetNicknameResponse,
+};
 use crate::api::advertising::{AdvertisingApi, AdvertisingRequest, AdvertisingResponse};
 use crate::api::analytics::{
     AnalyticsApi, AnalyticsDateRangeRequest, AnalyticsResponse, PerformanceDataRequest,
 };
-use crate::api::auth::{LoginResponse, ResetSessionKeyResponse, StableAccessTokenResponse};
+use crate::api::auth::{
+    LoginResponse, ResetSessionKeyResponse, SessionRefresh, SessionStore, StableAccessTokenResponse,
+};
 use crate::api::cloud::{
-    CloudApi, CloudDatabaseRequest, CloudResponse, DelayedFunctionTaskRequest,
-    DeleteCloudFileRequest, DownloadFileLinkRequest, InvokeCloudFunctionRequest,
-    SendCloudBaseSmsRequest, UploadFileLinkRequest,
+    CloudApi, CloudDatabaseRequest, CloudDownload, CloudFileInfo, CloudResponse,
+    DelayedFunctionTaskRequest, DeleteCloudFileRequest, DownloadFileLinkRequest,
+    InvokeCloudFunctionRequest, SendCloudBaseSmsRequest, UploadFileLinkRequest,
+};
+use crate::api::cloud_database::{
+    Collection, DatabaseAddResponse, DatabaseCollectionsResponse, DatabaseDeleteResponse,
+    DatabaseQueryResponse, DatabaseUpdateResponse,
 };
 use crate::api::customer_service::TypingCommand;
-use crate::api::delivery::{DeliveryApi, DeliveryRequest, DeliveryResponse};
+use crate::api::delivery::{
+    AddDeliveryOrderRequest, AddDeliveryOrderResponse, CancelDeliveryOrderRequest,
+    CancelDeliveryOrderResponse, ConfirmReturnRequest, DeliveryApi, DeliveryMerchant,
+    DeliveryRequest, DeliveryResponse, DeliveryResult, GetDeliveryOrderRequest,
+    GetDeliveryOrderResponse, MockUpdateOrderRequest,
+};
+use crate::api::endpoint_inventory::{self, EndpointInventoryItem};
 use crate::api::face::{FaceApi, FaceResponse, GetVerifyIdRequest, QueryVerifyInfoRequest};
 use crate::api::hardware::{HardwareApi, HardwareRequest, HardwareResponse};
-use crate::api::live::{DeleteRoomRequest, GetLiveInfoRequest, LiveApi, LiveRequest, LiveResponse};
-use crate::api::logistics::{LogisticsApi, LogisticsRequest, LogisticsResponse};
+use crate::api::live::{
+    DeleteRoomRequest, GetLiveInfoRequest, GoodsImportReport, GoodsSpec, LiveApi,
+    LiveGoodsListResponse, LiveGoodsStatus, LiveRequest, LiveResponse, LiveSharedCodeResponse,
+};
+use crate::api::logistics::{
+    AddOrderRequest, AddOrderResponse, GetOrderRequest, GetOrderResponse, GetPathRequest,
+    GetPathResponse, LogisticsApi, LogisticsRequest, LogisticsResponse,
+};
 use crate::api::nearby::{
-    AddNearbyPoiRequest, DeleteNearbyPoiRequest, NearbyApi, NearbyPoiListRequest, NearbyResponse,
-    NearbyShowStatusRequest,
+    AddNearbyPoiRequest, DeleteNearbyPoiRequest, NearbyApi, NearbyPoiListRequest,
+    NearbyPoiMaterial, NearbyPoiUploads, NearbyResponse, NearbyShowStatusRequest,
+};
+use crate::api::ocr::{
+    BankCardOcrResult, BusinessLicenseOcrResult, DriverLicenseOcrResult, IdCardOcrRequest,
+    IdCardOcrResult, OcrApi, OcrImageRequest, OcrResponse, VehicleLicenseOcrResult,
 };
-use crate::api::ocr::{IdCardOcrRequest, OcrApi, OcrImageRequest, OcrResponse};
 use crate::api::openapi::{
-    ApiQuotaResponse, CallbackCheckResponse, IpListResponse, OpenApiApi, RidInfoResponse,
+    ApiQuotaResponse, CallbackCheckAction, CallbackCheckResponse, CheckOperator, IpListResponse,
+    OpenApiApi, RidInfoResponse,
 };
 use crate::api::operations::{
-    FeedbackMediaRequest, FeedbackRequest, JsErrDetailRequest, JsErrListRequest, OperationsApi,
-    OperationsResponse, RealtimeLogSearchRequest,
+    FeedbackMediaRequest, FeedbackRequest, GrayReleaseWatch, GrayReleaseWatcher,
+    JsErrDetailRequest, JsErrListRequest, OperationsApi, OperationsResponse,
+    RealtimeLogSearchRequest,
 };
+use crate::api::outbox::{OrderCreation, OrderOutbox};
 use crate::api::plugin::{
-    ManagePluginApplicationRequest, ManagePluginRequest, PluginApi, PluginResponse,
+    ManagePluginApplicationRequest, ManagePluginRequest, PluginApi, PluginApplicant,
+    PluginApplicantPage, PluginResponse,
 };
 use crate::api::qrcode::{
-    NfcSchemeOptions, NfcSchemeResponse, QrcodeApi, QrcodeOptions, QuerySchemeResponse,
-    QueryUrlLinkResponse, ShortLinkOptions, UnlimitQrcodeOptions, UrlLinkOptions, UrlSchemeOptions,
+    NfcSchemeOptions, NfcSchemeResponse, QrcodeApi, QrcodeManifest, QrcodeOptions, QrcodePipeline,
+    QrcodeSink, QuerySchemeResponse, QueryUrlLinkResponse, ShortLinkOptions, UnlimitQrcodeOptions,
+    UrlLinkOptions, UrlSchemeOptions, WxaCodeImage,
 };
 use crate::api::security::{
-    MediaCheckAsyncResponse, MsgSecCheckResponse, SecurityApi, UserRiskRankOptions,
-    UserRiskRankResponse,
+    MediaCheckAsyncResponse, MsgSecCheckResponse, RiskAssessment, RiskDecision, RiskScene, Scene,
+    SecurityApi, UserRiskRankOptions, UserRiskRankResponse,
 };
 use crate::api::service_market::{InvokeServiceRequest, ServiceMarketApi, ServiceMarketResponse};
+use crate::api::shipping::{ShippingApi, UploadShippingInfoRequest};
 use crate::api::soter::{SoterApi, VerifySignatureRequest, VerifySignatureResponse};
-use crate::api::subscribe::SubscribeApi;
 use crate::api::subscribe::{
-    GetUserNotifyRequest, PubTemplateKeywordResponse, PubTemplateTitleListResponse,
-    UserNotifyExtRequest, UserNotifyRequest, UserNotifyResponse,
+    BatchRecipient, SubscribeApi, SubscribeBatch, SubscribeBatchReport, SubscribeMessageData,
+    SubscribeValidation,
 };
+use crate::api::subscribe::{
+    DesiredTemplate, ExpectedTemplate, MetadataCacheStats, PubTemplateKeywordResponse,
+    PubTemplateTitleListResponse, SetUserNotifyExtRequest, SetUserNotifyRequest,
+    TemplateDriftReport, TemplateSyncReport, UserNotifyInfoResponse, UserNotifyQuery,
+    UserNotifyResponse,
+};
+#[allow(deprecated)]
+use crate::api::subscribe::{GetUserNotifyRequest, UserNotifyExtRequest, UserNotifyRequest};
 use crate::api::template::TemplateApi;
 use crate::api::user::{
     CheckEncryptedDataResponse, PaidUnionIdResponse, PhoneNumberResponse, PluginOpenPIdResponse,
@@ -57,12 +103,14 @@ use crate::api::wechat_kf::{KfWorkBoundResponse, WechatKfApi};
 use crate::api::wxsearch::{SubmitPagesRequest, SubmitPagesResponse, WxsearchApi};
 use crate::api::WechatContext;
 use crate::api::{
-    CategoryInfo, MediaApi, MediaType, MediaUploadResponse, Message, SubscribeMessageOptions,
-    TemplateInfo,
+    CategoryInfo, MediaApi, MediaHandleCache, MediaType, MediaUploadResponse, Message,
+    SubscribeMessageOptions, TemplateInfo,
 };
 use crate::crypto::{decrypt_user_data, verify_watermark, DecryptedUserData};
 use crate::error::WechatError;
-use crate::types::{AppId, SessionKey};
+use crate::types::{AppId, OpenId, SessionKey};
+use crate::utils::run_cancellable;
+use crate::CancellationToken;
 
 /// Unified WeChat Mini Program client
 ///
@@ -108,7 +156,189 @@ impl WechatMp {
     }
 
     pub async fn invalidate_token(&self) {
-        self.context.token_manager.invalidate().await;
+        self.context.token_router.invalidate_all().await;
+    }
+
+    /// Redacted diagnostic JSON to attach to bug reports
+    ///
+    /// Contains the SDK version, appid, base URL (without credentials or
+    /// query), timeouts, middleware type, token age and sources, and the
+    /// last [`RECENT_ERRCODES_CAPACITY`](super::RECENT_ERRCODES_CAPACITY)
+    /// errcodes returned by WeChat. The AppSecret and access tokens are never
+    /// included.
+    pub async fn support_bundle(&self) -> serde_json::Value {
+        let mut bundle = self.context.client.support_info();
+        bundle["sdk_version"] = env!("CARGO_PKG_VERSION").into();
+        bundle["generated_at"] = unix_secs(SystemTime::now()).into();
+        bundle["token"] = self.context.token_manager.support_info().await;
+        bundle["token_routes"] = self.context.token_router.categories().into();
+        bundle
+    }
+
+    /// Current [`Mode`]
+    pub fn mode(&self) -> Mode {
+        self.context.client.mode()
+    }
+
+    /// Switch this `WechatMp` and all its clones to `mode`
+    ///
+    /// [`Mode::ReadOnly`] blocks mutating endpoints (sends, orders,
+    /// deletes) with [`WechatError::ReadOnly`] while reads keep working.
+    /// Takes effect for requests sent after the call.
+    ///
+    /// ```rust,ignore
+    /// wechat.set_mode(Mode::ReadOnly);
+    /// // ... incident handled
+    /// wechat.set_mode(Mode::Normal);
+    /// ```
+    pub fn set_mode(&self, mode: Mode) {
+        self.context.client.set_mode(mode);
+    }
+
+    /// Request and response bytes and JSON decode time so far, by endpoint
+    /// category
+    ///
+    /// Categories are those of the
+    /// [endpoint inventory](crate::api::endpoint_inventory::ENDPOINT_INVENTORY);
+    /// retried attempts count their request body again. Clones of this
+    /// `WechatMp` share the counters.
+    pub fn stats(&self) -> ClientStats {
+        self.context.client.stats()
+    }
+
+    /// Reset the counters behind [`stats`](Self::stats), e.g. after each
+    /// export to a dashboard
+    pub fn reset_stats(&self) {
+        self.context.client.reset_stats();
+    }
+
+    /// Inventory entry of the endpoint called by the facade method `name`
+    ///
+    /// Lets gateways key metrics and rate limits by the method names used in
+    /// application code. Methods calling no single endpoint, such as
+    /// [`stats`](Self::stats) or helpers spanning several calls, return
+    /// `None`. See
+    /// [`FACADE_METHOD_MAP`](crate::api::endpoint_inventory::FACADE_METHOD_MAP).
+    ///
+    /// ```
+    /// use wechat_mp_sdk::WechatMp;
+    ///
+    /// let endpoint = WechatMp::endpoint_for_method("get_phone_number").unwrap();
+    /// assert_eq!(endpoint.path, "/wxa/business/getuserphonenumber");
+    /// ```
+    pub fn endpoint_for_method(name: &str) -> Option<&'static EndpointInventoryItem> {
+        endpoint_inventory::endpoint_for_facade_method(name)
+    }
+
+    /// Facade method calling the inventory endpoint `endpoint_id`, e.g.
+    /// `"msg_sec_check"` for `"security.msgSecCheck"`
+    pub fn method_for_endpoint(endpoint_id: &str) -> Option<&'static str> {
+        endpoint_inventory::facade_method_for_endpoint(endpoint_id)
+    }
+
+    /// Run `call` and return the [`CallReport`]s of the requests it sent
+    ///
+    /// Reports cover requests made on the current task; background token
+    /// refreshes are only visible to the `on_call_report` hook.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let (result, reports) = wechat
+    ///     .with_call_report(wechat.get_category())
+    ///     .await;
+    /// for report in &reports {
+    ///     println!("{} took {} attempts in {:?}", report.path, report.attempts, report.latency);
+    /// }
+    /// ```
+    pub async fn with_call_report<F: Future>(&self, call: F) -> (F::Output, Vec<CallReport>) {
+        collect_reports(call).await
+    }
+
+    /// Run an SDK call until it completes or `token` is cancelled
+    ///
+    /// On cancellation the call is dropped, aborting any request in flight,
+    /// and `WechatError::Cancelled` is returned. Queued customer service sends
+    /// give up their place in the queue.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let shutdown = CancellationToken::new();
+    /// let result = wechat
+    ///     .with_cancellation(&shutdown, wechat.upload_temp_media(MediaType::Video, "a.mp4", &data))
+    ///     .await;
+    /// ```
+    pub async fn with_cancellation<F, T>(
+        &self,
+        token: &CancellationToken,
+        call: F,
+    ) -> Result<T, WechatError>
+    where
+        F: Future<Output = Result<T, WechatError>>,
+    {
+        run_cancellable(Some(token), call).await
+    }
+
+    // Category accessors
+    //
+    // Long-tail categories grouped behind one accessor each, e.g.
+    // `wechat.hardware().get_sn_ticket(&request)`. The returned Api shares
+    // this client's token, transport and stats; methods carry the official
+    // Chinese endpoint names as doc aliases.
+
+    /// Nearby mini program (附近的小程序) endpoints
+    #[doc(alias = "附近的小程序")]
+    pub fn nearby(&self) -> NearbyApi {
+        NearbyApi::new(self.context.clone())
+    }
+
+    /// Live streaming (小程序直播) endpoints
+    #[doc(alias = "小程序直播")]
+    pub fn live(&self) -> LiveApi {
+        LiveApi::new(self.context.clone())
+    }
+
+    /// Plugin management (插件管理) endpoints
+    #[doc(alias = "插件管理")]
+    pub fn plugin(&self) -> PluginApi {
+        PluginApi::new(self.context.clone())
+    }
+
+    /// Hardware device (硬件设备) endpoints
+    #[doc(alias = "硬件设备")]
+    pub fn hardware(&self) -> HardwareApi {
+        HardwareApi::new(self.context.clone())
+    }
+
+    /// SOTER biometric (生物认证) endpoints
+    #[doc(alias = "生物认证")]
+    pub fn soter(&self) -> SoterApi {
+        SoterApi::new(self.context.clone())
+    }
+
+    /// Face verification (人脸核身) endpoints
+    #[doc(alias = "人脸核身")]
+    pub fn face(&self) -> FaceApi {
+        FaceApi::new(self.context.clone())
+    }
+
+    /// Service market (服务市场) endpoints
+    #[doc(alias = "服务市场")]
+    pub fn service_market(&self) -> ServiceMarketApi {
+        ServiceMarketApi::new(self.context.clone())
+    }
+
+    /// WeChat search (微信搜索) endpoints
+    #[doc(alias = "微信搜索")]
+    pub fn wxsearch(&self) -> WxsearchApi {
+        WxsearchApi::new(self.context.clone())
+    }
+
+    /// Advertising (广告) endpoints
+    #[doc(alias = "广告")]
+    pub fn advertising(&self) -> AdvertisingApi {
+        AdvertisingApi::new(self.context.clone())
     }
 
     // Auth API
@@ -148,6 +378,18 @@ impl WechatMp {
             .await
     }
 
+    pub async fn send_customer_service_image_cached(
+        &self,
+        touser: &str,
+        cache: &MediaHandleCache,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<(), WechatError> {
+        crate::api::customer_service::CustomerServiceApi::new(self.context.clone())
+            .send_image_cached(touser, cache, filename, data)
+            .await
+    }
+
     pub async fn upload_temp_media(
         &self,
         media_type: MediaType,
@@ -159,7 +401,19 @@ impl WechatMp {
             .await
     }
 
-    pub async fn get_temp_media(&self, media_id: &str) -> Result<Vec<u8>, WechatError> {
+    pub async fn upload_temp_media_cached(
+        &self,
+        cache: &MediaHandleCache,
+        media_type: MediaType,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<String, WechatError> {
+        MediaApi::new(self.context.clone())
+            .upload_temp_media_cached(cache, media_type, filename, data)
+            .await
+    }
+
+    pub async fn get_temp_media(&self, media_id: &str) -> Result<Bytes, WechatError> {
         MediaApi::new(self.context.clone())
             .get_temp_media(media_id)
             .await
@@ -172,6 +426,84 @@ impl WechatMp {
         SubscribeApi::new(self.context.clone()).send(options).await
     }
 
+    /// Send one template to several users with per-user data
+    ///
+    /// See [`SubscribeApi::send_to_many`].
+    pub async fn send_subscribe_message_to_many<F>(
+        &self,
+        users: &[OpenId],
+        template_id: &str,
+        data_fn: F,
+    ) -> Result<Vec<(OpenId, Result<(), WechatError>)>, WechatError>
+    where
+        F: Fn(&OpenId) -> SubscribeMessageData,
+    {
+        SubscribeApi::new(self.context.clone())
+            .send_to_many(users, template_id, data_fn)
+            .await
+    }
+
+    /// Send one template to many recipients with bounded concurrency
+    ///
+    /// See [`SubscribeApi::send_batch`].
+    pub async fn send_subscribe_message_batch<I>(
+        &self,
+        batch: &SubscribeBatch,
+        recipients: I,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<SubscribeBatchReport, WechatError>
+    where
+        I: IntoIterator<Item = BatchRecipient>,
+    {
+        SubscribeApi::new(self.context.clone())
+            .send_batch(batch, recipients, cancel)
+            .await
+    }
+
+    /// Subscribe authorizations left for `openid` on `template_id`
+    ///
+    /// See [`SubscribeApi::remaining_quota`].
+    pub async fn subscribe_remaining_quota(
+        &self,
+        openid: &str,
+        template_id: &str,
+    ) -> Result<u32, WechatError> {
+        SubscribeApi::new(self.context.clone())
+            .remaining_quota(openid, template_id)
+            .await
+    }
+
+    /// Record the templates `openid` accepted in `wx.requestSubscribeMessage`
+    ///
+    /// See [`SubscribeApi::record_acceptance`].
+    pub async fn record_subscribe_acceptance(
+        &self,
+        openid: &str,
+        template_ids: &[&str],
+    ) -> Result<(), WechatError> {
+        SubscribeApi::new(self.context.clone())
+            .record_acceptance(openid, template_ids)
+            .await
+    }
+
+    /// Send a subscribe message after checking it against `validation`
+    pub async fn send_subscribe_message_validated(
+        &self,
+        options: SubscribeMessageOptions,
+        validation: &SubscribeValidation,
+    ) -> Result<(), WechatError> {
+        SubscribeApi::new(self.context.clone())
+            .with_validation(validation.clone())
+            .send(options)
+            .await
+    }
+
+    pub async fn load_subscribe_validation(&self) -> Result<SubscribeValidation, WechatError> {
+        SubscribeApi::new(self.context.clone())
+            .load_validation()
+            .await
+    }
+
     pub async fn add_template(
         &self,
         tid: &str,
@@ -189,6 +521,26 @@ impl WechatMp {
             .await
     }
 
+    pub async fn verify_templates(
+        &self,
+        expected: &[ExpectedTemplate],
+    ) -> Result<TemplateDriftReport, WechatError> {
+        SubscribeApi::new(self.context.clone())
+            .verify_templates(expected)
+            .await
+    }
+
+    /// See [`SubscribeApi::sync_templates`].
+    pub async fn sync_templates(
+        &self,
+        desired: &[DesiredTemplate],
+        prune: bool,
+    ) -> Result<TemplateSyncReport, WechatError> {
+        SubscribeApi::new(self.context.clone())
+            .sync_templates(desired, prune)
+            .await
+    }
+
     pub async fn delete_template(&self, pri_tmpl_id: &str) -> Result<(), WechatError> {
         TemplateApi::new(self.context.clone())
             .delete_template(pri_tmpl_id)
@@ -201,7 +553,7 @@ impl WechatMp {
 
     // QR Code API
 
-    pub async fn get_wxa_code(&self, options: QrcodeOptions) -> Result<Vec<u8>, WechatError> {
+    pub async fn get_wxa_code(&self, options: QrcodeOptions) -> Result<WxaCodeImage, WechatError> {
         QrcodeApi::new(self.context.clone())
             .get_wxa_code(options)
             .await
@@ -210,17 +562,33 @@ impl WechatMp {
     pub async fn get_wxa_code_unlimit(
         &self,
         options: UnlimitQrcodeOptions,
-    ) -> Result<Vec<u8>, WechatError> {
+    ) -> Result<WxaCodeImage, WechatError> {
         QrcodeApi::new(self.context.clone())
             .get_wxa_code_unlimit(options)
             .await
     }
 
+    pub async fn run_qrcode_pipeline<I>(
+        &self,
+        pipeline: &QrcodePipeline,
+        scenes: I,
+        sink: Arc<dyn QrcodeSink>,
+        cancel: Option<&CancellationToken>,
+    ) -> QrcodeManifest
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        QrcodeApi::new(self.context.clone())
+            .run_pipeline(pipeline, scenes, sink, cancel)
+            .await
+    }
+
     pub async fn create_qrcode(
         &self,
         path: &str,
         width: Option<u32>,
-    ) -> Result<Vec<u8>, WechatError> {
+    ) -> Result<WxaCodeImage, WechatError> {
         QrcodeApi::new(self.context.clone())
             .create_qrcode(path, width)
             .await
@@ -282,8 +650,8 @@ impl WechatMp {
 
     pub async fn callback_check(
         &self,
-        action: &str,
-        check_operator: &str,
+        action: CallbackCheckAction,
+        check_operator: CheckOperator,
     ) -> Result<CallbackCheckResponse, WechatError> {
         OpenApiApi::new(self.context.clone())
             .callback_check(action, check_operator)
@@ -307,7 +675,7 @@ impl WechatMp {
     pub async fn msg_sec_check(
         &self,
         openid: &str,
-        scene: u8,
+        scene: Scene,
         content: &str,
     ) -> Result<MsgSecCheckResponse, WechatError> {
         SecurityApi::new(self.context.clone())
@@ -320,7 +688,7 @@ impl WechatMp {
         media_url: &str,
         media_type: u8,
         openid: &str,
-        scene: u8,
+        scene: Scene,
     ) -> Result<MediaCheckAsyncResponse, WechatError> {
         SecurityApi::new(self.context.clone())
             .media_check_async(media_url, media_type, openid, scene)
@@ -330,7 +698,7 @@ impl WechatMp {
     pub async fn get_user_risk_rank(
         &self,
         openid: &str,
-        scene: u8,
+        scene: RiskScene,
         options: Option<UserRiskRankOptions>,
     ) -> Result<UserRiskRankResponse, WechatError> {
         SecurityApi::new(self.context.clone())
@@ -338,6 +706,15 @@ impl WechatMp {
             .await
     }
 
+    pub async fn assess_risk(
+        &self,
+        assessment: &RiskAssessment,
+    ) -> Result<RiskDecision, WechatError> {
+        SecurityApi::new(self.context.clone())
+            .assess_risk(assessment)
+            .await
+    }
+
     // Auth Extensions
 
     pub async fn check_session_key(
@@ -362,6 +739,19 @@ impl WechatMp {
             .await
     }
 
+    /// Check a session key and rotate it into `store` if WeChat rejects it
+    pub async fn ensure_fresh_session(
+        &self,
+        openid: &str,
+        signature: &str,
+        sig_method: &str,
+        store: &dyn SessionStore,
+    ) -> Result<SessionRefresh, WechatError> {
+        crate::api::auth::AuthApi::new(self.context.clone())
+            .ensure_fresh_session(openid, signature, sig_method, store)
+            .await
+    }
+
     // User Extensions
 
     pub async fn get_plugin_open_pid(
@@ -403,6 +793,16 @@ impl WechatMp {
             .await
     }
 
+    pub async fn get_user_encrypt_key_with_session_key(
+        &self,
+        openid: &str,
+        session_key: &str,
+    ) -> Result<UserEncryptKeyResponse, WechatError> {
+        crate::api::user::UserApi::new(self.context.clone())
+            .get_user_encrypt_key_with_session_key(openid, session_key)
+            .await
+    }
+
     // QR Code Extensions
 
     pub async fn query_scheme(&self, scheme: &str) -> Result<QuerySchemeResponse, WechatError> {
@@ -473,6 +873,14 @@ impl WechatMp {
             .await
     }
 
+    pub fn invalidate_subscribe_metadata(&self) {
+        SubscribeApi::new(self.context.clone()).invalidate_metadata()
+    }
+
+    pub fn subscribe_metadata_cache_stats(&self) -> MetadataCacheStats {
+        SubscribeApi::new(self.context.clone()).metadata_cache_stats()
+    }
+
     pub async fn get_pub_template_title_list(
         &self,
         ids: &[i32],
@@ -484,6 +892,8 @@ impl WechatMp {
             .await
     }
 
+    #[deprecated(note = "use update_user_notify")]
+    #[allow(deprecated)]
     pub async fn set_user_notify(
         &self,
         request: &UserNotifyRequest,
@@ -493,6 +903,8 @@ impl WechatMp {
             .await
     }
 
+    #[deprecated(note = "use update_user_notify_ext")]
+    #[allow(deprecated)]
     pub async fn set_user_notify_ext(
         &self,
         request: &UserNotifyExtRequest,
@@ -502,6 +914,8 @@ impl WechatMp {
             .await
     }
 
+    #[deprecated(note = "use query_user_notify")]
+    #[allow(deprecated)]
     pub async fn get_user_notify(
         &self,
         request: &GetUserNotifyRequest,
@@ -511,6 +925,33 @@ impl WechatMp {
             .await
     }
 
+    pub async fn update_user_notify(
+        &self,
+        request: &SetUserNotifyRequest,
+    ) -> Result<UserNotifyResponse, WechatError> {
+        SubscribeApi::new(self.context.clone())
+            .update_user_notify(request)
+            .await
+    }
+
+    pub async fn update_user_notify_ext(
+        &self,
+        request: &SetUserNotifyExtRequest,
+    ) -> Result<UserNotifyResponse, WechatError> {
+        SubscribeApi::new(self.context.clone())
+            .update_user_notify_ext(request)
+            .await
+    }
+
+    pub async fn query_user_notify(
+        &self,
+        query: &UserNotifyQuery,
+    ) -> Result<UserNotifyInfoResponse, WechatError> {
+        SubscribeApi::new(self.context.clone())
+            .query_user_notify(query)
+            .await
+    }
+
     pub async fn get_daily_summary(
         &self,
         request: &AnalyticsDateRangeRequest,
@@ -685,6 +1126,10 @@ impl WechatMp {
             .await
     }
 
+    pub fn watch_gray_release(&self, watch: GrayReleaseWatch) -> GrayReleaseWatcher {
+        OperationsApi::new(self.context.clone()).watch_gray_release(watch)
+    }
+
     pub async fn manage_plugin_application(
         &self,
         request: &ManagePluginApplicationRequest,
@@ -703,6 +1148,25 @@ impl WechatMp {
             .await
     }
 
+    pub async fn plugin_dev_apply_list(
+        &self,
+        page: u32,
+        num: u32,
+    ) -> Result<PluginApplicantPage, WechatError> {
+        PluginApi::new(self.context.clone())
+            .dev_apply_list(page, num)
+            .await
+    }
+
+    pub async fn plugin_dev_apply_list_all(
+        &self,
+        num: u32,
+    ) -> Result<Vec<PluginApplicant>, WechatError> {
+        PluginApi::new(self.context.clone())
+            .dev_apply_list_all(num)
+            .await
+    }
+
     pub async fn add_nearby_poi(
         &self,
         request: &AddNearbyPoiRequest,
@@ -712,6 +1176,28 @@ impl WechatMp {
             .await
     }
 
+    pub async fn add_nearby_poi_with_materials(
+        &self,
+        request: AddNearbyPoiRequest,
+        pictures: &[NearbyPoiMaterial],
+        qualifications: &[NearbyPoiMaterial],
+    ) -> Result<NearbyResponse, WechatError> {
+        NearbyApi::new(self.context.clone())
+            .add_nearby_poi_with_materials(request, pictures, qualifications)
+            .await
+    }
+
+    pub async fn upload_nearby_poi_materials(
+        &self,
+        pictures: &[NearbyPoiMaterial],
+        qualifications: &[NearbyPoiMaterial],
+        cancel: Option<&CancellationToken>,
+    ) -> Result<NearbyPoiUploads, WechatError> {
+        NearbyApi::new(self.context.clone())
+            .upload_poi_materials(pictures, qualifications, cancel)
+            .await
+    }
+
     pub async fn delete_nearby_poi(
         &self,
         request: &DeleteNearbyPoiRequest,
@@ -793,6 +1279,80 @@ impl WechatMp {
             .await
     }
 
+    /// See [`CloudApi::query_documents`].
+    pub async fn query_documents(
+        &self,
+        env: &str,
+        collection: &Collection,
+    ) -> Result<DatabaseQueryResponse, WechatError> {
+        CloudApi::new(self.context.clone())
+            .query_documents(env, collection)
+            .await
+    }
+
+    pub async fn add_documents<T: serde::Serialize>(
+        &self,
+        env: &str,
+        collection: &Collection,
+        documents: &[T],
+    ) -> Result<DatabaseAddResponse, WechatError> {
+        CloudApi::new(self.context.clone())
+            .add_documents(env, collection, documents)
+            .await
+    }
+
+    pub async fn update_documents<T: serde::Serialize>(
+        &self,
+        env: &str,
+        collection: &Collection,
+        data: &T,
+    ) -> Result<DatabaseUpdateResponse, WechatError> {
+        CloudApi::new(self.context.clone())
+            .update_documents(env, collection, data)
+            .await
+    }
+
+    pub async fn delete_documents(
+        &self,
+        env: &str,
+        collection: &Collection,
+    ) -> Result<DatabaseDeleteResponse, WechatError> {
+        CloudApi::new(self.context.clone())
+            .delete_documents(env, collection)
+            .await
+    }
+
+    pub async fn database_collection_add(
+        &self,
+        env: &str,
+        collection_name: &str,
+    ) -> Result<CloudResponse, WechatError> {
+        CloudApi::new(self.context.clone())
+            .database_collection_add(env, collection_name)
+            .await
+    }
+
+    pub async fn database_collection_delete(
+        &self,
+        env: &str,
+        collection_name: &str,
+    ) -> Result<CloudResponse, WechatError> {
+        CloudApi::new(self.context.clone())
+            .database_collection_delete(env, collection_name)
+            .await
+    }
+
+    pub async fn database_collection_get(
+        &self,
+        env: &str,
+        limit: u32,
+        offset: u32,
+    ) -> Result<DatabaseCollectionsResponse, WechatError> {
+        CloudApi::new(self.context.clone())
+            .database_collection_get(env, limit, offset)
+            .await
+    }
+
     pub async fn get_upload_file_link(
         &self,
         request: &UploadFileLinkRequest,
@@ -802,6 +1362,45 @@ impl WechatMp {
             .await
     }
 
+    /// See [`CloudApi::upload_file`].
+    pub async fn upload_file(
+        &self,
+        env: &str,
+        cloud_path: &str,
+        data: &[u8],
+    ) -> Result<String, WechatError> {
+        CloudApi::new(self.context.clone())
+            .upload_file(env, cloud_path, data)
+            .await
+    }
+
+    /// See [`CloudApi::download_file`].
+    pub async fn download_file(&self, env: &str, file_id: &str) -> Result<Bytes, WechatError> {
+        CloudApi::new(self.context.clone())
+            .download_file(env, file_id)
+            .await
+    }
+
+    pub async fn download_file_stream(
+        &self,
+        env: &str,
+        file_id: &str,
+    ) -> Result<reqwest::Response, WechatError> {
+        CloudApi::new(self.context.clone())
+            .download_file_stream(env, file_id)
+            .await
+    }
+
+    pub async fn download_files(
+        &self,
+        env: &str,
+        file_ids: &[&str],
+    ) -> Result<Vec<CloudDownload>, WechatError> {
+        CloudApi::new(self.context.clone())
+            .download_files(env, file_ids)
+            .await
+    }
+
     pub async fn get_download_file_link(
         &self,
         request: &DownloadFileLinkRequest,
@@ -820,6 +1419,16 @@ impl WechatMp {
             .await
     }
 
+    pub async fn get_cloud_file_info(
+        &self,
+        env: &str,
+        file_ids: &[&str],
+    ) -> Result<Vec<CloudFileInfo>, WechatError> {
+        CloudApi::new(self.context.clone())
+            .get_file_info(env, file_ids)
+            .await
+    }
+
     pub async fn new_send_cloud_base_sms(
         &self,
         request: &SendCloudBaseSmsRequest,
@@ -829,6 +1438,15 @@ impl WechatMp {
             .await
     }
 
+    /// Check that the account can use the live component
+    ///
+    /// See [`LiveApi::check_prerequisites`].
+    pub async fn check_live_prerequisites(&self) -> Result<(), WechatError> {
+        LiveApi::new(self.context.clone())
+            .check_prerequisites()
+            .await
+    }
+
     pub async fn create_room(&self, request: &LiveRequest) -> Result<LiveResponse, WechatError> {
         LiveApi::new(self.context.clone())
             .create_room(request)
@@ -891,6 +1509,49 @@ impl WechatMp {
             .await
     }
 
+    pub async fn get_live_shared_code(
+        &self,
+        room_id: i64,
+        params: Option<&str>,
+    ) -> Result<LiveSharedCodeResponse, WechatError> {
+        LiveApi::new(self.context.clone())
+            .get_shared_code(room_id, params)
+            .await
+    }
+
+    /// Download the poster image of a live room's sharing assets
+    pub async fn download_live_shared_poster(
+        &self,
+        shared: &LiveSharedCodeResponse,
+    ) -> Result<Bytes, WechatError> {
+        LiveApi::new(self.context.clone())
+            .download_shared_poster(shared)
+            .await
+    }
+
+    pub async fn get_approved_live_goods(
+        &self,
+        offset: u32,
+        limit: u32,
+        status: LiveGoodsStatus,
+    ) -> Result<LiveGoodsListResponse, WechatError> {
+        LiveApi::new(self.context.clone())
+            .get_approved_goods(offset, limit, status)
+            .await
+    }
+
+    /// Add catalog products missing from the live goods library
+    ///
+    /// See [`LiveApi::import_goods`].
+    pub async fn import_live_goods(
+        &self,
+        catalog: Vec<GoodsSpec>,
+    ) -> Result<GoodsImportReport, WechatError> {
+        LiveApi::new(self.context.clone())
+            .import_goods(catalog)
+            .await
+    }
+
     pub async fn send_hardware_device_message(
         &self,
         request: &HardwareRequest,
@@ -1010,6 +1671,132 @@ impl WechatMp {
         OcrApi::new(self.context.clone()).id_card_ocr(request).await
     }
 
+    pub async fn ai_crop_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        OcrApi::new(self.context.clone())
+            .ai_crop_from_bytes(filename, data)
+            .await
+    }
+
+    pub async fn scan_qr_code_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        OcrApi::new(self.context.clone())
+            .scan_qr_code_from_bytes(filename, data)
+            .await
+    }
+
+    pub async fn printed_text_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        OcrApi::new(self.context.clone())
+            .printed_text_ocr_from_bytes(filename, data)
+            .await
+    }
+
+    pub async fn vehicle_license_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        OcrApi::new(self.context.clone())
+            .vehicle_license_ocr_from_bytes(filename, data)
+            .await
+    }
+
+    pub async fn bank_card_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        OcrApi::new(self.context.clone())
+            .bank_card_ocr_from_bytes(filename, data)
+            .await
+    }
+
+    pub async fn business_license_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        OcrApi::new(self.context.clone())
+            .business_license_ocr_from_bytes(filename, data)
+            .await
+    }
+
+    pub async fn driver_license_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<OcrResponse, WechatError> {
+        OcrApi::new(self.context.clone())
+            .driver_license_ocr_from_bytes(filename, data)
+            .await
+    }
+
+    pub async fn id_card_ocr_from_bytes(
+        &self,
+        filename: &str,
+        data: &[u8],
+        r#type: Option<&str>,
+    ) -> Result<OcrResponse, WechatError> {
+        OcrApi::new(self.context.clone())
+            .id_card_ocr_from_bytes(filename, data, r#type)
+            .await
+    }
+
+    pub async fn recognize_id_card(
+        &self,
+        request: &IdCardOcrRequest,
+    ) -> Result<IdCardOcrResult, WechatError> {
+        OcrApi::new(self.context.clone())
+            .recognize_id_card(request)
+            .await
+    }
+
+    pub async fn recognize_bank_card(
+        &self,
+        request: &OcrImageRequest,
+    ) -> Result<BankCardOcrResult, WechatError> {
+        OcrApi::new(self.context.clone())
+            .recognize_bank_card(request)
+            .await
+    }
+
+    pub async fn recognize_driver_license(
+        &self,
+        request: &OcrImageRequest,
+    ) -> Result<DriverLicenseOcrResult, WechatError> {
+        OcrApi::new(self.context.clone())
+            .recognize_driver_license(request)
+            .await
+    }
+
+    pub async fn recognize_business_license(
+        &self,
+        request: &OcrImageRequest,
+    ) -> Result<BusinessLicenseOcrResult, WechatError> {
+        OcrApi::new(self.context.clone())
+            .recognize_business_license(request)
+            .await
+    }
+
+    pub async fn recognize_vehicle_license(
+        &self,
+        request: &OcrImageRequest,
+    ) -> Result<VehicleLicenseOcrResult, WechatError> {
+        OcrApi::new(self.context.clone())
+            .recognize_vehicle_license(request)
+            .await
+    }
+
     pub async fn get_all_imme_delivery(
         &self,
         request: &DeliveryRequest,
@@ -1046,6 +1833,26 @@ impl WechatMp {
             .await
     }
 
+    pub async fn get_local_order(
+        &self,
+        request: &DeliveryRequest,
+    ) -> Result<DeliveryResponse, WechatError> {
+        DeliveryApi::new(self.context.clone())
+            .get_local_order(request)
+            .await
+    }
+
+    /// See [`DeliveryApi::create_or_get_local_order`].
+    pub async fn create_or_get_local_order(
+        &self,
+        outbox: &dyn OrderOutbox,
+        request: &DeliveryRequest,
+    ) -> Result<OrderCreation<DeliveryResponse>, WechatError> {
+        DeliveryApi::new(self.context.clone())
+            .create_or_get_local_order(outbox, request)
+            .await
+    }
+
     pub async fn cancel_local_order(
         &self,
         request: &DeliveryRequest,
@@ -1055,6 +1862,66 @@ impl WechatMp {
             .await
     }
 
+    /// See [`DeliveryApi::add_delivery_order`].
+    pub async fn add_delivery_order(
+        &self,
+        merchant: &DeliveryMerchant,
+        request: &AddDeliveryOrderRequest,
+    ) -> Result<AddDeliveryOrderResponse, WechatError> {
+        DeliveryApi::new(self.context.clone())
+            .add_delivery_order(merchant, request)
+            .await
+    }
+
+    pub async fn re_add_delivery_order(
+        &self,
+        merchant: &DeliveryMerchant,
+        request: &AddDeliveryOrderRequest,
+    ) -> Result<AddDeliveryOrderResponse, WechatError> {
+        DeliveryApi::new(self.context.clone())
+            .re_add_delivery_order(merchant, request)
+            .await
+    }
+
+    pub async fn get_delivery_order(
+        &self,
+        merchant: &DeliveryMerchant,
+        request: &GetDeliveryOrderRequest,
+    ) -> Result<GetDeliveryOrderResponse, WechatError> {
+        DeliveryApi::new(self.context.clone())
+            .get_delivery_order(merchant, request)
+            .await
+    }
+
+    pub async fn cancel_delivery_order(
+        &self,
+        merchant: &DeliveryMerchant,
+        request: &CancelDeliveryOrderRequest,
+    ) -> Result<CancelDeliveryOrderResponse, WechatError> {
+        DeliveryApi::new(self.context.clone())
+            .cancel_delivery_order(merchant, request)
+            .await
+    }
+
+    pub async fn confirm_delivery_return(
+        &self,
+        merchant: &DeliveryMerchant,
+        request: &ConfirmReturnRequest,
+    ) -> Result<DeliveryResult, WechatError> {
+        DeliveryApi::new(self.context.clone())
+            .confirm_delivery_return(merchant, request)
+            .await
+    }
+
+    pub async fn mock_update_delivery_order(
+        &self,
+        request: &MockUpdateOrderRequest,
+    ) -> Result<DeliveryResult, WechatError> {
+        DeliveryApi::new(self.context.clone())
+            .mock_update_delivery_order(request)
+            .await
+    }
+
     pub async fn bind_account(
         &self,
         request: &LogisticsRequest,
@@ -1100,6 +1967,17 @@ impl WechatMp {
             .await
     }
 
+    /// See [`LogisticsApi::create_or_get_order`].
+    pub async fn create_or_get_order(
+        &self,
+        outbox: &dyn OrderOutbox,
+        request: &LogisticsRequest,
+    ) -> Result<OrderCreation<LogisticsResponse>, WechatError> {
+        LogisticsApi::new(self.context.clone())
+            .create_or_get_order(outbox, request)
+            .await
+    }
+
     pub async fn get_path(
         &self,
         request: &LogisticsRequest,
@@ -1109,6 +1987,54 @@ impl WechatMp {
             .await
     }
 
+    pub async fn add_express_order(
+        &self,
+        request: &AddOrderRequest,
+    ) -> Result<AddOrderResponse, WechatError> {
+        LogisticsApi::new(self.context.clone())
+            .add_express_order(request)
+            .await
+    }
+
+    /// See [`LogisticsApi::create_or_get_express_order`].
+    pub async fn create_or_get_express_order(
+        &self,
+        outbox: &dyn OrderOutbox,
+        request: &AddOrderRequest,
+    ) -> Result<OrderCreation<AddOrderResponse>, WechatError> {
+        LogisticsApi::new(self.context.clone())
+            .create_or_get_express_order(outbox, request)
+            .await
+    }
+
+    pub async fn get_express_order(
+        &self,
+        request: &GetOrderRequest,
+    ) -> Result<GetOrderResponse, WechatError> {
+        LogisticsApi::new(self.context.clone())
+            .get_express_order(request)
+            .await
+    }
+
+    pub async fn get_express_path(
+        &self,
+        request: &GetPathRequest,
+    ) -> Result<GetPathResponse, WechatError> {
+        LogisticsApi::new(self.context.clone())
+            .get_express_path(request)
+            .await
+    }
+
+    /// See [`ShippingApi::upload_shipping_info`].
+    pub async fn upload_shipping_info(
+        &self,
+        request: &UploadShippingInfoRequest,
+    ) -> Result<(), WechatError> {
+        ShippingApi::new(self.context.clone())
+            .upload_shipping_info(request)
+            .await
+    }
+
     pub async fn invoke_service(
         &self,
         request: &InvokeServiceRequest,
@@ -1154,6 +2080,57 @@ impl WechatMp {
             .await
     }
 
+    // Account API
+
+    pub async fn get_account_basic_info(&self) -> Result<AccountBasicInfo, WechatError> {
+        AccountApi::new(self.context.clone())
+            .get_account_basic_info()
+            .await
+    }
+
+    pub async fn set_nickname(
+        &self,
+        request: &SetNicknameRequest,
+    ) -> Result<SetNicknameResponse, WechatError> {
+        AccountApi::new(self.context.clone())
+            .set_nickname(request)
+            .await
+    }
+
+    pub async fn query_nickname_audit(
+        &self,
+        audit_id: i64,
+    ) -> Result<NicknameAuditResponse, WechatError> {
+        AccountApi::new(self.context.clone())
+            .query_nickname_audit(audit_id)
+            .await
+    }
+
+    pub async fn check_nickname(
+        &self,
+        nick_name: &str,
+    ) -> Result<CheckNicknameResponse, WechatError> {
+        AccountApi::new(self.context.clone())
+            .check_nickname(nick_name)
+            .await
+    }
+
+    pub async fn modify_head_image(
+        &self,
+        media_id: &str,
+        crop: HeadImageCrop,
+    ) -> Result<(), WechatError> {
+        AccountApi::new(self.context.clone())
+            .modify_head_image(media_id, crop)
+            .await
+    }
+
+    pub async fn modify_signature(&self, signature: &str) -> Result<(), WechatError> {
+        AccountApi::new(self.context.clone())
+            .modify_signature(signature)
+            .await
+    }
+
     pub async fn add_user_action(
         &self,
         request: &AdvertisingRequest,
@@ -1215,11 +2192,11 @@ impl From<Arc<WechatContext>> for WechatMp {
 
 #[cfg(test)]
 mod tests {
-    use std::time::{Duration, Instant};
+    use std::time::{Duration, SystemTime};
 
     use super::*;
-    use crate::token::CachedToken;
-    use crate::types::{AccessToken, AppSecret, Watermark};
+    use crate::token::StoredToken;
+    use crate::types::{AppSecret, Watermark};
 
     fn create_test_wechat_mp() -> WechatMp {
         let appid = AppId::new("wx1234567890abcdef").unwrap();
@@ -1274,11 +2251,12 @@ mod tests {
     #[tokio::test]
     async fn test_wechat_mp_get_access_token() {
         let wechat = create_test_wechat_mp();
-        let cached = CachedToken {
-            token: AccessToken::new("cached_access_token").unwrap(),
-            expires_at: Instant::now() + Duration::from_secs(7200),
-        };
-        *wechat.context.token_manager.cache.write().await = Some(cached);
+        let cached = StoredToken::new(
+            "cached_access_token",
+            SystemTime::now() + Duration::from_secs(7200),
+        );
+        let store = &wechat.context.token_manager.store;
+        store.set("wx1234567890abcdef", cached).await.unwrap();
 
         let token = wechat.get_access_token().await.unwrap();
 
@@ -1288,14 +2266,99 @@ mod tests {
     #[tokio::test]
     async fn test_wechat_mp_invalidate_token() {
         let wechat = create_test_wechat_mp();
-        let cached = CachedToken {
-            token: AccessToken::new("cached_access_token").unwrap(),
-            expires_at: Instant::now() + Duration::from_secs(7200),
-        };
-        *wechat.context.token_manager.cache.write().await = Some(cached);
+        let cached = StoredToken::new(
+            "cached_access_token",
+            SystemTime::now() + Duration::from_secs(7200),
+        );
+        let store = &wechat.context.token_manager.store;
+        store.set("wx1234567890abcdef", cached).await.unwrap();
 
         wechat.invalidate_token().await;
 
-        assert!(wechat.context.token_manager.cache.read().await.is_none());
+        assert!(store.get("wx1234567890abcdef").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_support_bundle_is_redacted() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "bundle_access_token",
+                "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/getcallbackip"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 45011,
+                "errmsg": "api minute-quota reach limit rid: 6440fe95-5cd8f3f8"
+            })))
+            .mount(&server)
+            .await;
+
+        let wechat = WechatMp::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri().replace("http://", "http://gateway:hidden@"))
+            .build()
+            .unwrap();
+        assert!(wechat.get_callback_ip().await.is_err());
+
+        let bundle = wechat.support_bundle().await;
+        assert_eq!(bundle["appid"], "wx1234567890abcdef");
+        assert_eq!(bundle["secret_configured"], true);
+        assert_eq!(bundle["token"]["cached"], true);
+        assert_eq!(bundle["recent_errcodes"][0]["errcode"], 45011);
+        assert_eq!(bundle["recent_errcodes"][0]["rid"], "6440fe95-5cd8f3f8");
+
+        let text = bundle.to_string();
+        for secret in ["secret1234567890ab", "bundle_access_token", "hidden"] {
+            assert!(!text.contains(secret), "bundle leaks {secret}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_category_accessor_shares_client() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "grouped_access_token",
+                "expires_in": 7200
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/wxa/business/hardware/sn_ticket/get"))
+            .and(query_param("access_token", "grouped_access_token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0,
+                "sn_ticket": "ticket"
+            })))
+            .mount(&server)
+            .await;
+
+        let wechat = WechatMp::builder()
+            .appid(AppId::new("wx1234567890abcdef").unwrap())
+            .secret(AppSecret::new("secret1234567890ab").unwrap())
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+
+        let request = HardwareRequest {
+            payload: [("sn".to_string(), serde_json::json!("sn-1"))].into(),
+        };
+        let response = wechat.hardware().get_sn_ticket(&request).await.unwrap();
+        assert_eq!(response.extra["sn_ticket"], "ticket");
+        assert_eq!(wechat.stats().category("hardware").requests, 1);
     }
 }
@@ -13,7 +13,7 @@ type Aes128CbcDecryptor = Decryptor<Aes128>;
 
 /// Decrypted user data with watermark
 #[non_exhaustive]
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct DecryptedUserData {
     /// Sensitive user data fields vary by scenario
     /// Common fields include: openId, unionId, nickName, gender, etc.
@@ -1,6 +1,7 @@
 //! Cryptography utilities for WeChat Mini Program data decryption
 //!
-//! Provides utilities for decrypting user data received from WeChat Mini Program.
+//! Provides utilities for decrypting user data received from WeChat Mini Program,
+//! and for exchanging sensitive fields with the user encrypt key ([`secure`]).
 //!
 //! ## Security
 //!
@@ -21,6 +22,8 @@
 //! ```
 
 pub mod aes;
+pub mod secure;
 
 pub use crate::types::Watermark;
 pub use aes::{decrypt_user_data, verify_watermark, DecryptedUserData};
+pub use secure::{session_signature, UserKeyCipher, SIG_METHOD_HMAC_SHA256};
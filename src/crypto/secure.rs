@@ -0,0 +1,301 @@
+//! Secure data transfer with the user encrypt key
+//!
+//! WeChat lets a mini program and its server exchange sensitive fields
+//! encrypted with a per-user key. The server fetches the key list through
+//! `getuserencryptkey` (signed with the session key) and the mini program
+//! obtains the same key via `wx.getUserCryptoManager().getLatestUserKey()`.
+//!
+//! - [`session_signature`] produces the `hmac_sha256` signature WeChat expects
+//!   for session-key authenticated calls.
+//! - [`UserKeyCipher`] encrypts and decrypts payloads (AES-128-CBC, PKCS#7,
+//!   base64 output) with a key from `key_info_list`; see
+//!   [`UserEncryptKeyResponse::cipher`](crate::api::user::UserEncryptKeyResponse::cipher)
+//!   for the newest unexpired one.
+
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::Aes128;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use cbc::cipher::block_padding::Pkcs7;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::api::user::EncryptKeyInfo;
+use crate::error::WechatError;
+
+type Aes128CbcEncryptor = cbc::Encryptor<Aes128>;
+type Aes128CbcDecryptor = cbc::Decryptor<Aes128>;
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signature method name sent alongside [`session_signature`] results.
+pub const SIG_METHOD_HMAC_SHA256: &str = "hmac_sha256";
+
+/// Compute the lowercase hex HMAC-SHA256 of `message` keyed by `session_key`.
+///
+/// `checksession`, `resetusersessionkey` and `getuserencryptkey` expect the
+/// signature of an empty message; request payloads that must be signed pass
+/// their serialized body instead.
+///
+/// # Errors
+/// Returns [`WechatError::Crypto`] if the HMAC cannot be keyed.
+pub fn session_signature(session_key: &str, message: &str) -> Result<String, WechatError> {
+    let mut mac = HmacSha256::new_from_slice(session_key.as_bytes())
+        .map_err(|e| WechatError::Crypto(format!("Invalid HMAC key: {}", e)))?;
+    mac.update(message.as_bytes());
+    Ok(mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// AES-128-CBC cipher bound to one version of the user encrypt key.
+#[derive(Clone)]
+pub struct UserKeyCipher {
+    key: [u8; 16],
+    iv: [u8; 16],
+    version: i32,
+}
+
+impl std::fmt::Debug for UserKeyCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserKeyCipher")
+            .field("key", &"[REDACTED]")
+            .field("iv", &"[REDACTED]")
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+impl UserKeyCipher {
+    /// Create a cipher from the `encrypt_key` and `iv` returned by WeChat.
+    ///
+    /// `encrypt_key` is base64 encoded. `iv` is the 16-character string
+    /// WeChat returns; a base64 encoded 16-byte IV is accepted as well.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] if the key or IV has the wrong length.
+    pub fn new(encrypt_key: &str, iv: &str, version: i32) -> Result<Self, WechatError> {
+        let key = BASE64
+            .decode(encrypt_key)
+            .map_err(|e| WechatError::Crypto(format!("Invalid encrypt_key: {}", e)))?;
+        let key: [u8; 16] = key.as_slice().try_into().map_err(|_| {
+            WechatError::Crypto(format!(
+                "Invalid encrypt_key length: expected 16, got {}",
+                key.len()
+            ))
+        })?;
+
+        let iv_bytes = if iv.len() == 16 {
+            iv.as_bytes().to_vec()
+        } else {
+            BASE64
+                .decode(iv)
+                .map_err(|e| WechatError::Crypto(format!("Invalid iv: {}", e)))?
+        };
+        let iv: [u8; 16] = iv_bytes.as_slice().try_into().map_err(|_| {
+            WechatError::Crypto(format!(
+                "Invalid IV length: expected 16, got {}",
+                iv_bytes.len()
+            ))
+        })?;
+
+        Ok(Self { key, iv, version })
+    }
+
+    /// Create a cipher from a `key_info_list` entry.
+    pub fn from_key_info(info: &EncryptKeyInfo) -> Result<Self, WechatError> {
+        Self::new(&info.encrypt_key, &info.iv, info.version)
+    }
+
+    /// Create a cipher from the highest key version in `key_info_list`.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] if the list is empty.
+    pub fn latest(key_info_list: &[EncryptKeyInfo]) -> Result<Self, WechatError> {
+        let info = key_info_list
+            .iter()
+            .max_by_key(|info| info.version)
+            .ok_or_else(|| WechatError::Crypto("key_info_list is empty".to_string()))?;
+        Self::from_key_info(info)
+    }
+
+    /// Key version, matching `version` on the mini program side.
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    /// Encrypt `plaintext` and return it base64 encoded.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] if padding fails.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<String, WechatError> {
+        let encryptor = Aes128CbcEncryptor::new(&self.key.into(), &self.iv.into());
+        let len = plaintext.len();
+        let mut buffer = plaintext.to_vec();
+        buffer.resize(len + 16 - len % 16, 0);
+        let encrypted = encryptor
+            .encrypt_padded_mut::<Pkcs7>(&mut buffer, len)
+            .map_err(|e| WechatError::Crypto(format!("Encryption failed: {:?}", e)))?;
+        Ok(BASE64.encode(encrypted))
+    }
+
+    /// Decrypt base64 encoded ciphertext produced by the mini program.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] on invalid base64 or padding.
+    pub fn decrypt(&self, ciphertext: &str) -> Result<Vec<u8>, WechatError> {
+        let mut buffer = BASE64
+            .decode(ciphertext)
+            .map_err(|e| WechatError::Crypto(format!("Invalid ciphertext: {}", e)))?;
+        let decryptor = Aes128CbcDecryptor::new(&self.key.into(), &self.iv.into());
+        let decrypted = decryptor
+            .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+            .map_err(|e| WechatError::Crypto(format!("Decryption failed: {:?}", e)))?;
+        Ok(decrypted.to_vec())
+    }
+
+    /// Encrypt the named top-level fields of a JSON object in place.
+    ///
+    /// String values are encrypted as-is; other values are encrypted as their
+    /// JSON text. Missing fields are skipped.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] if `payload` is not a JSON object or
+    /// encryption fails.
+    pub fn encrypt_fields(
+        &self,
+        payload: &mut serde_json::Value,
+        fields: &[&str],
+    ) -> Result<(), WechatError> {
+        let object = payload
+            .as_object_mut()
+            .ok_or_else(|| WechatError::Crypto("payload must be a JSON object".to_string()))?;
+        for field in fields {
+            if let Some(value) = object.get_mut(*field) {
+                let plaintext = match &*value {
+                    serde_json::Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                *value = serde_json::Value::String(self.encrypt(plaintext.as_bytes())?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypt the named top-level string fields of a JSON object in place.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Crypto`] if `payload` is not a JSON object, a
+    /// field is not a string, or decryption fails.
+    pub fn decrypt_fields(
+        &self,
+        payload: &mut serde_json::Value,
+        fields: &[&str],
+    ) -> Result<(), WechatError> {
+        let object = payload
+            .as_object_mut()
+            .ok_or_else(|| WechatError::Crypto("payload must be a JSON object".to_string()))?;
+        for field in fields {
+            if let Some(value) = object.get_mut(*field) {
+                let ciphertext = value.as_str().ok_or_else(|| {
+                    WechatError::Crypto(format!("Encrypted field `{}` must be a string", field))
+                })?;
+                let plaintext = self.decrypt(ciphertext)?;
+                let text = String::from_utf8(plaintext)
+                    .map_err(|e| WechatError::Crypto(format!("Invalid UTF-8: {}", e)))?;
+                *value = serde_json::Value::String(text);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "VI6BpyrK9XH4i4AIGe86tg==";
+    const IV: &str = "6003f73ec441c386";
+
+    #[test]
+    fn test_session_signature_empty_message() {
+        // HMAC-SHA256("key", "") reference value
+        assert_eq!(
+            session_signature("key", "").unwrap(),
+            "5d5d139563c95b5967b9bd9a8c9b233a9dedb45072794cd232dc1b74832607d0"
+        );
+    }
+
+    #[test]
+    fn test_cipher_round_trip() {
+        let cipher = UserKeyCipher::new(KEY, IV, 3).unwrap();
+        let encrypted = cipher.encrypt("13800138000".as_bytes()).unwrap();
+        assert_ne!(encrypted, "13800138000");
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), b"13800138000");
+        assert_eq!(cipher.version(), 3);
+    }
+
+    #[test]
+    fn test_cipher_accepts_base64_iv() {
+        let raw = UserKeyCipher::new(KEY, IV, 1).unwrap();
+        let encoded = UserKeyCipher::new(KEY, &BASE64.encode(IV), 1).unwrap();
+        assert_eq!(
+            raw.encrypt(b"data").unwrap(),
+            encoded.encrypt(b"data").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_cipher_rejects_bad_key() {
+        let result = UserKeyCipher::new("c2hvcnQ=", IV, 1);
+        assert!(matches!(result, Err(WechatError::Crypto(_))));
+    }
+
+    #[test]
+    fn test_latest_picks_highest_version() {
+        let list = vec![
+            EncryptKeyInfo {
+                encrypt_key: KEY.to_string(),
+                version: 1,
+                iv: IV.to_string(),
+                ..Default::default()
+            },
+            EncryptKeyInfo {
+                encrypt_key: KEY.to_string(),
+                version: 2,
+                iv: IV.to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(UserKeyCipher::latest(&list).unwrap().version(), 2);
+        assert!(UserKeyCipher::latest(&[]).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_fields_round_trip() {
+        let cipher = UserKeyCipher::new(KEY, IV, 1).unwrap();
+        let mut payload = serde_json::json!({"phone": "13800138000", "amount": 12, "note": "hi"});
+
+        cipher
+            .encrypt_fields(&mut payload, &["phone", "amount", "missing"])
+            .unwrap();
+        assert_ne!(payload["phone"], "13800138000");
+        assert!(payload["amount"].is_string());
+        assert_eq!(payload["note"], "hi");
+
+        cipher
+            .decrypt_fields(&mut payload, &["phone", "amount"])
+            .unwrap();
+        assert_eq!(payload["phone"], "13800138000");
+        assert_eq!(payload["amount"], "12");
+    }
+
+    #[test]
+    fn test_debug_redacts_key() {
+        let cipher = UserKeyCipher::new(KEY, IV, 1).unwrap();
+        let output = format!("{:?}", cipher);
+        assert!(!output.contains(IV));
+        assert!(output.contains("[REDACTED]"));
+    }
+}
@@ -16,13 +16,23 @@
 //! - `40001`: Invalid credential (access_token)
 //! - `40002`: Invalid grant_type
 //! - `40013`: Invalid appid
+//! - `40029`: Invalid `js_code` (surfaced by `auth_login` as `InvalidJsCode`)
+//! - `40163`: `js_code` already used (surfaced by `auth_login` as `CodeAlreadyUsed`)
 //! - `40125`: Invalid appsecret
+//! - `41004`: Appsecret missing
 
 use std::fmt;
 use std::sync::Arc;
 use thiserror::Error;
 
-use crate::token::RETRYABLE_ERROR_CODES;
+use crate::token::{FATAL_AUTH_ERROR_CODES, RETRYABLE_ERROR_CODES};
+
+/// This IP's calls need the admin's confirmation, which has been requested
+const NEEDS_ADMIN_CONFIRMATION: i32 = 89503;
+/// The admin refused this IP twice within 24 hours
+const ADMIN_REFUSED_TWICE: i32 = 89506;
+/// The admin refused this IP within the last hour
+const ADMIN_REFUSED_ONCE: i32 = 89507;
 
 /// HTTP/transport error wrapper
 ///
@@ -87,17 +97,31 @@ impl HttpError {
 ///
 /// - `Http`: HTTP request/response errors
 /// - `Json`: JSON serialization/deserialization errors
+/// - `Decode`: Response body did not match the expected type
 /// - `Api`: WeChat API returned an error
+/// - `Gateway`: A gateway in front of WeChat reported a failure via `errcode`
+/// - `InvalidJsCode`: Login `js_code` is invalid or expired
+/// - `CodeAlreadyUsed`: Login `js_code` was already exchanged
+/// - `NeedsAdminConfirmation`: The account admin must approve calls from this IP
+/// - `Live`: The live component rejected the call (errcode 300001–300036)
 /// - `Token`: Access token related errors
 /// - `Config`: Configuration errors
 /// - `Signature`: Signature verification errors
 /// - `Crypto`: Cryptography operation errors
+/// - `QueueFull`: A bounded per-user send queue rejected a message
+/// - `SubscribeQuotaExhausted`: No local subscribe authorization remains
+/// - `Cancelled`: The operation was aborted through a `CancellationToken`
+/// - `ReadOnly`: A mutating endpoint was blocked by read-only mode
 /// - `InvalidAppId`: Invalid AppId format
 /// - `InvalidOpenId`: Invalid OpenId format
 /// - `InvalidAccessToken`: Invalid access token
 /// - `InvalidAppSecret`: Invalid AppSecret
 /// - `InvalidSessionKey`: Invalid SessionKey
 /// - `InvalidUnionId`: Invalid UnionId
+/// - `Validation`: A request field failed local validation; nothing was sent
+///
+/// New variants may be added in minor releases, so matches need a wildcard arm.
+#[non_exhaustive]
 #[derive(Debug, Error)]
 pub enum WechatError {
     /// HTTP request/response error (includes decode errors)
@@ -108,13 +132,106 @@ pub enum WechatError {
     #[error("JSON serialization error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// Response body could not be decoded into the expected type
+    ///
+    /// # Fields
+    /// - `path`: JSON path at which decoding failed (e.g. `data[0].priTmplId`)
+    /// - `snippet`: Size-capped excerpt of the raw body, with sensitive fields redacted
+    /// - `message`: Underlying serde error message
+    #[error("Response decode error at `{path}`: {message} (body: {snippet})")]
+    Decode {
+        path: String,
+        snippet: String,
+        message: String,
+    },
+
     /// WeChat API returned an error
     ///
+    /// WeChat appends diagnostic suffixes such as `hint: [Xa1b2c3]` and
+    /// `rid: 6440fe95-5cd8f3f8-67e87b05` to `errmsg`; these are split off so
+    /// `message` stays stable across calls.
+    ///
+    /// # Fields
+    /// - `code`: Error code returned by WeChat API
+    /// - `message`: Error message from WeChat API, without `hint`/`rid` suffixes
+    /// - `hint`: Value of the `hint: [...]` suffix, if present
+    /// - `rid`: Request id from the `rid: ...` suffix, usable with `get_rid_info`
+    #[error(
+        "WeChat API error (code={code}): {message}{}",
+        .rid.as_deref().map(|rid| format!(" (rid: {})", rid)).unwrap_or_default()
+    )]
+    Api {
+        code: i32,
+        message: String,
+        hint: Option<String>,
+        rid: Option<String>,
+    },
+
+    /// A gateway in front of WeChat reported a failure through `errcode`
+    ///
+    /// Produced instead of `Api` for errcodes registered with
+    /// `GatewayErrcodes` on the client builder. Always retryable.
+    #[error("Gateway error (code={code}): {message}")]
+    Gateway { code: i32, message: String },
+
+    /// Login `js_code` is invalid or expired (errcode 40029)
+    ///
+    /// Codes from `wx.login()` are valid for five minutes. The client should
+    /// call `wx.login()` again and retry with the new code.
+    #[error("Invalid js_code (code=40029): {message}; call wx.login() for a new code")]
+    InvalidJsCode {
+        message: String,
+        rid: Option<String>,
+    },
+
+    /// Login `js_code` was already exchanged (errcode 40163)
+    ///
+    /// Each code can be used once; this usually means the client submitted the
+    /// same code twice. The client should call `wx.login()` again and retry.
+    #[error("js_code already used (code=40163): {message}; call wx.login() for a new code")]
+    CodeAlreadyUsed {
+        message: String,
+        rid: Option<String>,
+    },
+
+    /// The account admin must confirm calls from this IP in WeChat
+    /// (errcode 89503, 89506, 89507)
+    ///
+    /// 89503 means a confirmation request was sent and is awaiting the
+    /// admin; pipelines should pause and notify the admin rather than retry.
+    /// 89506 and 89507 mean the admin refused, and the IP is blocked for
+    /// `expire_seconds` (24 hours and 1 hour respectively).
+    #[error(
+        "Admin confirmation required (code={code}): {message}{}",
+        .expire_seconds.map(|secs| format!(" (retry after {}s)", secs)).unwrap_or_default()
+    )]
+    NeedsAdminConfirmation {
+        code: i32,
+        message: String,
+        expire_seconds: Option<u64>,
+        rid: Option<String>,
+    },
+
+    /// The live component (小程序直播) rejected the call
+    ///
+    /// Produced by [`LiveApi`](crate::api::live::LiveApi) for the errcodes
+    /// in [`LIVE_ERRCODES`](crate::api::live::LIVE_ERRCODES): 300001–300036,
+    /// and 48001 when the account has no live permission.
+    ///
     /// # Fields
     /// - `code`: Error code returned by WeChat API
-    /// - `message`: Error message from WeChat API
-    #[error("WeChat API error (code={code}): {message}")]
-    Api { code: i32, message: String },
+    /// - `message`: Error message from WeChat API, without `hint`/`rid` suffixes
+    /// - `reason`: What the code means
+    /// - `remediation`: What to change before retrying
+    /// - `rid`: Request id from the `rid: ...` suffix
+    #[error("Live component error (code={code}): {reason}; {remediation}")]
+    Live {
+        code: i32,
+        message: String,
+        reason: &'static str,
+        remediation: &'static str,
+        rid: Option<String>,
+    },
 
     /// Access token related error
     #[error("Access token error: {0}")]
@@ -132,6 +249,26 @@ pub enum WechatError {
     #[error("Crypto operation error: {0}")]
     Crypto(String),
 
+    /// A bounded per-user send queue is full and its overflow policy rejects
+    /// new messages
+    #[error("Send queue for {openid} is full (capacity {capacity})")]
+    QueueFull { openid: String, capacity: usize },
+
+    /// No subscribe authorization remains for this user and template in the
+    /// configured `SubscribeQuotaStore`; the message was not sent
+    #[error("No subscribe authorization left for {openid} on template {template_id}")]
+    SubscribeQuotaExhausted { openid: String, template_id: String },
+
+    /// The operation was aborted through a `CancellationToken`
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    /// A mutating endpoint was called while the client is in `Mode::ReadOnly`
+    ///
+    /// The request was not sent.
+    #[error("Blocked by read-only mode: {path}")]
+    ReadOnly { path: String },
+
     /// Invalid AppId format
     ///
     /// AppId must start with 'wx' and be 18 characters long
@@ -159,6 +296,14 @@ pub enum WechatError {
     /// Invalid UnionId
     #[error("Invalid UnionId: {0}")]
     InvalidUnionId(String),
+
+    /// A request field breaks a rule WeChat enforces, caught before sending
+    ///
+    /// # Fields
+    /// - `field`: Request field at fault, e.g. `scene`
+    /// - `message`: What is wrong and how to fix it
+    #[error("Invalid {field}: {message}")]
+    Validation { field: String, message: String },
 }
 
 impl Clone for WechatError {
@@ -169,20 +314,89 @@ impl Clone for WechatError {
                 std::io::ErrorKind::Other,
                 e.to_string(),
             ))),
-            WechatError::Api { code, message } => WechatError::Api {
+            WechatError::Decode {
+                path,
+                snippet,
+                message,
+            } => WechatError::Decode {
+                path: path.clone(),
+                snippet: snippet.clone(),
+                message: message.clone(),
+            },
+            WechatError::Api {
+                code,
+                message,
+                hint,
+                rid,
+            } => WechatError::Api {
+                code: *code,
+                message: message.clone(),
+                hint: hint.clone(),
+                rid: rid.clone(),
+            },
+            WechatError::Gateway { code, message } => WechatError::Gateway {
+                code: *code,
+                message: message.clone(),
+            },
+            WechatError::InvalidJsCode { message, rid } => WechatError::InvalidJsCode {
+                message: message.clone(),
+                rid: rid.clone(),
+            },
+            WechatError::CodeAlreadyUsed { message, rid } => WechatError::CodeAlreadyUsed {
+                message: message.clone(),
+                rid: rid.clone(),
+            },
+            WechatError::NeedsAdminConfirmation {
+                code,
+                message,
+                expire_seconds,
+                rid,
+            } => WechatError::NeedsAdminConfirmation {
                 code: *code,
                 message: message.clone(),
+                expire_seconds: *expire_seconds,
+                rid: rid.clone(),
+            },
+            WechatError::Live {
+                code,
+                message,
+                reason,
+                remediation,
+                rid,
+            } => WechatError::Live {
+                code: *code,
+                message: message.clone(),
+                reason,
+                remediation,
+                rid: rid.clone(),
             },
             WechatError::Token(msg) => WechatError::Token(msg.clone()),
             WechatError::Config(msg) => WechatError::Config(msg.clone()),
             WechatError::Signature(msg) => WechatError::Signature(msg.clone()),
             WechatError::Crypto(msg) => WechatError::Crypto(msg.clone()),
+            WechatError::QueueFull { openid, capacity } => WechatError::QueueFull {
+                openid: openid.clone(),
+                capacity: *capacity,
+            },
+            WechatError::SubscribeQuotaExhausted {
+                openid,
+                template_id,
+            } => WechatError::SubscribeQuotaExhausted {
+                openid: openid.clone(),
+                template_id: template_id.clone(),
+            },
+            WechatError::Cancelled => WechatError::Cancelled,
+            WechatError::ReadOnly { path } => WechatError::ReadOnly { path: path.clone() },
             WechatError::InvalidAppId(msg) => WechatError::InvalidAppId(msg.clone()),
             WechatError::InvalidOpenId(msg) => WechatError::InvalidOpenId(msg.clone()),
             WechatError::InvalidAccessToken(msg) => WechatError::InvalidAccessToken(msg.clone()),
             WechatError::InvalidAppSecret(msg) => WechatError::InvalidAppSecret(msg.clone()),
             WechatError::InvalidSessionKey(msg) => WechatError::InvalidSessionKey(msg.clone()),
             WechatError::InvalidUnionId(msg) => WechatError::InvalidUnionId(msg.clone()),
+            WechatError::Validation { field, message } => WechatError::Validation {
+                field: field.clone(),
+                message: message.clone(),
+            },
         }
     }
 }
@@ -191,25 +405,201 @@ impl WechatError {
     /// Check WeChat API response errcode, return error if non-zero.
     pub(crate) fn check_api(errcode: i32, errmsg: &str) -> Result<(), WechatError> {
         if errcode != 0 {
-            Err(WechatError::Api {
-                code: errcode,
-                message: errmsg.to_string(),
-            })
+            Err(WechatError::api(errcode, errmsg))
         } else {
             Ok(())
         }
     }
 
+    /// Build an `Api` error from a raw `errcode`/`errmsg` pair.
+    ///
+    /// Admin-confirmation errcodes become
+    /// [`NeedsAdminConfirmation`](WechatError::NeedsAdminConfirmation).
+    pub(crate) fn api(code: i32, errmsg: &str) -> Self {
+        let (message, hint, rid) = split_errmsg(errmsg);
+        let expire_seconds = match code {
+            NEEDS_ADMIN_CONFIRMATION => None,
+            ADMIN_REFUSED_TWICE => Some(24 * 60 * 60),
+            ADMIN_REFUSED_ONCE => Some(60 * 60),
+            _ => {
+                return WechatError::Api {
+                    code,
+                    message,
+                    hint,
+                    rid,
+                }
+            }
+        };
+        WechatError::NeedsAdminConfirmation {
+            code,
+            message,
+            expire_seconds,
+            rid,
+        }
+    }
+
+    /// Build a [`Validation`](WechatError::Validation) error for `field`.
+    pub(crate) fn validation(field: &str, message: impl Into<String>) -> Self {
+        WechatError::Validation {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Map code2Session `js_code` failures to [`WechatError::InvalidJsCode`]
+    /// and [`WechatError::CodeAlreadyUsed`]; other errors pass through.
+    pub(crate) fn into_login_error(self) -> Self {
+        match self {
+            WechatError::Api {
+                code: 40029,
+                message,
+                rid,
+                ..
+            } => WechatError::InvalidJsCode { message, rid },
+            WechatError::Api {
+                code: 40163,
+                message,
+                rid,
+                ..
+            } => WechatError::CodeAlreadyUsed { message, rid },
+            other => other,
+        }
+    }
+
+    /// Map live component errcodes to [`WechatError::Live`]; other errors
+    /// pass through.
+    pub(crate) fn into_live_error(self) -> Self {
+        let known = match &self {
+            WechatError::Api { code, .. } => crate::api::live::live_errcode(*code),
+            _ => None,
+        };
+        match (self, known) {
+            (
+                WechatError::Api {
+                    code, message, rid, ..
+                },
+                Some(known),
+            ) => WechatError::Live {
+                code,
+                message,
+                reason: known.reason,
+                remediation: known.remediation,
+                rid,
+            },
+            (other, _) => other,
+        }
+    }
+
+    /// Request id attached to an API error, if WeChat returned one.
+    pub fn rid(&self) -> Option<&str> {
+        match self {
+            WechatError::Api { rid, .. }
+            | WechatError::NeedsAdminConfirmation { rid, .. }
+            | WechatError::InvalidJsCode { rid, .. }
+            | WechatError::CodeAlreadyUsed { rid, .. }
+            | WechatError::Live { rid, .. } => rid.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns true when the client can recover by obtaining fresh input
+    /// (a new `wx.login()` code) and retrying.
+    ///
+    /// Unlike [`is_transient`](Self::is_transient), repeating the same request
+    /// will not succeed; frontends should re-run the login flow.
+    pub fn is_client_retryable(&self) -> bool {
+        matches!(
+            self,
+            WechatError::InvalidJsCode { .. } | WechatError::CodeAlreadyUsed { .. }
+        )
+    }
+
+    /// Returns true for credential errors (invalid AppID, invalid or missing
+    /// AppSecret) that need a configuration fix rather than a retry.
+    pub fn is_fatal_auth(&self) -> bool {
+        matches!(self, WechatError::Api { code, .. } if FATAL_AUTH_ERROR_CODES.contains(code))
+    }
+
     /// Returns true when this error is safe to retry.
     pub fn is_transient(&self) -> bool {
         match self {
             WechatError::Http(err) => err.is_transient(),
             WechatError::Api { code, .. } => RETRYABLE_ERROR_CODES.contains(code),
+            WechatError::Gateway { .. } => true,
             _ => false,
         }
     }
 }
 
+/// Split a raw `errmsg` into its base message, `hint` and `rid`.
+///
+/// Handles both orders WeChat uses (`... hint: [x] rid: y` and
+/// `..., rid: y`), full-width colons, and stray separators left behind.
+fn split_errmsg(errmsg: &str) -> (String, Option<String>, Option<String>) {
+    let mut rest = errmsg.to_string();
+
+    let mut rid = None;
+    if let Some((start, value_start)) = find_marker(&rest, "rid") {
+        let value = rest[value_start..].trim();
+        if !value.is_empty() {
+            rid = Some(value.to_string());
+        }
+        rest.truncate(start);
+    }
+
+    let mut hint = None;
+    if let Some((start, value_start)) = find_marker(&rest, "hint") {
+        let tail = rest[value_start..].trim_start();
+        let (value, remainder) = match tail.strip_prefix('[') {
+            Some(bracketed) => match bracketed.find(']') {
+                Some(end) => (&bracketed[..end], &bracketed[end + 1..]),
+                None => (bracketed, ""),
+            },
+            None => (tail, ""),
+        };
+        let value = value.trim();
+        if !value.is_empty() {
+            hint = Some(value.to_string());
+        }
+        rest = format!("{} {}", &rest[..start], remainder);
+    }
+
+    let message = rest
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches([',', ';', '.', '，', '；', '。'])
+        .trim_end()
+        .to_string();
+    (message, hint, rid)
+}
+
+/// Locate `name:` (or `name：`) as a standalone word, returning the marker
+/// start and the offset just after the colon.
+fn find_marker(text: &str, name: &str) -> Option<(usize, usize)> {
+    let mut search_from = 0;
+    while let Some(pos) = text[search_from..].find(name) {
+        let start = search_from + pos;
+        let after = &text[start + name.len()..];
+        let colon_len = if after.starts_with(':') {
+            1
+        } else if after.starts_with('：') {
+            '：'.len_utf8()
+        } else {
+            0
+        };
+        let standalone = text[..start]
+            .chars()
+            .next_back()
+            .map_or(true, |c| !c.is_alphanumeric() && c != '_');
+        if colon_len > 0 && standalone {
+            return Some((start, start + name.len() + colon_len));
+        }
+        search_from = start + name.len();
+    }
+    None
+}
+
 impl From<reqwest::Error> for WechatError {
     fn from(e: reqwest::Error) -> Self {
         WechatError::Http(HttpError::Reqwest(Arc::new(e)))
@@ -269,7 +659,7 @@ mod tests {
     fn test_check_api_error() {
         let result = WechatError::check_api(40013, "invalid appid");
         assert!(result.is_err());
-        if let Err(WechatError::Api { code, message }) = result {
+        if let Err(WechatError::Api { code, message, .. }) = result {
             assert_eq!(code, 40013);
             assert_eq!(message, "invalid appid");
         } else {
@@ -277,12 +667,128 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_into_login_error_maps_js_code_errors() {
+        let err = WechatError::api(40029, "invalid code, rid: 64a1-b2c3").into_login_error();
+        assert!(
+            matches!(&err, WechatError::InvalidJsCode { message, .. } if message == "invalid code")
+        );
+        assert_eq!(err.rid(), Some("64a1-b2c3"));
+        assert!(err.is_client_retryable());
+        assert!(!err.is_transient());
+
+        let err = WechatError::api(40163, "code been used").into_login_error();
+        assert!(matches!(err, WechatError::CodeAlreadyUsed { .. }));
+        assert!(err.is_client_retryable());
+
+        let err = WechatError::api(40013, "invalid appid").into_login_error();
+        assert!(matches!(err, WechatError::Api { code: 40013, .. }));
+        assert!(!err.is_client_retryable());
+    }
+
+    #[test]
+    fn test_admin_confirmation_errcodes_are_distinct() {
+        let err = WechatError::check_api(89503, "need admin confirm rid: abc-123").unwrap_err();
+        assert!(matches!(
+            &err,
+            WechatError::NeedsAdminConfirmation {
+                code: 89503,
+                expire_seconds: None,
+                ..
+            }
+        ));
+        assert_eq!(err.rid(), Some("abc-123"));
+        assert!(!err.is_transient());
+
+        let err = WechatError::api(89506, "refused twice");
+        assert!(matches!(
+            err,
+            WechatError::NeedsAdminConfirmation {
+                expire_seconds: Some(86400),
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Admin confirmation required (code=89506): refused twice (retry after 86400s)"
+        );
+
+        let err = WechatError::api(89507, "refused once");
+        assert!(matches!(
+            err,
+            WechatError::NeedsAdminConfirmation {
+                expire_seconds: Some(3600),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_check_api_splits_hint_and_rid() {
+        let err = WechatError::check_api(
+            40013,
+            "invalid appid hint: [Ad3vLa0] rid: 6440fe95-5cd8f3f8-67e87b05",
+        )
+        .unwrap_err();
+        match &err {
+            WechatError::Api {
+                code,
+                message,
+                hint,
+                rid,
+            } => {
+                assert_eq!(*code, 40013);
+                assert_eq!(message, "invalid appid");
+                assert_eq!(hint.as_deref(), Some("Ad3vLa0"));
+                assert_eq!(rid.as_deref(), Some("6440fe95-5cd8f3f8-67e87b05"));
+            }
+            other => panic!("Expected Api error, got {:?}", other),
+        }
+        assert_eq!(err.rid(), Some("6440fe95-5cd8f3f8-67e87b05"));
+        assert_eq!(
+            err.to_string(),
+            "WeChat API error (code=40013): invalid appid (rid: 6440fe95-5cd8f3f8-67e87b05)"
+        );
+    }
+
+    #[test]
+    fn test_split_errmsg_variants() {
+        let cases = [
+            ("invalid appid", ("invalid appid", None, None)),
+            (
+                "invalid code, rid: 64c0a1b2-1c2d3e4f-5a6b7c8d",
+                ("invalid code", None, Some("64c0a1b2-1c2d3e4f-5a6b7c8d")),
+            ),
+            (
+                "invalid credential, access_token is invalid or not latest hint: [yH5a] rid: 1-2-3",
+                (
+                    "invalid credential, access_token is invalid or not latest",
+                    Some("yH5a"),
+                    Some("1-2-3"),
+                ),
+            ),
+            (
+                "system error hint: [Zx09] more details",
+                ("system error more details", Some("Zx09"), None),
+            ),
+            (
+                "参数错误，rid：abc-123",
+                ("参数错误", None, Some("abc-123")),
+            ),
+            ("android rid invalid", ("android rid invalid", None, None)),
+        ];
+
+        for (raw, (message, hint, rid)) in cases {
+            let (m, h, r) = split_errmsg(raw);
+            assert_eq!(m, message, "message for {:?}", raw);
+            assert_eq!(h.as_deref(), hint, "hint for {:?}", raw);
+            assert_eq!(r.as_deref(), rid, "rid for {:?}", raw);
+        }
+    }
+
     #[test]
     fn test_wechat_error_clone() {
-        let err = WechatError::Api {
-            code: 40013,
-            message: "invalid appid".to_string(),
-        };
+        let err = WechatError::api(40013, "invalid appid rid: abc");
         let cloned = err.clone();
         assert_eq!(format!("{}", err), format!("{}", cloned));
 
@@ -371,10 +877,7 @@ mod tests {
     #[test]
     fn test_wechat_error_is_transient_for_api_and_all_other_variants() {
         for &code in RETRYABLE_ERROR_CODES {
-            let retryable = WechatError::Api {
-                code,
-                message: "retryable".to_string(),
-            };
+            let retryable = WechatError::api(code, "retryable");
             assert!(
                 retryable.is_transient(),
                 "code {} should be transient",
@@ -382,19 +885,26 @@ mod tests {
             );
         }
 
-        let non_retryable_api = WechatError::Api {
-            code: 40013,
-            message: "invalid appid".to_string(),
-        };
+        let non_retryable_api = WechatError::api(40013, "invalid appid");
         assert!(!non_retryable_api.is_transient());
 
         let json_error = serde_json::from_str::<serde_json::Value>("not json").unwrap_err();
         let non_transient_variants = [
             WechatError::Json(json_error),
+            WechatError::Decode {
+                path: ".".to_string(),
+                snippet: "{}".to_string(),
+                message: "missing field".to_string(),
+            },
             WechatError::Token("token".to_string()),
             WechatError::Config("config".to_string()),
             WechatError::Signature("sig".to_string()),
             WechatError::Crypto("crypto".to_string()),
+            WechatError::QueueFull {
+                openid: "openid".to_string(),
+                capacity: 1,
+            },
+            WechatError::Cancelled,
             WechatError::InvalidAppId("appid".to_string()),
             WechatError::InvalidOpenId("openid".to_string()),
             WechatError::InvalidAccessToken("token".to_string()),
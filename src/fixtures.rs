@@ -0,0 +1,391 @@
+//! Deterministic fixture generators for request types
+//!
+//! Available with the `fixtures` feature. Every [`Fixture`] value is derived
+//! from a `u64` seed, so a failing case can be reproduced from its seed alone.
+//! Generated values respect the constraints WeChat documents for each type,
+//! e.g. [`UrlLinkOptions`] only carries the expiry field matching its
+//! `expire_type`.
+//!
+//! With the `proptest` feature every fixture type implements
+//! [`proptest::arbitrary::Arbitrary`] by drawing a seed, so `any::<T>()`
+//! works directly. Shrinking walks the seed toward zero, so a minimised case
+//! is another valid value rather than a smaller one of the same shape.
+//!
+//! ```ignore
+//! use proptest::prelude::*;
+//! use wechat_mp_sdk::api::qrcode::UrlLinkOptions;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn url_link_serializes(options in any::<UrlLinkOptions>()) {
+//!         serde_json::to_value(&options).unwrap();
+//!     }
+//! }
+//! ```
+//!
+//! Other frameworks can map their own `u64` input through
+//! [`Fixture::from_seed`].
+
+use std::collections::HashMap;
+
+use crate::api::qrcode::{
+    EnvVersion, LineColor, QrcodeOptions, ShortLinkOptions, UnlimitQrcodeOptions, UrlLinkOptions,
+    UrlSchemeExpire, UrlSchemeOptions,
+};
+use crate::api::subscribe::{
+    Lang, MiniProgramState, SubscribeMessageData, SubscribeMessageOptions, SubscribeMessageValue,
+};
+use crate::types::OpenId;
+
+/// Reference "now" for generated expiry timestamps (2024-01-01T00:00:00Z).
+const FIXTURE_EPOCH: i64 = 1_704_067_200;
+const DAY_SECS: i64 = 86_400;
+
+const PATH_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789_";
+/// Characters accepted in a `getwxacodeunlimit` scene.
+const SCENE_CHARS: &[u8] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!#$&'()*+,/:;=?@-._~";
+const OPENID_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_-";
+
+/// Seeded pseudo-random source (SplitMix64)
+#[derive(Debug, Clone)]
+pub struct FixtureRng {
+    state: u64,
+}
+
+impl FixtureRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `low..=high`
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        debug_assert!(low <= high);
+        let span = high.abs_diff(low).saturating_add(1);
+        low.wrapping_add((self.next_u64() % span) as i64)
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    /// `Some` half of the time
+    pub fn option<T>(&mut self, generate: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        self.bool().then(|| generate(self))
+    }
+
+    /// String of `min..=max` bytes drawn from the ASCII `charset`
+    pub fn string(&mut self, charset: &[u8], min: usize, max: usize) -> String {
+        let len = self.range(min as i64, max as i64) as usize;
+        (0..len)
+            .map(|_| charset[self.next_u64() as usize % charset.len()] as char)
+            .collect()
+    }
+
+    pub fn choose<T: Clone>(&mut self, items: &[T]) -> T {
+        items[self.next_u64() as usize % items.len()].clone()
+    }
+
+    fn page_path(&mut self) -> String {
+        let dir = self.string(PATH_CHARS, 1, 12);
+        format!("pages/{dir}/index")
+    }
+
+    fn query(&mut self) -> String {
+        let key = self.string(PATH_CHARS, 1, 8);
+        let value = self.string(PATH_CHARS, 0, 16);
+        format!("{key}={value}")
+    }
+}
+
+/// Types that can be generated deterministically from a seed
+pub trait Fixture: Sized {
+    fn fixture(rng: &mut FixtureRng) -> Self;
+
+    fn from_seed(seed: u64) -> Self {
+        Self::fixture(&mut FixtureRng::new(seed))
+    }
+}
+
+impl Fixture for EnvVersion {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        rng.choose(&[EnvVersion::Release, EnvVersion::Trial, EnvVersion::Develop])
+    }
+}
+
+impl Fixture for LineColor {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        LineColor {
+            r: rng.range(0, 255) as u8,
+            g: rng.range(0, 255) as u8,
+            b: rng.range(0, 255) as u8,
+        }
+    }
+}
+
+impl Fixture for QrcodeOptions {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        let auto_color = rng.option(FixtureRng::bool);
+        let mut options = QrcodeOptions::new();
+        options.path = rng.option(FixtureRng::page_path);
+        options.width = rng.option(|rng| rng.range(280, 1280) as u32);
+        // line_color only applies when auto_color is off
+        options.line_color = match auto_color {
+            Some(true) => None,
+            _ => rng.option(LineColor::fixture),
+        };
+        options.auto_color = auto_color;
+        options.is_hyaline = rng.option(FixtureRng::bool);
+        options.env_version = rng.option(EnvVersion::fixture);
+        options
+    }
+}
+
+impl Fixture for UnlimitQrcodeOptions {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        let mut options = UnlimitQrcodeOptions::new(rng.string(SCENE_CHARS, 1, 32));
+        let auto_color = rng.option(FixtureRng::bool);
+        options.page = rng.option(FixtureRng::page_path);
+        options.width = rng.option(|rng| rng.range(280, 1280) as u32);
+        options.line_color = match auto_color {
+            Some(true) => None,
+            _ => rng.option(LineColor::fixture),
+        };
+        options.auto_color = auto_color;
+        options.is_hyaline = rng.option(FixtureRng::bool);
+        options.env_version = rng.option(EnvVersion::fixture);
+        // Unpublished versions cannot have their page checked
+        options.check_path = match options.env_version {
+            Some(EnvVersion::Trial | EnvVersion::Develop) => Some(false),
+            _ => rng.option(FixtureRng::bool),
+        };
+        options
+    }
+}
+
+/// Expiry as `(expire_type, expire_time, expire_interval)`: type 0 carries
+/// only a timestamp, type 1 only an interval in days (both at most 30 days).
+fn expiry(rng: &mut FixtureRng) -> (u8, Option<i64>, Option<i64>) {
+    if rng.bool() {
+        (0, Some(FIXTURE_EPOCH + rng.range(60, 30 * DAY_SECS)), None)
+    } else {
+        (1, None, Some(rng.range(1, 30)))
+    }
+}
+
+impl Fixture for UrlSchemeExpire {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        let (expire_type, expire_time, expire_interval) = expiry(rng);
+        UrlSchemeExpire {
+            expire_type,
+            expire_time,
+            expire_interval,
+        }
+    }
+}
+
+impl Fixture for UrlSchemeOptions {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        UrlSchemeOptions {
+            path: rng.option(FixtureRng::page_path),
+            query: rng.option(FixtureRng::query),
+            expire: rng.option(UrlSchemeExpire::fixture),
+        }
+    }
+}
+
+impl Fixture for UrlLinkOptions {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        let (expire_type, expire_time, expire_interval) = match rng.option(expiry) {
+            Some((expire_type, time, interval)) => (Some(expire_type), time, interval),
+            None => (None, None, None),
+        };
+        UrlLinkOptions {
+            path: rng.option(FixtureRng::page_path),
+            query: rng.option(FixtureRng::query),
+            expire_type,
+            expire_time,
+            expire_interval,
+        }
+    }
+}
+
+impl Fixture for ShortLinkOptions {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        let page_url = match rng.option(FixtureRng::query) {
+            Some(query) => format!("{}?{}", rng.page_path(), query),
+            None => rng.page_path(),
+        };
+        ShortLinkOptions { page_url }
+    }
+}
+
+impl Fixture for OpenId {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        OpenId::from_valid(rng.string(OPENID_CHARS, 28, 28))
+    }
+}
+
+impl Fixture for SubscribeMessageOptions {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        let mut data = SubscribeMessageData::new();
+        for index in 1..=rng.range(1, 5) {
+            let kind = rng.choose(&["thing", "time", "number", "phrase", "character_string"]);
+            data.insert(
+                format!("{kind}{index}"),
+                SubscribeMessageValue::new(rng.string(PATH_CHARS, 1, 20)),
+            );
+        }
+        SubscribeMessageOptions {
+            touser: OpenId::fixture(rng),
+            template_id: rng.string(OPENID_CHARS, 43, 43),
+            data,
+            page: rng.option(FixtureRng::page_path),
+            miniprogram_state: rng.option(|rng| {
+                rng.choose(&[
+                    MiniProgramState::Developer,
+                    MiniProgramState::Trial,
+                    MiniProgramState::Formal,
+                ])
+            }),
+            lang: rng.option(|rng| rng.choose(&[Lang::ZhCN, Lang::EnUS, Lang::ZhHK, Lang::ZhTW])),
+        }
+    }
+}
+
+impl Fixture for SubscribeMessageValue {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        SubscribeMessageValue::new(rng.string(PATH_CHARS, 1, 20))
+    }
+}
+
+impl<V: Fixture> Fixture for HashMap<String, V> {
+    fn fixture(rng: &mut FixtureRng) -> Self {
+        (0..rng.range(0, 4))
+            .map(|_| (rng.string(PATH_CHARS, 1, 10), V::fixture(rng)))
+            .collect()
+    }
+}
+
+/// `Arbitrary` through [`Fixture::from_seed`] for each listed type
+macro_rules! arbitrary_from_seed {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            cfg_proptest! {
+                impl proptest::arbitrary::Arbitrary for $ty {
+                    type Parameters = ();
+                    type Strategy = proptest::strategy::Map<proptest::num::u64::Any, fn(u64) -> Self>;
+
+                    fn arbitrary_with((): ()) -> Self::Strategy {
+                        proptest::strategy::Strategy::prop_map(
+                            proptest::num::u64::ANY,
+                            Self::from_seed as fn(u64) -> Self,
+                        )
+                    }
+                }
+            }
+        )*
+    };
+}
+
+arbitrary_from_seed!(
+    EnvVersion,
+    LineColor,
+    QrcodeOptions,
+    UnlimitQrcodeOptions,
+    UrlSchemeExpire,
+    UrlSchemeOptions,
+    UrlLinkOptions,
+    ShortLinkOptions,
+    OpenId,
+    SubscribeMessageOptions,
+    SubscribeMessageValue,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures_are_deterministic() {
+        for seed in 0..50 {
+            let a = serde_json::to_value(UrlLinkOptions::from_seed(seed)).unwrap();
+            let b = serde_json::to_value(UrlLinkOptions::from_seed(seed)).unwrap();
+            assert_eq!(a, b);
+            assert_eq!(
+                SubscribeMessageOptions::from_seed(seed).touser,
+                SubscribeMessageOptions::from_seed(seed).touser
+            );
+        }
+        assert_ne!(
+            serde_json::to_value(UnlimitQrcodeOptions::from_seed(1)).unwrap(),
+            serde_json::to_value(UnlimitQrcodeOptions::from_seed(2)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_url_link_expiry_is_never_contradictory() {
+        for seed in 0..1000 {
+            let json = serde_json::to_value(UrlLinkOptions::from_seed(seed)).unwrap();
+            let has_time = json.get("expire_time").is_some();
+            let has_interval = json.get("expire_interval").is_some();
+            match json.get("expire_type").and_then(|v| v.as_u64()) {
+                Some(0) => assert!(has_time && !has_interval, "seed {seed}: {json}"),
+                Some(1) => assert!(!has_time && has_interval, "seed {seed}: {json}"),
+                Some(other) => panic!("seed {seed}: unexpected expire_type {other}"),
+                None => assert!(!has_time && !has_interval, "seed {seed}: {json}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixtures_pass_client_validation() {
+        use crate::types::MiniProgramPath;
+
+        for seed in 0..1000 {
+            UnlimitQrcodeOptions::from_seed(seed).validate().unwrap();
+            UrlSchemeOptions::from_seed(seed).validate().unwrap();
+            UrlLinkOptions::from_seed(seed).validate().unwrap();
+            SubscribeMessageOptions::from_seed(seed).validate().unwrap();
+            MiniProgramPath::new(ShortLinkOptions::from_seed(seed).page_url).unwrap();
+            if let Some(path) = QrcodeOptions::from_seed(seed).path {
+                MiniProgramPath::new(path).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_qrcode_fixtures_respect_documented_limits() {
+        for seed in 0..1000 {
+            let options = UnlimitQrcodeOptions::from_seed(seed);
+            assert!((1..=32).contains(&options.scene.len()));
+            assert!(options.width.map_or(true, |w| (280..=1280).contains(&w)));
+            assert!(!(options.auto_color == Some(true) && options.line_color.is_some()));
+
+            let json = serde_json::to_value(QrcodeOptions::from_seed(seed)).unwrap();
+            assert!(json.as_object().unwrap().values().all(|v| !v.is_null()));
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn test_arbitrary_values_pass_client_validation(
+            unlimited in proptest::prelude::any::<UnlimitQrcodeOptions>(),
+            link in proptest::prelude::any::<UrlLinkOptions>(),
+            message in proptest::prelude::any::<SubscribeMessageOptions>(),
+        ) {
+            proptest::prop_assert!(unlimited.validate().is_ok());
+            proptest::prop_assert!(link.validate().is_ok());
+            proptest::prop_assert!(message.validate().is_ok());
+        }
+    }
+}
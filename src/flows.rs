@@ -0,0 +1,631 @@
+//! Multi-step flows built from [`WechatMp`] calls
+//!
+//! Each helper is the orchestration behind one of the runnable examples
+//! (`cargo run --example <name> --features flows`):
+//!
+//! | Helper | Example |
+//! |--------|---------|
+//! | [`login_and_decrypt`] | `flow_login_decrypt` |
+//! | [`send_subscribe_campaign`] | `flow_subscribe_campaign` |
+//! | [`generate_qrcode_batch`] | `flow_qrcode_batch` |
+//! | [`ship_order`] | `flow_logistics_shipment` |
+//! | [`NotificationRouter`] | `flow_order_status` |
+//! | [`OrderNotifications`] | `flow_order_lifecycle` |
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+
+use crate::api::auth::{LoginResponse, SessionRefresh, SessionStore};
+use crate::api::customer_service::Message;
+use crate::api::logistics::{
+    AddOrderRequest, AddOrderResponse, LogisticsRequest, LogisticsResponse,
+};
+use crate::api::outbox::{OrderCreation, OrderOutbox};
+use crate::api::qrcode::{QrcodeManifest, QrcodePipeline, QrcodeSink, UnlimitQrcodeOptions};
+use crate::api::shipping::{
+    LogisticsType, ShippingItem, ShippingOrderKey, UploadShippingInfoRequest,
+};
+use crate::api::subscribe::{
+    ExpectedTemplate, SubscribeMessageData, SubscribeMessageOptions, TemplateDriftReport,
+};
+use crate::client::WechatMp;
+use crate::crypto::{decrypt_user_data, verify_watermark, DecryptedUserData};
+use crate::error::WechatError;
+use crate::types::{OpenId, SessionKey};
+use crate::utils::lock;
+
+/// Result of [`login_and_decrypt`]
+#[derive(Debug, Clone)]
+pub struct LoginWithUserData {
+    pub session: LoginResponse,
+    pub user: DecryptedUserData,
+}
+
+/// Exchange a `wx.login` code and decrypt the user data sent with it.
+///
+/// The decrypted watermark must name this mini program's appid.
+///
+/// # Errors
+/// Returns the login error, [`WechatError::Crypto`] if decryption fails, or
+/// [`WechatError::Signature`] on a watermark mismatch.
+pub async fn login_and_decrypt(
+    wechat: &WechatMp,
+    js_code: &str,
+    encrypted_data: &str,
+    iv: &str,
+) -> Result<LoginWithUserData, WechatError> {
+    let session = wechat.auth_login(js_code).await?;
+    let user = decrypt_user_data(&session.session_key, encrypted_data, iv)?;
+    verify_watermark(&user, wechat.appid())?;
+    Ok(LoginWithUserData { session, user })
+}
+
+/// Outcome of [`send_subscribe_campaign`]
+#[derive(Debug, Clone, Default)]
+pub struct CampaignReport {
+    /// Recipients the message was delivered to, in input order
+    pub sent: Vec<OpenId>,
+    /// Recipients whose send failed, with the error
+    pub failed: Vec<(OpenId, WechatError)>,
+}
+
+impl CampaignReport {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Send one subscribe template to every recipient and sort the outcomes.
+///
+/// A failed send does not stop the campaign.
+pub async fn send_subscribe_campaign<F>(
+    wechat: &WechatMp,
+    template_id: &str,
+    recipients: &[OpenId],
+    data_fn: F,
+) -> Result<CampaignReport, WechatError>
+where
+    F: Fn(&OpenId) -> SubscribeMessageData,
+{
+    let outcomes = wechat
+        .send_subscribe_message_to_many(recipients, template_id, data_fn)
+        .await?;
+    let mut report = CampaignReport::default();
+    for (openid, result) in outcomes {
+        match result {
+            Ok(()) => report.sent.push(openid),
+            Err(error) => report.failed.push((openid, error)),
+        }
+    }
+    Ok(report)
+}
+
+/// Generate one unlimited code per scene, all opening `page`, into `sink`.
+pub async fn generate_qrcode_batch<I>(
+    wechat: &WechatMp,
+    page: &str,
+    scenes: I,
+    sink: Arc<dyn QrcodeSink>,
+) -> QrcodeManifest
+where
+    I: IntoIterator,
+    I::Item: Into<String>,
+{
+    let mut template = UnlimitQrcodeOptions::new("");
+    template.page = Some(page.to_string());
+    wechat
+        .run_qrcode_pipeline(&QrcodePipeline::new(template), scenes, sink, None)
+        .await
+}
+
+/// Result of [`ship_order`]
+#[derive(Debug, Clone)]
+pub struct Shipment {
+    /// The created (or previously created) express order
+    pub order: OrderCreation<LogisticsResponse>,
+    /// Tracking path of the waybill
+    pub path: LogisticsResponse,
+}
+
+impl Shipment {
+    pub fn waybill_id(&self) -> Option<&str> {
+        self.order
+            .response()
+            .extra
+            .get("waybill_id")
+            .and_then(Value::as_str)
+    }
+}
+
+/// Create an express order at most once, then fetch its tracking path.
+///
+/// See [`LogisticsApi::create_or_get_order`](crate::api::LogisticsApi::create_or_get_order)
+/// for how `outbox` prevents duplicate waybills.
+pub async fn ship_order(
+    wechat: &WechatMp,
+    outbox: &dyn OrderOutbox,
+    request: &LogisticsRequest,
+) -> Result<Shipment, WechatError> {
+    let order = wechat.create_or_get_order(outbox, request).await?;
+
+    let mut payload: HashMap<String, Value> = ["order_id", "openid", "delivery_id"]
+        .into_iter()
+        .filter_map(|field| Some((field.to_string(), request.payload.get(field)?.clone())))
+        .collect();
+    if let Some(waybill_id) = order.response().extra.get("waybill_id") {
+        payload.insert("waybill_id".to_string(), waybill_id.clone());
+    }
+    let path = wechat.get_path(&LogisticsRequest::new(payload)).await?;
+    Ok(Shipment { order, path })
+}
+
+/// How long after a user's last message customer service messages can reach
+/// them
+pub const CUSTOMER_SERVICE_WINDOW: Duration = Duration::from_secs(48 * 60 * 60);
+
+/// errcode for a subscribe send without an accepted subscription
+const SUBSCRIPTION_REFUSED: i32 = 43101;
+
+/// Channel a [`NotificationRouter`] delivered through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationChannel {
+    Subscribe,
+    CustomerService,
+}
+
+/// Send status updates (order shipped, refund issued, ...) as a subscribe
+/// message, falling back to a customer service message.
+///
+/// The fallback is used when the user has no subscribe authorization left
+/// (errcode 43101, or [`WechatError::SubscribeQuotaExhausted`] under an
+/// enforced quota) and last interacted within the
+/// [session window](Self::session_window). Other subscribe errors are
+/// returned as they are.
+#[derive(Clone)]
+pub struct NotificationRouter {
+    wechat: WechatMp,
+    session_window: Duration,
+}
+
+impl std::fmt::Debug for NotificationRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationRouter")
+            .field("appid", &self.wechat.appid())
+            .field("session_window", &self.session_window)
+            .finish()
+    }
+}
+
+impl NotificationRouter {
+    pub fn new(wechat: WechatMp) -> Self {
+        Self {
+            wechat,
+            session_window: CUSTOMER_SERVICE_WINDOW,
+        }
+    }
+
+    /// Treat users as reachable by customer service for `window` after
+    /// their last interaction (default [`CUSTOMER_SERVICE_WINDOW`])
+    pub fn session_window(mut self, window: Duration) -> Self {
+        self.session_window = window;
+        self
+    }
+
+    /// Send `subscribe`, or `fallback` to the same user when they have no
+    /// subscribe authorization left.
+    ///
+    /// `last_interaction` is when the user last messaged the mini program,
+    /// e.g. recorded from [`CallbackMessage`](crate::callback::CallbackMessage)
+    /// pushes. With `None` the fallback is attempted and WeChat decides.
+    ///
+    /// # Errors
+    /// Returns the subscribe error when it is not a missing authorization
+    /// or the session window has closed, otherwise the customer service
+    /// error.
+    pub async fn send(
+        &self,
+        subscribe: SubscribeMessageOptions,
+        fallback: Message,
+        last_interaction: Option<SystemTime>,
+    ) -> Result<NotificationChannel, WechatError> {
+        let touser = subscribe.touser.clone();
+        let refused = match self.wechat.send_subscribe_message(subscribe).await {
+            Ok(()) => return Ok(NotificationChannel::Subscribe),
+            Err(error) if is_subscription_missing(&error) => error,
+            Err(error) => return Err(error),
+        };
+        if !self.window_open(last_interaction) {
+            return Err(refused);
+        }
+
+        self.wechat
+            .send_customer_service_message(touser.as_str(), fallback)
+            .await?;
+        Ok(NotificationChannel::CustomerService)
+    }
+
+    fn window_open(&self, last_interaction: Option<SystemTime>) -> bool {
+        match last_interaction {
+            Some(at) => !matches!(
+                SystemTime::now().duration_since(at),
+                Ok(age) if age >= self.session_window
+            ),
+            None => true,
+        }
+    }
+}
+
+fn is_subscription_missing(error: &WechatError) -> bool {
+    matches!(
+        error,
+        WechatError::Api {
+            code: SUBSCRIPTION_REFUSED,
+            ..
+        } | WechatError::SubscribeQuotaExhausted { .. }
+    )
+}
+
+/// Observers for the stages of [`OrderNotifications`]
+///
+/// Every method defaults to doing nothing; implement the ones to record,
+/// e.g. to write an audit log or update an order record. Hooks run inline,
+/// so keep them short.
+pub trait OrderHooks: Send + Sync {
+    /// A user session was established or checked
+    fn session_resolved(&self, _session: &OrderSession) {}
+
+    /// The expected subscribe templates were compared with the account
+    fn templates_checked(&self, _report: &TemplateDriftReport) {}
+
+    /// A notification attempt failed and will be retried after `delay`
+    fn notify_retrying(&self, _attempt: u32, _error: &WechatError, _delay: Duration) {}
+
+    /// A notification reached `touser` through `channel`
+    fn notified(&self, _touser: &OpenId, _channel: NotificationChannel) {}
+
+    /// An express order exists for the waybill request, newly created or
+    /// found in the outbox
+    fn waybill_created(&self, _order: &OrderCreation<AddOrderResponse>) {}
+
+    /// Shipping information of a paid order was uploaded
+    fn shipping_uploaded(&self, _request: &UploadShippingInfoRequest) {}
+}
+
+struct NoHooks;
+
+impl OrderHooks for NoHooks {}
+
+/// Session produced by the first stage of [`OrderNotifications`]
+#[derive(Debug, Clone)]
+pub enum OrderSession {
+    /// Signed in with a `wx.login` code; the session key has been stored
+    LoggedIn {
+        openid: String,
+        session_key: SessionKey,
+    },
+    /// An existing session was checked and rotated if it had expired
+    Checked {
+        openid: String,
+        refresh: SessionRefresh,
+    },
+}
+
+impl OrderSession {
+    pub fn openid(&self) -> &str {
+        match self {
+            OrderSession::LoggedIn { openid, .. } | OrderSession::Checked { openid, .. } => openid,
+        }
+    }
+}
+
+/// A status update for [`OrderNotifications::notify`]
+#[derive(Debug, Clone)]
+pub struct OrderNotification {
+    pub subscribe: SubscribeMessageOptions,
+    /// Sent instead when the user has no subscribe authorization left
+    pub fallback: Message,
+    /// When the user last messaged the mini program, see
+    /// [`NotificationRouter::send`]
+    pub last_interaction: Option<SystemTime>,
+}
+
+/// A paid order to ship with [`OrderNotifications::ship`]
+///
+/// `waybill` must carry the `openid` of the payer; it is repeated in the
+/// shipping information with the `delivery_id` of the express company.
+#[derive(Debug, Clone)]
+pub struct OrderShipment {
+    pub waybill: AddOrderRequest,
+    pub order_key: ShippingOrderKey,
+    /// Goods description shown to the buyer, e.g. `T-shirt x2`
+    pub item_desc: String,
+    /// Masked receiver phone number, required by some express companies
+    pub receiver_contact: Option<String>,
+}
+
+/// Result of [`OrderNotifications::ship`]
+#[derive(Debug, Clone)]
+pub struct ShippedOrder {
+    pub order: OrderCreation<AddOrderResponse>,
+    pub tracking_no: String,
+    pub channel: NotificationChannel,
+}
+
+/// The e-commerce order lifecycle, one stage per method:
+///
+/// 1. [`login`](Self::login) / [`refresh_session`](Self::refresh_session)
+///    resolve the buyer's session.
+/// 2. [`check_templates`](Self::check_templates) compares the expected
+///    subscribe templates with the account, usually once at startup.
+///    Templates found drifted are refused by later notifications.
+/// 3. [`notify`](Self::notify) sends a status update through a
+///    [`NotificationRouter`], retrying transient errors.
+/// 4. [`create_waybill`](Self::create_waybill) creates the express order at
+///    most once per [`OrderOutbox`].
+/// 5. [`upload_shipping_info`](Self::upload_shipping_info) reports the
+///    shipment of the WeChat Pay order.
+///
+/// [`ship`](Self::ship) runs stages 4, 5 and 3 in order. Each stage reports
+/// to the [`OrderHooks`].
+///
+/// ```rust,ignore
+/// let orders = OrderNotifications::new(wechat)
+///     .expect_templates(vec![ExpectedTemplate::new("order_shipped_tmpl")])
+///     .hooks(Arc::new(AuditLog::default()));
+/// orders.check_templates().await?;
+/// let shipped = orders
+///     .ship(&outbox, shipment, |tracking_no| shipped_notice(tracking_no))
+///     .await?;
+/// ```
+#[derive(Clone)]
+pub struct OrderNotifications {
+    wechat: WechatMp,
+    router: NotificationRouter,
+    templates: Vec<ExpectedTemplate>,
+    drifted: Arc<Mutex<HashSet<String>>>,
+    send_attempts: u32,
+    retry_delay: Duration,
+    hooks: Arc<dyn OrderHooks>,
+}
+
+impl std::fmt::Debug for OrderNotifications {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OrderNotifications")
+            .field("router", &self.router)
+            .field("templates", &self.templates)
+            .field("send_attempts", &self.send_attempts)
+            .field("retry_delay", &self.retry_delay)
+            .finish()
+    }
+}
+
+impl OrderNotifications {
+    pub fn new(wechat: WechatMp) -> Self {
+        Self {
+            router: NotificationRouter::new(wechat.clone()),
+            wechat,
+            templates: Vec::new(),
+            drifted: Arc::new(Mutex::new(HashSet::new())),
+            send_attempts: 3,
+            retry_delay: Duration::from_millis(500),
+            hooks: Arc::new(NoHooks),
+        }
+    }
+
+    /// Route notifications through `router`, e.g. one with a custom
+    /// session window
+    pub fn router(mut self, router: NotificationRouter) -> Self {
+        self.router = router;
+        self
+    }
+
+    /// Templates compared by [`check_templates`](Self::check_templates)
+    pub fn expect_templates(mut self, templates: Vec<ExpectedTemplate>) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Try a notification up to `attempts` times (default 3), waiting
+    /// `delay` (default 500 ms, doubled each time) between transient
+    /// failures
+    pub fn retry(mut self, attempts: u32, delay: Duration) -> Self {
+        self.send_attempts = attempts.max(1);
+        self.retry_delay = delay;
+        self
+    }
+
+    pub fn hooks(mut self, hooks: Arc<dyn OrderHooks>) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Exchange a `wx.login` code and store the session key in `store`.
+    pub async fn login(
+        &self,
+        js_code: &str,
+        store: &dyn SessionStore,
+    ) -> Result<OrderSession, WechatError> {
+        let response = self.wechat.auth_login(js_code).await?;
+        let session_key = SessionKey::new(response.session_key)?;
+        store
+            .update_session_key(&response.openid, &session_key)
+            .await?;
+        let session = OrderSession::LoggedIn {
+            openid: response.openid,
+            session_key,
+        };
+        self.hooks.session_resolved(&session);
+        Ok(session)
+    }
+
+    /// Check the session of a returning user, rotating an expired key.
+    ///
+    /// See [`AuthApi::ensure_fresh_session`](crate::api::auth::AuthApi::ensure_fresh_session).
+    pub async fn refresh_session(
+        &self,
+        openid: &str,
+        signature: &str,
+        sig_method: &str,
+        store: &dyn SessionStore,
+    ) -> Result<OrderSession, WechatError> {
+        let refresh = self
+            .wechat
+            .ensure_fresh_session(openid, signature, sig_method, store)
+            .await?;
+        let session = OrderSession::Checked {
+            openid: openid.to_string(),
+            refresh,
+        };
+        self.hooks.session_resolved(&session);
+        Ok(session)
+    }
+
+    /// Compare the [expected templates](Self::expect_templates) with the
+    /// account.
+    ///
+    /// Until the next check, [`notify`](Self::notify) refuses templates
+    /// with drift instead of sending data that no longer fits them.
+    pub async fn check_templates(&self) -> Result<TemplateDriftReport, WechatError> {
+        let report = self.wechat.verify_templates(&self.templates).await?;
+        *lock(&self.drifted) = report
+            .drifts
+            .iter()
+            .map(|drift| drift.private_template_id().to_string())
+            .collect();
+        self.hooks.templates_checked(&report);
+        Ok(report)
+    }
+
+    /// Send a status update, falling back to customer service as described
+    /// on [`NotificationRouter`].
+    ///
+    /// Transient errors ([`WechatError::is_transient`]) are retried as
+    /// configured with [`retry`](Self::retry).
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Config`] without sending when the last
+    /// [`check_templates`](Self::check_templates) found the template drifted,
+    /// otherwise the error of the last attempt.
+    pub async fn notify(
+        &self,
+        notification: OrderNotification,
+    ) -> Result<NotificationChannel, WechatError> {
+        let template_id = &notification.subscribe.template_id;
+        if lock(&self.drifted)
+            .contains(template_id)
+        {
+            return Err(WechatError::Config(format!(
+                "subscribe template {template_id} drifted; run check_templates after updating it"
+            )));
+        }
+
+        let touser = notification.subscribe.touser.clone();
+        let mut delay = self.retry_delay;
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .router
+                .send(
+                    notification.subscribe.clone(),
+                    notification.fallback.clone(),
+                    notification.last_interaction,
+                )
+                .await;
+            match result {
+                Ok(channel) => {
+                    self.hooks.notified(&touser, channel);
+                    return Ok(channel);
+                }
+                Err(error) if error.is_transient() && attempt < self.send_attempts => {
+                    self.hooks.notify_retrying(attempt, &error, delay);
+                    tokio::time::sleep(delay).await;
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Create the express order of `request` at most once.
+    ///
+    /// See [`LogisticsApi::create_or_get_express_order`](crate::api::LogisticsApi::create_or_get_express_order).
+    pub async fn create_waybill(
+        &self,
+        outbox: &dyn OrderOutbox,
+        request: &AddOrderRequest,
+    ) -> Result<OrderCreation<AddOrderResponse>, WechatError> {
+        let order = self.wechat.create_or_get_express_order(outbox, request).await?;
+        self.hooks.waybill_created(&order);
+        Ok(order)
+    }
+
+    /// Report the shipment of a WeChat Pay order.
+    pub async fn upload_shipping_info(
+        &self,
+        request: &UploadShippingInfoRequest,
+    ) -> Result<(), WechatError> {
+        self.wechat.upload_shipping_info(request).await?;
+        self.hooks.shipping_uploaded(request);
+        Ok(())
+    }
+
+    /// Create the waybill, upload the shipping information with its
+    /// tracking number, then send the notification built from it.
+    ///
+    /// Every step can be repeated safely: the outbox returns the existing
+    /// waybill and a new upload replaces the previous one. After an error,
+    /// call `ship` again with the same outbox.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] when `shipment.waybill` has no
+    /// `openid`, [`WechatError::Config`] when WeChat returns no `waybill_id`,
+    /// otherwise the error of the failed stage.
+    pub async fn ship<F>(
+        &self,
+        outbox: &dyn OrderOutbox,
+        shipment: OrderShipment,
+        notification: F,
+    ) -> Result<ShippedOrder, WechatError>
+    where
+        F: FnOnce(&str) -> OrderNotification,
+    {
+        let payer = shipment.waybill.openid.clone().ok_or_else(|| {
+            WechatError::validation("waybill.openid", "required to upload shipping info")
+        })?;
+
+        let order = self.create_waybill(outbox, &shipment.waybill).await?;
+        let tracking_no = order.response().waybill_id.clone();
+        if tracking_no.is_empty() {
+            return Err(WechatError::Config(
+                "express order has no waybill_id".to_string(),
+            ));
+        }
+
+        let mut item = ShippingItem::express(
+            &tracking_no,
+            shipment.waybill.delivery_id,
+            shipment.item_desc,
+        );
+        if let Some(contact) = shipment.receiver_contact {
+            item = item.receiver_contact(contact);
+        }
+        let request = UploadShippingInfoRequest::new(
+            shipment.order_key,
+            LogisticsType::Express,
+            payer,
+            vec![item],
+        );
+        self.upload_shipping_info(&request).await?;
+
+        let channel = self.notify(notification(&tracking_no)).await?;
+        Ok(ShippedOrder {
+            order,
+            tracking_no,
+            channel,
+        })
+    }
+}
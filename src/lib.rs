@@ -1,7 +1,7 @@
 //! WeChat Mini Program SDK for Rust
 //!
 //! A complete Rust SDK for the WeChat Mini Program server-side APIs,
-//! covering **128 endpoints** across 24 categories.
+//! covering **129 endpoints** across 24 categories.
 //!
 //! ## API Coverage
 //!
@@ -21,7 +21,7 @@
 //! | Plugin | 2 |
 //! | Nearby Mini Programs | 4 |
 //! | Cloud Development | 10 |
-//! | Live Streaming | 9 |
+//! | Live Streaming | 10 |
 //! | Hardware / IoT | 6 |
 //! | Instant Delivery | 5 |
 //! | Logistics | 6 |
@@ -56,12 +56,32 @@
 //! }
 //! ```
 //!
+//! ## Feature Flags
+//!
+//! | Feature | Default | Enables |
+//! |---------|---------|---------|
+//! | `rustls-tls` | yes | TLS via rustls |
+//! | `native-tls` | no | TLS via the platform library |
+//! | `gzip` | no | [`client::Compression`], gzip request/response bodies |
+//! | `charset` | no | `client::ResponseCharset`: GBK/GB2312 JSON responses are transcoded instead of failing to decode |
+//! | `fixtures` | no | `fixtures`: deterministic generators for request types |
+//! | `proptest` | no | `proptest::arbitrary::Arbitrary` for the `fixtures` types; implies `fixtures` |
+//! | `opentelemetry` | no | `TraceContext::from_current_span`: trace context of the current `tracing` span through `tracing-opentelemetry` |
+//! | `flows` | no | `flows`: login, campaign, QR batch, shipment and order status helpers behind the `flow_*` examples |
+//! | `schemars` | no | `schemars::JsonSchema` for request and response types; `openapi_spec` references them as component schemas |
+//! | `snapshots` | no | Test-only: request body snapshots in `fixtures/requests` |
+//!
+//! Feature-gated items carry a "crate feature" badge on docs.rs.
+//!
 //! ## Modules
 //!
 //! - [`api`] - WeChat API modules (auth, user, message, qrcode, analytics, etc.)
+//! - [`callback`] - Verification and parsing of inbound push messages
 //! - [`client`] - HTTP client for API calls
 //! - [`crypto`] - Data decryption utilities
 //! - [`error`] - Error types
+//! - `flows` - Multi-step helpers (feature `flows`)
+//! - [`limit`] - Token-bucket rate limiting shared with application code
 //! - [`token`] - Access token management (internal, for advanced users)
 //! - [`types`] - Type definitions for WeChat API entities
 //!
@@ -74,7 +94,7 @@
 //!
 //! match result {
 //!     Ok(response) => { /* handle success */ }
-//!     Err(WechatError::Api { code, message }) => {
+//!     Err(WechatError::Api { code, message, .. }) => {
 //!         eprintln!("API error: {} - {}", code, message);
 //!     }
 //!     Err(WechatError::Http(e)) => {
@@ -86,14 +106,30 @@
 //! }
 //! ```
 
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+#[macro_use]
+mod macros;
+
 pub mod api;
+pub mod callback;
 pub mod client;
 pub mod crypto;
 pub mod error;
+pub mod limit;
+cfg_fixtures! {
+    pub mod fixtures;
+}
+cfg_flows! {
+    pub mod flows;
+}
 pub mod middleware;
+mod redact;
 pub mod token;
 pub mod types;
 mod utils;
 
+pub use bytes::Bytes;
 pub use client::{WechatClient, WechatClientBuilder, WechatMp, WechatMpBuilder};
 pub use error::WechatError;
+pub use tokio_util::sync::CancellationToken;
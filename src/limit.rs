@@ -0,0 +1,329 @@
+//! Request pacing
+//!
+//! [`RateLimiter`] is the token bucket the client awaits before sending each
+//! request when configured with
+//! [`WechatMpBuilder::rate_limiter`](crate::WechatMpBuilder::rate_limiter).
+//! Cloning shares the bucket, so application code calling WeChat directly
+//! (e.g. endpoints the SDK does not wrap) can draw from the same budget:
+//!
+//! ```
+//! use std::time::Duration;
+//! use wechat_mp_sdk::limit::RateLimiter;
+//!
+//! # async fn run() {
+//! let limiter = RateLimiter::new(20, Duration::from_secs(1));
+//! // pass `limiter.clone()` to the builder, then for raw calls:
+//! limiter.acquire().await;
+//! # }
+//! ```
+//!
+//! [`Jitter`] spaces out retries of failed calls. Pass one to
+//! [`RetryMiddleware::with_jitter`](crate::middleware::RetryMiddleware::with_jitter)
+//! or [`TokenManagerBuilder::jitter`](crate::token::TokenManagerBuilder::jitter)
+//! to pick the strategy, or seed it to make retry timing reproducible in
+//! tests:
+//!
+//! ```
+//! use std::time::Duration;
+//! use wechat_mp_sdk::limit::{Jitter, JitterMode};
+//!
+//! let jitter = Jitter::seeded(JitterMode::Full, 42);
+//! let delay = jitter.delay(100, 2, Duration::ZERO);
+//! assert!(delay <= Duration::from_millis(400));
+//! assert_eq!(Jitter::seeded(JitterMode::Full, 42).delay(100, 2, Duration::ZERO), delay);
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use tokio::time::Instant;
+
+use crate::utils::lock;
+
+/// Upper bound of any retry delay
+const MAX_BACKOFF_MS: u64 = 30_000;
+
+/// Shared token bucket
+///
+/// Holds up to `burst` permits and refills `permits` every `per`, spread
+/// evenly. Starts full.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    inner: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    /// Permits added per second
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.capacity);
+        self.refilled_at = now;
+    }
+}
+
+impl RateLimiter {
+    /// Allow `permits` requests every `per`, with bursts of up to `permits`.
+    ///
+    /// A `permits` of 0 is treated as 1; a zero `per` as one millisecond.
+    pub fn new(permits: u32, per: Duration) -> Self {
+        Self::with_burst(permits, per, permits)
+    }
+
+    /// Like [`new`](Self::new), with a bucket size different from the rate.
+    pub fn with_burst(permits: u32, per: Duration, burst: u32) -> Self {
+        let per = per.max(Duration::from_millis(1));
+        let capacity = f64::from(burst.max(1));
+        Self {
+            inner: Arc::new(Mutex::new(Bucket {
+                capacity,
+                rate: f64::from(permits.max(1)) / per.as_secs_f64(),
+                tokens: capacity,
+                refilled_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// Take a permit if one is available right now.
+    pub fn try_acquire(&self) -> bool {
+        let mut bucket = lock(&self.inner);
+        bucket.refill(Instant::now());
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Wait until a permit is available and take it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = lock(&self.inner);
+                bucket.refill(Instant::now());
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.rate)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Whole permits available right now
+    pub fn available(&self) -> u32 {
+        let mut bucket = lock(&self.inner);
+        bucket.refill(Instant::now());
+        bucket.tokens as u32
+    }
+}
+
+/// How [`Jitter`] randomizes the exponential backoff `base × 2^attempt`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum JitterMode {
+    /// The backoff plus up to half of it again
+    #[default]
+    Additive,
+    /// Anywhere between zero and the backoff
+    Full,
+    /// Half the backoff plus up to the other half
+    Equal,
+    /// Between the base delay and three times the previous delay,
+    /// independent of `attempt`
+    Decorrelated,
+    /// Exactly the backoff
+    None,
+}
+
+/// Retry delay strategy with its own random source
+///
+/// Unseeded jitters draw from a per-process random seed so replicas do not
+/// retry in lockstep; [`seeded`](Self::seeded) ones repeat the same delays.
+/// Clones share the random source. Delays never exceed 30 seconds.
+#[derive(Debug, Clone)]
+pub struct Jitter {
+    mode: JitterMode,
+    state: Arc<Mutex<u64>>,
+}
+
+impl Default for Jitter {
+    fn default() -> Self {
+        Self::new(JitterMode::default())
+    }
+}
+
+impl Jitter {
+    /// Randomly seeded jitter using `mode`
+    pub fn new(mode: JitterMode) -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        SystemTime::now().hash(&mut hasher);
+        std::process::id().hash(&mut hasher);
+        Self::seeded(mode, hasher.finish())
+    }
+
+    /// Jitter producing the same delay sequence for the same `seed`
+    pub fn seeded(mode: JitterMode, seed: u64) -> Self {
+        Self {
+            mode,
+            state: Arc::new(Mutex::new(seed)),
+        }
+    }
+
+    pub fn mode(&self) -> JitterMode {
+        self.mode
+    }
+
+    /// Delay before retry number `attempt` (0-based) of a call whose retries
+    /// start at `base_delay_ms`
+    ///
+    /// `previous` is the delay returned for the prior attempt, or zero; only
+    /// [`JitterMode::Decorrelated`] uses it.
+    pub fn delay(&self, base_delay_ms: u64, attempt: u32, previous: Duration) -> Duration {
+        let backoff = base_delay_ms
+            .saturating_mul(1u64.checked_shl(attempt).unwrap_or(u64::MAX))
+            .min(MAX_BACKOFF_MS);
+        let millis = match self.mode {
+            JitterMode::Additive => backoff + self.below(backoff / 2),
+            JitterMode::Full => self.below(backoff + 1),
+            JitterMode::Equal => backoff / 2 + self.below(backoff - backoff / 2 + 1),
+            JitterMode::Decorrelated => {
+                let low = base_delay_ms.min(MAX_BACKOFF_MS);
+                let previous = u64::try_from(previous.as_millis()).unwrap_or(u64::MAX);
+                let high = previous.saturating_mul(3).clamp(low, MAX_BACKOFF_MS);
+                low + self.below(high - low + 1)
+            }
+            JitterMode::None => backoff,
+        };
+        Duration::from_millis(millis.min(MAX_BACKOFF_MS))
+    }
+
+    /// Uniform value in `0..bound`, or 0 for an empty range
+    fn below(&self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+        // splitmix64
+        let mut state = lock(&self.state);
+        *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        (z ^ (z >> 31)) % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_empty() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(3600));
+        assert_eq!(limiter.available(), 2);
+        assert!(limiter.try_acquire());
+        assert!(limiter.clone().try_acquire());
+        assert!(!limiter.try_acquire());
+        assert_eq!(limiter.available(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_refill() {
+        let limiter = RateLimiter::with_burst(1, Duration::from_millis(50), 1);
+        limiter.acquire().await;
+
+        let started = std::time::Instant::now();
+        limiter.acquire().await;
+        assert!(started.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_jitter_additive_bounds() {
+        let jitter = Jitter::default();
+        for attempt in 0..9 {
+            let base = 100 * 2u64.pow(attempt);
+            let delay = jitter.delay(100, attempt, Duration::ZERO);
+            assert!(delay >= Duration::from_millis(base));
+            assert!(delay <= Duration::from_millis(base + (base / 2)));
+        }
+    }
+
+    #[test]
+    fn test_jitter_caps_and_handles_overflow() {
+        for mode in [
+            JitterMode::Additive,
+            JitterMode::Full,
+            JitterMode::Equal,
+            JitterMode::Decorrelated,
+            JitterMode::None,
+        ] {
+            let jitter = Jitter::new(mode);
+            assert!(jitter.delay(1_000, 20, Duration::from_secs(20)) <= Duration::from_secs(30));
+            assert!(jitter.delay(u64::MAX, u32::MAX, Duration::MAX) <= Duration::from_secs(30));
+        }
+        assert_eq!(
+            Jitter::default().delay(u64::MAX, u32::MAX, Duration::ZERO),
+            Duration::from_secs(30)
+        );
+    }
+
+    #[test]
+    fn test_jitter_same_attempt_produces_varied_delays() {
+        let jitter = Jitter::default();
+        let mut delays: Vec<Duration> = (0..20)
+            .map(|_| jitter.delay(1000, 3, Duration::ZERO))
+            .collect();
+        delays.sort();
+        delays.dedup();
+        assert!(delays.len() >= 2, "got {} unique out of 20", delays.len());
+    }
+
+    #[test]
+    fn test_seeded_jitter_is_reproducible_per_mode() {
+        let sequence = |mode, seed| {
+            let jitter = Jitter::seeded(mode, seed);
+            let mut previous = Duration::ZERO;
+            (0..6)
+                .map(|attempt| {
+                    previous = jitter.delay(100, attempt, previous);
+                    previous
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            sequence(JitterMode::Decorrelated, 7),
+            sequence(JitterMode::Decorrelated, 7)
+        );
+        assert_ne!(sequence(JitterMode::Full, 7), sequence(JitterMode::Full, 8));
+
+        for (attempt, delay) in sequence(JitterMode::Full, 1).into_iter().enumerate() {
+            assert!(delay <= Duration::from_millis(100 << attempt));
+        }
+        for (attempt, delay) in sequence(JitterMode::Equal, 1).into_iter().enumerate() {
+            let backoff = 100u64 << attempt;
+            assert!(delay >= Duration::from_millis(backoff / 2));
+            assert!(delay <= Duration::from_millis(backoff));
+        }
+        let decorrelated = sequence(JitterMode::Decorrelated, 1);
+        assert_eq!(decorrelated[0], Duration::from_millis(100));
+        for pair in decorrelated.windows(2) {
+            assert!(pair[1] >= Duration::from_millis(100));
+            assert!(pair[1] <= pair[0] * 3);
+        }
+        assert_eq!(
+            sequence(JitterMode::None, 1),
+            [100, 200, 400, 800, 1600, 3200].map(Duration::from_millis)
+        );
+    }
+}
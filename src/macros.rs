@@ -0,0 +1,68 @@
+//! Feature-gating helpers
+//!
+//! Each `cfg_<feature>!` macro applies `#[cfg(feature = "...")]` to the items
+//! it wraps and, when documenting on docs.rs, `#[doc(cfg(feature = "..."))]`,
+//! so rustdoc shows which feature enables an item. Gate new public items
+//! through these macros rather than bare `#[cfg]`.
+
+macro_rules! cfg_gzip {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "gzip")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+            $item
+        )*
+    };
+}
+
+macro_rules! cfg_charset {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "charset")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "charset")))]
+            $item
+        )*
+    };
+}
+
+macro_rules! cfg_fixtures {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "fixtures")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "fixtures")))]
+            $item
+        )*
+    };
+}
+
+// Only used inside the `fixtures` module, which `proptest` implies
+#[cfg(feature = "fixtures")]
+macro_rules! cfg_proptest {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "proptest")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "proptest")))]
+            $item
+        )*
+    };
+}
+
+macro_rules! cfg_flows {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "flows")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "flows")))]
+            $item
+        )*
+    };
+}
+
+macro_rules! cfg_opentelemetry {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "opentelemetry")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "opentelemetry")))]
+            $item
+        )*
+    };
+}
@@ -1,25 +1,101 @@
 use std::future::Future;
 use std::pin::Pin;
-use std::time::Instant;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 
 use log::{debug, info};
 use reqwest::{Request, Response};
+use serde::Deserialize;
 use tower::{Layer, Service};
 
+use crate::client::{buffer_response_with_body, current_attempts};
+use crate::redact::Redactor;
+
+/// Output format of [`LoggingMiddleware`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable request and response lines
+    #[default]
+    Text,
+    /// One JSON object per response with `ts_ms`, `method`, `endpoint`,
+    /// `status`, `errcode`, `latency_ms` and `attempt`
+    ///
+    /// `status` is `null` for transport errors, `errcode` for non-JSON
+    /// bodies, and `attempt` outside a [`WechatClient`](crate::WechatClient)
+    /// call.
+    Json,
+}
+
+/// Receives each JSON log line instead of the `log` crate
+pub type LogSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Logs each request and response
+///
+/// URLs and (in verbose mode) request bodies pass through the crate's central
+/// redaction filter first, so `access_token`, `secret`, `code`, `session_key`,
+/// signatures and similar fields are never written to the log. Extra field
+/// names can be added with [`redact_field`](Self::redact_field).
+///
+/// With [`LogFormat::Json`] each response becomes one JSON line for log
+/// pipelines; [`sample_one_in`](Self::sample_one_in) thins out successful
+/// calls on high-QPS deployments while failures are always logged.
 #[derive(Clone)]
 pub struct LoggingMiddleware {
     verbose: bool,
+    redactor: Redactor,
+    format: LogFormat,
+    sink: Option<LogSink>,
+    sampler: Sampler,
 }
 
 impl LoggingMiddleware {
     pub fn new() -> Self {
-        Self { verbose: false }
+        Self {
+            verbose: false,
+            redactor: Redactor::default(),
+            format: LogFormat::default(),
+            sink: None,
+            sampler: Sampler::new(1),
+        }
+    }
+
+    /// Select the output format (default [`LogFormat::Text`])
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Write JSON lines to `sink` instead of the `log` crate
+    ///
+    /// Implies [`LogFormat::Json`].
+    pub fn json_sink(mut self, sink: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.format = LogFormat::Json;
+        self.sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Log only one in `n` successful requests (default every request)
+    ///
+    /// Transport errors, HTTP error statuses and non-zero errcodes are
+    /// always logged. Clones of this middleware share one counter.
+    pub fn sample_one_in(mut self, n: u64) -> Self {
+        self.sampler = Sampler::new(n);
+        self
     }
 
+    /// Log at debug level, including redacted request bodies
     pub fn verbose(mut self) -> Self {
         self.verbose = true;
         self
     }
+
+    /// Also redact query parameters and body fields named `name`
+    /// (case-insensitive), in addition to the built-in set
+    pub fn redact_field(mut self, name: impl Into<String>) -> Self {
+        self.redactor = self.redactor.with_field(name);
+        self
+    }
 }
 
 impl Default for LoggingMiddleware {
@@ -28,6 +104,31 @@ impl Default for LoggingMiddleware {
     }
 }
 
+/// Picks one request in `every`, counting across clones
+#[derive(Clone)]
+struct Sampler {
+    every: u64,
+    seen: Arc<AtomicU64>,
+}
+
+impl Sampler {
+    fn new(every: u64) -> Self {
+        Self {
+            every: every.max(1),
+            seen: Arc::default(),
+        }
+    }
+
+    fn sample(&self) -> bool {
+        self.every == 1 || self.seen.fetch_add(1, Ordering::Relaxed) % self.every == 0
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrcodeProbe {
+    errcode: Option<i64>,
+}
+
 impl<S> Layer<S> for LoggingMiddleware
 where
     S: Service<Request, Response = Response> + Clone + Send + 'static,
@@ -39,6 +140,10 @@ where
         LoggingMiddlewareService {
             inner,
             verbose: self.verbose,
+            redactor: self.redactor.clone(),
+            format: self.format,
+            sink: self.sink.clone(),
+            sampler: self.sampler.clone(),
         }
     }
 }
@@ -47,49 +152,21 @@ where
 pub struct LoggingMiddlewareService<S> {
     inner: S,
     verbose: bool,
+    redactor: Redactor,
+    format: LogFormat,
+    sink: Option<LogSink>,
+    sampler: Sampler,
 }
 
-const SENSITIVE_FIELDS: &[&str] = &[
-    "access_token",
-    "appsecret",
-    "secret",
-    "session_key",
-    "password",
-    "token",
-    "authorization",
-];
-
 impl<S> LoggingMiddlewareService<S> {
-    fn redact_url(url: &str) -> String {
-        if let Some(idx) = url.find('?') {
-            let base = &url[..idx];
-            let query = &url[idx + 1..];
-            let redacted_query: String = query
-                .split('&')
-                .map(|param| {
-                    if let Some(eq_idx) = param.find('=') {
-                        let key = &param[..eq_idx];
-                        if SENSITIVE_FIELDS.iter().any(|s| key.eq_ignore_ascii_case(s)) {
-                            format!("{}={}", key, "[REDACTED]")
-                        } else {
-                            param.to_string()
-                        }
-                    } else {
-                        param.to_string()
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join("&");
-            format!("{}?{}", base, redacted_query)
-        } else {
-            url.to_string()
-        }
-    }
-
-    fn log_request(method: &str, url: &str, verbose: bool) {
-        let safe_url = Self::redact_url(url);
+    fn log_request(redactor: &Redactor, request: &Request, verbose: bool) {
+        let method = request.method().as_str();
+        let safe_url = redactor.redact_url(request.url().as_str());
         if verbose {
             debug!("[WechatMp] >>> {} {}", method, safe_url);
+            if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+                debug!("[WechatMp] >>> body: {}", redactor.redact_body(body));
+            }
         } else {
             info!("[WechatMp] {} {}", method, safe_url);
         }
@@ -108,6 +185,25 @@ impl<S> LoggingMiddlewareService<S> {
         }
     }
 
+    /// Buffer a JSON-looking body and read its `errcode`
+    async fn peek_errcode(response: Response) -> Result<(Response, Option<i64>), reqwest::Error> {
+        let textual = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map_or(true, |value| {
+                value.contains("json") || value.starts_with("text/")
+            });
+        if !textual {
+            return Ok((response, None));
+        }
+        let (response, body) = buffer_response_with_body(response).await?;
+        let errcode = serde_json::from_slice::<ErrcodeProbe>(&body)
+            .ok()
+            .and_then(|probe| probe.errcode);
+        Ok((response, errcode))
+    }
+
     fn status_text(status: u16) -> &'static str {
         match status {
             200 => "OK",
@@ -135,7 +231,7 @@ impl<S, Error> Service<Request> for LoggingMiddlewareService<S>
 where
     S: Service<Request, Response = Response, Error = Error> + Send + Clone + 'static,
     S::Future: Send,
-    Error: Send + 'static,
+    Error: From<reqwest::Error> + Send + 'static,
 {
     type Response = Response;
     type Error = Error;
@@ -149,21 +245,62 @@ where
     }
 
     fn call(&mut self, req: Request) -> Self::Future {
-        let method = req.method().as_str().to_string();
-        let url = req.url().to_string();
         let verbose = self.verbose;
+        let format = self.format;
+        let sampled = self.sampler.sample();
+        if format == LogFormat::Text && sampled {
+            Self::log_request(&self.redactor, &req, verbose);
+        }
+        let method = req.method().to_string();
+        let endpoint = self.redactor.redact_url(req.url().path());
+        let sink = self.sink.clone();
         let mut inner = self.inner.clone();
 
         Box::pin(async move {
-            Self::log_request(&method, &url, verbose);
-
             let start = Instant::now();
-            let response = inner.call(req).await?;
+            let result = inner.call(req).await;
             let duration = start.elapsed();
 
-            Self::log_response(response.status().as_u16(), duration, verbose);
-
-            Ok(response)
+            if format == LogFormat::Text {
+                let response = result?;
+                if sampled || !response.status().is_success() {
+                    Self::log_response(response.status().as_u16(), duration, verbose);
+                }
+                return Ok(response);
+            }
+
+            let (result, errcode) = match result {
+                Ok(response) => {
+                    let (response, errcode) = Self::peek_errcode(response).await?;
+                    (Ok(response), errcode)
+                }
+                Err(error) => (Err(error), None),
+            };
+            let status = result
+                .as_ref()
+                .ok()
+                .map(|response| response.status().as_u16());
+            let failed = status.map_or(true, |status| status >= 400)
+                || errcode.is_some_and(|code| code != 0);
+            if sampled || failed {
+                let line = serde_json::json!({
+                    "ts_ms": SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .map_or(0, |since| since.as_millis() as u64),
+                    "method": method,
+                    "endpoint": endpoint,
+                    "status": status,
+                    "errcode": errcode,
+                    "latency_ms": duration.as_millis() as u64,
+                    "attempt": current_attempts(),
+                })
+                .to_string();
+                match &sink {
+                    Some(sink) => sink(&line),
+                    None => info!("{}", line),
+                }
+            }
+            result
         })
     }
 }
@@ -172,19 +309,56 @@ where
 mod tests {
     use super::*;
     use reqwest::Client;
+    use std::sync::{Mutex, OnceLock};
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+    struct CaptureLogger {
+        lines: Mutex<Vec<String>>,
+    }
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            self.lines.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn captured_lines(marker: &str) -> Vec<String> {
+        static LOGGER: OnceLock<&'static CaptureLogger> = OnceLock::new();
+        let logger = LOGGER.get_or_init(|| {
+            let logger: &'static CaptureLogger = Box::leak(Box::new(CaptureLogger {
+                lines: Mutex::new(Vec::new()),
+            }));
+            log::set_logger(logger).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+            logger
+        });
+        logger
+            .lines
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|line| line.contains(marker))
+            .cloned()
+            .collect()
+    }
+
     #[test]
     fn test_redact_url_no_sensitive_params() {
         let url = "https://api.weixin.qq.com/cgi-bin/token?grant_type=client_credential";
-        let redacted = LoggingMiddlewareService::<()>::redact_url(url);
+        let redacted = Redactor::default().redact_url(url);
         assert_eq!(redacted, url);
     }
 
     #[test]
     fn test_redact_url_with_access_token() {
         let url = "https://api.weixin.qq.com/cgi-bin/token?access_token=abc123&grant_type=client_credential";
-        let redacted = LoggingMiddlewareService::<()>::redact_url(url);
+        let redacted = Redactor::default().redact_url(url);
         assert!(redacted.contains("access_token=[REDACTED]"));
         assert!(redacted.contains("grant_type=client_credential"));
     }
@@ -192,14 +366,14 @@ mod tests {
     #[test]
     fn test_redact_url_with_secret() {
         let url = "https://api.weixin.qq.com/cgi-bin/token?appsecret=mysecret&grant_type=client_credential";
-        let redacted = LoggingMiddlewareService::<()>::redact_url(url);
+        let redacted = Redactor::default().redact_url(url);
         assert!(redacted.contains("appsecret=[REDACTED]"));
     }
 
     #[test]
     fn test_redact_url_with_session_key() {
         let url = "https://api.weixin.qq.com/wxa/getUserInfo?session_key=key123&encryptedData=data";
-        let redacted = LoggingMiddlewareService::<()>::redact_url(url);
+        let redacted = Redactor::default().redact_url(url);
         assert!(redacted.contains("session_key=[REDACTED]"));
     }
 
@@ -282,4 +456,162 @@ mod tests {
             _ = tokio::time::sleep(std::time::Duration::from_millis(100)) => {}
         }
     }
+
+    #[tokio::test]
+    async fn test_logged_output_never_contains_credentials() {
+        captured_lines("");
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let response = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let client = Client::builder().build().unwrap();
+        let mut service = LoggingMiddleware::new()
+            .verbose()
+            .redact_field("phone_number")
+            .layer(client.clone());
+
+        let url = format!(
+            "http://{}/redaction-marker?access_token=TOKEN_AAA&secret=SECRET_BBB&js_code=CODE_CCC&grant_type=authorization_code",
+            addr
+        );
+        let body = serde_json::json!({
+            "openid": "redaction-marker",
+            "signature": "SIG_DDD",
+            "session_key": "KEY_EEE",
+            "phone_number": "PHONE_FFF"
+        });
+        let req = client.post(&url).json(&body).build().unwrap();
+        let _ = service.call(req).await;
+        let _ = server.await;
+
+        let lines = captured_lines("redaction-marker");
+        assert!(lines.len() >= 2, "expected url and body lines: {:?}", lines);
+        for secret in [
+            "TOKEN_AAA",
+            "SECRET_BBB",
+            "CODE_CCC",
+            "SIG_DDD",
+            "KEY_EEE",
+            "PHONE_FFF",
+        ] {
+            assert!(
+                lines.iter().all(|line| !line.contains(secret)),
+                "{} leaked into {:?}",
+                secret,
+                lines
+            );
+        }
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("grant_type=authorization_code")));
+    }
+
+    fn capture_sink() -> (
+        Arc<Mutex<Vec<serde_json::Value>>>,
+        impl Fn(&str) + Send + Sync + 'static,
+    ) {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink_lines = Arc::clone(&lines);
+        let sink = move |line: &str| {
+            sink_lines
+                .lock()
+                .unwrap()
+                .push(serde_json::from_str(line).unwrap());
+        };
+        (lines, sink)
+    }
+
+    #[tokio::test]
+    async fn test_json_sink_writes_structured_lines() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 40001,
+                "errmsg": "invalid credential"
+            })))
+            .mount(&server)
+            .await;
+
+        let (lines, sink) = capture_sink();
+        let client = Client::builder().build().unwrap();
+        let mut service = LoggingMiddleware::new()
+            .json_sink(sink)
+            .layer(client.clone());
+
+        let url = format!(
+            "{}/cgi-bin/message/subscribe/send?access_token=TOKEN_AAA",
+            server.uri()
+        );
+        let response = service
+            .call(client.post(&url).build().unwrap())
+            .await
+            .unwrap();
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["errcode"], 40001, "body must survive errcode probing");
+
+        let lines = lines.lock().unwrap();
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        assert_eq!(line["method"], "POST");
+        assert_eq!(line["endpoint"], "/cgi-bin/message/subscribe/send");
+        assert_eq!(line["status"], 200);
+        assert_eq!(line["errcode"], 40001);
+        assert!(line["ts_ms"].as_u64().unwrap() > 0);
+        assert!(line["latency_ms"].is_u64());
+        assert!(line["attempt"].is_null());
+        assert!(!line.to_string().contains("TOKEN_AAA"));
+    }
+
+    #[tokio::test]
+    async fn test_sampling_keeps_failures() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ok"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 0
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fail"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 45009
+            })))
+            .mount(&server)
+            .await;
+
+        let (lines, sink) = capture_sink();
+        let client = Client::builder().build().unwrap();
+        let middleware = LoggingMiddleware::new().json_sink(sink).sample_one_in(3);
+        let mut first = middleware.layer(client.clone());
+        let mut second = middleware.layer(client.clone());
+
+        for i in 0..6 {
+            let service = if i % 2 == 0 { &mut first } else { &mut second };
+            let url = format!("{}/ok", server.uri());
+            service
+                .call(client.get(&url).build().unwrap())
+                .await
+                .unwrap();
+        }
+        let url = format!("{}/fail", server.uri());
+        first.call(client.get(&url).build().unwrap()).await.unwrap();
+
+        let lines = lines.lock().unwrap();
+        let endpoints: Vec<_> = lines.iter().map(|line| line["endpoint"].clone()).collect();
+        assert_eq!(endpoints, ["/ok", "/ok", "/fail"]);
+    }
 }
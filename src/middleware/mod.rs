@@ -12,16 +12,32 @@
 //!
 //! ## Usage
 //!
-//! ```ignore
-//! use tower::ServiceBuilder;
-//! use wechat_mp_sdk::middleware::{AuthMiddleware, RetryMiddleware, LoggingMiddleware};
-//!
-//! let service = ServiceBuilder::new()
-//!     .layer(LoggingMiddleware::new())
-//!     .layer(RetryMiddleware::new())
-//!     .layer(AuthMiddleware::new())
-//!     .service(inner_service);
+//! Install a stack with `with_middleware` on [`WechatMpBuilder`] or
+//! [`WechatClientBuilder`]; every API call, including token fetches, then
+//! goes through it. The first layer added is the outermost.
+//!
+//! ```
+//! use wechat_mp_sdk::middleware::{LoggingMiddleware, RetryMiddleware, ServiceBuilder};
+//! use wechat_mp_sdk::types::{AppId, AppSecret};
+//! use wechat_mp_sdk::WechatMp;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let wechat = WechatMp::builder()
+//!     .appid(AppId::new("wx1234567890abcdef")?)
+//!     .secret(AppSecret::new("abc1234567890abcdef")?)
+//!     .with_middleware(
+//!         ServiceBuilder::new()
+//!             .layer(LoggingMiddleware::new())
+//!             .layer(RetryMiddleware::new()),
+//!     )
+//!     .build()?;
+//! # let _ = wechat;
+//! # Ok(())
+//! # }
 //! ```
+//!
+//! [`WechatMpBuilder`]: crate::WechatMpBuilder
+//! [`WechatClientBuilder`]: crate::WechatClientBuilder
 
 // Re-export tower types for convenience
 pub use tower::{Layer, Service, ServiceBuilder};
@@ -31,5 +47,5 @@ mod logging;
 mod retry;
 
 pub use auth::{AuthMiddleware, ConfigurableAuthMiddleware, TokenInjection};
-pub use logging::LoggingMiddleware;
+pub use logging::{LogFormat, LogSink, LoggingMiddleware};
 pub use retry::RetryMiddleware;
@@ -18,12 +18,13 @@ use std::future::Future;
 use std::pin::Pin;
 use std::task::Context;
 use std::task::Poll;
+use std::time::Duration;
 
 use tokio::time::sleep;
 use tower::{Layer, Service};
 
 use crate::error::WechatError;
-use crate::utils::jittered_delay;
+use crate::limit::Jitter;
 
 /// Middleware that retries requests on 5xx and retryable errors.
 #[derive(Clone)]
@@ -31,17 +32,20 @@ pub struct RetryMiddleware {
     max_retries: usize,
     delay_ms: u64,
     retry_post: bool,
+    jitter: Jitter,
 }
 
 impl RetryMiddleware {
     /// Create a new RetryMiddleware with default settings.
     ///
-    /// Default: max_retries = 3, delay_ms = 100ms, retry_post = false
+    /// Default: max_retries = 3, delay_ms = 100ms, retry_post = false,
+    /// [`JitterMode::Additive`](crate::limit::JitterMode::Additive) jitter
     pub fn new() -> Self {
         Self {
             max_retries: 3,
             delay_ms: 100,
             retry_post: false,
+            jitter: Jitter::default(),
         }
     }
 
@@ -59,6 +63,14 @@ impl RetryMiddleware {
         self
     }
 
+    /// Randomize the delay between retries with `jitter`
+    ///
+    /// Use [`Jitter::seeded`] for reproducible timing in tests.
+    pub fn with_jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
     /// Enable retrying POST requests (disabled by default).
     pub fn with_retry_post(mut self, retry: bool) -> Self {
         self.retry_post = retry;
@@ -89,6 +101,7 @@ impl<S> Layer<S> for RetryMiddleware {
             max_retries: self.max_retries,
             delay_ms: self.delay_ms,
             retry_post: self.retry_post,
+            jitter: self.jitter.clone(),
         }
     }
 }
@@ -99,6 +112,7 @@ pub struct RetryMiddlewareService<S> {
     pub(crate) max_retries: usize,
     pub(crate) delay_ms: u64,
     pub(crate) retry_post: bool,
+    pub(crate) jitter: Jitter,
 }
 
 /// A wrapper to identify if a request is idempotent (safe to retry).
@@ -107,6 +121,12 @@ pub trait RetryableRequest {
     /// Returns true if the request is idempotent (GET, DELETE, etc.)
     /// POST and PUT are not idempotent by default.
     fn is_idempotent(&self) -> bool;
+
+    /// Copy the request for another attempt, or `None` when it cannot be
+    /// replayed (e.g. a streaming body); such requests are sent once.
+    fn try_clone_request(&self) -> Option<Self>
+    where
+        Self: Sized;
 }
 
 impl RetryableRequest for reqwest::Request {
@@ -116,6 +136,10 @@ impl RetryableRequest for reqwest::Request {
             &reqwest::Method::POST | &reqwest::Method::PUT | &reqwest::Method::PATCH
         )
     }
+
+    fn try_clone_request(&self) -> Option<Self> {
+        self.try_clone()
+    }
 }
 
 impl<S, R> Service<R> for RetryMiddlewareService<S>
@@ -124,7 +148,7 @@ where
     S::Future: Send,
     S::Error: std::fmt::Debug + Send + 'static,
     S::Response: Send,
-    R: Send + Clone + RetryableRequest + 'static,
+    R: Send + RetryableRequest + 'static,
 {
     type Response = S::Response;
     type Error = S::Error;
@@ -139,11 +163,13 @@ where
         let max_retries = self.max_retries;
         let delay_ms = self.delay_ms;
         let retry_post = self.retry_post;
+        let jitter = self.jitter.clone();
 
         Box::pin(async move {
             // max_retries=0 means "no retry", but still perform one attempt.
             let attempts = max_retries.max(1);
             let mut last_error: Option<S::Error> = None;
+            let mut delay = Duration::ZERO;
 
             // Check if request is retryable
             if !req.is_idempotent() && !retry_post {
@@ -152,8 +178,11 @@ where
             }
 
             for attempt in 0..attempts {
-                // Clone the request for each attempt
-                let req_clone = req.clone();
+                // Clone the request for each attempt; unreplayable requests
+                // get a single attempt.
+                let Some(req_clone) = req.try_clone_request() else {
+                    return inner.call(req).await;
+                };
 
                 match inner.call(req_clone).await {
                     Ok(response) => return Ok(response),
@@ -165,11 +194,12 @@ where
                         if is_retryable {
                             last_error = Some(e);
                             if attempt < attempts - 1 {
-                                sleep(jittered_delay(
+                                delay = jitter.delay(
                                     delay_ms,
                                     u32::try_from(attempt).unwrap_or(u32::MAX),
-                                ))
-                                .await;
+                                    delay,
+                                );
+                                sleep(delay).await;
                             }
                         } else {
                             // Non-retryable error, return immediately
@@ -234,15 +264,13 @@ mod tests {
         let decode_err = WechatError::Http(HttpError::Decode("bad".into()));
         assert!(!RetryMiddleware::is_retryable_error(&decode_err));
         // Api with retryable code => retryable
-        assert!(RetryMiddleware::is_retryable_error(&WechatError::Api {
-            code: -1,
-            message: "busy".into(),
-        }));
+        assert!(RetryMiddleware::is_retryable_error(&WechatError::api(
+            -1, "busy"
+        )));
         // Api with non-retryable code => not retryable
-        assert!(!RetryMiddleware::is_retryable_error(&WechatError::Api {
-            code: 40001,
-            message: "invalid".into(),
-        }));
+        assert!(!RetryMiddleware::is_retryable_error(&WechatError::api(
+            40001, "invalid"
+        )));
         // All remaining variants => not retryable
         let non_retryable: Vec<WechatError> = vec![
             WechatError::Json(serde_json::from_str::<String>("bad").unwrap_err()),
@@ -268,22 +296,13 @@ mod tests {
 
     #[test]
     fn test_retryable_error_codes() {
-        let err = WechatError::Api {
-            code: -1,
-            message: "System busy".to_string(),
-        };
+        let err = WechatError::api(-1, "System busy");
         assert!(RetryMiddleware::is_retryable_error(&err));
 
-        let err = WechatError::Api {
-            code: 45009,
-            message: "API limit".to_string(),
-        };
+        let err = WechatError::api(45009, "API limit");
         assert!(RetryMiddleware::is_retryable_error(&err));
 
-        let err = WechatError::Api {
-            code: 40001,
-            message: "Invalid credential".to_string(),
-        };
+        let err = WechatError::api(40001, "Invalid credential");
         assert!(!RetryMiddleware::is_retryable_error(&err));
     }
 
@@ -368,6 +387,10 @@ mod tests {
         fn is_idempotent(&self) -> bool {
             true
         }
+
+        fn try_clone_request(&self) -> Option<Self> {
+            Some(self.clone())
+        }
     }
 
     /// Mock service that always returns a retryable error
@@ -384,12 +407,7 @@ mod tests {
         }
 
         fn call(&mut self, _req: MockIdempotentRequest) -> Self::Future {
-            Box::pin(async {
-                Err(WechatError::Api {
-                    code: -1,
-                    message: "system busy".to_string(),
-                })
-            })
+            Box::pin(async { Err(WechatError::api(-1, "system busy")) })
         }
     }
 
@@ -407,12 +425,7 @@ mod tests {
         }
 
         fn call(&mut self, _req: MockIdempotentRequest) -> Self::Future {
-            Box::pin(async {
-                Err(WechatError::Api {
-                    code: 40001,
-                    message: "invalid credential".to_string(),
-                })
-            })
+            Box::pin(async { Err(WechatError::api(40001, "invalid credential")) })
         }
     }
 
@@ -484,6 +497,28 @@ mod tests {
         }
     }
 
+    /// Test: a seeded jitter makes the waits between attempts reproducible
+    #[tokio::test]
+    async fn test_seeded_jitter_sets_retry_delays() {
+        use crate::limit::JitterMode;
+
+        let expected = Jitter::seeded(JitterMode::Decorrelated, 9);
+        let first = expected.delay(20, 0, Duration::ZERO);
+        let second = expected.delay(20, 1, first);
+
+        let middleware = RetryMiddleware::new()
+            .with_max_retries(3)
+            .with_delay_ms(20)
+            .with_jitter(Jitter::seeded(JitterMode::Decorrelated, 9));
+        assert_eq!(middleware.jitter.mode(), JitterMode::Decorrelated);
+        let mut service = middleware.layer(AlwaysRetryableErrorService);
+
+        let started = std::time::Instant::now();
+        let result: Result<String, WechatError> = service.call(MockIdempotentRequest).await;
+        assert!(result.is_err());
+        assert!(started.elapsed() >= first + second);
+    }
+
     /// Test: success case - should return immediately without retry logic
     #[tokio::test]
     async fn test_success_case_no_retry() {
@@ -506,6 +541,10 @@ mod tests {
         fn is_idempotent(&self) -> bool {
             false
         }
+
+        fn try_clone_request(&self) -> Option<Self> {
+            Some(self.clone())
+        }
     }
 
     #[derive(Clone)]
@@ -521,12 +560,7 @@ mod tests {
         }
 
         fn call(&mut self, _req: NonIdempotentRequest) -> Self::Future {
-            Box::pin(async {
-                Err(WechatError::Api {
-                    code: -1,
-                    message: "system busy".to_string(),
-                })
-            })
+            Box::pin(async { Err(WechatError::api(-1, "system busy")) })
         }
     }
 
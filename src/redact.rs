@@ -0,0 +1,145 @@
+//! Central redaction of credentials in URLs and bodies
+//!
+//! Everything that renders request or response data for humans (logging
+//! middleware, decode error snippets) goes through [`Redactor`], so the set of
+//! fields that never leave the process in clear text is defined once.
+
+use percent_encoding::percent_decode_str;
+use serde_json::Value;
+
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// Field and query parameter names that are always redacted, compared
+/// case-insensitively.
+pub(crate) const SENSITIVE_FIELDS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "appsecret",
+    "secret",
+    "session_key",
+    "encrypt_key",
+    "code",
+    "js_code",
+    "signature",
+    "pay_sig",
+    "password",
+    "token",
+    "authorization",
+];
+
+/// Redaction filter: the built-in [`SENSITIVE_FIELDS`] plus caller-supplied names
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Redactor {
+    extra_fields: Vec<String>,
+}
+
+impl Redactor {
+    pub(crate) fn with_field(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        if !self.is_sensitive(&name) {
+            self.extra_fields.push(name);
+        }
+        self
+    }
+
+    pub(crate) fn is_sensitive(&self, name: &str) -> bool {
+        let name = percent_decode_str(name).decode_utf8_lossy();
+        SENSITIVE_FIELDS
+            .iter()
+            .copied()
+            .chain(self.extra_fields.iter().map(String::as_str))
+            .any(|field| name.eq_ignore_ascii_case(field))
+    }
+
+    /// Replace the values of sensitive query parameters.
+    pub(crate) fn redact_url(&self, url: &str) -> String {
+        match url.split_once('?') {
+            Some((base, query)) => format!("{}?{}", base, self.redact_pairs(query)),
+            None => url.to_string(),
+        }
+    }
+
+    /// Render a body for logging: JSON objects are redacted field by field,
+    /// form-encoded bodies pair by pair, anything else is summarised by size.
+    pub(crate) fn redact_body(&self, body: &[u8]) -> String {
+        if let Ok(mut value) = serde_json::from_slice::<Value>(body) {
+            self.redact_value(&mut value);
+            return value.to_string();
+        }
+        match std::str::from_utf8(body) {
+            Ok(text) if text.contains('=') && !text.contains(char::is_whitespace) => {
+                self.redact_pairs(text)
+            }
+            _ => format!("<{} bytes>", body.len()),
+        }
+    }
+
+    pub(crate) fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, field) in map.iter_mut() {
+                    if self.is_sensitive(key) {
+                        *field = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_value(field);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_value(item)),
+            _ => {}
+        }
+    }
+
+    fn redact_pairs(&self, pairs: &str) -> String {
+        pairs
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, _)) if self.is_sensitive(key) => format!("{}={}", key, REDACTED),
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_url_covers_builtin_fields() {
+        let redactor = Redactor::default();
+        let url = "https://api.weixin.qq.com/sns/jscode2session?appid=wx1&secret=s3cr3t&js_code=c0de&grant_type=authorization_code";
+        let redacted = redactor.redact_url(url);
+        assert!(!redacted.contains("s3cr3t"));
+        assert!(!redacted.contains("c0de"));
+        assert!(redacted.contains("appid=wx1"));
+        assert!(redacted.contains("grant_type=authorization_code"));
+    }
+
+    #[test]
+    fn test_redact_url_matches_encoded_and_cased_keys() {
+        let redactor = Redactor::default();
+        let redacted = redactor.redact_url("/x?ACCESS_TOKEN=a1&access%5Ftoken=b2");
+        assert_eq!(
+            redacted,
+            "/x?ACCESS_TOKEN=[REDACTED]&access%5Ftoken=[REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_redact_body_json_and_form() {
+        let redactor = Redactor::default().with_field("phone_number");
+        let json =
+            br#"{"openid":"o1","signature":"sig","nested":[{"phone_number":"13800000000"}]}"#;
+        let redacted = redactor.redact_body(json);
+        assert!(!redacted.contains("sig\""));
+        assert!(!redacted.contains("13800000000"));
+        assert!(redacted.contains("\"openid\":\"o1\""));
+
+        let form = redactor.redact_body(b"code=abc&scene=1");
+        assert_eq!(form, "code=[REDACTED]&scene=1");
+
+        assert_eq!(redactor.redact_body(&[0xff, 0xd8, 0xff]), "<3 bytes>");
+    }
+}
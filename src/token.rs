@@ -8,7 +8,24 @@
 //! - Automatic token caching with configurable expiration buffer
 //! - Single-flight pattern to prevent duplicate API calls
 //! - Automatic retry with configurable attempts for rate-limited errors
+//! - Time-boxed refresh barrier after [`TokenManager::invalidate`] so callers
+//!   share one upstream fetch instead of stampeding
 //! - Thread-safe async implementation using tokio
+//! - Optional [`AccessTokenProvider`] for workers that receive tokens from a
+//!   central token service and never hold the AppSecret
+//! - [`TokenStrategy::Stable`] to fetch every token from `stable_token`,
+//!   which does not revoke tokens held by other instances
+//! - Ordered [`TokenSource`] fallback chain (e.g. stable token, then classic
+//!   token, then an external provider) for WeChat-side auth incidents
+//! - Expiry measured from when the token request was sent, optionally
+//!   corrected by the response `Date` header, so slow or queued responses do
+//!   not keep tokens cached past their real lifetime
+//! - [`TokenRouter`] to give endpoint categories their own token manager,
+//!   e.g. stable tokens for some API families and classic ones elsewhere
+//! - [`on_fatal_auth_error`](TokenManagerBuilder::on_fatal_auth_error) hook
+//!   to page someone when the AppID or AppSecret is rejected
+//! - Pluggable [`TokenStore`] so several instances can share one token
+//!   through Redis, etcd or similar instead of each caching its own
 //!
 //! ## Usage
 //!
@@ -41,41 +58,149 @@
 //! - [`WechatError::Api`] - WeChat API errors (invalid credentials, rate limits)
 //! - [`WechatError::Token`] - Token parsing or refresh failures
 
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
-use serde::Deserialize;
-use tokio::sync::{Mutex, Notify, RwLock};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{watch, Mutex};
 
+use crate::api::endpoint_inventory::category_for_path;
 use crate::client::WechatClient;
-use crate::error::{HttpError, WechatError};
+use crate::error::WechatError;
+use crate::limit::Jitter;
 use crate::types::AccessToken;
-use crate::utils::jittered_delay;
+use crate::utils::{decode_json, lock};
 
 const MAX_RETRIES: u32 = 3;
 const RETRY_DELAY_MS: u64 = 100;
+const INVALIDATION_BARRIER_MS: u64 = 1000;
 const MAX_TOKEN_TTL_SECS: u64 = 60 * 60 * 24 * 365;
+/// How long a token from a fallback [`TokenSource::Provider`] stays cached,
+/// on top of the refresh buffer.
+const FALLBACK_PROVIDER_TTL_SECS: u64 = 60;
 
 /// Retryable WeChat API error codes.
 /// - -1: System busy
 /// - 45009: API call limit exceeded
 pub(crate) const RETRYABLE_ERROR_CODES: &[i32] = &[-1, 45009];
 
-pub(crate) struct CachedToken {
-    pub(crate) token: AccessToken,
-    pub(crate) expires_at: Instant,
+/// Credential error codes no retry can fix.
+/// - 40013: Invalid appid
+/// - 40125: Invalid appsecret
+/// - 41004: Appsecret missing
+pub(crate) const FATAL_AUTH_ERROR_CODES: &[i32] = &[40013, 40125, 41004];
+
+/// Callback for token refreshes that fail with a fatal credential error;
+/// see [`TokenManagerBuilder::on_fatal_auth_error`]
+pub type FatalAuthHook = Arc<dyn Fn(&WechatError) + Send + Sync>;
+
+/// An access token as held by a [`TokenStore`]
+///
+/// The expiry is wall-clock time so it stays meaningful across processes.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub access_token: String,
+    pub expires_at: SystemTime,
 }
 
-impl CachedToken {
+impl std::fmt::Debug for StoredToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoredToken")
+            .field("access_token", &"[REDACTED]")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+impl StoredToken {
+    pub fn new(access_token: impl Into<String>, expires_at: SystemTime) -> Self {
+        Self {
+            access_token: access_token.into(),
+            expires_at,
+        }
+    }
+
+    /// Returns true when the token expires within `buffer` from now.
     pub fn is_expired(&self, buffer: Duration) -> bool {
-        match Instant::now().checked_add(buffer) {
+        match SystemTime::now().checked_add(buffer) {
             Some(deadline) => deadline >= self.expires_at,
             None => true,
         }
     }
+
+    /// Time left before expiry, zero once expired
+    pub fn expires_in(&self) -> Duration {
+        self.expires_at
+            .duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+/// Future returned by [`TokenStore`] methods
+pub type TokenStoreFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<T, WechatError>> + Send + 'a>>;
+
+/// Where [`TokenManager`] caches fetched access tokens, keyed by AppID
+///
+/// Implement this over shared storage (Redis, etcd, a database) when several
+/// instances serve the same mini program: they then reuse one token instead
+/// of each fetching its own, which with [`TokenSource::Classic`] revokes the
+/// others'. [`MemoryTokenStore`] is the default.
+///
+/// Single-flight only merges refreshes within one process; when a shared
+/// store is empty, every instance that sees the miss fetches once. Prefer
+/// [`TokenSource::Stable`] with a shared store, or populate it from a single
+/// refresher.
+///
+/// Errors from [`get`](Self::get) are returned by
+/// [`TokenManager::get_token`] rather than triggering a fetch; errors from
+/// `set` and `invalidate` are logged.
+pub trait TokenStore: Send + Sync {
+    /// Stored token for `appid`, expired or not; `None` when absent.
+    fn get<'a>(&'a self, appid: &'a str) -> TokenStoreFuture<'a, Option<StoredToken>>;
+
+    /// Replace the stored token for `appid`.
+    fn set<'a>(&'a self, appid: &'a str, token: StoredToken) -> TokenStoreFuture<'a, ()>;
+
+    /// Drop the stored token for `appid`, e.g. after WeChat rejected it.
+    fn invalidate<'a>(&'a self, appid: &'a str) -> TokenStoreFuture<'a, ()>;
+}
+
+/// In-process [`TokenStore`]; cloning shares the tokens
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTokenStore {
+    tokens: Arc<std::sync::Mutex<HashMap<String, StoredToken>>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn get<'a>(&'a self, appid: &'a str) -> TokenStoreFuture<'a, Option<StoredToken>> {
+        let token = lock(&self.tokens).get(appid).cloned();
+        Box::pin(async move { Ok(token) })
+    }
+
+    fn set<'a>(&'a self, appid: &'a str, token: StoredToken) -> TokenStoreFuture<'a, ()> {
+        lock(&self.tokens).insert(appid.to_string(), token);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn invalidate<'a>(&'a self, appid: &'a str) -> TokenStoreFuture<'a, ()> {
+        lock(&self.tokens).remove(appid);
+        Box::pin(async { Ok(()) })
+    }
 }
 
 #[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub(crate) struct TokenResponse {
     #[serde(default)]
     pub(crate) access_token: String,
@@ -92,6 +217,7 @@ impl TokenResponse {
         self.errcode == 0
     }
 
+    #[cfg(test)]
     pub(crate) fn is_retryable_error(&self) -> bool {
         RETRYABLE_ERROR_CODES.contains(&self.errcode)
     }
@@ -99,22 +225,208 @@ impl TokenResponse {
 
 type FetchResult = Result<(String, u64), WechatError>;
 
+/// A fetched token and how much of its `expires_in` had already elapsed
+/// when the response was read
+type Fetched = (TokenResponse, Duration);
+
+/// Which measurements shorten a fetched token's lifetime
+#[derive(Debug, Clone, Copy)]
+struct ExpiryCorrection {
+    latency: bool,
+    server_date: bool,
+}
+
+impl ExpiryCorrection {
+    /// Time the token spent in flight, given when its request was sent and
+    /// the response headers
+    fn elapsed(&self, sent_at: Instant, headers: &http::HeaderMap) -> Duration {
+        let mut elapsed = if self.latency {
+            sent_at.elapsed()
+        } else {
+            Duration::ZERO
+        };
+        if self.server_date {
+            let age = headers
+                .get(http::header::DATE)
+                .and_then(|date| date.to_str().ok())
+                .and_then(|date| httpdate::parse_http_date(date).ok())
+                .and_then(|date| SystemTime::now().duration_since(date).ok());
+            if let Some(age) = age {
+                elapsed = elapsed.max(age);
+            }
+        }
+        elapsed
+    }
+}
+
+/// Future returned by an [`AccessTokenProvider`]
+pub type AccessTokenFuture = Pin<Box<dyn Future<Output = Result<String, WechatError>> + Send>>;
+
+/// Source of pre-issued access tokens, such as a central token service
+///
+/// The provider owns caching and refreshing: [`TokenManager::get_token`]
+/// calls it on every request and expects a currently valid token back.
+pub type AccessTokenProvider = Arc<dyn Fn() -> AccessTokenFuture + Send + Sync>;
+
+pub(crate) fn boxed_provider<F, Fut>(provider: F) -> AccessTokenProvider
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<String, WechatError>> + Send + 'static,
+{
+    Arc::new(move || Box::pin(provider()))
+}
+
+/// Where [`TokenManager`] obtains access tokens
+///
+/// Configured as an ordered chain with [`TokenManagerBuilder::sources`]: each
+/// source is tried with the usual retries, and when it keeps failing the
+/// manager logs a warning and falls over to the next one.
+#[derive(Clone)]
+pub enum TokenSource {
+    /// `GET /cgi-bin/token` with the AppSecret (the default)
+    Classic,
+    /// `POST /cgi-bin/stable_token` with the AppSecret
+    Stable,
+    /// An external provider; its tokens are cached for 60 seconds beyond the
+    /// refresh buffer
+    Provider(AccessTokenProvider),
+}
+
+impl TokenSource {
+    /// Wrap an async closure as a [`TokenSource::Provider`]
+    pub fn provider<F, Fut>(provider: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, WechatError>> + Send + 'static,
+    {
+        TokenSource::Provider(boxed_provider(provider))
+    }
+
+    pub(crate) fn needs_secret(&self) -> bool {
+        !matches!(self, TokenSource::Provider(_))
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            TokenSource::Classic => "classic token",
+            TokenSource::Stable => "stable token",
+            TokenSource::Provider(_) => "token provider",
+        }
+    }
+}
+
+impl std::fmt::Debug for TokenSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenSource::Classic => f.write_str("Classic"),
+            TokenSource::Stable => f.write_str("Stable"),
+            TokenSource::Provider(_) => f.write_str("Provider(..)"),
+        }
+    }
+}
+
+/// How [`TokenManager`] fetches access tokens with the AppSecret
+///
+/// `/cgi-bin/token` revokes the previous token on every fetch, so instances
+/// that fetch independently keep invalidating each other. `stable_token`
+/// returns the current token while it is valid and is what WeChat
+/// recommends for multi-instance services.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TokenStrategy {
+    /// `GET /cgi-bin/token` (the default)
+    #[default]
+    Classic,
+    /// `POST /cgi-bin/stable_token`
+    Stable {
+        /// Send `force_refresh: true` on the first fetch after
+        /// [`TokenManager::invalidate`], so a token WeChat rejected is
+        /// replaced instead of returned again. Forced refreshes are limited
+        /// to 20 a day per AppID and revoke the previous stable token.
+        force_refresh_on_invalidate: bool,
+    },
+}
+
+impl TokenStrategy {
+    fn source(self) -> TokenSource {
+        match self {
+            TokenStrategy::Classic => TokenSource::Classic,
+            TokenStrategy::Stable { .. } => TokenSource::Stable,
+        }
+    }
+
+    fn force_refresh_on_invalidate(self) -> bool {
+        matches!(
+            self,
+            TokenStrategy::Stable {
+                force_refresh_on_invalidate: true
+            }
+        )
+    }
+}
+
 /// Represents an in-flight token refresh operation.
 /// Multiple concurrent requests share this state and wait for the same result.
+///
+/// The sender lives in the fetch task; if the task goes away without
+/// publishing (panic, runtime shutdown), the channel closes while still
+/// empty and waiters elect a new fetcher instead of waiting forever.
 struct InFlightFetch {
-    result: Arc<Mutex<Option<FetchResult>>>,
-    notify: Arc<Notify>,
+    result: watch::Receiver<Option<FetchResult>>,
+}
+
+impl InFlightFetch {
+    fn start() -> (Arc<Self>, watch::Sender<Option<FetchResult>>) {
+        let (sender, result) = watch::channel(None);
+        (Arc::new(Self { result }), sender)
+    }
+
+    /// Wait for the shared result; `None` if the fetch was abandoned
+    async fn wait(&self) -> Option<FetchResult> {
+        let mut result = self.result.clone();
+        loop {
+            if let Some(fetched) = &*result.borrow_and_update() {
+                return Some(fetched.clone());
+            }
+            if result.changed().await.is_err() {
+                return result.borrow().clone();
+            }
+        }
+    }
+}
+
+/// Window opened by [`TokenManager::invalidate`] during which every caller
+/// shares the first fetch started after invalidation, including its error.
+struct RefreshBarrier {
+    deadline: Instant,
+    fetch: Option<Arc<InFlightFetch>>,
+}
+
+#[derive(Default)]
+struct FlightState {
+    current: Option<Arc<InFlightFetch>>,
+    barrier: Option<RefreshBarrier>,
 }
 
 /// Manages access_token lifecycle with automatic refresh.
 /// Uses single-flight pattern to merge concurrent refresh requests.
 pub struct TokenManager {
     client: WechatClient,
-    pub(crate) cache: Arc<RwLock<Option<CachedToken>>>,
-    in_flight: Arc<Mutex<Option<Arc<InFlightFetch>>>>,
+    pub(crate) store: Arc<dyn TokenStore>,
+    in_flight: Arc<Mutex<FlightState>>,
     pub(crate) refresh_buffer: Duration,
     max_retries: u32,
     retry_delay_ms: u64,
+    jitter: Jitter,
+    invalidation_barrier: Duration,
+    provider: Option<AccessTokenProvider>,
+    sources: Arc<[TokenSource]>,
+    strategy: TokenStrategy,
+    /// Set by `invalidate` under a force-refreshing stable strategy; taken
+    /// by the next fetch
+    force_refresh: Arc<AtomicBool>,
+    fatal_auth_hook: Option<FatalAuthHook>,
+    expiry: ExpiryCorrection,
+    refreshed_at: Arc<std::sync::Mutex<Option<Instant>>>,
 }
 
 impl std::fmt::Debug for TokenManager {
@@ -123,6 +435,16 @@ impl std::fmt::Debug for TokenManager {
             .field("refresh_buffer", &self.refresh_buffer)
             .field("max_retries", &self.max_retries)
             .field("retry_delay_ms", &self.retry_delay_ms)
+            .field("jitter", &self.jitter.mode())
+            .field("invalidation_barrier", &self.invalidation_barrier)
+            .field("provider", &self.provider.as_ref().map(|_| ".."))
+            .field("sources", &self.sources)
+            .field("strategy", &self.strategy)
+            .field(
+                "fatal_auth_hook",
+                &self.fatal_auth_hook.as_ref().map(|_| ".."),
+            )
+            .field("expiry", &self.expiry)
             .finish_non_exhaustive()
     }
 }
@@ -131,11 +453,23 @@ impl TokenManager {
     pub fn new(client: WechatClient) -> Self {
         Self {
             client,
-            cache: Arc::new(RwLock::new(None)),
-            in_flight: Arc::new(Mutex::new(None)),
+            store: Arc::new(MemoryTokenStore::new()),
+            in_flight: Arc::new(Mutex::new(FlightState::default())),
             refresh_buffer: Duration::from_secs(5 * 60),
             max_retries: MAX_RETRIES,
             retry_delay_ms: RETRY_DELAY_MS,
+            jitter: Jitter::default(),
+            invalidation_barrier: Duration::from_millis(INVALIDATION_BARRIER_MS),
+            provider: None,
+            sources: Arc::new([TokenSource::Classic]),
+            strategy: TokenStrategy::Classic,
+            force_refresh: Arc::default(),
+            fatal_auth_hook: None,
+            expiry: ExpiryCorrection {
+                latency: true,
+                server_date: false,
+            },
+            refreshed_at: Arc::default(),
         }
     }
 
@@ -149,7 +483,9 @@ impl TokenManager {
     /// - No cached token exists
     /// - Cached token is expired or expiring soon (within refresh buffer)
     ///
-    /// Uses single-flight pattern to merge concurrent requests.
+    /// Uses single-flight pattern to merge concurrent requests. Within the
+    /// refresh barrier after [`invalidate`](Self::invalidate), callers reuse
+    /// the first post-invalidation fetch even if it failed.
     ///
     /// # Returns
     /// The access token string.
@@ -158,186 +494,477 @@ impl TokenManager {
     /// Returns [`WechatError::Api`] if WeChat returns an error code.
     /// Returns [`WechatError::Http`] if network request fails.
     /// Returns [`WechatError::Token`] if token parsing fails.
+    ///
+    /// With an [`AccessTokenProvider`] configured, the provider is called
+    /// instead and its errors are returned as-is.
     pub async fn get_token(&self) -> Result<String, WechatError> {
-        {
-            let cache = self.cache.read().await;
-            if let Some(ref cached) = *cache {
-                if !cached.is_expired(self.refresh_buffer) {
-                    return Ok(cached.token.as_str().to_string());
-                }
-            }
+        if let Some(provider) = &self.provider {
+            let token = provider().await?;
+            AccessToken::new(token.as_str())?;
+            return Ok(token);
         }
 
-        let (in_flight_fetch, is_creator) = {
-            let mut in_flight = self.in_flight.lock().await;
+        if let Some(token) = self.stored_token().await? {
+            return Ok(token);
+        }
 
-            {
-                let cache = self.cache.read().await;
-                if let Some(ref cached) = *cache {
-                    if !cached.is_expired(self.refresh_buffer) {
-                        return Ok(cached.token.as_str().to_string());
-                    }
-                }
-            }
+        loop {
+            let in_flight_fetch = {
+                let mut in_flight = self.in_flight.lock().await;
 
-            match in_flight.as_ref() {
-                Some(fetch) => (Arc::clone(fetch), false),
-                None => {
-                    let fetch = Arc::new(InFlightFetch {
-                        result: Arc::new(Mutex::new(None)),
-                        notify: Arc::new(Notify::new()),
-                    });
-                    *in_flight = Some(Arc::clone(&fetch));
-                    (fetch, true)
+                if let Some(token) = self.stored_token().await? {
+                    return Ok(token);
                 }
-            }
-        };
 
-        if is_creator {
-            self.spawn_refresh_task(Arc::clone(&in_flight_fetch));
-        }
+                if in_flight
+                    .barrier
+                    .as_ref()
+                    .is_some_and(|barrier| barrier.deadline <= Instant::now())
+                {
+                    in_flight.barrier = None;
+                }
 
-        // Wait for the shared single-flight result.
-        // Creator and waiters both follow this path so cancellation of the creator
-        // cannot strand the in-flight state.
-        loop {
-            let notified = in_flight_fetch.notify.notified();
+                let barrier_fetch = in_flight
+                    .barrier
+                    .as_ref()
+                    .and_then(|barrier| barrier.fetch.clone());
+
+                match (in_flight.current.clone(), barrier_fetch) {
+                    (Some(fetch), _) | (None, Some(fetch)) => fetch,
+                    (None, None) => {
+                        let (fetch, sender) = InFlightFetch::start();
+                        in_flight.current = Some(Arc::clone(&fetch));
+                        if let Some(barrier) = in_flight.barrier.as_mut() {
+                            barrier.fetch = Some(Arc::clone(&fetch));
+                        }
+                        // Spawned before the lock is released, so dropping
+                        // this caller cannot leave `current` without a fetcher.
+                        self.spawn_refresh_task(sender);
+                        fetch
+                    }
+                }
+            };
 
-            if let Some(ref result) = *in_flight_fetch.result.lock().await {
-                return result.clone().map(|(token, _)| token);
+            // Creator and waiters both wait on the shared result.
+            match in_flight_fetch.wait().await {
+                Some(result) => return result.map(|(token, _)| token),
+                None => self.abandon(&in_flight_fetch).await,
             }
+        }
+    }
 
-            notified.await;
+    /// Token from the store if it is still outside the refresh buffer
+    async fn stored_token(&self) -> Result<Option<String>, WechatError> {
+        let stored = self.store.get(self.client.appid()).await?;
+        Ok(stored
+            .filter(|stored| !stored.is_expired(self.refresh_buffer))
+            .map(|stored| stored.access_token))
+    }
 
-            if let Some(ref result) = *in_flight_fetch.result.lock().await {
-                return result.clone().map(|(token, _)| token);
+    /// Forget a fetch whose task ended without a result, so the next loop
+    /// iteration elects a new fetcher
+    async fn abandon(&self, fetch: &Arc<InFlightFetch>) {
+        let mut in_flight = self.in_flight.lock().await;
+        if in_flight
+            .current
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current, fetch))
+        {
+            in_flight.current = None;
+        }
+        if let Some(barrier) = in_flight.barrier.as_mut() {
+            if barrier
+                .fetch
+                .as_ref()
+                .is_some_and(|shared| Arc::ptr_eq(shared, fetch))
+            {
+                barrier.fetch = None;
             }
         }
     }
 
-    fn spawn_refresh_task(&self, in_flight_fetch: Arc<InFlightFetch>) {
+    fn spawn_refresh_task(&self, sender: watch::Sender<Option<FetchResult>>) {
         let client = self.client.clone();
-        let cache = Arc::clone(&self.cache);
+        let store = Arc::clone(&self.store);
         let in_flight = Arc::clone(&self.in_flight);
-        let max_retries = self.max_retries;
-        let retry_delay_ms = self.retry_delay_ms;
+        let sources = Arc::clone(&self.sources);
+        let refreshed_at = Arc::clone(&self.refreshed_at);
+        let force_refresh = Arc::clone(&self.force_refresh);
+        let fatal_auth_hook = self.fatal_auth_hook.clone();
+        let retry = RetryPolicy {
+            max_retries: self.max_retries,
+            retry_delay_ms: self.retry_delay_ms,
+            jitter: self.jitter.clone(),
+            provider_ttl_secs: self.refresh_buffer.as_secs() + FALLBACK_PROVIDER_TTL_SECS,
+            expiry: self.expiry,
+            force_refresh: force_refresh.swap(false, Ordering::AcqRel),
+        };
 
         tokio::spawn(async move {
-            let fetch_result = TokenManager::fetch_token_with_retry_for_client(
-                &client,
-                max_retries,
-                retry_delay_ms,
-            )
-            .await;
+            let fetch_result =
+                TokenManager::fetch_token_from_sources(&client, &sources, &retry).await;
+            if retry.force_refresh && fetch_result.is_err() {
+                // Keep forcing until a replacement token is obtained.
+                force_refresh.store(true, Ordering::Release);
+            }
+            if let (Err(error), Some(hook)) = (&fetch_result, &fatal_auth_hook) {
+                if error.is_fatal_auth() {
+                    hook(error);
+                }
+            }
 
+            let mut elapsed = Duration::ZERO;
             let result_to_store = match fetch_result {
-                Ok(TokenResponse {
-                    access_token,
-                    expires_in,
-                    ..
-                }) => AccessToken::new(access_token.as_str()).map(|_| (access_token, expires_in)),
+                Ok((
+                    TokenResponse {
+                        access_token,
+                        expires_in,
+                        ..
+                    },
+                    in_flight_for,
+                )) => {
+                    elapsed = in_flight_for;
+                    AccessToken::new(access_token.as_str()).map(|_| (access_token, expires_in))
+                }
                 Err(e) => Err(e),
             };
 
-            if let Ok((ref token_str, expires_in)) = result_to_store {
-                if let Ok(token) = AccessToken::new(token_str) {
-                    let ttl = Duration::from_secs(expires_in.min(MAX_TOKEN_TTL_SECS));
-                    if let Some(expires_at) = Instant::now().checked_add(ttl) {
-                        let cached = CachedToken { token, expires_at };
-                        *cache.write().await = Some(cached);
+            if let Ok((ref token, expires_in)) = result_to_store {
+                let ttl =
+                    Duration::from_secs(expires_in.min(MAX_TOKEN_TTL_SECS)).saturating_sub(elapsed);
+                if let Some(expires_at) = SystemTime::now().checked_add(ttl) {
+                    let stored = StoredToken::new(token.as_str(), expires_at);
+                    // The fetched token is still handed to waiters; only
+                    // later calls miss the cache.
+                    if let Err(error) = store.set(client.appid(), stored).await {
+                        log::warn!("failed to store access token: {}", error);
+                    } else {
+                        *lock(&refreshed_at) = Some(Instant::now());
                     }
                 }
             }
 
-            *in_flight_fetch.result.lock().await = Some(result_to_store);
-            in_flight_fetch.notify.notify_waiters();
-            *in_flight.lock().await = None;
+            let mut in_flight = in_flight.lock().await;
+            sender.send_replace(Some(result_to_store));
+            in_flight.current = None;
         });
     }
 
     #[cfg(test)]
     async fn fetch_token_with_retry(&self) -> Result<TokenResponse, WechatError> {
-        Self::fetch_token_with_retry_for_client(&self.client, self.max_retries, self.retry_delay_ms)
+        let retry = RetryPolicy {
+            max_retries: self.max_retries,
+            retry_delay_ms: self.retry_delay_ms,
+            jitter: self.jitter.clone(),
+            provider_ttl_secs: self.refresh_buffer.as_secs() + FALLBACK_PROVIDER_TTL_SECS,
+            expiry: self.expiry,
+            force_refresh: false,
+        };
+        Self::fetch_token_from_sources(&self.client, &self.sources, &retry)
             .await
+            .map(|(response, _)| response)
+    }
+
+    /// Try each source in order, falling over to the next when one keeps
+    /// failing.
+    async fn fetch_token_from_sources(
+        client: &WechatClient,
+        sources: &[TokenSource],
+        retry: &RetryPolicy,
+    ) -> Result<Fetched, WechatError> {
+        let mut last_error = None;
+        for (index, source) in sources.iter().enumerate() {
+            match Self::fetch_token_with_retry_for_client(client, source, retry).await {
+                Ok(response) => return Ok(response),
+                Err(error) => {
+                    if let Some(next) = sources.get(index + 1) {
+                        log::warn!(
+                            "access token source {} failed ({}); falling back to {}",
+                            source.name(),
+                            error,
+                            next.name()
+                        );
+                    }
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| WechatError::Token("no token source".to_string())))
     }
 
     async fn fetch_token_with_retry_for_client(
         client: &WechatClient,
-        max_retries: u32,
-        retry_delay_ms: u64,
-    ) -> Result<TokenResponse, WechatError> {
-        let attempts = max_retries.max(1);
+        source: &TokenSource,
+        retry: &RetryPolicy,
+    ) -> Result<Fetched, WechatError> {
+        let attempts = retry.max_retries.max(1);
         let mut last_error = None;
+        let mut delay = Duration::ZERO;
 
         for attempt in 0..attempts {
-            match Self::fetch_token_for_client(client).await {
-                Ok(response) => {
-                    if response.is_success() {
-                        return Ok(response);
-                    }
-                    if response.is_retryable_error() {
-                        last_error = Some(WechatError::Api {
-                            code: response.errcode,
-                            message: response.errmsg,
-                        });
-                        if attempt < attempts - 1 {
-                            tokio::time::sleep(jittered_delay(retry_delay_ms, attempt)).await;
-                        }
-                    } else {
-                        return Err(WechatError::Api {
-                            code: response.errcode,
-                            message: response.errmsg,
-                        });
-                    }
+            let result = match source {
+                TokenSource::Classic => Self::fetch_token_for_client(client, retry.expiry).await,
+                TokenSource::Stable => {
+                    Self::fetch_stable_token_for_client(client, retry.expiry, retry.force_refresh)
+                        .await
                 }
-                Err(WechatError::Http(e)) if e.is_transient() => {
-                    last_error = Some(WechatError::Http(e));
-                    if attempt < attempts - 1 {
-                        tokio::time::sleep(jittered_delay(retry_delay_ms, attempt)).await;
-                    }
+                TokenSource::Provider(provider) => provider().await.map(|access_token| {
+                    let response = TokenResponse {
+                        access_token,
+                        expires_in: retry.provider_ttl_secs,
+                        errcode: 0,
+                        errmsg: String::new(),
+                    };
+                    (response, Duration::ZERO)
+                }),
+            };
+            let error = match result {
+                Ok((response, elapsed)) if response.is_success() => return Ok((response, elapsed)),
+                Ok((response, _)) => {
+                    client.classify_error(WechatError::api(response.errcode, &response.errmsg))
                 }
-                Err(e) => return Err(e),
+                Err(error) => error,
+            };
+            // Covers transient HTTP failures, RETRYABLE_ERROR_CODES and
+            // gateway errcodes.
+            if !error.is_transient() {
+                return Err(error);
+            }
+            last_error = Some(error);
+            if attempt < attempts - 1 {
+                delay = retry.jitter.delay(retry.retry_delay_ms, attempt, delay);
+                tokio::time::sleep(delay).await;
             }
         }
 
         Err(last_error.unwrap_or_else(|| WechatError::Token("Unknown error".to_string())))
     }
 
-    async fn fetch_token_for_client(client: &WechatClient) -> Result<TokenResponse, WechatError> {
+    async fn fetch_stable_token_for_client(
+        client: &WechatClient,
+        expiry: ExpiryCorrection,
+        force_refresh: bool,
+    ) -> Result<Fetched, WechatError> {
+        let path = "/cgi-bin/stable_token";
+        let body = serde_json::json!({
+            "grant_type": "client_credential",
+            "appid": client.appid(),
+            "secret": client.secret()?,
+            "force_refresh": force_refresh,
+        });
+        let url = client.url_for(path)?;
+        let request = client
+            .json_body(client.http().post(url), &body)?
+            .timeout(client.timeout_for_path(path))
+            .build()?;
+        let sent_at = Instant::now();
+        let response = client.send_request(request).await?;
+        if let Err(error) = response.error_for_status_ref() {
+            return Err(error.into());
+        }
+
+        let elapsed = expiry.elapsed(sent_at, response.headers());
+        let bytes = response.bytes().await?;
+        Ok((decode_json::<TokenResponse>(&bytes)?, elapsed))
+    }
+
+    async fn fetch_token_for_client(
+        client: &WechatClient,
+        expiry: ExpiryCorrection,
+    ) -> Result<Fetched, WechatError> {
         let path = "/cgi-bin/token";
         let query = [
             ("grant_type", "client_credential"),
             ("appid", client.appid()),
-            ("secret", client.secret()),
+            ("secret", client.secret()?),
         ];
-        let url = format!("{}{}", client.base_url(), path);
-        let request = client.http().get(&url).query(&query).build()?;
+        let url = client.url_for(path)?;
+        let request = client
+            .http()
+            .get(url)
+            .query(&query)
+            .timeout(client.timeout_for_path(path))
+            .build()?;
+        let sent_at = Instant::now();
         let response = client.send_request(request).await?;
         if let Err(error) = response.error_for_status_ref() {
             return Err(error.into());
         }
 
+        let elapsed = expiry.elapsed(sent_at, response.headers());
         let bytes = response.bytes().await?;
-        serde_json::from_slice::<TokenResponse>(&bytes)
-            .map_err(|error| WechatError::Http(HttpError::Decode(error.to_string())))
+        Ok((decode_json::<TokenResponse>(&bytes)?, elapsed))
+    }
+
+    /// Token state without the token itself, for
+    /// [`WechatMp::support_bundle`](crate::WechatMp::support_bundle)
+    pub(crate) async fn support_info(&self) -> serde_json::Value {
+        let stored = self.store.get(self.client.appid()).await;
+        let expires_in = stored
+            .as_ref()
+            .ok()
+            .and_then(Option::as_ref)
+            .map(|stored| stored.expires_in().as_secs());
+        let age = lock(&self.refreshed_at).map(|refreshed_at| refreshed_at.elapsed().as_secs());
+        serde_json::json!({
+            "provider": self.provider.is_some(),
+            "sources": self.sources.iter().map(TokenSource::name).collect::<Vec<_>>(),
+            "force_refresh_on_invalidate": self.strategy.force_refresh_on_invalidate(),
+            "cached": expires_in.is_some(),
+            "store_error": stored.err().map(|error| error.to_string()),
+            "age_secs": age,
+            "expires_in_secs": expires_in,
+            "refresh_buffer_secs": self.refresh_buffer.as_secs(),
+        })
     }
 
     /// Invalidate cached token.
     ///
     /// Call this when you know the current access token is no longer valid
     /// (e.g., after calling the WeChat auth ticket revoke API).
+    ///
+    /// Opens a refresh barrier (1 second by default): until it closes, all
+    /// callers wait on a single fetch — the one already in flight, or the
+    /// first one started after invalidation — and share its result.
+    ///
+    /// The token is also removed from the [`TokenStore`]; a store error is
+    /// logged. Under [`TokenStrategy::Stable`] with
+    /// `force_refresh_on_invalidate`, the next fetch forces a new stable
+    /// token.
     pub async fn invalidate(&self) {
-        let mut cache = self.cache.write().await;
-        *cache = None;
+        let mut in_flight = self.in_flight.lock().await;
+        self.invalidate_locked(&mut in_flight).await;
+    }
+
+    /// Invalidate the cached token only if it is still `rejected`
+    ///
+    /// For callers that saw WeChat reject a token (40001, 42001): when a
+    /// concurrent caller already replaced it, the newer token is kept, since
+    /// refreshing again would revoke it for everyone else.
+    pub async fn invalidate_rejected(&self, rejected: &str) {
+        let mut in_flight = self.in_flight.lock().await;
+        let replaced = match self.store.get(self.client.appid()).await {
+            Ok(Some(stored)) => stored.access_token != rejected,
+            Ok(None) | Err(_) => false,
+        };
+        if !replaced {
+            self.invalidate_locked(&mut in_flight).await;
+        }
+    }
+
+    async fn invalidate_locked(&self, in_flight: &mut FlightState) {
+        if self.strategy.force_refresh_on_invalidate() {
+            self.force_refresh.store(true, Ordering::Release);
+        }
+        if let Err(error) = self.store.invalidate(self.client.appid()).await {
+            log::warn!("failed to invalidate stored access token: {}", error);
+        }
+
+        in_flight.barrier = Instant::now()
+            .checked_add(self.invalidation_barrier)
+            .filter(|_| !self.invalidation_barrier.is_zero())
+            .map(|deadline| RefreshBarrier {
+                deadline,
+                fetch: in_flight.current.clone(),
+            });
+    }
+}
+
+/// Picks the [`TokenManager`] for each endpoint category
+///
+/// Categories are those of the
+/// [endpoint inventory](crate::api::endpoint_inventory::ENDPOINT_INVENTORY);
+/// paths in unrouted categories, or outside the inventory, use the default
+/// manager. Configure it on
+/// [`WechatMpBuilder::token_route`](crate::WechatMpBuilder::token_route).
+///
+/// Fetching a classic token revokes the previous one, so at most one manager
+/// in a router should use [`TokenSource::Classic`]; give the others
+/// [`TokenSource::Stable`] or a provider.
+#[derive(Debug, Clone)]
+pub struct TokenRouter {
+    default: Arc<TokenManager>,
+    routes: HashMap<String, Arc<TokenManager>>,
+}
+
+impl TokenRouter {
+    /// Route every category to `default`
+    pub fn new(default: Arc<TokenManager>) -> Self {
+        Self {
+            default,
+            routes: HashMap::new(),
+        }
+    }
+
+    /// Use `manager` for endpoints of `category`, e.g. `"image-ocr"`
+    pub fn route(mut self, category: impl Into<String>, manager: Arc<TokenManager>) -> Self {
+        self.routes.insert(category.into(), manager);
+        self
+    }
+
+    /// Manager for categories without a route
+    pub fn default_manager(&self) -> &Arc<TokenManager> {
+        &self.default
+    }
+
+    /// Manager used for endpoints of `category`
+    pub fn manager_for_category(&self, category: &str) -> &Arc<TokenManager> {
+        self.routes.get(category).unwrap_or(&self.default)
+    }
+
+    /// Manager used for the endpoint at `path`; query strings are ignored
+    pub fn manager_for_path(&self, path: &str) -> &Arc<TokenManager> {
+        category_for_path(path).map_or(&self.default, |category| {
+            self.manager_for_category(category)
+        })
+    }
+
+    /// Routed categories, sorted
+    pub(crate) fn categories(&self) -> Vec<&str> {
+        let mut categories: Vec<&str> = self.routes.keys().map(String::as_str).collect();
+        categories.sort_unstable();
+        categories
+    }
+
+    /// Invalidate the cached token of every manager in the router
+    pub async fn invalidate_all(&self) {
+        self.default.invalidate().await;
+        for manager in self.routes.values() {
+            if !Arc::ptr_eq(manager, &self.default) {
+                manager.invalidate().await;
+            }
+        }
     }
 }
 
+/// Retry settings applied to each [`TokenSource`]
+#[derive(Debug, Clone)]
+struct RetryPolicy {
+    max_retries: u32,
+    retry_delay_ms: u64,
+    jitter: Jitter,
+    provider_ttl_secs: u64,
+    expiry: ExpiryCorrection,
+    /// Send `force_refresh: true` to `stable_token`
+    force_refresh: bool,
+}
+
 /// Builder for creating a `TokenManager` with custom configuration
 pub struct TokenManagerBuilder {
     client: WechatClient,
     max_retries: Option<u32>,
     retry_delay_ms: Option<u64>,
+    jitter: Option<Jitter>,
     refresh_buffer_secs: Option<u64>,
+    invalidation_barrier_ms: Option<u64>,
+    provider: Option<AccessTokenProvider>,
+    sources: Vec<TokenSource>,
+    strategy: TokenStrategy,
+    fatal_auth_hook: Option<FatalAuthHook>,
+    latency_compensation: Option<bool>,
+    server_date_correction: Option<bool>,
+    store: Option<Arc<dyn TokenStore>>,
 }
 
 impl TokenManagerBuilder {
@@ -347,7 +974,16 @@ impl TokenManagerBuilder {
             client,
             max_retries: None,
             retry_delay_ms: None,
+            jitter: None,
             refresh_buffer_secs: None,
+            invalidation_barrier_ms: None,
+            provider: None,
+            sources: Vec::new(),
+            strategy: TokenStrategy::Classic,
+            fatal_auth_hook: None,
+            latency_compensation: None,
+            server_date_correction: None,
+            store: None,
         }
     }
 
@@ -367,6 +1003,15 @@ impl TokenManagerBuilder {
         self
     }
 
+    /// Randomize the delay between retry attempts with `jitter`
+    ///
+    /// Default: [`JitterMode::Additive`](crate::limit::JitterMode::Additive),
+    /// randomly seeded
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = Some(jitter);
+        self
+    }
+
     /// Set the buffer time in seconds before token expiration to trigger refresh
     ///
     /// Default: 300 seconds (5 minutes)
@@ -375,15 +1020,184 @@ impl TokenManagerBuilder {
         self
     }
 
+    /// Set how long after `invalidate()` callers share a single refresh result
+    ///
+    /// `0` disables the barrier.
+    ///
+    /// Default: 1000ms
+    pub fn invalidation_barrier_ms(mut self, barrier_ms: u64) -> Self {
+        self.invalidation_barrier_ms = Some(barrier_ms);
+        self
+    }
+
+    /// Start a fetched token's `expires_in` when its request was sent rather
+    /// than when the response was parsed
+    ///
+    /// Subtracts the round trip, including queueing behind a rate limiter or
+    /// middleware retries, from the cached lifetime.
+    ///
+    /// Default: true
+    pub fn latency_compensation(mut self, enabled: bool) -> Self {
+        self.latency_compensation = Some(enabled);
+        self
+    }
+
+    /// Also age a fetched token by the response `Date` header
+    ///
+    /// When the `Date` is further in the past than the measured round trip
+    /// (e.g. a response held by a gateway), that age is subtracted from
+    /// `expires_in` instead. Relies on the local wall clock being in sync;
+    /// a server `Date` ahead of the local clock is ignored.
+    ///
+    /// Default: false
+    pub fn server_date_correction(mut self, enabled: bool) -> Self {
+        self.server_date_correction = Some(enabled);
+        self
+    }
+
+    /// Take access tokens from `provider` instead of fetching them with the
+    /// AppSecret
+    ///
+    /// Retry, refresh buffer and invalidation settings do not apply; the
+    /// provider is called on every [`get_token`](TokenManager::get_token).
+    pub fn access_token_provider<F, Fut>(mut self, provider: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, WechatError>> + Send + 'static,
+    {
+        self.provider = Some(boxed_provider(provider));
+        self
+    }
+
+    /// Fetch tokens from `/cgi-bin/token` or `stable_token`
+    ///
+    /// Picks the source used when [`sources`](Self::sources) is not set;
+    /// with an explicit chain, only `force_refresh_on_invalidate` applies, to
+    /// its [`TokenSource::Stable`] entries.
+    ///
+    /// Default: [`TokenStrategy::Classic`]
+    ///
+    /// ```
+    /// use wechat_mp_sdk::token::{TokenManager, TokenStrategy};
+    /// # fn build(client: wechat_mp_sdk::WechatClient) -> TokenManager {
+    /// TokenManager::builder(client)
+    ///     .strategy(TokenStrategy::Stable {
+    ///         force_refresh_on_invalidate: true,
+    ///     })
+    ///     .build()
+    /// # }
+    /// ```
+    pub fn strategy(mut self, strategy: TokenStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set the ordered chain of token sources
+    ///
+    /// Each source gets the configured retries; when it still fails, a
+    /// warning is logged and the next source is tried. Tokens from any source
+    /// are cached and shared as usual.
+    ///
+    /// Default: the source of the [`strategy`](Self::strategy),
+    /// `[TokenSource::Classic]` unless set
+    ///
+    /// ```
+    /// use wechat_mp_sdk::token::{TokenManager, TokenSource};
+    /// # fn build(client: wechat_mp_sdk::WechatClient) -> TokenManager {
+    /// TokenManager::builder(client)
+    ///     .sources([
+    ///         TokenSource::Stable,
+    ///         TokenSource::Classic,
+    ///         TokenSource::provider(|| async { Ok("token-from-central-service".to_string()) }),
+    ///     ])
+    ///     .build()
+    /// # }
+    /// ```
+    pub fn sources(mut self, sources: impl IntoIterator<Item = TokenSource>) -> Self {
+        self.sources = sources.into_iter().collect();
+        self
+    }
+
+    /// Cache tokens in `store` instead of process memory
+    ///
+    /// Lets several instances share one token; see [`TokenStore`].
+    ///
+    /// Default: a new [`MemoryTokenStore`]
+    pub fn token_store(mut self, store: impl TokenStore + 'static) -> Self {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Call `hook` when a token refresh fails with a credential error no
+    /// retry can fix: invalid AppID (40013), invalid AppSecret (40125) or
+    /// missing AppSecret (41004)
+    ///
+    /// Runs on the refresh task once per failed refresh, after every
+    /// [`TokenSource`] was tried, so it fires again while requests keep
+    /// arriving; deduplicate in the alerting system. A source that falls
+    /// back successfully does not trigger it.
+    ///
+    /// ```
+    /// use wechat_mp_sdk::token::TokenManager;
+    /// # fn build(client: wechat_mp_sdk::WechatClient) -> TokenManager {
+    /// TokenManager::builder(client)
+    ///     .on_fatal_auth_error(|error| log::error!("page on-call: {error}"))
+    ///     .build()
+    /// # }
+    /// ```
+    pub fn on_fatal_auth_error<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&WechatError) + Send + Sync + 'static,
+    {
+        self.fatal_auth_hook = Some(Arc::new(hook));
+        self
+    }
+
+    pub(crate) fn fatal_auth_hook(mut self, hook: Option<FatalAuthHook>) -> Self {
+        self.fatal_auth_hook = hook;
+        self
+    }
+
+    pub(crate) fn store(mut self, store: Option<Arc<dyn TokenStore>>) -> Self {
+        self.store = store;
+        self
+    }
+
+    pub(crate) fn provider(mut self, provider: Option<AccessTokenProvider>) -> Self {
+        self.provider = provider;
+        self
+    }
+
     /// Build the TokenManager with the configured options
     pub fn build(self) -> TokenManager {
         TokenManager {
             client: self.client,
-            cache: Arc::new(RwLock::new(None)),
-            in_flight: Arc::new(Mutex::new(None)),
+            store: self
+                .store
+                .unwrap_or_else(|| Arc::new(MemoryTokenStore::new())),
+            in_flight: Arc::new(Mutex::new(FlightState::default())),
             refresh_buffer: Duration::from_secs(self.refresh_buffer_secs.unwrap_or(300)),
             max_retries: self.max_retries.unwrap_or(MAX_RETRIES),
             retry_delay_ms: self.retry_delay_ms.unwrap_or(RETRY_DELAY_MS),
+            jitter: self.jitter.unwrap_or_default(),
+            invalidation_barrier: Duration::from_millis(
+                self.invalidation_barrier_ms
+                    .unwrap_or(INVALIDATION_BARRIER_MS),
+            ),
+            provider: self.provider,
+            sources: if self.sources.is_empty() {
+                Arc::new([self.strategy.source()])
+            } else {
+                self.sources.into()
+            },
+            strategy: self.strategy,
+            force_refresh: Arc::default(),
+            fatal_auth_hook: self.fatal_auth_hook,
+            expiry: ExpiryCorrection {
+                latency: self.latency_compensation.unwrap_or(true),
+                server_date: self.server_date_correction.unwrap_or(false),
+            },
+            refreshed_at: Arc::default(),
         }
     }
 }
@@ -418,40 +1232,34 @@ mod tests {
             .unwrap()
     }
 
-    #[test]
-    fn test_token_manager_creation() {
+    const APPID: &str = "wx1234567890abcdef";
+
+    fn stored(token: &str, expires_in: Duration) -> StoredToken {
+        StoredToken::new(token, SystemTime::now() + expires_in)
+    }
+
+    #[tokio::test]
+    async fn test_token_manager_creation() {
         let client = create_test_client();
         let manager = TokenManager::new(client);
-        assert!(manager.cache.try_read().unwrap().is_none());
+        assert!(manager.store.get(APPID).await.unwrap().is_none());
     }
 
     #[test]
     fn test_cached_token_not_expired() {
-        let token = AccessToken::new("test_token").unwrap();
-        let cached = CachedToken {
-            token,
-            expires_at: Instant::now() + Duration::from_secs(7200),
-        };
+        let cached = stored("test_token", Duration::from_secs(7200));
         assert!(!cached.is_expired(Duration::from_secs(300)));
     }
 
     #[test]
     fn test_cached_token_expired() {
-        let token = AccessToken::new("test_token").unwrap();
-        let cached = CachedToken {
-            token,
-            expires_at: Instant::now() + Duration::from_secs(100),
-        };
+        let cached = stored("test_token", Duration::from_secs(100));
         assert!(cached.is_expired(Duration::from_secs(300)));
     }
 
     #[test]
     fn test_cached_token_at_boundary() {
-        let token = AccessToken::new("test_token").unwrap();
-        let cached = CachedToken {
-            token,
-            expires_at: Instant::now() + Duration::from_secs(300),
-        };
+        let cached = stored("test_token", Duration::from_secs(300));
         assert!(cached.is_expired(Duration::from_secs(300)));
     }
 
@@ -544,16 +1352,12 @@ mod tests {
         let client = create_test_client();
         let manager = TokenManager::new(client);
 
-        let token = AccessToken::new("test").unwrap();
-        let cached = CachedToken {
-            token,
-            expires_at: Instant::now() + Duration::from_secs(7200),
-        };
-        *manager.cache.write().await = Some(cached);
+        let cached = stored("test", Duration::from_secs(7200));
+        manager.store.set(APPID, cached).await.unwrap();
 
         manager.invalidate().await;
 
-        assert!(manager.cache.read().await.is_none());
+        assert!(manager.store.get(APPID).await.unwrap().is_none());
     }
 
     #[tokio::test]
@@ -561,18 +1365,14 @@ mod tests {
         let client = create_test_client();
         let manager = TokenManager::new(client);
 
-        let token = AccessToken::new("test_token").unwrap();
-        let cached = CachedToken {
-            token,
-            expires_at: Instant::now() + Duration::from_secs(7200),
-        };
-        *manager.cache.write().await = Some(cached);
+        let cached = stored("test_token", Duration::from_secs(7200));
+        manager.store.set(APPID, cached).await.unwrap();
 
-        assert!(manager.cache.read().await.is_some());
+        assert_eq!(manager.get_token().await.unwrap(), "test_token");
 
         manager.invalidate().await;
 
-        assert!(manager.cache.read().await.is_none());
+        assert!(manager.store.get(APPID).await.unwrap().is_none());
     }
 
     #[test]
@@ -629,6 +1429,82 @@ mod tests {
         assert_eq!(call_count.load(Ordering::SeqCst), 1);
     }
 
+    #[tokio::test]
+    async fn test_invalidate_under_load_single_api_call() {
+        let mock_server = MockServer::start().await;
+        let call_count = Arc::new(AtomicU32::new(0));
+        let call_count_clone = Arc::clone(&call_count);
+
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(move |_request: &wiremock::Request| {
+                let n = call_count_clone.fetch_add(1, Ordering::SeqCst);
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "access_token": format!("token_{n}"),
+                        "expires_in": 7200
+                    }))
+                    .set_delay(Duration::from_millis(50))
+            })
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client_with_base_url(&mock_server.uri());
+        let manager = Arc::new(TokenManager::new(client));
+        assert_eq!(manager.get_token().await.unwrap(), "token_0");
+
+        manager.invalidate().await;
+
+        let handles: Vec<_> = (0..20)
+            .map(|_| {
+                let manager = Arc::clone(&manager);
+                tokio::spawn(async move { manager.get_token().await })
+            })
+            .collect();
+        for result in futures::future::join_all(handles).await {
+            assert_eq!(result.unwrap().unwrap(), "token_1");
+        }
+
+        assert_eq!(call_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_barrier_shares_failed_fetch() {
+        let mock_server = MockServer::start().await;
+        let call_count = Arc::new(AtomicU32::new(0));
+        let call_count_clone = Arc::clone(&call_count);
+
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(move |_request: &wiremock::Request| {
+                call_count_clone.fetch_add(1, Ordering::SeqCst);
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "errcode": 40013,
+                    "errmsg": "invalid appid"
+                }))
+            })
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client_with_base_url(&mock_server.uri());
+        let manager = TokenManager::builder(client)
+            .invalidation_barrier_ms(200)
+            .build();
+
+        manager.invalidate().await;
+        for _ in 0..5 {
+            assert!(matches!(
+                manager.get_token().await,
+                Err(WechatError::Api { code: 40013, .. })
+            ));
+        }
+        assert_eq!(call_count.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(manager.get_token().await.is_err());
+        assert_eq!(call_count.load(Ordering::SeqCst), 2);
+    }
+
     #[tokio::test]
     async fn test_fetch_token_with_retry_retries_retryable_errors_then_succeeds() {
         let mock_server = MockServer::start().await;
@@ -745,13 +1621,84 @@ mod tests {
         assert_eq!(token, "cancel_safe_token");
     }
 
+    #[tokio::test]
+    async fn test_single_flight_dropping_first_caller_keeps_one_fetch() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_delay(Duration::from_millis(100))
+                    .set_body_json(serde_json::json!({
+                        "access_token": "shared_token",
+                        "expires_in": 7200
+                    })),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client_with_base_url(&mock_server.uri());
+        let manager = Arc::new(TokenManager::new(client));
+
+        let waiters: Vec<_> = (0..4)
+            .map(|_| {
+                let manager = Arc::clone(&manager);
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    manager.get_token().await
+                })
+            })
+            .collect();
+        let first = tokio::time::timeout(Duration::from_millis(30), manager.get_token()).await;
+        assert!(first.is_err(), "first caller should be dropped mid-fetch");
+
+        for waiter in waiters {
+            assert_eq!(waiter.await.unwrap().unwrap(), "shared_token");
+        }
+        assert_eq!(manager.get_token().await.unwrap(), "shared_token");
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_elects_new_fetcher_when_fetch_is_abandoned() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "reelected_token",
+                "expires_in": 7200
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client_with_base_url(&mock_server.uri());
+        let manager = TokenManager::new(client);
+
+        // A fetch whose task died before publishing a result.
+        let (abandoned, sender) = InFlightFetch::start();
+        manager.in_flight.lock().await.current = Some(Arc::clone(&abandoned));
+
+        let waiter = {
+            let abandoned = Arc::clone(&abandoned);
+            tokio::spawn(async move { abandoned.wait().await.is_none() })
+        };
+        drop(sender);
+        assert!(waiter.await.unwrap());
+
+        let token = tokio::time::timeout(Duration::from_millis(500), manager.get_token())
+            .await
+            .expect("waiters must not hang on an abandoned fetch")
+            .unwrap();
+        assert_eq!(token, "reelected_token");
+        assert!(manager.in_flight.lock().await.current.is_none());
+    }
+
     #[test]
     fn test_cached_token_is_expired_when_buffer_addition_overflows() {
-        let token = AccessToken::new("test_token").unwrap();
-        let cached = CachedToken {
-            token,
-            expires_at: Instant::now() + Duration::from_secs(1),
-        };
+        let cached = stored("test_token", Duration::from_secs(1));
 
         assert!(cached.is_expired(Duration::MAX));
     }
@@ -763,6 +1710,10 @@ mod tests {
         assert_eq!(manager.refresh_buffer, Duration::from_secs(300));
         assert_eq!(manager.max_retries, 3);
         assert_eq!(manager.retry_delay_ms, 100);
+        assert_eq!(
+            manager.invalidation_barrier,
+            Duration::from_millis(INVALIDATION_BARRIER_MS)
+        );
     }
 
     #[test]
@@ -778,6 +1729,21 @@ mod tests {
         assert_eq!(manager.retry_delay_ms, 200);
     }
 
+    #[test]
+    fn test_token_manager_builder_jitter() {
+        use crate::limit::JitterMode;
+
+        let client = create_test_client();
+        assert_eq!(
+            TokenManager::builder(client.clone()).build().jitter.mode(),
+            JitterMode::Additive
+        );
+        let manager = TokenManager::builder(client)
+            .jitter(Jitter::seeded(JitterMode::Equal, 1))
+            .build();
+        assert_eq!(manager.jitter.mode(), JitterMode::Equal);
+    }
+
     #[test]
     fn test_token_manager_builder_partial_custom() {
         let client = create_test_client();
@@ -786,4 +1752,304 @@ mod tests {
         assert_eq!(manager.retry_delay_ms, 100);
         assert_eq!(manager.refresh_buffer, Duration::from_secs(300));
     }
+
+    #[tokio::test]
+    async fn test_sources_fall_back_from_stable_to_classic() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/cgi-bin/stable_token"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "classic_token",
+                "expires_in": 7200
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client_with_base_url(&mock_server.uri());
+        let manager = TokenManager::builder(client)
+            .max_retries(2)
+            .retry_delay_ms(1)
+            .sources([TokenSource::Stable, TokenSource::Classic])
+            .build();
+
+        assert_eq!(manager.get_token().await.unwrap(), "classic_token");
+    }
+
+    #[tokio::test]
+    async fn test_stable_strategy_forces_refresh_after_invalidate() {
+        use wiremock::matchers::body_partial_json;
+
+        let mock_server = MockServer::start().await;
+        for (force_refresh, token, calls) in [(false, "stable_token", 2), (true, "forced_token", 1)]
+        {
+            Mock::given(method("POST"))
+                .and(path("/cgi-bin/stable_token"))
+                .and(body_partial_json(serde_json::json!({
+                    "grant_type": "client_credential",
+                    "force_refresh": force_refresh
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "access_token": token,
+                    "expires_in": 7200
+                })))
+                .expect(calls)
+                .mount(&mock_server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client_with_base_url(&mock_server.uri());
+        let manager = TokenManager::builder(client)
+            .invalidation_barrier_ms(0)
+            .strategy(TokenStrategy::Stable {
+                force_refresh_on_invalidate: true,
+            })
+            .build();
+
+        assert_eq!(manager.get_token().await.unwrap(), "stable_token");
+        manager.invalidate().await;
+        assert_eq!(manager.get_token().await.unwrap(), "forced_token");
+
+        // Only the first fetch after invalidation is forced.
+        manager.store.invalidate(APPID).await.unwrap();
+        assert_eq!(manager.get_token().await.unwrap(), "stable_token");
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_rejected_keeps_replaced_token() {
+        let manager = TokenManager::new(create_test_client());
+        manager
+            .store
+            .set(APPID, stored("replacement", Duration::from_secs(7200)))
+            .await
+            .unwrap();
+
+        manager.invalidate_rejected("revoked").await;
+        assert_eq!(manager.get_token().await.unwrap(), "replacement");
+
+        manager.invalidate_rejected("replacement").await;
+        assert!(manager.store.get(APPID).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fatal_auth_hook_fires_on_rejected_secret() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 40125,
+                "errmsg": "invalid appsecret rid: 64a1b2c3-1d2e3f4a"
+            })))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 40164,
+                "errmsg": "invalid ip, not in whitelist"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let alerts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let received = Arc::clone(&alerts);
+        let client = create_test_client_with_base_url(&mock_server.uri());
+        let manager = TokenManager::builder(client)
+            .invalidation_barrier_ms(0)
+            .on_fatal_auth_error(move |error| received.lock().unwrap().push(error.to_string()))
+            .build();
+
+        let error = manager.get_token().await.unwrap_err();
+        assert!(error.is_fatal_auth());
+        assert_eq!(alerts.lock().unwrap().len(), 1);
+        assert!(alerts.lock().unwrap()[0].contains("40125"));
+
+        let error = manager.get_token().await.unwrap_err();
+        assert!(!error.is_fatal_auth());
+        assert_eq!(alerts.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sources_fall_back_to_provider_on_persistent_api_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errcode": 40164,
+                "errmsg": "invalid ip, not in whitelist"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client_with_base_url(&mock_server.uri());
+        let manager = TokenManager::builder(client)
+            .retry_delay_ms(1)
+            .refresh_buffer_secs(300)
+            .sources([
+                TokenSource::Classic,
+                TokenSource::provider(|| async { Ok("provided_token".to_string()) }),
+            ])
+            .build();
+
+        let response = manager.fetch_token_with_retry().await.unwrap();
+        assert_eq!(response.access_token, "provided_token");
+        assert_eq!(response.expires_in, 300 + FALLBACK_PROVIDER_TTL_SECS);
+    }
+
+    #[tokio::test]
+    async fn test_sources_return_last_error_when_all_fail() {
+        let client = create_test_client();
+        let manager = TokenManager::builder(client)
+            .sources([
+                TokenSource::provider(|| async { Err(WechatError::Token("first".to_string())) }),
+                TokenSource::provider(|| async { Err(WechatError::Token("second".to_string())) }),
+            ])
+            .build();
+
+        let error = manager.fetch_token_with_retry().await.unwrap_err();
+        assert!(matches!(error, WechatError::Token(message) if message == "second"));
+    }
+
+    #[tokio::test]
+    async fn test_expiry_is_shortened_by_latency_and_server_date() {
+        let mock_server = MockServer::start().await;
+        let issued = httpdate::fmt_http_date(SystemTime::now() - Duration::from_secs(7000));
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Date", issued.as_str())
+                    .set_delay(Duration::from_millis(50))
+                    .set_body_json(serde_json::json!({
+                        "access_token": "aged_token",
+                        "expires_in": 7200
+                    })),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let store = MemoryTokenStore::new();
+        let expires_at = || lock(&store.tokens).get(APPID).unwrap().expires_at;
+        let client = create_test_client_with_base_url(&mock_server.uri());
+
+        let naive = TokenManager::builder(client.clone())
+            .latency_compensation(false)
+            .token_store(store.clone())
+            .build();
+        let before = SystemTime::now();
+        naive.get_token().await.unwrap();
+        assert!(expires_at() >= before + Duration::from_millis(7_200_050));
+
+        let compensated = TokenManager::builder(client.clone())
+            .token_store(store.clone())
+            .build();
+        compensated.invalidate().await;
+        let before = SystemTime::now();
+        compensated.get_token().await.unwrap();
+        assert!(expires_at() <= SystemTime::now() + Duration::from_secs(7200));
+        assert!(expires_at() >= before + Duration::from_secs(7199));
+
+        let dated = TokenManager::builder(client)
+            .server_date_correction(true)
+            .refresh_buffer_secs(0)
+            .token_store(store.clone())
+            .build();
+        dated.invalidate().await;
+        dated.get_token().await.unwrap();
+        let remaining = expires_at().duration_since(SystemTime::now()).unwrap();
+        assert!(remaining <= Duration::from_secs(201), "{remaining:?}");
+        assert!(remaining >= Duration::from_secs(190), "{remaining:?}");
+    }
+
+    /// A store shared by two managers, standing in for Redis between
+    /// instances
+    #[tokio::test]
+    async fn test_token_store_is_shared_between_managers() {
+        let mock_server = MockServer::start().await;
+        let call_count = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&call_count);
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(move |_: &wiremock::Request| {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "access_token": format!("shared_token_{n}"),
+                    "expires_in": 7200
+                }))
+            })
+            .mount(&mock_server)
+            .await;
+
+        let store = MemoryTokenStore::new();
+        let client = create_test_client_with_base_url(&mock_server.uri());
+        let first = TokenManager::builder(client.clone())
+            .token_store(store.clone())
+            .build();
+        let second = TokenManager::builder(client)
+            .token_store(store.clone())
+            .build();
+
+        assert_eq!(first.get_token().await.unwrap(), "shared_token_0");
+        assert_eq!(second.get_token().await.unwrap(), "shared_token_0");
+        assert_eq!(call_count.load(Ordering::SeqCst), 1);
+
+        second.invalidate().await;
+        assert!(store.get(APPID).await.unwrap().is_none());
+        assert_eq!(first.get_token().await.unwrap(), "shared_token_1");
+        assert_eq!(call_count.load(Ordering::SeqCst), 2);
+    }
+
+    struct FailingStore;
+
+    impl TokenStore for FailingStore {
+        fn get<'a>(&'a self, _appid: &'a str) -> TokenStoreFuture<'a, Option<StoredToken>> {
+            Box::pin(async { Err(WechatError::Token("store unavailable".to_string())) })
+        }
+
+        fn set<'a>(&'a self, _appid: &'a str, _token: StoredToken) -> TokenStoreFuture<'a, ()> {
+            Box::pin(async { Err(WechatError::Token("store unavailable".to_string())) })
+        }
+
+        fn invalidate<'a>(&'a self, _appid: &'a str) -> TokenStoreFuture<'a, ()> {
+            Box::pin(async { Err(WechatError::Token("store unavailable".to_string())) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_store_errors_are_returned_without_fetching() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cgi-bin/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "unused",
+                "expires_in": 7200
+            })))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let manager = TokenManager::builder(create_test_client_with_base_url(&mock_server.uri()))
+            .token_store(FailingStore)
+            .build();
+        let error = manager.get_token().await.unwrap_err();
+        assert!(matches!(error, WechatError::Token(message) if message == "store unavailable"));
+        manager.invalidate().await;
+    }
 }
@@ -26,25 +26,69 @@ fn validate_base64_and_decode(s: &str) -> Result<Vec<u8>, String> {
         .map_err(|e| format!("invalid base64: {}", e))
 }
 
+/// How strictly an [`AppId`] is checked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AppIdValidation {
+    /// `wx` prefix and exactly 18 characters, as issued for Mini Programs
+    #[default]
+    Strict,
+    /// Non-empty, without whitespace or control characters; for test and
+    /// sandbox appids that do not follow the production format
+    Lenient,
+}
+
+impl AppIdValidation {
+    pub fn validate(self, id: &str) -> Result<(), WechatError> {
+        match self {
+            AppIdValidation::Strict => {
+                if !id.starts_with("wx") {
+                    return Err(WechatError::InvalidAppId(format!(
+                        "AppId must start with 'wx', got {}",
+                        id
+                    )));
+                }
+                if id.len() != 18 {
+                    return Err(WechatError::InvalidAppId(format!(
+                        "AppId must be 18 characters, got {}",
+                        id.len()
+                    )));
+                }
+            }
+            AppIdValidation::Lenient => {
+                if id.is_empty() {
+                    return Err(WechatError::InvalidAppId(
+                        "AppId must not be empty".to_string(),
+                    ));
+                }
+                if id.chars().any(|c| c.is_whitespace() || c.is_control()) {
+                    return Err(WechatError::InvalidAppId(
+                        "AppId must not contain whitespace or control characters".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// WeChat Mini Program AppID (18 characters)
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AppId(String);
 
 impl AppId {
+    /// Creates an AppId, checked with [`AppIdValidation::Strict`].
     pub fn new(id: impl Into<String>) -> Result<Self, WechatError> {
+        Self::with_validation(id, AppIdValidation::Strict)
+    }
+
+    /// Creates an AppId checked at the given validation level.
+    pub fn with_validation(
+        id: impl Into<String>,
+        validation: AppIdValidation,
+    ) -> Result<Self, WechatError> {
         let id = id.into();
-        if !id.starts_with("wx") {
-            return Err(WechatError::InvalidAppId(format!(
-                "AppId must start with 'wx', got {}",
-                id
-            )));
-        }
-        if id.len() != 18 {
-            return Err(WechatError::InvalidAppId(format!(
-                "AppId must be 18 characters, got {}",
-                id.len()
-            )));
-        }
+        validation.validate(&id)?;
         Ok(Self(id))
     }
 
@@ -113,6 +157,7 @@ impl fmt::Display for AppSecret {
 
 /// WeChat Mini Program OpenID (20-40 characters)
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct OpenId(String);
 
 impl OpenId {
@@ -127,6 +172,13 @@ impl OpenId {
         Ok(Self(id))
     }
 
+    /// OpenId from an id already known to be 20-40 characters
+    #[cfg(feature = "fixtures")]
+    pub(crate) fn from_valid(id: String) -> Self {
+        debug_assert!((20..=40).contains(&id.len()));
+        Self(id)
+    }
+
     pub fn as_str(&self) -> &str {
         &self.0
     }
@@ -296,6 +348,18 @@ mod tests {
         assert!(err_str.contains("must start with 'wx'"));
     }
 
+    #[test]
+    fn test_app_id_validation_levels() {
+        let sandbox = "tt_sandbox_app";
+        assert!(AppId::new(sandbox).is_err());
+        let app_id = AppId::with_validation(sandbox, AppIdValidation::Lenient).unwrap();
+        assert_eq!(app_id.as_str(), sandbox);
+
+        assert!(AppId::with_validation("", AppIdValidation::Lenient).is_err());
+        assert!(AppId::with_validation("wx 123", AppIdValidation::Lenient).is_err());
+        assert!(AppId::with_validation("wx1234567890abcdef", AppIdValidation::Strict).is_ok());
+    }
+
     #[test]
     fn test_app_secret_valid() {
         let secret = "abc123".to_string();
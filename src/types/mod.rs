@@ -1,5 +1,7 @@
 pub mod ids;
+pub mod request;
 pub mod watermark;
 
 pub use ids::*;
+pub use request::MiniProgramPath;
 pub use watermark::Watermark;
@@ -0,0 +1,189 @@
+//! Validated request values
+//!
+//! [`MiniProgramPath`] catches malformed page paths before they reach
+//! WeChat, which otherwise rejects them with errcodes such as 40097 (invalid
+//! args) or 44002 (empty post data) and little detail.
+
+use std::fmt;
+
+use crate::error::WechatError;
+
+/// Prefix every mini program page path starts with
+pub const PAGES_PREFIX: &str = "pages/";
+
+/// Maximum length of a path's query string, in characters
+pub const MAX_QUERY_LEN: usize = 1024;
+
+/// A mini program page path with an optional query, e.g.
+/// `pages/detail/detail?id=42`
+///
+/// Parsing accepts an optional leading `/` and drops it: URL links tolerate
+/// it but code generation (`getwxacode`, `getwxacodeunlimit`) rejects it, so
+/// paths are stored in the form every endpoint accepts.
+///
+/// Use it for qrcode options ([`QrcodeOptions::with_path`],
+/// [`UnlimitQrcodeOptions::with_page`], [`UrlLinkOptions::for_path`],
+/// [`UrlSchemeOptions::for_path`]) and, through `Into<String>`, for subscribe
+/// message `page` and customer service `pagepath` fields.
+///
+/// [`QrcodeOptions::with_path`]: crate::api::qrcode::QrcodeOptions::with_path
+/// [`UnlimitQrcodeOptions::with_page`]: crate::api::qrcode::UnlimitQrcodeOptions::with_page
+/// [`UrlLinkOptions::for_path`]: crate::api::qrcode::UrlLinkOptions::for_path
+/// [`UrlSchemeOptions::for_path`]: crate::api::qrcode::UrlSchemeOptions::for_path
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MiniProgramPath {
+    page: String,
+    query: Option<String>,
+}
+
+impl MiniProgramPath {
+    /// Parse `page[?query]`.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] unless the page starts with
+    /// `pages/`, has no whitespace, control characters or empty segments, and
+    /// the query is at most [`MAX_QUERY_LEN`] characters.
+    pub fn new(path: impl AsRef<str>) -> Result<Self, WechatError> {
+        let path = path.as_ref();
+        let (page, query) = match path.split_once('?') {
+            Some((page, query)) => (page, Some(query)),
+            None => (path, None),
+        };
+        let page = page.strip_prefix('/').unwrap_or(page);
+
+        if !page.starts_with(PAGES_PREFIX) {
+            return Err(WechatError::validation(
+                "path",
+                format!("page must start with '{}', got {:?}", PAGES_PREFIX, path),
+            ));
+        }
+        if page.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(WechatError::validation(
+                "path",
+                format!(
+                    "page must not contain whitespace or control characters, got {:?}",
+                    path
+                ),
+            ));
+        }
+        if page.split('/').any(str::is_empty) {
+            return Err(WechatError::validation(
+                "path",
+                format!("page must not contain empty segments, got {:?}", path),
+            ));
+        }
+
+        let query = query.filter(|query| !query.is_empty());
+        if let Some(query) = query {
+            Self::validate_query(query)?;
+        }
+        Ok(Self {
+            page: page.to_string(),
+            query: query.map(str::to_string),
+        })
+    }
+
+    /// Add or replace the query string.
+    ///
+    /// # Errors
+    /// Returns [`WechatError::Validation`] for queries over
+    /// [`MAX_QUERY_LEN`] characters or containing whitespace.
+    pub fn with_query(mut self, query: impl Into<String>) -> Result<Self, WechatError> {
+        let query = query.into();
+        Self::validate_query(&query)?;
+        self.query = Some(query).filter(|query| !query.is_empty());
+        Ok(self)
+    }
+
+    fn validate_query(query: &str) -> Result<(), WechatError> {
+        let len = query.chars().count();
+        if len > MAX_QUERY_LEN {
+            return Err(WechatError::validation(
+                "query",
+                format!("must be at most {} characters, got {}", MAX_QUERY_LEN, len),
+            ));
+        }
+        if query.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(WechatError::validation(
+                "query",
+                "must not contain whitespace or control characters",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Page without the query, e.g. `pages/detail/detail`
+    pub fn page(&self) -> &str {
+        &self.page
+    }
+
+    /// Query string without the `?`, if any
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+}
+
+impl fmt::Display for MiniProgramPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.query {
+            Some(query) => write!(f, "{}?{}", self.page, query),
+            None => f.write_str(&self.page),
+        }
+    }
+}
+
+impl From<MiniProgramPath> for String {
+    fn from(path: MiniProgramPath) -> Self {
+        path.to_string()
+    }
+}
+
+impl From<&MiniProgramPath> for String {
+    fn from(path: &MiniProgramPath) -> Self {
+        path.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_normalizes_leading_slash_and_splits_query() {
+        let path = MiniProgramPath::new("/pages/detail/detail?id=42").unwrap();
+        assert_eq!(path.page(), "pages/detail/detail");
+        assert_eq!(path.query(), Some("id=42"));
+        assert_eq!(String::from(&path), "pages/detail/detail?id=42");
+
+        let bare = MiniProgramPath::new("pages/index/index?").unwrap();
+        assert_eq!(bare.query(), None);
+        assert_eq!(bare.to_string(), "pages/index/index");
+    }
+
+    #[test]
+    fn test_path_rejects_malformed_values() {
+        for path in [
+            "",
+            "index/index",
+            "//pages/index",
+            "pages//index",
+            "pages/index index",
+            "https://example.com/pages/index",
+        ] {
+            assert!(
+                matches!(
+                    MiniProgramPath::new(path),
+                    Err(WechatError::Validation { ref field, .. }) if field == "path"
+                ),
+                "{path:?} should be rejected"
+            );
+        }
+
+        let long_query = "a".repeat(MAX_QUERY_LEN + 1);
+        assert!(MiniProgramPath::new(format!("pages/index?{long_query}")).is_err());
+        assert!(MiniProgramPath::new("pages/index")
+            .unwrap()
+            .with_query(&long_query[1..])
+            .is_ok());
+    }
+}
@@ -8,6 +8,7 @@ use crate::error::WechatError;
 /// WeChat's encrypted user data and phone number responses.
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Watermark {
     /// Timestamp when data was encrypted
     pub(crate) timestamp: i64,
@@ -1,29 +1,179 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Duration;
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard};
 
-const MAX_BACKOFF_MS: u64 = 30_000;
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
 
-pub(crate) fn jittered_delay(base_delay_ms: u64, attempt: u32) -> Duration {
-    let backoff_multiplier = 1u64.checked_shl(attempt).unwrap_or(u64::MAX);
-    let base = base_delay_ms.saturating_mul(backoff_multiplier);
+use crate::client::ErrorSniffing;
+use crate::error::WechatError;
+use crate::redact::Redactor;
 
-    let jitter_upper_bound = base / 2;
-    let jitter = if jitter_upper_bound == 0 {
-        0
+/// Maximum number of body bytes kept in a [`WechatError::Decode`] snippet.
+const DECODE_SNIPPET_MAX_BYTES: usize = 512;
+
+/// Run `future` until it completes or `token` is cancelled.
+///
+/// The future is dropped on cancellation, which aborts any request in flight.
+pub(crate) async fn run_cancellable<F, T>(
+    token: Option<&CancellationToken>,
+    future: F,
+) -> Result<T, WechatError>
+where
+    F: Future<Output = Result<T, WechatError>>,
+{
+    let Some(token) = token else {
+        return future.await;
+    };
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => Err(WechatError::Cancelled),
+        output = future => output,
+    }
+}
+
+/// Resolve once `token` is cancelled; never resolves without a token.
+pub(crate) async fn cancelled(token: Option<&CancellationToken>) {
+    match token {
+        Some(token) => token.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Lock `mutex`, recovering the guard if a previous holder panicked.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Decode a raw JSON body into `T`, reporting the failing path and a body snippet.
+pub(crate) fn decode_json<T: DeserializeOwned>(body: &[u8]) -> Result<T, WechatError> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let value = serde_path_to_error::deserialize(&mut deserializer)
+        .map_err(|error| decode_error(error.path().to_string(), error.inner(), body))?;
+    deserializer
+        .end()
+        .map_err(|error| decode_error(".".to_string(), &error, body))?;
+    Ok(value)
+}
+
+/// Decode an already-parsed JSON value into `T`, using `body` for the error snippet.
+pub(crate) fn decode_json_value<T: DeserializeOwned>(
+    value: &Value,
+    body: &[u8],
+) -> Result<T, WechatError> {
+    serde_path_to_error::deserialize(value)
+        .map_err(|error| decode_error(error.path().to_string(), error.inner(), body))
+}
+
+/// Extract a non-zero `errcode` from a parsed JSON body.
+pub(crate) fn api_error_from_value(value: &Value) -> Option<WechatError> {
+    let raw_code = value.get("errcode")?.as_i64()?;
+    if raw_code == 0 {
+        return None;
+    }
+
+    let code = i32::try_from(raw_code).unwrap_or(if raw_code.is_negative() {
+        i32::MIN
     } else {
-        static COUNTER: AtomicU64 = AtomicU64::new(0);
-        let seq = COUNTER.fetch_add(1, Ordering::Relaxed);
-        let mut hasher = DefaultHasher::new();
-        seq.hash(&mut hasher);
-        (attempt as u64).hash(&mut hasher);
-        base_delay_ms.hash(&mut hasher);
-        hasher.finish() % jitter_upper_bound
+        i32::MAX
+    });
+    let message = value
+        .get("errmsg")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown error");
+    Some(WechatError::api(code, message))
+}
+
+/// Detect a JSON error envelope in a body that is normally binary.
+///
+/// WeChat answers binary endpoints with HTTP 200 and a JSON `errcode` body on
+/// failure, sometimes labelled `text/plain` or even with the binary content
+/// type. Whether a body is parsed is decided by the [`ErrorSniffing`] policy;
+/// bodies that are not parsed are returned as data.
+pub(crate) fn sniff_api_error(
+    policy: ErrorSniffing,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Option<WechatError> {
+    if !policy.should_parse(content_type, body) {
+        return None;
+    }
+    let body = body.strip_prefix(b"\xef\xbb\xbf").unwrap_or(body);
+    let value: Value = serde_json::from_slice(body).ok()?;
+    api_error_from_value(&value)
+}
+
+/// A binary response body with the headers describing it
+pub(crate) struct BinaryBody {
+    pub(crate) bytes: Bytes,
+    pub(crate) content_type: Option<String>,
+    pub(crate) content_length: Option<u64>,
+}
+
+/// Read a binary response body, surfacing HTTP and JSON `errcode` failures.
+pub(crate) async fn binary_body(
+    response: reqwest::Response,
+    policy: ErrorSniffing,
+) -> Result<Bytes, WechatError> {
+    binary_body_parts(response, policy)
+        .await
+        .map(|body| body.bytes)
+}
+
+/// Like [`binary_body`], keeping the `Content-Type` and `Content-Length`.
+pub(crate) async fn binary_body_parts(
+    response: reqwest::Response,
+    policy: ErrorSniffing,
+) -> Result<BinaryBody, WechatError> {
+    if let Err(error) = response.error_for_status_ref() {
+        return Err(error.into());
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    let content_length = response.content_length();
+    let bytes = response.bytes().await?;
+    if let Some(error) = sniff_api_error(policy, content_type.as_deref(), &bytes) {
+        return Err(error);
+    }
+    Ok(BinaryBody {
+        bytes,
+        content_type,
+        content_length,
+    })
+}
+
+fn decode_error(path: String, error: &serde_json::Error, body: &[u8]) -> WechatError {
+    WechatError::Decode {
+        path,
+        snippet: body_snippet(body),
+        message: error.to_string(),
+    }
+}
+
+fn body_snippet(body: &[u8]) -> String {
+    let text = match serde_json::from_slice::<Value>(body) {
+        Ok(mut value) => {
+            Redactor::default().redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(body).into_owned(),
     };
 
-    let total_delay_ms = base.saturating_add(jitter).min(MAX_BACKOFF_MS);
-    Duration::from_millis(total_delay_ms)
+    if text.len() <= DECODE_SNIPPET_MAX_BYTES {
+        return text;
+    }
+
+    let mut end = DECODE_SNIPPET_MAX_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...({} bytes total)", &text[..end], body.len())
 }
 
 #[cfg(test)]
@@ -31,50 +181,130 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_jittered_delay_bounds() {
-        let base_delay_ms = 100;
-        for attempt in 0..9 {
-            let base = base_delay_ms * 2u64.pow(attempt);
-            let delay = jittered_delay(base_delay_ms, attempt);
-            assert!(delay >= Duration::from_millis(base));
-            assert!(delay <= Duration::from_millis(base + (base / 2)));
+    fn test_sniff_api_error() {
+        let auto = ErrorSniffing::Auto;
+        let error = sniff_api_error(
+            auto,
+            Some("application/json; charset=utf-8"),
+            br#"{"errcode":45009,"errmsg":"reach max api daily quota limit"}"#,
+        )
+        .unwrap();
+        assert!(matches!(error, WechatError::Api { code: 45009, .. }));
+
+        assert!(sniff_api_error(auto, None, br#"{"errcode":0,"errmsg":"ok"}"#).is_none());
+        assert!(sniff_api_error(auto, Some("image/jpeg"), b"\xff\xd8\xff").is_none());
+        assert!(sniff_api_error(auto, Some("text/plain"), b"not json").is_none());
+        assert!(sniff_api_error(auto, None, b"\x89PNG").is_none());
+
+        let mislabelled =
+            sniff_api_error(auto, Some("image/jpeg"), br#"{"errcode":40001}"#).unwrap();
+        assert!(matches!(mislabelled, WechatError::Api { code: 40001, .. }));
+    }
+
+    /// Error bodies captured from binary endpoints with misleading headers.
+    #[test]
+    fn test_sniff_api_error_captured_payloads() {
+        let captured: &[(&str, &[u8], i32)] = &[
+            (
+                "image/jpeg",
+                br#"{"errcode":40001,"errmsg":"invalid credential, access_token is invalid or not latest rid: 6582a1c4-1d2f3e4a-5b6c7d8e"}"#,
+                40001,
+            ),
+            (
+                "image/jpeg",
+                "{\"errcode\":41030,\"errmsg\":\"invalid page rid: 65a0b1c2-3d4e5f60-718293a4\"}\n".as_bytes(),
+                41030,
+            ),
+            (
+                "image/png",
+                b"\xef\xbb\xbf{\"errcode\":45009,\"errmsg\":\"reach max api daily quota limit\"}",
+                45009,
+            ),
+            (
+                "application/octet-stream",
+                b"\r\n  {\"errcode\":40007,\"errmsg\":\"invalid media_id\"}",
+                40007,
+            ),
+        ];
+        for (content_type, body, code) in captured {
+            let error = sniff_api_error(ErrorSniffing::Auto, Some(content_type), body)
+                .unwrap_or_else(|| panic!("{content_type} body not sniffed"));
+            assert!(matches!(error, WechatError::Api { code: c, .. } if c == *code));
         }
+
+        let (content_type, body, _) = captured[0];
+        assert!(sniff_api_error(ErrorSniffing::ContentType, Some(content_type), body).is_none());
+        assert!(sniff_api_error(ErrorSniffing::Disabled, Some("application/json"), body).is_none());
+        assert!(sniff_api_error(ErrorSniffing::ContentType, Some("text/plain"), body).is_some());
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Template {
+        #[serde(rename = "priTmplId")]
+        #[allow(dead_code)]
+        private_template_id: String,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct TemplateList {
+        #[allow(dead_code)]
+        data: Vec<Template>,
     }
 
     #[test]
-    fn test_jittered_delay_max_cap() {
-        let delay = jittered_delay(1_000, 20);
-        assert!(delay <= Duration::from_secs(30));
+    fn test_decode_json_reports_path_and_snippet() {
+        let body = br#"{"data":[{"priTmplId":"a"},{"priTmplId":7}]}"#;
+        let err = decode_json::<TemplateList>(body).unwrap_err();
+        match err {
+            WechatError::Decode {
+                path,
+                snippet,
+                message,
+            } => {
+                assert_eq!(path, "data[1].priTmplId");
+                assert!(snippet.contains("priTmplId"));
+                assert!(message.contains("invalid type"));
+            }
+            other => panic!("Expected Decode error, got {:?}", other),
+        }
     }
 
     #[test]
-    fn test_jittered_delay_handles_overflow_safely() {
-        let delay = jittered_delay(u64::MAX, u32::MAX);
-        assert_eq!(delay, Duration::from_secs(30));
+    fn test_decode_json_rejects_malformed_body() {
+        let err = decode_json::<Value>(b"{ invalid json }").unwrap_err();
+        match err {
+            WechatError::Decode { snippet, .. } => assert_eq!(snippet, "{ invalid json }"),
+            other => panic!("Expected Decode error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_json_value_uses_body_snippet() {
+        let body = br#"{"data":"oops"}"#;
+        let value: Value = serde_json::from_slice(body).unwrap();
+        let err = decode_json_value::<TemplateList>(&value, body).unwrap_err();
+        assert!(matches!(err, WechatError::Decode { ref path, .. } if path == "data"));
     }
 
     #[test]
-    fn test_jittered_delay_varies_by_attempt() {
-        let delay_a = jittered_delay(100, 0);
-        let delay_b = jittered_delay(100, 1);
-        assert_ne!(delay_a, delay_b);
+    fn test_decode_snippet_is_size_capped() {
+        let body = format!(r#"{{"data":"{}"}}"#, "x".repeat(4096));
+        let err = decode_json::<TemplateList>(body.as_bytes()).unwrap_err();
+        match err {
+            WechatError::Decode { snippet, .. } => {
+                assert!(snippet.len() < 600);
+                assert!(snippet.ends_with(&format!("({} bytes total)", body.len())));
+            }
+            other => panic!("Expected Decode error, got {:?}", other),
+        }
     }
+
     #[test]
-    fn test_jittered_delay_same_attempt_produces_varied_jitter() {
-        // Call jittered_delay many times with the same attempt to verify
-        // that the AtomicU64 counter produces different jitter values.
-        let delays: Vec<Duration> = (0..20).map(|_| jittered_delay(1000, 3)).collect();
-        let unique_count = {
-            let mut sorted = delays.clone();
-            sorted.sort();
-            sorted.dedup();
-            sorted.len()
-        };
-        // With 20 samples and a jitter range of [0, 4000), we expect multiple distinct values.
-        assert!(
-            unique_count >= 2,
-            "Expected varied jitter across same-attempt calls, got {} unique out of 20",
-            unique_count,
-        );
+    fn test_decode_snippet_redacts_sensitive_fields() {
+        let body = br#"{"access_token":"tok_secret_value","expires_in":"soon"}"#;
+        let err = decode_json::<Template>(body).unwrap_err();
+        let rendered = err.to_string();
+        assert!(!rendered.contains("tok_secret_value"));
+        assert!(rendered.contains("[REDACTED]"));
     }
 }
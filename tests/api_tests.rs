@@ -7,7 +7,10 @@
 use wechat_mp_sdk::api::auth::LoginResponse;
 use wechat_mp_sdk::api::qrcode::{LineColor, QrcodeOptions, UnlimitQrcodeOptions};
 use wechat_mp_sdk::api::user::{PhoneInfo, UserInfo};
-use wechat_mp_sdk::api::{MediaMessage, MediaType, Message, MiniProgramPageMessage, TextMessage};
+use wechat_mp_sdk::api::{
+    MediaMessage, MediaType, MediaUploadResponse, Message, MiniProgramPageMessage, PluginApplicant,
+    TemplateListResponse, TextMessage,
+};
 use wechat_mp_sdk::types::Watermark;
 
 #[test]
@@ -367,3 +370,26 @@ fn test_user_info_new_constructor() {
     assert!(user.avatar_url.is_none());
     assert!(user.city.is_none());
 }
+
+#[test]
+fn test_typed_responses_reserialize_for_transcoding() {
+    let json = r#"{"data":[{"priTmplId":"tpl","title":"Shipped","content":"{{thing1.DATA}}","type":2}],"errcode":0,"errmsg":"ok"}"#;
+    let templates: TemplateListResponse = serde_json::from_str(json).unwrap();
+    let value = serde_json::to_value(&templates).unwrap();
+    assert_eq!(value["data"][0]["priTmplId"], "tpl");
+    assert_eq!(value["data"][0]["type"], 2);
+    let again: TemplateListResponse = serde_json::from_value(value).unwrap();
+    assert_eq!(again.data[0].private_template_id, "tpl");
+
+    let json = r#"{"appid":"wxapplicant","status":5,"create_time":"1536305096"}"#;
+    let applicant: PluginApplicant = serde_json::from_str(json).unwrap();
+    let value = serde_json::to_value(&applicant).unwrap();
+    assert_eq!(value["status"], 5);
+    assert_eq!(value["create_time"], 1536305096);
+    let again: PluginApplicant = serde_json::from_value(value).unwrap();
+    assert_eq!(again, applicant);
+
+    let json = r#"{"type":"image","media_id":"MEDIA","created_at":1700000000}"#;
+    let upload: MediaUploadResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(serde_json::to_value(&upload).unwrap()["type"], "image");
+}
@@ -63,7 +63,7 @@ async fn mock_token(mock_server: &MockServer) {
         .await;
 }
 
-async fn post_token<T: DeserializeOwned>(
+async fn post_token<T: DeserializeOwned + Send + 'static>(
     context: &(WechatClient, TokenManager),
     endpoint: &str,
     body: serde_json::Value,
@@ -123,6 +123,7 @@ mod openapi_contract {
 
 mod security_contract {
     use super::*;
+    use wechat_mp_sdk::api::security::Scene;
 
     #[tokio::test]
     async fn security_success_contract() {
@@ -139,7 +140,7 @@ mod security_contract {
             .await;
         let response = create_test_wechat(&server)
             .await
-            .msg_sec_check("openid", 1, "content")
+            .msg_sec_check("openid", Scene::Profile, "content")
             .await
             .unwrap();
         assert_eq!(response.result.suggest, "pass");
@@ -160,7 +161,7 @@ mod security_contract {
         assert_api_error(
             create_test_wechat(&server)
                 .await
-                .msg_sec_check("openid", 1, "bad")
+                .msg_sec_check("openid", Scene::Profile, "bad")
                 .await,
             87014,
         );
@@ -469,7 +470,7 @@ mod analytics_contract {
             )
             .mount(&server)
             .await;
-        let req = AnalyticsDateRangeRequest::new("bad", "bad");
+        let req = AnalyticsDateRangeRequest::new("20250101", "20250101");
         assert_api_error(
             create_test_wechat(&server)
                 .await
@@ -655,7 +656,10 @@ mod ocr_contract {
 mod generic_family_contracts {
     use super::*;
 
-    async fn run_success<T: DeserializeOwned>(endpoint: &str, body: serde_json::Value) -> T {
+    async fn run_success<T: DeserializeOwned + Send + 'static>(
+        endpoint: &str,
+        body: serde_json::Value,
+    ) -> T {
         let server = MockServer::start().await;
         mock_token(&server).await;
         Mock::given(method("POST"))
@@ -671,7 +675,7 @@ mod generic_family_contracts {
             .unwrap()
     }
 
-    async fn run_error<T: DeserializeOwned + std::fmt::Debug>(
+    async fn run_error<T: DeserializeOwned + Send + 'static + std::fmt::Debug>(
         endpoint: &str,
         body: serde_json::Value,
         code: i32,
@@ -0,0 +1,384 @@
+//! Tests for the `flows` helpers behind the `flow_*` examples.
+
+#![cfg(feature = "flows")]
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+use wechat_mp_sdk::api::customer_service::{Message, TextMessage};
+use wechat_mp_sdk::api::subscribe::{
+    ExpectedTemplate, SubscribeMessageData, SubscribeMessageOptions, SubscribeMessageValue,
+    TemplateDriftReport,
+};
+use wechat_mp_sdk::api::{
+    AddOrderRequest, AddOrderResponse, LogisticsRequest, MemoryOrderOutbox, OrderCreation,
+    ShippingOrderKey, UploadShippingInfoRequest,
+};
+use wechat_mp_sdk::crypto::UserKeyCipher;
+use wechat_mp_sdk::flows::{
+    login_and_decrypt, send_subscribe_campaign, ship_order, NotificationChannel,
+    NotificationRouter, OrderHooks, OrderNotification, OrderNotifications, OrderShipment,
+};
+use wechat_mp_sdk::types::{AppId, AppSecret, OpenId};
+use wechat_mp_sdk::{WechatError, WechatMp};
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const APPID: &str = "wx1234567890abcdef";
+
+async fn setup() -> (MockServer, WechatMp) {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "test_token",
+            "expires_in": 7200,
+        })))
+        .mount(&server)
+        .await;
+    let wechat = WechatMp::builder()
+        .appid(AppId::new(APPID).unwrap())
+        .secret(AppSecret::new("secret1234567890ab").unwrap())
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+    (server, wechat)
+}
+
+#[tokio::test]
+async fn test_login_and_decrypt_checks_watermark() {
+    let (server, wechat) = setup().await;
+    let session_key = BASE64.encode(b"0123456789abcdef");
+    let iv = BASE64.encode(b"fedcba9876543210");
+    Mock::given(method("GET"))
+        .and(path("/sns/jscode2session"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "openid": "o_flow_user",
+            "session_key": session_key,
+        })))
+        .mount(&server)
+        .await;
+    let cipher = UserKeyCipher::new(&session_key, &iv, 0).unwrap();
+    let encrypt = |appid: &str| {
+        let plaintext = json!({
+            "nickName": "Flow",
+            "watermark": { "appid": appid, "timestamp": 1_700_000_000 },
+        });
+        cipher.encrypt(plaintext.to_string().as_bytes()).unwrap()
+    };
+
+    let login = login_and_decrypt(&wechat, "code", &encrypt(APPID), &iv)
+        .await
+        .unwrap();
+    assert_eq!(login.session.openid, "o_flow_user");
+    assert_eq!(login.user.nick_name(), Some("Flow"));
+
+    let foreign = login_and_decrypt(&wechat, "code", &encrypt("wxffffffffffffffff"), &iv).await;
+    assert!(matches!(foreign, Err(WechatError::Signature(_))));
+}
+
+#[tokio::test]
+async fn test_subscribe_campaign_splits_outcomes() {
+    let (server, wechat) = setup().await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/message/subscribe/send"))
+        .and(body_partial_json(
+            json!({ "touser": "o_user_rejected_00000000" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 43101,
+            "errmsg": "user refuse to accept the msg",
+        })))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/message/subscribe/send"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "errcode": 0 })))
+        .mount(&server)
+        .await;
+
+    let recipients = [
+        OpenId::new("o_user_first_000000000000").unwrap(),
+        OpenId::new("o_user_rejected_00000000").unwrap(),
+    ];
+    let report = send_subscribe_campaign(&wechat, "tmpl", &recipients, |_| {
+        SubscribeMessageData::from([("thing1".to_string(), SubscribeMessageValue::new("Sale"))])
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(report.sent, recipients[..1]);
+    assert_eq!(report.failed.len(), 1);
+    assert!(matches!(
+        report.failed[0].1,
+        WechatError::Api { code: 43101, .. }
+    ));
+    assert!(!report.is_complete());
+}
+
+#[tokio::test]
+async fn test_ship_order_creates_once_and_tracks_waybill() {
+    let (server, wechat) = setup().await;
+    let order = json!({ "errcode": 0, "order_id": "SO-1", "waybill_id": "WB-1" });
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/business/order/add"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(order.clone()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/business/order/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(order))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/business/path/get"))
+        .and(body_partial_json(
+            json!({ "waybill_id": "WB-1", "delivery_id": "SF" }),
+        ))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({ "errcode": 0, "path_item_num": 0 })),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let payload: HashMap<String, Value> = [
+        ("order_id".to_string(), json!("SO-1")),
+        ("delivery_id".to_string(), json!("SF")),
+    ]
+    .into();
+    let request = LogisticsRequest::new(payload);
+    let outbox = MemoryOrderOutbox::new();
+
+    let first = ship_order(&wechat, &outbox, &request).await.unwrap();
+    assert!(first.order.is_created());
+    assert_eq!(first.waybill_id(), Some("WB-1"));
+
+    let second = ship_order(&wechat, &outbox, &request).await.unwrap();
+    assert!(!second.order.is_created());
+}
+
+#[tokio::test]
+async fn test_notification_router_falls_back_within_session_window() {
+    let (server, wechat) = setup().await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/message/subscribe/send"))
+        .and(body_partial_json(
+            json!({ "touser": "o_user_subscribed_0000000" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "errcode": 0 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/message/subscribe/send"))
+        .and(body_partial_json(
+            json!({ "touser": "o_user_out_of_quota_00000" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 43101,
+            "errmsg": "user refuse to accept the msg",
+        })))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/message/custom/send"))
+        .and(body_partial_json(json!({
+            "touser": "o_user_out_of_quota_00000",
+            "msgtype": "text",
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "errcode": 0 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let router = NotificationRouter::new(wechat);
+    let update = |touser: &str| SubscribeMessageOptions {
+        touser: OpenId::new(touser).unwrap(),
+        template_id: "order_status".to_string(),
+        data: SubscribeMessageData::from([(
+            "phrase1".to_string(),
+            SubscribeMessageValue::new("Shipped"),
+        )]),
+        page: None,
+        miniprogram_state: None,
+        lang: None,
+    };
+    let fallback = || Message::Text {
+        text: TextMessage::new("Your order has shipped"),
+    };
+    let recently = Some(SystemTime::now() - Duration::from_secs(60 * 60));
+    let long_ago = Some(SystemTime::now() - Duration::from_secs(72 * 60 * 60));
+
+    let subscribed = router
+        .send(update("o_user_subscribed_0000000"), fallback(), None)
+        .await;
+    assert_eq!(subscribed.unwrap(), NotificationChannel::Subscribe);
+
+    let fell_back = router
+        .send(update("o_user_out_of_quota_00000"), fallback(), recently)
+        .await;
+    assert_eq!(fell_back.unwrap(), NotificationChannel::CustomerService);
+
+    let unreachable = router
+        .send(update("o_user_out_of_quota_00000"), fallback(), long_ago)
+        .await;
+    assert!(matches!(
+        unreachable,
+        Err(WechatError::Api { code: 43101, .. })
+    ));
+}
+
+#[derive(Default)]
+struct RecordedStages(Mutex<Vec<String>>);
+
+impl RecordedStages {
+    fn push(&self, stage: String) {
+        self.0.lock().unwrap().push(stage);
+    }
+}
+
+impl OrderHooks for RecordedStages {
+    fn templates_checked(&self, report: &TemplateDriftReport) {
+        self.push(format!("templates drifted={}", report.drifts.len()));
+    }
+
+    fn notify_retrying(&self, attempt: u32, _error: &WechatError, _delay: Duration) {
+        self.push(format!("retry {attempt}"));
+    }
+
+    fn notified(&self, touser: &OpenId, channel: NotificationChannel) {
+        self.push(format!("notified {} {channel:?}", touser.as_str()));
+    }
+
+    fn waybill_created(&self, order: &OrderCreation<AddOrderResponse>) {
+        self.push(format!("waybill created={}", order.is_created()));
+    }
+
+    fn shipping_uploaded(&self, _request: &UploadShippingInfoRequest) {
+        self.push("shipping uploaded".to_string());
+    }
+}
+
+#[tokio::test]
+async fn test_order_notifications_ship_runs_every_stage() {
+    let (server, wechat) = setup().await;
+    Mock::given(method("GET"))
+        .and(path("/wxaapi/newtmpl/gettemplate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 0,
+            "data": [{ "priTmplId": "shipped", "title": "Shipped", "content": "", "type": 2 }],
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/business/order/add"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": 0, "order_id": "SO-1", "waybill_id": "WB-1",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/wxa/sec/order/upload_shipping_info"))
+        .and(body_partial_json(json!({
+            "order_key": { "order_number_type": 2, "transaction_id": "4200001" },
+            "shipping_list": [{ "tracking_no": "WB-1", "express_company": "SF" }],
+            "payer": { "openid": "o_buyer_00000000000000000" },
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "errcode": 0 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    // WeChat is busy once; the retry succeeds
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/message/subscribe/send"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errcode": -1, "errmsg": "system error",
+        })))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/message/subscribe/send"))
+        .and(body_partial_json(
+            json!({ "data": { "character_string1": { "value": "WB-1" } } }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "errcode": 0 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let stages = Arc::new(RecordedStages::default());
+    let orders = OrderNotifications::new(wechat)
+        .expect_templates(vec![
+            ExpectedTemplate::new("shipped"),
+            ExpectedTemplate::new("retired"),
+        ])
+        .retry(2, Duration::from_millis(1))
+        .hooks(stages.clone());
+    orders.check_templates().await.unwrap();
+
+    let notification = |template_id: &str, tracking_no: &str| {
+        let mut data = SubscribeMessageData::new();
+        data.insert(
+            "character_string1".to_string(),
+            SubscribeMessageValue::new(tracking_no),
+        );
+        OrderNotification {
+            subscribe: SubscribeMessageOptions {
+                touser: OpenId::new("o_buyer_00000000000000000").unwrap(),
+                template_id: template_id.to_string(),
+                data,
+                page: None,
+                miniprogram_state: None,
+                lang: None,
+            },
+            fallback: Message::Text {
+                text: TextMessage::new("Your order has shipped"),
+            },
+            last_interaction: None,
+        }
+    };
+    let refused = orders.notify(notification("retired", "-")).await;
+    assert!(matches!(refused, Err(WechatError::Config(_))));
+
+    let shipment = OrderShipment {
+        waybill: AddOrderRequest {
+            order_id: "SO-1".to_string(),
+            openid: Some("o_buyer_00000000000000000".to_string()),
+            delivery_id: "SF".to_string(),
+            ..Default::default()
+        },
+        order_key: ShippingOrderKey::transaction_id("4200001"),
+        item_desc: "T-shirt".to_string(),
+        receiver_contact: None,
+    };
+    let shipped = orders
+        .ship(&MemoryOrderOutbox::new(), shipment, |tracking_no| {
+            notification("shipped", tracking_no)
+        })
+        .await
+        .unwrap();
+    assert_eq!(shipped.tracking_no, "WB-1");
+    assert_eq!(shipped.channel, NotificationChannel::Subscribe);
+
+    assert_eq!(
+        *stages.0.lock().unwrap(),
+        [
+            "templates drifted=1",
+            "waybill created=true",
+            "shipping uploaded",
+            "retry 1",
+            "notified o_buyer_00000000000000000 Subscribe",
+        ]
+    );
+}
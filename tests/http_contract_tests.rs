@@ -217,7 +217,7 @@ async fn test_http_503_status_should_return_http_error() {
 
 /// Test: 200 OK with WeChat API error (errcode != 0) should return Api error
 ///
-/// Expected: WechatError::Api { code: non-zero, message: _ }
+/// Expected: WechatError::Api { code: non-zero, .. }
 /// Current (RED): Returns success (errcode not checked in client methods)
 #[tokio::test]
 async fn test_http_200_with_errcode_should_return_api_error() {
@@ -248,7 +248,7 @@ async fn test_http_200_with_errcode_should_return_api_error() {
 
 /// Test: 200 OK with different WeChat API error code
 ///
-/// Expected: WechatError::Api { code: non-zero, message: _ }
+/// Expected: WechatError::Api { code: non-zero, .. }
 /// Current (RED): Returns success (errcode not checked)
 #[tokio::test]
 async fn test_http_200_with_errcode_40001_should_return_api_error() {
@@ -318,8 +318,7 @@ async fn test_http_post_200_with_errcode_should_return_api_error() {
 
 /// Test: 200 OK with malformed JSON body should return error
 ///
-/// Expected: WechatError::Http (reqwest wraps JSON errors as Http::Decode)
-/// Current: Returns Http error with JSON decode failure
+/// Expected: WechatError::Decode carrying the offending body snippet
 #[tokio::test]
 async fn test_http_200_with_malformed_json_should_return_error() {
     let mock_server = MockServer::start().await;
@@ -335,16 +334,15 @@ async fn test_http_200_with_malformed_json_should_return_error() {
 
     let result = client.get::<TokenResponse>("/cgi-bin/token", &[]).await;
 
-    assert!(
-        matches!(result, Err(WechatError::Http(_))),
-        "Expected error for malformed JSON, got: {:?}",
-        result
-    );
+    match result {
+        Err(WechatError::Decode { snippet, .. }) => assert_eq!(snippet, "{ invalid json }"),
+        other => panic!("Expected Decode error for malformed JSON, got: {:?}", other),
+    }
 }
 
 /// Test: 200 OK with valid JSON but wrong structure should return error
 ///
-/// Expected: WechatError::Http (reqwest wraps JSON errors)
+/// Expected: WechatError::Decode
 #[tokio::test]
 async fn test_http_200_with_incomplete_json_should_return_error() {
     let mock_server = MockServer::start().await;
@@ -363,17 +361,20 @@ async fn test_http_200_with_incomplete_json_should_return_error() {
 
     let result = client.get::<TokenResponse>("/cgi-bin/token", &[]).await;
 
-    // This should fail with error (reqwest wraps JSON errors as Http)
-    assert!(
-        matches!(result, Err(WechatError::Http(_))),
-        "Expected error for missing field, got: {:?}",
-        result
-    );
+    match result {
+        Err(WechatError::Decode {
+            message, snippet, ..
+        }) => {
+            assert!(message.contains("access_token"));
+            assert!(snippet.contains("expires_in"));
+        }
+        other => panic!("Expected Decode error for missing field, got: {:?}", other),
+    }
 }
 
 /// Test: Empty response body should return error
 ///
-/// Expected: WechatError::Http (reqwest wraps JSON errors)
+/// Expected: WechatError::Decode
 #[tokio::test]
 async fn test_http_200_with_empty_body_should_return_error() {
     let mock_server = MockServer::start().await;
@@ -390,8 +391,8 @@ async fn test_http_200_with_empty_body_should_return_error() {
     let result = client.get::<TokenResponse>("/cgi-bin/token", &[]).await;
 
     assert!(
-        matches!(result, Err(WechatError::Http(_))),
-        "Expected error for empty body, got: {:?}",
+        matches!(result, Err(WechatError::Decode { .. })),
+        "Expected Decode error for empty body, got: {:?}",
         result
     );
 }
@@ -488,6 +489,53 @@ async fn test_http_get_timeout_should_return_http_error() {
     let _ = server.await;
 }
 
+#[tokio::test]
+async fn test_http_timeout_follows_endpoint_timeout_class() {
+    use std::time::Duration;
+    use wechat_mp_sdk::api::endpoint_inventory::TimeoutClass;
+
+    let mock_server = MockServer::start().await;
+    let delayed = ResponseTemplate::new(200)
+        .set_body_json(serde_json::json!({
+            "access_token": "token",
+            "expires_in": 7200
+        }))
+        .set_delay(Duration::from_millis(300));
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/token"))
+        .respond_with(delayed.clone())
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cv/ocr/idcard"))
+        .respond_with(delayed)
+        .mount(&mock_server)
+        .await;
+
+    let client = WechatClient::builder()
+        .appid(AppId::new("wx1234567890abcdef".to_string()).unwrap())
+        .secret(AppSecret::new("test_secret_12345".to_string()).unwrap())
+        .base_url(mock_server.uri())
+        .class_timeout(TimeoutClass::Fast, Duration::from_millis(100))
+        .class_timeout(TimeoutClass::Slow, Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let fast = client.get::<TokenResponse>("/cgi-bin/token", &[]).await;
+    assert!(
+        matches!(&fast, Err(WechatError::Http(_))),
+        "Expected fast endpoint to time out, got: {:?}",
+        fast
+    );
+
+    let slow = client.get::<TokenResponse>("/cv/ocr/idcard", &[]).await;
+    assert!(
+        slow.is_ok(),
+        "Expected slow endpoint to succeed: {:?}",
+        slow
+    );
+}
+
 #[tokio::test]
 async fn test_http_post_non_2xx_status_should_return_http_error() {
     let mock_server = MockServer::start().await;
@@ -525,7 +573,7 @@ async fn test_http_post_non_2xx_status_should_return_http_error() {
 }
 
 #[tokio::test]
-async fn test_http_post_decode_error_should_return_http_decode_error() {
+async fn test_http_post_decode_error_should_return_decode_error() {
     let mock_server = MockServer::start().await;
 
     Mock::given(method("POST"))
@@ -553,8 +601,8 @@ async fn test_http_post_decode_error_should_return_http_decode_error() {
         .await;
 
     assert!(
-        matches!(result, Err(WechatError::Http(_))),
-        "Expected WechatError::Http decode error for POST, got: {:?}",
+        matches!(result, Err(WechatError::Decode { ref path, .. }) if path == "."),
+        "Expected WechatError::Decode for POST, got: {:?}",
         result
     );
 }
@@ -586,7 +634,7 @@ fn test_wechat_api_response_parses_error() {
     assert!(!resp.is_success());
     let err = resp.check().unwrap_err();
     match err {
-        WechatError::Api { code, message } => {
+        WechatError::Api { code, message, .. } => {
             assert_eq!(code, 40013);
             assert_eq!(message, "invalid appid");
         }
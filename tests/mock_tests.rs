@@ -8,6 +8,7 @@ use std::sync::Arc;
 
 use wechat_mp_sdk::api::auth::AuthApi;
 use wechat_mp_sdk::api::media::{MediaApi, MediaType};
+use wechat_mp_sdk::api::ocr::{IdCardOcrResult, OcrApi};
 use wechat_mp_sdk::api::qrcode::{QrcodeApi, QrcodeOptions};
 use wechat_mp_sdk::api::user::UserApi;
 use wechat_mp_sdk::api::WechatContext;
@@ -15,7 +16,7 @@ use wechat_mp_sdk::client::WechatClient;
 use wechat_mp_sdk::token::TokenManager;
 use wechat_mp_sdk::types::{AppId, AppSecret};
 use wechat_mp_sdk::WechatError;
-use wiremock::matchers::{method, path, query_param};
+use wiremock::matchers::{body_string_contains, method, path, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 /// Create a test client pointing to the mock server
@@ -121,8 +122,10 @@ async fn test_mock_login_error() {
 
     let result = auth_api.login("invalid_code").await;
 
-    // Should return error
-    assert!(result.is_err());
+    // Should return a typed js_code error
+    let err = result.unwrap_err();
+    assert!(matches!(err, WechatError::InvalidJsCode { .. }));
+    assert!(err.is_client_retryable());
 }
 
 /// Test get phone number with mock
@@ -330,7 +333,7 @@ async fn test_execute_returns_api_error_for_errcode_non_zero_json() {
         .await;
 
     match result {
-        Err(WechatError::Api { code, message }) => {
+        Err(WechatError::Api { code, message, .. }) => {
             assert_eq!(code, 40013);
             assert_eq!(message, "invalid appid");
         }
@@ -365,7 +368,7 @@ async fn test_media_get_temp_media_returns_bytes_for_image_jpeg_content_type() {
     let result = media_api.get_temp_media("jpeg_media").await;
 
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), b"jpeg_bytes");
+    assert_eq!(result.unwrap(), &b"jpeg_bytes"[..]);
 }
 
 #[tokio::test]
@@ -402,7 +405,7 @@ async fn test_media_get_temp_media_returns_api_error_for_application_json() {
     let result = media_api.get_temp_media("json_error_media").await;
 
     match result {
-        Err(WechatError::Api { code, message }) => {
+        Err(WechatError::Api { code, message, .. }) => {
             assert_eq!(code, 40007);
             assert_eq!(message, "invalid media_id");
         }
@@ -473,7 +476,7 @@ async fn test_media_get_temp_media_detects_json_error_when_content_type_is_not_j
     let result = media_api.get_temp_media("wrong_content_type_error").await;
 
     match result {
-        Err(WechatError::Api { code, message }) => {
+        Err(WechatError::Api { code, message, .. }) => {
             assert_eq!(code, 40007);
             assert_eq!(message, "invalid media_id");
         }
@@ -510,7 +513,7 @@ async fn test_media_get_temp_media_encodes_media_id_query_value() {
     let result = media_api.get_temp_media(raw_media_id).await;
 
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), b"encoded_media_bytes");
+    assert_eq!(result.unwrap(), &b"encoded_media_bytes"[..]);
 }
 
 #[tokio::test]
@@ -544,7 +547,7 @@ async fn test_upload_temp_media_maps_minimal_error_payload_to_api_error() {
         .await;
 
     match result {
-        Err(WechatError::Api { code, message }) => {
+        Err(WechatError::Api { code, message, .. }) => {
             assert_eq!(code, 40001);
             assert_eq!(message, "invalid credential");
         }
@@ -552,6 +555,45 @@ async fn test_upload_temp_media_maps_minimal_error_payload_to_api_error() {
     }
 }
 
+#[tokio::test]
+async fn test_id_card_ocr_from_bytes_uploads_img_field() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": "mock_ocr_token",
+            "expires_in": 7200
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/cv/ocr/idcard"))
+        .and(query_param("access_token", "mock_ocr_token"))
+        .and(query_param("type", "photo"))
+        .and(body_string_contains(r#"name="img"; filename="card.jpg""#))
+        .and(body_string_contains("mock_card_bytes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "errcode": 0,
+            "errmsg": "ok",
+            "type": "Back",
+            "valid_date": "20070105-20270105"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let context = create_test_context(&mock_server).await;
+    let response = OcrApi::new(context)
+        .id_card_ocr_from_bytes("card.jpg", b"mock_card_bytes", Some("photo"))
+        .await
+        .unwrap();
+
+    let id_card: IdCardOcrResult = response.parse().unwrap();
+    assert_eq!(id_card.validity(), Some(("20070105", "20270105")));
+}
+
 #[tokio::test]
 async fn test_qrcode_create_qrcode_returns_error_for_non_2xx_status() {
     let mock_server = MockServer::start().await;
@@ -612,7 +654,7 @@ async fn test_qrcode_get_wxa_code_detects_json_error_with_content_type_case_vari
     let result = qrcode_api.get_wxa_code(QrcodeOptions::new()).await;
 
     match result {
-        Err(WechatError::Api { code, message }) => {
+        Err(WechatError::Api { code, message, .. }) => {
             assert_eq!(code, 41030);
             assert_eq!(message, "invalid page");
         }
@@ -685,7 +727,7 @@ async fn test_fetch_token_with_retry_current_behavior_does_not_retry_non_retryab
     let result = token_manager.get_token().await;
 
     match result {
-        Err(WechatError::Api { code, message }) => {
+        Err(WechatError::Api { code, message, .. }) => {
             assert_eq!(code, 40013);
             assert_eq!(message, "invalid appid");
         }
@@ -693,3 +735,104 @@ async fn test_fetch_token_with_retry_current_behavior_does_not_retry_non_retryab
     }
     assert_eq!(call_count.load(Ordering::SeqCst), 1);
 }
+
+/// `DeliveryRequest` can be built outside the crate for the generic delivery calls
+#[tokio::test]
+async fn test_create_or_get_local_order_with_constructed_request() {
+    use std::collections::HashMap;
+    use wechat_mp_sdk::api::delivery::{DeliveryApi, DeliveryRequest};
+    use wechat_mp_sdk::api::MemoryOrderOutbox;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": "mock_token_delivery",
+            "expires_in": 7200
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/local/business/order/add"))
+        .and(query_param("access_token", "mock_token_delivery"))
+        .and(body_string_contains(r#""shop_order_id":"order-1""#))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "errcode": 0,
+            "errmsg": "ok",
+            "waybill_id": "wb-1"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let request = DeliveryRequest::new(HashMap::from([
+        ("shopid".to_string(), serde_json::json!("shop-1")),
+        ("shop_order_id".to_string(), serde_json::json!("order-1")),
+    ]));
+    let api = DeliveryApi::new(create_test_context(&mock_server).await);
+    let created = api
+        .create_or_get_local_order(&MemoryOrderOutbox::new(), &request)
+        .await
+        .unwrap();
+
+    assert!(created.is_created());
+    assert_eq!(created.response().extra["waybill_id"], "wb-1");
+}
+
+/// A typed express order already recorded in the outbox is queried, not re-created
+#[tokio::test]
+async fn test_create_or_get_express_order_returns_existing_order() {
+    use wechat_mp_sdk::api::logistics::{AddOrderRequest, LogisticsApi};
+    use wechat_mp_sdk::api::{MemoryOrderOutbox, OrderOutbox, OutboxState};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": "mock_token_express",
+            "expires_in": 7200
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/business/order/get"))
+        .and(body_string_contains(r#""order_id":"SO-1""#))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "errcode": 0,
+            "order_id": "SO-1",
+            "delivery_id": "SF",
+            "waybill_id": "WB-1"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/express/business/order/add"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "errcode": 0
+        })))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let outbox = MemoryOrderOutbox::new();
+    outbox
+        .put("express:SO-1", OutboxState::Created)
+        .await
+        .unwrap();
+    let request = AddOrderRequest {
+        order_id: "SO-1".to_string(),
+        openid: Some("o_buyer".to_string()),
+        delivery_id: "SF".to_string(),
+        ..Default::default()
+    };
+    let api = LogisticsApi::new(create_test_context(&mock_server).await);
+    let order = api
+        .create_or_get_express_order(&outbox, &request)
+        .await
+        .unwrap();
+
+    assert!(!order.is_created());
+    assert_eq!(order.response().order_id, "SO-1");
+    assert_eq!(order.response().waybill_id, "WB-1");
+}
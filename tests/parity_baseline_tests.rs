@@ -561,7 +561,7 @@ mod qrcode_api_parity {
         let options = UrlLinkOptions {
             path: Some("/pages/index/index".to_string()),
             query: Some("id=456".to_string()),
-            expire_type: Some(1),
+            expire_type: Some(0),
             expire_time: Some(1672531200),
             expire_interval: None,
         };
@@ -960,166 +960,7 @@ mod facade_guards {
 
     use std::collections::{HashMap, HashSet};
 
-    use wechat_mp_sdk::api::endpoint_inventory::get_endpoint_inventory;
-
-    /// Maps endpoint_id -> facade method name for all currently-implemented endpoints.
-    ///
-    /// MAINTENANCE: When you set `implemented: true` for a new endpoint in
-    /// `endpoint_inventory.rs`, you MUST add a corresponding entry here AND
-    /// add the facade method to `WechatMp` in `src/client/wechat_mp.rs`.
-    const FACADE_METHOD_MAP: &[(&str, &str)] = &[
-        ("accessToken.getAccessToken", "get_access_token"),
-        ("auth.code2Session", "auth_login"),
-        ("user.getPhoneNumber", "get_phone_number"),
-        ("qrcode.getQRCode", "get_wxa_code"),
-        ("qrcode.getUnlimitedQRCode", "get_wxa_code_unlimit"),
-        ("qrcode.createQRCode", "create_qrcode"),
-        ("qrcode.generateScheme", "generate_url_scheme"),
-        ("qrcode.generateUrlLink", "generate_url_link"),
-        ("qrcode.generateShortLink", "generate_short_link"),
-        (
-            "customerService.sendCustomMessage",
-            "send_customer_service_message",
-        ),
-        ("customerService.uploadTempMedia", "upload_temp_media"),
-        ("customerService.getTempMedia", "get_temp_media"),
-        ("subscribe.sendMessage", "send_subscribe_message"),
-        ("subscribe.addMessageTemplate", "add_template"),
-        ("subscribe.deleteMessageTemplate", "delete_template"),
-        ("subscribe.getCategory", "get_category"),
-        ("subscribe.getMessageTemplateList", "get_template_list"),
-        (
-            "accessToken.getStableAccessToken",
-            "get_stable_access_token",
-        ),
-        ("openapi.clearQuota", "clear_quota"),
-        ("openapi.getApiQuota", "get_api_quota"),
-        ("openapi.clearApiQuota", "clear_api_quota"),
-        ("openapi.clearQuotaByAppSecret", "clear_quota_by_app_secret"),
-        ("openapi.getRidInfo", "get_rid_info"),
-        ("openapi.callbackCheck", "callback_check"),
-        ("openapi.getApiDomainIp", "get_api_domain_ip"),
-        ("openapi.getCallbackIp", "get_callback_ip"),
-        // Wave 2: Security
-        ("security.msgSecCheck", "msg_sec_check"),
-        ("security.mediaCheckAsync", "media_check_async"),
-        ("security.getUserRiskRank", "get_user_risk_rank"),
-        // Wave 2: Auth extensions
-        ("auth.checkSessionKey", "check_session_key"),
-        ("auth.resetUserSessionKey", "reset_user_session_key"),
-        // Wave 2: User extensions
-        ("user.getPluginOpenPId", "get_plugin_open_pid"),
-        ("user.checkEncryptedData", "check_encrypted_data"),
-        ("user.getPaidUnionid", "get_paid_unionid"),
-        ("user.getUserEncryptKey", "get_user_encrypt_key"),
-        // Wave 2: QR Code extensions
-        ("qrcode.queryScheme", "query_scheme"),
-        ("qrcode.queryUrlLink", "query_url_link"),
-        ("qrcode.generateNFCScheme", "generate_nfc_scheme"),
-        // Wave 2: Customer service extension
-        ("customerService.setTyping", "set_typing"),
-        // Wave 2: WeChat KF
-        ("kfWork.getKfWorkBound", "get_kf_work_bound"),
-        ("kfWork.bindKfWork", "bind_kf_work"),
-        ("kfWork.unbindKfWork", "unbind_kf_work"),
-        (
-            "subscribe.getPubTemplateKeyWordsById",
-            "get_pub_template_keywords_by_id",
-        ),
-        (
-            "subscribe.getPubTemplateTitleList",
-            "get_pub_template_title_list",
-        ),
-        ("subscribe.setUserNotify", "set_user_notify"),
-        ("subscribe.setUserNotifyExt", "set_user_notify_ext"),
-        ("subscribe.getUserNotify", "get_user_notify"),
-        ("analytics.getDailySummary", "get_daily_summary"),
-        ("analytics.getDailyVisitTrend", "get_daily_visit_trend"),
-        ("analytics.getWeeklyVisitTrend", "get_weekly_visit_trend"),
-        ("analytics.getMonthlyVisitTrend", "get_monthly_visit_trend"),
-        ("analytics.getDailyRetain", "get_daily_retain"),
-        ("analytics.getWeeklyRetain", "get_weekly_retain"),
-        ("analytics.getMonthlyRetain", "get_monthly_retain"),
-        ("analytics.getVisitPage", "get_visit_page"),
-        ("analytics.getVisitDistribution", "get_visit_distribution"),
-        ("analytics.getUserPortrait", "get_user_portrait"),
-        ("analytics.getPerformanceData", "get_performance_data"),
-        ("operations.getDomainInfo", "get_domain_info"),
-        ("operations.getPerformance", "get_operations_performance"),
-        ("operations.getSceneList", "get_scene_list"),
-        ("operations.getVersionList", "get_version_list"),
-        ("operations.realtimeLogSearch", "realtime_log_search"),
-        ("operations.getFeedback", "get_feedback"),
-        ("operations.getFeedbackMedia", "get_feedback_media"),
-        ("operations.getJsErrDetail", "get_js_err_detail"),
-        ("operations.getJsErrList", "get_js_err_list"),
-        ("operations.getGrayReleasePlan", "get_gray_release_plan"),
-        (
-            "plugin.managePluginApplication",
-            "manage_plugin_application",
-        ),
-        ("plugin.managePlugin", "manage_plugin"),
-        ("nearby.addNearbyPoi", "add_nearby_poi"),
-        ("nearby.deleteNearbyPoi", "delete_nearby_poi"),
-        ("nearby.getNearbyPoiList", "get_nearby_poi_list"),
-        ("nearby.setShowStatus", "set_nearby_show_status"),
-        ("cloud.invokeCloudFunction", "invoke_cloud_function"),
-        ("cloud.addDelayedFunctionTask", "add_delayed_function_task"),
-        ("cloud.databaseAdd", "database_add"),
-        ("cloud.databaseDelete", "database_delete"),
-        ("cloud.databaseUpdate", "database_update"),
-        ("cloud.databaseQuery", "database_query"),
-        ("cloud.getUploadFileLink", "get_upload_file_link"),
-        ("cloud.getDownloadFileLink", "get_download_file_link"),
-        ("cloud.deleteCloudFile", "delete_cloud_file"),
-        ("cloud.newSendCloudBaseSms", "new_send_cloud_base_sms"),
-        ("live.createRoom", "create_room"),
-        ("live.deleteRoom", "delete_room"),
-        ("live.editRoom", "edit_room"),
-        ("live.getLiveInfo", "get_live_info"),
-        ("live.addGoods", "add_goods"),
-        ("live.updateGoodsInfo", "update_goods_info"),
-        ("live.deleteGoodsInfo", "delete_goods_info"),
-        ("live.pushMessage", "push_message"),
-        ("live.getFollowers", "get_followers"),
-        (
-            "hardware.sendHardwareDeviceMessage",
-            "send_hardware_device_message",
-        ),
-        ("hardware.getSnTicket", "get_sn_ticket"),
-        ("hardware.createIotGroupId", "create_iot_group_id"),
-        ("hardware.getIotGroupInfo", "get_iot_group_info"),
-        ("hardware.addIotGroupDevice", "add_iot_group_device"),
-        ("hardware.removeIotGroupDevice", "remove_iot_group_device"),
-        ("image.aiCrop", "ai_crop"),
-        ("image.scanQRCode", "scan_qr_code"),
-        ("ocr.printedTextOCR", "printed_text_ocr"),
-        ("ocr.vehicleLicenseOCR", "vehicle_license_ocr"),
-        ("ocr.bankCardOCR", "bank_card_ocr"),
-        ("ocr.businessLicenseOCR", "business_license_ocr"),
-        ("ocr.driverLicenseOCR", "driver_license_ocr"),
-        ("ocr.idCardOCR", "id_card_ocr"),
-        ("delivery.getAllImmeDelivery", "get_all_imme_delivery"),
-        ("delivery.preAddOrder", "pre_add_order"),
-        ("delivery.preCancelOrder", "pre_cancel_order"),
-        ("delivery.addLocalOrder", "add_local_order"),
-        ("delivery.cancelLocalOrder", "cancel_local_order"),
-        ("express.bindAccount", "bind_account"),
-        ("express.getAllAccount", "get_all_account"),
-        ("express.getAllDelivery", "get_all_delivery"),
-        ("express.getOrder", "get_order"),
-        ("express.addOrder", "add_order"),
-        ("express.getPath", "get_path"),
-        ("serviceMarket.invokeService", "invoke_service"),
-        ("soter.verifySignature", "verify_signature"),
-        ("face.getVerifyId", "get_verify_id"),
-        ("face.queryVerifyInfo", "query_verify_info"),
-        ("wxsearch.submitPages", "submit_pages"),
-        ("ad.addUserAction", "add_user_action"),
-        ("ad.addUserActionSet", "add_user_action_set"),
-        ("ad.getUserActionSetReports", "get_user_action_set_reports"),
-        ("ad.getUserActionSets", "get_user_action_sets"),
-    ];
+    use wechat_mp_sdk::api::endpoint_inventory::{get_endpoint_inventory, FACADE_METHOD_MAP};
 
     /// Verifies that every currently-implemented endpoint in the inventory has
     /// a corresponding entry in FACADE_METHOD_MAP.
@@ -1152,7 +993,7 @@ mod facade_guards {
         if !missing_from_facade.is_empty() {
             missing_from_facade.sort();
             panic!(
-                "GUARD FAILURE: {} implemented endpoint(s) have no facade method mapping:\n  - {}\n\nAdd entries to FACADE_METHOD_MAP in tests/parity_baseline_tests.rs\nAND add the corresponding facade method to WechatMp in src/client/wechat_mp.rs",
+                "GUARD FAILURE: {} implemented endpoint(s) have no facade method mapping:\n  - {}\n\nAdd entries to FACADE_METHOD_MAP in src/api/endpoint_inventory.rs\nAND add the corresponding facade method to WechatMp in src/client/wechat_mp.rs",
                 missing_from_facade.len(),
                 missing_from_facade.join("\n  - "),
             );
@@ -1204,11 +1045,50 @@ mod facade_guards {
         );
     }
 
+    /// Verifies that each facade method maps to one endpoint, so
+    /// `WechatMp::endpoint_for_method` is unambiguous.
+    #[test]
+    fn facade_map_methods_are_unique() {
+        let mut seen: HashMap<&str, &str> = HashMap::new();
+        let mut duplicates = Vec::new();
+
+        for (endpoint_id, facade_method) in FACADE_METHOD_MAP {
+            if let Some(existing) = seen.insert(facade_method, endpoint_id) {
+                duplicates.push(format!(
+                    "'{}' maps to '{}' and '{}'",
+                    facade_method, existing, endpoint_id
+                ));
+            }
+        }
+
+        assert!(
+            duplicates.is_empty(),
+            "GUARD FAILURE: FACADE_METHOD_MAP reuses facade methods:\n  - {}\n",
+            duplicates.join("\n  - ")
+        );
+    }
+
+    #[test]
+    fn facade_lookups_round_trip() {
+        use wechat_mp_sdk::WechatMp;
+
+        let endpoint = WechatMp::endpoint_for_method("msg_sec_check").unwrap();
+        assert_eq!(endpoint.endpoint_id, "security.msgSecCheck");
+        assert_eq!(endpoint.path, "/wxa/msg_sec_check");
+        assert_eq!(
+            WechatMp::method_for_endpoint("security.msgSecCheck"),
+            Some("msg_sec_check")
+        );
+        assert!(WechatMp::endpoint_for_method("stats").is_none());
+        assert!(WechatMp::method_for_endpoint("security.unknown").is_none());
+    }
+
     /// Compile-time guard: verifies existing facade methods still exist on WechatMp.
     ///
     /// If this test fails to compile, a facade method was removed or renamed.
     /// Taking a reference to a method is sufficient to verify it exists at compile time.
     #[test]
+    #[allow(deprecated)]
     fn existing_facade_methods_are_backward_compatible() {
         use wechat_mp_sdk::WechatMp;
 
@@ -1265,6 +1145,9 @@ mod facade_guards {
             WechatMp::set_user_notify as *const (),
             WechatMp::set_user_notify_ext as *const (),
             WechatMp::get_user_notify as *const (),
+            WechatMp::update_user_notify as *const (),
+            WechatMp::update_user_notify_ext as *const (),
+            WechatMp::query_user_notify as *const (),
             WechatMp::get_daily_summary as *const (),
             WechatMp::get_daily_visit_trend as *const (),
             WechatMp::get_weekly_visit_trend as *const (),
@@ -1311,6 +1194,7 @@ mod facade_guards {
             WechatMp::delete_goods_info as *const (),
             WechatMp::push_message as *const (),
             WechatMp::get_followers as *const (),
+            WechatMp::get_live_shared_code as *const (),
             WechatMp::send_hardware_device_message as *const (),
             WechatMp::get_sn_ticket as *const (),
             WechatMp::create_iot_group_id as *const (),
@@ -1329,6 +1213,7 @@ mod facade_guards {
             WechatMp::pre_add_order as *const (),
             WechatMp::pre_cancel_order as *const (),
             WechatMp::add_local_order as *const (),
+            WechatMp::get_local_order as *const (),
             WechatMp::cancel_local_order as *const (),
             WechatMp::bind_account as *const (),
             WechatMp::get_all_account as *const (),
@@ -355,7 +355,7 @@ async fn test_mock_generate_url_link_success() {
     let options = UrlLinkOptions {
         path: Some("/pages/index/index".to_string()),
         query: Some("id=456".to_string()),
-        expire_type: Some(1),
+        expire_type: Some(0),
         expire_time: Some(1672531200),
         expire_interval: None,
     };
@@ -7,11 +7,13 @@
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
+use wechat_mp_sdk::api::media::MediaType;
+use wechat_mp_sdk::api::qrcode::UnlimitQrcodeOptions;
 use wechat_mp_sdk::client::WechatClient;
 use wechat_mp_sdk::token::TokenManager;
 use wechat_mp_sdk::types::{AppId, AppSecret};
-use wechat_mp_sdk::WechatError;
-use wiremock::matchers::{method, path, query_param};
+use wechat_mp_sdk::{WechatError, WechatMp};
+use wiremock::matchers::{body_string_contains, method, path, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 // ---------------------------------------------------------------------------
@@ -317,7 +319,7 @@ async fn test_errcode_45009_rate_limit_returns_api_error() {
     assert!(result.is_err());
 
     match result.unwrap_err() {
-        WechatError::Api { code, message } => {
+        WechatError::Api { code, message, .. } => {
             assert_eq!(code, 45009);
             assert!(message.contains("quota"), "Unexpected message: {message}");
         }
@@ -509,3 +511,187 @@ async fn test_invalidation_consistency() {
 
     assert_eq!(call_count.load(Ordering::SeqCst), 3);
 }
+
+// ============================================================
+// 8. Rejected Token Retry Tests
+// ============================================================
+
+fn create_test_wechat(base_url: &str) -> WechatMp {
+    WechatMp::builder()
+        .appid(AppId::new("wx1234567890abcdef").unwrap())
+        .secret(AppSecret::new("secret1234567890ab").unwrap())
+        .base_url(base_url)
+        .build()
+        .unwrap()
+}
+
+/// A token revoked server-side (another process refreshed it) is replaced
+/// and the call retried once.
+#[tokio::test]
+async fn test_errcode_40001_refreshes_token_and_retries_once() {
+    let mock_server = MockServer::start().await;
+    let call_count = Arc::new(AtomicU32::new(0));
+    let cc = Arc::clone(&call_count);
+
+    Mock::given(token_mock())
+        .and(token_path())
+        .respond_with(move |_req: &wiremock::Request| {
+            let n = cc.fetch_add(1, Ordering::SeqCst);
+            ResponseTemplate::new(200)
+                .set_body_json(token_success_json(&format!("version_{}", n + 1)))
+        })
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/getcallbackip"))
+        .and(query_param("access_token", "version_1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(token_error_json(
+            40001,
+            "invalid credential, access_token is invalid or not latest",
+        )))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/getcallbackip"))
+        .and(query_param("access_token", "version_2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "ip_list": ["101.226.103.61"]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let wechat = create_test_wechat(&mock_server.uri());
+    let response = wechat.get_callback_ip().await.unwrap();
+
+    assert_eq!(response.ip_list, vec!["101.226.103.61".to_string()]);
+    assert_eq!(wechat.get_access_token().await.unwrap(), "version_2");
+}
+
+/// A token rejected again after the refresh is reported, not retried forever.
+#[tokio::test]
+async fn test_errcode_42001_retries_only_once() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(token_mock())
+        .and(token_path())
+        .respond_with(ResponseTemplate::new(200).set_body_json(token_success_json("expired")))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cgi-bin/getcallbackip"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(token_error_json(42001, "access_token expired")),
+        )
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let wechat = create_test_wechat(&mock_server.uri());
+    let error = wechat.get_callback_ip().await.unwrap_err();
+
+    assert!(matches!(error, WechatError::Api { code: 42001, .. }));
+}
+
+/// Binary endpoints retry a rejected token like JSON ones.
+#[tokio::test]
+async fn test_rejected_token_retries_wxa_code_request() {
+    let mock_server = MockServer::start().await;
+    let call_count = Arc::new(AtomicU32::new(0));
+    let cc = Arc::clone(&call_count);
+
+    Mock::given(token_mock())
+        .and(token_path())
+        .respond_with(move |_req: &wiremock::Request| {
+            let n = cc.fetch_add(1, Ordering::SeqCst);
+            ResponseTemplate::new(200)
+                .set_body_json(token_success_json(&format!("version_{}", n + 1)))
+        })
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/wxa/getwxacodeunlimit"))
+        .and(query_param("access_token", "version_1"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(token_error_json(42001, "access_token expired")),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/wxa/getwxacodeunlimit"))
+        .and(query_param("access_token", "version_2"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Content-Type", "image/png")
+                .set_body_bytes(b"\x89PNG\r\n\x1a\nimage".to_vec()),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let wechat = create_test_wechat(&mock_server.uri());
+    let image = wechat
+        .get_wxa_code_unlimit(UnlimitQrcodeOptions::new("a=1"))
+        .await
+        .unwrap();
+
+    assert_eq!(image.content_type, "image/png");
+}
+
+/// Multipart uploads rebuild their form for the retry.
+#[tokio::test]
+async fn test_rejected_token_retries_media_upload() {
+    let mock_server = MockServer::start().await;
+    let call_count = Arc::new(AtomicU32::new(0));
+    let cc = Arc::clone(&call_count);
+
+    Mock::given(token_mock())
+        .and(token_path())
+        .respond_with(move |_req: &wiremock::Request| {
+            let n = cc.fetch_add(1, Ordering::SeqCst);
+            ResponseTemplate::new(200)
+                .set_body_json(token_success_json(&format!("version_{}", n + 1)))
+        })
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/media/upload"))
+        .and(query_param("access_token", "version_1"))
+        .and(query_param("type", "image"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(token_error_json(
+            40001,
+            "invalid credential, access_token is invalid or not latest",
+        )))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cgi-bin/media/upload"))
+        .and(query_param("access_token", "version_2"))
+        .and(query_param("type", "image"))
+        .and(body_string_contains("image-bytes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "type": "image",
+            "media_id": "MEDIA_ID",
+            "created_at": 1234567890
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let wechat = create_test_wechat(&mock_server.uri());
+    let response = wechat
+        .upload_temp_media(MediaType::Image, "image.jpg", b"image-bytes")
+        .await
+        .unwrap();
+
+    assert_eq!(response.media_id, "MEDIA_ID");
+}